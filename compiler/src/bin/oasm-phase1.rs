@@ -1,16 +1,16 @@
-/// OASM Phase 1 - One-Time Initializer
-///
-/// Drops into root folder, performs deterministic scan, generates:
-/// - Directory structure (logs/, templates/, schemas/, scripts/)
-/// - CLI dashboard snapshot (JSONL + TXT)
-/// - Longform structure log (JSONL + TXT)
-/// - Folder blueprint (JSON + TXT)
-/// - Schemas and templates
-/// - Baby wrapper placeholders
-/// - Preflight record and run summary
-
-use compiler::cli_dashboard::{DashboardBuilder, DashboardRow, Totals, FileMetrics};
-use compiler::diagnostics::{DiagnosticBag, DiagnosticCode, SourceLocation};
+//! OASM Phase 1 - One-Time Initializer
+//!
+//! Drops into root folder, performs deterministic scan, generates:
+//! - Directory structure (logs/, templates/, schemas/, scripts/)
+//! - CLI dashboard snapshot (JSONL + TXT)
+//! - Longform structure log (JSONL + TXT)
+//! - Folder blueprint (JSON + TXT)
+//! - Schemas and templates
+//! - Baby wrapper placeholders
+//! - Preflight record and run summary
+
+use compiler::cli_dashboard::{DashboardBuilder, DashboardRow, Section, Totals, FileMetrics};
+use asm_formats::storage::{join_key, FsBackend, StorageBackend};
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::HashMap;
@@ -38,6 +38,7 @@ struct Args {
 #[derive(Debug)]
 struct ProjectArm {
     name: String,
+    #[allow(dead_code)]
     path: PathBuf,
     file_count: usize,
 }
@@ -48,6 +49,7 @@ fn main() -> Result<()> {
 
     let root = fs::canonicalize(&args.root)
         .context("Failed to resolve root directory")?;
+    let storage = FsBackend::new(&root);
 
     println!("🚀 OASM Phase 1 - One-Time Initializer");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -58,10 +60,10 @@ fn main() -> Result<()> {
     if !args.skip_setup {
         println!("📁 Creating directory structure...");
         create_directory_structure(&root)?;
-        create_schemas(&root)?;
-        create_templates(&root)?;
-        create_baby_placeholders(&root)?;
-        create_config_skeleton(&root)?;
+        create_schemas(&storage)?;
+        create_templates(&storage)?;
+        create_baby_placeholders(&storage)?;
+        create_config_skeleton(&storage)?;
         println!("   ✓ Directory structure created\n");
     }
 
@@ -83,26 +85,26 @@ fn main() -> Result<()> {
 
     // Step 5: Generate outputs
     let timestamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
-    let logs_out = root.join("logs").join("logs");
+    let logs_out = "logs/logs";
 
     println!("📊 Generating CLI dashboard...");
     let cli_rows = generate_cli_dashboard(&files, &root)?;
-    write_cli_snapshot(&cli_rows, &logs_out, &timestamp)?;
+    write_cli_snapshot(&storage, &cli_rows, logs_out, &timestamp)?;
     println!("   ✓ CLI snapshot written\n");
 
     println!("📝 Generating longform structure log...");
     let longform_rows = generate_longform(&files, &root)?;
-    write_longform(&longform_rows, &logs_out, &timestamp)?;
+    write_longform(&storage, &longform_rows, logs_out, &timestamp)?;
     println!("   ✓ Longform log written\n");
 
     println!("🗂️  Generating folder blueprint...");
     let folder_map = generate_folder_blueprint(&files, &root)?;
-    write_folder_blueprint(&folder_map, &logs_out, &timestamp)?;
+    write_folder_blueprint(&storage, &folder_map, logs_out, &timestamp)?;
     println!("   ✓ Folder blueprint written\n");
 
     // Step 6: Write preflight and run summary
-    write_preflight(&logs_out, &timestamp, &root)?;
-    write_run_summary(&logs_out, &timestamp, files.len(), &arms)?;
+    write_preflight(&storage, logs_out, &timestamp, &root)?;
+    write_run_summary(&storage, logs_out, &timestamp, files.len(), &arms)?;
 
     // Step 7: Final summary
     println!("✅ Phase 1 Complete!");
@@ -144,8 +146,7 @@ fn create_directory_structure(root: &Path) -> Result<()> {
     Ok(())
 }
 
-fn create_schemas(root: &Path) -> Result<()> {
-    let structure_dir = root.join("logs/structure");
+fn create_schemas(storage: &dyn StorageBackend) -> Result<()> {
 
     // CLI state schema
     let cli_schema = serde_json::json!({
@@ -173,9 +174,9 @@ fn create_schemas(root: &Path) -> Result<()> {
         "required": ["id", "n", "alias", "relPath", "link", "timestamp"]
     });
 
-    fs::write(
-        structure_dir.join("cli_state_schema.json"),
-        serde_json::to_string_pretty(&cli_schema)?
+    storage.put_atomic(
+        "logs/structure/cli_state_schema.json",
+        serde_json::to_string_pretty(&cli_schema)?.as_bytes()
     )?;
 
     // Diagnostic index schema
@@ -193,33 +194,31 @@ fn create_schemas(root: &Path) -> Result<()> {
         "required": ["fileId", "phase", "status", "short", "timestamp"]
     });
 
-    fs::write(
-        structure_dir.join("diagnostic_index_schema.json"),
-        serde_json::to_string_pretty(&diag_schema)?
+    storage.put_atomic(
+        "logs/structure/diagnostic_index_schema.json",
+        serde_json::to_string_pretty(&diag_schema)?.as_bytes()
     )?;
 
     Ok(())
 }
 
-fn create_templates(root: &Path) -> Result<()> {
-    let templates_dir = root.join("templates");
-    let index_path = templates_dir.join("index.yaml");
+fn create_templates(storage: &dyn StorageBackend) -> Result<()> {
+    let index_key = "templates/index.yaml";
 
-    if !index_path.exists() {
+    if !storage.exists(index_key)? {
         let template = r#"templates:
   - id: sample-repair
     matcher: 'TODO_FIX_ME'
     patch: 'FIXED_BY_AUTOREPAIR'
     confidence: 50
 "#;
-        fs::write(index_path, template)?;
+        storage.put_atomic(index_key, template.as_bytes())?;
     }
 
     Ok(())
 }
 
-fn create_baby_placeholders(root: &Path) -> Result<()> {
-    let scripts_ps = root.join("scripts/PS");
+fn create_baby_placeholders(storage: &dyn StorageBackend) -> Result<()> {
 
     let babies = vec![
         ("baby-full.ps1", "# Placeholder: Full scan (manifest-driven)\n# Usage: pwsh baby-full.ps1\n"),
@@ -228,19 +227,19 @@ fn create_baby_placeholders(root: &Path) -> Result<()> {
     ];
 
     for (name, content) in babies {
-        let path = scripts_ps.join(name);
-        if !path.exists() {
-            fs::write(path, content)?;
+        let key = join_key(&["scripts/PS", name]);
+        if !storage.exists(&key)? {
+            storage.put_atomic(&key, content.as_bytes())?;
         }
     }
 
     Ok(())
 }
 
-fn create_config_skeleton(root: &Path) -> Result<()> {
-    let config_path = root.join("oasm.config.yaml");
+fn create_config_skeleton(storage: &dyn StorageBackend) -> Result<()> {
+    let config_key = "oasm.config.yaml";
 
-    if !config_path.exists() {
+    if !storage.exists(config_key)? {
         let config = r#"exclusions:
   - .git/
   - node_modules/
@@ -257,7 +256,7 @@ arms: []
 logRetention: 10
 concurrency: 2
 "#;
-        fs::write(config_path, config)?;
+        storage.put_atomic(config_key, config.as_bytes())?;
     }
 
     Ok(())
@@ -356,7 +355,7 @@ fn identify_arms(root: &Path, files: &[PathBuf]) -> Vec<ProjectArm> {
         })
         .collect();
 
-    arms.sort_by(|a, b| b.file_count.cmp(&a.file_count));
+    arms.sort_by_key(|a| std::cmp::Reverse(a.file_count));
     arms.truncate(20); // Top 20 arms
 
     arms
@@ -373,7 +372,7 @@ fn generate_cli_dashboard(files: &[PathBuf], root: &Path) -> Result<Vec<Dashboar
         let row = builder.build_row(
             rel_path,
             Some(file.clone()),
-            Some("Phase1".to_string()),
+            Some(Section::Phase1),
             totals,
         );
 
@@ -383,9 +382,9 @@ fn generate_cli_dashboard(files: &[PathBuf], root: &Path) -> Result<Vec<Dashboar
     Ok(rows)
 }
 
-fn write_cli_snapshot(rows: &[DashboardRow], logs_out: &Path, timestamp: &str) -> Result<()> {
-    let jsonl_path = logs_out.join(format!("cli_snapshot-{}.jsonl", timestamp));
-    let txt_path = logs_out.join(format!("cli_snapshot-{}.txt", timestamp));
+fn write_cli_snapshot(storage: &dyn StorageBackend, rows: &[DashboardRow], logs_out: &str, timestamp: &str) -> Result<()> {
+    let jsonl_key = join_key(&[logs_out, &format!("cli_snapshot-{}.jsonl", timestamp)]);
+    let txt_key = join_key(&[logs_out, &format!("cli_snapshot-{}.txt", timestamp)]);
 
     let mut jsonl_content = String::new();
     let mut txt_content = String::new();
@@ -399,8 +398,8 @@ fn write_cli_snapshot(rows: &[DashboardRow], logs_out: &Path, timestamp: &str) -
         txt_content.push('\n');
     }
 
-    fs::write(jsonl_path, jsonl_content)?;
-    fs::write(txt_path, txt_content)?;
+    storage.put_atomic(&jsonl_key, jsonl_content.as_bytes())?;
+    storage.put_atomic(&txt_key, txt_content.as_bytes())?;
 
     Ok(())
 }
@@ -419,7 +418,7 @@ fn generate_longform(files: &[PathBuf], root: &Path) -> Result<Vec<DashboardRow>
         let row = builder.build_row_with_metrics(
             rel_path,
             Some(file.clone()),
-            Some("Structure".to_string()),
+            Some(Section::Structure),
             totals,
             metrics,
         );
@@ -478,9 +477,9 @@ fn compute_file_metrics(file: &Path) -> Result<FileMetrics> {
     })
 }
 
-fn write_longform(rows: &[DashboardRow], logs_out: &Path, timestamp: &str) -> Result<()> {
-    let jsonl_path = logs_out.join(format!("longform-{}.jsonl", timestamp));
-    let txt_path = logs_out.join(format!("longform-{}.txt", timestamp));
+fn write_longform(storage: &dyn StorageBackend, rows: &[DashboardRow], logs_out: &str, timestamp: &str) -> Result<()> {
+    let jsonl_key = join_key(&[logs_out, &format!("longform-{}.jsonl", timestamp)]);
+    let txt_key = join_key(&[logs_out, &format!("longform-{}.txt", timestamp)]);
 
     let mut jsonl_content = String::new();
     let mut txt_content = String::new();
@@ -498,8 +497,8 @@ fn write_longform(rows: &[DashboardRow], logs_out: &Path, timestamp: &str) -> Re
         txt_content.push('\n');
     }
 
-    fs::write(jsonl_path, jsonl_content)?;
-    fs::write(txt_path, txt_content)?;
+    storage.put_atomic(&jsonl_key, jsonl_content.as_bytes())?;
+    storage.put_atomic(&txt_key, txt_content.as_bytes())?;
 
     Ok(())
 }
@@ -516,7 +515,7 @@ fn generate_folder_blueprint(files: &[PathBuf], root: &Path) -> Result<HashMap<S
 
             folder_map
                 .entry(folder)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(rel_path.to_string_lossy().to_string());
         }
     }
@@ -525,12 +524,13 @@ fn generate_folder_blueprint(files: &[PathBuf], root: &Path) -> Result<HashMap<S
 }
 
 fn write_folder_blueprint(
+    storage: &dyn StorageBackend,
     folder_map: &HashMap<String, Vec<String>>,
-    logs_out: &Path,
+    logs_out: &str,
     timestamp: &str,
 ) -> Result<()> {
-    let json_path = logs_out.join(format!("folder_structure-{}.json", timestamp));
-    let txt_path = logs_out.join(format!("folder_structure-{}.txt", timestamp));
+    let json_key = join_key(&[logs_out, &format!("folder_structure-{}.json", timestamp)]);
+    let txt_key = join_key(&[logs_out, &format!("folder_structure-{}.txt", timestamp)]);
 
     // JSON output
     let mut folders: Vec<_> = folder_map
@@ -551,7 +551,7 @@ fn write_folder_blueprint(
             .cmp(b["folderPath"].as_str().unwrap_or(""))
     });
 
-    fs::write(json_path, serde_json::to_string_pretty(&folders)?)?;
+    storage.put_atomic(&json_key, serde_json::to_string_pretty(&folders)?.as_bytes())?;
 
     // Text output
     let mut txt_content = String::new();
@@ -567,12 +567,12 @@ fn write_folder_blueprint(
         ));
     }
 
-    fs::write(txt_path, txt_content)?;
+    storage.put_atomic(&txt_key, txt_content.as_bytes())?;
 
     Ok(())
 }
 
-fn write_preflight(logs_out: &Path, timestamp: &str, root: &Path) -> Result<()> {
+fn write_preflight(storage: &dyn StorageBackend, logs_out: &str, timestamp: &str, root: &Path) -> Result<()> {
     let preflight = serde_json::json!({
         "root": root.to_string_lossy(),
         "timestamp": Utc::now().to_rfc3339(),
@@ -584,16 +584,17 @@ fn write_preflight(logs_out: &Path, timestamp: &str, root: &Path) -> Result<()>
         ]
     });
 
-    fs::write(
-        logs_out.join(format!("preflight-{}.json", timestamp)),
-        serde_json::to_string_pretty(&preflight)?,
+    storage.put_atomic(
+        &join_key(&[logs_out, &format!("preflight-{}.json", timestamp)]),
+        serde_json::to_string_pretty(&preflight)?.as_bytes(),
     )?;
 
     Ok(())
 }
 
 fn write_run_summary(
-    logs_out: &Path,
+    storage: &dyn StorageBackend,
+    logs_out: &str,
     timestamp: &str,
     total_files: usize,
     arms: &[ProjectArm],
@@ -619,9 +620,9 @@ fn write_run_summary(
         ]
     });
 
-    fs::write(
-        logs_out.join(format!("run_summary-{}.json", timestamp)),
-        serde_json::to_string_pretty(&summary)?,
+    storage.put_atomic(
+        &join_key(&[logs_out, &format!("run_summary-{}.json", timestamp)]),
+        serde_json::to_string_pretty(&summary)?.as_bytes(),
     )?;
 
     Ok(())
@@ -0,0 +1,88 @@
+//! OASM Report CLI
+//! Cross-artifact reports over a history directory
+//!
+//! Usage:
+//!   oasm-report since-green [--history <dir>] [--format markdown|json]
+//!   oasm-report run-summary --run <id> [--lineage <dir>] [--format markdown|json] [--save]
+
+use asm_formats::lineage::LineageManager;
+use compiler::module_map::ModuleMapper;
+use compiler::since_green::build_report;
+use std::path::PathBuf;
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(name = "oasm-report")]
+#[command(about = "Workspace reports built from compile reports, scan snapshots and lineage", long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Files, runs and diagnostics since the last fully green compile
+    SinceGreen {
+        /// History directory (compile_reports/, snapshots/, lineage/)
+        #[arg(long, default_value = "logs/history")]
+        history: PathBuf,
+
+        /// Output format (markdown, json)
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+    },
+    /// Intent, outcomes, impact and failures of one lineage run
+    RunSummary {
+        /// Run id (as stored under the lineage directory)
+        #[arg(long)]
+        run: String,
+
+        /// Lineage directory
+        #[arg(long, default_value = "logs/history/lineage")]
+        lineage: PathBuf,
+
+        /// Output format (markdown, json)
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+
+        /// Also write summary.md into the run's lineage directory
+        #[arg(long)]
+        save: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let mapper = ModuleMapper::new().with_crate("runtime/daemon", "runtime_daemon");
+    match args.command {
+        Command::SinceGreen { history, format } => {
+            let report = build_report(&history, &mapper);
+            match format.as_str() {
+                "markdown" | "md" => print!("{}", report.to_markdown()),
+                "json" => println!("{}", report.to_json()?),
+                other => bail!("Unknown format '{}' (expected markdown or json)", other),
+            }
+        }
+        Command::RunSummary { run, lineage, format, save } => {
+            // Run from the project root, like the default lineage path
+            let manager = LineageManager::new(&lineage).with_project_config(".");
+            let Some(run_id) = manager.list_runs()?.into_iter().map(|(id, ..)| id).find(|id| id.to_string() == run) else {
+                bail!("No run '{}' under {}", run, lineage.display());
+            };
+            let summary = manager.summarize_run(run_id, &mapper)?;
+            if save {
+                manager.save_summary(&summary)?;
+            }
+            match format.as_str() {
+                "markdown" | "md" => print!("{}", summary.to_markdown()),
+                "json" => println!("{}", serde_json::to_string_pretty(&summary)?),
+                other => bail!("Unknown format '{}' (expected markdown or json)", other),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,10 +1,12 @@
-/// OASM Scanner CLI
-/// Universal pre-compile diagnostic tool
-///
-/// Usage:
-///   oasm-scan <project_root> [--output <dir>]
-///   oasm-scan --help
-
+//! OASM Scanner CLI
+//! Universal pre-compile diagnostic tool
+//!
+//! Usage:
+//!   oasm-scan <project_root> [--output <dir>]
+//!   oasm-scan --help
+
+use compiler::cli_dashboard::write_dashboard;
+use asm_formats::storage::{FsBackend, StorageBackend};
 use compiler::scanner::Scanner;
 use std::path::PathBuf;
 use std::fs;
@@ -42,6 +44,7 @@ fn main() -> Result<()> {
     // Ensure output directory exists
     fs::create_dir_all(&args.output)
         .context("Failed to create output directory")?;
+    let storage = FsBackend::new(&args.output);
 
     println!("🔍 OASM Scanner - Pre-Compile Diagnostics");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -57,35 +60,11 @@ fn main() -> Result<()> {
             .context("Failed to scan with dashboard format")?;
 
         let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S").to_string();
-
-        // Write JSONL (one JSON object per line)
-        let jsonl_path = args.output.join(format!("scan_dashboard_{}.jsonl", timestamp));
-        let mut jsonl_content = String::new();
-        for row in &dashboard_rows {
-            if let Ok(json) = row.to_jsonl() {
-                jsonl_content.push_str(&json);
-                jsonl_content.push('\n');
-            }
-        }
-        fs::write(&jsonl_path, jsonl_content)
-            .context("Failed to write JSONL file")?;
-        println!("✓ JSONL dashboard: {}", jsonl_path.display());
-
-        // Write plain text dashboard
-        let plain_path = args.output.join(format!("scan_dashboard_{}.txt", timestamp));
-        let mut plain_content = String::new();
-        plain_content.push_str("=== OASM Scan Dashboard ===\n");
-        plain_content.push_str(&format!("Timestamp: {}\n", timestamp));
-        plain_content.push_str(&format!("Total files: {}\n\n", dashboard_rows.len()));
-
-        for row in &dashboard_rows {
-            plain_content.push_str(&row.to_plain_text());
-            plain_content.push('\n');
-        }
-
-        fs::write(&plain_path, plain_content)
-            .context("Failed to write plain text dashboard")?;
-        println!("✓ Plain text dashboard: {}", plain_path.display());
+        let files = write_dashboard(&storage, &timestamp, &dashboard_rows)
+            .context("Failed to write dashboard")?;
+        println!("✓ JSONL dashboard: {}", args.output.join(&files.jsonl).display());
+        println!("✓ Plain text dashboard: {}", args.output.join(&files.plain).display());
+        println!("✓ Alias manifest: {}", args.output.join(&files.aliases).display());
 
         // Also print to stdout
         if args.verbose {
@@ -107,43 +86,43 @@ fn main() -> Result<()> {
 
     // Write structured log
     if args.format == "json" || args.format == "both" {
-        let json_path = args.output.join(format!("baseline_index_{}.json", timestamp));
+        let json_key = format!("baseline_index_{}.json", timestamp);
         let json = serde_json::to_string_pretty(&results.files)
             .context("Failed to serialize JSON")?;
-        fs::write(&json_path, json)
+        storage.put_atomic(&json_key, json.as_bytes())
             .context("Failed to write JSON file")?;
-        println!("✓ JSON index: {}", json_path.display());
+        println!("✓ JSON index: {}", args.output.join(&json_key).display());
     }
 
     // Write human-readable log
     if args.format == "both" {
-        let log_path = args.output.join(format!("structure_{}.log", timestamp));
+        let log_key = format!("structure_{}.log", timestamp);
         let log_content = format_structure_log(&results);
-        fs::write(&log_path, log_content)
+        storage.put_atomic(&log_key, log_content.as_bytes())
             .context("Failed to write structure log")?;
-        println!("✓ Structure log: {}", log_path.display());
+        println!("✓ Structure log: {}", args.output.join(&log_key).display());
     }
 
     // Write CLI state
-    let cli_state_path = args.output.join(format!("cli_state_{}.json", timestamp));
+    let cli_state_key = format!("cli_state_{}.json", timestamp);
     let cli_state = serde_json::to_string_pretty(&results.files)
         .context("Failed to serialize CLI state")?;
-    fs::write(&cli_state_path, cli_state)
+    storage.put_atomic(&cli_state_key, cli_state.as_bytes())
         .context("Failed to write CLI state")?;
-    println!("✓ CLI state: {}", cli_state_path.display());
+    println!("✓ CLI state: {}", args.output.join(&cli_state_key).display());
 
     // Print summary
     println!("\n📈 Summary:");
     println!("   Files: {}", results.total_files);
     println!("   Total LOC: {}", results.total_loc);
     println!("   Average LOC/file: {}",
-        if results.total_files > 0 { results.total_loc / results.total_files } else { 0 });
+        results.total_loc.checked_div(results.total_files).unwrap_or(0));
 
     // Print top files by LOC
     if args.verbose {
         println!("\n🔝 Top 10 files by LOC:");
         let mut sorted_files = results.files.clone();
-        sorted_files.sort_by(|a, b| b.loc.cmp(&a.loc));
+        sorted_files.sort_by_key(|f| std::cmp::Reverse(f.loc));
         for (i, file) in sorted_files.iter().take(10).enumerate() {
             println!("   {}. {} ({} LOC)", i + 1, file.alias, file.loc);
         }
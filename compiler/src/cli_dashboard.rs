@@ -1,10 +1,12 @@
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
 use sha2::{Sha256, Digest};
+use asm_formats::storage::StorageBackend;
 
 /// Detailed file metrics (compatible with structure log format)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileMetrics {
     pub loc: usize,
     pub fn_count: usize,
@@ -41,8 +43,23 @@ impl FileMetrics {
             modified: String::new(),
         }
     }
+
+    /// Number of non-empty fields, used to pick the richer of two metric sets
+    pub fn populated_fields(&self) -> usize {
+        [
+            self.loc, self.fn_count, self.pub_fn, self.unsafe_fn, self.imports,
+            self.logs_info, self.logs_warn, self.logs_error, self.printlns,
+            self.structs, self.enums, self.derives, self.tests,
+        ]
+        .iter()
+        .filter(|&&n| n > 0)
+        .count()
+            + usize::from(!self.modified.is_empty())
+    }
 }
 
+pub use asm_formats::schemas::Section;
+
 /// CLI Dashboard row - JSONL format with exact field names
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DashboardRow {
@@ -59,12 +76,18 @@ pub struct DashboardRow {
     pub diagnostics: Vec<String>,
     pub timestamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub section: Option<String>,
+    pub section: Option<Section>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metrics: Option<FileMetrics>,
+    /// Rows per second over the builder's recent rows (none for the first row)
+    #[serde(default, rename = "ratePerSec", skip_serializing_if = "Option::is_none")]
+    pub rate_per_sec: Option<f64>,
+    /// Estimated seconds until the last row (needs a rate and a known total)
+    #[serde(default, rename = "etaSeconds", skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Totals {
     pub crit: usize,
     pub block: usize,
@@ -81,11 +104,24 @@ impl Totals {
     }
 }
 
-/// Dashboard builder with stateful counter and alias tracking
+/// Rows averaged for the rate, so one slow file doesn't swing the ETA
+pub const DEFAULT_RATE_WINDOW: usize = 8;
+
+/// Source of the current time for row timing (`Instant::now` unless a test
+/// injects one)
+pub type Clock = Box<dyn Fn() -> Instant + Send + Sync>;
+
+/// Dashboard builder with stateful counter, alias and timing tracking
 pub struct DashboardBuilder {
     total: usize,
     next_id: usize,
     alias_set: HashMap<String, bool>,
+    clock: Clock,
+    started: Instant,
+    last_row: Option<Instant>,
+    /// Time between consecutive rows, most recent last
+    recent: VecDeque<Duration>,
+    rate_window: usize,
 }
 
 impl DashboardBuilder {
@@ -94,9 +130,33 @@ impl DashboardBuilder {
             total,
             next_id: 1,
             alias_set: HashMap::new(),
+            clock: Box::new(Instant::now),
+            started: Instant::now(),
+            last_row: None,
+            recent: VecDeque::new(),
+            rate_window: DEFAULT_RATE_WINDOW,
         }
     }
 
+    /// Average the rate over the last `rows` rows (at least 1)
+    pub fn with_rate_window(mut self, rows: usize) -> Self {
+        self.rate_window = rows.max(1);
+        self
+    }
+
+    /// Read the time from `clock` instead of `Instant::now`; the builder's
+    /// start time is taken from it too
+    pub fn with_clock(mut self, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.started = clock();
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Time since the builder was created
+    pub fn elapsed(&self) -> Duration {
+        (self.clock)() - self.started
+    }
+
     pub fn next_id(&mut self) -> usize {
         let id = self.next_id;
         self.next_id += 1;
@@ -112,7 +172,7 @@ impl DashboardBuilder {
     }
 
     /// Create short alias from basename (max 20 chars, sanitized)
-    fn short_alias(basename: &str) -> String {
+    pub(crate) fn short_alias(basename: &str) -> String {
         let sanitized: String = basename
             .chars()
             .map(|c| if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' { c } else { '_' })
@@ -139,12 +199,32 @@ impl DashboardBuilder {
         format!("{:width$}", bar, width = length)
     }
 
+    /// Record a row finishing now; returns (rate, ETA) after row `id`.
+    /// The first row only starts the clock.
+    fn record_timing(&mut self, id: usize) -> (Option<f64>, Option<f64>) {
+        let now = (self.clock)();
+        if let Some(last) = self.last_row.replace(now) {
+            self.recent.push_back(now - last);
+            while self.recent.len() > self.rate_window {
+                self.recent.pop_front();
+            }
+        }
+
+        let window: Duration = self.recent.iter().sum();
+        if self.recent.is_empty() || window.is_zero() {
+            return (None, None);
+        }
+        let rate = self.recent.len() as f64 / window.as_secs_f64();
+        let eta = (self.total > 0).then(|| self.total.saturating_sub(id) as f64 / rate);
+        (Some(rate), eta)
+    }
+
     /// Build a single dashboard row
     pub fn build_row(
         &mut self,
         rel_path: impl AsRef<Path>,
         full_path: Option<PathBuf>,
-        section: Option<String>,
+        section: Option<Section>,
         totals: Totals,
     ) -> DashboardRow {
         let id = self.next_id();
@@ -185,6 +265,7 @@ impl DashboardBuilder {
         let visual = Self::make_visual_bar(id, self.total, 11);
 
         let timestamp = chrono::Utc::now().to_rfc3339();
+        let (rate_per_sec, eta_seconds) = self.record_timing(id);
 
         DashboardRow {
             id,
@@ -199,6 +280,8 @@ impl DashboardBuilder {
             timestamp,
             section,
             metrics: None, // Can be populated later
+            rate_per_sec,
+            eta_seconds,
         }
     }
 
@@ -207,7 +290,7 @@ impl DashboardBuilder {
         &mut self,
         rel_path: impl AsRef<Path>,
         full_path: Option<PathBuf>,
-        section: Option<String>,
+        section: Option<Section>,
         totals: Totals,
         metrics: FileMetrics,
     ) -> DashboardRow {
@@ -221,7 +304,7 @@ impl DashboardRow {
     /// Format as plain text compact dashboard line
     /// Format: [progress][section]relPath[visual][▼crit/block/warn][▼]
     pub fn to_plain_text(&self) -> String {
-        let section = self.section.as_deref().unwrap_or("Structure");
+        let section = self.section.as_ref().unwrap_or(&Section::Structure);
         format!(
             "[{}][{}]{}[{}][▼{}/{}/{}][▼]",
             self.progress,
@@ -234,6 +317,22 @@ impl DashboardRow {
         )
     }
 
+    /// `to_plain_text` followed by the rate and ETA, e.g. `[4.2/s eta 12s]`;
+    /// `[eta --]` until there is a rate
+    pub fn to_plain_text_with_eta(&self) -> String {
+        let timing = match (self.rate_per_sec, self.eta_seconds) {
+            (Some(rate), Some(eta)) => format!("[{:.1}/s eta {}s]", rate, eta.round() as u64),
+            (Some(rate), None) => format!("[{:.1}/s eta --]", rate),
+            _ => "[eta --]".to_string(),
+        };
+        format!("{}{}", self.to_plain_text(), timing)
+    }
+
+    /// Whether the row came from `section` (alone or as part of a merge)
+    pub fn in_section(&self, section: &Section) -> bool {
+        self.section.as_ref().is_some_and(|own| own.includes(section))
+    }
+
     /// Format as high-density structure log line (compatible with existing logs)
     /// Format: · [n/total] relPath | LOC | fn | logging | structs | etc.
     pub fn to_structure_log_line(&self) -> String {
@@ -309,7 +408,7 @@ impl DashboardRow {
 pub fn build_dashboard_from_paths(
     rel_paths: &[PathBuf],
     full_paths: Option<&[PathBuf]>,
-    section: Option<String>,
+    section: Option<Section>,
 ) -> Vec<DashboardRow> {
     // Sort by relPath for deterministic ordering
     let mut pairs: Vec<(PathBuf, Option<PathBuf>)> = rel_paths
@@ -334,6 +433,167 @@ pub fn build_dashboard_from_paths(
     rows
 }
 
+/// Merge rows that share a relPath (e.g. a Compile and a Structure pass over
+/// the same file) into one row per file, in order of first appearance.
+/// Totals are summed, diagnostics concatenated, sections joined with `+`, and
+/// the metrics with the most populated fields are kept.
+pub fn merge_rows_by_path(rows: Vec<DashboardRow>) -> Vec<DashboardRow> {
+    let mut merged: Vec<DashboardRow> = Vec::new();
+    let mut index_by_path: HashMap<String, usize> = HashMap::new();
+
+    for row in rows {
+        let Some(&index) = index_by_path.get(&row.rel_path) else {
+            index_by_path.insert(row.rel_path.clone(), merged.len());
+            merged.push(row);
+            continue;
+        };
+
+        let target = &mut merged[index];
+        target.totals.crit += row.totals.crit;
+        target.totals.block += row.totals.block;
+        target.totals.warn += row.totals.warn;
+        target.diagnostics.extend(row.diagnostics);
+
+        target.section = match (target.section.take(), row.section) {
+            (Some(a), Some(b)) if a.includes(&b) => Some(a),
+            (Some(a), Some(b)) => Some(Section::from(format!("{}+{}", a, b))),
+            (a, b) => a.or(b),
+        };
+
+        let richness = |m: &Option<FileMetrics>| m.as_ref().map_or(0, FileMetrics::populated_fields);
+        if richness(&row.metrics) > richness(&target.metrics) {
+            target.metrics = row.metrics;
+        }
+    }
+
+    merged
+}
+
+/// File-level differences between two dashboard snapshots (by relPath)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DashboardDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Rows whose totals, diagnostics or metrics differ
+    pub changed: Vec<String>,
+}
+
+impl DashboardDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Every path that appears in the diff, sorted
+    pub fn paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = self.added.iter()
+            .chain(&self.removed)
+            .chain(&self.changed)
+            .map(String::as_str)
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+/// Diff two dashboard snapshots. Ids, progress and timestamps are ignored
+/// since they change on every scan.
+pub fn diff_dashboards(old: &[DashboardRow], new: &[DashboardRow]) -> DashboardDiff {
+    let old_rows: HashMap<&str, &DashboardRow> = old.iter().map(|r| (r.rel_path.as_str(), r)).collect();
+    let new_rows: HashMap<&str, &DashboardRow> = new.iter().map(|r| (r.rel_path.as_str(), r)).collect();
+
+    let mut diff = DashboardDiff::default();
+    for (path, row) in &new_rows {
+        match old_rows.get(path) {
+            None => diff.added.push(path.to_string()),
+            Some(before) => {
+                if before.totals != row.totals || before.diagnostics != row.diagnostics || before.metrics != row.metrics {
+                    diff.changed.push(path.to_string());
+                }
+            }
+        }
+    }
+    diff.removed = old_rows.keys()
+        .filter(|path| !new_rows.contains_key(*path))
+        .map(|path| path.to_string())
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    diff
+}
+
+/// Rows produced by (or merged from) one section, in their original order
+pub fn rows_in_section<'a>(rows: &'a [DashboardRow], section: &Section) -> Vec<&'a DashboardRow> {
+    rows.iter().filter(|row| row.in_section(section)).collect()
+}
+
+/// Parse a JSONL dashboard snapshot (as written by `oasm-scan`)
+pub fn parse_dashboard_jsonl(content: &str) -> Result<Vec<DashboardRow>, serde_json::Error> {
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// Where an alias points (one entry of the alias manifest)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AliasTarget {
+    #[serde(rename = "relPath")]
+    pub rel_path: String,
+    pub link: String,
+}
+
+/// Alias -> path manifest for a set of rows, written next to the dashboard
+/// as `aliases-<ts>.json` so tools can refer to files by alias
+pub fn alias_manifest(rows: &[DashboardRow]) -> BTreeMap<String, AliasTarget> {
+    rows.iter()
+        .map(|row| {
+            let target = AliasTarget { rel_path: row.rel_path.clone(), link: row.link.clone() };
+            (row.alias.clone(), target)
+        })
+        .collect()
+}
+
+/// Storage keys written by `write_dashboard`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DashboardFiles {
+    pub jsonl: String,
+    pub plain: String,
+    pub aliases: String,
+}
+
+/// Write a scan dashboard to `storage`: `scan_dashboard_<ts>.jsonl` (one row
+/// per line), `scan_dashboard_<ts>.txt` and `aliases-<ts>.json`
+pub fn write_dashboard(storage: &dyn StorageBackend, timestamp: &str, rows: &[DashboardRow]) -> anyhow::Result<DashboardFiles> {
+    let files = DashboardFiles {
+        jsonl: format!("scan_dashboard_{}.jsonl", timestamp),
+        plain: format!("scan_dashboard_{}.txt", timestamp),
+        aliases: format!("aliases-{}.json", timestamp),
+    };
+
+    let mut jsonl_content = String::new();
+    for row in rows {
+        jsonl_content.push_str(&row.to_jsonl()?);
+        jsonl_content.push('\n');
+    }
+    storage.put_atomic(&files.jsonl, jsonl_content.as_bytes())?;
+
+    let mut plain_content = String::new();
+    plain_content.push_str("=== OASM Scan Dashboard ===\n");
+    plain_content.push_str(&format!("Timestamp: {}\n", timestamp));
+    plain_content.push_str(&format!("Total files: {}\n\n", rows.len()));
+    for row in rows {
+        plain_content.push_str(&row.to_plain_text());
+        plain_content.push('\n');
+    }
+    storage.put_atomic(&files.plain, plain_content.as_bytes())?;
+
+    storage.put_atomic(&files.aliases, serde_json::to_string_pretty(&alias_manifest(rows))?.as_bytes())?;
+
+    Ok(files)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,11 +623,49 @@ mod tests {
             totals: Totals { crit: 0, block: 0, warn: 1 },
             diagnostics: Vec::new(),
             timestamp: "2025-12-18T10:00:00Z".to_string(),
-            section: Some("Structure".to_string()),
+            section: Some(Section::Structure),
+            metrics: None,
+            rate_per_sec: None,
+            eta_seconds: None,
         };
 
         let plain = row.to_plain_text();
         assert_eq!(plain, "[2/130][Structure]bindings\\build.rs[///////////][▼0/0/1][▼]");
+        assert_eq!(row.to_plain_text_with_eta(), format!("{}[eta --]", plain));
+
+        let row = DashboardRow { rate_per_sec: Some(4.25), eta_seconds: Some(11.6), ..row };
+        assert!(row.to_plain_text_with_eta().ends_with("[▼][4.2/s eta 12s]"), "{}", row.to_plain_text_with_eta());
+        assert!(row.to_jsonl().unwrap().contains("\"etaSeconds\":11.6"));
+    }
+
+    #[test]
+    fn test_eta_decreases_as_rows_complete() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        // Every row takes 40ms on the injected clock
+        let base = Instant::now();
+        let elapsed_ms = Arc::new(AtomicU64::new(0));
+        let clock = Arc::clone(&elapsed_ms);
+        let mut builder = DashboardBuilder::new(6)
+            .with_rate_window(3)
+            .with_clock(move || base + Duration::from_millis(clock.load(Ordering::SeqCst)));
+
+        let first = builder.build_row("src/a.rs", None, None, Totals::zero());
+        assert_eq!((first.rate_per_sec, first.eta_seconds), (None, None));
+
+        let mut previous = f64::INFINITY;
+        for name in ["b", "c", "d", "e", "f"] {
+            elapsed_ms.fetch_add(40, Ordering::SeqCst);
+            let row = builder.build_row(format!("src/{}.rs", name), None, None, Totals::zero());
+            let eta = row.eta_seconds.expect("rate after the first row");
+            let expected = (6 - row.id) as f64 * 0.04;
+            assert!((eta - expected).abs() < 1e-9, "row {}: eta {} vs {}", row.id, eta, expected);
+            assert!(eta < previous, "row {}: eta {} after {}", row.id, eta, previous);
+            previous = eta;
+        }
+        assert_eq!(previous, 0.0);
+        assert_eq!(builder.elapsed(), Duration::from_millis(200));
     }
 
     #[test]
@@ -377,7 +675,7 @@ mod tests {
         let row1 = builder.build_row(
             PathBuf::from("src/lib.rs"),
             None,
-            Some("Structure".to_string()),
+            Some(Section::Structure),
             Totals::zero()
         );
 
@@ -407,4 +705,141 @@ mod tests {
         assert_eq!(row1.alias, "test.rs");
         assert!(row2.alias.starts_with("test.rs#"));
     }
+
+    #[test]
+    fn test_diff_dashboards_ignores_volatile_fields() {
+        let mut old_builder = DashboardBuilder::new(3);
+        let old = vec![
+            old_builder.build_row("src/a.rs", None, None, Totals::zero()),
+            old_builder.build_row("src/b.rs", None, None, Totals::zero()),
+            old_builder.build_row("src/c.rs", None, None, Totals::zero()),
+        ];
+
+        // Rebuilt in a different order with fresh ids and timestamps
+        let mut new_builder = DashboardBuilder::new(3);
+        let new = vec![
+            new_builder.build_row("src/d.rs", None, None, Totals::zero()),
+            new_builder.build_row("src/b.rs", None, None, Totals::new(1, 0, 0)),
+            new_builder.build_row("src/a.rs", None, None, Totals::zero()),
+        ];
+
+        let diff = diff_dashboards(&old, &new);
+        assert_eq!(diff.added, vec!["src/d.rs"]);
+        assert_eq!(diff.removed, vec!["src/c.rs"]);
+        assert_eq!(diff.changed, vec!["src/b.rs"]);
+        assert_eq!(diff.paths(), vec!["src/b.rs", "src/c.rs", "src/d.rs"]);
+    }
+
+    #[test]
+    fn test_merge_rows_by_path_combines_passes() {
+        let mut builder = DashboardBuilder::new(3);
+
+        let mut compile = builder.build_row("src/lib.rs", None, Some(Section::Compile), Totals::new(1, 1, 0));
+        compile.diagnostics = vec!["[E0001] Unexpected token".to_string()];
+
+        let metrics = FileMetrics { loc: 120, fn_count: 8, ..FileMetrics::zero() };
+        let mut structure = builder.build_row_with_metrics(
+            "src/lib.rs",
+            None,
+            Some(Section::Structure),
+            Totals::new(0, 0, 2),
+            metrics.clone(),
+        );
+        structure.diagnostics = vec!["[W0001] Unused import".to_string()];
+
+        let other = builder.build_row("src/main.rs", None, Some(Section::Structure), Totals::zero());
+
+        let merged = merge_rows_by_path(vec![compile, other, structure]);
+
+        assert_eq!(merged.len(), 2);
+        let lib = &merged[0];
+        assert_eq!(lib.rel_path, "src/lib.rs");
+        assert_eq!(lib.totals, Totals::new(1, 1, 2));
+        assert_eq!(lib.diagnostics, vec!["[E0001] Unexpected token", "[W0001] Unused import"]);
+        assert_eq!(lib.section, Some(Section::Custom("Compile+Structure".to_string())));
+        assert_eq!(lib.metrics, Some(metrics));
+        assert_eq!(merged[1].rel_path, "src/main.rs");
+    }
+
+    #[test]
+    fn test_alias_manifest_maps_every_row() {
+        let rows = build_dashboard_from_paths(
+            &[PathBuf::from("src/lib.rs"), PathBuf::from("src/test.rs"), PathBuf::from("tests/test.rs")],
+            None,
+            None,
+        );
+        let manifest = alias_manifest(&rows);
+
+        assert_eq!(manifest.len(), rows.len());
+        for row in &rows {
+            assert_eq!(manifest[&row.alias].rel_path, row.rel_path);
+            assert_eq!(manifest[&row.alias].link, row.link);
+        }
+        let collision = rows.iter().find(|r| r.rel_path == "tests/test.rs").unwrap();
+        assert!(collision.alias.starts_with("test.rs#"));
+        assert_eq!(manifest[&collision.alias].rel_path, "tests/test.rs");
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["test.rs"]["relPath"], "src/test.rs");
+    }
+
+    #[test]
+    fn test_section_serializes_as_legacy_strings() {
+        for (section, legacy) in [
+            (Section::Structure, "Structure"),
+            (Section::Compile, "Compile"),
+            (Section::Phase1, "Phase1"),
+            (Section::Scan, "Scan"),
+            (Section::Overlay, "Overlay"),
+            (Section::Custom("Lint".to_string()), "Lint"),
+        ] {
+            assert_eq!(serde_json::to_value(&section).unwrap(), serde_json::json!(legacy));
+            assert_eq!(serde_json::from_value::<Section>(serde_json::json!(legacy)).unwrap(), section);
+            assert_eq!(legacy.parse::<Section>().unwrap(), section);
+        }
+
+        let mut builder = DashboardBuilder::new(4);
+        let rows = vec![
+            builder.build_row("src/a.rs", None, Some(Section::Compile), Totals::zero()),
+            builder.build_row("src/b.rs", None, Some(Section::Structure), Totals::zero()),
+            builder.build_row("src/c.rs", None, None, Totals::zero()),
+            builder.build_row("src/b.rs", None, Some(Section::Compile), Totals::zero()),
+        ];
+        let line = rows[0].to_jsonl().unwrap();
+        assert!(line.contains("\"section\":\"Compile\""));
+        assert_eq!(parse_dashboard_jsonl(&line).unwrap()[0].section, Some(Section::Compile));
+
+        let compiled: Vec<&str> = rows_in_section(&rows, &Section::Compile).iter().map(|r| r.rel_path.as_str()).collect();
+        assert_eq!(compiled, vec!["src/a.rs", "src/b.rs"]);
+
+        // A merged Structure+Compile row still filters as Compile
+        let merged = merge_rows_by_path(rows);
+        let compiled: Vec<&str> = rows_in_section(&merged, &Section::Compile).iter().map(|r| r.rel_path.as_str()).collect();
+        assert_eq!(compiled, vec!["src/a.rs", "src/b.rs"]);
+        assert!(rows_in_section(&merged, &Section::Phase1).is_empty());
+    }
+
+    #[test]
+    fn test_write_dashboard_to_memory_backend() {
+        use asm_formats::storage::MemoryBackend;
+
+        let rows = build_dashboard_from_paths(
+            &[PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")],
+            None,
+            Some(Section::Scan),
+        );
+        let storage = MemoryBackend::new();
+        let files = write_dashboard(&storage, "20250101T000000", &rows).unwrap();
+
+        assert_eq!(storage.list_prefix("").unwrap().len(), 3);
+        assert_eq!(files.jsonl, "scan_dashboard_20250101T000000.jsonl");
+        let jsonl = String::from_utf8(storage.get(&files.jsonl).unwrap()).unwrap();
+        let parsed = parse_dashboard_jsonl(&jsonl).unwrap();
+        assert_eq!(parsed.iter().map(|r| &r.rel_path).collect::<Vec<_>>(), vec!["src/lib.rs", "src/main.rs"]);
+
+        let plain = String::from_utf8(storage.get(&files.plain).unwrap()).unwrap();
+        assert!(plain.starts_with("=== OASM Scan Dashboard ===\nTimestamp: 20250101T000000\nTotal files: 2\n"));
+        let aliases: BTreeMap<String, AliasTarget> = serde_json::from_slice(&storage.get(&files.aliases).unwrap()).unwrap();
+        assert_eq!(aliases, alias_manifest(&rows));
+    }
 }
@@ -1,6 +1,8 @@
 use std::fmt;
 use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
 use crate::cli_dashboard::{DashboardRow, Totals};
+use runtime_daemon::validator::{ManifestIssue, ManifestIssueKind};
 
 /// Severity level for diagnostics
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -90,7 +92,7 @@ pub enum DiagnosticCode {
 
     // Warnings (W0001-W9999)
     W0001, // Unused variable
-    W0002, // Deprecated feature
+    W0002, // Unknown or deprecated field
     W0003, // Unreachable code
     W0004, // Performance warning
 }
@@ -252,6 +254,19 @@ impl DiagnosticBag {
         self.add(Diagnostic::warning(code, message, location));
     }
 
+    /// Add `check_manifest` findings: missing fields as E0501, invalid
+    /// values as E0502 and unknown fields as W0002, at the key's line
+    pub fn add_manifest_issues(&mut self, file: &str, issues: &[ManifestIssue]) {
+        for issue in issues {
+            let location = SourceLocation::new(PathBuf::from(file), issue.line, 1, issue.field.len());
+            match issue.kind {
+                ManifestIssueKind::MissingField => self.add_error(DiagnosticCode::E0501, &issue.message, location),
+                ManifestIssueKind::InvalidValue => self.add_error(DiagnosticCode::E0502, &issue.message, location),
+                ManifestIssueKind::UnknownField => self.add_warning(DiagnosticCode::W0002, &issue.message, location),
+            }
+        }
+    }
+
     pub fn has_errors(&self) -> bool {
         self.diagnostics.iter().any(|d| d.severity == Severity::Error)
     }
@@ -327,6 +342,39 @@ impl DiagnosticBag {
     }
 }
 
+/// Persisted summary of one compile (JSON), used to find the last green build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompileReport {
+    pub manifest: String,
+    /// RFC 3339
+    pub timestamp: String,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Distinct diagnostic codes, sorted
+    pub codes: Vec<String>,
+}
+
+impl CompileReport {
+    pub fn from_diagnostics(manifest: impl Into<String>, bag: &DiagnosticBag) -> Self {
+        let mut codes: Vec<String> = bag.diagnostics().iter().map(|d| d.code.to_string()).collect();
+        codes.sort();
+        codes.dedup();
+
+        Self {
+            manifest: manifest.into(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            errors: bag.error_count(),
+            warnings: bag.warning_count(),
+            codes,
+        }
+    }
+
+    /// A compile with no errors (warnings allowed)
+    pub fn is_green(&self) -> bool {
+        self.errors == 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -371,4 +419,23 @@ mod tests {
 
         assert_eq!(diag.suggestions.len(), 2);
     }
+
+    #[test]
+    fn test_manifest_issue_codes() {
+        let content = "package:\n  name: gear\n  version: 1.0\n  license: MIT\n";
+        let issues = runtime_daemon::validator::check_manifest(content);
+        let mut bag = DiagnosticBag::new();
+        bag.add_manifest_issues("crate_manifest.yaml", &issues);
+
+        let found: Vec<(DiagnosticCode, usize)> =
+            bag.diagnostics().iter().map(|d| (d.code, d.location.line)).collect();
+        assert_eq!(
+            found,
+            vec![(DiagnosticCode::E0502, 3), (DiagnosticCode::E0501, 1), (DiagnosticCode::W0002, 4)]
+        );
+        let totals = bag.to_dashboard_totals();
+        assert_eq!(bag.error_count(), 2);
+        assert_eq!(bag.warning_count(), 1);
+        assert_eq!((totals.crit, totals.warn), (2, 1));
+    }
 }
@@ -1,14 +1,18 @@
 use runtime_daemon::parser::{parse_manifest, to_yaml};
-use runtime_daemon::validator::validate_manifest;
+use runtime_daemon::validator::{check_manifest, validate_manifest};
 use runtime_daemon::commit::commit_text;
-use runtime_daemon::lineage::record_event;
+use runtime_daemon::lineage::{record_event, record_event_to};
 
 pub mod scanner;
 pub mod diagnostics;
 pub mod cli_dashboard;
+pub mod oasm_cbor;
+pub use asm_formats::module_map;
+pub mod since_green;
 
 use diagnostics::{DiagnosticBag, DiagnosticCode, SourceLocation};
-use cli_dashboard::DashboardBuilder;
+use cli_dashboard::{DashboardBuilder, Section};
+pub use oasm_cbor::compile_oasm_to_cbor;
 use std::path::PathBuf;
 
 pub fn compile_manifest(path: &str) -> Result<(), String> {
@@ -16,10 +20,36 @@ pub fn compile_manifest(path: &str) -> Result<(), String> {
 }
 
 pub fn compile_manifest_with_diagnostics(path: &str, enable_dashboard: bool) -> Result<(), String> {
+    compile_manifest_with(path, &CompileOptions { dashboard: enable_dashboard, ..CompileOptions::default() })
+}
+
+/// Options for `compile_manifest_with`
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Emit a Compile dashboard row for the manifest
+    pub dashboard: bool,
+    /// Lineage log to record the result in (the daemon's log by default)
+    pub lineage_log: Option<PathBuf>,
+}
+
+pub fn compile_manifest_with(path: &str, options: &CompileOptions) -> Result<(), String> {
+    let enable_dashboard = options.dashboard;
     log::info!("Compiler invoked on manifest: {}", path);
 
     let mut diagnostics = DiagnosticBag::new();
 
+    // Field-level checks, located by line; unknown fields only warn
+    if let Ok(content) = std::fs::read_to_string(path) {
+        diagnostics.add_manifest_issues(path, &check_manifest(&content));
+        if diagnostics.has_errors() {
+            if enable_dashboard {
+                emit_dashboard_for_path(path, &diagnostics);
+            }
+            diagnostics.print_all();
+            return Err(format!("Validation failed: {} manifest error(s)", diagnostics.error_count()));
+        }
+    }
+
     // Parse manifest
     let manifest = match parse_manifest(path) {
         Ok(m) => m,
@@ -90,7 +120,11 @@ pub fn compile_manifest_with_diagnostics(path: &str, enable_dashboard: bool) ->
         emit_dashboard_for_path(path, &diagnostics);
     }
 
-    record_event(&format!("Manifest compiled successfully: {}", path)).ok();
+    let event = format!("Manifest compiled successfully: {}", path);
+    match &options.lineage_log {
+        Some(log) => record_event_to(log, &event).ok(),
+        None => record_event(&event).ok(),
+    };
 
     if diagnostics.has_errors() {
         diagnostics.print_summary();
@@ -108,7 +142,7 @@ fn emit_dashboard_for_path(path: &str, diagnostics: &DiagnosticBag) {
     let mut row = builder.build_row(
         PathBuf::from(path),
         Some(PathBuf::from(path)),
-        Some("Compile".to_string()),
+        Some(Section::Compile),
         totals
     );
 
@@ -0,0 +1,75 @@
+//! OASM source -> CBOR runtime object
+//!
+//! Entry point for driving execution from plain `.oasm` files: the script is
+//! parsed with the native parser and packed into an `OasmProgram` command
+//! block as typed instructions, ready for `serde_cbor` encoding.
+
+use anyhow::{anyhow, Result};
+use asm_formats::runtime::CommandBlockBuilder;
+use asm_formats::schemas::{BlockType, CBORRuntimeObject};
+use asm_formats::{Actor, RunId, Seq};
+use oasm_core::parser::{InstructionParser, NativeParser};
+use sha2::{Digest, Sha256};
+
+/// Parse `source` and wrap it in a runtime object for a new run. The
+/// metadata's `config_hash` is the SHA-256 of the source, so the object can
+/// be traced back to the exact script it was compiled from.
+pub fn compile_oasm_to_cbor(source: &str, actor: Actor) -> Result<CBORRuntimeObject> {
+    let instructions = NativeParser
+        .parse_file(source)
+        .map_err(|e| anyhow!("Failed to parse OASM source: {:?}", e))?;
+
+    let command = instructions
+        .into_iter()
+        .fold(CommandBlockBuilder::new(BlockType::OasmProgram), CommandBlockBuilder::parsed_instruction)
+        .build();
+
+    let mut object = CBORRuntimeObject::new(RunId::generate(), Seq::zero(), actor, command);
+    object.metadata.config_hash = format!("{:x}", Sha256::digest(source.as_bytes()));
+    Ok(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_oasm_to_cbor() {
+        let source = "# gear\nCREATE gear\nSET gear_0000.teeth = 24\n\nEXTRUDE gear_0000 10\n";
+        let object = compile_oasm_to_cbor(source, Actor::System).unwrap();
+
+        let bytes = serde_cbor::to_vec(&object).unwrap();
+        let decoded: CBORRuntimeObject = serde_cbor::from_slice(&bytes).unwrap();
+
+        assert!(matches!(decoded.command.block_type, BlockType::OasmProgram));
+        assert_eq!(decoded.command.program, NativeParser.parse_file(source).unwrap());
+        assert_eq!(
+            decoded.command.program.iter().map(|i| i.line_number).collect::<Vec<_>>(),
+            vec![2, 3, 5]
+        );
+        let run_id = RunId::from_string(&decoded.metadata.run_id.to_string()).unwrap();
+        assert_eq!(run_id, decoded.auto_fields.run_id);
+        assert_eq!(decoded.object_id, format!("{}_0", run_id));
+        assert_eq!(decoded.metadata.config_hash.len(), 64);
+
+        assert!(compile_oasm_to_cbor("SET x = \"open", Actor::System).is_err());
+    }
+
+    #[test]
+    fn test_operands_keep_their_types() {
+        use oasm_core::parser::Operand;
+        use oasm_core::types::Value;
+
+        let object = compile_oasm_to_cbor("SET module = 2.0\nSET label = \"spur, 20 deg\"", Actor::System).unwrap();
+        let decoded: CBORRuntimeObject = serde_cbor::from_slice(&serde_cbor::to_vec(&object).unwrap()).unwrap();
+
+        let value = |i: usize| match &decoded.command.program[i].operands[0] {
+            Operand::Assignment { value, .. } => (**value).clone(),
+            other => panic!("expected an assignment, got {:?}", other),
+        };
+        // A rendered `2` would come back as an integer
+        assert_eq!(value(0), Operand::Literal(Value::F64(2.0)));
+        assert_eq!(value(1), Operand::Literal(Value::String("spur, 20 deg".to_string())));
+        assert_eq!(asm_formats::runtime::translate_command(&decoded.command).unwrap(), decoded.command.program);
+    }
+}
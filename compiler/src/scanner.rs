@@ -1,9 +1,14 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use chrono;
 use serde::{Serialize, Deserialize};
-use anyhow::Result;
-use crate::cli_dashboard::{DashboardBuilder, DashboardRow, Totals};
+use sha2::{Digest, Sha256};
+use anyhow::{Context, Result};
+use crate::cli_dashboard::{DashboardBuilder, DashboardRow, Section, Totals};
+
+/// Directories never descended into while scanning
+const EXCLUDED_DIRS: [&str; 6] = [".git", "target", "node_modules", "build", "dist", "logs"];
 
 #[derive(Debug, Clone)]
 pub struct Scanner {
@@ -37,6 +42,14 @@ pub struct FileInfo {
     pub derives: usize,
     pub tests: usize,
     pub modified: String,
+    /// Size, modification time and SHA-256 of the content when scanned, used
+    /// by `scan_incremental`; absent in logs written before it existed
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub mtime_ms: i64,
+    #[serde(default)]
+    pub hash: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +67,50 @@ pub struct FileMetrics {
     pub structs: usize,
 }
 
+/// How a file differs from the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// One file that differs from the baseline. Deleted files carry their
+/// baseline entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    pub file: FileInfo,
+}
+
+/// Result of `Scanner::scan_incremental`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementalResult {
+    /// Only the files added, modified or deleted since the baseline, by path
+    pub changes: Vec<FileChange>,
+    /// The full current log; unchanged files keep their baseline metrics
+    pub log: StructureLog,
+    /// Unchanged files whose metrics were taken from the baseline
+    pub reused: usize,
+}
+
+/// Size and modification time of a file on disk
+struct FileStamp {
+    size: u64,
+    mtime_ms: i64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path).with_context(|| format!("Failed to stat {}", path.display()))?;
+        let mtime_ms = metadata
+            .modified()
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).timestamp_millis())
+            .unwrap_or_default();
+        Ok(Self { size: metadata.len(), mtime_ms })
+    }
+}
+
 impl Scanner {
     pub fn new(root_path: impl AsRef<std::path::Path>) -> Self {
         Self {
@@ -62,17 +119,132 @@ impl Scanner {
     }
 
     pub fn scan(&self) -> Result<StructureLog> {
-        // TODO: Implement actual scanning logic
-        // For now, return a valid but empty structure
-        Ok(StructureLog {
+        let files = self
+            .list_files()
+            .into_iter()
+            .map(|(path, rel_path)| self.scan_file(&path, rel_path))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.build_log(files))
+    }
+
+    /// Re-scan against a previous log. Files whose size and mtime match the
+    /// baseline are reused as-is; the rest are hashed, and only a content
+    /// change re-computes metrics. Baseline entries without a hash (older
+    /// logs) count as modified.
+    pub fn scan_incremental(&self, baseline: &StructureLog) -> Result<IncrementalResult> {
+        let mut previous: HashMap<&str, &FileInfo> =
+            baseline.files.iter().map(|f| (f.rel_path.as_str(), f)).collect();
+        let mut changes = Vec::new();
+        let mut files = Vec::new();
+        let mut reused = 0;
+
+        for (path, rel_path) in self.list_files() {
+            let Some(old) = previous.remove(rel_path.as_str()) else {
+                let file = self.scan_file(&path, rel_path)?;
+                changes.push(FileChange { kind: ChangeKind::Added, file: file.clone() });
+                files.push(file);
+                continue;
+            };
+
+            let stamp = FileStamp::of(&path)?;
+            let unchanged = !old.hash.is_empty()
+                && ((stamp.size == old.size && stamp.mtime_ms == old.mtime_ms) || hash_file(&path)? == old.hash);
+            if unchanged {
+                reused += 1;
+                files.push(FileInfo { size: stamp.size, mtime_ms: stamp.mtime_ms, ..old.clone() });
+            } else {
+                let file = self.scan_file(&path, rel_path)?;
+                changes.push(FileChange { kind: ChangeKind::Modified, file: file.clone() });
+                files.push(file);
+            }
+        }
+
+        changes.extend(
+            previous
+                .into_values()
+                .map(|file| FileChange { kind: ChangeKind::Deleted, file: file.clone() }),
+        );
+        changes.sort_by(|a, b| a.file.rel_path.cmp(&b.file.rel_path));
+
+        Ok(IncrementalResult { changes, log: self.build_log(files), reused })
+    }
+
+    /// Every file under the root outside EXCLUDED_DIRS, with its `/`-separated
+    /// relative path, sorted by that path
+    fn list_files(&self) -> Vec<(PathBuf, String)> {
+        let root = Path::new(&self.root_path);
+        let mut files: Vec<(PathBuf, String)> = walkdir::WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !EXCLUDED_DIRS.contains(&e.file_name().to_string_lossy().as_ref()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| {
+                let rel = e.path().strip_prefix(root).unwrap_or(e.path()).to_string_lossy().replace('\\', "/");
+                (e.into_path(), rel)
+            })
+            .collect();
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        files
+    }
+
+    /// Metrics for one file; non-UTF-8 files get zero counts
+    fn scan_file(&self, path: &Path, rel_path: String) -> Result<FileInfo> {
+        let stamp = FileStamp::of(path)?;
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let content = std::str::from_utf8(&bytes).unwrap_or_default();
+        let lines: Vec<&str> = content.lines().collect();
+        let count = |pred: &dyn Fn(&str) -> bool| lines.iter().filter(|l| pred(l.trim_start())).count();
+
+        let basename = rel_path.rsplit('/').next().unwrap_or_default();
+        Ok(FileInfo {
+            n: 0,
+            alias: DashboardBuilder::short_alias(basename),
+            loc: lines.len(),
+            fn_count: count(&|l| l.starts_with("fn ") || l.starts_with("pub fn ")),
+            pub_fn_count: count(&|l| l.starts_with("pub fn ")),
+            unsafe_fn_count: count(&|l| l.contains("unsafe fn")),
+            imports: count(&|l| l.starts_with("use ")),
+            logging: LoggingMetrics {
+                info: content.matches("info!").count(),
+                warn: content.matches("warn!").count(),
+                error: content.matches("error!").count(),
+                println: content.matches("println!").count(),
+            },
+            structs: count(&|l| l.contains("struct ")),
+            enums: count(&|l| l.contains("enum ")),
+            derives: content.matches("#[derive").count(),
+            tests: count(&|l| l.contains("#[test]")),
+            modified: chrono::DateTime::from_timestamp_millis(stamp.mtime_ms)
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            size: stamp.size,
+            mtime_ms: stamp.mtime_ms,
+            hash: format!("{:x}", Sha256::digest(&bytes)),
+            rel_path,
+        })
+    }
+
+    /// Number the files in order and total them up
+    fn build_log(&self, mut files: Vec<FileInfo>) -> StructureLog {
+        for (i, file) in files.iter_mut().enumerate() {
+            file.n = i + 1;
+        }
+        let file_details = files
+            .iter()
+            .map(|f| (f.rel_path.clone(), FileMetrics { lines: f.loc, functions: f.fn_count, structs: f.structs }))
+            .collect();
+        let total_loc = files.iter().map(|f| f.loc).sum();
+
+        StructureLog {
             root: self.root_path.clone(),
             timestamp: chrono::Utc::now().to_rfc3339(),
-            total_files: 0,
-            total_lines: 0,
-            total_loc: 0,
-            files: Vec::new(),
-            file_details: HashMap::new(),
-        })
+            total_files: files.len(),
+            total_lines: total_loc,
+            total_loc,
+            files,
+            file_details,
+        }
     }
 
     /// Scan and emit dashboard format (JSONL + plain text)
@@ -96,7 +268,7 @@ impl Scanner {
             let row = builder.build_row(
                 rel_path,
                 None,
-                Some("Structure".to_string()),
+                Some(Section::Structure),
                 totals
             );
 
@@ -107,7 +279,56 @@ impl Scanner {
     }
 }
 
+fn hash_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
 pub fn scan_manifest(path: &str) -> Result<()> {
     println!("Scanning manifest at {}", path);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_incremental_returns_only_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(dir.path().join("src/util.rs"), "fn b() {}\n").unwrap();
+        fs::write(dir.path().join("README.md"), "# demo\n").unwrap();
+        fs::write(dir.path().join("target/out.rs"), "fn ignored() {}\n").unwrap();
+
+        let scanner = Scanner::new(dir.path());
+        let baseline = scanner.scan().unwrap();
+        let paths: Vec<&str> = baseline.files.iter().map(|f| f.rel_path.as_str()).collect();
+        assert_eq!(paths, vec!["README.md", "src/lib.rs", "src/util.rs"]);
+
+        // Nothing touched: everything is reused
+        let unchanged = scanner.scan_incremental(&baseline).unwrap();
+        assert!(unchanged.changes.is_empty());
+        assert_eq!(unchanged.reused, 3);
+
+        fs::write(dir.path().join("src/util.rs"), "fn b() {}\nfn c() {}\n").unwrap();
+        fs::write(dir.path().join("src/new.rs"), "use std::fmt;\n").unwrap();
+
+        let result = scanner.scan_incremental(&baseline).unwrap();
+        let changes: Vec<(ChangeKind, &str)> =
+            result.changes.iter().map(|c| (c.kind, c.file.rel_path.as_str())).collect();
+        assert_eq!(changes, vec![(ChangeKind::Added, "src/new.rs"), (ChangeKind::Modified, "src/util.rs")]);
+        assert_eq!(result.changes[1].file.fn_count, 2);
+        assert_eq!(result.reused, 2);
+        assert_eq!(result.log.total_files, 4);
+
+        fs::remove_file(dir.path().join("README.md")).unwrap();
+        let result = scanner.scan_incremental(&result.log).unwrap();
+        let changes: Vec<(ChangeKind, &str)> =
+            result.changes.iter().map(|c| (c.kind, c.file.rel_path.as_str())).collect();
+        assert_eq!(changes, vec![(ChangeKind::Deleted, "README.md")]);
+        assert_eq!(result.log.total_files, 3);
+    }
+}
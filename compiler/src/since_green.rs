@@ -0,0 +1,484 @@
+//! "What changed since last green" report
+//!
+//! Reads a history directory laid out as:
+//!   compile_reports/*.json   CompileReport per compile
+//!   snapshots/*.jsonl        dashboard snapshots (`scan_dashboard_<YYYYmmddTHHMMSS>.jsonl`)
+//!   lineage/                 LineageManager root
+//!
+//! Any missing or unreadable piece turns into an explicit "unknown" section
+//! instead of failing the whole report.
+
+use crate::cli_dashboard::{diff_dashboards, parse_dashboard_jsonl, DashboardDiff};
+use crate::diagnostics::CompileReport;
+use crate::module_map::ModuleMapper;
+use asm_formats::lineage::LineageManager;
+use asm_formats::schemas::JSONLineage;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+pub const COMPILE_REPORTS_DIR: &str = "compile_reports";
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+pub const LINEAGE_DIR: &str = "lineage";
+
+/// A report section that may be unavailable
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum Section<T> {
+    Known(T),
+    Unknown(String),
+}
+
+impl<T> Section<T> {
+    pub fn known(&self) -> Option<&T> {
+        match self {
+            Section::Known(value) => Some(value),
+            Section::Unknown(_) => None,
+        }
+    }
+}
+
+/// Green baseline and new diagnostics for one manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestStatus {
+    pub manifest: String,
+    pub last_green: Option<String>,
+    pub reports_since_green: usize,
+    /// Codes reported after the last green compile that it did not have
+    pub new_codes: Section<Vec<String>>,
+}
+
+/// Dashboard diff between the snapshot nearest the green baseline and the latest one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotChanges {
+    pub baseline: String,
+    pub current: String,
+    pub diff: DashboardDiff,
+}
+
+/// A lineage entry since the baseline that touched changed files
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunTouch {
+    pub run_id: String,
+    pub seq: u64,
+    pub timestamp: String,
+    pub summary: String,
+    pub files: Vec<String>,
+    /// Overlay field behind a failed overlay-driven run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SinceGreenReport {
+    /// Earliest last-green time across manifests
+    pub green_at: Option<String>,
+    pub manifests: Section<Vec<ManifestStatus>>,
+    pub files: Section<SnapshotChanges>,
+    pub runs: Section<Vec<RunTouch>>,
+}
+
+/// Build the report for a history directory
+pub fn build_report(history: &Path, mapper: &ModuleMapper) -> SinceGreenReport {
+    let manifests = manifest_statuses(&history.join(COMPILE_REPORTS_DIR));
+
+    let green_at = manifests
+        .known()
+        .and_then(|statuses| statuses.iter().filter_map(|s| s.last_green.as_deref().and_then(parse_time)).min());
+
+    let (files, runs) = match green_at {
+        None => {
+            let reason = "no green compile report found".to_string();
+            (Section::Unknown(reason.clone()), Section::Unknown(reason))
+        }
+        Some(green_at) => {
+            let files = snapshot_changes(&history.join(SNAPSHOTS_DIR), green_at);
+            let runs = match files.known() {
+                Some(changes) => runs_touching(&history.join(LINEAGE_DIR), green_at, &changes.diff, mapper),
+                None => Section::Unknown("changed files unknown".to_string()),
+            };
+            (files, runs)
+        }
+    };
+
+    SinceGreenReport {
+        green_at: green_at.map(|t| t.to_rfc3339()),
+        manifests,
+        files,
+        runs,
+    }
+}
+
+fn parse_time(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// `*.<extension>` files in a directory, sorted by name
+fn files_with_extension(dir: &Path, extension: &str) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn manifest_statuses(dir: &Path) -> Section<Vec<ManifestStatus>> {
+    let files = match files_with_extension(dir, "json") {
+        Ok(files) => files,
+        Err(e) => return Section::Unknown(format!("cannot read {}: {}", dir.display(), e)),
+    };
+
+    let mut by_manifest: BTreeMap<String, Vec<(DateTime<Utc>, CompileReport)>> = BTreeMap::new();
+    for path in files {
+        let report = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<CompileReport>(&json).ok());
+        match report.and_then(|r| parse_time(&r.timestamp).map(|t| (t, r))) {
+            Some((time, report)) => by_manifest.entry(report.manifest.clone()).or_default().push((time, report)),
+            None => log::warn!("Skipping unreadable compile report {}", path.display()),
+        }
+    }
+    if by_manifest.is_empty() {
+        return Section::Unknown(format!("no compile reports in {}", dir.display()));
+    }
+
+    let statuses = by_manifest
+        .into_iter()
+        .map(|(manifest, mut reports)| {
+            reports.sort_by_key(|(time, _)| *time);
+            let green = reports.iter().rposition(|(_, r)| r.is_green());
+
+            let (last_green, reports_since_green, new_codes) = match green {
+                Some(index) => {
+                    let baseline: BTreeSet<&String> = reports[index].1.codes.iter().collect();
+                    let new_codes: BTreeSet<String> = reports[index + 1..]
+                        .iter()
+                        .flat_map(|(_, r)| &r.codes)
+                        .filter(|code| !baseline.contains(code))
+                        .cloned()
+                        .collect();
+                    (
+                        Some(reports[index].1.timestamp.clone()),
+                        reports.len() - index - 1,
+                        Section::Known(new_codes.into_iter().collect()),
+                    )
+                }
+                None => (None, reports.len(), Section::Unknown("never compiled green".to_string())),
+            };
+
+            ManifestStatus { manifest, last_green, reports_since_green, new_codes }
+        })
+        .collect();
+
+    Section::Known(statuses)
+}
+
+/// Snapshot time from `scan_dashboard_<YYYYmmddTHHMMSS>.jsonl`, else the first row's timestamp
+fn snapshot_time(path: &Path, content: &str) -> Option<DateTime<Utc>> {
+    let from_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.rsplit('_').next())
+        .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S").ok())
+        .map(|t| t.and_utc());
+
+    from_name.or_else(|| {
+        parse_dashboard_jsonl(content)
+            .ok()
+            .and_then(|rows| rows.first().and_then(|row| parse_time(&row.timestamp)))
+    })
+}
+
+fn snapshot_changes(dir: &Path, green_at: DateTime<Utc>) -> Section<SnapshotChanges> {
+    let files = match files_with_extension(dir, "jsonl") {
+        Ok(files) => files,
+        Err(e) => return Section::Unknown(format!("cannot read {}: {}", dir.display(), e)),
+    };
+
+    let mut snapshots: Vec<(DateTime<Utc>, PathBuf, String)> = files
+        .into_iter()
+        .filter_map(|path| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            let time = snapshot_time(&path, &content)?;
+            Some((time, path, content))
+        })
+        .collect();
+    if snapshots.len() < 2 {
+        return Section::Unknown(format!("need at least two dashboard snapshots in {}", dir.display()));
+    }
+    snapshots.sort_by_key(|(time, _, _)| *time);
+
+    let (_, current_path, current) = snapshots.last().unwrap();
+    let (_, baseline_path, baseline) = snapshots
+        .iter()
+        .min_by_key(|(time, _, _)| (*time - green_at).num_seconds().abs())
+        .unwrap();
+
+    let rows = |content: &str, path: &Path| {
+        parse_dashboard_jsonl(content).map_err(|e| format!("cannot parse {}: {}", path.display(), e))
+    };
+    match (rows(baseline, baseline_path), rows(current, current_path)) {
+        (Ok(old), Ok(new)) => Section::Known(SnapshotChanges {
+            baseline: baseline_path.display().to_string(),
+            current: current_path.display().to_string(),
+            diff: diff_dashboards(&old, &new),
+        }),
+        (Err(reason), _) | (_, Err(reason)) => Section::Unknown(reason),
+    }
+}
+
+/// Changed files an entry mentions, by path or module, in its impact or command
+fn touched_files(entry: &JSONLineage, paths: &[&str], mapper: &ModuleMapper) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| {
+            let module = mapper.module_for(path);
+            entry.impact.modules_affected.iter().any(|m| m == *path || Some(m) == module.as_ref())
+                || entry.command_executed.contains(*path)
+        })
+        .map(|path| path.to_string())
+        .collect()
+}
+
+fn runs_touching(
+    dir: &Path,
+    green_at: DateTime<Utc>,
+    diff: &DashboardDiff,
+    mapper: &ModuleMapper,
+) -> Section<Vec<RunTouch>> {
+    if !dir.exists() {
+        return Section::Unknown(format!("no lineage directory at {}", dir.display()));
+    }
+
+    // Reports run from the project root, like the history paths they read
+    let manager = LineageManager::new(dir).with_project_config(".");
+    let runs = match manager.list_runs() {
+        Ok(runs) => runs,
+        Err(e) => return Section::Unknown(format!("cannot list lineage runs: {}", e)),
+    };
+
+    let paths = diff.paths();
+    let mut touches = Vec::new();
+    for (run_id, ..) in runs {
+        let entries = match manager.iter_run(run_id) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Skipping lineage run {}: {}", run_id, e);
+                continue;
+            }
+        };
+        for entry in entries.filter_map(|e| e.ok()).filter(|e| e.timestamp >= green_at) {
+            let files = touched_files(&entry, &paths, mapper);
+            if !files.is_empty() {
+                touches.push(RunTouch {
+                    run_id: entry.run_id.to_string(),
+                    seq: entry.seq.0,
+                    timestamp: entry.timestamp.to_rfc3339(),
+                    summary: entry.summary.clone(),
+                    files,
+                    origin: entry.origin.as_ref().map(|o| o.to_string()),
+                });
+            }
+        }
+    }
+    touches.sort_by(|a, b| (&a.timestamp, &a.run_id, a.seq).cmp(&(&b.timestamp, &b.run_id, b.seq)));
+
+    Section::Known(touches)
+}
+
+impl SinceGreenReport {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Changes since last green\n\n");
+        out.push_str(&format!("Baseline: {}\n", self.green_at.as_deref().unwrap_or("unknown")));
+
+        out.push_str("\n## Diagnostics\n\n");
+        match &self.manifests {
+            Section::Unknown(reason) => out.push_str(&format!("Unknown: {}\n", reason)),
+            Section::Known(statuses) => {
+                for status in statuses {
+                    let codes = match &status.new_codes {
+                        Section::Known(codes) if codes.is_empty() => "no new codes".to_string(),
+                        Section::Known(codes) => format!("new: {}", codes.join(", ")),
+                        Section::Unknown(reason) => format!("unknown ({})", reason),
+                    };
+                    out.push_str(&format!(
+                        "- `{}` (last green: {}, {} report(s) since): {}\n",
+                        status.manifest,
+                        status.last_green.as_deref().unwrap_or("never"),
+                        status.reports_since_green,
+                        codes
+                    ));
+                }
+            }
+        }
+
+        out.push_str("\n## Changed files\n\n");
+        match &self.files {
+            Section::Unknown(reason) => out.push_str(&format!("Unknown: {}\n", reason)),
+            Section::Known(changes) if changes.diff.is_empty() => out.push_str("No changes\n"),
+            Section::Known(changes) => {
+                for (label, paths) in [("added", &changes.diff.added), ("removed", &changes.diff.removed), ("changed", &changes.diff.changed)] {
+                    for path in paths {
+                        out.push_str(&format!("- {} `{}`\n", label, path));
+                    }
+                }
+            }
+        }
+
+        out.push_str("\n## Runs\n\n");
+        match &self.runs {
+            Section::Unknown(reason) => out.push_str(&format!("Unknown: {}\n", reason)),
+            Section::Known(runs) if runs.is_empty() => out.push_str("No runs touched the changed files\n"),
+            Section::Known(runs) => {
+                for run in runs {
+                    out.push_str(&format!(
+                        "- {} #{} ({}): {} [{}]\n",
+                        run.run_id,
+                        run.seq,
+                        run.timestamp,
+                        run.summary,
+                        run.files.join(", ")
+                    ));
+                    if let Some(origin) = &run.origin {
+                        out.push_str(&format!("  - {}\n", origin));
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_dashboard::{DashboardBuilder, Totals};
+    use asm_formats::schemas::{ExecutionOutcome, FieldOrigin, Provenance};
+    use asm_formats::{Actor, Impact, RunId, Seq, ToolVersions};
+
+    fn write_report(dir: &Path, name: &str, timestamp: &str, errors: usize, codes: &[&str]) {
+        let report = CompileReport {
+            manifest: "oasm.yaml".to_string(),
+            timestamp: timestamp.to_string(),
+            errors,
+            warnings: 0,
+            codes: codes.iter().map(|c| c.to_string()).collect(),
+        };
+        std::fs::write(dir.join(name), serde_json::to_string(&report).unwrap()).unwrap();
+    }
+
+    fn write_snapshot(dir: &Path, name: &str, rows: &[(&str, Totals)]) {
+        let mut builder = DashboardBuilder::new(rows.len());
+        let content: String = rows
+            .iter()
+            .map(|(path, totals)| builder.build_row(path, None, None, totals.clone()).to_jsonl().unwrap() + "\n")
+            .collect();
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+
+    fn fabricate_history(root: &Path) {
+        let reports = root.join(COMPILE_REPORTS_DIR);
+        let snapshots = root.join(SNAPSHOTS_DIR);
+        std::fs::create_dir_all(&reports).unwrap();
+        std::fs::create_dir_all(&snapshots).unwrap();
+
+        write_report(&reports, "001.json", "2025-01-01T10:00:00+00:00", 0, &["W0001"]);
+        write_report(&reports, "002.json", "2025-01-03T10:00:00+00:00", 2, &["W0001", "E0200"]);
+
+        write_snapshot(&snapshots, "scan_dashboard_20250101T100500.jsonl", &[
+            ("compiler/src/scanner.rs", Totals::zero()),
+            ("compiler/src/audit.rs", Totals::zero()),
+        ]);
+        write_snapshot(&snapshots, "scan_dashboard_20250103T100000.jsonl", &[
+            ("compiler/src/scanner.rs", Totals::new(1, 0, 0)),
+            ("compiler/src/storage.rs", Totals::zero()),
+        ]);
+    }
+
+    fn provenance() -> Provenance {
+        Provenance {
+            tool_versions: ToolVersions::current(),
+            config_hash: String::new(),
+            template_id: None,
+            parent_run_id: None,
+            lineage_chain: Vec::new(),
+            confidence: None,
+            git_dirty: None,
+            authored_by: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_report_joins_snapshot_diff_lineage_and_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        fabricate_history(dir.path());
+
+        let manager = LineageManager::new(dir.path().join(LINEAGE_DIR));
+        let run = RunId::new();
+        let impact = |modules: &[&str]| Impact {
+            modules_affected: modules.iter().map(|m| m.to_string()).collect(),
+            ..Impact::default()
+        };
+        let mut scanner_run = manager
+            .record(run, Seq(1), Actor::System, "Refactor scanner", "", "", ExecutionOutcome::Success, provenance(), impact(&["compiler::scanner"]))
+            .unwrap();
+        scanner_run.origin = Some(FieldOrigin {
+            field: "command.parameters[0]".to_string(),
+            annotation: Some("scanner depth".to_string()),
+        });
+        manager.save(&scanner_run).unwrap();
+        manager
+            .record(run, Seq(2), Actor::System, "Touch docs", "", "", ExecutionOutcome::Success, provenance(), impact(&["docs"]))
+            .unwrap();
+
+        let report = build_report(dir.path(), &ModuleMapper::new());
+
+        assert_eq!(report.green_at.as_deref(), Some("2025-01-01T10:00:00+00:00"));
+        let statuses = report.manifests.known().unwrap();
+        assert_eq!(statuses[0].reports_since_green, 1);
+        assert_eq!(statuses[0].new_codes, Section::Known(vec!["E0200".to_string()]));
+
+        let changes = report.files.known().unwrap();
+        assert!(changes.baseline.ends_with("scan_dashboard_20250101T100500.jsonl"));
+        assert_eq!(changes.diff.added, vec!["compiler/src/storage.rs"]);
+        assert_eq!(changes.diff.removed, vec!["compiler/src/audit.rs"]);
+        assert_eq!(changes.diff.changed, vec!["compiler/src/scanner.rs"]);
+
+        let runs = report.runs.known().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].summary, "Refactor scanner");
+        assert_eq!(runs[0].files, vec!["compiler/src/scanner.rs"]);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("new: E0200"));
+        assert!(markdown.contains("- changed `compiler/src/scanner.rs`"));
+        assert!(markdown.contains("originating overlay field: command.parameters[0] (annotated: 'scanner depth')"));
+        assert!(report.to_json().unwrap().contains("\"status\": \"known\""));
+    }
+
+    #[test]
+    fn test_missing_history_degrades_to_unknown_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        fabricate_history(dir.path());
+        std::fs::remove_dir_all(dir.path().join(SNAPSHOTS_DIR)).unwrap();
+
+        let report = build_report(dir.path(), &ModuleMapper::new());
+        assert!(report.manifests.known().is_some());
+        assert!(matches!(report.files, Section::Unknown(_)));
+        assert_eq!(report.runs, Section::Unknown("changed files unknown".to_string()));
+
+        let empty = tempfile::tempdir().unwrap();
+        let report = build_report(empty.path(), &ModuleMapper::new());
+        assert_eq!(report.green_at, None);
+        assert!(matches!(report.manifests, Section::Unknown(_)));
+        assert!(report.to_markdown().contains("Unknown: no green compile report found"));
+    }
+}
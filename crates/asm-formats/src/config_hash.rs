@@ -0,0 +1,222 @@
+//! Configuration Hashing
+//!
+//! `config_hash` in execution metadata and lineage provenance identifies the
+//! configuration a run executed under: `oasm.config.yaml` plus any session
+//! overrides. The effective configuration is hashed as canonical CBOR (map
+//! keys in canonical order, shortest encodings), so the digest only changes
+//! when a value does, never because keys were reordered or reformatted.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt::Display;
+use std::path::Path;
+
+/// The project configuration file, at the project root
+pub const CONFIG_FILE: &str = "oasm.config.yaml";
+
+/// Builds the effective configuration and hashes it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigHasher {
+    config: Value,
+}
+
+impl ConfigHasher {
+    /// Empty configuration (no config file)
+    pub fn new() -> Self {
+        Self { config: Value::Object(Map::new()) }
+    }
+
+    /// Configuration from YAML text (an empty document is an empty config)
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let config: Value = serde_yaml::from_str(yaml).context("Invalid configuration YAML")?;
+        Ok(Self { config: if config.is_null() { Value::Object(Map::new()) } else { config } })
+    }
+
+    /// Configuration from a file such as `oasm.config.yaml`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let yaml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read configuration {}", path.display()))?;
+        Self::from_yaml(&yaml).with_context(|| format!("In {}", path.display()))
+    }
+
+    /// Configuration of the project at `root`: its `oasm.config.yaml`, or
+    /// an empty configuration when it has none
+    pub fn for_project(root: impl AsRef<Path>) -> Result<Self> {
+        let path = root.as_ref().join(CONFIG_FILE);
+        if path.is_file() {
+            Self::from_file(path)
+        } else {
+            Ok(Self::new())
+        }
+    }
+
+    /// Apply a session override; `key` is a dotted path (`scanner.arms`)
+    /// and intermediate maps are created as needed
+    pub fn with_override(mut self, key: &str, value: impl Serialize) -> Result<Self> {
+        let value = serde_json::to_value(value)?;
+        let mut node = &mut self.config;
+        for segment in key.split('.') {
+            if !node.is_object() {
+                bail!("Cannot override '{}': '{}' is not a map", key, segment);
+            }
+            node = node
+                .as_object_mut()
+                .expect("checked above")
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        *node = value;
+        Ok(self)
+    }
+
+    /// The configuration after overrides
+    pub fn effective(&self) -> &Value {
+        &self.config
+    }
+
+    /// SHA-256 hex digest of the canonical effective configuration
+    pub fn hash(&self) -> String {
+        Self::hash_of(&self.config).expect("JSON values always encode as CBOR")
+    }
+
+    /// Hash any serializable configuration the same way
+    pub fn hash_of(config: &impl Serialize) -> Result<String> {
+        Ok(crate::lineage::sha256_hex(&canonical_cbor(config)?))
+    }
+}
+
+impl Default for ConfigHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SHA-256 hex digest of the canonical form of any serializable
+/// configuration: equal configurations hash the same on every machine,
+/// whatever order their maps were built in
+pub fn compute_config_hash(config: &impl Serialize) -> Result<String> {
+    ConfigHasher::hash_of(config)
+}
+
+/// Hash of the project configuration at `root`, for tools that record or
+/// read lineage there. A configuration that cannot be read is logged and
+/// gives no hash, leaving `ConfigHashPolicy` to decide.
+pub fn project_config_hash(root: impl AsRef<Path>) -> Option<String> {
+    match ConfigHasher::for_project(root) {
+        Ok(hasher) => Some(hasher.hash()),
+        Err(e) => {
+            log::warn!("Cannot hash project configuration: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Canonical CBOR encoding of `config`. Every map becomes a `serde_cbor`
+/// value map, which orders keys the canonical way (RFC 7049 §3.9: shorter
+/// keys first, then bytewise), and the encoder always picks the shortest
+/// form for integers, floats and lengths.
+fn canonical_cbor(config: &impl Serialize) -> Result<Vec<u8>> {
+    let value = serde_cbor::value::to_value(config).context("Configuration cannot be encoded as CBOR")?;
+    Ok(serde_cbor::to_vec(&value)?)
+}
+
+/// What to do when metadata or provenance carries no `config_hash`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigHashPolicy {
+    /// Accept it silently
+    Allow,
+    /// Accept it, logging a warning
+    #[default]
+    Warn,
+    /// Reject it
+    Require,
+}
+
+impl ConfigHashPolicy {
+    /// Check `hash` for `what` (e.g. "lineage entry <id>")
+    pub fn check(self, hash: &str, what: impl Display) -> Result<()> {
+        if !hash.is_empty() {
+            return Ok(());
+        }
+        match self {
+            ConfigHashPolicy::Allow => Ok(()),
+            ConfigHashPolicy::Warn => {
+                log::warn!("{} has no config_hash", what);
+                Ok(())
+            }
+            ConfigHashPolicy::Require => bail!("{} has no config_hash", what),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_ignores_key_order() -> Result<()> {
+        let a = ConfigHasher::from_yaml("scanner:\n  arms: [lint, test]\n  depth: 3\nexclude:\n  - target\n")?;
+        let b = ConfigHasher::from_yaml("exclude: [target]\nscanner: {depth: 3, arms: [lint, test]}\n")?;
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.hash().len(), 64);
+
+        let changed = ConfigHasher::from_yaml("scanner:\n  arms: [lint, test]\n  depth: 4\nexclude:\n  - target\n")?;
+        assert_ne!(a.hash(), changed.hash());
+        // List order is meaningful
+        let reordered = ConfigHasher::from_yaml("scanner:\n  arms: [test, lint]\n  depth: 3\nexclude:\n  - target\n")?;
+        assert_ne!(a.hash(), reordered.hash());
+
+        assert_eq!(ConfigHasher::from_yaml("")?.hash(), ConfigHasher::new().hash());
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical_cbor_orders_keys() -> Result<()> {
+        let config = ConfigHasher::from_yaml("bb: 1\na: [1.5]\n")?;
+        // {"a": [1.5], "bb": 1}: the shorter key first, 1.5 as a half float
+        let expected = [0xa2, 0x61, b'a', 0x81, 0xf9, 0x3e, 0x00, 0x62, b'b', b'b', 0x01];
+        assert_eq!(canonical_cbor(config.effective())?, expected);
+        assert_eq!(config.hash(), crate::lineage::sha256_hex(&expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overrides_change_hash() -> Result<()> {
+        let base = ConfigHasher::from_yaml("scanner:\n  depth: 3\n")?;
+        let overridden = base.clone().with_override("scanner.depth", 5)?;
+        assert_eq!(overridden.effective()["scanner"]["depth"], 5);
+        assert_ne!(base.hash(), overridden.hash());
+
+        // Overriding with the same value is the same configuration
+        assert_eq!(base.hash(), base.clone().with_override("scanner.depth", 3)?.hash());
+        assert_eq!(
+            ConfigHasher::new().with_override("session.user", "alice")?.hash(),
+            ConfigHasher::from_yaml("session: {user: alice}")?.hash()
+        );
+        assert!(base.with_override("scanner.depth.max", 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert_eq!(project_config_hash(dir.path()), Some(ConfigHasher::new().hash()));
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "scanner: {depth: 3}\n")?;
+        assert_eq!(project_config_hash(dir.path()), Some(ConfigHasher::from_yaml("scanner:\n  depth: 3\n")?.hash()));
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "scanner: [unclosed\n")?;
+        assert!(ConfigHasher::for_project(dir.path()).is_err());
+        assert_eq!(project_config_hash(dir.path()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy() {
+        assert!(ConfigHashPolicy::Require.check("", "entry").is_err());
+        assert!(ConfigHashPolicy::Require.check("abc", "entry").is_ok());
+        assert!(ConfigHashPolicy::Allow.check("", "entry").is_ok());
+    }
+}
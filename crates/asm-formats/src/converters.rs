@@ -25,6 +25,8 @@
 //!    - Reference CBOR object by object_id (not duplicate)
 //!    - Reference HDF5 template by template_id (not duplicate)
 //!
+//!    `audit_no_duplication` checks rules 1 and 5 for a converted CBOR object.
+//!
 //! 5. FORBIDDEN CONVERSIONS:
 //!    - CBOR → HDF5 (HDF5 is immutable canonical, never generated from runtime)
 //!    - JSON → HDF5 (JSON is audit trail, not source of truth)
@@ -35,16 +37,26 @@ use crate::schemas::{
     AutoPopulatedFields, Annotation, CommandBlock,
 };
 use crate::templates::TemplateStore;
-use crate::runtime::RuntimeObjectManager;
-use crate::lineage::LineageManager;
+use crate::runtime::{ExecutionResult, RuntimeObjectManager};
+use crate::lineage::{DiffManager, LineageManager};
+use crate::config_hash::ConfigHashPolicy;
+use crate::impact::ImpactCalculator;
 use crate::{RunId, Seq, Actor};
-use anyhow::{Result, Context};
+use anyhow::{bail, Result};
+
+// Test support only: built for this crate's tests, or for other crates'
+// tests through the `golden` feature
+#[cfg(any(test, feature = "golden"))]
+pub mod golden;
 
 /// Converter between data formats
 pub struct FormatConverter {
     template_store: TemplateStore,
     runtime_manager: RuntimeObjectManager,
     lineage_manager: LineageManager,
+    /// Stamped on metadata the converter creates (see `config_hash`)
+    config_hash: Option<String>,
+    config_hash_policy: ConfigHashPolicy,
 }
 
 impl FormatConverter {
@@ -57,9 +69,49 @@ impl FormatConverter {
             template_store,
             runtime_manager,
             lineage_manager,
+            config_hash: None,
+            config_hash_policy: ConfigHashPolicy::default(),
         }
     }
 
+    /// Hash of the effective configuration, filled into generated metadata
+    /// and into overlays that carry none
+    pub fn with_config_hash(mut self, config_hash: impl Into<String>) -> Self {
+        self.config_hash = Some(config_hash.into());
+        self
+    }
+
+    /// `with_config_hash` with the canonical hash of `config`, the effective
+    /// configuration (see `config_hash::compute_config_hash`)
+    pub fn with_config(self, config: &impl serde::Serialize) -> Result<Self> {
+        Ok(self.with_config_hash(crate::config_hash::compute_config_hash(config)?))
+    }
+
+    /// Metadata for objects the converter generates, carrying its config hash
+    fn new_metadata(&self, actor: Actor) -> crate::ExecutionMetadata {
+        crate::ExecutionMetadata {
+            config_hash: self.config_hash.clone().unwrap_or_default(),
+            ..crate::ExecutionMetadata::new(actor)
+        }
+    }
+
+    /// Warn about (default), allow or reject runtime objects produced
+    /// without a config hash
+    pub fn with_config_hash_policy(mut self, policy: ConfigHashPolicy) -> Self {
+        self.config_hash_policy = policy;
+        self
+    }
+
+    /// Fill an empty `config_hash` from the converter's, then apply the policy
+    fn stamp_config_hash(&self, metadata: &mut crate::ExecutionMetadata, what: &str) -> Result<()> {
+        if metadata.config_hash.is_empty() {
+            if let Some(hash) = &self.config_hash {
+                metadata.config_hash = hash.clone();
+            }
+        }
+        self.config_hash_policy.check(&metadata.config_hash, what)
+    }
+
     /// HDF5 → CBOR (extract template, generate runtime object)
     ///
     /// CRITICAL: Deep artifacts (CFG/DFG, datasets) remain in HDF5.
@@ -78,10 +130,13 @@ impl FormatConverter {
         let command = self.extract_command_from_template(&template)?;
 
         // Create CBOR runtime object
-        let obj = self.runtime_manager.create_object(run_id, seq, actor, command);
+        let mut obj = self.runtime_manager.create_object(run_id, seq, actor, command);
+        obj.source_template_id = Some(template.template_id.clone());
+        self.stamp_config_hash(&mut obj.metadata, &format!("CBOR object {}", obj.object_id))?;
 
         // IMPORTANT: obj does NOT contain CFG/DFG/datasets
         // Those remain in HDF5, referenced by template.artifacts[].data_path
+        audit_no_duplication(&template, &obj)?;
 
         Ok(obj)
     }
@@ -94,7 +149,7 @@ impl FormatConverter {
 
         // Generate auto-populated fields
         let auto_fields = AutoPopulatedFields {
-            run_id: RunId::new(),
+            run_id: RunId::generate(),
             seq: Seq::zero(),
             timestamp: chrono::Utc::now(),
             actor: Actor::System,
@@ -122,16 +177,18 @@ impl FormatConverter {
             });
         }
 
-        let overlay = YAMLOverlay {
+        let mut overlay = YAMLOverlay {
             comment: Some(format!(
                 "Auto-generated overlay for template: {}\nDeep artifacts in HDF5, not embedded here.",
                 template.template_id
             )),
-            metadata: crate::ExecutionMetadata::new(Actor::System),
+            metadata: self.new_metadata(Actor::System),
             command: self.extract_command_from_template(&template)?,
             auto_populated: auto_fields,
             annotations,
+            source_template_id: Some(template.template_id.clone()),
         };
+        self.stamp_config_hash(&mut overlay.metadata, &format!("Overlay for template {}", template.template_id))?;
 
         Ok(overlay)
     }
@@ -140,68 +197,51 @@ impl FormatConverter {
     ///
     /// CRITICAL: Validate YAML, strip comments, produce compact binary.
     pub fn yaml_to_cbor(&self, yaml_overlay: &YAMLOverlay) -> Result<CBORRuntimeObject> {
-        // Validate YAML structure
-        self.validate_yaml_overlay(yaml_overlay)?;
-
-        // Create CBOR object (comments stripped, annotations logged separately)
-        let obj = CBORRuntimeObject {
-            object_id: format!(
-                "{}_{}",
-                yaml_overlay.auto_populated.run_id,
-                yaml_overlay.auto_populated.seq.0
-            ),
-            metadata: yaml_overlay.metadata.clone(),
-            command: yaml_overlay.command.clone(),
-            auto_fields: yaml_overlay.auto_populated.clone(),
-            decisions: Vec::new(),
-        };
-
+        let mut obj = overlay_to_cbor(yaml_overlay)?;
+        self.stamp_config_hash(&mut obj.metadata, &format!("CBOR object {}", obj.object_id))?;
         Ok(obj)
     }
 
-    /// CBOR → JSON Lineage (record execution outcome)
+    /// CBOR → JSON Lineage (entry for the execution outcome; not saved
+    /// until it is passed to `LineageManager::commit`)
     ///
     /// CRITICAL: Reference CBOR object and HDF5 template by ID, don't duplicate.
     pub fn cbor_to_json_lineage(
         &self,
         cbor_obj: &CBORRuntimeObject,
-        outcome: crate::schemas::ExecutionOutcome,
+        result: &ExecutionResult,
         impact: crate::Impact,
     ) -> Result<JSONLineage> {
-        let lineage = self.lineage_manager.record(
+        // Sources, oldest first: template, then the overlay made from it
+        let lineage_chain = [
+            cbor_obj.source_template_id.as_ref().map(|id| format!("template:{}", id)),
+            cbor_obj.source_overlay_id.as_ref().map(|id| format!("overlay:{}", id)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        self.lineage_manager.prepare(
             cbor_obj.auto_fields.run_id,
             cbor_obj.auto_fields.seq,
             cbor_obj.auto_fields.actor.clone(),
             format!("Executed {:?}", cbor_obj.command.block_type),
             "Automated execution", // TODO: extract from CBOR
-            outcome,
+            cbor_obj.command.source(),
+            result.outcome.clone(),
             crate::schemas::Provenance {
                 tool_versions: cbor_obj.metadata.tool_versions.clone(),
                 config_hash: cbor_obj.metadata.config_hash.clone(),
-                template_id: None, // TODO: track template_id in CBOR
+                template_id: cbor_obj.source_template_id.clone(),
                 parent_run_id: None,
-                lineage_chain: vec![],
+                lineage_chain,
                 confidence: cbor_obj.auto_fields.confidence,
+                git_dirty: None,
+                authored_by: result.authored_by.clone(),
+                annotations: Vec::new(),
             },
             impact,
-        )?;
-
-        Ok(lineage)
-    }
-
-    /// Validate YAML overlay structure
-    fn validate_yaml_overlay(&self, overlay: &YAMLOverlay) -> Result<()> {
-        // Check required fields
-        if overlay.auto_populated.run_id.0.is_nil() {
-            anyhow::bail!("Invalid run_id in YAML overlay");
-        }
-
-        // Validate command block
-        if overlay.command.target_files.is_empty() && overlay.command.parameters.is_empty() {
-            anyhow::bail!("YAML overlay has empty command block");
-        }
-
-        Ok(())
+        )
     }
 
     /// Extract command block from HDF5 template (lightweight)
@@ -221,18 +261,159 @@ impl FormatConverter {
             parameters: vec![],
             target_files: vec![],
             rules: vec![],
+            instructions: vec![],
+            program: vec![],
         })
     }
 }
 
+/// Check that no deep artifact of `template` was duplicated into `cbor`:
+/// no string or byte value may contain an artifact's HDF5 `data_path`, or
+/// hash to its checksum (embedded contents). The error lists every
+/// offending artifact.
+pub fn audit_no_duplication(template: &HDF5Template, cbor: &CBORRuntimeObject) -> Result<()> {
+    let value = serde_cbor::value::to_value(cbor)?;
+    let mut leaves = Vec::new();
+    collect_leaves(&value, &mut leaves);
+    let hashes: Vec<String> = leaves.iter().map(|leaf| crate::lineage::sha256_hex(leaf)).collect();
+
+    let mut violations = Vec::new();
+    for artifact in &template.artifacts {
+        let path = artifact.data_path.as_bytes();
+        if !path.is_empty() && leaves.iter().any(|leaf| leaf.windows(path.len()).any(|w| w == path)) {
+            violations.push(format!("'{}' (path {} embedded)", artifact.artifact_id, artifact.data_path));
+        }
+
+        let checksum = artifact.checksum.trim_start_matches("sha256:").to_lowercase();
+        if !checksum.is_empty() && hashes.contains(&checksum) {
+            violations.push(format!("'{}' (contents embedded)", artifact.artifact_id));
+        }
+    }
+
+    if !violations.is_empty() {
+        bail!(
+            "CBOR object {} duplicates deep artifacts of template {}: {}",
+            cbor.object_id,
+            template.template_id,
+            violations.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Every text and byte string in a CBOR value
+fn collect_leaves<'a>(value: &'a serde_cbor::Value, leaves: &mut Vec<&'a [u8]>) {
+    match value {
+        serde_cbor::Value::Text(text) => leaves.push(text.as_bytes()),
+        serde_cbor::Value::Bytes(bytes) => leaves.push(bytes),
+        serde_cbor::Value::Array(items) => items.iter().for_each(|item| collect_leaves(item, leaves)),
+        serde_cbor::Value::Map(map) => {
+            for (key, value) in map {
+                collect_leaves(key, leaves);
+                collect_leaves(value, leaves);
+            }
+        }
+        serde_cbor::Value::Tag(_, inner) => collect_leaves(inner, leaves),
+        _ => {}
+    }
+}
+
+/// Validate YAML overlay structure
+pub fn validate_yaml_overlay(overlay: &YAMLOverlay) -> Result<()> {
+    // Check required fields
+    if overlay.auto_populated.run_id.0.is_nil() {
+        anyhow::bail!("Invalid run_id in YAML overlay");
+    }
+
+    // Validate command block
+    let command = &overlay.command;
+    if command.target_files.is_empty()
+        && command.parameters.is_empty()
+        && command.instructions.is_empty()
+        && command.program.is_empty()
+    {
+        anyhow::bail!("YAML overlay has empty command block");
+    }
+
+    Ok(())
+}
+
+/// Validate an overlay and build its CBOR runtime object. Needs no stores,
+/// so overlays can be pre-converted as soon as they are saved.
+pub fn overlay_to_cbor(yaml_overlay: &YAMLOverlay) -> Result<CBORRuntimeObject> {
+    // Validate YAML structure
+    validate_yaml_overlay(yaml_overlay)?;
+
+    // Record which overlay field each parameter came from so failures
+    // can be traced back after the comments are gone
+    let mut command = yaml_overlay.command.clone();
+    for (i, parameter) in command.parameters.iter_mut().enumerate() {
+        parameter.origin.get_or_insert_with(|| format!("command.parameters[{}]", i));
+    }
+
+    // Create CBOR object (comments stripped, annotations logged separately)
+    Ok(CBORRuntimeObject {
+        object_id: format!(
+            "{}_{}",
+            yaml_overlay.auto_populated.run_id,
+            yaml_overlay.auto_populated.seq.0
+        ),
+        metadata: yaml_overlay.metadata.clone(),
+        command,
+        auto_fields: yaml_overlay.auto_populated.clone(),
+        decisions: Vec::new(),
+        source_template_id: yaml_overlay.source_template_id.clone(),
+        source_overlay_id: Some(yaml_overlay.overlay_id()),
+    })
+}
+
 /// Conversion pipeline orchestrator
 pub struct ConversionPipeline {
     converter: FormatConverter,
+    impact_calculator: ImpactCalculator,
+    /// Where to look for a diff captured for the executed entry
+    diff_manager: Option<DiffManager>,
 }
 
 impl ConversionPipeline {
     pub fn new(converter: FormatConverter) -> Self {
-        Self { converter }
+        Self { converter, impact_calculator: ImpactCalculator::new(), diff_manager: None }
+    }
+
+    /// Compute recorded impact with `calculator` (e.g. with a custom module mapper)
+    pub fn with_impact_calculator(mut self, calculator: ImpactCalculator) -> Self {
+        self.impact_calculator = calculator;
+        self
+    }
+
+    /// Take line counts from, and link, the diff stored in `diffs` for the
+    /// executed run and seq
+    pub fn with_diff_manager(mut self, diffs: DiffManager) -> Self {
+        self.diff_manager = Some(diffs);
+        self
+    }
+
+    /// Execute `cbor_obj` and record its lineage entry with the computed
+    /// impact, linked diff and, from `overlay`, annotations and failure origin
+    fn execute_and_record(&self, cbor_obj: &CBORRuntimeObject, overlay: Option<&YAMLOverlay>) -> Result<JSONLineage> {
+        let result = self.converter.runtime_manager.execute(cbor_obj)?;
+
+        let diff = match &self.diff_manager {
+            Some(diffs) => diffs.find_diff(result.run_id, result.seq)?,
+            None => None,
+        };
+        let impact = self.impact_calculator.compute(&result.modified_objects, &cbor_obj.command.target_files, diff.as_ref());
+
+        let mut lineage = self.converter.cbor_to_json_lineage(cbor_obj, &result, impact)?;
+        lineage.diff_id = diff.map(|diff| diff.header.diff_id);
+        if let Some(overlay) = overlay {
+            // Attribute a failure to the overlay field (and its annotation)
+            lineage.origin = result.origin.as_deref().map(|field| overlay.origin_for(field));
+            // Preserves human reasoning without embedding it in CBOR
+            lineage.provenance.annotations = overlay.annotations.clone();
+        }
+        self.converter.lineage_manager.commit(&lineage, &result.modified_objects)?;
+        Ok(lineage)
     }
 
     /// Full pipeline: HDF5 → CBOR → Execute → JSON Lineage
@@ -254,15 +435,8 @@ impl ConversionPipeline {
         // Step 1: HDF5 → CBOR
         let cbor_obj = self.converter.hdf5_to_cbor(template_id, run_id, seq, actor)?;
 
-        // Step 2: Execute CBOR
-        let result = self.converter.runtime_manager.execute(&cbor_obj)?;
-
-        // Step 3: CBOR → JSON Lineage
-        let lineage = self.converter.cbor_to_json_lineage(
-            &cbor_obj,
-            result.outcome,
-            crate::Impact::default(), // TODO: extract from result
-        )?;
+        // Step 2 + 3: Execute CBOR, CBOR → JSON Lineage
+        let lineage = self.execute_and_record(&cbor_obj, None)?;
 
         // Step 4: CBOR object is ephemeral, discarded here
         // Only lineage persists
@@ -283,34 +457,384 @@ impl ConversionPipeline {
         // Step 1: YAML → CBOR
         let cbor_obj = self.converter.yaml_to_cbor(yaml_overlay)?;
 
-        // Step 2: Execute CBOR
-        let result = self.converter.runtime_manager.execute(&cbor_obj)?;
-
-        // Step 3: CBOR → JSON Lineage (with YAML annotations)
-        let mut lineage = self.converter.cbor_to_json_lineage(
-            &cbor_obj,
-            result.outcome,
-            crate::Impact::default(),
-        )?;
-
-        // Step 4: Attach YAML annotations to lineage
-        // (This preserves human reasoning without embedding in CBOR)
-        for annotation in &yaml_overlay.annotations {
-            // TODO: Store annotations in lineage provenance
-        }
-
-        Ok(lineage)
+        // Step 2 + 3 + 4: Execute CBOR, CBOR → JSON Lineage with the YAML
+        // annotations attached
+        self.execute_and_record(&cbor_obj, Some(yaml_overlay))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runtime::CommandBlockBuilder;
+    use crate::schemas::{BlockType, ExecutionOutcome, ParameterValue};
+    use crate::storage::MemoryBackend;
 
     #[test]
     fn test_conversion_rules() {
         // Test that conversion rules are documented
         // Actual conversion logic tested in integration tests
-        assert!(true);
+    }
+
+    fn overlay(command: CommandBlock, annotations: Vec<Annotation>) -> YAMLOverlay {
+        YAMLOverlay {
+            comment: None,
+            metadata: crate::ExecutionMetadata::with_config(Actor::System, &serde_json::json!({"scanner": {"depth": 3}}))
+                .unwrap(),
+            command,
+            auto_populated: AutoPopulatedFields {
+                run_id: RunId::new(),
+                seq: Seq::zero(),
+                timestamp: chrono::Utc::now(),
+                actor: Actor::System,
+                file_path: None,
+                rule_group: None,
+                confidence: None,
+                tests_planned: vec![],
+            },
+            annotations,
+            source_template_id: None,
+        }
+    }
+
+    #[test]
+    fn test_execute_from_yaml_records_failing_origin() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pipeline = ConversionPipeline::new(FormatConverter::new(
+            TemplateStore::with_backend(MemoryBackend::shared()),
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ));
+
+        let command = CommandBlockBuilder::new(BlockType::RepairBlock)
+            .parameter("mode", ParameterValue::String("fast".to_string()))
+            .parameter("target", ParameterValue::String("src/lib.rs".to_string()))
+            .parameter("retry_count", ParameterValue::Integer(-1))
+            .build();
+        let overlay = overlay(command, vec![Annotation {
+            field: "command.parameters[2]".to_string(),
+            explanation: "retry count chosen by ops team".to_string(),
+            rationale: None,
+        }]);
+
+        // Origins survive the CBOR round trip
+        let cbor_obj = pipeline.converter.yaml_to_cbor(&overlay)?;
+        let manager = &pipeline.converter.runtime_manager;
+        let decoded = manager.from_cbor(&manager.to_cbor(&cbor_obj)?)?;
+        assert_eq!(decoded.command.parameters[2].origin.as_deref(), Some("command.parameters[2]"));
+
+        let lineage = pipeline.execute_from_yaml(&overlay)?;
+        let ExecutionOutcome::Failed { reason } = &lineage.outcome else { panic!("expected failure, got {:?}", lineage.outcome) };
+        assert_eq!(reason, "Parameter 'retry_count' (from command.parameters[2]) must not be negative (got -1)");
+
+        let saved = pipeline.converter.lineage_manager.load(lineage.run_id, lineage.seq)?;
+        let origin = saved.origin.expect("failure should carry its origin");
+        assert_eq!(origin.field, "command.parameters[2]");
+        assert_eq!(
+            origin.to_string(),
+            "originating overlay field: command.parameters[2] (annotated: 'retry count chosen by ops team')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_from_yaml_records_instruction_author() -> Result<()> {
+        use oasm_core::context::Actor as CoreActor;
+        use oasm_core::parser::{InstructionProvenance, NativeParser};
+
+        let dir = tempfile::tempdir()?;
+        let pipeline = ConversionPipeline::new(FormatConverter::new(
+            TemplateStore::with_backend(MemoryBackend::shared()),
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ));
+        let provenance = InstructionProvenance {
+            actor: CoreActor::Human { username: "alice".to_string() },
+            source_file: Some("parts/gear.oasm".into()),
+        };
+        let command = NativeParser
+            .parse_with_provenance("CREATE gear\nSET shaft_0000.teeth = 24", &provenance)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?
+            .into_iter()
+            .fold(CommandBlockBuilder::new(BlockType::OasmProgram), CommandBlockBuilder::parsed_instruction)
+            .build();
+
+        let lineage = pipeline.execute_from_yaml(&overlay(command, vec![]))?;
+        let saved = pipeline.converter.lineage_manager.load(lineage.run_id, lineage.seq)?;
+        let author = saved.provenance.authored_by.expect("the failing line's author is recorded");
+        assert!(matches!(&author.actor, Actor::Human { username } if username == "alice"));
+        assert_eq!((author.source_file.as_deref(), author.line), (Some("parts/gear.oasm"), 2));
+        assert_eq!(saved.command_executed, "CREATE gear\nSET shaft_0000.teeth = 24");
+        Ok(())
+    }
+
+    #[test]
+    fn test_provenance_follows_template_and_overlay() -> Result<()> {
+        use crate::schemas::TemplateType;
+        use crate::templates::TemplateBuilder;
+
+        let dir = tempfile::tempdir()?;
+        let templates = TemplateStore::with_backend(MemoryBackend::shared());
+        templates.store_template(&TemplateBuilder::new("lint_001", TemplateType::LintBundle).build())?;
+        let pipeline = ConversionPipeline::new(FormatConverter::new(
+            templates,
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ));
+
+        // HDF5 → CBOR → lineage
+        let lineage = pipeline.execute_from_template("lint_001", RunId::new(), Seq::zero(), Actor::System)?;
+        assert_eq!(lineage.provenance.template_id.as_deref(), Some("lint_001"));
+        assert_eq!(lineage.provenance.lineage_chain, vec!["template:lint_001".to_string()]);
+
+        // HDF5 → YAML → CBOR → lineage, annotations included
+        let mut overlay = pipeline.converter.hdf5_to_yaml("lint_001")?;
+        overlay.command.parameters.push(crate::schemas::Parameter {
+            key: "level".to_string(),
+            value: ParameterValue::String("strict".to_string()),
+            origin: None,
+        });
+        let cbor_obj = pipeline.converter.yaml_to_cbor(&overlay)?;
+        let manager = &pipeline.converter.runtime_manager;
+        let decoded = manager.from_cbor(&manager.to_cbor(&cbor_obj)?)?;
+        assert_eq!(decoded.source_template_id.as_deref(), Some("lint_001"));
+        assert_eq!(decoded.source_overlay_id, Some(overlay.overlay_id()));
+
+        let lineage = pipeline.execute_from_yaml(&overlay)?;
+        let saved = pipeline.converter.lineage_manager.load(lineage.run_id, lineage.seq)?;
+        assert_eq!(saved.provenance.template_id.as_deref(), Some("lint_001"));
+        assert_eq!(
+            saved.provenance.lineage_chain,
+            vec!["template:lint_001".to_string(), format!("overlay:{}", overlay.overlay_id())]
+        );
+        assert_eq!(saved.provenance.annotations.len(), overlay.annotations.len());
+        assert_eq!(saved.provenance.annotations[0].field, "template_id");
+        Ok(())
+    }
+
+    #[test]
+    fn test_lineage_without_provenance_fields_still_loads() -> Result<()> {
+        // Written before annotations and source ids existed
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/golden/lint_bundle_success.json"))?;
+        json["timestamp"] = serde_json::json!("2024-01-01T00:00:00Z");
+        json["provenance"]["tool_versions"] = serde_json::to_value(crate::ToolVersions::current())?;
+        json["provenance"].as_object_mut().unwrap().remove("annotations");
+        let lineage: JSONLineage = serde_json::from_value(json)?;
+        assert!(lineage.provenance.annotations.is_empty());
+
+        let dir = tempfile::tempdir()?;
+        let obj = RuntimeObjectManager::new(dir.path().join("cache")).create_object(
+            RunId::new(),
+            Seq::zero(),
+            Actor::System,
+            CommandBlockBuilder::new(BlockType::LintCheck).build(),
+        );
+        let mut value = serde_cbor::value::to_value(&obj)?;
+        if let serde_cbor::Value::Map(map) = &mut value {
+            assert!(!map.contains_key(&serde_cbor::Value::Text("source_template_id".to_string())));
+        }
+        let decoded: CBORRuntimeObject = serde_cbor::value::from_value(value)?;
+        assert!(decoded.source_template_id.is_none() && decoded.source_overlay_id.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_no_duplication() -> Result<()> {
+        use crate::schemas::{Artifact, ArtifactType, TemplateType};
+        use crate::templates::TemplateBuilder;
+
+        let cfg = "digraph cfg { entry -> exit }";
+        let template = TemplateBuilder::new("pass_001", TemplateType::AssemblerPass)
+            .add_artifact(Artifact {
+                artifact_id: "cfg".to_string(),
+                artifact_type: ArtifactType::CFG,
+                data_path: "/artifacts/pass_001/cfg".to_string(),
+                size_bytes: cfg.len() as u64,
+                checksum: format!("sha256:{}", crate::lineage::sha256_hex(cfg.as_bytes())),
+            })
+            .build();
+
+        // A compliant object only carries execution parameters
+        let dir = tempfile::tempdir()?;
+        let command = CommandBlockBuilder::new(BlockType::AnalysisPass)
+            .parameter("pass", ParameterValue::String("pass_001".to_string()))
+            .build();
+        let mut obj = RuntimeObjectManager::new(dir.path().join("cache"))
+            .create_object(RunId::new(), Seq::zero(), Actor::System, command);
+        audit_no_duplication(&template, &obj)?;
+
+        obj.command.parameters.push(crate::schemas::Parameter {
+            key: "graph".to_string(),
+            value: ParameterValue::String(cfg.to_string()),
+            origin: None,
+        });
+        obj.command.target_files.push("/artifacts/pass_001/cfg".to_string());
+        let err = audit_no_duplication(&template, &obj).unwrap_err().to_string();
+        assert!(err.contains("'cfg' (path /artifacts/pass_001/cfg embedded)"), "{}", err);
+        assert!(err.contains("'cfg' (contents embedded)"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_to_cbor_stamps_config_hash() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let converter = || FormatConverter::new(
+            TemplateStore::with_backend(MemoryBackend::shared()),
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ).with_config_hash_policy(ConfigHashPolicy::Require);
+        let command = CommandBlockBuilder::new(BlockType::LintCheck)
+            .parameter("retry_count", ParameterValue::Integer(3))
+            .build();
+        let mut overlay = overlay(command, vec![]);
+        overlay.metadata.config_hash.clear();
+
+        let err = converter().yaml_to_cbor(&overlay).unwrap_err();
+        assert!(err.to_string().contains("has no config_hash"), "{}", err);
+
+        let hasher = crate::config_hash::ConfigHasher::from_yaml("scanner: {depth: 4}")?;
+        let hash = hasher.hash();
+        let obj = converter().with_config(hasher.effective())?.yaml_to_cbor(&overlay)?;
+        assert_eq!(obj.metadata.config_hash, hash);
+
+        // The overlay's own hash wins over the converter's
+        let mut hashed = overlay.clone();
+        hashed.metadata.config_hash = "from-overlay".to_string();
+        let obj = converter().with_config_hash(hash).yaml_to_cbor(&hashed)?;
+        assert_eq!(obj.metadata.config_hash, "from-overlay");
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_from_yaml_success_has_no_origin() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pipeline = ConversionPipeline::new(FormatConverter::new(
+            TemplateStore::with_backend(MemoryBackend::shared()),
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ));
+
+        let command = CommandBlockBuilder::new(BlockType::LintCheck)
+            .parameter("retry_count", ParameterValue::Integer(3))
+            .build();
+        let lineage = pipeline.execute_from_yaml(&overlay(command, vec![]))?;
+
+        assert!(matches!(lineage.outcome, ExecutionOutcome::Success));
+        assert!(lineage.origin.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_from_yaml_persists_annotations_in_provenance() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pipeline = ConversionPipeline::new(FormatConverter::new(
+            TemplateStore::with_backend(MemoryBackend::shared()),
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ));
+
+        let command = CommandBlockBuilder::new(BlockType::LintCheck)
+            .parameter("retry_count", ParameterValue::Integer(3))
+            .build();
+        let overlay = overlay(command, vec![
+            Annotation {
+                field: "command.parameters[0]".to_string(),
+                explanation: "three retries ride out flaky runners".to_string(),
+                rationale: Some("CI history".to_string()),
+            },
+            Annotation {
+                field: "metadata.actor".to_string(),
+                explanation: "scheduled nightly".to_string(),
+                rationale: None,
+            },
+        ]);
+
+        // The CBOR object never carries them
+        let cbor_obj = pipeline.converter.yaml_to_cbor(&overlay)?;
+        if let serde_cbor::Value::Map(map) = serde_cbor::value::to_value(&cbor_obj)? {
+            assert!(!map.contains_key(&serde_cbor::Value::Text("annotations".to_string())));
+        }
+
+        let lineage = pipeline.execute_from_yaml(&overlay)?;
+        let saved = pipeline.converter.lineage_manager.load(lineage.run_id, lineage.seq)?;
+        let annotations = &saved.provenance.annotations;
+        assert_eq!(annotations.len(), 2);
+        for (saved, expected) in annotations.iter().zip(&overlay.annotations) {
+            assert_eq!(saved.field, expected.field);
+            assert_eq!(saved.explanation, expected.explanation);
+            assert_eq!(saved.rationale, expected.rationale);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipeline_records_computed_impact() -> Result<()> {
+        use crate::schemas::{DiffHeader, DiffHunk, DiffLine, DiffLineType, DiffSnapshot};
+
+        let dir = tempfile::tempdir()?;
+        let diffs = MemoryBackend::shared();
+        let pipeline = ConversionPipeline::new(FormatConverter::new(
+            TemplateStore::with_backend(MemoryBackend::shared()),
+            RuntimeObjectManager::new(dir.path().join("cache")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        ))
+        .with_diff_manager(DiffManager::with_backend(diffs.clone()));
+
+        let command = CommandBlockBuilder::new(BlockType::RepairBlock)
+            .parameter("mode", ParameterValue::String("fast".to_string()))
+            .target_file("crates/oasm-core/src/parser/mod.rs")
+            .build();
+
+        // No diff: the touched target counts as changed
+        let lineage = pipeline.execute_from_yaml(&overlay(command.clone(), vec![]))?;
+        assert_eq!((lineage.impact.files_changed, lineage.impact.lines_added), (1, 0));
+        assert_eq!(lineage.impact.objects_affected, 1);
+        assert_eq!(lineage.impact.modules_affected, vec!["oasm_core::parser"]);
+        assert!(lineage.diff_id.is_none());
+
+        // A diff captured for the entry gives its line counts and is linked
+        let overlay = overlay(command, vec![]);
+        let line = DiffLine::new;
+        DiffManager::with_backend(diffs).save_diff(&DiffSnapshot {
+            header: DiffHeader {
+                diff_id: "diff_0".to_string(),
+                run_id: overlay.auto_populated.run_id,
+                seq: overlay.auto_populated.seq,
+                timestamp: chrono::Utc::now(),
+                actor: Actor::System,
+                summary: "Repair parser".to_string(),
+                confidence: crate::Confidence(0.9),
+                intent: "Repair build".to_string(),
+                tests: vec![],
+                impact: crate::Impact::default(),
+            },
+            hunks: vec![DiffHunk {
+                file_path: "crates/oasm-core/src/parser/mod.rs".to_string(),
+                old_start: 10,
+                old_count: 2,
+                new_start: 10,
+                new_count: 3,
+                lines: vec![
+                    line(DiffLineType::Context, "fn parse() {"),
+                    line(DiffLineType::Removal, "    todo!()"),
+                    line(DiffLineType::Addition, "    let tokens = lex();"),
+                    line(DiffLineType::Addition, "    build(tokens)"),
+                ],
+            }],
+            file_changes: Default::default(),
+            binary_files: vec![],
+            compression: None,
+        })?;
+
+        let lineage = pipeline.execute_from_yaml(&overlay)?;
+        assert_eq!(
+            (lineage.impact.files_changed, lineage.impact.lines_added, lineage.impact.lines_removed),
+            (1, 2, 1)
+        );
+        let saved = pipeline.converter.lineage_manager.load(lineage.run_id, lineage.seq)?;
+        assert_eq!(saved.diff_id.as_deref(), Some("diff_0"));
+        assert_eq!(saved.impact.lines_added, 2);
+        Ok(())
     }
 }
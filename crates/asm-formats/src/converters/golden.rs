@@ -0,0 +1,104 @@
+//! Golden-file harness for the conversion pipeline
+//!
+//! Runs HDF5 → CBOR → execute → JSON lineage against in-memory stores with a
+//! fixed run id, seq, actor and config hash, then compares the lineage with
+//! a committed JSON file. Fields that differ between machines or runs
+//! (timestamp, tool versions) are replaced by placeholders first.
+//!
+//! Set `OASM_UPDATE_GOLDEN=1` to rewrite the golden files instead of
+//! comparing against them.
+
+use super::{ConversionPipeline, FormatConverter};
+use crate::lineage::LineageManager;
+use crate::runtime::RuntimeObjectManager;
+use crate::schemas::HDF5Template;
+use crate::storage::MemoryBackend;
+use crate::templates::TemplateStore;
+use crate::{Actor, RunId, Seq};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Seed of the run id every golden pipeline run uses (`RunId::from_seed`)
+pub const GOLDEN_RUN_SEED: u64 = 0x0a5e;
+/// Config hash stamped on golden runtime objects
+pub const GOLDEN_CONFIG_HASH: &str = "golden";
+/// Environment variable that switches to rewriting golden files
+pub const UPDATE_ENV: &str = "OASM_UPDATE_GOLDEN";
+
+/// Run `template` through the pipeline and assert its lineage matches the
+/// golden JSON at `expected_lineage_json`. Panics with both documents on a
+/// mismatch; errors only if the pipeline or file I/O fails.
+pub fn assert_pipeline(template: &HDF5Template, expected_lineage_json: impl AsRef<Path>) -> Result<()> {
+    let golden = expected_lineage_json.as_ref();
+    let actual = run_pipeline(template)?;
+    let actual = serde_json::to_string_pretty(&actual)? + "\n";
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        if let Some(parent) = golden.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(golden, &actual).with_context(|| format!("Failed to write {}", golden.display()))?;
+        return Ok(());
+    }
+
+    let expected = std::fs::read_to_string(golden).with_context(|| {
+        format!("Failed to read golden file {} (run with {}=1 to create it)", golden.display(), UPDATE_ENV)
+    })?;
+    assert_eq!(
+        expected.trim_end(),
+        actual.trim_end(),
+        "lineage for template '{}' differs from {} (run with {}=1 to accept)",
+        template.template_id,
+        golden.display(),
+        UPDATE_ENV
+    );
+    Ok(())
+}
+
+/// Normalized lineage of one deterministic pipeline run
+pub fn run_pipeline(template: &HDF5Template) -> Result<Value> {
+    let templates = TemplateStore::with_backend(MemoryBackend::shared());
+    templates.store_template(template)?;
+
+    // Nothing is written to the runtime cache while executing
+    let pipeline = ConversionPipeline::new(
+        FormatConverter::new(
+            templates,
+            RuntimeObjectManager::new(std::env::temp_dir().join("oasm-golden")),
+            LineageManager::with_backend(MemoryBackend::shared()),
+        )
+        .with_config_hash(GOLDEN_CONFIG_HASH),
+    );
+    let run_id = RunId::from_seed(GOLDEN_RUN_SEED);
+    let lineage = pipeline.execute_from_template(&template.template_id, run_id, Seq::zero(), Actor::System)?;
+
+    let mut value = serde_json::to_value(&lineage)?;
+    normalize(&mut value);
+    Ok(value)
+}
+
+/// Replace fields that change between runs or machines with placeholders
+fn normalize(lineage: &mut Value) {
+    lineage["timestamp"] = Value::String("<timestamp>".to_string());
+    lineage["provenance"]["tool_versions"] = Value::String("<tool_versions>".to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::TemplateType;
+    use crate::templates::TemplateBuilder;
+
+    fn golden(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/golden").join(name)
+    }
+
+    #[test]
+    fn test_lint_bundle_pipeline_golden() -> Result<()> {
+        let template = TemplateBuilder::new("lint_001", TemplateType::LintBundle)
+            .description("Workspace lint bundle")
+            .build();
+        assert_pipeline(&template, golden("lint_bundle_success.json"))
+    }
+}
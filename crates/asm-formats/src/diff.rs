@@ -0,0 +1,724 @@
+//! Diff Generation
+//!
+//! Builds `DiffSnapshot`s from before/after file contents with a Myers diff,
+//! grouped into unified-format hunks the way `diff -u` groups them: changes
+//! closer than twice the context window share a hunk, and an empty side
+//! starts at line 0.
+//!
+//! `patch_text` applies (or reverts) a file's hunks. Every context and
+//! removed line must match exactly; there is no fuzz or offset search.
+
+use crate::impact::ImpactCalculator;
+use crate::schemas::{DiffHeader, DiffHunk, DiffLine, DiffLineType, DiffSnapshot, FileChange};
+use crate::{Actor, Confidence, RunId, Seq};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// Context lines around each change, as in `diff -u`
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Generates diff snapshots for one lineage entry
+#[derive(Debug, Clone)]
+pub struct DiffBuilder {
+    run_id: RunId,
+    seq: Seq,
+    actor: Actor,
+    diff_id: Option<String>,
+    summary: String,
+    intent: String,
+    confidence: Confidence,
+    context_lines: usize,
+    impact_calculator: ImpactCalculator,
+}
+
+impl DiffBuilder {
+    pub fn new(run_id: RunId, seq: Seq, actor: Actor) -> Self {
+        Self {
+            run_id,
+            seq,
+            actor,
+            diff_id: None,
+            summary: String::new(),
+            intent: String::new(),
+            confidence: Confidence(1.0),
+            context_lines: DEFAULT_CONTEXT_LINES,
+            impact_calculator: ImpactCalculator::new(),
+        }
+    }
+
+    /// Diff id (default `diff_<seq>`)
+    pub fn with_diff_id(mut self, diff_id: impl Into<String>) -> Self {
+        self.diff_id = Some(diff_id.into());
+        self
+    }
+
+    pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = summary.into();
+        self
+    }
+
+    pub fn with_intent(mut self, intent: impl Into<String>) -> Self {
+        self.intent = intent.into();
+        self
+    }
+
+    pub fn with_confidence(mut self, confidence: Confidence) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Unchanged lines kept around each change (default 3)
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Compute the header impact with `calculator` (e.g. with a custom module mapper)
+    pub fn with_impact_calculator(mut self, calculator: ImpactCalculator) -> Self {
+        self.impact_calculator = calculator;
+        self
+    }
+
+    /// Snapshot of one file changing from `old` to `new`
+    pub fn diff_files(&self, old: &str, new: &str, file_path: &str) -> DiffSnapshot {
+        self.snapshot(self.hunks(old, new, file_path), BTreeMap::new(), Vec::new())
+    }
+
+    /// Snapshot of file `a` changing into file `b`, recorded under `b`'s path
+    pub fn diff_paths(&self, a: impl AsRef<Path>, b: impl AsRef<Path>) -> Result<DiffSnapshot> {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        let old = read_text(a)?;
+        let new = read_text(b)?;
+        Ok(self.diff_files(&old, &new, &b.to_string_lossy().replace('\\', "/")))
+    }
+
+    /// Snapshot of every file that differs between two directory trees,
+    /// recorded under paths relative to the roots. A file present on one
+    /// side only is diffed against an empty file and listed in
+    /// `file_changes` as created or deleted. Files that are not UTF-8 text
+    /// are only listed in `binary_files`, when their bytes differ.
+    pub fn diff_tree(&self, dir_a: impl AsRef<Path>, dir_b: impl AsRef<Path>) -> Result<DiffSnapshot> {
+        let (dir_a, dir_b) = (dir_a.as_ref(), dir_b.as_ref());
+        let mut files = BTreeSet::new();
+        collect_files(dir_a, dir_a, &mut files)?;
+        collect_files(dir_b, dir_b, &mut files)?;
+
+        let mut hunks = Vec::new();
+        let mut file_changes = BTreeMap::new();
+        let mut binary_files = Vec::new();
+        for file in files {
+            let read = |root: &Path| -> Result<Option<Vec<u8>>> {
+                let path = root.join(&file);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                std::fs::read(&path).map(Some).with_context(|| format!("Failed to read {}", path.display()))
+            };
+            let (old, new) = (read(dir_a)?, read(dir_b)?);
+            let (Ok(old_text), Ok(new_text)) = (text_of(old.as_deref()), text_of(new.as_deref())) else {
+                if old != new {
+                    binary_files.push(file);
+                }
+                continue;
+            };
+            match (&old, &new) {
+                (None, Some(_)) => file_changes.insert(file.clone(), FileChange::Created),
+                (Some(_), None) => file_changes.insert(file.clone(), FileChange::Deleted),
+                _ => None,
+            };
+            hunks.extend(self.hunks(old_text, new_text, &file));
+        }
+        Ok(self.snapshot(hunks, file_changes, binary_files))
+    }
+
+    /// Unified-format hunks turning `old` into `new`
+    pub fn hunks(&self, old: &str, new: &str, file_path: &str) -> Vec<DiffHunk> {
+        let old_lines = split_lines(old);
+        let new_lines = split_lines(new);
+        let ops = edit_script(&old_lines, &new_lines);
+
+        // Lines of each side consumed before each op
+        let mut positions = Vec::with_capacity(ops.len() + 1);
+        let (mut old_pos, mut new_pos) = (0, 0);
+        for op in &ops {
+            positions.push((old_pos, new_pos));
+            match op {
+                Op::Equal => (old_pos, new_pos) = (old_pos + 1, new_pos + 1),
+                Op::Delete => old_pos += 1,
+                Op::Insert => new_pos += 1,
+            }
+        }
+        positions.push((old_pos, new_pos));
+
+        let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i] != Op::Equal).collect();
+        let mut hunks = Vec::new();
+        let mut group_start = 0;
+        for i in 0..changes.len() {
+            let last_in_group = i + 1 == changes.len() || changes[i + 1] - changes[i] - 1 > 2 * self.context_lines;
+            if !last_in_group {
+                continue;
+            }
+            let start = changes[group_start].saturating_sub(self.context_lines);
+            let end = (changes[i] + 1 + self.context_lines).min(ops.len());
+            hunks.push(self.hunk(file_path, &ops[start..end], positions[start], &old_lines, &new_lines));
+            group_start = i + 1;
+        }
+        hunks
+    }
+
+    fn hunk(
+        &self,
+        file_path: &str,
+        ops: &[Op],
+        (old_pos, new_pos): (usize, usize),
+        old_lines: &[Line],
+        new_lines: &[Line],
+    ) -> DiffHunk {
+        let (mut old_index, mut new_index) = (old_pos, new_pos);
+        let mut lines = Vec::with_capacity(ops.len());
+        for op in ops {
+            let (line_type, line) = match op {
+                Op::Equal => {
+                    old_index += 1;
+                    new_index += 1;
+                    (DiffLineType::Context, &new_lines[new_index - 1])
+                }
+                Op::Delete => {
+                    old_index += 1;
+                    (DiffLineType::Removal, &old_lines[old_index - 1])
+                }
+                Op::Insert => {
+                    new_index += 1;
+                    (DiffLineType::Addition, &new_lines[new_index - 1])
+                }
+            };
+            lines.push(DiffLine { missing_newline: !line.terminated, ..DiffLine::new(line_type, line.text) });
+        }
+
+        let old_count = old_index - old_pos;
+        let new_count = new_index - new_pos;
+        // An empty range starts at the line before it, as in `diff -u`
+        let start = |pos: usize, count: usize| if count == 0 { pos } else { pos + 1 };
+        DiffHunk {
+            file_path: file_path.to_string(),
+            old_start: start(old_pos, old_count),
+            old_count,
+            new_start: start(new_pos, new_count),
+            new_count,
+            lines,
+        }
+    }
+
+    fn snapshot(
+        &self,
+        hunks: Vec<DiffHunk>,
+        file_changes: BTreeMap<String, FileChange>,
+        binary_files: Vec<String>,
+    ) -> DiffSnapshot {
+        let mut snapshot = DiffSnapshot {
+            header: DiffHeader {
+                diff_id: self.diff_id.clone().unwrap_or_else(|| format!("diff_{:04}", self.seq.0)),
+                run_id: self.run_id,
+                seq: self.seq,
+                timestamp: chrono::Utc::now(),
+                actor: self.actor.clone(),
+                summary: self.summary.clone(),
+                confidence: self.confidence,
+                intent: self.intent.clone(),
+                tests: Vec::new(),
+                impact: crate::Impact::default(),
+            },
+            hunks,
+            file_changes,
+            binary_files,
+            compression: None,
+        };
+        snapshot.header.impact = self.impact_calculator.compute(&[], &[], Some(&snapshot));
+        snapshot
+    }
+}
+
+/// One line of a file, without its terminator
+#[derive(Debug, PartialEq)]
+struct Line<'a> {
+    text: &'a str,
+    /// False for a last line with no trailing newline
+    terminated: bool,
+}
+
+fn split_lines(text: &str) -> Vec<Line<'_>> {
+    text.split_inclusive('\n')
+        .map(|line| match line.strip_suffix('\n') {
+            Some(text) => Line { text, terminated: true },
+            None => Line { text: line, terminated: false },
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Shortest edit script from `a` to `b` (Myers' O(ND) algorithm), with
+/// deletions before insertions within a change
+fn edit_script(a: &[Line], b: &[Line]) -> Vec<Op> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    // Diagonals -(max + 1)..=max + 1, so step d can read k - 1 and k + 1
+    let offset = max as isize + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    let mut v = vec![0isize; 2 * max + 3];
+    // Before step d only diagonals -(d + 1)..=d + 1 are read, so keep just
+    // that range of each step: O(D^2) memory rather than O(D * (N + M))
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=max as isize {
+        trace.push(v[index(-d - 1)..=index(d + 1)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, row) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| row[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    ops.reverse();
+    ops
+}
+
+/// Direction to apply a diff in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyMode {
+    /// Turn the old contents into the new
+    Apply,
+    /// Turn the new contents back into the old
+    Revert,
+}
+
+/// A hunk whose lines don't match the file it would change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkConflict {
+    pub file_path: String,
+    /// Index of the hunk in the diff
+    pub hunk: usize,
+    /// 1-based line where the mismatch was found
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Why a diff was not applied (wrapped in anyhow; downcast to inspect).
+/// Nothing is written when any hunk conflicts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictError {
+    pub conflicts: Vec<HunkConflict>,
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hunk(s) do not apply:", self.conflicts.len())?;
+        for conflict in &self.conflicts {
+            write!(f, "\n  {} hunk {} at line {}: {}", conflict.file_path, conflict.hunk, conflict.line, conflict.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConflictError {}
+
+/// Apply the hunks of `diff` numbered `hunks` (all for one file) to
+/// `text`. Conflicts are returned instead of a partial result.
+pub(crate) fn patch_text(
+    text: &str,
+    diff: &DiffSnapshot,
+    hunks: &[usize],
+    mode: ApplyMode,
+) -> std::result::Result<String, Vec<HunkConflict>> {
+    let lines = split_lines(text);
+    let mut output = String::with_capacity(text.len());
+    let mut conflicts = Vec::new();
+    let mut cursor = 0;
+
+    let push = |output: &mut String, text: &str, terminated: bool| {
+        output.push_str(text);
+        if terminated {
+            output.push('\n');
+        }
+    };
+
+    for &index in hunks {
+        let hunk = &diff.hunks[index];
+        let (start, count, before, after) = match mode {
+            ApplyMode::Apply => (hunk.old_start, hunk.old_count, DiffLineType::Removal, DiffLineType::Addition),
+            ApplyMode::Revert => (hunk.new_start, hunk.new_count, DiffLineType::Addition, DiffLineType::Removal),
+        };
+        let expected: Vec<&DiffLine> = hunk.lines.iter().filter(|l| l.line_type != after).collect();
+        let replacement = hunk.lines.iter().filter(|l| l.line_type != before);
+        // An empty range is positioned after line `start`
+        let at = if count == 0 { start } else { start.saturating_sub(1) };
+
+        let conflict = |line: usize, reason: String| HunkConflict { file_path: hunk.file_path.clone(), hunk: index, line, reason };
+        if at < cursor || at + expected.len() > lines.len() {
+            conflicts.push(conflict(at + 1, format!("expects {} line(s) at line {}, file has {}", expected.len(), at + 1, lines.len())));
+            continue;
+        }
+        let mismatch = expected.iter().zip(&lines[at..]).position(|(want, have)| {
+            want.content != have.text || want.missing_newline == have.terminated
+        });
+        if let Some(offset) = mismatch {
+            conflicts.push(conflict(at + offset + 1, format!("expected {:?}, found {:?}", expected[offset].content, lines[at + offset].text)));
+            continue;
+        }
+
+        for line in &lines[cursor..at] {
+            push(&mut output, line.text, line.terminated);
+        }
+        for line in replacement {
+            push(&mut output, &line.content, !line.missing_newline);
+        }
+        cursor = at + expected.len();
+    }
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+    for line in &lines[cursor..] {
+        push(&mut output, line.text, line.terminated);
+    }
+    Ok(output)
+}
+
+/// Contents as text; an absent file reads as empty
+fn text_of(bytes: Option<&[u8]>) -> Result<&str, std::str::Utf8Error> {
+    bytes.map_or(Ok(""), std::str::from_utf8)
+}
+
+fn read_text(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Paths of all files under `dir`, relative to `root` with `/` separators
+fn collect_files(root: &Path, dir: &Path, files: &mut BTreeSet<String>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).expect("walked from root");
+            files.insert(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lineage::DiffManager;
+    use crate::storage::MemoryBackend;
+
+    fn builder() -> DiffBuilder {
+        DiffBuilder::new(RunId::new(), Seq(2), Actor::System).with_summary("Edit")
+    }
+
+    /// Everything `preview_diff` prints after its header block
+    fn unified(diff: &DiffSnapshot) -> String {
+        let preview = DiffManager::with_backend(MemoryBackend::shared()).preview_diff(diff);
+        preview.split_once("\n\n").map(|(_, body)| body.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_single_change_matches_diff_u() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\n";
+        let diff = builder().diff_files(old, new, "notes.txt");
+
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(
+            unified(&diff),
+            "--- notes.txt\n+++ notes.txt\n@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n"
+        );
+        assert_eq!((diff.header.impact.lines_added, diff.header.impact.lines_removed), (1, 1));
+        assert_eq!(diff.header.impact.files_changed, 1);
+        assert_eq!(diff.header.diff_id, "diff_0002");
+    }
+
+    #[test]
+    fn test_distant_changes_split_into_hunks() {
+        let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let new: String = (1..=20)
+            .filter(|&i| i != 19)
+            .map(|i| if i == 2 { "two\n".to_string() } else { format!("{}\n", i) })
+            .collect();
+        let diff = builder().diff_files(&old, &new, "n.txt");
+
+        assert_eq!(
+            unified(&diff),
+            "--- n.txt\n+++ n.txt\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -16,5 +16,4 @@\n 16\n 17\n 18\n-19\n 20\n"
+        );
+
+        // A wider window merges them; no context keeps only the changes
+        assert_eq!(builder().with_context_lines(10).diff_files(&old, &new, "n.txt").hunks.len(), 1);
+        let tight = builder().with_context_lines(0).diff_files(&old, &new, "n.txt");
+        assert_eq!((tight.hunks[1].old_start, tight.hunks[1].old_count), (19, 1));
+        assert_eq!((tight.hunks[1].new_start, tight.hunks[1].new_count), (18, 0));
+    }
+
+    #[test]
+    fn test_empty_file_edges() {
+        let created = builder().diff_files("", "one\ntwo\n", "new.txt");
+        assert_eq!(unified(&created), "--- new.txt\n+++ new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n");
+
+        let deleted = builder().diff_files("only\n", "", "gone.txt");
+        assert_eq!(unified(&deleted), "--- gone.txt\n+++ gone.txt\n@@ -1 +0,0 @@\n-only\n");
+
+        assert!(builder().diff_files("", "", "empty.txt").hunks.is_empty());
+        assert!(builder().diff_files("same\n", "same\n", "same.txt").hunks.is_empty());
+
+        // A missing final newline is a change of its own
+        let newline = builder().diff_files("x\ny", "x\ny\n", "eol.txt");
+        assert_eq!(
+            unified(&newline),
+            "--- eol.txt\n+++ eol.txt\n@@ -1,2 +1,2 @@\n x\n-y\n\\ No newline at end of file\n+y\n"
+        );
+    }
+
+    #[test]
+    fn test_tree_diff_round_trips_through_manager() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        for root in [&a, &b] {
+            std::fs::create_dir_all(root.join("src"))?;
+            std::fs::write(root.join("src/same.rs"), "fn same() {}\n")?;
+        }
+        std::fs::write(a.join("src/lib.rs"), "mod a;\nmod b;\n")?;
+        std::fs::write(b.join("src/lib.rs"), "mod a;\nmod c;\n")?;
+        std::fs::write(b.join("README.md"), "Hello\n")?;
+
+        let diff = builder().with_intent("Rename module").diff_tree(&a, &b)?;
+        let files: Vec<&str> = diff.hunks.iter().map(|h| h.file_path.as_str()).collect();
+        assert_eq!(files, vec!["README.md", "src/lib.rs"]);
+        assert_eq!(diff.header.impact.files_changed, 2);
+        assert_eq!((diff.header.impact.lines_added, diff.header.impact.lines_removed), (2, 1));
+
+        let manager = DiffManager::with_backend(MemoryBackend::shared());
+        manager.save_diff(&diff)?;
+        let loaded = manager.load_diff(diff.header.run_id, &diff.header.diff_id)?;
+        assert_eq!(manager.preview_diff(&loaded), manager.preview_diff(&diff));
+        assert_eq!(
+            unified(&loaded),
+            "--- README.md\n+++ README.md\n@@ -0,0 +1 @@\n+Hello\n\
+             --- src/lib.rs\n+++ src/lib.rs\n@@ -1,2 +1,2 @@\n mod a;\n-mod b;\n+mod c;\n"
+        );
+
+        let single = builder().diff_paths(a.join("src/lib.rs"), b.join("src/lib.rs"))?;
+        assert_eq!(single.hunks.len(), 1);
+        assert!(single.hunks[0].file_path.ends_with("b/src/lib.rs"));
+        Ok(())
+    }
+    /// `before` and `after` trees and the tree diff between them; the
+    /// working tree starts as a copy of `before`
+    fn trees(before: &[(&str, &str)], after: &[(&str, &str)]) -> Result<(tempfile::TempDir, DiffSnapshot)> {
+        let dir = tempfile::tempdir()?;
+        for (tree, files) in [("a", before), ("b", after), ("work", before)] {
+            for (path, text) in files {
+                let path = dir.path().join(tree).join(path);
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                std::fs::write(path, text)?;
+            }
+        }
+        let diff = builder().diff_tree(dir.path().join("a"), dir.path().join("b"))?;
+        Ok((dir, diff))
+    }
+
+    const LIB_BEFORE: &str = "mod a;\nmod b;\n\nfn main() {\n    run();\n}";
+    const LIB_AFTER: &str = "mod a;\nmod c;\n\nfn main() {\n    run();\n    exit();\n}\n";
+
+    #[test]
+    fn test_apply_then_revert_restores_bytes() -> Result<()> {
+        let (dir, diff) = trees(
+            &[("src/lib.rs", LIB_BEFORE), ("crlf.txt", "one\r\ntwo\r\n")],
+            &[("src/lib.rs", LIB_AFTER), ("crlf.txt", "one\r\n2\r\n"), ("new.txt", "fresh\n")],
+        )?;
+        let work = dir.path().join("work");
+        let manager = DiffManager::with_backend(MemoryBackend::shared());
+
+        // A dry run reports the files without touching them
+        let planned = DiffManager::with_backend(MemoryBackend::shared())
+            .with_dry_run(true)
+            .apply_diff(&diff, &work, ApplyMode::Apply)?;
+        assert_eq!(planned, vec!["crlf.txt", "new.txt", "src/lib.rs"]);
+        assert_eq!(std::fs::read_to_string(work.join("src/lib.rs"))?, LIB_BEFORE);
+
+        assert_eq!(manager.apply_diff(&diff, &work, ApplyMode::Apply)?, planned);
+        assert_eq!(std::fs::read_to_string(work.join("src/lib.rs"))?, LIB_AFTER);
+        assert_eq!(std::fs::read(work.join("crlf.txt"))?, b"one\r\n2\r\n");
+        assert_eq!(std::fs::read_to_string(work.join("new.txt"))?, "fresh\n");
+
+        manager.apply_diff(&diff, &work, ApplyMode::Revert)?;
+        assert_eq!(std::fs::read_to_string(work.join("src/lib.rs"))?, LIB_BEFORE);
+        assert_eq!(std::fs::read(work.join("crlf.txt"))?, b"one\r\ntwo\r\n");
+        assert!(!work.join("new.txt").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tree_diff_lists_binary_files_without_hunks() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        for root in [&a, &b] {
+            std::fs::create_dir_all(root)?;
+            std::fs::write(root.join("same.bin"), [0xff, 0x00, 0xfe])?;
+        }
+        std::fs::write(a.join("logo.png"), [0x89, b'P', b'N', b'G', 0xff])?;
+        std::fs::write(b.join("logo.png"), [0x89, b'P', b'N', b'G', 0xfe])?;
+        std::fs::write(b.join("new.bin"), [0xc3])?;
+        std::fs::write(a.join("notes.txt"), "one\n")?;
+        std::fs::write(b.join("notes.txt"), "two\n")?;
+
+        let diff = builder().diff_tree(&a, &b)?;
+        assert_eq!(diff.binary_files, vec!["logo.png", "new.bin"]);
+        assert!(diff.file_changes.is_empty());
+        assert!(diff.hunks.iter().all(|hunk| hunk.file_path == "notes.txt"));
+        assert_eq!(diff.header.impact.files_changed, 3);
+
+        // Applying patches the text and leaves the binaries alone
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(&work)?;
+        std::fs::write(work.join("notes.txt"), "one\n")?;
+        std::fs::write(work.join("logo.png"), [0x89])?;
+        let changed = DiffManager::with_backend(MemoryBackend::shared()).apply_diff(&diff, &work, ApplyMode::Apply)?;
+        assert_eq!(changed, vec!["notes.txt"]);
+        assert_eq!(std::fs::read(work.join("logo.png"))?, [0x89]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_revert_keeps_files_that_existed_empty() -> Result<()> {
+        let (dir, diff) = trees(
+            &[("empty.txt", ""), ("gone.txt", "old\n"), ("blank_gone.txt", "")],
+            &[("empty.txt", "filled\n"), ("blank_new.txt", "")],
+        )?;
+        assert_eq!(diff.file_changes.get("empty.txt"), None);
+        assert_eq!(diff.file_changes.get("blank_new.txt"), Some(&FileChange::Created));
+        assert_eq!(diff.file_changes.get("gone.txt"), Some(&FileChange::Deleted));
+
+        let work = dir.path().join("work");
+        let manager = DiffManager::with_backend(MemoryBackend::shared());
+        manager.apply_diff(&diff, &work, ApplyMode::Apply)?;
+        assert_eq!(std::fs::read_to_string(work.join("empty.txt"))?, "filled\n");
+        assert!(work.join("blank_new.txt").is_file());
+        assert!(!work.join("gone.txt").exists() && !work.join("blank_gone.txt").exists());
+
+        // The file was empty, not absent, before the diff: revert empties it
+        manager.apply_diff(&diff, &work, ApplyMode::Revert)?;
+        assert_eq!(std::fs::read_to_string(work.join("empty.txt"))?, "");
+        assert!(!work.join("blank_new.txt").exists());
+        assert_eq!(std::fs::read_to_string(work.join("gone.txt"))?, "old\n");
+        assert_eq!(std::fs::read_to_string(work.join("blank_gone.txt"))?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_rejects_drifted_files() -> Result<()> {
+        let (dir, diff) = trees(
+            &[("src/lib.rs", LIB_BEFORE), ("notes.txt", "keep\n")],
+            &[("src/lib.rs", LIB_AFTER), ("notes.txt", "kept\n")],
+        )?;
+        let work = dir.path().join("work");
+        std::fs::write(work.join("src/lib.rs"), LIB_BEFORE.replace("mod b;", "mod z;"))?;
+
+        let manager = DiffManager::with_backend(MemoryBackend::shared());
+        let err = manager.apply_diff(&diff, &work, ApplyMode::Apply).unwrap_err();
+        let conflict = err.downcast_ref::<ConflictError>().expect("a ConflictError");
+        assert_eq!(conflict.conflicts.len(), 1);
+        assert_eq!((conflict.conflicts[0].file_path.as_str(), conflict.conflicts[0].line), ("src/lib.rs", 2));
+        assert!(err.to_string().contains("expected \"mod b;\", found \"mod z;\""), "{}", err);
+
+        // Nothing was written, not even the file that would have applied
+        assert_eq!(std::fs::read_to_string(work.join("notes.txt"))?, "keep\n");
+
+        // Reverting a diff that was never applied conflicts too
+        let err = manager.apply_diff(&diff, dir.path().join("a"), ApplyMode::Revert).unwrap_err();
+        assert_eq!(err.downcast_ref::<ConflictError>().unwrap().conflicts.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_diffs_round_trip() -> Result<()> {
+        use crate::schemas::CompressionAlgorithm;
+
+        let old: String = (0..2000).map(|i| format!("let value_{} = {};\n", i, i)).collect();
+        let new: String = (0..2000).map(|i| format!("let value_{} = {};\n", i, i * 2)).collect();
+        let diff = builder().diff_files(&old, &new, "src/generated.rs");
+        let plain = serde_yaml::to_string(&diff)?;
+
+        let mut algorithms = vec![CompressionAlgorithm::Gzip];
+        if cfg!(feature = "zstd") {
+            algorithms.push(CompressionAlgorithm::Zstd);
+        }
+        for algorithm in algorithms {
+            let backend = MemoryBackend::shared();
+            let manager = DiffManager::with_backend(backend.clone()).with_compression(algorithm);
+            manager.save_diff(&diff)?;
+
+            let key = format!("{}/diff_0002.diff.yaml.{}", diff.header.run_id, algorithm.extension().unwrap());
+            let stored = backend.get(&key)?;
+            assert_eq!(CompressionAlgorithm::detect(&stored), algorithm);
+
+            let mut loaded = manager.load_diff(diff.header.run_id, "diff_0002")?;
+            let info = loaded.compression.take().expect("compressed diffs record their compression");
+            assert_eq!(info.algorithm, algorithm);
+            assert_eq!(serde_yaml::to_string(&loaded)?, plain);
+
+            // Exact up to the digits of the sizes themselves
+            let original = algorithm.decompress(&stored)?;
+            assert!(info.original_size.abs_diff(original.len() as u64) <= 16, "{:?}", info);
+            assert!(info.compressed_size.abs_diff(stored.len() as u64) <= 16, "{:?}", info);
+            assert!(info.compressed_size * 4 < info.original_size, "{:?}", info);
+
+            // Found by seq too, and readable by a manager that does not compress
+            assert!(manager.find_diff(diff.header.run_id, Seq(2))?.is_some());
+            DiffManager::with_backend(backend).load_diff(diff.header.run_id, "diff_0002")?;
+        }
+        Ok(())
+    }
+}
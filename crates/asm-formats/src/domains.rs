@@ -14,8 +14,11 @@
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use crate::lineage::sha256_hex;
+use crate::schemas::{CompressionAlgorithm, CompressionInfo};
 use crate::{RunId, Seq};
-use std::path::PathBuf;
+use anyhow::{bail, Context};
+use std::path::{Path, PathBuf};
 
 /// Universal artifact stored in HDF5 (any file type)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,11 +37,23 @@ pub struct ImmutableArtifact {
     /// Metadata about the artifact
     pub metadata: ArtifactMetadata,
 
-    /// Checksum for integrity verification
+    /// Checksum for integrity verification (see `content_checksum`)
     pub checksum: String,
 
     /// Size in bytes
     pub size_bytes: u64,
+
+    /// How the stored bytes are compressed, with the stored size;
+    /// `checksum` and `size_bytes` always describe the uncompressed content
+    #[serde(default)]
+    pub compression: Option<CompressionInfo>,
+}
+
+impl ImmutableArtifact {
+    /// Compression of the stored files (`None` if stored as is)
+    pub fn stored_compression(&self) -> CompressionAlgorithm {
+        self.compression.as_ref().map_or(CompressionAlgorithm::None, |info| info.algorithm)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +80,14 @@ pub enum ArtifactType {
     Template { template_type: String },
 }
 
+impl ArtifactType {
+    /// Libraries and templates are directory trees; everything else is a
+    /// single file
+    pub fn is_tree(&self) -> bool {
+        matches!(self, ArtifactType::Library { .. } | ArtifactType::Template { .. })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ImageFormat {
     PNG,
@@ -114,8 +137,13 @@ pub struct WorkingCopy {
     pub run_id: RunId,
     pub seq: Seq,
     pub source_artifact_id: String,
+    /// Type of the source artifact, carried over on commit
+    pub artifact_type: ArtifactType,
     pub created: DateTime<Utc>,
 
+    /// Bytes materialized from the source artifact
+    pub bytes_copied: u64,
+
     /// Working directory where copy resides
     pub working_path: PathBuf,
 
@@ -178,6 +206,114 @@ pub struct FileEntry {
     pub checksum: String,
 }
 
+/// Changes between two folder snapshots (drives incremental re-scans)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FolderDiff {
+    pub added: Vec<FileEntry>,
+    pub removed: Vec<FileEntry>,
+    pub modified: Vec<FileModification>,
+    pub renamed: Vec<FileRename>,
+    /// Change in total file size (new - old)
+    pub byte_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileModification {
+    pub path: PathBuf,
+    pub old_checksum: String,
+    pub new_checksum: String,
+    pub old_size_bytes: u64,
+    pub new_size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub checksum: String,
+}
+
+impl FolderDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.renamed.is_empty()
+    }
+}
+
+impl FolderSnapshot {
+    /// Compute what changed from `self` (older) to `other` (newer).
+    ///
+    /// A removed and an added file with the same checksum are reported as a
+    /// rename instead; when several candidates share a checksum they are
+    /// paired in path order.
+    pub fn diff(&self, other: &FolderSnapshot) -> FolderDiff {
+        use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+        let old: BTreeMap<&PathBuf, &FileEntry> = self.files.iter().map(|f| (&f.path, f)).collect();
+        let new: BTreeMap<&PathBuf, &FileEntry> = other.files.iter().map(|f| (&f.path, f)).collect();
+
+        let mut diff = FolderDiff::default();
+        let mut removed = Vec::new();
+
+        for (path, old_entry) in &old {
+            match new.get(path) {
+                Some(new_entry) if new_entry.checksum != old_entry.checksum => {
+                    diff.modified.push(FileModification {
+                        path: (*path).clone(),
+                        old_checksum: old_entry.checksum.clone(),
+                        new_checksum: new_entry.checksum.clone(),
+                        old_size_bytes: old_entry.size_bytes,
+                        new_size_bytes: new_entry.size_bytes,
+                    });
+                }
+                Some(_) => {}
+                None => removed.push(*old_entry),
+            }
+        }
+
+        // Removed files by checksum, consumed in path order as renames are found
+        let mut removed_by_checksum: HashMap<&str, VecDeque<&FileEntry>> = HashMap::new();
+        for entry in &removed {
+            removed_by_checksum.entry(entry.checksum.as_str()).or_default().push_back(entry);
+        }
+
+        let mut renamed_from = HashSet::new();
+        for (path, new_entry) in &new {
+            if old.contains_key(path) {
+                continue;
+            }
+            match removed_by_checksum
+                .get_mut(new_entry.checksum.as_str())
+                .and_then(|candidates| candidates.pop_front())
+            {
+                Some(old_entry) => {
+                    renamed_from.insert(&old_entry.path);
+                    diff.renamed.push(FileRename {
+                        from: old_entry.path.clone(),
+                        to: (*path).clone(),
+                        checksum: new_entry.checksum.clone(),
+                    });
+                }
+                None => diff.added.push((*new_entry).clone()),
+            }
+        }
+
+        diff.removed = removed
+            .into_iter()
+            .filter(|entry| !renamed_from.contains(&entry.path))
+            .cloned()
+            .collect();
+
+        let old_bytes: u64 = self.files.iter().map(|f| f.size_bytes).sum();
+        let new_bytes: u64 = other.files.iter().map(|f| f.size_bytes).sum();
+        diff.byte_delta = new_bytes as i64 - old_bytes as i64;
+
+        diff
+    }
+}
+
 //
 // DOMAIN 2: Logging
 //
@@ -311,6 +447,7 @@ pub enum BuildProfile {
 pub struct CopyOnWorkManager {
     immutable_store_path: PathBuf,
     working_dir: PathBuf,
+    compression: CompressionAlgorithm,
 }
 
 impl CopyOnWorkManager {
@@ -318,10 +455,34 @@ impl CopyOnWorkManager {
         Self {
             immutable_store_path: immutable_store,
             working_dir,
+            compression: CompressionAlgorithm::None,
         }
     }
 
-    /// Create working copy from immutable artifact
+    /// Compress the files of committed artifacts (each file on its own, so
+    /// tree artifacts keep their layout in the store)
+    pub fn with_compression(mut self, compression: CompressionAlgorithm) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Where an artifact's source lives: `source_path` as is when absolute,
+    /// otherwise relative to the immutable store
+    pub fn source_location(&self, artifact: &ImmutableArtifact) -> PathBuf {
+        let source = Path::new(&artifact.source_path);
+        if source.is_absolute() {
+            source.to_path_buf()
+        } else {
+            self.immutable_store_path.join(source)
+        }
+    }
+
+    /// Create working copy from immutable artifact. The source is checked
+    /// against `artifact.checksum` first (after decompression, if it is
+    /// stored compressed); a mismatch means the stored artifact is corrupt
+    /// (or the record is stale) and no copy is made. Tree artifacts are
+    /// copied into the working path, file artifacts become a single file
+    /// inside it.
     pub fn create_working_copy(
         &self,
         artifact: &ImmutableArtifact,
@@ -331,16 +492,57 @@ impl CopyOnWorkManager {
         let copy_id = format!("copy_{}_{}", run_id, seq.0);
         let working_path = self.working_dir.join(&copy_id);
 
-        // TODO: Implement actual copy from HDF5
-        // For now, create placeholder directory
+        let source = self.source_location(artifact);
+        if artifact.artifact_type.is_tree() != source.is_dir() {
+            bail!(
+                "Artifact {} should be a {}, but {} is not",
+                artifact.artifact_id,
+                if artifact.artifact_type.is_tree() { "directory" } else { "file" },
+                source.display()
+            );
+        }
+        let (checksum, _) = stored_checksum(&source, artifact.stored_compression())
+            .with_context(|| format!("Cannot read source of artifact {}", artifact.artifact_id))?;
+        if !checksum.eq_ignore_ascii_case(&artifact.checksum) {
+            bail!(
+                "Checksum mismatch for artifact {} ({}): expected {}, found {}; the stored copy may be corrupt",
+                artifact.artifact_id,
+                source.display(),
+                artifact.checksum,
+                checksum
+            );
+        }
+
+        let restore = |from: &Path, to: &Path| -> anyhow::Result<u64> {
+            let bytes = read_stored(from, artifact.stored_compression())?;
+            std::fs::write(to, &bytes)?;
+            Ok(bytes.len() as u64)
+        };
         std::fs::create_dir_all(&working_path)?;
+        let copied = if source.is_dir() {
+            copy_tree(&source, &working_path, &restore)?;
+            working_path.clone()
+        } else {
+            let target = working_path.join(source.file_name().unwrap_or(source.as_os_str()));
+            restore(&source, &target)?;
+            target
+        };
+
+        // The copy must hash like the source it came from
+        let (copied_checksum, bytes_copied) = content_checksum(&copied)?;
+        if copied_checksum != checksum {
+            let _ = std::fs::remove_dir_all(&working_path);
+            bail!("Working copy of artifact {} does not match its source", artifact.artifact_id);
+        }
 
         Ok(WorkingCopy {
             copy_id,
             run_id,
             seq,
             source_artifact_id: artifact.artifact_id.clone(),
+            artifact_type: artifact.artifact_type.clone(),
             created: Utc::now(),
+            bytes_copied,
             working_path,
             modifications: Vec::new(),
             status: WorkingCopyStatus::Active,
@@ -363,23 +565,51 @@ impl CopyOnWorkManager {
         });
     }
 
-    /// Commit working copy as new immutable version
+    /// Commit an active working copy as a new immutable artifact: its
+    /// contents are written to `<store>/<source id>_v<version>` (which must
+    /// not exist yet) and the copy is marked completed
     pub fn commit_as_immutable(
         &self,
-        copy: &WorkingCopy,
+        copy: &mut WorkingCopy,
         new_version: String,
     ) -> anyhow::Result<ImmutableArtifact> {
-        // TODO: Implement actual commit to HDF5
-        // For now, return placeholder
+        if !matches!(copy.status, WorkingCopyStatus::Active) {
+            bail!("Working copy {} is {:?}; only active copies can be committed", copy.copy_id, copy.status);
+        }
+        let content = if copy.artifact_type.is_tree() {
+            copy.working_path.clone()
+        } else {
+            single_file(&copy.working_path)
+                .with_context(|| format!("Cannot commit working copy {}", copy.copy_id))?
+        };
+        let (checksum, size_bytes) = content_checksum(&content)
+            .with_context(|| format!("Cannot read working copy {}", copy.copy_id))?;
 
-        Ok(ImmutableArtifact {
-            artifact_id: format!("{}_v{}", copy.source_artifact_id, new_version),
-            artifact_type: ArtifactType::Template {
-                template_type: "modified".to_string(),
-            },
+        let artifact_id = format!("{}_v{}", copy.source_artifact_id, new_version);
+        let store_dir = self.immutable_store_path.join(&artifact_id);
+        if store_dir.exists() {
+            bail!("Artifact {} already exists; committed artifacts are immutable", artifact_id);
+        }
+        let compression = self.compression;
+        let store = |from: &Path, to: &Path| -> anyhow::Result<u64> {
+            let bytes = compression.compress(&std::fs::read(from)?)?;
+            std::fs::write(to, &bytes)?;
+            Ok(bytes.len() as u64)
+        };
+        std::fs::create_dir_all(&store_dir)?;
+        let (source_path, stored_size) = if copy.artifact_type.is_tree() {
+            (PathBuf::from(&artifact_id), copy_tree(&content, &store_dir, &store)?)
+        } else {
+            let name = content.file_name().unwrap_or_default();
+            (Path::new(&artifact_id).join(name), store(&content, &store_dir.join(name))?)
+        };
+
+        let artifact = ImmutableArtifact {
+            artifact_id,
+            artifact_type: copy.artifact_type.clone(),
             version: new_version,
             created: Utc::now(),
-            source_path: copy.working_path.to_string_lossy().to_string(),
+            source_path: source_path.to_string_lossy().replace('\\', "/"),
             hdf5_path: String::new(),
             metadata: ArtifactMetadata {
                 author: None,
@@ -388,25 +618,119 @@ impl CopyOnWorkManager {
                 parent_artifact_id: Some(copy.source_artifact_id.clone()),
                 custom_fields: std::collections::HashMap::new(),
             },
-            checksum: String::new(),
-            size_bytes: 0,
-        })
+            checksum,
+            size_bytes,
+            compression: (compression != CompressionAlgorithm::None).then_some(CompressionInfo {
+                algorithm: compression,
+                original_size: size_bytes,
+                compressed_size: stored_size,
+            }),
+        };
+        if stored_checksum(&self.source_location(&artifact), compression)?.0 != artifact.checksum {
+            let _ = std::fs::remove_dir_all(&store_dir);
+            bail!("Stored copy of {} does not match the working copy", artifact.artifact_id);
+        }
+
+        copy.status = WorkingCopyStatus::Completed { outcome: format!("committed as {}", artifact.artifact_id) };
+        Ok(artifact)
     }
 
-    /// Discard working copy
+    /// Discard working copy. Only paths inside the working directory are
+    /// ever deleted.
     pub fn discard_working_copy(&self, copy: &mut WorkingCopy) -> anyhow::Result<()> {
-        copy.status = WorkingCopyStatus::Discarded;
-
-        // TODO: Implement actual cleanup
-        // For now, just mark as discarded
         if copy.working_path.exists() {
-            std::fs::remove_dir_all(&copy.working_path)?;
+            let working_dir = self.working_dir.canonicalize()?;
+            let path = copy.working_path.canonicalize()?;
+            if path == working_dir || !path.starts_with(&working_dir) {
+                bail!(
+                    "Refusing to delete {}: it is outside the working directory {}",
+                    copy.working_path.display(),
+                    self.working_dir.display()
+                );
+            }
+            std::fs::remove_dir_all(&path)?;
         }
 
+        copy.status = WorkingCopyStatus::Discarded;
         Ok(())
     }
 }
 
+/// The only file in `dir`
+fn single_file(dir: &Path) -> anyhow::Result<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    match files.as_slice() {
+        [file] => Ok(dir.join(file)),
+        _ => bail!("{} should hold exactly one file, found {}", dir.display(), files.len()),
+    }
+}
+
+/// SHA-256 (hex) and total size of a file or directory. A file hashes its
+/// bytes; a directory hashes one `relative/path:file-hash` line per file, in
+/// path order, so the same tree always gives the same checksum.
+pub fn content_checksum(path: &Path) -> anyhow::Result<(String, u64)> {
+    stored_checksum(path, CompressionAlgorithm::None)
+}
+
+/// `content_checksum` of stored content, decompressing each file first
+fn stored_checksum(path: &Path, compression: CompressionAlgorithm) -> anyhow::Result<(String, u64)> {
+    if !path.is_dir() {
+        let bytes = read_stored(path, compression)?;
+        return Ok((sha256_hex(&bytes), bytes.len() as u64));
+    }
+
+    let mut files = Vec::new();
+    collect_files(path, path, &mut files)?;
+    files.sort();
+
+    let mut manifest = String::new();
+    let mut size = 0;
+    for rel in files {
+        let bytes = read_stored(&path.join(&rel), compression)?;
+        size += bytes.len() as u64;
+        manifest.push_str(&format!("{}:{}\n", rel, sha256_hex(&bytes)));
+    }
+    Ok((sha256_hex(manifest.as_bytes()), size))
+}
+
+/// Relative `/`-separated paths of every file under `dir`
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let rel = path.strip_prefix(root).unwrap_or(&path);
+            files.push(rel.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+fn read_stored(path: &Path, compression: CompressionAlgorithm) -> anyhow::Result<Vec<u8>> {
+    compression
+        .decompress(&std::fs::read(path)?)
+        .with_context(|| format!("Cannot decompress {}", path.display()))
+}
+
+/// Copy every file under `from` to the same place under `to` with
+/// `copy_file`; returns the total it reports (bytes written)
+fn copy_tree(from: &Path, to: &Path, copy_file: &dyn Fn(&Path, &Path) -> anyhow::Result<u64>) -> anyhow::Result<u64> {
+    let mut written = 0;
+    for entry in std::fs::read_dir(from)? {
+        let path = entry?.path();
+        let target = to.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            written += copy_tree(&path, &target, copy_file)?;
+        } else {
+            written += copy_file(&path, &target)?;
+        }
+    }
+    Ok(written)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -420,15 +744,8 @@ mod tests {
         assert!(json.contains("Image"));
     }
 
-    #[test]
-    fn test_working_copy_creation() -> anyhow::Result<()> {
-        let temp_dir = tempfile::tempdir()?;
-        let manager = CopyOnWorkManager::new(
-            temp_dir.path().join("immutable"),
-            temp_dir.path().join("working"),
-        );
-
-        let artifact = ImmutableArtifact {
+    fn artifact(checksum: &str) -> ImmutableArtifact {
+        ImmutableArtifact {
             artifact_id: "test_001".to_string(),
             artifact_type: ArtifactType::SourceCode {
                 language: "rust".to_string(),
@@ -444,15 +761,201 @@ mod tests {
                 parent_artifact_id: None,
                 custom_fields: std::collections::HashMap::new(),
             },
-            checksum: "abc123".to_string(),
+            checksum: checksum.to_string(),
             size_bytes: 1024,
-        };
+            compression: None,
+        }
+    }
 
-        let copy = manager.create_working_copy(&artifact, RunId::new(), Seq::zero())?;
+    /// A manager whose store holds `src/main.rs`
+    fn manager_with_source(temp_dir: &Path) -> anyhow::Result<CopyOnWorkManager> {
+        std::fs::create_dir_all(temp_dir.join("immutable/src"))?;
+        std::fs::write(temp_dir.join("immutable/src/main.rs"), "fn main() {}\n")?;
+        Ok(CopyOnWorkManager::new(temp_dir.join("immutable"), temp_dir.join("working")))
+    }
+
+    #[test]
+    fn test_working_copy_creation() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = manager_with_source(temp_dir.path())?;
+        let checksum = sha256_hex(b"fn main() {}\n");
+
+        let copy = manager.create_working_copy(&artifact(&checksum), RunId::new(), Seq::zero())?;
 
         assert_eq!(copy.source_artifact_id, "test_001");
         assert!(matches!(copy.status, WorkingCopyStatus::Active));
+        assert_eq!(std::fs::read_to_string(copy.working_path.join("main.rs"))?, "fn main() {}\n");
 
         Ok(())
     }
+
+    #[test]
+    fn test_working_copy_rejects_checksum_mismatch() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = manager_with_source(temp_dir.path())?;
+
+        let err = manager.create_working_copy(&artifact("abc123"), RunId::new(), Seq::zero()).unwrap_err();
+
+        assert!(err.to_string().contains("Checksum mismatch for artifact test_001"), "{}", err);
+        assert!(!temp_dir.path().join("working").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_modify_commit() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = manager_with_source(temp_dir.path())?;
+        let mut copy = manager.create_working_copy(&artifact(&sha256_hex(b"fn main() {}\n")), RunId::new(), Seq::zero())?;
+        assert_eq!(copy.bytes_copied, 13);
+        std::fs::write(copy.working_path.join("main.rs"), "fn main() { run(); }\n")?;
+
+        let committed = manager.commit_as_immutable(&mut copy, "2".to_string())?;
+
+        assert_eq!(committed.artifact_id, "test_001_v2");
+        assert_eq!(committed.metadata.parent_artifact_id.as_deref(), Some("test_001"));
+        assert_eq!(committed.checksum, sha256_hex(b"fn main() { run(); }\n"));
+        assert_eq!(committed.size_bytes, 21);
+        assert_eq!(committed.source_path, "test_001_v2/main.rs");
+        assert!(matches!(copy.status, WorkingCopyStatus::Completed { .. }));
+
+        // Only active copies commit, and a version is never overwritten
+        assert!(manager.commit_as_immutable(&mut copy, "3".to_string()).is_err());
+        let mut other = manager.create_working_copy(&artifact(&sha256_hex(b"fn main() {}\n")), RunId::new(), Seq(1))?;
+        assert!(manager.commit_as_immutable(&mut other, "2".to_string()).unwrap_err().to_string().contains("immutable"));
+
+        // The stored artifact verifies as the source of a new working copy,
+        // independent of the working copy it came from
+        std::fs::write(copy.working_path.join("main.rs"), "scribbled")?;
+        let again = manager.create_working_copy(&committed, RunId::new(), Seq(2))?;
+        assert_eq!(std::fs::read_to_string(again.working_path.join("main.rs"))?, "fn main() { run(); }\n");
+
+        std::fs::write(temp_dir.path().join("immutable/test_001_v2/main.rs"), "corrupt")?;
+        assert!(manager.create_working_copy(&committed, RunId::new(), Seq(3)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_tree_artifact() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = manager_with_source(temp_dir.path())?;
+        let mut tree = artifact(&content_checksum(&temp_dir.path().join("immutable/src"))?.0);
+        tree.artifact_type = ArtifactType::Template { template_type: "crate".to_string() };
+        tree.source_path = "src".to_string();
+
+        let mut copy = manager.create_working_copy(&tree, RunId::new(), Seq::zero())?;
+        std::fs::create_dir_all(copy.working_path.join("assets"))?;
+        std::fs::write(copy.working_path.join("assets/logo.svg"), "<svg/>")?;
+        let committed = manager.commit_as_immutable(&mut copy, "2".to_string())?;
+
+        assert_eq!(committed.size_bytes, 13 + 6);
+        assert_eq!(content_checksum(&copy.working_path)?.0, committed.checksum);
+        let again = manager.create_working_copy(&committed, RunId::new(), Seq(1))?;
+        assert_eq!(std::fs::read_to_string(again.working_path.join("assets/logo.svg"))?, "<svg/>");
+
+        // A file artifact pointing at a directory is rejected
+        let mut mismatched = tree.clone();
+        mismatched.artifact_type = ArtifactType::SourceCode { language: "rust".to_string() };
+        assert!(manager.create_working_copy(&mismatched, RunId::new(), Seq(2)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_artifact_round_trip() -> anyhow::Result<()> {
+        let mut algorithms = vec![CompressionAlgorithm::Gzip];
+        if cfg!(feature = "zstd") {
+            algorithms.push(CompressionAlgorithm::Zstd);
+        }
+        for algorithm in algorithms {
+            let temp_dir = tempfile::tempdir()?;
+            let manager = manager_with_source(temp_dir.path())?.with_compression(algorithm);
+            let mut copy = manager.create_working_copy(&artifact(&sha256_hex(b"fn main() {}\n")), RunId::new(), Seq::zero())?;
+            let mesh = "v 0.0 0.0 0.0\n".repeat(4096);
+            std::fs::write(copy.working_path.join("main.rs"), &mesh)?;
+
+            let committed = manager.commit_as_immutable(&mut copy, "2".to_string())?;
+
+            let info = committed.compression.clone().expect("compressed artifacts record their compression");
+            assert_eq!(info.algorithm, algorithm);
+            assert_eq!(committed.size_bytes, mesh.len() as u64);
+            assert_eq!(info.original_size, committed.size_bytes);
+            assert!(info.compressed_size < info.original_size, "{:?}", info);
+            let stored = std::fs::read(temp_dir.path().join("immutable").join(&committed.source_path))?;
+            assert_eq!(CompressionAlgorithm::detect(&stored), algorithm);
+            assert_eq!(stored.len() as u64, info.compressed_size);
+
+            let restored = manager.create_working_copy(&committed, RunId::new(), Seq(1))?;
+            assert_eq!(std::fs::read_to_string(restored.working_path.join("main.rs"))?, mesh);
+            assert_eq!(restored.bytes_copied, mesh.len() as u64);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_discard_refuses_paths_outside_working_dir() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = manager_with_source(temp_dir.path())?;
+        let mut copy = manager.create_working_copy(&artifact(&sha256_hex(b"fn main() {}\n")), RunId::new(), Seq::zero())?;
+
+        let mut escaped = copy.clone();
+        escaped.working_path = temp_dir.path().join("working/../immutable");
+        assert!(manager.discard_working_copy(&mut escaped).unwrap_err().to_string().contains("outside"));
+        assert!(temp_dir.path().join("immutable/src/main.rs").exists());
+        assert!(matches!(escaped.status, WorkingCopyStatus::Active));
+
+        manager.discard_working_copy(&mut copy)?;
+        assert!(!copy.working_path.exists());
+        assert!(matches!(copy.status, WorkingCopyStatus::Discarded));
+        Ok(())
+    }
+
+    fn file(path: &str, checksum: &str, size_bytes: u64) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            size_bytes,
+            modified: Utc::now(),
+            checksum: checksum.to_string(),
+        }
+    }
+
+    fn snapshot(files: Vec<FileEntry>) -> FolderSnapshot {
+        FolderSnapshot {
+            snapshot_id: "snap".to_string(),
+            timestamp: Utc::now(),
+            folders: vec![],
+            total_size_bytes: files.iter().map(|f| f.size_bytes).sum(),
+            files,
+        }
+    }
+
+    #[test]
+    fn test_folder_snapshot_diff() {
+        let before = snapshot(vec![
+            file("src/main.rs", "aaa", 100),
+            file("src/lib.rs", "bbb", 200),
+            file("src/old.rs", "ccc", 50),
+            file("src/util.rs", "ddd", 30),
+        ]);
+        let after = snapshot(vec![
+            file("src/main.rs", "aaa", 100),
+            file("src/lib.rs", "bbb2", 260),
+            file("src/helpers/util.rs", "ddd", 30),
+            file("src/new.rs", "eee", 10),
+        ]);
+
+        let diff = before.diff(&after);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].path, PathBuf::from("src/new.rs"));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].path, PathBuf::from("src/old.rs"));
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].path, PathBuf::from("src/lib.rs"));
+        assert_eq!(diff.modified[0].new_checksum, "bbb2");
+        assert_eq!(diff.renamed.len(), 1);
+        assert_eq!(diff.renamed[0].from, PathBuf::from("src/util.rs"));
+        assert_eq!(diff.renamed[0].to, PathBuf::from("src/helpers/util.rs"));
+        assert_eq!(diff.byte_delta, 20);
+
+        assert!(after.diff(&after).is_empty());
+    }
 }
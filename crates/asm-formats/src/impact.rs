@@ -0,0 +1,145 @@
+//! Impact Computation
+//!
+//! Derives the `Impact` recorded in lineage from what a run actually did.
+//! A diff snapshot, when one exists, gives exact file and line counts;
+//! otherwise every target file of a run that modified objects counts as
+//! changed. Objects count what the executor touched; functions are left to
+//! callers that know them. Modules come from the changed files (as Rust module paths where
+//! they map to one), or from the modified objects when the run named no files.
+
+use crate::module_map::ModuleMapper;
+use crate::schemas::{DiffLineType, DiffSnapshot};
+use crate::Impact;
+use std::collections::BTreeSet;
+
+/// Computes `Impact` from execution results and diffs
+#[derive(Debug, Clone, Default)]
+pub struct ImpactCalculator {
+    mapper: ModuleMapper,
+}
+
+impl ImpactCalculator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map changed files to modules with `mapper` (e.g. one that knows
+    /// `runtime/daemon` is the `runtime_daemon` crate)
+    pub fn with_mapper(mut self, mapper: ModuleMapper) -> Self {
+        self.mapper = mapper;
+        self
+    }
+
+    /// Impact of a run that modified `modified_objects` while working on
+    /// `target_files`, with its diff if one was captured
+    pub fn compute(&self, modified_objects: &[String], target_files: &[String], diff: Option<&DiffSnapshot>) -> Impact {
+        let mut impact = Impact { objects_affected: modified_objects.len(), ..Impact::default() };
+
+        let files: BTreeSet<&str> = match diff {
+            Some(diff) => {
+                for line in diff.hunks.iter().flat_map(|hunk| &hunk.lines) {
+                    match line.line_type {
+                        DiffLineType::Addition => impact.lines_added += 1,
+                        DiffLineType::Removal => impact.lines_removed += 1,
+                        DiffLineType::Context => {}
+                    }
+                }
+                diff.hunks.iter().map(|hunk| hunk.file_path.as_str()).chain(diff.binary_files.iter().map(String::as_str)).collect()
+            }
+            // Without a diff, a target only counts once the run touched something
+            None if modified_objects.is_empty() => BTreeSet::new(),
+            None => target_files.iter().map(String::as_str).collect(),
+        };
+        impact.files_changed = files.len();
+
+        let modules: BTreeSet<String> = if files.is_empty() {
+            modified_objects.iter().cloned().collect()
+        } else {
+            files.iter().map(|file| self.mapper.module_for(file).unwrap_or_else(|| file.to_string())).collect()
+        };
+        impact.modules_affected = modules.into_iter().collect();
+        impact
+    }
+}
+
+impl Impact {
+    /// Whether nothing at all was recorded
+    pub fn is_zero(&self) -> bool {
+        self.files_changed == 0
+            && self.lines_added == 0
+            && self.lines_removed == 0
+            && self.functions_affected == 0
+            && self.objects_affected == 0
+            && self.modules_affected.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{DiffHeader, DiffHunk, DiffLine};
+    use crate::{Actor, Confidence, RunId, Seq};
+
+    fn hunk(file_path: &str, lines: &[(DiffLineType, &str)]) -> DiffHunk {
+        DiffHunk {
+            file_path: file_path.to_string(),
+            old_start: 1,
+            old_count: 0,
+            new_start: 1,
+            new_count: 0,
+            lines: lines
+                .iter()
+                .map(|(line_type, content)| DiffLine::new(*line_type, *content))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_impact_from_diff_counts_lines() {
+        use DiffLineType::{Addition, Context, Removal};
+
+        let diff = DiffSnapshot {
+            header: DiffHeader {
+                diff_id: "diff_0".to_string(),
+                run_id: RunId::new(),
+                seq: Seq::zero(),
+                timestamp: chrono::Utc::now(),
+                actor: Actor::System,
+                summary: "Fix imports".to_string(),
+                confidence: Confidence(1.0),
+                intent: "Repair build".to_string(),
+                tests: vec![],
+                impact: Impact::default(),
+            },
+            hunks: vec![
+                hunk("crates/oasm-core/src/executor/mod.rs", &[(Context, "use a;"), (Removal, "use b;"), (Addition, "use c;"), (Addition, "use d;")]),
+                hunk("crates/oasm-core/src/executor/mod.rs", &[(Removal, "fn old() {}")]),
+                hunk("README.md", &[(Addition, "Notes")]),
+            ],
+            file_changes: Default::default(),
+            binary_files: vec![],
+            compression: None,
+        };
+        let objects = vec!["repair_block_0000".to_string()];
+
+        let impact = ImpactCalculator::new().compute(&objects, &["src/unrelated.rs".to_string()], Some(&diff));
+        assert_eq!((impact.files_changed, impact.lines_added, impact.lines_removed), (2, 3, 2));
+        assert_eq!((impact.objects_affected, impact.functions_affected), (1, 0));
+        assert_eq!(impact.modules_affected, vec!["README.md", "oasm_core::executor"]);
+    }
+
+    #[test]
+    fn test_impact_without_diff_counts_touched_targets() {
+        let calculator = ImpactCalculator::new().with_mapper(ModuleMapper::new().with_crate("runtime/daemon", "runtime_daemon"));
+        let targets = vec!["runtime/daemon/src/commit.rs".to_string(), "docs/guide.md".to_string()];
+        let objects = vec!["lint_check_0000".to_string()];
+
+        let impact = calculator.compute(&objects, &targets, None);
+        assert_eq!((impact.files_changed, impact.lines_added, impact.lines_removed), (2, 0, 0));
+        assert_eq!(impact.modules_affected, vec!["docs/guide.md", "runtime_daemon::commit"]);
+
+        // Nothing touched, nothing changed; no targets falls back to the objects
+        assert!(calculator.compute(&[], &targets, None).is_zero());
+        assert_eq!(calculator.compute(&objects, &[], None).modules_affected, objects);
+    }
+}
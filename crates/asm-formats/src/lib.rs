@@ -12,38 +12,20 @@ pub mod schemas;
 pub mod templates;
 pub mod runtime;
 pub mod lineage;
+pub mod module_map;
 pub mod converters;
 pub mod domains;
+pub mod storage;
+pub mod config_hash;
+pub mod impact;
+pub mod diff;
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use uuid::Uuid;
 
-/// Universal identifier for tracking runs, sequences, and diffs
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct RunId(pub Uuid);
-
-impl RunId {
-    pub fn new() -> Self {
-        Self(Uuid::new_v4())
-    }
-
-    pub fn from_string(s: &str) -> anyhow::Result<Self> {
-        Ok(Self(Uuid::parse_str(s)?))
-    }
-}
-
-impl Default for RunId {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl std::fmt::Display for RunId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
+/// Universal identifier for tracking runs, sequences, and diffs; shared
+/// with the executor so lineage keys follow its deterministic mode
+pub use oasm_core::context::RunId;
 
 /// Sequence number within a run
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -69,30 +51,7 @@ pub enum Actor {
 }
 
 /// Confidence level for automated actions
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
-pub struct Confidence(pub f64);
-
-impl Confidence {
-    pub fn new(value: f64) -> Self {
-        Self(value.clamp(0.0, 1.0))
-    }
-
-    pub fn high() -> Self {
-        Self(0.9)
-    }
-
-    pub fn medium() -> Self {
-        Self(0.7)
-    }
-
-    pub fn low() -> Self {
-        Self(0.5)
-    }
-
-    pub fn exceeds_threshold(&self, threshold: f64) -> bool {
-        self.0 >= threshold
-    }
-}
+pub use oasm_core::context::Confidence;
 
 /// Test execution status
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -111,6 +70,9 @@ pub struct Impact {
     pub lines_added: usize,
     pub lines_removed: usize,
     pub functions_affected: usize,
+    /// CBOR objects the run created or modified
+    #[serde(default)]
+    pub objects_affected: usize,
     pub modules_affected: Vec<String>,
 }
 
@@ -128,14 +90,23 @@ pub struct ExecutionMetadata {
 impl ExecutionMetadata {
     pub fn new(actor: Actor) -> Self {
         Self {
-            run_id: RunId::new(),
+            run_id: RunId::generate(),
             seq: Seq::zero(),
             timestamp: Utc::now(),
             actor,
             tool_versions: ToolVersions::current(),
-            config_hash: String::new(), // Populated by caller
+            config_hash: String::new(), // See with_config
         }
     }
+
+    /// Metadata with `config_hash` set to the canonical hash of `config`
+    /// (see `config_hash::compute_config_hash`)
+    pub fn with_config(actor: Actor, config: &impl Serialize) -> anyhow::Result<Self> {
+        Ok(Self {
+            config_hash: config_hash::compute_config_hash(config)?,
+            ..Self::new(actor)
+        })
+    }
 }
 
 /// Tool version tracking for provenance
@@ -147,17 +118,64 @@ pub struct ToolVersions {
     pub python_version: Option<String>,
 }
 
+/// Recorded when the Rust toolchain could not be probed
+pub const UNKNOWN_VERSION: &str = "unknown";
+
 impl ToolVersions {
+    /// Versions of the toolchain on this machine, probed once per process
+    /// (`rustc --version --verbose`, then `python3`/`python --version`).
+    /// A missing tool is recorded as `UNKNOWN_VERSION` or None, never an error.
     pub fn current() -> Self {
+        static DETECTED: std::sync::OnceLock<ToolVersions> = std::sync::OnceLock::new();
+        DETECTED.get_or_init(|| Self::detect_with("rustc", &["python3", "python"])).clone()
+    }
+
+    fn detect_with(rustc: &str, pythons: &[&str]) -> Self {
+        let (rust_version, llvm_version) = tool_output(rustc, &["--version", "--verbose"])
+            .and_then(|out| parse_rustc_version(&out))
+            .unwrap_or_else(|| (UNKNOWN_VERSION.to_string(), None));
+        let python_version = pythons
+            .iter()
+            .find_map(|python| tool_output(python, &["--version"]).and_then(|out| parse_python_version(&out)));
+
         Self {
             oasm_version: env!("CARGO_PKG_VERSION").to_string(),
-            rust_version: "1.70+".to_string(), // TODO: detect actual version
-            llvm_version: None,
-            python_version: None,
+            rust_version,
+            llvm_version,
+            python_version,
         }
     }
 }
 
+/// stdout and stderr of a successful run (python 2 prints its version to stderr)
+fn tool_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Release and LLVM version from `rustc --version --verbose` output
+pub fn parse_rustc_version(output: &str) -> Option<(String, Option<String>)> {
+    let field = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    Some((field("release:")?, field("LLVM version:")))
+}
+
+/// Version from `python --version` output (`Python 3.11.4`)
+pub fn parse_python_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Python "))
+        .map(|version| version.trim().to_string())
+}
+
 /// Format type enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FormatType {
@@ -183,14 +201,7 @@ pub enum DuplicateStrategy {
 }
 
 /// Language/runtime choice (popup decision point)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Language {
-    CPlusPlus,
-    Python,
-    Rust,
-    Go,
-    JavaScript,
-}
+pub use oasm_core::state_evaluator::Language;
 
 /// User decision from popup
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -212,6 +223,17 @@ mod tests {
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn test_metadata_run_id_follows_deterministic_mode() {
+        let previous = oasm_core::context::set_deterministic_seed(Some(7));
+        let first = ExecutionMetadata::new(Actor::System);
+        let second = ExecutionMetadata::new(Actor::System);
+        oasm_core::context::set_deterministic_seed(previous);
+
+        assert_eq!((first.run_id, second.run_id), (RunId::from_seed(7), RunId::from_seed(8)));
+        assert_eq!(RunId::from_string(&first.run_id.to_string()).unwrap(), first.run_id);
+    }
+
     #[test]
     fn test_seq_ordering() {
         let seq1 = Seq(1);
@@ -235,4 +257,64 @@ mod tests {
         assert!(c.exceeds_threshold(0.8));
         assert!(!c.exceeds_threshold(0.9));
     }
+
+    #[test]
+    fn test_parse_rustc_version_verbose() {
+        let output = "rustc 1.78.0 (9b00956e5 2024-04-29)\nbinary: rustc\ncommit-hash: 9b00956e56009bab2aa15d7bff10916599e3d6d6\ncommit-date: 2024-04-29\nhost: x86_64-unknown-linux-gnu\nrelease: 1.78.0\nLLVM version: 18.1.2\n";
+        assert_eq!(parse_rustc_version(output), Some(("1.78.0".to_string(), Some("18.1.2".to_string()))));
+
+        // Toolchains built without LLVM info still report the release
+        assert_eq!(parse_rustc_version("rustc 1.80.0-nightly\nrelease: 1.80.0-nightly\n"), Some(("1.80.0-nightly".to_string(), None)));
+        assert_eq!(parse_rustc_version("error: no such command"), None);
+
+        assert_eq!(parse_python_version("Python 3.11.4\n"), Some("3.11.4".to_string()));
+        assert_eq!(parse_python_version("command not found"), None);
+    }
+
+    #[test]
+    fn test_detection_failure_falls_back_to_unknown() {
+        let versions = ToolVersions::detect_with("oasm-no-such-rustc", &["oasm-no-such-python"]);
+        assert_eq!(versions.rust_version, UNKNOWN_VERSION);
+        assert_eq!(versions.llvm_version, None);
+        assert_eq!(versions.python_version, None);
+        assert_eq!(versions.oasm_version, env!("CARGO_PKG_VERSION"));
+
+        // Probed once, and the same values land in execution metadata
+        assert_eq!(ToolVersions::current().rust_version, ExecutionMetadata::new(Actor::System).tool_versions.rust_version);
+    }
+
+    #[test]
+    fn test_metadata_with_config() -> anyhow::Result<()> {
+        let hasher = config_hash::ConfigHasher::from_yaml("scanner:\n  depth: 3\n")?;
+        let metadata = ExecutionMetadata::with_config(Actor::System, hasher.effective())?;
+        assert_eq!(metadata.config_hash, hasher.hash());
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_config_hash_ignores_map_order() -> anyhow::Result<()> {
+        use serde_yaml::{Mapping, Value};
+
+        // Mappings keep insertion order, so these serialize differently
+        let config = |keys: &[&str]| {
+            let mut scanner = Mapping::new();
+            let mut config = Mapping::new();
+            for key in keys {
+                scanner.insert(Value::from(*key), Value::from(key.len()));
+                config.insert(Value::from(format!("{}_enabled", key)), Value::from(true));
+            }
+            config.insert(Value::from("scanner"), Value::Mapping(scanner));
+            config
+        };
+        let a = config(&["depth", "arms", "exclude"]);
+        let b = config(&["exclude", "depth", "arms"]);
+        assert_ne!(serde_yaml::to_string(&a)?, serde_yaml::to_string(&b)?);
+
+        let hash_a = ExecutionMetadata::with_config(Actor::System, &a)?.config_hash;
+        let hash_b = ExecutionMetadata::with_config(Actor::Human { username: "alice".to_string() }, &b)?.config_hash;
+        assert_eq!(hash_a, hash_b);
+        assert_eq!(hash_a, config_hash::compute_config_hash(&a)?);
+        assert_ne!(hash_a, config_hash::compute_config_hash(&config(&["depth", "arms"]))?);
+        Ok(())
+    }
 }
@@ -9,25 +9,327 @@
 //! - YAML: Captures annotations and human decisions from overlays
 //! - JSON: Standalone format optimized for Git diffs and audit trails
 
-use crate::schemas::{JSONLineage, ExecutionOutcome, Provenance, TestRecord, DiffSnapshot};
-use crate::{RunId, Seq, Actor, Impact};
-use anyhow::Result;
-use std::path::Path;
-use chrono::Utc;
+use crate::schemas::{
+    JSONLineage, ExecutionOutcome, Provenance, TestRecord, DiffSnapshot, DiffReference, SessionIndex, SessionTotals,
+    FieldOrigin, CompressionAlgorithm, CompressionInfo, FileChange,
+};
+use crate::config_hash::ConfigHashPolicy;
+use crate::module_map::ModuleMapper;
+use crate::diff::{patch_text, ApplyMode, ConflictError};
+use crate::storage::{join_key, normalize_key, FsBackend, StorageBackend};
+use crate::{RunId, Seq, Actor, Impact, TestStatus};
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+
+/// Placeholder written over redacted values
+pub const REDACTED: &str = "[redacted]";
+
+/// Lineage fields that can be scrubbed before sharing a log externally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactField {
+    /// `Actor::Human` username
+    ActorUsername,
+    /// Path-like tokens in summary, intent, command, test logs and affected modules
+    FilePaths,
+    /// `provenance.config_hash`
+    ConfigHash,
+}
+
+/// How redacted values are replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactStyle {
+    /// Replace with `[redacted]`
+    Placeholder,
+    /// Replace with a short SHA-256 digest so equal values still correlate
+    Hash,
+}
+
+impl RedactStyle {
+    fn apply(&self, value: &str) -> String {
+        match self {
+            RedactStyle::Placeholder => REDACTED.to_string(),
+            RedactStyle::Hash => {
+                let digest = Sha256::digest(value.as_bytes());
+                let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+                format!("sha256:{}", hex)
+            }
+        }
+    }
+
+    fn apply_to_paths(&self, text: &str) -> String {
+        text.split(' ')
+            .map(|token| {
+                if token.contains('/') || token.contains('\\') {
+                    self.apply(token)
+                } else {
+                    token.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl JSONLineage {
+    /// Copy of this entry with the chosen fields replaced by `[redacted]`
+    pub fn redact(&self, fields: &[RedactField]) -> JSONLineage {
+        self.redact_with(fields, RedactStyle::Placeholder)
+    }
+
+    /// Copy of this entry with the chosen fields redacted in the given style
+    pub fn redact_with(&self, fields: &[RedactField], style: RedactStyle) -> JSONLineage {
+        let mut redacted = self.clone();
+
+        for field in fields {
+            match field {
+                RedactField::ActorUsername => {
+                    if let Actor::Human { username } = &mut redacted.actor {
+                        *username = style.apply(username);
+                    }
+                }
+                RedactField::FilePaths => {
+                    redacted.summary = style.apply_to_paths(&redacted.summary);
+                    redacted.intent = style.apply_to_paths(&redacted.intent);
+                    redacted.command_executed = style.apply_to_paths(&redacted.command_executed);
+                    for module in &mut redacted.impact.modules_affected {
+                        *module = style.apply_to_paths(module);
+                    }
+                    for test in &mut redacted.tests {
+                        for line in &mut test.logs {
+                            *line = style.apply_to_paths(line);
+                        }
+                    }
+                }
+                RedactField::ConfigHash => {
+                    redacted.provenance.config_hash = style.apply(&redacted.provenance.config_hash);
+                }
+            }
+        }
+
+        redacted
+    }
+}
+
+/// Name of the per-run file recording the on-disk layout version
+pub const LAYOUT_MARKER: &str = "layout_version";
+
+/// Number of seq files per shard directory
+pub const SHARD_SIZE: u64 = 1000;
+
+/// Parent runs `build_full_ancestry` follows before giving up
+pub const DEFAULT_MAX_ANCESTRY_DEPTH: usize = 64;
+
+/// Storage layout of a run's keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineageLayout {
+    /// v1: run_id/seq_0042.json (no marker file)
+    Flat,
+    /// v2: run_id/shard_000/seq_0000000042.json
+    Sharded,
+}
+
+impl LineageLayout {
+    pub fn version(&self) -> u32 {
+        match self {
+            LineageLayout::Flat => 1,
+            LineageLayout::Sharded => 2,
+        }
+    }
+
+    fn entry_key(&self, run_prefix: &str, seq: Seq) -> String {
+        match self {
+            LineageLayout::Flat => join_key(&[run_prefix, &format!("seq_{:04}.json", seq.0)]),
+            LineageLayout::Sharded => join_key(&[
+                run_prefix,
+                &format!("shard_{:03}", seq.0 / SHARD_SIZE),
+                &format!("seq_{:010}.json", seq.0),
+            ]),
+        }
+    }
+
+    /// Seq of a key below the run prefix (`rest` excludes `<run>/`), if the
+    /// key is an entry in this layout
+    fn seq_of(&self, rest: &str) -> Option<Seq> {
+        let name = match (self, rest.split_once('/')) {
+            (LineageLayout::Flat, None) => rest,
+            (LineageLayout::Sharded, Some((shard, name))) if shard.starts_with("shard_") && !name.contains('/') => name,
+            _ => return None,
+        };
+        seq_from_file_name(name)
+    }
+}
+
+/// Parse the seq number out of a `seq_N.json` file name
+fn seq_from_file_name(name: &str) -> Option<Seq> {
+    name.strip_suffix(".json")?.strip_prefix("seq_")?.parse().ok().map(Seq)
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lazy, seq-ordered iterator over a run's lineage entries (either layout)
+pub struct LineageIter {
+    backend: Arc<dyn StorageBackend>,
+    keys: std::vec::IntoIter<(Seq, String)>,
+}
+
+impl Iterator for LineageIter {
+    type Item = Result<JSONLineage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, key) = self.keys.next()?;
+        Some(read_entry(self.backend.as_ref(), &key))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl ExactSizeIterator for LineageIter {}
+
+fn read_entry(backend: &dyn StorageBackend, key: &str) -> Result<JSONLineage> {
+    backend
+        .get(key)
+        .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+        .with_context(|| format!("Failed to read lineage entry {}", key))
+}
+
+/// Collects test records produced while a step runs (e.g. in-script
+/// assertions) and attaches them to that step's lineage entry
+#[derive(Debug, Default)]
+pub struct LineageHook {
+    pending: Vec<TestRecord>,
+}
+
+impl LineageHook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_test(&mut self, record: TestRecord) {
+        self.pending.push(record);
+    }
+
+    pub fn pending(&self) -> &[TestRecord] {
+        &self.pending
+    }
+
+    /// Move pending records into `lineage.tests`
+    pub fn attach(&mut self, lineage: &mut JSONLineage) {
+        lineage.tests.append(&mut self.pending);
+    }
+
+    /// Attach pending records to a stored lineage entry and save it
+    pub fn flush(&mut self, manager: &LineageManager, run_id: RunId, seq: Seq) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut lineage = manager.load(run_id, seq)?;
+        manager.update_session(run_id, |session| session.add_totals(tests_delta(&self.pending)))?;
+        self.attach(&mut lineage);
+        manager.save(&lineage)
+    }
+}
+
+/// HEAD commit and working tree state of a git repo
+struct GitState {
+    sha: String,
+    dirty: bool,
+}
+
+/// None if `repo_root` is not inside a git repo (or git is unavailable)
+fn git_state(repo_root: &Path) -> Option<GitState> {
+    let git = |args: &[&str]| {
+        let output = Command::new("git").arg("-C").arg(repo_root).args(args).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let sha = git(&["rev-parse", "HEAD"])?;
+    let dirty = git(&["status", "--porcelain"]).is_some_and(|status| !status.is_empty());
+    Some(GitState { sha, dirty })
+}
 
 /// Lineage manager for tracking execution history
 pub struct LineageManager {
-    lineage_dir: std::path::PathBuf,
+    backend: Arc<dyn StorageBackend>,
+    /// Repo whose HEAD and dirty state are captured by `record`
+    repo_root: Option<PathBuf>,
+    /// Stamped on provenance recorded without a config hash
+    config_hash: Option<String>,
+    /// How `record` treats provenance without a config hash
+    config_hash_policy: ConfigHashPolicy,
+    /// Parent runs `build_full_ancestry` follows before giving up
+    max_ancestry_depth: usize,
+    /// One lock per run, serializing its session index updates (read,
+    /// change, write) among writers sharing this manager
+    session_locks: Mutex<HashMap<RunId, Arc<Mutex<()>>>>,
 }
 
 impl LineageManager {
+    /// Lineage stored on local disk under `lineage_dir`
     pub fn new(lineage_dir: impl AsRef<Path>) -> Self {
+        Self::with_backend(Arc::new(FsBackend::new(lineage_dir)))
+    }
+
+    /// Lineage stored in any backend (keys are `<run_id>/...`)
+    pub fn with_backend(backend: Arc<dyn StorageBackend>) -> Self {
         Self {
-            lineage_dir: lineage_dir.as_ref().to_path_buf(),
+            backend,
+            repo_root: None,
+            config_hash: None,
+            config_hash_policy: ConfigHashPolicy::default(),
+            max_ancestry_depth: DEFAULT_MAX_ANCESTRY_DEPTH,
+            session_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fill the empty `config_hash` of recorded provenance with `config_hash`
+    /// (see `config_hash::project_config_hash`)
+    pub fn with_config_hash(mut self, config_hash: impl Into<String>) -> Self {
+        self.config_hash = Some(config_hash.into());
+        self
+    }
+
+    /// `with_config_hash` for the project at `root` (its `oasm.config.yaml`);
+    /// a configuration that cannot be read leaves the manager unchanged
+    pub fn with_project_config(self, root: impl AsRef<Path>) -> Self {
+        match crate::config_hash::project_config_hash(root) {
+            Some(hash) => self.with_config_hash(hash),
+            None => self,
         }
     }
 
-    /// Record a new lineage entry
+    /// Warn about (default), allow or reject recording entries whose
+    /// provenance has an empty `config_hash`
+    pub fn with_config_hash_policy(mut self, policy: ConfigHashPolicy) -> Self {
+        self.config_hash_policy = policy;
+        self
+    }
+
+    /// Follow at most `depth` parent runs in `build_full_ancestry`
+    pub fn with_max_ancestry_depth(mut self, depth: usize) -> Self {
+        self.max_ancestry_depth = depth;
+        self
+    }
+
+    /// Capture the commit and dirty state of the git repo at `repo_root` on
+    /// every `record`. Outside a git repo both stay unset.
+    pub fn with_repo_root(mut self, repo_root: impl AsRef<Path>) -> Self {
+        self.repo_root = Some(repo_root.as_ref().to_path_buf());
+        self
+    }
+
+    /// Record a new lineage entry; `command` is the OASM source that ran
+    /// (empty when there is none), kept for `reconstruct_source`
+    #[allow(clippy::too_many_arguments)]
     pub fn record(
         &self,
         run_id: RunId,
@@ -35,82 +337,305 @@ impl LineageManager {
         actor: Actor,
         summary: impl Into<String>,
         intent: impl Into<String>,
+        command: impl Into<String>,
         outcome: ExecutionOutcome,
         provenance: Provenance,
         impact: Impact,
+    ) -> Result<JSONLineage> {
+        let lineage = self.prepare(run_id, seq, actor, summary, intent, command, outcome, provenance, impact)?;
+        self.commit(&lineage, &[])?;
+        Ok(lineage)
+    }
+
+    /// Build a lineage entry as `record` would, without saving it; finish
+    /// it (e.g. set `diff_id`) and `commit` it
+    #[allow(clippy::too_many_arguments)]
+    pub fn prepare(
+        &self,
+        run_id: RunId,
+        seq: Seq,
+        actor: Actor,
+        summary: impl Into<String>,
+        intent: impl Into<String>,
+        command: impl Into<String>,
+        outcome: ExecutionOutcome,
+        mut provenance: Provenance,
+        impact: Impact,
     ) -> Result<JSONLineage> {
         let lineage_id = format!("{}_{}", run_id, seq.0);
+        if provenance.config_hash.is_empty() {
+            if let Some(hash) = &self.config_hash {
+                provenance.config_hash = hash.clone();
+            }
+        }
+        self.config_hash_policy
+            .check(&provenance.config_hash, format_args!("Lineage entry {}", lineage_id))?;
+        let git = self.repo_root.as_deref().and_then(git_state);
+        if let Some(git) = &git {
+            provenance.git_dirty.get_or_insert(git.dirty);
+        }
 
-        let lineage = JSONLineage {
-            lineage_id: lineage_id.clone(),
+        Ok(JSONLineage {
+            lineage_id,
             run_id,
             seq,
             timestamp: Utc::now(),
             actor,
             summary: summary.into(),
             intent: intent.into(),
-            command_executed: String::new(), // Populated by caller
+            command_executed: command.into(),
             outcome,
             provenance,
             impact,
             tests: Vec::new(),
             diff_id: None,
-            git_sha: None,
-        };
+            git_sha: git.map(|git| git.sha),
+            origin: None,
+        })
+    }
 
-        self.save(&lineage)?;
+    /// Save a prepared entry and count it in its run's session. Once the
+    /// session is closed the run is sealed: committing to it is an error and
+    /// the entry is not saved.
+    /// `modified_objects` are what the run changed: a successful entry that
+    /// modified objects yet records no impact gets a warning, since its
+    /// caller never computed one.
+    pub fn commit(&self, lineage: &JSONLineage, modified_objects: &[String]) -> Result<()> {
+        if matches!(lineage.outcome, ExecutionOutcome::Success) && lineage.impact.is_zero() && !modified_objects.is_empty() {
+            log::warn!(
+                "Lineage entry {} modified {} object(s) but records no impact",
+                lineage.lineage_id,
+                modified_objects.len()
+            );
+        }
+        self.update_session(lineage.run_id, |session| session.record_entry(lineage))?;
+        self.save(lineage)
+    }
 
-        Ok(lineage)
+    fn run_prefix(run_id: RunId) -> String {
+        run_id.to_string()
     }
 
-    /// Save lineage entry to disk (JSON format, Git-friendly)
-    pub fn save(&self, lineage: &JSONLineage) -> Result<()> {
-        std::fs::create_dir_all(&self.lineage_dir)?;
+    /// Layout of an existing run (runs without a marker are v1)
+    pub fn layout(&self, run_id: RunId) -> Result<LineageLayout> {
+        self.read_layout(&Self::run_prefix(run_id))
+    }
+
+    fn read_layout(&self, run_prefix: &str) -> Result<LineageLayout> {
+        let marker = join_key(&[run_prefix, LAYOUT_MARKER]);
+        if !self.backend.exists(&marker)? {
+            return Ok(LineageLayout::Flat);
+        }
+        match String::from_utf8_lossy(&self.backend.get(&marker)?).trim() {
+            "1" => Ok(LineageLayout::Flat),
+            "2" => Ok(LineageLayout::Sharded),
+            other => bail!("Unsupported lineage layout version '{}' in {}", other, marker),
+        }
+    }
 
-        // Organize by run_id for easy browsing
-        let run_dir = self.lineage_dir.join(lineage.run_id.to_string());
-        std::fs::create_dir_all(&run_dir)?;
+    fn write_layout(&self, run_prefix: &str, layout: LineageLayout) -> Result<()> {
+        self.backend.put_atomic(&join_key(&[run_prefix, LAYOUT_MARKER]), format!("{}\n", layout.version()).as_bytes())
+    }
 
-        let path = run_dir.join(format!("seq_{:04}.json", lineage.seq.0));
+    /// Save lineage entry (JSON format, Git-friendly)
+    pub fn save(&self, lineage: &JSONLineage) -> Result<()> {
+        // Organize by run_id for easy browsing; new runs use the sharded layout
+        let run_prefix = Self::run_prefix(lineage.run_id);
+        let layout = self.read_layout(&run_prefix)?;
+        let is_new_run = layout == LineageLayout::Flat
+            && self.backend.list_prefix(&join_key(&[&run_prefix, "seq_"]))?.is_empty();
+        let layout = if is_new_run {
+            self.write_layout(&run_prefix, LineageLayout::Sharded)?;
+            LineageLayout::Sharded
+        } else {
+            layout
+        };
 
         // Pretty JSON for Git-friendly diffs
         let json = serde_json::to_string_pretty(lineage)?;
-        std::fs::write(path, json)?;
-
-        Ok(())
+        self.backend.put_atomic(&layout.entry_key(&run_prefix, lineage.seq), json.as_bytes())
     }
 
     /// Load lineage entry
     pub fn load(&self, run_id: RunId, seq: Seq) -> Result<JSONLineage> {
-        let path = self.lineage_dir
-            .join(run_id.to_string())
-            .join(format!("seq_{:04}.json", seq.0));
+        let run_prefix = Self::run_prefix(run_id);
+        let key = self.read_layout(&run_prefix)?.entry_key(&run_prefix, seq);
+
+        Ok(serde_json::from_slice(&self.backend.get(&key)?)?)
+    }
+
+    /// All entry keys of a run in the given layout, sorted by seq
+    fn list_entries(&self, run_prefix: &str, layout: LineageLayout) -> Result<Vec<(Seq, String)>> {
+        let prefix = format!("{}/", run_prefix);
+        let mut entries: Vec<(Seq, String)> = self
+            .backend
+            .list_prefix(&prefix)?
+            .into_iter()
+            .filter_map(|key| Some((layout.seq_of(&key[prefix.len()..])?, key)))
+            .collect();
+
+        entries.sort_by_key(|(seq, _)| *seq);
+        Ok(entries)
+    }
+
+    /// Iterate a run's lineage entries in seq order, loading each lazily
+    pub fn iter_run(&self, run_id: RunId) -> Result<LineageIter> {
+        let run_prefix = Self::run_prefix(run_id);
+        let keys = self.list_entries(&run_prefix, self.read_layout(&run_prefix)?)?;
+        Ok(LineageIter { backend: Arc::clone(&self.backend), keys: keys.into_iter() })
+    }
+
+    /// Load one page of a run's lineage (entries `offset..offset + limit` in seq order)
+    pub fn page(&self, run_id: RunId, offset: usize, limit: usize) -> Result<Vec<JSONLineage>> {
+        self.iter_run(run_id)?.skip(offset).take(limit).collect()
+    }
+
+    /// Runs with stored lineage as (run id, time of its first entry, entry
+    /// count), newest first (an empty store means no runs)
+    pub fn list_runs(&self) -> Result<Vec<(RunId, DateTime<Utc>, usize)>> {
+        let mut runs = Vec::new();
+        for run_id in self.run_ids()? {
+            let mut entries = self.iter_run(run_id)?;
+            let count = entries.len();
+            if let Some(first) = entries.next() {
+                runs.push((run_id, first?.timestamp, count));
+            }
+        }
+        runs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0 .0.cmp(&b.0 .0)));
+        Ok(runs)
+    }
 
-        let json = std::fs::read_to_string(path)?;
-        Ok(serde_json::from_str(&json)?)
+    /// Ids of every run prefix in the store, sorted
+    fn run_ids(&self) -> Result<Vec<RunId>> {
+        // Skips anything not keyed by a run id (e.g. migration leftovers)
+        let mut runs: Vec<RunId> = self
+            .backend
+            .list_top_level()?
+            .iter()
+            .filter_map(|name| RunId::from_string(name).ok())
+            .collect();
+        runs.sort_by_key(|run| run.0);
+        Ok(runs)
     }
 
     /// Get all lineage entries for a run
     pub fn get_run_lineage(&self, run_id: RunId) -> Result<Vec<JSONLineage>> {
-        let run_dir = self.lineage_dir.join(run_id.to_string());
+        self.iter_run(run_id)?.collect()
+    }
+
+    /// Search lineage across all runs, newest entry first, keeping at most
+    /// the filter's `limit`.
+    ///
+    /// Entries within a run are recorded in seq order, so with an `until`
+    /// bound a run whose first entry is already past it is skipped without
+    /// reading the rest, and a run is abandoned at its first entry past it.
+    /// With a `since` bound, entries whose file was last written before it
+    /// are skipped unread: an entry is written after its timestamp is taken.
+    pub fn query(&self, filter: LineageQuery) -> Result<Vec<JSONLineage>> {
+        let since = filter.since.map(std::time::SystemTime::from);
+        let mut matches = Vec::new();
+        for run_id in self.run_ids()? {
+            let run_prefix = Self::run_prefix(run_id);
+            for (_, key) in self.list_entries(&run_prefix, self.read_layout(&run_prefix)?)? {
+                if let (Some(since), Some(modified)) = (since, self.backend.modified(&key)?) {
+                    if modified < since {
+                        continue;
+                    }
+                }
+                let entry = read_entry(self.backend.as_ref(), &key)?;
+                if filter.until.is_some_and(|until| entry.timestamp > until) {
+                    break;
+                }
+                if filter.matches(&entry) {
+                    matches.push(entry);
+                }
+            }
+        }
+        matches.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        if let Some(limit) = filter.limit {
+            matches.truncate(limit);
+        }
+        Ok(matches)
+    }
 
-        let mut entries = Vec::new();
+    /// Convert a flat (v1) run to the sharded layout in place.
+    ///
+    /// Sharded copies are written alongside the flat entries (flat readers
+    /// ignore them), read back and checked against the originals (entry count
+    /// and per-entry SHA-256), and only then is the layout marker written.
+    /// That single `put_atomic` is the commit point: a failure before it
+    /// leaves the run readable as v1, after it as v2. The flat entries are
+    /// removed last. Returns the number of entries migrated (0 if the run is
+    /// already sharded).
+    pub fn migrate_layout(&self, run_id: RunId) -> Result<usize> {
+        let run_prefix = Self::run_prefix(run_id);
+        if self.read_layout(&run_prefix)? == LineageLayout::Sharded {
+            return Ok(0);
+        }
 
-        for entry in std::fs::read_dir(run_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        // Write the sharded copies
+        let originals = self.list_entries(&run_prefix, LineageLayout::Flat)?;
+        let mut hashes = Vec::with_capacity(originals.len());
+        for (seq, key) in &originals {
+            let bytes = self.backend.get(key)?;
+            self.backend.put_atomic(&LineageLayout::Sharded.entry_key(&run_prefix, *seq), &bytes)?;
+            hashes.push((*seq, sha256_hex(&bytes)));
+        }
 
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let json = std::fs::read_to_string(&path)?;
-                let lineage: JSONLineage = serde_json::from_str(&json)?;
-                entries.push(lineage);
+        // Verify counts and hashes before committing
+        let migrated = self.list_entries(&run_prefix, LineageLayout::Sharded)?;
+        let discard = |manager: &Self| -> Result<()> {
+            for (_, key) in &migrated {
+                manager.backend.delete(key)?;
+            }
+            Ok(())
+        };
+        if migrated.len() != originals.len() {
+            discard(self)?;
+            bail!(
+                "Layout migration of run {} wrote {} entries, expected {}",
+                run_id, migrated.len(), originals.len()
+            );
+        }
+        for ((seq, key), (expected_seq, expected_hash)) in migrated.iter().zip(&hashes) {
+            if seq != expected_seq || &sha256_hex(&self.backend.get(key)?) != expected_hash {
+                discard(self)?;
+                bail!("Layout migration of run {} failed verification at seq {}", run_id, expected_seq.0);
             }
         }
 
-        // Sort by sequence
-        entries.sort_by_key(|e| e.seq);
+        // Commit, then drop the flat entries
+        self.write_layout(&run_prefix, LineageLayout::Sharded)
+            .with_context(|| format!("Failed to commit migrated layout for run {}", run_id))?;
+        for (_, key) in &originals {
+            self.backend.delete(key)?;
+        }
 
-        Ok(entries)
+        Ok(originals.len())
+    }
+
+    /// Reassemble the OASM program a run executed from its entries'
+    /// `command_executed`, in seq order. Entries without a command become
+    /// `;` comments so the gap stays visible; a run with no commands at all
+    /// is an error.
+    pub fn reconstruct_source(&self, run_id: RunId) -> Result<String> {
+        let mut source = format!("; Reconstructed from lineage of run {}\n", run_id);
+        let mut commands = 0;
+        for entry in self.iter_run(run_id)? {
+            let entry = entry?;
+            let command = entry.command_executed.trim();
+            if command.is_empty() {
+                source.push_str(&format!("; seq {}: no command recorded ({})\n", entry.seq.0, entry.summary));
+            } else {
+                source.push_str(command);
+                source.push('\n');
+                commands += 1;
+            }
+        }
+        if commands == 0 {
+            bail!("Run {} has no recorded commands to reconstruct", run_id);
+        }
+        Ok(source)
     }
 
     /// Build lineage chain (parent → child relationships)
@@ -135,6 +660,137 @@ impl LineageManager {
         Ok(chain)
     }
 
+    /// Summaries of `run_id` and the runs it descends from (following each
+    /// run's `parent_run_id`), oldest ancestor first. A run reached twice is
+    /// a cycle and an error, as is a chain longer than the configured depth
+    /// or a parent with no lineage.
+    pub fn build_full_ancestry(&self, run_id: RunId) -> Result<Vec<RunSummary>> {
+        let mapper = ModuleMapper::new();
+        let mut ancestry: Vec<RunSummary> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut next = Some(run_id);
+        while let Some(run) = next {
+            if !seen.insert(run) {
+                bail!("Lineage cycle: run {} appears twice in the ancestry of run {}", run, run_id);
+            }
+            if ancestry.len() > self.max_ancestry_depth {
+                bail!("Ancestry of run {} is deeper than {} runs", run_id, self.max_ancestry_depth);
+            }
+            let summary = self.summarize_run(run, &mapper)?;
+            if summary.entries == 0 {
+                bail!("Run {} has no lineage entries", run);
+            }
+            next = summary.parent_run_id;
+            ancestry.push(summary);
+        }
+        ancestry.reverse();
+        Ok(ancestry)
+    }
+
+    /// Mark `child` as spawned from `parent`: every child entry gets
+    /// `parent_run_id` set and a `run:<parent>` link in its lineage chain
+    pub fn record_child_run(&self, parent: RunId, child: RunId) -> Result<()> {
+        if parent == child {
+            bail!("Run {} cannot be its own parent", child);
+        }
+        let entries = self.get_run_lineage(child)?;
+        if entries.is_empty() {
+            bail!("Run {} has no lineage entries", child);
+        }
+        let link = format!("run:{}", parent);
+        for mut entry in entries {
+            entry.provenance.parent_run_id = Some(parent);
+            if !entry.provenance.lineage_chain.contains(&link) {
+                entry.provenance.lineage_chain.push(link.clone());
+            }
+            self.save(&entry)?;
+        }
+        Ok(())
+    }
+
+    /// Summarize a run: intents, outcomes, impact, artifacts and failures.
+    /// Entries are streamed one at a time, so long runs are never loaded whole.
+    pub fn summarize_run(&self, run_id: RunId, mapper: &ModuleMapper) -> Result<RunSummary> {
+        let mut summary = RunSummary::new(run_id);
+        let mut intent_index: HashMap<String, usize> = HashMap::new();
+        let mut modules: HashMap<String, usize> = HashMap::new();
+
+        for entry in self.iter_run(run_id)? {
+            let entry = entry?;
+            summary.entries += 1;
+            if summary.entries == 1 {
+                summary.actor = Some(entry.actor.clone());
+                summary.parent_run_id = entry.provenance.parent_run_id;
+            }
+
+            match intent_index.get(&entry.intent) {
+                Some(&i) => summary.intents[i].count += 1,
+                None => {
+                    intent_index.insert(entry.intent.clone(), summary.intents.len());
+                    summary.intents.push(IntentCount { intent: entry.intent.clone(), count: 1 });
+                }
+            }
+
+            let status = match &entry.outcome {
+                ExecutionOutcome::Success => "success",
+                ExecutionOutcome::Failed { .. } => "failed",
+                ExecutionOutcome::PartialSuccess { .. } => "partial",
+                ExecutionOutcome::Cancelled => "cancelled",
+            };
+            *summary.outcomes.entry(status.to_string()).or_default() += 1;
+            if let ExecutionOutcome::Failed { reason } = &entry.outcome {
+                summary.failures.push(RunFailure {
+                    seq: entry.seq,
+                    summary: entry.summary.clone(),
+                    reason: reason.clone(),
+                    origin: entry.origin.clone(),
+                });
+            }
+
+            let impact = &mut summary.impact;
+            impact.files_changed += entry.impact.files_changed;
+            impact.lines_added += entry.impact.lines_added;
+            impact.lines_removed += entry.impact.lines_removed;
+            impact.functions_affected += entry.impact.functions_affected;
+            impact.objects_affected += entry.impact.objects_affected;
+            for module in &entry.impact.modules_affected {
+                let module = mapper.module_for(module).unwrap_or_else(|| module.clone());
+                *modules.entry(module).or_default() += 1;
+            }
+
+            if let Some(diff_id) = &entry.diff_id {
+                summary.artifacts.push(format!("diff:{}", diff_id));
+            }
+            if let Some(git_sha) = &entry.git_sha {
+                summary.artifacts.push(format!("git:{}", git_sha));
+            }
+
+            for test in &entry.tests {
+                match test.status {
+                    TestStatus::Passed => summary.tests.passed += 1,
+                    TestStatus::Failed { .. } => summary.tests.failed += 1,
+                    _ => summary.tests.other += 1,
+                }
+            }
+        }
+
+        let mut modules: Vec<ModuleCount> = modules
+            .into_iter()
+            .map(|(module, entries)| ModuleCount { module, entries })
+            .collect();
+        modules.sort_by(|a, b| b.entries.cmp(&a.entries).then_with(|| a.module.cmp(&b.module)));
+        modules.truncate(RunSummary::TOP_MODULES);
+        summary.top_modules = modules;
+
+        Ok(summary)
+    }
+
+    /// Write a summary as `<run_id>/summary.md` next to the run's entries
+    pub fn save_summary(&self, summary: &RunSummary) -> Result<()> {
+        let key = join_key(&[&Self::run_prefix(summary.run_id), "summary.md"]);
+        self.backend.put_atomic(&key, summary.to_markdown().as_bytes())
+    }
+
     /// Add test record to lineage entry
     pub fn add_test_record(
         &self,
@@ -143,14 +799,25 @@ impl LineageManager {
         test_record: TestRecord,
     ) -> Result<()> {
         let mut lineage = self.load(run_id, seq)?;
+        self.update_session(run_id, |session| session.add_totals(tests_delta(std::slice::from_ref(&test_record))))?;
         lineage.tests.push(test_record);
         self.save(&lineage)?;
         Ok(())
     }
 
-    /// Link diff to lineage entry
+    /// Link diff to lineage entry (and list it in the run's open session;
+    /// a session takes one diff per entry)
     pub fn link_diff(&self, run_id: RunId, seq: Seq, diff_id: String) -> Result<()> {
         let mut lineage = self.load(run_id, seq)?;
+        self.update_session(run_id, |session| {
+            let reference = DiffReference {
+                seq,
+                diff_id: diff_id.clone(),
+                timestamp: Utc::now(),
+                summary: lineage.summary.clone(),
+            };
+            session.append_diff(reference, SessionTotals::default())
+        })?;
         lineage.diff_id = Some(diff_id);
         self.save(&lineage)?;
         Ok(())
@@ -159,97 +826,728 @@ impl LineageManager {
     /// Link Git SHA to lineage entry
     pub fn link_git_sha(&self, run_id: RunId, seq: Seq, git_sha: String) -> Result<()> {
         let mut lineage = self.load(run_id, seq)?;
+        self.update_session(run_id, |session| session.add_git_sha(&git_sha))?;
         lineage.git_sha = Some(git_sha);
         self.save(&lineage)?;
         Ok(())
     }
-}
 
-/// Diff snapshot manager (unified diff format)
-pub struct DiffManager {
-    diffs_dir: std::path::PathBuf,
-}
+    /// Start maintaining a session index for `run_id`: from now on
+    /// `record`, `add_test_record`, `link_diff` and `link_git_sha` keep it
+    /// up to date. Opening an existing session returns it unchanged.
+    /// Concurrent writers to one session should share this manager.
+    pub fn open_session(&self, run_id: RunId) -> Result<SessionIndex> {
+        let lock = self.session_lock(run_id);
+        let _guard = lock.lock().unwrap();
+        let session = SessionIndexManager::open_with_backend(Arc::clone(&self.backend), run_id)?;
+        session.save()?;
+        Ok(session.index().clone())
+    }
 
-impl DiffManager {
-    pub fn new(diffs_dir: impl AsRef<Path>) -> Self {
-        Self {
-            diffs_dir: diffs_dir.as_ref().to_path_buf(),
+    /// Set the session's end time. This seals the run: later `record`,
+    /// `add_test_record`, `link_diff` and `link_git_sha` calls for it are
+    /// errors and change nothing.
+    pub fn close_session(&self, run_id: RunId) -> Result<SessionIndex> {
+        let lock = self.session_lock(run_id);
+        let _guard = lock.lock().unwrap();
+        if !self.backend.exists(&SessionIndexManager::index_key(run_id))? {
+            bail!("Run {} has no open session", run_id);
         }
+        let mut session = SessionIndexManager::open_with_backend(Arc::clone(&self.backend), run_id)?;
+        Ok(session.finalize()?.clone())
     }
 
-    /// Save diff snapshot
-    pub fn save_diff(&self, diff: &DiffSnapshot) -> Result<()> {
-        std::fs::create_dir_all(&self.diffs_dir)?;
+    pub fn load_session_index(&self, run_id: RunId) -> Result<SessionIndex> {
+        let key = SessionIndexManager::index_key(run_id);
+        if !self.backend.exists(&key)? {
+            bail!("Run {} has no session index", run_id);
+        }
+        serde_json::from_slice(&self.backend.get(&key)?).with_context(|| format!("Failed to read session index {}", key))
+    }
 
-        // Organize by run_id
-        let run_dir = self.diffs_dir.join(diff.header.run_id.to_string());
-        std::fs::create_dir_all(&run_dir)?;
+    /// Plain-text overview of a session, for the shell and dashboard
+    pub fn summarize_session(&self, run_id: RunId) -> Result<String> {
+        let index = self.load_session_index(run_id)?;
+        let totals = &index.totals;
+        let mut out = format!("Session {}\n", index.run_id);
+        out.push_str(&format!("Started: {}\n", index.started.to_rfc3339()));
+        match index.ended {
+            Some(ended) => out.push_str(&format!("Ended: {}\n", ended.to_rfc3339())),
+            None => out.push_str("Ended: (open)\n"),
+        }
+        out.push_str(&format!(
+            "Entries: {}, diffs: {}, files changed: {}, +{} -{}\n",
+            index.provenance_links.len(),
+            totals.total_diffs,
+            totals.files_changed,
+            totals.lines_added,
+            totals.lines_removed
+        ));
+        out.push_str(&format!("Tests: {}/{} passed\n", totals.tests_passed, totals.tests_run));
+        if !index.git_shas.is_empty() {
+            out.push_str(&format!("Commits: {}\n", index.git_shas.join(", ")));
+        }
+        for diff in &index.diffs {
+            out.push_str(&format!("- #{} {}: {}\n", diff.seq.0, diff.diff_id, diff.summary));
+        }
+        Ok(out)
+    }
 
-        let path = run_dir.join(format!("{}.diff.yaml", diff.header.diff_id));
+    /// Apply `update` to the run's session, if one was opened
+    fn update_session(&self, run_id: RunId, update: impl FnOnce(&mut SessionIndexManager) -> Result<()>) -> Result<()> {
+        let lock = self.session_lock(run_id);
+        let _guard = lock.lock().unwrap();
+        if !self.backend.exists(&SessionIndexManager::index_key(run_id))? {
+            return Ok(());
+        }
+        update(&mut SessionIndexManager::open_with_backend(Arc::clone(&self.backend), run_id)?)
+    }
 
-        // YAML format for diffs (header + hunks)
-        let yaml = serde_yaml::to_string(diff)?;
-        std::fs::write(path, yaml)?;
+    fn session_lock(&self, run_id: RunId) -> Arc<Mutex<()>> {
+        Arc::clone(self.session_locks.lock().unwrap().entry(run_id).or_default())
+    }
+}
 
-        Ok(())
+/// Session totals contributed by test records
+fn tests_delta(tests: &[TestRecord]) -> SessionTotals {
+    SessionTotals {
+        tests_run: tests.len(),
+        tests_passed: tests.iter().filter(|t| t.status == TestStatus::Passed).count(),
+        ..SessionTotals::default()
     }
+}
 
-    /// Load diff snapshot
-    pub fn load_diff(&self, run_id: RunId, diff_id: &str) -> Result<DiffSnapshot> {
-        let path = self.diffs_dir
-            .join(run_id.to_string())
-            .join(format!("{}.diff.yaml", diff_id));
+/// Kind of actor behind an entry, regardless of who exactly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActorKind {
+    Human,
+    Automation,
+    AI,
+    System,
+}
 
-        let yaml = std::fs::read_to_string(path)?;
-        Ok(serde_yaml::from_str(&yaml)?)
+impl ActorKind {
+    pub fn of(actor: &Actor) -> Self {
+        match actor {
+            Actor::Human { .. } => ActorKind::Human,
+            Actor::Automation { .. } => ActorKind::Automation,
+            Actor::AI { .. } => ActorKind::AI,
+            Actor::System => ActorKind::System,
+        }
     }
+}
 
-    /// Apply diff (preview mode)
-    pub fn preview_diff(&self, diff: &DiffSnapshot) -> String {
-        let mut output = String::new();
+/// Outcome of an entry, without its reason or warnings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutcomeKind {
+    Success,
+    Failed,
+    PartialSuccess,
+    Cancelled,
+}
 
-        output.push_str(&format!("=== Diff {} ===\n", diff.header.diff_id));
-        output.push_str(&format!("Summary: {}\n", diff.header.summary));
-        output.push_str(&format!("Confidence: {}\n", diff.header.confidence.0));
-        output.push_str(&format!("Impact: +{} -{} files\n",
-            diff.header.impact.lines_added,
-            diff.header.impact.lines_removed
-        ));
-        output.push('\n');
+impl OutcomeKind {
+    pub fn of(outcome: &ExecutionOutcome) -> Self {
+        match outcome {
+            ExecutionOutcome::Success => OutcomeKind::Success,
+            ExecutionOutcome::Failed { .. } => OutcomeKind::Failed,
+            ExecutionOutcome::PartialSuccess { .. } => OutcomeKind::PartialSuccess,
+            ExecutionOutcome::Cancelled => OutcomeKind::Cancelled,
+        }
+    }
+}
 
-        for hunk in &diff.hunks {
-            output.push_str(&format!("--- {}\n", hunk.file_path));
-            output.push_str(&format!("+++ {}\n", hunk.file_path));
-            output.push_str(&format!("@@ -{},{} +{},{} @@\n",
-                hunk.old_start, hunk.old_count,
-                hunk.new_start, hunk.new_count
-            ));
+/// Filter for `LineageManager::query`; unset fields match everything.
+/// The time range is inclusive on both ends.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineageQuery {
+    pub actor: Option<ActorKind>,
+    pub outcome: Option<OutcomeKind>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Template the entry's provenance names
+    pub template_id: Option<String>,
+    /// Case-insensitive text to find in the summary or intent
+    pub text: Option<String>,
+    /// Most entries returned (the newest)
+    pub limit: Option<usize>,
+}
 
-            for line in &hunk.lines {
-                let prefix = match line.line_type {
-                    crate::schemas::DiffLineType::Context => " ",
-                    crate::schemas::DiffLineType::Removal => "-",
-                    crate::schemas::DiffLineType::Addition => "+",
-                };
-                output.push_str(&format!("{}{}\n", prefix, line.content));
-            }
+impl LineageQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-            output.push('\n');
-        }
+    pub fn with_actor(mut self, actor: ActorKind) -> Self {
+        self.actor = Some(actor);
+        self
+    }
 
-        output
+    pub fn with_outcome(mut self, outcome: OutcomeKind) -> Self {
+        self.outcome = Some(outcome);
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::Confidence;
+    pub fn with_range(mut self, since: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self.until = Some(until);
+        self
+    }
 
-    #[test]
-    fn test_lineage_recording() -> Result<()> {
+    pub fn with_template(mut self, template_id: impl Into<String>) -> Self {
+        self.template_id = Some(template_id.into());
+        self
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn matches(&self, entry: &JSONLineage) -> bool {
+        self.actor.is_none_or(|kind| kind == ActorKind::of(&entry.actor))
+            && self.outcome.is_none_or(|kind| kind == OutcomeKind::of(&entry.outcome))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+            && self.template_id.as_ref().is_none_or(|id| entry.provenance.template_id.as_ref() == Some(id))
+            && self.text.as_ref().is_none_or(|text| {
+                let text = text.to_lowercase();
+                entry.summary.to_lowercase().contains(&text) || entry.intent.to_lowercase().contains(&text)
+            })
+    }
+}
+
+/// Intent text and how many entries recorded it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntentCount {
+    pub intent: String,
+    pub count: usize,
+}
+
+/// Module and how many entries affected it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleCount {
+    pub module: String,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestTally {
+    pub passed: usize,
+    pub failed: usize,
+    /// Planned, running or skipped
+    pub other: usize,
+}
+
+/// A failed entry and the overlay field behind it, if known
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunFailure {
+    pub seq: Seq,
+    pub summary: String,
+    pub reason: String,
+    pub origin: Option<FieldOrigin>,
+}
+
+/// Intent-to-impact summary of one run (see `LineageManager::summarize_run`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub run_id: RunId,
+    pub entries: usize,
+    /// Actor of the first entry
+    #[serde(default)]
+    pub actor: Option<Actor>,
+    /// Run this one was spawned from, per its first entry
+    #[serde(default)]
+    pub parent_run_id: Option<RunId>,
+    /// Distinct intents in first-seen order
+    pub intents: Vec<IntentCount>,
+    /// Entry count per status (success, failed, partial, cancelled)
+    pub outcomes: BTreeMap<String, usize>,
+    /// Summed impact; `modules_affected` stays empty, see `top_modules`
+    pub impact: Impact,
+    pub top_modules: Vec<ModuleCount>,
+    /// Linked diffs and commits (`diff:<id>`, `git:<sha>`) in seq order
+    pub artifacts: Vec<String>,
+    pub tests: TestTally,
+    pub failures: Vec<RunFailure>,
+}
+
+impl RunSummary {
+    /// Modules listed under "top affected modules"
+    pub const TOP_MODULES: usize = 10;
+
+    fn new(run_id: RunId) -> Self {
+        Self {
+            run_id,
+            entries: 0,
+            actor: None,
+            parent_run_id: None,
+            intents: Vec::new(),
+            outcomes: BTreeMap::new(),
+            impact: Impact::default(),
+            top_modules: Vec::new(),
+            artifacts: Vec::new(),
+            tests: TestTally::default(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Run {}\n\n", self.run_id);
+        out.push_str(&format!("{} lineage entries\n", self.entries));
+
+        out.push_str("\n## Intent\n\n");
+        if self.intents.is_empty() {
+            out.push_str("No entries recorded\n");
+        }
+        for intent in &self.intents {
+            out.push_str(&format!("- {} (x{})\n", intent.intent, intent.count));
+        }
+
+        out.push_str("\n## Outcomes\n\n");
+        for (status, count) in &self.outcomes {
+            out.push_str(&format!("- {}: {}\n", status, count));
+        }
+        out.push_str(&format!(
+            "- tests: {} passed, {} failed, {} other\n",
+            self.tests.passed, self.tests.failed, self.tests.other
+        ));
+
+        out.push_str("\n## Impact\n\n");
+        out.push_str(&format!(
+            "{} file(s) changed, +{} -{} lines, {} function(s) and {} object(s) affected\n",
+            self.impact.files_changed,
+            self.impact.lines_added,
+            self.impact.lines_removed,
+            self.impact.functions_affected,
+            self.impact.objects_affected
+        ));
+        if !self.top_modules.is_empty() {
+            out.push('\n');
+            for module in &self.top_modules {
+                out.push_str(&format!("- `{}` ({} entries)\n", module.module, module.entries));
+            }
+        }
+
+        out.push_str("\n## Artifacts\n\n");
+        if self.artifacts.is_empty() {
+            out.push_str("None\n");
+        }
+        for artifact in &self.artifacts {
+            out.push_str(&format!("- {}\n", artifact));
+        }
+
+        out.push_str("\n## Failures\n\n");
+        if self.failures.is_empty() {
+            out.push_str("None\n");
+        }
+        for failure in &self.failures {
+            out.push_str(&format!("- #{} {}: {}\n", failure.seq.0, failure.summary, failure.reason));
+            if let Some(origin) = &failure.origin {
+                out.push_str(&format!("  - {}\n", origin));
+            }
+        }
+
+        out
+    }
+}
+
+/// Indented tree of an ancestry from `LineageManager::build_full_ancestry`,
+/// one line per run: id, actor, first intent and outcome tallies
+pub fn render_ancestry(ancestry: &[RunSummary]) -> String {
+    let mut out = String::new();
+    for (depth, run) in ancestry.iter().enumerate() {
+        let branch = if depth == 0 { String::new() } else { format!("{}└─ ", "   ".repeat(depth - 1)) };
+        let actor = match &run.actor {
+            Some(Actor::Human { username }) => username.clone(),
+            Some(Actor::Automation { rule_id }) => format!("rule:{}", rule_id),
+            Some(Actor::AI { model, .. }) => model.clone(),
+            Some(Actor::System) | None => "system".to_string(),
+        };
+        let intent = run.intents.first().map_or("-", |intent| intent.intent.as_str());
+        let outcomes: Vec<String> = run.outcomes.iter().map(|(status, count)| format!("{} {}", count, status)).collect();
+        out.push_str(&format!("{}{} [{}] {} ({})\n", branch, run.run_id, actor, intent, outcomes.join(", ")));
+    }
+    out
+}
+
+/// Key of a run's session index, next to its lineage entries
+const SESSION_INDEX: &str = "session_index.json";
+
+/// Maintains one run's SessionIndex as diffs arrive. The index is rewritten
+/// after every change, so a reopened session continues where it stopped.
+pub struct SessionIndexManager {
+    backend: Arc<dyn StorageBackend>,
+    index: SessionIndex,
+}
+
+impl SessionIndexManager {
+    /// Open (or start) the session of `run_id` in the lineage dir on local disk
+    pub fn open(lineage_dir: impl AsRef<Path>, run_id: RunId) -> Result<Self> {
+        Self::open_with_backend(Arc::new(FsBackend::new(lineage_dir)), run_id)
+    }
+
+    pub fn open_with_backend(backend: Arc<dyn StorageBackend>, run_id: RunId) -> Result<Self> {
+        let key = Self::index_key(run_id);
+        let index = if backend.exists(&key)? {
+            serde_json::from_slice(&backend.get(&key)?)
+                .with_context(|| format!("Failed to read session index {}", key))?
+        } else {
+            SessionIndex {
+                run_id,
+                started: Utc::now(),
+                ended: None,
+                diffs: Vec::new(),
+                totals: SessionTotals::default(),
+                git_shas: Vec::new(),
+                provenance_links: Vec::new(),
+            }
+        };
+        Ok(Self { backend, index })
+    }
+
+    fn index_key(run_id: RunId) -> String {
+        join_key(&[&run_id.to_string(), SESSION_INDEX])
+    }
+
+    pub fn index(&self) -> &SessionIndex {
+        &self.index
+    }
+
+    /// Record a diff and add `delta` to the totals. Diffs stay sorted by seq;
+    /// `total_diffs` counts recorded diffs, so the delta's value is ignored.
+    pub fn append_diff(&mut self, diff: DiffReference, delta: SessionTotals) -> Result<()> {
+        self.check_open()?;
+        let position = match self.index.diffs.binary_search_by_key(&diff.seq, |d| d.seq) {
+            Ok(_) => bail!("Session {} already has a diff at seq {}", self.index.run_id, diff.seq.0),
+            Err(position) => position,
+        };
+        self.index.diffs.insert(position, diff);
+        self.index.totals.total_diffs = self.index.diffs.len();
+        self.accumulate(&delta);
+
+        self.save()
+    }
+
+    /// Add `delta` to the totals without recording a diff
+    pub fn add_totals(&mut self, delta: SessionTotals) -> Result<()> {
+        self.check_open()?;
+        self.accumulate(&delta);
+        self.save()
+    }
+
+    /// Count a lineage entry: its impact and tests go into the totals, its
+    /// commit (if new) into `git_shas` and its id into `provenance_links`
+    pub fn record_entry(&mut self, lineage: &JSONLineage) -> Result<()> {
+        self.check_open()?;
+        self.accumulate(&SessionTotals {
+            files_changed: lineage.impact.files_changed,
+            lines_added: lineage.impact.lines_added,
+            lines_removed: lineage.impact.lines_removed,
+            ..tests_delta(&lineage.tests)
+        });
+        if let Some(sha) = &lineage.git_sha {
+            if !self.index.git_shas.contains(sha) {
+                self.index.git_shas.push(sha.clone());
+            }
+        }
+        self.index.provenance_links.push(lineage.lineage_id.clone());
+        self.save()
+    }
+
+    /// Note a commit made during the session (once)
+    pub fn add_git_sha(&mut self, sha: &str) -> Result<()> {
+        self.check_open()?;
+        if !self.index.git_shas.iter().any(|known| known == sha) {
+            self.index.git_shas.push(sha.to_string());
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn check_open(&self) -> Result<()> {
+        if self.index.ended.is_some() {
+            bail!("Session {} is already finalized", self.index.run_id);
+        }
+        Ok(())
+    }
+
+    fn accumulate(&mut self, delta: &SessionTotals) {
+        let totals = &mut self.index.totals;
+        totals.files_changed += delta.files_changed;
+        totals.lines_added += delta.lines_added;
+        totals.lines_removed += delta.lines_removed;
+        totals.tests_run += delta.tests_run;
+        totals.tests_passed += delta.tests_passed;
+    }
+
+    /// Mark the session ended and write the final index
+    pub fn finalize(&mut self) -> Result<&SessionIndex> {
+        if self.index.ended.is_none() {
+            self.index.ended = Some(Utc::now());
+            self.save()?;
+        }
+        Ok(&self.index)
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.index)?;
+        self.backend.put_atomic(&Self::index_key(self.index.run_id), json.as_bytes())
+    }
+}
+
+/// Diff snapshot manager (unified diff format)
+pub struct DiffManager {
+    backend: Arc<dyn StorageBackend>,
+    /// Only check diffs in `apply_diff`, never write files
+    dry_run: bool,
+    /// Compression of newly saved diffs
+    compression: CompressionAlgorithm,
+}
+
+impl DiffManager {
+    /// Diffs stored on local disk under `diffs_dir`
+    pub fn new(diffs_dir: impl AsRef<Path>) -> Self {
+        Self::with_backend(Arc::new(FsBackend::new(diffs_dir)))
+    }
+
+    pub fn with_backend(backend: Arc<dyn StorageBackend>) -> Self {
+        Self { backend, dry_run: false, compression: CompressionAlgorithm::None }
+    }
+
+    /// Compress diffs on `save_diff` (stored as `.diff.yaml.gz` /
+    /// `.diff.yaml.zst`); loading detects compression either way
+    pub fn with_compression(mut self, compression: CompressionAlgorithm) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Make `apply_diff` verify every hunk and report the files it would
+    /// change without writing them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Apply (or revert) `diff` to the files under `root`, returning the
+    /// changed file paths. Every hunk is checked against the current files
+    /// first; any mismatch fails with a `ConflictError` and nothing is
+    /// written. Each file is replaced atomically (temp file + rename). Files
+    /// the diff lists in `file_changes` are created or deleted (the reverse
+    /// on revert); any other file it empties is kept. `binary_files` have no
+    /// hunks and are left alone, with a warning.
+    pub fn apply_diff(&self, diff: &DiffSnapshot, root: impl AsRef<Path>, mode: ApplyMode) -> Result<Vec<String>> {
+        let files = FsBackend::new(root.as_ref());
+        if !diff.binary_files.is_empty() {
+            log::warn!(
+                "Diff {} leaves binary file(s) unchanged: {}",
+                diff.header.diff_id,
+                diff.binary_files.join(", ")
+            );
+        }
+
+        // Hunk indices per file, in first-appearance order; created or
+        // deleted files without hunks (empty ones) come last
+        let mut by_file: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (index, hunk) in diff.hunks.iter().enumerate() {
+            match by_file.iter_mut().find(|(path, _)| *path == hunk.file_path) {
+                Some((_, hunks)) => hunks.push(index),
+                None => by_file.push((&hunk.file_path, vec![index])),
+            }
+        }
+        for path in diff.file_changes.keys() {
+            if !by_file.iter().any(|(known, _)| known == path) {
+                by_file.push((path, Vec::new()));
+            }
+        }
+
+        let mut patched = Vec::new();
+        let mut conflicts = Vec::new();
+        for (path, hunks) in by_file {
+            let key = normalize_key(path)?;
+            let current = if files.exists(&key)? {
+                String::from_utf8(files.get(&key)?).with_context(|| format!("{} is not UTF-8 text", path))?
+            } else {
+                String::new()
+            };
+            let (removes, adds) = match (diff.file_changes.get(path), mode) {
+                (Some(FileChange::Deleted), ApplyMode::Apply) | (Some(FileChange::Created), ApplyMode::Revert) => (true, false),
+                (Some(FileChange::Created), ApplyMode::Apply) | (Some(FileChange::Deleted), ApplyMode::Revert) => (false, true),
+                (None, _) => (false, false),
+            };
+            match patch_text(&current, diff, &hunks, mode) {
+                Ok(text) if removes && text.is_empty() => {
+                    if files.exists(&key)? {
+                        patched.push((key, None));
+                    }
+                }
+                Ok(text) if adds && !files.exists(&key)? => patched.push((key, Some(text))),
+                Ok(text) if text != current => patched.push((key, Some(text))),
+                Ok(_) => {}
+                Err(found) => conflicts.extend(found),
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(ConflictError { conflicts }.into());
+        }
+
+        if !self.dry_run {
+            for (key, text) in &patched {
+                match text {
+                    Some(text) => files.put_atomic(key, text.as_bytes())?,
+                    None => files.delete(key)?,
+                }
+            }
+        }
+        Ok(patched.into_iter().map(|(key, _)| key).collect())
+    }
+
+    /// Organized by run_id: `<run_id>/<diff_id>.diff.yaml`, plus the
+    /// compression's extension
+    fn diff_key(run_id: RunId, diff_id: &str, compression: CompressionAlgorithm) -> String {
+        let name = match compression.extension() {
+            Some(extension) => format!("{}.diff.yaml.{}", diff_id, extension),
+            None => format!("{}.diff.yaml", diff_id),
+        };
+        join_key(&[&run_id.to_string(), &name])
+    }
+
+    fn is_diff_key(key: &str) -> bool {
+        [".diff.yaml", ".diff.yaml.gz", ".diff.yaml.zst"].iter().any(|suffix| key.ends_with(suffix))
+    }
+
+    /// Save diff snapshot, compressed if the manager compresses diffs
+    pub fn save_diff(&self, diff: &DiffSnapshot) -> Result<()> {
+        // YAML format for diffs (header + hunks)
+        let key = Self::diff_key(diff.header.run_id, &diff.header.diff_id, self.compression);
+        if self.compression == CompressionAlgorithm::None {
+            return self.backend.put_atomic(&key, serde_yaml::to_string(diff)?.as_bytes());
+        }
+
+        // The recorded sizes are those of the encoding before last, so they
+        // can be off by the few digits the sizes themselves add
+        let mut diff = diff.clone();
+        let mut info = CompressionInfo { algorithm: self.compression, original_size: 0, compressed_size: 0 };
+        let mut compressed = Vec::new();
+        for _ in 0..2 {
+            diff.compression = Some(info.clone());
+            let yaml = serde_yaml::to_string(&diff)?;
+            compressed = self.compression.compress(yaml.as_bytes())?;
+            info.original_size = yaml.len() as u64;
+            info.compressed_size = compressed.len() as u64;
+        }
+        self.backend.put_atomic(&key, &compressed)
+    }
+
+    /// Parse stored diff bytes, decompressing them if needed
+    fn decode_diff(key: &str, bytes: &[u8]) -> Result<DiffSnapshot> {
+        let yaml = CompressionAlgorithm::detect(bytes)
+            .decompress(bytes)
+            .with_context(|| format!("Cannot decompress diff snapshot {}", key))?;
+        serde_yaml::from_slice(&yaml).with_context(|| format!("Invalid diff snapshot {}", key))
+    }
+
+    /// The diff captured for entry `seq` of a run, if any
+    pub fn find_diff(&self, run_id: RunId, seq: Seq) -> Result<Option<DiffSnapshot>> {
+        let prefix = format!("{}/", run_id);
+        for key in self.backend.list_prefix(&prefix)? {
+            if !Self::is_diff_key(&key) {
+                continue;
+            }
+            let diff = Self::decode_diff(&key, &self.backend.get(&key)?)?;
+            if diff.header.seq == seq {
+                return Ok(Some(diff));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load diff snapshot, whichever compression it was saved with
+    pub fn load_diff(&self, run_id: RunId, diff_id: &str) -> Result<DiffSnapshot> {
+        for compression in [CompressionAlgorithm::None, CompressionAlgorithm::Gzip, CompressionAlgorithm::Zstd] {
+            let key = Self::diff_key(run_id, diff_id, compression);
+            if self.backend.exists(&key)? {
+                return Self::decode_diff(&key, &self.backend.get(&key)?);
+            }
+        }
+        bail!("Diff snapshot {} not found in run {}", diff_id, run_id)
+    }
+
+    /// Render a diff for review: a header block, then the hunks in
+    /// `diff -u` format
+    pub fn preview_diff(&self, diff: &DiffSnapshot) -> String {
+        let mut output = String::new();
+
+        output.push_str(&format!("=== Diff {} ===\n", diff.header.diff_id));
+        output.push_str(&format!("Summary: {}\n", diff.header.summary));
+        output.push_str(&format!("Confidence: {}\n", diff.header.confidence.0));
+        output.push_str(&format!("Impact: +{} -{} files\n",
+            diff.header.impact.lines_added,
+            diff.header.impact.lines_removed
+        ));
+        output.push('\n');
+
+        // `diff -u` omits a range's count when it is 1
+        let range = |start: usize, count: usize| match count {
+            1 => start.to_string(),
+            _ => format!("{},{}", start, count),
+        };
+
+        let mut previous_file = None;
+        for hunk in &diff.hunks {
+            if previous_file != Some(&hunk.file_path) {
+                output.push_str(&format!("--- {}\n", hunk.file_path));
+                output.push_str(&format!("+++ {}\n", hunk.file_path));
+                previous_file = Some(&hunk.file_path);
+            }
+            output.push_str(&format!("@@ -{} +{} @@\n",
+                range(hunk.old_start, hunk.old_count),
+                range(hunk.new_start, hunk.new_count)
+            ));
+
+            for line in &hunk.lines {
+                let prefix = match line.line_type {
+                    crate::schemas::DiffLineType::Context => " ",
+                    crate::schemas::DiffLineType::Removal => "-",
+                    crate::schemas::DiffLineType::Addition => "+",
+                };
+                output.push_str(&format!("{}{}\n", prefix, line.content));
+                if line.missing_newline {
+                    output.push_str("\\ No newline at end of file\n");
+                }
+            }
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+    use crate::Confidence;
+
+    /// Run a test body against the filesystem and in-memory backends
+    fn for_each_backend(test: impl Fn(Arc<dyn StorageBackend>) -> Result<()>) -> Result<()> {
         let temp_dir = tempfile::tempdir()?;
-        let manager = LineageManager::new(temp_dir.path());
+        test(Arc::new(FsBackend::new(temp_dir.path())))
+            .context("filesystem backend")?;
+        test(MemoryBackend::shared()).context("memory backend")
+    }
+
+    #[test]
+    fn test_lineage_recording() -> Result<()> {
+        for_each_backend(check_lineage_recording)
+    }
+
+    fn check_lineage_recording(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(backend);
 
         let run_id = RunId::new();
         let seq = Seq::zero();
@@ -260,6 +1558,7 @@ mod tests {
             Actor::System,
             "Test operation",
             "Testing lineage",
+            "",
             ExecutionOutcome::Success,
             Provenance {
                 tool_versions: crate::ToolVersions::current(),
@@ -268,6 +1567,9 @@ mod tests {
                 parent_run_id: None,
                 lineage_chain: vec![],
                 confidence: Some(Confidence::high()),
+                git_dirty: None,
+                authored_by: None,
+                annotations: Vec::new(),
             },
             Impact::default(),
         )?;
@@ -281,10 +1583,60 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_reconstruct_source() -> Result<()> {
+        use oasm_core::parser::{InstructionParser, NativeParser};
+
+        let manager = LineageManager::with_backend(MemoryBackend::shared());
+        let run_id = RunId::new();
+        let original = "CREATE gear\nSET teeth = 20\nSET area = teeth * 2.5\nASSERT teeth >= 4 \"need 4 teeth\"\n";
+
+        let provenance = || Provenance {
+            tool_versions: crate::ToolVersions::current(),
+            config_hash: "abc123".to_string(),
+            template_id: None,
+            parent_run_id: None,
+            lineage_chain: vec![],
+            confidence: None,
+            git_dirty: None,
+            authored_by: None,
+            annotations: Vec::new(),
+        };
+        for (i, line) in original.lines().enumerate() {
+            manager.record(
+                run_id,
+                Seq(i as u64),
+                Actor::System,
+                format!("Step {}", i),
+                "Replay",
+                line,
+                ExecutionOutcome::Success,
+                provenance(),
+                Impact::default(),
+            )?;
+        }
+        // Entries without a command stay visible as comments
+        manager.record(run_id, Seq(4), Actor::System, "Lint", "Replay", "", ExecutionOutcome::Success, provenance(), Impact::default())?;
+
+        let source = manager.reconstruct_source(run_id)?;
+        let parse = |source: &str| -> Result<Vec<_>> {
+            let instructions = NativeParser.parse_file(source).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+            Ok(instructions.into_iter().map(|i| (i.mnemonic, i.operands)).collect::<Vec<_>>())
+        };
+        assert_eq!(parse(&source)?, parse(original)?);
+        assert!(source.contains("; seq 4: no command recorded (Lint)"));
+
+        assert!(manager.reconstruct_source(RunId::new()).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_lineage_chain() -> Result<()> {
-        let temp_dir = tempfile::tempdir()?;
-        let manager = LineageManager::new(temp_dir.path());
+        for_each_backend(check_lineage_chain)
+    }
+
+    fn check_lineage_chain(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(backend);
 
         let run_id = RunId::new();
 
@@ -296,6 +1648,7 @@ mod tests {
                 Actor::System,
                 format!("Step {}", i),
                 format!("Intent {}", i),
+                "",
                 ExecutionOutcome::Success,
                 Provenance {
                     tool_versions: crate::ToolVersions::current(),
@@ -304,6 +1657,9 @@ mod tests {
                     parent_run_id: None,
                     lineage_chain: vec![],
                     confidence: None,
+                    git_dirty: None,
+                    authored_by: None,
+                    annotations: Vec::new(),
                 },
                 Impact::default(),
             )?;
@@ -314,4 +1670,741 @@ mod tests {
 
         Ok(())
     }
+
+    /// Record a one-entry run with the given actor and outcome
+    fn record_run(manager: &LineageManager, actor: Actor, intent: &str, outcome: ExecutionOutcome) -> Result<RunId> {
+        let run_id = RunId::new();
+        manager.record(
+            run_id,
+            Seq::zero(),
+            actor,
+            format!("{} step", intent),
+            intent,
+            "",
+            outcome,
+            Provenance {
+                tool_versions: crate::ToolVersions::current(),
+                config_hash: "abc123".to_string(),
+                template_id: None,
+                parent_run_id: None,
+                lineage_chain: vec!["template:lint_001".to_string()],
+                confidence: None,
+                git_dirty: None,
+                authored_by: None,
+                annotations: Vec::new(),
+            },
+            Impact::default(),
+        )?;
+        Ok(run_id)
+    }
+
+    #[test]
+    fn test_full_ancestry_across_generations() -> Result<()> {
+        for_each_backend(check_full_ancestry_across_generations)
+    }
+
+    fn check_full_ancestry_across_generations(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(backend);
+        let human = Actor::Human { username: "alice".to_string() };
+        let grandparent = record_run(&manager, human, "Lint", ExecutionOutcome::Success)?;
+        let failed = ExecutionOutcome::Failed { reason: "tests failed".to_string() };
+        let parent = record_run(&manager, Actor::Automation { rule_id: "repair".to_string() }, "Repair", failed)?;
+        let child = record_run(&manager, Actor::System, "Rerun", ExecutionOutcome::Success)?;
+        manager.record_child_run(grandparent, parent)?;
+        manager.record_child_run(parent, child)?;
+
+        let entry = manager.load(child, Seq::zero())?;
+        assert_eq!(entry.provenance.parent_run_id, Some(parent));
+        assert_eq!(entry.provenance.lineage_chain, vec!["template:lint_001".to_string(), format!("run:{}", parent)]);
+
+        let ancestry = manager.build_full_ancestry(child)?;
+        let ids: Vec<RunId> = ancestry.iter().map(|run| run.run_id).collect();
+        assert_eq!(ids, vec![grandparent, parent, child]);
+        assert_eq!(ancestry[1].outcomes.get("failed"), Some(&1));
+        assert_eq!(ancestry[0].actor, Some(Actor::Human { username: "alice".to_string() }));
+
+        let tree = render_ancestry(&ancestry);
+        assert_eq!(tree.lines().count(), 3);
+        assert!(tree.starts_with(&format!("{} [alice] Lint (1 success)\n", grandparent)), "{}", tree);
+        assert!(tree.contains(&format!("\n└─ {} [rule:repair] Repair (1 failed)\n", parent)), "{}", tree);
+        assert!(tree.ends_with(&format!("\n   └─ {} [system] Rerun (1 success)\n", child)), "{}", tree);
+
+        // A root has only itself; a shallow depth limit stops the walk
+        assert_eq!(manager.build_full_ancestry(grandparent)?.len(), 1);
+        let shallow = LineageManager::with_backend(Arc::clone(&manager.backend)).with_max_ancestry_depth(1);
+        assert!(shallow.build_full_ancestry(child).is_err());
+        assert!(manager.record_child_run(child, child).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_ancestry_detects_cycles() -> Result<()> {
+        let manager = LineageManager::with_backend(MemoryBackend::shared());
+        let first = record_run(&manager, Actor::System, "First", ExecutionOutcome::Success)?;
+        let second = record_run(&manager, Actor::System, "Second", ExecutionOutcome::Success)?;
+        let third = record_run(&manager, Actor::System, "Third", ExecutionOutcome::Success)?;
+        manager.record_child_run(first, second)?;
+        manager.record_child_run(second, third)?;
+        manager.record_child_run(third, first)?;
+
+        let err = manager.build_full_ancestry(third).unwrap_err();
+        assert!(err.to_string().contains("Lineage cycle"), "{}", err);
+
+        // A parent without lineage is reported, not skipped
+        manager.record_child_run(RunId::new(), first)?;
+        assert!(manager.build_full_ancestry(second).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact_username() -> Result<()> {
+        let manager = LineageManager::with_backend(MemoryBackend::shared());
+
+        let lineage = manager.record(
+            RunId::new(),
+            Seq::zero(),
+            Actor::Human { username: "alice".to_string() },
+            "Fixed imports in /home/alice/proj/src/main.rs",
+            "Repair",
+            "",
+            ExecutionOutcome::Success,
+            Provenance {
+                tool_versions: crate::ToolVersions::current(),
+                config_hash: "abc123".to_string(),
+                template_id: None,
+                parent_run_id: None,
+                lineage_chain: vec![],
+                confidence: None,
+                git_dirty: None,
+                authored_by: None,
+                annotations: Vec::new(),
+            },
+            Impact::default(),
+        )?;
+
+        let redacted = lineage.redact(&[RedactField::ActorUsername]);
+        assert_eq!(redacted.actor, Actor::Human { username: REDACTED.to_string() });
+        assert_eq!(redacted.summary, lineage.summary);
+        assert_eq!(redacted.provenance.config_hash, "abc123");
+        assert_eq!(redacted.lineage_id, lineage.lineage_id);
+
+        let scrubbed = lineage.redact_with(
+            &[RedactField::FilePaths, RedactField::ConfigHash],
+            RedactStyle::Hash,
+        );
+        assert!(scrubbed.summary.starts_with("Fixed imports in sha256:"));
+        assert!(!scrubbed.summary.contains("alice"));
+        assert_ne!(scrubbed.provenance.config_hash, "abc123");
+        assert_eq!(scrubbed.actor, lineage.actor);
+
+        Ok(())
+    }
+
+    /// Write a v1 (flat, marker-less) run the way older versions did
+    fn write_flat_run(backend: &dyn StorageBackend, run_id: RunId, count: u64) -> Result<()> {
+        for i in 0..count {
+            let lineage = JSONLineage {
+                lineage_id: format!("{}_{}", run_id, i),
+                run_id,
+                seq: Seq(i),
+                timestamp: Utc::now(),
+                actor: Actor::System,
+                summary: format!("Step {}", i),
+                intent: format!("Intent {}", i),
+                command_executed: String::new(),
+                outcome: ExecutionOutcome::Success,
+                provenance: Provenance {
+                    tool_versions: crate::ToolVersions::current(),
+                    config_hash: "abc123".to_string(),
+                    template_id: None,
+                    parent_run_id: None,
+                    lineage_chain: vec![],
+                    confidence: None,
+                    git_dirty: None,
+                    authored_by: None,
+                    annotations: Vec::new(),
+                },
+                impact: Impact::default(),
+                tests: Vec::new(),
+                diff_id: None,
+                git_sha: None,
+                origin: None,
+            };
+            backend.put_atomic(
+                &format!("{}/seq_{:04}.json", run_id, i),
+                serde_json::to_string_pretty(&lineage)?.as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn as_json(entries: &[JSONLineage]) -> Result<Vec<String>> {
+        entries.iter().map(|e| Ok(serde_json::to_string(e)?)).collect()
+    }
+
+    #[test]
+    fn test_migrate_flat_run_to_sharded() -> Result<()> {
+        for_each_backend(check_migrate_flat_run_to_sharded)
+    }
+
+    fn check_migrate_flat_run_to_sharded(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(Arc::clone(&backend));
+        let run_id = RunId::new();
+        write_flat_run(backend.as_ref(), run_id, 3000)?;
+
+        assert_eq!(manager.layout(run_id)?, LineageLayout::Flat);
+        let before = as_json(&manager.iter_run(run_id)?.collect::<Result<Vec<_>>>()?)?;
+        let page_before = as_json(&manager.page(run_id, 995, 10)?)?;
+        let chain_before = manager.build_lineage_chain(run_id)?;
+
+        assert_eq!(manager.migrate_layout(run_id)?, 3000);
+
+        assert_eq!(manager.layout(run_id)?, LineageLayout::Sharded);
+        assert!(backend.exists(&format!("{}/shard_002/seq_0000002999.json", run_id))?);
+        assert!(!backend.exists(&format!("{}/seq_0042.json", run_id))?);
+
+        let after = as_json(&manager.iter_run(run_id)?.collect::<Result<Vec<_>>>()?)?;
+        assert_eq!(before.len(), 3000);
+        assert_eq!(before, after);
+        assert_eq!(page_before, as_json(&manager.page(run_id, 995, 10)?)?);
+        assert_eq!(chain_before, manager.build_lineage_chain(run_id)?);
+
+        // Already sharded: nothing to do
+        assert_eq!(manager.migrate_layout(run_id)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_runs_use_sharded_layout() -> Result<()> {
+        for_each_backend(check_new_runs_use_sharded_layout)
+    }
+
+    fn check_new_runs_use_sharded_layout(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(Arc::clone(&backend));
+        let run_id = RunId::new();
+        let flat_run = RunId::new();
+        write_flat_run(backend.as_ref(), flat_run, 2)?;
+
+        manager.link_git_sha(flat_run, Seq(1), "abc".to_string())?;
+        assert_eq!(manager.layout(flat_run)?, LineageLayout::Flat);
+        assert_eq!(manager.load(flat_run, Seq(1))?.git_sha.as_deref(), Some("abc"));
+
+        manager.record(
+            run_id,
+            Seq(1001),
+            Actor::System,
+            "Sharded",
+            "Intent",
+            "",
+            ExecutionOutcome::Success,
+            Provenance {
+                tool_versions: crate::ToolVersions::current(),
+                config_hash: "abc123".to_string(),
+                template_id: None,
+                parent_run_id: None,
+                lineage_chain: vec![],
+                confidence: None,
+                git_dirty: None,
+                authored_by: None,
+                annotations: Vec::new(),
+            },
+            Impact::default(),
+        )?;
+
+        assert_eq!(manager.layout(run_id)?, LineageLayout::Sharded);
+        assert!(backend.exists(&format!("{}/shard_001/seq_0000001001.json", run_id))?);
+        assert_eq!(manager.load(run_id, Seq(1001))?.summary, "Sharded");
+
+        let mut runs = vec![run_id, flat_run];
+        runs.sort_by_key(|run| run.0);
+        let mut listed: Vec<RunId> = manager.list_runs()?.into_iter().map(|(run, ..)| run).collect();
+        listed.sort_by_key(|run| run.0);
+        assert_eq!(listed, runs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lineage_hook_attaches_tests() -> Result<()> {
+        for_each_backend(check_lineage_hook_attaches_tests)
+    }
+
+    fn check_lineage_hook_attaches_tests(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(Arc::clone(&backend));
+        let run_id = RunId::new();
+        write_flat_run(backend.as_ref(), run_id, 1)?;
+
+        let mut hook = LineageHook::new();
+        hook.push_test(TestRecord {
+            test_id: "assert_line_2".to_string(),
+            test_name: "teeth >= 4".to_string(),
+            status: crate::TestStatus::Passed,
+            duration_ms: None,
+            logs: vec![],
+        });
+        hook.flush(&manager, run_id, Seq(0))?;
+
+        assert!(hook.pending().is_empty());
+        let lineage = manager.load(run_id, Seq(0))?;
+        assert_eq!(lineage.tests.len(), 1);
+        assert_eq!(lineage.tests[0].test_name, "teeth >= 4");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_index_lifecycle() -> Result<()> {
+        for_each_backend(check_session_index_lifecycle)
+    }
+
+    fn check_session_index_lifecycle(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let run_id = RunId::new();
+        let diff = |seq: u64| DiffReference {
+            seq: Seq(seq),
+            diff_id: format!("diff_{}", seq),
+            timestamp: Utc::now(),
+            summary: format!("Change {}", seq),
+        };
+        let delta = |files: usize, added: usize| SessionTotals {
+            files_changed: files,
+            lines_added: added,
+            lines_removed: 1,
+            tests_run: 2,
+            tests_passed: 1,
+            ..SessionTotals::default()
+        };
+
+        let mut session = SessionIndexManager::open_with_backend(Arc::clone(&backend), run_id)?;
+        session.append_diff(diff(3), delta(1, 10))?;
+        session.append_diff(diff(1), delta(2, 5))?;
+        assert!(session.append_diff(diff(1), delta(9, 9)).is_err());
+
+        // Reopening continues the same session
+        let mut session = SessionIndexManager::open_with_backend(Arc::clone(&backend), run_id)?;
+        assert_eq!(session.index().diffs.len(), 2);
+        session.append_diff(diff(2), delta(3, 7))?;
+        let before_finalize = Utc::now();
+        session.finalize()?;
+        assert!(session.append_diff(diff(4), delta(1, 1)).is_err());
+
+        let reloaded = SessionIndexManager::open_with_backend(backend, run_id)?;
+        let index = reloaded.index();
+        let seqs: Vec<u64> = index.diffs.iter().map(|d| d.seq.0).collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert_eq!(index.totals.total_diffs, 3);
+        assert_eq!(index.totals.files_changed, 6);
+        assert_eq!(index.totals.lines_added, 22);
+        assert_eq!(index.totals.lines_removed, 3);
+        assert_eq!(index.totals.tests_run, 6);
+        assert_eq!(index.totals.tests_passed, 3);
+        let ended = index.ended.expect("finalized session has an end time");
+        assert!(ended >= before_finalize && ended >= index.started);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lineage_manager_maintains_session() -> Result<()> {
+        for_each_backend(check_lineage_manager_maintains_session)
+    }
+
+    fn check_lineage_manager_maintains_session(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(backend);
+        let run_id = RunId::new();
+        let record = |seq: u64, summary: &str, lines: usize| {
+            manager.record(
+                run_id,
+                Seq(seq),
+                Actor::System,
+                summary,
+                "Repair build",
+                "",
+                ExecutionOutcome::Success,
+                Provenance {
+                    tool_versions: crate::ToolVersions::current(),
+                    config_hash: "abc123".to_string(),
+                    template_id: None,
+                    parent_run_id: None,
+                    lineage_chain: vec![],
+                    confidence: None,
+                    git_dirty: None,
+                    authored_by: None,
+                    annotations: Vec::new(),
+                },
+                Impact { files_changed: 1, lines_added: lines, lines_removed: 1, ..Impact::default() },
+            )
+        };
+        let test = |name: &str, status: TestStatus| TestRecord {
+            test_id: name.to_string(),
+            test_name: name.to_string(),
+            status,
+            duration_ms: Some(5),
+            logs: vec![],
+        };
+
+        // Nothing is indexed before the session opens
+        assert!(manager.load_session_index(run_id).is_err());
+        let opened = manager.open_session(run_id)?;
+        assert!(opened.diffs.is_empty() && opened.ended.is_none());
+
+        // Entries from concurrent writers all land in the index
+        std::thread::scope(|scope| {
+            let writers: Vec<_> = [(0, "Fix imports", 4), (1, "Rename module", 2), (2, "Drop dead code", 0)]
+                .into_iter()
+                .map(|(seq, summary, lines)| {
+                    let (record, test, manager) = (&record, &test, &manager);
+                    scope.spawn(move || -> Result<()> {
+                        record(seq, summary, lines)?;
+                        manager.add_test_record(run_id, Seq(seq), test("unit", TestStatus::Passed))?;
+                        manager.add_test_record(run_id, Seq(seq), test("integration", TestStatus::Skipped))
+                    })
+                })
+                .collect();
+            writers.into_iter().try_for_each(|writer| writer.join().expect("writer panicked"))
+        })?;
+        manager.link_diff(run_id, Seq(2), "diff_0002".to_string())?;
+        manager.link_diff(run_id, Seq(0), "diff_0000".to_string())?;
+        manager.link_diff(run_id, Seq(1), "diff_0001".to_string())?;
+        assert!(manager.link_diff(run_id, Seq(1), "diff_0003".to_string()).is_err());
+        manager.link_git_sha(run_id, Seq(1), "abc1234".to_string())?;
+        manager.link_git_sha(run_id, Seq(2), "abc1234".to_string())?;
+
+        let closed = manager.close_session(run_id)?;
+        assert!(closed.ended.is_some());
+        assert!(manager.add_test_record(run_id, Seq(0), test("late", TestStatus::Passed)).is_err());
+        assert_eq!(manager.load(run_id, Seq(0))?.tests.len(), 2);
+        // A closed session seals the run: late entries are rejected, not saved
+        assert!(record(3, "Late fix", 1).is_err());
+        assert!(manager.load(run_id, Seq(3)).is_err());
+
+        let index = manager.load_session_index(run_id)?;
+        let diffs: Vec<(u64, &str, &str)> =
+            index.diffs.iter().map(|d| (d.seq.0, d.diff_id.as_str(), d.summary.as_str())).collect();
+        assert_eq!(diffs, vec![
+            (0, "diff_0000", "Fix imports"),
+            (1, "diff_0001", "Rename module"),
+            (2, "diff_0002", "Drop dead code"),
+        ]);
+        let totals = &index.totals;
+        assert_eq!((totals.total_diffs, totals.files_changed, totals.lines_added, totals.lines_removed), (3, 3, 6, 3));
+        assert_eq!((totals.tests_run, totals.tests_passed), (6, 3));
+        assert_eq!(index.git_shas, vec!["abc1234"]);
+        assert_eq!(index.provenance_links.len(), 3);
+
+        let summary = manager.summarize_session(run_id)?;
+        assert!(summary.contains("Entries: 3, diffs: 3, files changed: 3, +6 -3"), "{}", summary);
+        assert!(summary.contains("Tests: 3/6 passed"), "{}", summary);
+        assert!(summary.contains("- #1 diff_0001: Rename module"), "{}", summary);
+        Ok(())
+    }
+
+    #[test]
+    fn test_summarize_run() -> Result<()> {
+        let backend = MemoryBackend::shared();
+        let manager = LineageManager::with_backend(Arc::clone(&backend));
+        let run_id = RunId::new();
+        let provenance = || Provenance {
+            tool_versions: crate::ToolVersions::current(),
+            config_hash: "abc123".to_string(),
+            template_id: None,
+            parent_run_id: None,
+            lineage_chain: vec![],
+            confidence: None,
+            git_dirty: None,
+            authored_by: None,
+            annotations: Vec::new(),
+        };
+        let impact = |modules: &[&str], lines: usize| Impact {
+            files_changed: modules.len(),
+            lines_added: lines,
+            lines_removed: 1,
+            functions_affected: 1,
+            objects_affected: 1,
+            modules_affected: modules.iter().map(|m| m.to_string()).collect(),
+        };
+
+        manager.record(run_id, Seq(0), Actor::System, "Fix imports", "Repair build", "", ExecutionOutcome::Success,
+            provenance(), impact(&["crates/oasm-core/src/executor/mod.rs"], 4))?;
+        manager.record(run_id, Seq(1), Actor::System, "Retry imports", "Repair build", "", ExecutionOutcome::Success,
+            provenance(), impact(&["oasm_core::executor", "oasm_core::parser"], 2))?;
+        let mut failed = manager.record(run_id, Seq(2), Actor::System, "Apply gear overlay", "Apply overlay", "",
+            ExecutionOutcome::Failed { reason: "teeth out of range".to_string() }, provenance(), impact(&[], 0))?;
+        failed.origin = Some(FieldOrigin { field: "gear.teeth".to_string(), annotation: Some("tooth count".to_string()) });
+        manager.save(&failed)?;
+        manager.link_diff(run_id, Seq(0), "diff_0".to_string())?;
+        manager.link_git_sha(run_id, Seq(1), "abc1234".to_string())?;
+
+        let summary = manager.summarize_run(run_id, &ModuleMapper::new())?;
+        assert_eq!(summary.entries, 3);
+        assert_eq!(summary.intents, vec![
+            IntentCount { intent: "Repair build".to_string(), count: 2 },
+            IntentCount { intent: "Apply overlay".to_string(), count: 1 },
+        ]);
+        assert_eq!(summary.outcomes.get("success"), Some(&2));
+        assert_eq!(summary.outcomes.get("failed"), Some(&1));
+        assert_eq!(summary.impact.lines_added, 6);
+        assert_eq!(summary.top_modules[0], ModuleCount { module: "oasm_core::executor".to_string(), entries: 2 });
+        assert_eq!(summary.top_modules.len(), 2);
+        assert_eq!(summary.artifacts, vec!["diff:diff_0", "git:abc1234"]);
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.failures[0].seq, Seq(2));
+
+        let markdown = summary.to_markdown();
+        assert!(markdown.starts_with(&format!("# Run {}", run_id)));
+        assert!(markdown.contains("- Repair build (x2)"));
+        assert!(markdown.contains("- #2 Apply gear overlay: teeth out of range"));
+        assert!(markdown.contains("originating overlay field: gear.teeth (annotated: 'tooth count')"));
+
+        manager.save_summary(&summary)?;
+        let saved = backend.get(&format!("{}/summary.md", run_id))?;
+        assert_eq!(String::from_utf8(saved)?, markdown);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_across_runs() -> Result<()> {
+        for_each_backend(check_query_across_runs)
+    }
+
+    fn check_query_across_runs(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(backend);
+        let start = Utc::now() - chrono::Duration::hours(10);
+        let failed = || ExecutionOutcome::Failed { reason: "build broke".to_string() };
+        let record = |run_id: RunId, seq: u64, hours: i64, actor: Actor, outcome: ExecutionOutcome| -> Result<()> {
+            let provenance = Provenance {
+                tool_versions: crate::ToolVersions::current(),
+                config_hash: "abc123".to_string(),
+                template_id: None,
+                parent_run_id: None,
+                lineage_chain: vec![],
+                confidence: None,
+                git_dirty: None,
+                authored_by: None,
+                annotations: Vec::new(),
+            };
+            let mut entry = manager.record(run_id, Seq(seq), actor, format!("step {}", seq), "Query", "",
+                outcome, provenance, Impact::default())?;
+            entry.timestamp = start + chrono::Duration::hours(hours);
+            manager.save(&entry)
+        };
+
+        let (run_a, run_b) = (RunId::new(), RunId::new());
+        let human = || Actor::Human { username: "alice".to_string() };
+        record(run_a, 0, 0, Actor::System, failed())?;
+        record(run_a, 1, 2, Actor::System, ExecutionOutcome::Success)?;
+        record(run_a, 2, 3, human(), failed())?;
+        record(run_a, 3, 8, Actor::System, failed())?;
+        record(run_b, 0, 4, Actor::System, ExecutionOutcome::Cancelled)?;
+        record(run_b, 1, 5, Actor::System, failed())?;
+        record(run_b, 2, 6, Actor::System, ExecutionOutcome::Success)?;
+
+        let window = LineageQuery::new()
+            .with_outcome(OutcomeKind::Failed)
+            .with_range(start + chrono::Duration::hours(1), start + chrono::Duration::hours(7));
+        let found: Vec<String> = manager.query(window.clone())?.into_iter().map(|e| e.lineage_id).collect();
+        assert_eq!(found, vec![format!("{}_1", run_b), format!("{}_2", run_a)]);
+
+        let humans = manager.query(window.with_actor(ActorKind::Human))?;
+        assert_eq!(humans.len(), 1);
+        assert_eq!(humans[0].seq, Seq(2));
+
+        assert_eq!(manager.query(LineageQuery::new())?.len(), 7);
+        assert_eq!(manager.query(LineageQuery::new().with_outcome(OutcomeKind::Failed))?.len(), 4);
+
+        // Newest first, cut at the limit
+        let latest: Vec<Seq> = manager
+            .query(LineageQuery::new().with_actor(ActorKind::System).with_limit(3))?
+            .into_iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(latest, vec![Seq(3), Seq(2), Seq(1)]);
+
+        // Runs by first entry, newest first
+        assert_eq!(manager.list_runs()?, vec![
+            (run_b, start + chrono::Duration::hours(4), 3),
+            (run_a, start, 4),
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_by_template_and_text() -> Result<()> {
+        for_each_backend(check_query_by_template_and_text)
+    }
+
+    fn check_query_by_template_and_text(backend: Arc<dyn StorageBackend>) -> Result<()> {
+        let manager = LineageManager::with_backend(backend);
+        let run_id = RunId::new();
+        let record = |seq: u64, actor: Actor, summary: &str, intent: &str, template_id: Option<&str>| {
+            manager.record(run_id, Seq(seq), actor, summary, intent, "", ExecutionOutcome::Failed { reason: "lint".to_string() },
+                Provenance {
+                    tool_versions: crate::ToolVersions::current(),
+                    config_hash: "abc123".to_string(),
+                    template_id: template_id.map(str::to_string),
+                    parent_run_id: None,
+                    lineage_chain: vec![],
+                    confidence: None,
+                    git_dirty: None,
+                    authored_by: None,
+                    annotations: Vec::new(),
+                },
+                Impact::default())
+        };
+        let ai = || Actor::AI { model: "repair-bot".to_string(), confidence: 0.9 };
+
+        record(0, ai(), "Fix unsafe block", "Repair build", Some("lint_001"))?;
+        record(1, Actor::System, "Format imports", "Tidy", Some("lint_001"))?;
+        record(2, ai(), "Rename parser", "Refactor UNSAFE helpers", Some("refactor_002"))?;
+        record(3, ai(), "Bump version", "Release", None)?;
+
+        let seqs = |query: LineageQuery| -> Result<Vec<u64>> {
+            let mut seqs: Vec<u64> = manager.query(query)?.into_iter().map(|e| e.seq.0).collect();
+            seqs.sort();
+            Ok(seqs)
+        };
+        assert_eq!(seqs(LineageQuery::new().with_template("lint_001"))?, vec![0, 1]);
+        assert_eq!(seqs(LineageQuery::new().with_text("unsafe"))?, vec![0, 2]);
+        assert_eq!(seqs(LineageQuery::new().with_text("unsafe").with_template("lint_001"))?, vec![0]);
+        let failed_ai = LineageQuery::new().with_actor(ActorKind::AI).with_outcome(OutcomeKind::Failed);
+        assert_eq!(seqs(failed_ai.clone())?, vec![0, 2, 3]);
+        assert_eq!(seqs(failed_ai.with_text("release"))?, vec![3]);
+        assert!(seqs(LineageQuery::new().with_template("missing"))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_skips_files_written_before_since() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = LineageManager::new(temp_dir.path());
+        let run_id = RunId::new();
+        for seq in 0..2 {
+            manager.record(run_id, Seq(seq), Actor::System, "Step", "Intent", "", ExecutionOutcome::Success,
+                Provenance {
+                    tool_versions: crate::ToolVersions::current(),
+                    config_hash: "abc123".to_string(),
+                    template_id: None,
+                    parent_run_id: None,
+                    lineage_chain: vec![],
+                    confidence: None,
+                    git_dirty: None,
+                    authored_by: None,
+                    annotations: Vec::new(),
+                },
+                Impact { functions_affected: 1, ..Impact::default() })?;
+        }
+
+        // An old file is never parsed, so its corruption goes unnoticed
+        let layout = manager.layout(run_id)?;
+        let old = temp_dir.path().join(layout.entry_key(&run_id.to_string(), Seq(0)));
+        std::fs::write(&old, "not json")?;
+        let week_ago = Utc::now() - chrono::Duration::days(7);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)?
+            .set_modified((week_ago - chrono::Duration::days(1)).into())?;
+
+        let recent = manager.query(LineageQuery::new().with_range(week_ago, Utc::now()))?;
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].seq, Seq(1));
+        assert!(manager.query(LineageQuery::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_requires_config_hash_when_strict() -> Result<()> {
+        let manager = LineageManager::with_backend(MemoryBackend::shared())
+            .with_config_hash_policy(ConfigHashPolicy::Require);
+        let provenance = |config_hash: &str| Provenance {
+            tool_versions: crate::ToolVersions::current(),
+            config_hash: config_hash.to_string(),
+            template_id: None,
+            parent_run_id: None,
+            lineage_chain: vec![],
+            confidence: None,
+            git_dirty: None,
+            authored_by: None,
+            annotations: Vec::new(),
+        };
+        let run_id = RunId::new();
+
+        let err = manager
+            .record(run_id, Seq(0), Actor::System, "Unhashed", "Intent", "", ExecutionOutcome::Success,
+                provenance(""), Impact::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("has no config_hash"), "{}", err);
+        assert!(manager.get_run_lineage(run_id)?.is_empty());
+
+        manager.record(run_id, Seq(0), Actor::System, "Hashed", "Intent", "", ExecutionOutcome::Success,
+            provenance("abc123"), Impact::default())?;
+        assert_eq!(manager.get_run_lineage(run_id)?.len(), 1);
+
+        // The manager's own hash fills unhashed provenance, but never replaces a hash
+        let stamping = LineageManager::with_backend(MemoryBackend::shared())
+            .with_config_hash("def456")
+            .with_config_hash_policy(ConfigHashPolicy::Require);
+        let stamped = stamping.record(run_id, Seq(0), Actor::System, "Stamped", "Intent", "", ExecutionOutcome::Success,
+            provenance(""), Impact::default())?;
+        assert_eq!(stamped.provenance.config_hash, "def456");
+        let kept = stamping.record(run_id, Seq(1), Actor::System, "Kept", "Intent", "", ExecutionOutcome::Success,
+            provenance("abc123"), Impact::default())?;
+        assert_eq!(kept.provenance.config_hash, "abc123");
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_captures_git_state() -> Result<()> {
+        let repo = tempfile::tempdir()?;
+        let git = |args: &[&str]| -> Result<String> {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(repo.path())
+                .args(["-c", "user.name=oasm", "-c", "user.email=oasm@example.com"])
+                .args(args)
+                .output()?;
+            anyhow::ensure!(output.status.success(), "git {:?} failed", args);
+            Ok(String::from_utf8(output.stdout)?.trim().to_string())
+        };
+        git(&["init", "-q"])?;
+        std::fs::write(repo.path().join("gear.oasm"), "CREATE gear\n")?;
+        git(&["add", "."])?;
+        git(&["commit", "-q", "-m", "init"])?;
+
+        let provenance = || Provenance {
+            tool_versions: crate::ToolVersions::current(),
+            config_hash: "abc123".to_string(),
+            template_id: None,
+            parent_run_id: None,
+            lineage_chain: vec![],
+            confidence: None,
+            git_dirty: None,
+            authored_by: None,
+            annotations: Vec::new(),
+        };
+        let manager = LineageManager::with_backend(MemoryBackend::shared()).with_repo_root(repo.path());
+        let run_id = RunId::new();
+
+        let clean = manager.record(run_id, Seq(0), Actor::System, "Step", "Intent", "", ExecutionOutcome::Success,
+            provenance(), Impact::default())?;
+        assert_eq!(clean.git_sha, Some(git(&["rev-parse", "HEAD"])?));
+        assert_eq!(clean.provenance.git_dirty, Some(false));
+
+        std::fs::write(repo.path().join("gear.oasm"), "CREATE gear\nSET gear.teeth 24\n")?;
+        let dirty = manager.record(run_id, Seq(1), Actor::System, "Step", "Intent", "", ExecutionOutcome::Success,
+            provenance(), Impact::default())?;
+        assert_eq!(dirty.provenance.git_dirty, Some(true));
+        assert_eq!(manager.load(run_id, Seq(1))?.git_sha, clean.git_sha);
+
+        // Not a repo: nothing captured, no error
+        let not_a_repo = tempfile::tempdir()?;
+        let manager = LineageManager::with_backend(MemoryBackend::shared()).with_repo_root(not_a_repo.path());
+        let lineage = manager.record(run_id, Seq(0), Actor::System, "Step", "Intent", "", ExecutionOutcome::Success,
+            provenance(), Impact::default())?;
+        assert_eq!(lineage.git_sha, None);
+        assert_eq!(lineage.provenance.git_dirty, None);
+
+        Ok(())
+    }
 }
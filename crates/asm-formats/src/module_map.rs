@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+/// Maps workspace source paths to Rust module paths, e.g.
+/// `crates/oasm-core/src/executor/mod.rs` -> `oasm_core::executor`.
+/// Used to match changed files against lineage impact records, which name
+/// modules rather than files.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleMapper {
+    /// Crate directory -> crate name, for crates not named after their directory
+    crate_names: HashMap<String, String>,
+}
+
+impl ModuleMapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the crate name for a crate directory (e.g. `runtime/daemon` -> `runtime_daemon`)
+    pub fn with_crate(mut self, crate_dir: impl Into<String>, crate_name: impl Into<String>) -> Self {
+        self.crate_names.insert(crate_dir.into(), crate_name.into());
+        self
+    }
+
+    /// Module path for a `.rs` file under a crate's `src/`; None for anything else
+    pub fn module_for(&self, path: &str) -> Option<String> {
+        let path = path.replace('\\', "/");
+        let stem = path.strip_suffix(".rs")?;
+
+        let (crate_dir, module) = match stem.rsplit_once("/src/") {
+            Some((crate_dir, module)) => (crate_dir, module),
+            None => ("", stem.strip_prefix("src/")?),
+        };
+
+        let crate_name = match self.crate_names.get(crate_dir) {
+            Some(name) => name.clone(),
+            None => crate_dir.rsplit('/').next().filter(|n| !n.is_empty())?.replace('-', "_"),
+        };
+
+        let mut parts: Vec<&str> = module.split('/').collect();
+        if parts.last() == Some(&"mod") || parts == ["lib"] || parts == ["main"] {
+            parts.pop();
+        }
+
+        Some(std::iter::once(crate_name.as_str()).chain(parts).collect::<Vec<_>>().join("::"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_for_paths() {
+        let mapper = ModuleMapper::new().with_crate("runtime/daemon", "runtime_daemon");
+
+        assert_eq!(mapper.module_for("crates/oasm-core/src/executor/mod.rs").as_deref(), Some("oasm_core::executor"));
+        assert_eq!(mapper.module_for("compiler/src/lib.rs").as_deref(), Some("compiler"));
+        assert_eq!(mapper.module_for("runtime\\daemon\\src\\commit.rs").as_deref(), Some("runtime_daemon::commit"));
+        assert_eq!(mapper.module_for("README.md"), None);
+    }
+}
@@ -3,23 +3,61 @@
 //! Binary execution units generated from HDF5 templates or YAML overlays.
 //! Compact, deterministic, immutable once created for a run.
 
-use crate::schemas::{CBORRuntimeObject, CommandBlock, BlockType, AutoPopulatedFields};
-use crate::{RunId, Seq, Actor, ExecutionMetadata};
-use anyhow::Result;
-use std::path::Path;
+use crate::schemas::{CBORRuntimeObject, CommandBlock, BlockType, AutoPopulatedFields, ParameterValue};
+use crate::lineage::LineageManager;
+use crate::schemas::{InstructionAuthor, TestRecord};
+use crate::{RunId, Seq, Actor, ExecutionMetadata, Language, TestStatus};
+use anyhow::{anyhow, Result};
+use oasm_core::command_blocks::CommandBlock as CoreCommandBlock;
+use oasm_core::context::{Actor as CoreActor, ExecutionContext};
+use oasm_core::executor::{ExecutionOutcome as CoreOutcome, InstructionExecutor, NativeExecutor};
+use oasm_core::parser::{Instruction, InstructionParser, NativeParser, Operand};
+use oasm_core::types::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 /// Runtime object manager
 pub struct RuntimeObjectManager {
     cache_dir: std::path::PathBuf,
+    /// Working directory of the context objects execute in
+    working_directory: PathBuf,
+    /// Translate and dry-run objects instead of executing them
+    dry_run: bool,
+    /// `config_hash` of the metadata of created objects
+    config_hash: Option<String>,
 }
 
 impl RuntimeObjectManager {
     pub fn new(cache_dir: impl AsRef<Path>) -> Self {
         Self {
             cache_dir: cache_dir.as_ref().to_path_buf(),
+            working_directory: PathBuf::from("."),
+            dry_run: false,
+            config_hash: None,
         }
     }
 
+    /// Stamp created objects with the canonical hash of `config`, the
+    /// effective configuration (see `config_hash::compute_config_hash`)
+    pub fn with_config(mut self, config: &impl serde::Serialize) -> Result<Self> {
+        self.config_hash = Some(crate::config_hash::compute_config_hash(config)?);
+        Ok(self)
+    }
+
+    /// Root the execution context at `dir` (default: the current directory)
+    pub fn with_working_directory(mut self, dir: impl AsRef<Path>) -> Self {
+        self.working_directory = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Only translate objects and dry-run the instructions against a
+    /// scratch context; translation errors still fail the object
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
     /// Create a new runtime object
     pub fn create_object(
         &self,
@@ -28,24 +66,11 @@ impl RuntimeObjectManager {
         actor: Actor,
         command: CommandBlock,
     ) -> CBORRuntimeObject {
-        let object_id = format!("{}_{}", run_id, seq.0);
-
-        CBORRuntimeObject {
-            object_id,
-            metadata: ExecutionMetadata::new(actor.clone()),
-            command,
-            auto_fields: AutoPopulatedFields {
-                run_id,
-                seq,
-                timestamp: chrono::Utc::now(),
-                actor,
-                file_path: None,
-                rule_group: None,
-                confidence: None,
-                tests_planned: Vec::new(),
-            },
-            decisions: Vec::new(),
+        let mut object = CBORRuntimeObject::new(run_id, seq, actor, command);
+        if let Some(hash) = &self.config_hash {
+            object.metadata.config_hash = hash.clone();
         }
+        object
     }
 
     /// Serialize to CBOR bytes
@@ -62,36 +87,224 @@ impl RuntimeObjectManager {
     pub fn save_object(&self, obj: &CBORRuntimeObject) -> Result<()> {
         std::fs::create_dir_all(&self.cache_dir)?;
 
-        let path = self.cache_dir.join(format!("{}.cbor", obj.object_id));
-        let bytes = self.to_cbor(obj)?;
-        std::fs::write(path, bytes)?;
-
+        obj.write_to(self.cache_dir.join(format!("{}.cbor", obj.object_id)))?;
         Ok(())
     }
 
     /// Load runtime object from cache
     pub fn load_object(&self, object_id: &str) -> Result<CBORRuntimeObject> {
-        let path = self.cache_dir.join(format!("{}.cbor", object_id));
-        let bytes = std::fs::read(path)?;
-        self.from_cbor(&bytes)
+        Ok(CBORRuntimeObject::read_from(self.cache_dir.join(format!("{}.cbor", object_id)))?)
     }
 
-    /// Execute a runtime object
+    /// Execute a runtime object: translate its command block into OASM
+    /// instructions (see `translate_command`) and run them with a
+    /// NativeExecutor in a fresh context
     pub fn execute(&self, obj: &CBORRuntimeObject) -> Result<ExecutionResult> {
-        // TODO: Implement actual execution logic
-        // This would dispatch to appropriate handlers based on block_type
+        let failed = |reason: String, origin: Option<String>| ExecutionResult {
+            object_id: obj.object_id.clone(),
+            run_id: obj.auto_fields.run_id,
+            seq: obj.auto_fields.seq,
+            outcome: crate::schemas::ExecutionOutcome::Failed { reason: reason.clone() },
+            duration_ms: 0,
+            logs: vec![reason],
+            origin,
+            modified_objects: Vec::new(),
+            authored_by: None,
+        };
+
+        // Parameters are checked before dispatch; a bad one fails the object
+        // and is attributed to the overlay field it came from
+        for parameter in &obj.command.parameters {
+            if let Some(reason) = check_parameter(parameter) {
+                return Ok(failed(reason, parameter.origin.clone()));
+            }
+        }
+
+        let instructions = match translate_command(&obj.command) {
+            Ok(instructions) => instructions,
+            Err(e) => return Ok(failed(format!("Could not translate command block: {}", e), None)),
+        };
+        let mut logs: Vec<String> = instructions.iter().map(Instruction::render).collect();
+        let ctx = ExecutionContext::new(core_actor(&obj.auto_fields.actor), self.working_directory.clone());
+        let mut executor = NativeExecutor::new();
+        let start = std::time::Instant::now();
+
+        // Index of the instruction the outcome is blamed on: the first that
+        // failed, else the last one run
+        let (outcome, mut touched, blamed) = if self.dry_run {
+            let report = executor.dry_run(&instructions, &ctx);
+            logs.push(format!("dry run: {} instruction(s) checked", report.instructions_run));
+            let (outcome, blamed) = match report.predicted_errors.first() {
+                Some(error) => (
+                    crate::schemas::ExecutionOutcome::Failed {
+                        reason: format!("line {}: {} would fail: {}", error.line_number, error.mnemonic, error.reason),
+                    },
+                    instructions.iter().position(|i| i.line_number == error.line_number),
+                ),
+                None => (crate::schemas::ExecutionOutcome::Success, instructions.len().checked_sub(1)),
+            };
+            (outcome, [report.created_objects, report.modified_objects].concat(), blamed)
+        } else {
+            let mut ctx = ctx;
+            match executor.execute_batch(&instructions, &mut ctx) {
+                Ok(batch) => {
+                    logs.extend(batch.warnings.iter().map(|w| w.message.clone()));
+                    let touched = batch
+                        .individual_results
+                        .iter()
+                        .flat_map(|r| r.modified_objects.iter().cloned())
+                        .collect();
+                    // Results run in order; an instruction that errored out
+                    // of the batch is the one after the last result
+                    let ran = batch.individual_results.len();
+                    let blamed = batch
+                        .individual_results
+                        .iter()
+                        .position(|r| matches!(r.outcome, CoreOutcome::Failed { .. }))
+                        .or_else(|| {
+                            let stopped = batch.outcome != CoreOutcome::Success && ran < instructions.len();
+                            if stopped { Some(ran) } else { ran.checked_sub(1) }
+                        });
+                    let outcome = match batch.outcome {
+                        CoreOutcome::Success => crate::schemas::ExecutionOutcome::Success,
+                        CoreOutcome::Failed { reason } => crate::schemas::ExecutionOutcome::Failed { reason },
+                        CoreOutcome::PartialSuccess { completed, total } => {
+                            let mut warnings = vec![format!("{} of {} instructions completed", completed, total)];
+                            warnings.extend(batch.warnings.into_iter().map(|w| w.message));
+                            crate::schemas::ExecutionOutcome::PartialSuccess { warnings }
+                        }
+                    };
+                    (outcome, touched, blamed)
+                }
+                Err(e) => (crate::schemas::ExecutionOutcome::Failed { reason: format!("{:?}", e) }, Vec::new(), None),
+            }
+        };
 
+        touched.sort();
+        touched.dedup();
         Ok(ExecutionResult {
             object_id: obj.object_id.clone(),
             run_id: obj.auto_fields.run_id,
             seq: obj.auto_fields.seq,
-            outcome: crate::schemas::ExecutionOutcome::Success,
-            duration_ms: 0,
-            logs: vec![],
+            outcome,
+            duration_ms: start.elapsed().as_millis() as u64,
+            logs,
+            origin: None,
+            modified_objects: touched,
+            authored_by: blamed.and_then(|i| instruction_author(&instructions[i])),
         })
     }
 }
 
+/// Instructions a command block stands for. `OasmProgram` blocks run their
+/// parsed `program`, or else parse their source `instructions`; every other block becomes `CREATE <block_type>` plus one
+/// `SET` per parameter, and `target_files` / `rules` as string arrays, all
+/// on the object the CREATE allocates (`<block_type>_0000` in a fresh
+/// context).
+pub fn translate_command(command: &CommandBlock) -> Result<Vec<Instruction>> {
+    if matches!(command.block_type, BlockType::OasmProgram) {
+        if !command.program.is_empty() {
+            if !command.instructions.is_empty() {
+                return Err(anyhow!("OASM program block has both parsed and source instructions"));
+            }
+            return Ok(command.program.clone());
+        }
+        return NativeParser
+            .parse_file(&command.instructions.join("\n"))
+            .map_err(|e| anyhow!("Invalid OASM instruction: {:?}", e));
+    }
+
+    let object_type = match command.block_type {
+        BlockType::LintCheck => "lint_check",
+        BlockType::RepairBlock => "repair_block",
+        BlockType::TestRunner => "test_runner",
+        BlockType::AnalysisPass => "analysis_pass",
+        BlockType::Converter => "converter",
+        BlockType::OasmProgram => unreachable!("handled above"),
+    };
+    let object_id = format!("{}_0000", object_type);
+    let mut instructions = vec![instruction("CREATE", Operand::Identifier(object_type.to_string()))];
+
+    let strings = |items: &[String]| Operand::Array(items.iter().map(|s| Operand::Literal(Value::String(s.clone()))).collect());
+    let mut set = |property: &str, value: Operand| {
+        let target = Operand::Property { object: object_id.clone(), property: property.to_string() };
+        instructions.push(instruction("SET", Operand::Assignment { target: Box::new(target), value: Box::new(value) }));
+    };
+    for parameter in &command.parameters {
+        if parameter.key.is_empty() || !parameter.key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(anyhow!("Parameter key '{}' is not an identifier", parameter.key));
+        }
+        let value = match &parameter.value {
+            ParameterValue::String(s) => Operand::Literal(Value::String(s.clone())),
+            ParameterValue::Integer(n) => Operand::Literal(Value::I64(*n)),
+            ParameterValue::Float(n) => Operand::Literal(Value::F64(*n)),
+            ParameterValue::Boolean(b) => Operand::Literal(Value::Bool(*b)),
+            ParameterValue::List(items) => strings(items),
+        };
+        set(&parameter.key, value);
+    }
+    if !command.target_files.is_empty() {
+        set("target_files", strings(&command.target_files));
+    }
+    if !command.rules.is_empty() {
+        set("rules", strings(&command.rules));
+    }
+
+    for (i, instruction) in instructions.iter_mut().enumerate() {
+        instruction.line_number = i + 1;
+    }
+    Ok(instructions)
+}
+
+fn instruction(mnemonic: &str, operand: Operand) -> Instruction {
+    Instruction { mnemonic: mnemonic.to_string(), operands: vec![operand], line_number: 0, provenance: None }
+}
+
+fn core_actor(actor: &Actor) -> CoreActor {
+    match actor {
+        Actor::Human { username } => CoreActor::Human { username: username.clone() },
+        Actor::Automation { rule_id } => CoreActor::Automation { rule_id: rule_id.clone() },
+        Actor::AI { model, confidence } => CoreActor::AI { model: model.clone(), confidence: *confidence },
+        Actor::System => CoreActor::System,
+    }
+}
+
+fn formats_actor(actor: &CoreActor) -> Actor {
+    match actor {
+        CoreActor::Human { username } => Actor::Human { username: username.clone() },
+        CoreActor::Automation { rule_id } => Actor::Automation { rule_id: rule_id.clone() },
+        CoreActor::AI { model, confidence } => Actor::AI { model: model.clone(), confidence: *confidence },
+        CoreActor::System => Actor::System,
+    }
+}
+
+/// Lineage provenance for an instruction parsed with provenance, so
+/// lineage entries can be blamed on the line's author
+pub fn instruction_author(instruction: &Instruction) -> Option<InstructionAuthor> {
+    let provenance = instruction.provenance.as_ref()?;
+    Some(InstructionAuthor {
+        actor: formats_actor(&provenance.actor),
+        source_file: provenance.source_file.as_ref().map(|p| p.to_string_lossy().into_owned()),
+        line: instruction.line_number,
+    })
+}
+
+/// Reason a parameter cannot be executed: counts, limits and timeouts are
+/// never negative, and values must not be empty. The reason names the
+/// overlay field the parameter came from, when known.
+fn check_parameter(parameter: &crate::schemas::Parameter) -> Option<String> {
+    let name = match &parameter.origin {
+        Some(origin) => format!("'{}' (from {})", parameter.key, origin),
+        None => format!("'{}'", parameter.key),
+    };
+    match &parameter.value {
+        ParameterValue::Integer(n) if *n < 0 => Some(format!("Parameter {} must not be negative (got {})", name, n)),
+        ParameterValue::Float(n) if !n.is_finite() => Some(format!("Parameter {} is not a finite number", name)),
+        ParameterValue::String(s) if s.trim().is_empty() => Some(format!("Parameter {} is empty", name)),
+        _ => None,
+    }
+}
+
 /// Result of executing a runtime object
 #[derive(Debug, Clone)]
 pub struct ExecutionResult {
@@ -101,14 +314,179 @@ pub struct ExecutionResult {
     pub outcome: crate::schemas::ExecutionOutcome,
     pub duration_ms: u64,
     pub logs: Vec<String>,
+    /// Overlay field of the parameter that caused a failure
+    pub origin: Option<String>,
+    /// Objects the instructions created or modified, sorted (see
+    /// `ImpactCalculator` for the impact recorded from them)
+    pub modified_objects: Vec<String>,
+    /// Author of the instruction the outcome is blamed on (the first that
+    /// failed, else the last one run), when it was parsed with provenance
+    pub authored_by: Option<InstructionAuthor>,
 }
 
+impl CBORRuntimeObject {
+    /// Runtime object `<run_id>_<seq>` whose metadata and auto-populated
+    /// fields both carry `run_id` and `seq`
+    pub fn new(run_id: RunId, seq: Seq, actor: Actor, command: CommandBlock) -> Self {
+        let object_id = format!("{}_{}", run_id, seq.0);
+
+        Self {
+            object_id,
+            metadata: ExecutionMetadata {
+                run_id,
+                seq,
+                ..ExecutionMetadata::new(actor.clone())
+            },
+            command,
+            auto_fields: AutoPopulatedFields {
+                run_id,
+                seq,
+                timestamp: chrono::Utc::now(),
+                actor,
+                file_path: None,
+                rule_group: None,
+                confidence: None,
+                tests_planned: Vec::new(),
+            },
+            decisions: Vec::new(),
+            source_template_id: None,
+            source_overlay_id: None,
+        }
+    }
+
+    /// Encode as `CBOR_MAGIC`, `CBOR_SCHEMA_VERSION`, then the CBOR payload.
+    /// The same framing is used on disk and over IPC.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RuntimeObjectError> {
+        let mut bytes = Vec::with_capacity(CBOR_HEADER_LEN);
+        bytes.extend_from_slice(&CBOR_MAGIC);
+        bytes.push(CBOR_SCHEMA_VERSION);
+        serde_cbor::to_writer(&mut bytes, self).map_err(RuntimeObjectError::Encode)?;
+        Ok(bytes)
+    }
+
+    /// Decode bytes written by `to_bytes`, checking the header first
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RuntimeObjectError> {
+        if bytes.len() < CBOR_HEADER_LEN {
+            return Err(RuntimeObjectError::Truncated { len: bytes.len() });
+        }
+        let (magic, rest) = bytes.split_at(CBOR_MAGIC.len());
+        if magic != CBOR_MAGIC {
+            return Err(RuntimeObjectError::BadMagic { found: magic.to_vec() });
+        }
+        if rest[0] != CBOR_SCHEMA_VERSION {
+            return Err(RuntimeObjectError::UnsupportedVersion { found: rest[0] });
+        }
+        serde_cbor::from_slice(&rest[1..]).map_err(|e| {
+            if e.is_eof() {
+                RuntimeObjectError::Truncated { len: bytes.len() }
+            } else {
+                RuntimeObjectError::Decode(e)
+            }
+        })
+    }
+
+    /// Write the framed object to `path`, replacing any existing file
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<(), RuntimeObjectError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes()?).map_err(|source| RuntimeObjectError::Io { path: path.to_path_buf(), source })
+    }
+
+    /// Read an object written by `write_to`
+    pub fn read_from(path: impl AsRef<Path>) -> Result<Self, RuntimeObjectError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| RuntimeObjectError::Io { path: path.to_path_buf(), source })?;
+        Self::from_bytes(&bytes).map_err(|e| e.in_file(path))
+    }
+
+    /// Every `*.cbor` object in `dir`, sorted by the seq in its file name
+    /// (`<run_id>_<seq>.cbor`, as `save_object` writes them; other names
+    /// sort last). Files are read one at a time as the iterator advances.
+    pub fn read_all(dir: impl AsRef<Path>) -> Result<RuntimeObjectIter, RuntimeObjectError> {
+        let dir = dir.as_ref();
+        let io_error = |source| RuntimeObjectError::Io { path: dir.to_path_buf(), source };
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "cbor") {
+                paths.push(path);
+            }
+        }
+        paths.sort_by_cached_key(|path| (seq_from_object_path(path).unwrap_or(u64::MAX), path.clone()));
+        Ok(RuntimeObjectIter { paths: paths.into_iter() })
+    }
+}
+
+/// First bytes of every framed runtime object
+pub const CBOR_MAGIC: [u8; 8] = *b"OASMCBOR";
+/// Bumped whenever the CBORRuntimeObject layout changes incompatibly
+pub const CBOR_SCHEMA_VERSION: u8 = 1;
+const CBOR_HEADER_LEN: usize = CBOR_MAGIC.len() + 1;
+
+fn seq_from_object_path(path: &Path) -> Option<u64> {
+    path.file_stem()?.to_str()?.rsplit_once('_')?.1.parse().ok()
+}
+
+/// Reading or writing a framed runtime object failed
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeObjectError {
+    #[error("I/O error on {}: {source}", path.display())]
+    Io { path: PathBuf, source: std::io::Error },
+    #[error("runtime object is truncated ({len} bytes)")]
+    Truncated { len: usize },
+    #[error("not a runtime object (magic {found:02x?})")]
+    BadMagic { found: Vec<u8> },
+    #[error("unsupported runtime object schema version {found} (expected {CBOR_SCHEMA_VERSION})")]
+    UnsupportedVersion { found: u8 },
+    #[error("failed to encode runtime object: {0}")]
+    Encode(serde_cbor::Error),
+    #[error("corrupted runtime object: {0}")]
+    Decode(serde_cbor::Error),
+    #[error("{}: {error}", path.display())]
+    InFile { path: PathBuf, error: Box<RuntimeObjectError> },
+}
+
+impl RuntimeObjectError {
+    fn in_file(self, path: &Path) -> Self {
+        RuntimeObjectError::InFile { path: path.to_path_buf(), error: Box::new(self) }
+    }
+
+    /// The error without the file it occurred in
+    pub fn root(&self) -> &RuntimeObjectError {
+        match self {
+            RuntimeObjectError::InFile { error, .. } => error.root(),
+            other => other,
+        }
+    }
+}
+
+/// Runtime objects of a directory, loaded lazily (see `CBORRuntimeObject::read_all`)
+pub struct RuntimeObjectIter {
+    paths: std::vec::IntoIter<PathBuf>,
+}
+
+impl Iterator for RuntimeObjectIter {
+    type Item = Result<CBORRuntimeObject, RuntimeObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.paths.next().map(CBORRuntimeObject::read_from)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.paths.size_hint()
+    }
+}
+
+impl ExactSizeIterator for RuntimeObjectIter {}
+
 /// Command block builder
 pub struct CommandBlockBuilder {
     block_type: BlockType,
     parameters: Vec<crate::schemas::Parameter>,
     target_files: Vec<String>,
     rules: Vec<String>,
+    instructions: Vec<String>,
+    program: Vec<Instruction>,
 }
 
 impl CommandBlockBuilder {
@@ -118,6 +496,8 @@ impl CommandBlockBuilder {
             parameters: Vec::new(),
             target_files: Vec::new(),
             rules: Vec::new(),
+            instructions: Vec::new(),
+            program: Vec::new(),
         }
     }
 
@@ -125,6 +505,7 @@ impl CommandBlockBuilder {
         self.parameters.push(crate::schemas::Parameter {
             key: key.into(),
             value,
+            origin: None,
         });
         self
     }
@@ -139,16 +520,225 @@ impl CommandBlockBuilder {
         self
     }
 
+    /// Append one OASM instruction, in source form
+    pub fn instruction(mut self, source: impl Into<String>) -> Self {
+        self.instructions.push(source.into());
+        self
+    }
+
+    /// Append one parsed OASM instruction, operands kept typed
+    pub fn parsed_instruction(mut self, instruction: Instruction) -> Self {
+        self.program.push(instruction);
+        self
+    }
+
     pub fn build(self) -> CommandBlock {
         CommandBlock {
             block_type: self.block_type,
             parameters: self.parameters,
             target_files: self.target_files,
             rules: self.rules,
+            instructions: self.instructions,
+            program: self.program,
         }
     }
 }
 
+/// Command run in the working directory to test a block's targets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl TestCommand {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Default test command for a language, if there is one whose output
+    /// `TestRunner` can read (libtest's `test <name> ... ok` lines)
+    pub fn default_for(language: Language) -> Option<Self> {
+        match language {
+            Language::Rust => Some(Self::new("cargo", &["test"])),
+            _ => None,
+        }
+    }
+
+    fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Runs the test command after blocks with `test_after_execution` and
+/// turns its output into lineage `TestRecord`s: one per `test <name> ...`
+/// line, with the `---- <name> stdout ----` section of a failure as its
+/// logs. Output without such lines (a build error, an unknown harness)
+/// yields a single record for the whole command, failed on a non-zero exit.
+pub struct TestRunner {
+    language: Language,
+    commands: HashMap<Language, TestCommand>,
+    working_directory: PathBuf,
+}
+
+impl TestRunner {
+    pub fn new() -> Self {
+        Self {
+            language: Language::Rust,
+            commands: HashMap::new(),
+            working_directory: PathBuf::from("."),
+        }
+    }
+
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Override the test command for a language
+    pub fn with_command(mut self, language: Language, command: TestCommand) -> Self {
+        self.commands.insert(language, command);
+        self
+    }
+
+    /// Run tests in `dir` (default: the current directory)
+    pub fn with_working_directory(mut self, dir: impl AsRef<Path>) -> Self {
+        self.working_directory = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Run the test command for `block`; blocks without
+    /// `test_after_execution` run nothing
+    pub fn run(&self, block: &CoreCommandBlock) -> Result<Vec<TestRecord>> {
+        if !block.test_after_execution {
+            return Ok(Vec::new());
+        }
+
+        let command = self
+            .commands
+            .get(&self.language)
+            .cloned()
+            .or_else(|| TestCommand::default_for(self.language))
+            .ok_or_else(|| anyhow!("No test command configured for {:?}", self.language))?;
+        let start = std::time::Instant::now();
+        let output = Command::new(&command.program)
+            .args(&command.args)
+            .current_dir(&self.working_directory)
+            .output()
+            .map_err(|e| anyhow!("Cannot run '{}': {}", command.display(), e))?;
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut records = parse_test_output(&stdout);
+
+        let failed = records.iter().any(|r| matches!(r.status, TestStatus::Failed { .. }));
+        if records.is_empty() || (!output.status.success() && !failed) {
+            let status = if output.status.success() {
+                TestStatus::Passed
+            } else {
+                TestStatus::Failed { reason: format!("'{}' exited with {}", command.display(), output.status) }
+            };
+            records.push(TestRecord {
+                test_id: command.program.clone(),
+                test_name: command.display(),
+                status,
+                duration_ms: Some(duration_ms),
+                logs: stdout.lines().chain(stderr.lines()).map(str::to_string).collect(),
+            });
+        }
+        Ok(records)
+    }
+
+    /// Run the tests for `block` and attach the records to the lineage
+    /// entry of `run_id` / `seq`
+    pub fn run_and_record(
+        &self,
+        block: &CoreCommandBlock,
+        lineage: &LineageManager,
+        run_id: RunId,
+        seq: Seq,
+    ) -> Result<Vec<TestRecord>> {
+        let records = self.run(block)?;
+        for record in &records {
+            lineage.add_test_record(run_id, seq, record.clone())?;
+        }
+        Ok(records)
+    }
+}
+
+impl Default for TestRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records for the `test <name> ... ok|FAILED|ignored` lines of libtest
+/// output. A trailing `<0.012s>` (from `--report-time`) sets `duration_ms`.
+pub fn parse_test_output(output: &str) -> Vec<TestRecord> {
+    let mut records = Vec::new();
+    let mut failure_logs: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut section: Option<&str> = None;
+
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("---- ").and_then(|l| l.strip_suffix(" stdout ----")) {
+            section = Some(name);
+            continue;
+        }
+        if line == "failures:" || line.starts_with("test result:") {
+            section = None;
+        }
+        if let Some(name) = section {
+            if !line.is_empty() {
+                failure_logs.entry(name).or_default().push(line.to_string());
+            }
+            continue;
+        }
+
+        let Some((name, result)) = line.strip_prefix("test ").and_then(|l| l.split_once(" ... ")) else {
+            continue;
+        };
+        let mut words = result.split_whitespace();
+        let status = match words.next() {
+            Some("ok") => TestStatus::Passed,
+            Some("FAILED") => TestStatus::Failed { reason: String::new() },
+            Some("ignored") => TestStatus::Skipped,
+            _ => continue,
+        };
+        let duration_ms = words
+            .next()
+            .and_then(|t| t.strip_prefix('<')?.strip_suffix("s>")?.parse::<f64>().ok())
+            .map(|secs| (secs * 1000.0).round() as u64);
+        records.push(TestRecord {
+            test_id: name.to_string(),
+            test_name: name.rsplit("::").next().unwrap_or(name).to_string(),
+            status,
+            duration_ms,
+            logs: Vec::new(),
+        });
+    }
+
+    // Failure sections come after the result lines
+    for record in &mut records {
+        if let TestStatus::Failed { reason } = &mut record.status {
+            record.logs = failure_logs.remove(record.test_id.as_str()).unwrap_or_default();
+            *reason = record
+                .logs
+                .iter()
+                .find(|l| !l.starts_with("thread '") && !l.starts_with("note:"))
+                .cloned()
+                .unwrap_or_else(|| "test failed".to_string());
+        }
+    }
+    records
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +761,12 @@ mod tests {
         );
 
         assert_eq!(obj.auto_fields.seq, Seq::zero());
+        assert!(obj.metadata.config_hash.is_empty());
+
+        let config = serde_json::json!({"scanner": {"depth": 3}});
+        let manager = RuntimeObjectManager::new(temp_dir.path()).with_config(&config).unwrap();
+        let obj = manager.create_object(RunId::new(), Seq::zero(), Actor::System, CommandBlockBuilder::new(BlockType::LintCheck).build());
+        assert_eq!(obj.metadata.config_hash, crate::config_hash::compute_config_hash(&config).unwrap());
     }
 
     #[test]
@@ -193,4 +789,263 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_execute_lint_check() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = RuntimeObjectManager::new(temp_dir.path().join("cache")).with_working_directory(temp_dir.path());
+        let command = CommandBlockBuilder::new(BlockType::LintCheck)
+            .parameter("max_warnings", ParameterValue::Integer(0))
+            .parameter("strict", ParameterValue::Boolean(true))
+            .target_file("src/main.rs")
+            .rule("no_unsafe")
+            .build();
+        let obj = manager.create_object(RunId::new(), Seq(3), Actor::System, command);
+
+        let rendered: Vec<String> = translate_command(&obj.command)?.iter().map(Instruction::render).collect();
+        assert_eq!(rendered, vec![
+            "CREATE lint_check",
+            "SET lint_check_0000.max_warnings = 0",
+            "SET lint_check_0000.strict = true",
+            r#"SET lint_check_0000.target_files = ["src/main.rs"]"#,
+            r#"SET lint_check_0000.rules = ["no_unsafe"]"#,
+        ]);
+
+        let result = manager.execute(&obj)?;
+        assert!(matches!(result.outcome, crate::schemas::ExecutionOutcome::Success), "{:?}", result.outcome);
+        assert_eq!((result.run_id, result.seq), (obj.auto_fields.run_id, Seq(3)));
+        assert_eq!(result.modified_objects, vec!["lint_check_0000"]);
+
+        // A dry run reports the same without executing
+        let dry = RuntimeObjectManager::new(temp_dir.path()).with_dry_run(true).execute(&obj)?;
+        assert!(matches!(dry.outcome, crate::schemas::ExecutionOutcome::Success));
+        assert_eq!(dry.modified_objects, vec!["lint_check_0000"]);
+        assert!(dry.logs.last().unwrap().starts_with("dry run: 5"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_reports_failures() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = RuntimeObjectManager::new(temp_dir.path());
+
+        // A program that fails at run time, and one that does not translate
+        let program = CommandBlockBuilder::new(BlockType::OasmProgram)
+            .instruction("CREATE gear")
+            .instruction("SET shaft_0000.teeth = 24")
+            .build();
+        let obj = manager.create_object(RunId::new(), Seq::zero(), Actor::System, program);
+        let result = manager.execute(&obj)?;
+        let crate::schemas::ExecutionOutcome::PartialSuccess { warnings } = result.outcome else {
+            panic!("expected a partial success, got {:?}", result.outcome);
+        };
+        assert_eq!(warnings, vec!["1 of 2 instructions completed"]);
+        let dry = manager.with_dry_run(true).execute(&obj)?;
+        assert!(matches!(dry.outcome, crate::schemas::ExecutionOutcome::Failed { .. }), "{:?}", dry.outcome);
+
+        let manager = RuntimeObjectManager::new(temp_dir.path());
+        let bad_key = CommandBlockBuilder::new(BlockType::LintCheck)
+            .parameter("max warnings", ParameterValue::Integer(1))
+            .build();
+        let result = manager.execute(&manager.create_object(RunId::new(), Seq::zero(), Actor::System, bad_key))?;
+        let crate::schemas::ExecutionOutcome::Failed { reason } = result.outcome else {
+            panic!("untranslatable blocks fail");
+        };
+        assert!(reason.contains("'max warnings' is not an identifier"), "{}", reason);
+        Ok(())
+    }
+
+    #[test]
+    fn test_outcomes_are_blamed_on_instruction_authors() -> Result<()> {
+        use oasm_core::parser::InstructionProvenance;
+
+        let temp_dir = tempfile::tempdir()?;
+        let manager = RuntimeObjectManager::new(temp_dir.path());
+        let provenance = InstructionProvenance {
+            actor: CoreActor::Human { username: "alice".to_string() },
+            source_file: Some("parts/gear.oasm".into()),
+        };
+        let run = |manager: &RuntimeObjectManager, source: &str| -> Result<ExecutionResult> {
+            let program = NativeParser.parse_with_provenance(source, &provenance).map_err(|e| anyhow!("{:?}", e))?;
+            let block = program
+                .into_iter()
+                .fold(CommandBlockBuilder::new(BlockType::OasmProgram), CommandBlockBuilder::parsed_instruction)
+                .build();
+            manager.execute(&manager.create_object(RunId::new(), Seq::zero(), Actor::System, block))
+        };
+
+        // The failing line, when there is one
+        let failed = run(&manager, "CREATE gear\nSET shaft_0000.teeth = 24\nCREATE shaft")?;
+        let author = failed.authored_by.expect("parsed with provenance");
+        assert!(matches!(&author.actor, Actor::Human { username } if username == "alice"));
+        assert_eq!((author.source_file.as_deref(), author.line), (Some("parts/gear.oasm"), 2));
+        let dry = run(&RuntimeObjectManager::new(temp_dir.path()).with_dry_run(true), "CREATE gear\nSET shaft_0000.teeth = 24")?;
+        assert_eq!(dry.authored_by.map(|a| a.line), Some(2));
+
+        // Otherwise the last line run
+        assert_eq!(run(&manager, "CREATE gear\n\nCREATE shaft")?.authored_by.map(|a| a.line), Some(3));
+
+        // Instructions without provenance have no author
+        assert!(instruction_author(&NativeParser.parse_line("CREATE gear", 1).unwrap().unwrap()).is_none());
+        let unattributed = CommandBlockBuilder::new(BlockType::OasmProgram).instruction("CREATE gear").build();
+        assert!(manager.execute(&manager.create_object(RunId::new(), Seq::zero(), Actor::System, unattributed))?.authored_by.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_framed_round_trip() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let manager = RuntimeObjectManager::new(temp_dir.path());
+        let run_id = RunId::new();
+        for seq in [10, 2, 1] {
+            let command = CommandBlockBuilder::new(BlockType::OasmProgram).instruction("CREATE gear").build();
+            manager.save_object(&CBORRuntimeObject::new(run_id, Seq(seq), Actor::System, command))?;
+        }
+        std::fs::write(temp_dir.path().join("notes.txt"), "not an object")?;
+
+        let path = temp_dir.path().join(format!("{}_2.cbor", run_id));
+        let obj = CBORRuntimeObject::read_from(&path)?;
+        assert_eq!(obj.command.instructions, vec!["CREATE gear"]);
+        assert_eq!(CBORRuntimeObject::from_bytes(&obj.to_bytes()?)?.object_id, obj.object_id);
+        assert_eq!(manager.load_object(&obj.object_id)?.auto_fields.seq, Seq(2));
+
+        let seqs: Vec<u64> = CBORRuntimeObject::read_all(temp_dir.path())?
+            .map(|obj| obj.map(|obj| obj.auto_fields.seq.0))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(seqs, vec![1, 2, 10]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupted_objects_are_typed_errors() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let obj = CBORRuntimeObject::new(RunId::new(), Seq::zero(), Actor::System,
+            CommandBlockBuilder::new(BlockType::LintCheck).rule("no_unsafe").build());
+        let bytes = obj.to_bytes()?;
+        assert_eq!(&bytes[..8], b"OASMCBOR");
+
+        // Cut off mid-payload, and inside the header
+        let truncated = temp_dir.path().join("truncated.cbor");
+        std::fs::write(&truncated, &bytes[..bytes.len() - 5])?;
+        let err = CBORRuntimeObject::read_from(&truncated).unwrap_err();
+        assert!(matches!(err.root(), RuntimeObjectError::Truncated { .. }), "{}", err);
+        assert!(err.to_string().contains("truncated.cbor"), "{}", err);
+        assert!(matches!(CBORRuntimeObject::from_bytes(&bytes[..4]), Err(RuntimeObjectError::Truncated { len: 4 })));
+
+        // Plain CBOR without the header
+        let err = CBORRuntimeObject::from_bytes(&serde_cbor::to_vec(&obj)?).unwrap_err();
+        assert!(matches!(err, RuntimeObjectError::BadMagic { .. }), "{}", err);
+
+        let mut future = bytes.clone();
+        future[8] = CBOR_SCHEMA_VERSION + 1;
+        assert!(matches!(
+            CBORRuntimeObject::from_bytes(&future),
+            Err(RuntimeObjectError::UnsupportedVersion { found }) if found == CBOR_SCHEMA_VERSION + 1
+        ));
+
+        let mut garbled = bytes;
+        garbled[9] = 0xff;
+        assert!(matches!(CBORRuntimeObject::from_bytes(&garbled), Err(RuntimeObjectError::Decode(_))));
+
+        assert!(matches!(
+            CBORRuntimeObject::read_from(temp_dir.path().join("missing.cbor")),
+            Err(RuntimeObjectError::Io { .. })
+        ));
+        Ok(())
+    }
+
+    fn tested_block(test_after_execution: bool) -> CoreCommandBlock {
+        use oasm_core::command_blocks::{BatchBuilder, BlockType as CoreBlockType, CommandBlockBuilder as _};
+
+        let mut builder = BatchBuilder::new(CoreBlockType::TestBlock);
+        builder.add_instruction(Instruction {
+            mnemonic: "CREATE".to_string(),
+            operands: vec![Operand::Identifier("gear".to_string())],
+            line_number: 1,
+            provenance: None,
+        });
+        if test_after_execution {
+            builder.enable_testing();
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_runner_records_parsed_results() -> Result<()> {
+        use crate::schemas::{ExecutionOutcome, Provenance};
+        use crate::storage::MemoryBackend;
+
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("fake_test.sh"), r#"cat <<'OUT'
+running 3 tests
+test parser::tests::parses ... ok <0.002s>
+test parser::tests::rejects ... FAILED <0.010s>
+test slow_roundtrip ... ignored
+
+failures:
+
+---- parser::tests::rejects stdout ----
+thread 'parser::tests::rejects' panicked at src/parser.rs:9:5:
+expected an error
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+failures:
+    parser::tests::rejects
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+OUT
+exit 101
+"#)?;
+        std::fs::write(dir.path().join("broken.sh"), "echo 'error[E0425]: cannot find value `x`' >&2\nexit 101\n")?;
+        let runner = |script: &str| {
+            TestRunner::new()
+                .with_command(Language::Rust, TestCommand::new("sh", &[script]))
+                .with_working_directory(dir.path())
+        };
+
+        let lineage = LineageManager::with_backend(MemoryBackend::shared());
+        let (run_id, seq) = (RunId::new(), Seq::zero());
+        lineage.record(
+            run_id,
+            seq,
+            Actor::System,
+            "Parser tests",
+            "Check the parser",
+            "",
+            ExecutionOutcome::Success,
+            Provenance {
+                tool_versions: crate::ToolVersions::current(),
+                config_hash: "abc123".to_string(),
+                template_id: None,
+                parent_run_id: None,
+                lineage_chain: vec![],
+                confidence: None,
+                git_dirty: None,
+                authored_by: None,
+                annotations: Vec::new(),
+            },
+            crate::Impact { functions_affected: 1, ..crate::Impact::default() },
+        )?;
+
+        let records = runner("fake_test.sh").run_and_record(&tested_block(true), &lineage, run_id, seq)?;
+        let statuses: Vec<_> = records.iter().map(|r| (r.test_id.as_str(), r.status.clone(), r.duration_ms)).collect();
+        assert_eq!(statuses, vec![
+            ("parser::tests::parses", TestStatus::Passed, Some(2)),
+            ("parser::tests::rejects", TestStatus::Failed { reason: "expected an error".to_string() }, Some(10)),
+            ("slow_roundtrip", TestStatus::Skipped, None),
+        ]);
+        assert_eq!(records[1].test_name, "rejects");
+        assert_eq!(records[1].logs.len(), 3);
+        assert_eq!(lineage.load(run_id, seq)?.tests.len(), 3);
+
+        // Nothing parseable and a non-zero exit: one failed record for the command
+        let records = runner("broken.sh").run(&tested_block(true))?;
+        assert_eq!(records.len(), 1);
+        assert!(matches!(&records[0].status, TestStatus::Failed { reason } if reason.contains("'sh broken.sh' exited")));
+        assert!(records[0].duration_ms.is_some());
+        assert_eq!(records[0].logs, vec!["error[E0425]: cannot find value `x`"]);
+
+        assert!(runner("broken.sh").run(&tested_block(false))?.is_empty());
+        Ok(())
+    }
 }
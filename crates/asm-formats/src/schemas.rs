@@ -8,6 +8,7 @@
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 use crate::{RunId, Seq, Actor, Confidence, Impact, ExecutionMetadata, TestStatus, PopupDecision};
 
 /// HDF5 Template Schema (Immutable Canonical)
@@ -31,7 +32,7 @@ pub struct HDF5Template {
     pub baseline: BaselineSnapshot,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TemplateType {
     AssemblerPass,
     CompilerStage,
@@ -100,6 +101,14 @@ pub struct CBORRuntimeObject {
 
     /// User decisions from popups (if any)
     pub decisions: Vec<PopupDecision>,
+
+    /// HDF5 template the object was generated from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_template_id: Option<String>,
+
+    /// YAML overlay the object was converted from (see `YAMLOverlay::overlay_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_overlay_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +117,27 @@ pub struct CommandBlock {
     pub parameters: Vec<Parameter>,
     pub target_files: Vec<String>,
     pub rules: Vec<String>,
+
+    /// OASM instructions in source form, one per entry (`OasmProgram` blocks)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instructions: Vec<String>,
+
+    /// Parsed OASM instructions with typed operands (`OasmProgram` blocks
+    /// compiled ahead of time; used instead of `instructions`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub program: Vec<oasm_core::parser::Instruction>,
+}
+
+impl CommandBlock {
+    /// OASM source the block runs, one instruction per line (empty for
+    /// blocks that are not OASM programs)
+    pub fn source(&self) -> String {
+        if self.program.is_empty() {
+            self.instructions.join("\n")
+        } else {
+            self.program.iter().map(|i| i.render()).collect::<Vec<_>>().join("\n")
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,12 +147,18 @@ pub enum BlockType {
     TestRunner,
     AnalysisPass,
     Converter,
+    /// Native OASM instructions compiled from a `.oasm` script
+    OasmProgram,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Parameter {
     pub key: String,
     pub value: ParameterValue,
+
+    /// Overlay field this parameter came from (e.g. `command.parameters[2]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,6 +201,10 @@ pub struct YAMLOverlay {
 
     /// Annotations for human understanding
     pub annotations: Vec<Annotation>,
+
+    /// HDF5 template the overlay was generated from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_template_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -174,6 +214,49 @@ pub struct Annotation {
     pub rationale: Option<String>,
 }
 
+impl YAMLOverlay {
+    /// Identifies the overlay in provenance: its file when it has one,
+    /// otherwise its run and seq
+    pub fn overlay_id(&self) -> String {
+        match &self.auto_populated.file_path {
+            Some(path) => path.clone(),
+            None => format!("overlay_{}_{}", self.auto_populated.run_id, self.auto_populated.seq.0),
+        }
+    }
+
+    /// Annotation attached to an overlay field, if any
+    pub fn annotation_for(&self, field: &str) -> Option<&Annotation> {
+        self.annotations.iter().find(|a| a.field == field)
+    }
+
+    /// Origin reference for a field, joined with its annotation text
+    pub fn origin_for(&self, field: &str) -> FieldOrigin {
+        FieldOrigin {
+            field: field.to_string(),
+            annotation: self.annotation_for(field).map(|a| a.explanation.clone()),
+        }
+    }
+}
+
+/// Overlay field that produced an executed parameter or instruction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldOrigin {
+    pub field: String,
+    /// Annotation text for the field, when the overlay was available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+}
+
+impl std::fmt::Display for FieldOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "originating overlay field: {}", self.field)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, " (annotated: '{}')", annotation)?;
+        }
+        Ok(())
+    }
+}
+
 /// JSON Lineage Schema (Audit Trail)
 ///
 /// Records execution outcomes, decisions, and provenance.
@@ -208,6 +291,10 @@ pub struct JSONLineage {
 
     /// Git integration
     pub git_sha: Option<String>,
+
+    /// Overlay field behind a failure, for overlay-driven runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<FieldOrigin>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -226,6 +313,25 @@ pub struct Provenance {
     pub parent_run_id: Option<RunId>,
     pub lineage_chain: Vec<String>,
     pub confidence: Option<Confidence>,
+    /// Uncommitted changes in the repo when the entry was recorded; None
+    /// outside a git repo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_dirty: Option<bool>,
+    /// Author of the instruction this entry records, for per-line blame
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authored_by: Option<InstructionAuthor>,
+    /// Annotations of the overlay behind an overlay-driven run; never part
+    /// of the CBOR object itself
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+/// Who wrote an instruction, and where
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstructionAuthor {
+    pub actor: Actor,
+    pub source_file: Option<String>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -248,10 +354,27 @@ pub struct DiffSnapshot {
     /// Diff payload (unified format)
     pub hunks: Vec<DiffHunk>,
 
+    /// Files the diff creates or deletes, by path (files it only modifies
+    /// are not listed; empty ones may have no hunks)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_changes: BTreeMap<String, FileChange>,
+
+    /// Files that differ but are not UTF-8 text on both sides; they have no
+    /// hunks (like `diff`'s "Binary files differ") and are not applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub binary_files: Vec<String>,
+
     /// Compression info
     pub compression: Option<CompressionInfo>,
 }
 
+/// Whether a diff brings a file into existence or removes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileChange {
+    Created,
+    Deleted,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiffHeader {
     pub diff_id: String,
@@ -280,6 +403,15 @@ pub struct DiffHunk {
 pub struct DiffLine {
     pub line_type: DiffLineType,
     pub content: String,
+    /// Last line of its file, with no trailing newline
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub missing_newline: bool,
+}
+
+impl DiffLine {
+    pub fn new(line_type: DiffLineType, content: impl Into<String>) -> Self {
+        Self { line_type, content: content.into(), missing_newline: false }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -303,6 +435,71 @@ pub enum CompressionAlgorithm {
     None,
 }
 
+/// zstd support is an optional feature of this crate
+#[cfg(not(feature = "zstd"))]
+fn zstd_unavailable<T>() -> anyhow::Result<T> {
+    anyhow::bail!("zstd support is not compiled in (enable the `zstd` feature)")
+}
+
+impl CompressionAlgorithm {
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+    /// Extension appended to the stored file name, if any
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            CompressionAlgorithm::Gzip => Some("gz"),
+            CompressionAlgorithm::Zstd => Some("zst"),
+            CompressionAlgorithm::None => None,
+        }
+    }
+
+    /// Compression of `bytes`, going by their magic number
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&Self::GZIP_MAGIC) {
+            CompressionAlgorithm::Gzip
+        } else if bytes.starts_with(&Self::ZSTD_MAGIC) {
+            CompressionAlgorithm::Zstd
+        } else {
+            CompressionAlgorithm::None
+        }
+    }
+
+    pub fn compress(self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+        use std::io::Write;
+
+        match self {
+            CompressionAlgorithm::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd => Ok(zstd::encode_all(bytes, 0)?),
+            #[cfg(not(feature = "zstd"))]
+            CompressionAlgorithm::Zstd => zstd_unavailable(),
+            CompressionAlgorithm::None => Ok(bytes.to_vec()),
+        }
+    }
+
+    pub fn decompress(self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+        use std::io::Read;
+
+        match self {
+            CompressionAlgorithm::Gzip => {
+                let mut decoded = Vec::new();
+                flate2::read::GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+                Ok(decoded)
+            }
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd => Ok(zstd::decode_all(bytes)?),
+            #[cfg(not(feature = "zstd"))]
+            CompressionAlgorithm::Zstd => zstd_unavailable(),
+            CompressionAlgorithm::None => Ok(bytes.to_vec()),
+        }
+    }
+}
+
 /// Session Index (ordered diffs for a run)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionIndex {
@@ -333,6 +530,78 @@ pub struct SessionTotals {
     pub tests_passed: usize,
 }
 
+/// Which pass produced a dashboard row (the compiler's JSONL dashboard);
+/// serialized as its name ("Compile", ...)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Section {
+    Structure,
+    Compile,
+    Phase1,
+    Scan,
+    /// YAML overlays validated on save by the daemon
+    Overlay,
+    /// Any other name, e.g. a merged "Compile+Structure"
+    Custom(String),
+}
+
+impl Section {
+    /// Whether this section is, or (for a merged `A+B` section) contains, `other`
+    pub fn includes(&self, other: &Section) -> bool {
+        match self {
+            Section::Custom(name) if name.contains('+') => name.split('+').any(|part| Section::from(part) == *other),
+            _ => self == other,
+        }
+    }
+}
+
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Section::Structure => write!(f, "Structure"),
+            Section::Compile => write!(f, "Compile"),
+            Section::Phase1 => write!(f, "Phase1"),
+            Section::Scan => write!(f, "Scan"),
+            Section::Overlay => write!(f, "Overlay"),
+            Section::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl std::str::FromStr for Section {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Structure" => Section::Structure,
+            "Compile" => Section::Compile,
+            "Phase1" => Section::Phase1,
+            "Scan" => Section::Scan,
+            "Overlay" => Section::Overlay,
+            other => Section::Custom(other.to_string()),
+        })
+    }
+}
+
+impl From<&str> for Section {
+    fn from(s: &str) -> Self {
+        let Ok(section) = s.parse();
+        section
+    }
+}
+
+impl From<String> for Section {
+    fn from(s: String) -> Self {
+        Section::from(s.as_str())
+    }
+}
+
+impl From<Section> for String {
+    fn from(section: Section) -> Self {
+        section.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -373,6 +642,8 @@ mod tests {
                 parameters: vec![],
                 target_files: vec![],
                 rules: vec![],
+                instructions: vec![],
+                program: vec![],
             },
             auto_fields: AutoPopulatedFields {
                 run_id: RunId::new(),
@@ -385,6 +656,8 @@ mod tests {
                 tests_planned: vec![],
             },
             decisions: vec![],
+            source_template_id: None,
+            source_overlay_id: None,
         };
 
         let cbor = serde_cbor::to_vec(&obj).unwrap();
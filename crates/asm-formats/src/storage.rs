@@ -0,0 +1,327 @@
+//! Storage Backends
+//!
+//! Templates, lineage and diffs are stored as bytes under string keys
+//! (`<run_id>/shard_000/seq_0000000042.json`) rather than file paths, so the
+//! managers can run against local disk, memory, or (later) an object store.
+//! The compiler's Phase1 and scan dashboards are written the same way.
+//!
+//! KEYS:
+//! - `/`-separated, relative, no `.` or `..` segments
+//! - Built with `join_key` / checked with `normalize_key` (backslashes are
+//!   folded to `/`, so keys written on Windows read back everywhere)
+//!
+//! CONSISTENCY (what the managers rely on):
+//! - `put_atomic` replaces a key's whole value: readers see either the old
+//!   bytes or the new bytes, never a partial write. Last writer wins.
+//! - There is no atomicity across keys. Multi-key operations (layout
+//!   migration) order their writes so a single `put_atomic` is the commit
+//!   point and anything written before it is ignored by readers.
+//! - `list_prefix` returns keys in lexicographic order; keys written
+//!   concurrently may or may not appear. `list_top_level` likewise.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Byte storage addressed by string keys
+pub trait StorageBackend: Send + Sync {
+    /// Bytes stored under `key`; error if it does not exist
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Store `bytes` under `key`, replacing any previous value atomically
+    fn put_atomic(&self, key: &str, bytes: &[u8]) -> Result<()>;
+
+    /// All keys starting with `prefix`, sorted
+    fn list_prefix(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// First segment of every key, sorted and deduplicated: top-level keys
+    /// and the names nested keys are grouped under (e.g. run ids)
+    fn list_top_level(&self) -> Result<Vec<String>> {
+        // Sorted keys do not keep a name's entries together: "run" and
+        // "run/seq_1.json" sort either side of "run-2"
+        let names: BTreeSet<String> = self
+            .list_prefix("")?
+            .into_iter()
+            .map(|key| key.split('/').next().unwrap_or_default().to_string())
+            .collect();
+        Ok(names.into_iter().collect())
+    }
+
+    /// Remove `key`; removing a missing key is not an error
+    fn delete(&self, key: &str) -> Result<()>;
+
+    fn exists(&self, key: &str) -> Result<bool>;
+
+    /// When `key` was last written, if the backend keeps track
+    fn modified(&self, _key: &str) -> Result<Option<SystemTime>> {
+        Ok(None)
+    }
+}
+
+/// Normalize a key: `\` becomes `/`, empty and `.` segments are dropped.
+/// Fails on `..` segments and keys with nothing left.
+pub fn normalize_key(key: &str) -> Result<String> {
+    let key = key.replace('\\', "/");
+    let mut segments = Vec::new();
+    for segment in key.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => bail!("Storage key '{}' must not contain '..'", key),
+            segment => segments.push(segment),
+        }
+    }
+    if segments.is_empty() {
+        bail!("Storage key '{}' is empty", key);
+    }
+    Ok(segments.join("/"))
+}
+
+/// Join key segments with `/`
+pub fn join_key(parts: &[&str]) -> String {
+    parts.join("/")
+}
+
+/// Normalize a listing prefix; unlike keys it may be empty or end in `/`
+fn normalize_prefix(prefix: &str) -> Result<String> {
+    let prefix = prefix.replace('\\', "/");
+    if prefix.trim_matches('/').is_empty() {
+        return Ok(String::new());
+    }
+    let mut normalized = normalize_key(&prefix)?;
+    if prefix.ends_with('/') {
+        normalized.push('/');
+    }
+    Ok(normalized)
+}
+
+/// Suffix marking an in-flight `put_atomic` temp file
+const TEMP_MARKER: &str = ".tmp-";
+
+/// Local filesystem backend: key `a/b.json` is file `<root>/a/b.json`
+#[derive(Debug, Clone)]
+pub struct FsBackend {
+    root: PathBuf,
+}
+
+impl FsBackend {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self { root: root.as_ref().to_path_buf() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf> {
+        Ok(self.root.join(normalize_key(key)?))
+    }
+
+    fn walk(&self, dir: &Path, keys: &mut Vec<String>) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.walk(&path, keys)?;
+                continue;
+            }
+            let is_temp = path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.contains(TEMP_MARKER));
+            if is_temp {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(&self.root) {
+                let segments: Vec<&str> = relative.iter().filter_map(|s| s.to_str()).collect();
+                keys.push(segments.join("/"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for FsBackend {
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path_for(key)?;
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Writes a temp file next to the target and renames it into place
+    fn put_atomic(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let temp = path.with_file_name(format!(
+            ".{}{}{}-{}",
+            file_name,
+            TEMP_MARKER,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        std::fs::write(&temp, bytes)?;
+        if let Err(e) = std::fs::rename(&temp, &path) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e).with_context(|| format!("Failed to write {}", path.display()));
+        }
+        Ok(())
+    }
+
+    fn list_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let prefix = normalize_prefix(prefix)?;
+
+        // Only walk the directory the prefix's last complete segment names
+        let dir = match prefix.rfind('/') {
+            Some(i) => self.root.join(&prefix[..i]),
+            None => self.root.clone(),
+        };
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut keys = Vec::new();
+        self.walk(&dir, &mut keys)?;
+        keys.retain(|key| key.starts_with(&prefix));
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Reads only the root directory instead of walking every key
+    fn list_top_level(&self) -> Result<Vec<String>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let name = entry?.file_name();
+            match name.to_str() {
+                Some(name) if !name.contains(TEMP_MARKER) => names.push(name.to_string()),
+                _ => {}
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match std::fs::remove_file(self.path_for(key)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.path_for(key)?.is_file())
+    }
+
+    fn modified(&self, key: &str) -> Result<Option<SystemTime>> {
+        Ok(std::fs::metadata(self.path_for(key)?).and_then(|meta| meta.modified()).ok())
+    }
+}
+
+/// In-memory backend for tests and ephemeral runs
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    entries: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shared handle, ready to pass to a manager's `with_backend`
+    pub fn shared() -> Arc<dyn StorageBackend> {
+        Arc::new(Self::new())
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Vec<u8>>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let key = normalize_key(key)?;
+        match self.entries().get(&key) {
+            Some(bytes) => Ok(bytes.clone()),
+            None => bail!("No such key: {}", key),
+        }
+    }
+
+    fn put_atomic(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        self.entries().insert(normalize_key(key)?, bytes.to_vec());
+        Ok(())
+    }
+
+    fn list_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let prefix = normalize_prefix(prefix)?;
+        Ok(self
+            .entries()
+            .range(prefix.clone()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.entries().remove(&normalize_key(key)?);
+        Ok(())
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.entries().contains_key(&normalize_key(key)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(normalize_key("run\\shard_000/./seq_1.json").unwrap(), "run/shard_000/seq_1.json");
+        assert_eq!(normalize_key("/a//b/").unwrap(), "a/b");
+        assert!(normalize_key("a/../b").is_err());
+        assert!(normalize_key("./").is_err());
+    }
+
+    #[test]
+    fn test_backends_agree() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let backends: Vec<Arc<dyn StorageBackend>> = vec![Arc::new(FsBackend::new(dir.path())), MemoryBackend::shared()];
+
+        for backend in backends {
+            backend.put_atomic("run/shard_000/seq_2.json", b"two")?;
+            backend.put_atomic("run/seq_1.json", b"one")?;
+            backend.put_atomic("run/seq_1.json", b"uno")?;
+            backend.put_atomic("runner/seq_9.json", b"other")?;
+
+            assert_eq!(backend.get("run\\seq_1.json")?, b"uno");
+            assert_eq!(backend.list_prefix("run/")?, vec!["run/seq_1.json", "run/shard_000/seq_2.json"]);
+            assert_eq!(backend.list_prefix("run/seq_")?, vec!["run/seq_1.json"]);
+            assert_eq!(backend.list_prefix("")?.len(), 3);
+            assert!(backend.list_prefix("missing/")?.is_empty());
+            assert_eq!(backend.list_top_level()?, vec!["run", "runner"]);
+
+            backend.delete("run/seq_1.json")?;
+            backend.delete("run/seq_1.json")?;
+            assert!(!backend.exists("run/seq_1.json")?);
+            assert!(backend.get("run/seq_1.json").is_err());
+        }
+
+        // A top-level key may share its name with a group of nested keys
+        let memory = MemoryBackend::shared();
+        for key in ["run", "run-2/seq_1.json", "run/seq_1.json"] {
+            memory.put_atomic(key, b"")?;
+        }
+        assert_eq!(memory.list_top_level()?, vec!["run", "run-2"]);
+        Ok(())
+    }
+}
@@ -1,72 +1,208 @@
 //! HDF5 Template Management
 //!
-//! Immutable canonical templates stored in HDF5 format.
-//! Provides baseline snapshots and deep artifacts (CFG/DFG, test fixtures, datasets).
+//! Immutable canonical templates: baseline snapshots and deep artifacts
+//! (CFG/DFG, test fixtures, datasets). Stored as JSON metadata plus
+//! content-addressed artifact blobs by default, or in HDF5 files with the
+//! `hdf5` feature.
 
-use crate::schemas::{HDF5Template, TemplateType, Artifact, BaselineSnapshot};
-use anyhow::{Result, Context};
+use crate::lineage::sha256_hex;
+use crate::schemas::{HDF5Template, TemplateType, Artifact, ArtifactType, BaselineSnapshot};
+use crate::storage::{join_key, normalize_key, FsBackend, StorageBackend};
+use anyhow::{bail, Context, Result};
 use std::path::Path;
+use std::sync::Arc;
 
-/// Template store backed by HDF5
+#[cfg(feature = "hdf5")]
+mod hdf5_backend;
+#[cfg(feature = "hdf5")]
+pub use hdf5_backend::Hdf5TemplateBackend;
+
+/// Where template metadata and artifact bytes live. Backends only store
+/// and fetch; immutability and checksums are enforced by `TemplateStore`.
+pub trait TemplateBackend: Send + Sync {
+    fn contains(&self, template_id: &str) -> Result<bool>;
+
+    fn read_template(&self, template_id: &str) -> Result<HDF5Template>;
+
+    /// Write a template that is not stored yet
+    fn write_template(&self, template: &HDF5Template) -> Result<()>;
+
+    /// IDs of all stored templates, sorted
+    fn template_ids(&self) -> Result<Vec<String>>;
+
+    /// Store artifact bytes under their checksum; returns the data path to
+    /// record in the artifact. Storing the same bytes twice is a no-op.
+    fn write_blob(&self, checksum: &str, bytes: &[u8]) -> Result<String>;
+
+    fn read_blob(&self, data_path: &str) -> Result<Vec<u8>>;
+}
+
+/// Default backend: metadata as `<template_id>.json` and artifact bytes in a
+/// content-addressed `blobs/<sha256>` directory, on any storage backend
+pub struct JsonTemplateBackend {
+    storage: Arc<dyn StorageBackend>,
+}
+
+/// Key prefix of the content-addressed artifact store
+const BLOB_DIR: &str = "blobs";
+
+impl JsonTemplateBackend {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+
+    fn metadata_key(template_id: &str) -> Result<String> {
+        let key = normalize_key(&format!("{}.json", template_id))?;
+        if key.contains('/') {
+            bail!("Template ID '{}' must not contain path separators", template_id);
+        }
+        Ok(key)
+    }
+}
+
+impl TemplateBackend for JsonTemplateBackend {
+    fn contains(&self, template_id: &str) -> Result<bool> {
+        self.storage.exists(&Self::metadata_key(template_id)?)
+    }
+
+    fn read_template(&self, template_id: &str) -> Result<HDF5Template> {
+        let bytes = self.storage.get(&Self::metadata_key(template_id)?)?;
+        serde_json::from_slice(&bytes).with_context(|| format!("Invalid metadata for template '{}'", template_id))
+    }
+
+    fn write_template(&self, template: &HDF5Template) -> Result<()> {
+        let json = serde_json::to_string_pretty(template)?;
+        self.storage.put_atomic(&Self::metadata_key(&template.template_id)?, json.as_bytes())
+    }
+
+    fn template_ids(&self) -> Result<Vec<String>> {
+        Ok(self
+            .storage
+            .list_top_level()?
+            .into_iter()
+            .filter_map(|name| name.strip_suffix(".json").map(str::to_string))
+            .collect())
+    }
+
+    fn write_blob(&self, checksum: &str, bytes: &[u8]) -> Result<String> {
+        let key = join_key(&[BLOB_DIR, checksum]);
+        if !self.storage.exists(&key)? {
+            self.storage.put_atomic(&key, bytes)?;
+        }
+        Ok(key)
+    }
+
+    fn read_blob(&self, data_path: &str) -> Result<Vec<u8>> {
+        self.storage.get(data_path)
+    }
+}
+
+/// An artifact whose stored bytes no longer match its recorded checksum
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityFailure {
+    pub artifact_id: String,
+    pub expected: String,
+    /// Checksum of the stored bytes; None when they could not be read
+    pub found: Option<String>,
+}
+
+/// Store of immutable templates
 pub struct TemplateStore {
-    base_path: std::path::PathBuf,
+    backend: Arc<dyn TemplateBackend>,
 }
 
 impl TemplateStore {
+    /// Templates stored on local disk under `base_path`
     pub fn new(base_path: impl AsRef<Path>) -> Self {
-        Self {
-            base_path: base_path.as_ref().to_path_buf(),
-        }
+        Self::with_backend(Arc::new(FsBackend::new(base_path)))
+    }
+
+    /// Templates in the JSON + blob layout on any storage backend
+    pub fn with_backend(storage: Arc<dyn StorageBackend>) -> Self {
+        Self::with_template_backend(Arc::new(JsonTemplateBackend::new(storage)))
+    }
+
+    /// Templates in HDF5 files under `base_path` (`hdf5` feature)
+    #[cfg(feature = "hdf5")]
+    pub fn hdf5(base_path: impl AsRef<Path>) -> Self {
+        Self::with_template_backend(Arc::new(Hdf5TemplateBackend::new(base_path)))
+    }
+
+    pub fn with_template_backend(backend: Arc<dyn TemplateBackend>) -> Self {
+        Self { backend }
     }
 
     /// Load an immutable template by ID
     pub fn load_template(&self, template_id: &str) -> Result<HDF5Template> {
-        let template_path = self.base_path.join(format!("{}.h5", template_id));
-
-        // TODO: Implement actual HDF5 reading
-        // For now, return a placeholder
-        anyhow::bail!("HDF5 reading not yet implemented for: {}", template_path.display())
+        if !self.backend.contains(template_id)? {
+            bail!("No template '{}'", template_id);
+        }
+        self.backend.read_template(template_id)
     }
 
-    /// Store a new immutable template
+    /// Store a new immutable template; a template ID is never reused
     pub fn store_template(&self, template: &HDF5Template) -> Result<()> {
-        let template_path = self.base_path.join(format!("{}.h5", template.template_id));
+        if self.backend.contains(&template.template_id)? {
+            bail!("Template '{}' already exists; templates are immutable", template.template_id);
+        }
+        self.backend.write_template(template)
+    }
 
-        // TODO: Implement actual HDF5 writing
-        // For now, serialize to JSON as placeholder
-        let json = serde_json::to_string_pretty(template)?;
-        std::fs::write(
-            template_path.with_extension("json"),
-            json
-        )?;
+    /// Store artifact bytes and describe them, ready for `TemplateBuilder::add_artifact`
+    pub fn store_artifact(&self, artifact_id: impl Into<String>, artifact_type: ArtifactType, bytes: &[u8]) -> Result<Artifact> {
+        let checksum = sha256_hex(bytes);
+        let data_path = self.backend.write_blob(&checksum, bytes)?;
+        Ok(Artifact {
+            artifact_id: artifact_id.into(),
+            artifact_type,
+            data_path,
+            size_bytes: bytes.len() as u64,
+            checksum,
+        })
+    }
 
-        Ok(())
+    /// Stored bytes of an artifact, checked against its checksum
+    pub fn load_artifact(&self, artifact: &Artifact) -> Result<Vec<u8>> {
+        let bytes = self.backend.read_blob(&artifact.data_path)?;
+        let found = sha256_hex(&bytes);
+        if found != artifact.checksum {
+            bail!("Artifact '{}' checksum mismatch: expected {}, found {}", artifact.artifact_id, artifact.checksum, found);
+        }
+        Ok(bytes)
     }
 
     /// List all available templates
     pub fn list_templates(&self) -> Result<Vec<String>> {
-        let mut templates = Vec::new();
-
-        for entry in std::fs::read_dir(&self.base_path)? {
-            let entry = entry?;
-            let path = entry.path();
+        self.backend.template_ids()
+    }
 
-            if path.extension().and_then(|s| s.to_str()) == Some("h5") ||
-               path.extension().and_then(|s| s.to_str()) == Some("json") {
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                    templates.push(stem.to_string());
-                }
+    /// Templates of the given type
+    pub fn find_by_type(&self, template_type: TemplateType) -> Result<Vec<String>> {
+        let mut matching = Vec::new();
+        for template_id in self.list_templates()? {
+            if self.backend.read_template(&template_id)?.template_type == template_type {
+                matching.push(template_id);
             }
         }
-
-        Ok(templates)
+        Ok(matching)
     }
 
-    /// Get template by type
-    pub fn find_by_type(&self, template_type: TemplateType) -> Result<Vec<String>> {
-        // TODO: Implement actual filtering
-        // For now, list all templates
-        self.list_templates()
+    /// Recompute the checksum of every artifact of a template; empty when
+    /// all of them match
+    pub fn verify_integrity(&self, template_id: &str) -> Result<Vec<IntegrityFailure>> {
+        let template = self.load_template(template_id)?;
+        Ok(template
+            .artifacts
+            .iter()
+            .filter_map(|artifact| {
+                let found = self.backend.read_blob(&artifact.data_path).ok().map(|bytes| sha256_hex(&bytes));
+                (found.as_deref() != Some(artifact.checksum.as_str())).then(|| IntegrityFailure {
+                    artifact_id: artifact.artifact_id.clone(),
+                    expected: artifact.checksum.clone(),
+                    found,
+                })
+            })
+            .collect())
     }
 }
 
@@ -143,17 +279,72 @@ mod tests {
     #[test]
     fn test_template_store() -> Result<()> {
         let temp_dir = tempfile::tempdir()?;
-        let store = TemplateStore::new(temp_dir.path());
+        let backends: Vec<Arc<dyn StorageBackend>> = vec![
+            Arc::new(FsBackend::new(temp_dir.path())),
+            crate::storage::MemoryBackend::shared(),
+        ];
 
-        let template = TemplateBuilder::new("test_001", TemplateType::LintBundle)
-            .description("Test lint bundle")
-            .build();
+        for backend in backends {
+            let store = TemplateStore::with_backend(backend);
+            let template = TemplateBuilder::new("test_001", TemplateType::LintBundle)
+                .description("Test lint bundle")
+                .build();
 
+            store.store_template(&template)?;
+
+            let templates = store.list_templates()?;
+            assert_eq!(templates, vec!["test_001".to_string()]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_templates_are_immutable() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let store = TemplateStore::new(temp_dir.path());
+        let template = TemplateBuilder::new("lint_001", TemplateType::LintBundle).description("original").build();
         store.store_template(&template)?;
+        store.store_template(&TemplateBuilder::new("pass_001", TemplateType::AssemblerPass).build())?;
 
-        let templates = store.list_templates()?;
-        assert!(templates.contains(&"test_001".to_string()));
+        let replacement = TemplateBuilder::new("lint_001", TemplateType::LintBundle).description("replacement").build();
+        let err = store.store_template(&replacement).unwrap_err();
+        assert!(err.to_string().contains("immutable"), "{}", err);
+        assert_eq!(store.load_template("lint_001")?.description, "original");
+
+        assert_eq!(store.find_by_type(TemplateType::LintBundle)?, vec!["lint_001".to_string()]);
+        assert!(store.find_by_type(TemplateType::TestHarness)?.is_empty());
+        assert!(store.load_template("missing").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_integrity_detects_checksum_mismatch() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let storage = Arc::new(FsBackend::new(temp_dir.path()));
+        let store = TemplateStore::with_backend(storage.clone());
+
+        let cfg = store.store_artifact("cfg", ArtifactType::CFG, b"entry -> exit")?;
+        let fixture = store.store_artifact("fixture", ArtifactType::TestFixture, b"expected output")?;
+        assert!(cfg.data_path.starts_with("blobs/"));
+        let template = TemplateBuilder::new("pass_001", TemplateType::AssemblerPass)
+            .add_artifact(cfg.clone())
+            .add_artifact(fixture.clone())
+            .build();
+        store.store_template(&template)?;
+        assert!(store.verify_integrity("pass_001")?.is_empty());
+        assert_eq!(store.load_artifact(&cfg)?, b"entry -> exit");
 
+        // Corrupt one blob and remove the other
+        storage.put_atomic(&cfg.data_path, b"entry -> loop")?;
+        storage.delete(&fixture.data_path)?;
+        let failures = store.verify_integrity("pass_001")?;
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].artifact_id, "cfg");
+        assert_eq!(failures[0].expected, cfg.checksum);
+        assert_eq!(failures[0].found, Some(sha256_hex(b"entry -> loop")));
+        assert_eq!(failures[1].found, None);
+        assert!(store.load_artifact(&cfg).is_err());
         Ok(())
     }
 }
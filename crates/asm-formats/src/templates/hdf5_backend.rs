@@ -0,0 +1,100 @@
+//! HDF5 template backend (`hdf5` feature)
+//!
+//! Each template is `<root>/<template_id>.h5` holding its metadata as a JSON
+//! byte dataset, `/metadata`. Artifact bytes are shared across templates in
+//! `<root>/blobs.h5`, one dataset per checksum under `/blobs`, and an
+//! artifact's `data_path` is that dataset path.
+
+use super::TemplateBackend;
+use crate::schemas::HDF5Template;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+const METADATA_DATASET: &str = "metadata";
+const BLOB_FILE: &str = "blobs.h5";
+const BLOB_GROUP: &str = "blobs";
+
+pub struct Hdf5TemplateBackend {
+    root: PathBuf,
+}
+
+impl Hdf5TemplateBackend {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self { root: root.as_ref().to_path_buf() }
+    }
+
+    fn template_path(&self, template_id: &str) -> Result<PathBuf> {
+        if template_id.is_empty() || template_id.contains(['/', '\\']) || template_id == ".." {
+            bail!("Invalid template ID '{}'", template_id);
+        }
+        Ok(self.root.join(format!("{}.h5", template_id)))
+    }
+
+    fn blob_file(&self) -> Result<hdf5::File> {
+        std::fs::create_dir_all(&self.root)?;
+        let file = hdf5::File::append(self.root.join(BLOB_FILE))?;
+        if !file.link_exists(BLOB_GROUP) {
+            file.create_group(BLOB_GROUP)?;
+        }
+        Ok(file)
+    }
+}
+
+impl TemplateBackend for Hdf5TemplateBackend {
+    fn contains(&self, template_id: &str) -> Result<bool> {
+        Ok(self.template_path(template_id)?.is_file())
+    }
+
+    fn read_template(&self, template_id: &str) -> Result<HDF5Template> {
+        let path = self.template_path(template_id)?;
+        let file = hdf5::File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let json = file.dataset(METADATA_DATASET)?.read_raw::<u8>()?;
+        serde_json::from_slice(&json).with_context(|| format!("Invalid metadata in {}", path.display()))
+    }
+
+    fn write_template(&self, template: &HDF5Template) -> Result<()> {
+        std::fs::create_dir_all(&self.root)?;
+        let path = self.template_path(&template.template_id)?;
+        let json = serde_json::to_vec(template)?;
+
+        // Write under a temporary name so a failed write leaves no template behind
+        let temp = path.with_extension("h5.tmp");
+        {
+            let file = hdf5::File::create(&temp)?;
+            file.new_dataset_builder().with_data(json.as_slice()).create(METADATA_DATASET)?;
+        }
+        std::fs::rename(&temp, &path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn template_ids(&self) -> Result<Vec<String>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut ids = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name == BLOB_FILE {
+                continue;
+            }
+            if let Some(id) = name.strip_suffix(".h5") {
+                ids.push(id.to_string());
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn write_blob(&self, checksum: &str, bytes: &[u8]) -> Result<String> {
+        let data_path = format!("/{}/{}", BLOB_GROUP, checksum);
+        let file = self.blob_file()?;
+        if !file.link_exists(&data_path) {
+            file.new_dataset_builder().with_data(bytes).create(data_path.as_str())?;
+        }
+        Ok(data_path)
+    }
+
+    fn read_blob(&self, data_path: &str) -> Result<Vec<u8>> {
+        let file = hdf5::File::open(self.root.join(BLOB_FILE))?;
+        Ok(file.dataset(data_path)?.read_raw::<u8>()?)
+    }
+}
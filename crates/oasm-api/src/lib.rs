@@ -1,4 +1,13 @@
 // Core types for OASM API
+use oasm_core::context::{Actor, ExecutionContext};
+use oasm_core::executor::{ExecutionOutcome, InstructionExecutor, InstructionRegistry, NativeExecutor};
+use oasm_core::parser::{self as core_parser, InstructionParser, NativeParser, Operand};
+use oasm_core::rules::{hierarchy, ValidationResult};
+use oasm_core::types::Value;
+use oasm_core::validators::ValidationContext;
+use oasm_core::{Condition, Rule, RuleCategory, Severity};
+use std::collections::HashMap;
+
 #[derive(Debug, Clone)]
 pub enum ProgramType {
     CAD,
@@ -19,18 +28,307 @@ pub enum Instruction {
 }
 
 pub struct OasmContext {
-    pub rules: Vec<String>,              // TODO: wire into validation
-    pub metadata: std::collections::HashMap<String, String>, // TODO: wire into runtime
+    pub name: String,
+    /// Program type rules are resolved for (`cad`, `engine`, ...)
+    pub program_type: String,
+    /// Condition check types (`required:author`, `max_value:teeth:200`, ...)
+    /// registered as Session rules and checked after every instruction
+    pub rules: Vec<String>,
+    /// Seeds the properties the rules are checked against
+    pub metadata: HashMap<String, String>,
+    /// Objects and variables the executed instructions have built up
+    pub execution: ExecutionContext,
+}
+
+impl OasmContext {
+    /// Rule engine holding the built-in rules plus this program's `rules`
+    fn rule_engine(&self) -> Result<oasm_core::rules::HierarchicalRuleEngine, OasmApiError> {
+        let mut engine = hierarchy::builtin_engine().map_err(|e| OasmApiError::ContextError(format!("{:?}", e)))?;
+        for (index, check_type) in self.rules.iter().enumerate() {
+            let rule = Rule {
+                id: format!("{}.rule_{}", self.name, index),
+                program_type: self.program_type.clone(),
+                category: RuleCategory::Validation,
+                conditions: vec![Condition {
+                    check_type: check_type.clone(),
+                    severity: Severity::Error,
+                    message: format!("Program rule '{}' failed", check_type),
+                }],
+            };
+            engine
+                .add_session_rule(rule, &self.name, None)
+                .map_err(|e| OasmApiError::ContextError(format!("{:?}", e)))?;
+        }
+        Ok(engine)
+    }
+
+    /// Snapshot of the execution state for validation, with `metadata` as
+    /// its properties (inner scopes shadow outer ones)
+    fn validation_context(&self) -> ValidationContext {
+        let mut context = ValidationContext::new(self.program_type.clone());
+        context.objects = self.execution.objects.clone();
+        for scope in &self.execution.scope_stack {
+            context.variables.extend(scope.variables.iter().map(|(name, var)| (name.clone(), var.clone())));
+        }
+        context.properties = self.metadata.clone();
+        context.suppressions = self.execution.active_suppressions();
+        context
+    }
+}
+
+/// What running one instruction did
+#[derive(Debug, Clone)]
+pub struct ExecuteOutcome {
+    pub outcome: ExecutionOutcome,
+    pub modified_objects: Vec<String>,
+    /// The context checked against the program's rules afterwards
+    pub validation: ValidationResult,
+}
+
+/// Run one instruction against a program context. `Execute` commands must
+/// name an instruction the native executor knows; `Define` declares a
+/// variable the way `SET name = value` does.
+pub fn execute(instruction: Instruction, context: &mut OasmContext) -> Result<ExecuteOutcome, OasmApiError> {
+    let core_instruction = to_core(&instruction)?;
+    if InstructionRegistry::default().get(&core_instruction.mnemonic).is_none() {
+        return Err(OasmApiError::UnknownInstruction(core_instruction.mnemonic));
+    }
+
+    // Executor errors are the instruction failing, reported in the outcome
+    let (outcome, modified_objects) = match NativeExecutor::new().execute(&core_instruction, &mut context.execution) {
+        Ok(result) => (result.outcome, result.modified_objects),
+        Err(e) => (ExecutionOutcome::Failed { reason: format!("{:?}", e) }, vec![]),
+    };
+    let validation = context.rule_engine()?.validate_context(&context.validation_context());
+
+    Ok(ExecuteOutcome { outcome, modified_objects, validation })
+}
+
+/// Map an API instruction back onto a native one by rendering and reparsing it
+fn to_core(instruction: &Instruction) -> Result<core_parser::Instruction, OasmApiError> {
+    let source = match instruction {
+        Instruction::Create { object_type } => format!("CREATE {}", object_type),
+        Instruction::Define { name, value } => format!("SET {} = {}", name, value),
+        Instruction::Set { property, value } => format!("SET {} = {}", property, value),
+        Instruction::Execute { command } => command.clone(),
+    };
+    NativeParser
+        .parse_line(&source, 1)?
+        .ok_or_else(|| OasmApiError::UnknownInstruction(source.trim().to_string()))
+}
+
+/// Result of a successful parse; an empty but valid program has no instructions
+#[derive(Debug, Clone, Default)]
+pub struct ParseOutcome {
+    pub instructions: Vec<Instruction>,
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Non-fatal issue found while parsing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Errors returned by the API
+#[derive(Debug, Clone, PartialEq)]
+pub enum OasmApiError {
+    /// Source did not parse; `line` is 1-based
+    ParseFailed { line: usize, message: String },
+    UnknownInstruction(String),
+    ContextError(String),
+    UnsupportedProgramType(String),
+}
+
+impl std::fmt::Display for OasmApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OasmApiError::ParseFailed { line, message } => write!(f, "line {}: {}", line, message),
+            OasmApiError::UnknownInstruction(mnemonic) => write!(f, "unknown instruction '{}'", mnemonic),
+            OasmApiError::ContextError(message) => write!(f, "context error: {}", message),
+            OasmApiError::UnsupportedProgramType(name) => write!(f, "unsupported program type '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for OasmApiError {}
+
+impl From<core_parser::ParseError> for OasmApiError {
+    fn from(e: core_parser::ParseError) -> Self {
+        OasmApiError::ParseFailed { line: e.line(), message: e.message() }
+    }
+}
+
+/// Parse OASM source with the native parser
+pub fn parse(source: &str) -> Result<ParseOutcome, OasmApiError> {
+    let parsed = NativeParser.parse_file(source)?;
+
+    let mut outcome = ParseOutcome::default();
+    for instruction in &parsed {
+        let (converted, warning) = convert(instruction);
+        outcome.instructions.push(converted);
+        if let Some(message) = warning {
+            outcome.warnings.push(ParseWarning { line: instruction.line_number, message });
+        }
+    }
+    Ok(outcome)
 }
 
-pub fn execute(_instruction: Instruction, _context: &mut OasmContext) -> Result<(), String> {
-    Ok(())
+/// Map a native instruction onto the API's instruction set; anything without
+/// a dedicated variant becomes `Execute` with the instruction re-rendered
+fn convert(instruction: &core_parser::Instruction) -> (Instruction, Option<String>) {
+    let operands = &instruction.operands;
+    let extra = |expected: usize| {
+        (operands.len() > expected).then(|| {
+            format!("{} takes {} operand(s); {} extra ignored", instruction.mnemonic, expected, operands.len() - expected)
+        })
+    };
+
+    match (instruction.mnemonic.as_str(), operands.first()) {
+        ("CREATE", Some(Operand::Identifier(object_type))) => {
+            (Instruction::Create { object_type: object_type.clone() }, extra(1))
+        }
+        ("DEFINE", Some(Operand::Assignment { target, value })) => {
+            (Instruction::Define { name: render(target), value: render(value) }, extra(1))
+        }
+        ("DEFINE", Some(Operand::Identifier(name))) if operands.len() >= 2 => {
+            (Instruction::Define { name: name.clone(), value: render(&operands[1]) }, extra(2))
+        }
+        ("SET", Some(Operand::Assignment { target, value })) => {
+            (Instruction::Set { property: render(target), value: render(value) }, extra(1))
+        }
+        _ => {
+            let command = std::iter::once(instruction.mnemonic.clone())
+                .chain(operands.iter().map(render))
+                .collect::<Vec<_>>()
+                .join(" ");
+            (Instruction::Execute { command }, None)
+        }
+    }
 }
 
-pub fn parse(_source: &str) -> Result<Vec<Instruction>, String> {
-    Ok(vec![])
+fn render(operand: &Operand) -> String {
+    match operand {
+        Operand::Identifier(name) => name.clone(),
+        Operand::Literal(value) => render_value(value),
+        Operand::Property { object, property } => format!("{}.{}", object, property),
+        Operand::Array(items) => format!("[{}]", items.iter().map(render).collect::<Vec<_>>().join(", ")),
+        Operand::Assignment { target, value } => format!("{} = {}", render(target), render(value)),
+        Operand::Expression { .. } => operand.render(),
+    }
 }
 
-pub fn register_program(_name: &str, _program_type: ProgramType) -> Result<OasmContext, String> {
-    Ok(OasmContext { rules: vec![], metadata: std::collections::HashMap::new() })
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::U32(n) => n.to_string(),
+        Value::F64(n) => n.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Context for a new program; custom program types need a name
+pub fn register_program(name: &str, program_type: ProgramType) -> Result<OasmContext, OasmApiError> {
+    if name.trim().is_empty() {
+        return Err(OasmApiError::ContextError("program name is empty".to_string()));
+    }
+    if let ProgramType::Custom(kind) = &program_type
+        && kind.trim().is_empty()
+    {
+        return Err(OasmApiError::UnsupportedProgramType(kind.clone()));
+    }
+    let program_type = match program_type {
+        ProgramType::CAD => "cad".to_string(),
+        ProgramType::Engine => "engine".to_string(),
+        ProgramType::Document => "document".to_string(),
+        ProgramType::Compression => "compression".to_string(),
+        ProgramType::Debug => "debug".to_string(),
+        ProgramType::Custom(kind) => kind,
+    };
+    Ok(OasmContext {
+        name: name.to_string(),
+        program_type,
+        rules: vec![],
+        metadata: HashMap::new(),
+        execution: ExecutionContext::new(Actor::System, std::path::PathBuf::from(".")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty_input() {
+        let outcome = parse("").unwrap();
+        assert!(outcome.instructions.is_empty());
+        assert!(outcome.warnings.is_empty());
+
+        // Comments and blank lines only are still a valid, empty program
+        assert!(parse("; nothing here\n\n# still nothing\n").unwrap().instructions.is_empty());
+    }
+
+    #[test]
+    fn test_parse_valid_program() {
+        let outcome = parse("CREATE gear\nSET teeth = 20\nEXTRUDE gear 2.5\nCREATE shaft extra").unwrap();
+
+        assert_eq!(outcome.instructions.len(), 4);
+        assert!(matches!(&outcome.instructions[0], Instruction::Create { object_type } if object_type == "gear"));
+        assert!(matches!(&outcome.instructions[1], Instruction::Set { property, value } if property == "teeth" && value == "20"));
+        assert!(matches!(&outcome.instructions[2], Instruction::Execute { command } if command == "EXTRUDE gear 2.5"));
+        assert_eq!(outcome.warnings.len(), 1);
+        assert_eq!(outcome.warnings[0].line, 4);
+    }
+
+    #[test]
+    fn test_parse_syntax_error_has_location() {
+        let err = parse("CREATE gear\nSET name = \"unterminated").unwrap_err();
+        assert_eq!(err, OasmApiError::ParseFailed { line: 2, message: "unterminated string".to_string() });
+        assert_eq!(err.to_string(), "line 2: unterminated string");
+
+        let err = parse("CREATE gear\n\nASSERT (count > 1").unwrap_err();
+        assert!(matches!(err, OasmApiError::ParseFailed { line: 3, .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_execute_and_register_errors() {
+        let mut context = register_program("gearbox", ProgramType::CAD).unwrap();
+        // A known instruction that fails at run time is an outcome, not an API error
+        execute(Instruction::Execute { command: "EXTRUDE gear 2.5".to_string() }, &mut context).unwrap();
+        let result = execute(Instruction::Set { property: "shaft_0000.length".to_string(), value: "4".to_string() }, &mut context).unwrap();
+        assert!(matches!(result.outcome, ExecutionOutcome::Failed { .. }), "{:?}", result.outcome);
+        assert_eq!(
+            execute(Instruction::Execute { command: "FROB gear".to_string() }, &mut context).unwrap_err(),
+            OasmApiError::UnknownInstruction("FROB".to_string())
+        );
+
+        assert!(matches!(
+            register_program("widget", ProgramType::Custom(String::new())),
+            Err(OasmApiError::UnsupportedProgramType(_))
+        ));
+        assert!(matches!(register_program(" ", ProgramType::CAD), Err(OasmApiError::ContextError(_))));
+    }
+
+    #[test]
+    fn test_execute_creates_object() {
+        let mut context = register_program("gearbox", ProgramType::CAD).unwrap();
+        context.rules.push("required:author".to_string());
+
+        let result = execute(Instruction::Create { object_type: "gear".to_string() }, &mut context).unwrap();
+        assert_eq!(result.outcome, ExecutionOutcome::Success);
+        assert_eq!(result.modified_objects, vec!["gear_0000".to_string()]);
+        assert_eq!(context.execution.objects["gear_0000"].object_type, "gear");
+
+        let result = execute(Instruction::Set { property: "gear_0000.teeth".to_string(), value: "20".to_string() }, &mut context).unwrap();
+        assert_eq!(result.outcome, ExecutionOutcome::Success);
+        assert_eq!(context.execution.objects["gear_0000"].properties["teeth"], Value::U32(20));
+
+        // Session rules are checked against the metadata after each instruction
+        assert!(result.validation.errors.iter().any(|m| m.check_type == "required:author"));
+        context.metadata.insert("author".to_string(), "mike".to_string());
+        let result = execute(Instruction::Define { name: "ratio".to_string(), value: "2.5".to_string() }, &mut context).unwrap();
+        assert!(!result.validation.errors.iter().any(|m| m.check_type == "required:author"));
+    }
 }
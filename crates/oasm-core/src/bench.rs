@@ -0,0 +1,104 @@
+//! Parse + execute throughput measurement
+//! Runs a program repeatedly through the native parser and executor so hot
+//! path regressions show up as a drop in instructions per second.
+
+use crate::context::{Actor, ExecutionContext};
+use crate::executor::{ExecutionOutcome, InstructionExecutor, NativeExecutor};
+use crate::parser::{InstructionParser, NativeParser, ParseError};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Timings from `run_throughput`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThroughputStats {
+    pub iterations: usize,
+    /// Instructions parsed and executed per iteration
+    pub instructions: usize,
+    /// Instructions that failed (same each iteration for a deterministic program)
+    pub failed_instructions: usize,
+    pub parse_time: Duration,
+    pub execute_time: Duration,
+    /// Executed instructions per second of parse + execute time
+    pub instructions_per_second: f64,
+}
+
+impl ThroughputStats {
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.execute_time
+    }
+
+    /// Share of the total time spent parsing (0.0..=1.0)
+    pub fn parse_fraction(&self) -> f64 {
+        let total = self.total_time().as_secs_f64();
+        if total == 0.0 {
+            0.0
+        } else {
+            self.parse_time.as_secs_f64() / total
+        }
+    }
+}
+
+/// Parse and execute `source` `iterations` times. One executor (and so one
+/// instruction registry) is reused; each iteration gets a fresh context.
+/// Only a parse error stops the run; failing instructions are counted.
+pub fn run_throughput(source: &str, iterations: usize) -> Result<ThroughputStats, ParseError> {
+    let parser = NativeParser;
+    let mut executor = NativeExecutor::new();
+    let working_directory = PathBuf::from(".");
+
+    let mut stats = ThroughputStats {
+        iterations,
+        instructions: 0,
+        failed_instructions: 0,
+        parse_time: Duration::ZERO,
+        execute_time: Duration::ZERO,
+        instructions_per_second: 0.0,
+    };
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let instructions = parser.parse_file(source)?;
+        stats.parse_time += start.elapsed();
+        stats.instructions = instructions.len();
+
+        let mut ctx = ExecutionContext::new(Actor::System, working_directory.clone());
+        let start = Instant::now();
+        let failed = instructions
+            .iter()
+            .map(|instruction| executor.execute(instruction, &mut ctx))
+            .filter(|result| match result {
+                Ok(r) => matches!(r.outcome, ExecutionOutcome::Failed { .. }),
+                Err(_) => true,
+            })
+            .count();
+        stats.execute_time += start.elapsed();
+        stats.failed_instructions = failed;
+    }
+
+    let seconds = stats.total_time().as_secs_f64();
+    if seconds > 0.0 {
+        stats.instructions_per_second = (stats.instructions * iterations) as f64 / seconds;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput_is_measured() {
+        let source = "CREATE gear\nSET teeth = 20\nSET ratio = 1.5\nASSERT teeth == 20\n";
+        let stats = run_throughput(source, 50).unwrap();
+
+        assert_eq!(stats.iterations, 50);
+        assert_eq!(stats.instructions, 4);
+        assert_eq!(stats.failed_instructions, 0);
+        assert!(stats.parse_time > Duration::ZERO);
+        assert!(stats.execute_time > Duration::ZERO);
+        assert!(stats.instructions_per_second > 0.0);
+        assert!((0.0..=1.0).contains(&stats.parse_fraction()));
+
+        assert!(run_throughput("SET name = \"unterminated", 1).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-/// Block system for program-specific functionality
+//! Block system for program-specific functionality
 
 use crate::{Block, BlockRegistry};
 
@@ -0,0 +1,505 @@
+//! Command block executor
+//! Runs a CommandBlock end-to-end: instructions, post-execution tests, and the
+//! repair loop driven by the block's RepairConfig.
+
+use super::{CommandBlock, ExecutionMode, RepairStrategy, TestType};
+use crate::context::{ContextCheckpoint, ExecutionContext};
+use crate::executor::{ExecutionOutcome, InstructionExecutor};
+use crate::state_evaluator::StateEvaluator;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default delay before the first RetryWithBackoff attempt (doubles each retry)
+pub const DEFAULT_BACKOFF_BASE: Duration = Duration::from_millis(100);
+
+/// A post-execution check for one TestType
+pub type TestCheck = Box<dyn Fn(&CommandBlock, &ExecutionContext) -> Result<(), String> + Send + Sync>;
+
+/// Result of one configured test check
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub test_type: TestType,
+    pub passed: bool,
+    pub message: Option<String>,
+}
+
+/// Outcome of a single attempt at running the block
+#[derive(Debug, Clone)]
+pub struct AttemptReport {
+    pub attempt: usize,
+    /// Strategy that triggered this attempt (None for the first run)
+    pub strategy: Option<RepairStrategy>,
+    pub outcome: ExecutionOutcome,
+    pub error: Option<String>,
+    pub test_results: Vec<TestResult>,
+    pub backoff_ms: u64,
+    pub duration_ms: u64,
+}
+
+impl AttemptReport {
+    pub fn succeeded(&self) -> bool {
+        self.outcome == ExecutionOutcome::Success
+    }
+}
+
+/// Final state of a block after the repair loop
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockOutcome {
+    Success,
+    Failed { reason: String },
+    /// Rolled back to the pre-block state and skipped (RollbackAndSkip)
+    RolledBack,
+    /// Repair handed over to the user (RequestUserInput)
+    AwaitingUserInput,
+}
+
+/// Report for a whole block run, with one entry per attempt
+#[derive(Debug, Clone)]
+pub struct BlockExecutionReport {
+    pub block_id: String,
+    pub outcome: BlockOutcome,
+    pub attempts: Vec<AttemptReport>,
+    pub total_duration_ms: u64,
+}
+
+/// Executes command blocks with testing and repair loops
+pub struct CommandBlockExecutor {
+    checks: Vec<(TestType, TestCheck)>,
+    backoff_base: Duration,
+    /// Consulted for blocks with `require_compilable_state`
+    state_evaluator: Arc<StateEvaluator>,
+}
+
+impl CommandBlockExecutor {
+    pub fn new() -> Self {
+        Self {
+            checks: Vec::new(),
+            backoff_base: DEFAULT_BACKOFF_BASE,
+            state_evaluator: Arc::new(StateEvaluator::new()),
+        }
+    }
+
+    pub fn with_backoff_base(mut self, base: Duration) -> Self {
+        self.backoff_base = base;
+        self
+    }
+
+    /// Share a StateEvaluator (and its cache) between executors
+    pub fn with_state_evaluator(mut self, evaluator: Arc<StateEvaluator>) -> Self {
+        self.state_evaluator = evaluator;
+        self
+    }
+
+    /// Register the check run for `test_type`. Configured test types without
+    /// a registered check are skipped.
+    pub fn register_check(&mut self, test_type: TestType, check: TestCheck) {
+        self.checks.push((test_type, check));
+    }
+
+    /// Run a block. On failure the block's RepairConfig strategies are used in
+    /// order, one per repair attempt (the last one repeats), up to max_attempts.
+    /// A block with `require_compilable_state` does not run at all while the
+    /// context's working directory fails to compile.
+    pub fn execute(
+        &self,
+        block: &CommandBlock,
+        executor: &mut dyn InstructionExecutor,
+        ctx: &mut ExecutionContext,
+    ) -> BlockExecutionReport {
+        let start = Instant::now();
+        if block.require_compilable_state {
+            if let Some(reason) = self.broken_state(&ctx.working_directory) {
+                return BlockExecutionReport {
+                    block_id: block.block_id.clone(),
+                    outcome: BlockOutcome::Failed { reason },
+                    attempts: Vec::new(),
+                    total_duration_ms: start.elapsed().as_millis() as u64,
+                };
+            }
+        }
+        let checkpoint = ctx.checkpoint();
+        let mut attempts = vec![self.run_attempt(1, None, 0, block, executor, ctx)];
+
+        let outcome = if attempts[0].succeeded() {
+            BlockOutcome::Success
+        } else if !block.repair_on_failure || !block.repair_config.enabled {
+            self.fail(block, &checkpoint, &attempts, ctx)
+        } else {
+            self.repair(block, executor, ctx, &checkpoint, &mut attempts)
+        };
+
+        BlockExecutionReport {
+            block_id: block.block_id.clone(),
+            outcome,
+            attempts,
+            total_duration_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// Why the working directory is not in a compilable state, if it isn't
+    fn broken_state(&self, working_directory: &std::path::Path) -> Option<String> {
+        match self.state_evaluator.is_compilable(working_directory) {
+            Ok(report) if report.compilable => None,
+            Ok(report) => Some(format!(
+                "Project does not compile: {}",
+                report.first_error().unwrap_or("check failed without diagnostics")
+            )),
+            Err(e) => Some(format!("Cannot determine project state: {}", e)),
+        }
+    }
+
+    fn repair(
+        &self,
+        block: &CommandBlock,
+        executor: &mut dyn InstructionExecutor,
+        ctx: &mut ExecutionContext,
+        checkpoint: &ContextCheckpoint,
+        attempts: &mut Vec<AttemptReport>,
+    ) -> BlockOutcome {
+        let config = &block.repair_config;
+        let mut retries = 0u32;
+
+        for n in 0..config.max_attempts {
+            let strategy = match config.repair_strategies.get(n).or(config.repair_strategies.last()) {
+                Some(strategy) => strategy.clone(),
+                None => break,
+            };
+
+            let backoff = match strategy {
+                RepairStrategy::RollbackAndSkip => {
+                    ctx.restore(checkpoint.clone());
+                    return BlockOutcome::RolledBack;
+                }
+                RepairStrategy::RequestUserInput => return BlockOutcome::AwaitingUserInput,
+                RepairStrategy::RetryWithBackoff => {
+                    let delay = self.backoff_base.saturating_mul(2u32.saturating_pow(retries));
+                    retries += 1;
+                    std::thread::sleep(delay);
+                    delay
+                }
+                // No alternative-method registry yet: re-run from a clean state
+                RepairStrategy::ApplyAlternativeMethod => Duration::ZERO,
+            };
+
+            // Every repair attempt starts from the pre-block state
+            ctx.restore(checkpoint.clone());
+            let attempt = self.run_attempt(
+                attempts.len() + 1,
+                Some(strategy),
+                backoff.as_millis() as u64,
+                block,
+                executor,
+                ctx,
+            );
+            let succeeded = attempt.succeeded();
+            attempts.push(attempt);
+            if succeeded {
+                return BlockOutcome::Success;
+            }
+        }
+
+        self.fail(block, checkpoint, attempts, ctx)
+    }
+
+    fn fail(
+        &self,
+        block: &CommandBlock,
+        checkpoint: &ContextCheckpoint,
+        attempts: &[AttemptReport],
+        ctx: &mut ExecutionContext,
+    ) -> BlockOutcome {
+        if block.checkpoint_before {
+            ctx.restore(checkpoint.clone());
+        }
+
+        let last = attempts.last();
+        let reason = last
+            .and_then(|a| a.error.clone())
+            .or_else(|| {
+                last.and_then(|a| a.test_results.iter().find(|t| !t.passed))
+                    .map(|t| format!("{:?} failed: {}", t.test_type, t.message.clone().unwrap_or_default()))
+            })
+            .unwrap_or_else(|| "block did not complete".to_string());
+        BlockOutcome::Failed { reason }
+    }
+
+    fn run_attempt(
+        &self,
+        attempt: usize,
+        strategy: Option<RepairStrategy>,
+        backoff_ms: u64,
+        block: &CommandBlock,
+        executor: &mut dyn InstructionExecutor,
+        ctx: &mut ExecutionContext,
+    ) -> AttemptReport {
+        let start = Instant::now();
+        let total = block.instructions.len();
+        let mut completed = 0;
+        let mut error = None;
+
+        if block.execution_mode == ExecutionMode::Sequential {
+            for instruction in &block.instructions {
+                let failure = match executor.execute(instruction, ctx) {
+                    Ok(result) if result.outcome == ExecutionOutcome::Success => {
+                        completed += 1;
+                        None
+                    }
+                    Ok(result) => Some(format!("line {}: {} returned {:?}", instruction.line_number, instruction.mnemonic, result.outcome)),
+                    Err(e) => Some(format!("line {}: {} failed: {:?}", instruction.line_number, instruction.mnemonic, e)),
+                };
+                if failure.is_some() {
+                    error = failure;
+                    break;
+                }
+            }
+        } else {
+            // The executor decides which instructions can run concurrently
+            match executor.execute_batch_with_mode(&block.instructions, &block.execution_mode, ctx) {
+                Ok(batch) => {
+                    completed = batch
+                        .individual_results
+                        .iter()
+                        .filter(|r| r.outcome == ExecutionOutcome::Success)
+                        .count();
+                    if completed != total {
+                        // A partial batch names its first failed instruction
+                        let first_failure = batch.individual_results.iter().find_map(|r| match &r.outcome {
+                            ExecutionOutcome::Failed { reason } => Some(reason.clone()),
+                            _ => None,
+                        });
+                        error = Some(match batch.outcome {
+                            ExecutionOutcome::Failed { reason } => reason,
+                            other => first_failure.unwrap_or_else(|| format!("batch returned {:?}", other)),
+                        });
+                    }
+                }
+                Err(e) => error = Some(format!("batch failed: {:?}", e)),
+            }
+        }
+
+        let mut outcome = if completed == total {
+            ExecutionOutcome::Success
+        } else {
+            ExecutionOutcome::PartialSuccess { completed, total }
+        };
+
+        let mut test_results = Vec::new();
+        let testing = &block.testing_config;
+        if outcome == ExecutionOutcome::Success && block.test_after_execution && testing.run_tests {
+            test_results = self.run_tests(block, ctx);
+            let failed = test_results.iter().filter(|t| !t.passed).count();
+            if !test_results.is_empty() && failed as f64 / test_results.len() as f64 > testing.failure_threshold {
+                outcome = ExecutionOutcome::Failed {
+                    reason: format!("{} of {} tests failed", failed, test_results.len()),
+                };
+            }
+        }
+
+        AttemptReport {
+            attempt,
+            strategy,
+            outcome,
+            error,
+            test_results,
+            backoff_ms,
+            duration_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+
+    fn run_tests(&self, block: &CommandBlock, ctx: &ExecutionContext) -> Vec<TestResult> {
+        block
+            .testing_config
+            .test_types
+            .iter()
+            .flat_map(|test_type| {
+                self.checks
+                    .iter()
+                    .filter(move |(t, _)| t == test_type)
+                    .map(move |(_, check)| match check(block, ctx) {
+                        Ok(()) => TestResult { test_type: test_type.clone(), passed: true, message: None },
+                        Err(message) => TestResult { test_type: test_type.clone(), passed: false, message: Some(message) },
+                    })
+            })
+            .collect()
+    }
+}
+
+impl Default for CommandBlockExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_blocks::{BatchBuilder, BlockType, CommandBlockBuilder, RepairConfig, TestingConfig};
+    use crate::context::{Actor, ContextManager};
+    use crate::executor::{
+        ExecutionResult, ExecutorError, InstructionHandler, InstructionRegistry, NativeExecutor,
+    };
+    use crate::parser::{Instruction, Operand};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Fails the first `failures` calls, then succeeds
+    struct FlakyHandler {
+        failures: usize,
+        calls: AtomicUsize,
+    }
+
+    impl InstructionHandler for FlakyHandler {
+        fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(ExecutorError::RuntimeError("flaky".to_string()));
+            }
+            Ok(ExecutionResult {
+                outcome: ExecutionOutcome::Success,
+                output: None,
+                modified_objects: vec![],
+                duration_ms: 0,
+            })
+        }
+    }
+
+    fn instruction(mnemonic: &str, operands: Vec<Operand>, line_number: usize) -> Instruction {
+        Instruction { mnemonic: mnemonic.to_string(), operands, line_number, provenance: None }
+    }
+
+    fn setup(failures: usize) -> (NativeExecutor, ExecutionContext, Arc<FlakyHandler>) {
+        let flaky = Arc::new(FlakyHandler { failures, calls: AtomicUsize::new(0) });
+        let mut registry = InstructionRegistry::default();
+        registry.register("FLAKY", flaky.clone());
+        let ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        (NativeExecutor::with_registry(registry), ctx, flaky)
+    }
+
+    fn flaky_block(strategies: Vec<RepairStrategy>, max_attempts: usize) -> CommandBlock {
+        let mut builder = BatchBuilder::new(BlockType::CADBlock);
+        builder
+            .add_instruction(instruction("CREATE", vec![Operand::Identifier("gear".to_string())], 1))
+            .add_instruction(instruction("FLAKY", vec![], 2))
+            .enable_checkpoints()
+            .set_repair_config(RepairConfig {
+                enabled: true,
+                max_attempts,
+                repair_strategies: strategies,
+                ..RepairConfig::default()
+            });
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_retry_with_backoff_until_success() {
+        let (mut executor, mut ctx, flaky) = setup(2);
+        let block = flaky_block(vec![RepairStrategy::RetryWithBackoff], 3);
+        let runner = CommandBlockExecutor::new().with_backoff_base(Duration::from_millis(1));
+
+        let report = runner.execute(&block, &mut executor, &mut ctx);
+
+        assert_eq!(report.outcome, BlockOutcome::Success);
+        assert_eq!(report.attempts.len(), 3);
+        assert!(!report.attempts[0].succeeded());
+        assert_eq!(report.attempts[1].strategy, Some(RepairStrategy::RetryWithBackoff));
+        assert_eq!(report.attempts[1].backoff_ms, 1);
+        assert_eq!(report.attempts[2].backoff_ms, 2);
+        assert!(report.attempts[2].succeeded());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+        // Retries start from the pre-block state, so CREATE ran effectively once
+        assert_eq!(ctx.objects.len(), 1);
+    }
+
+    #[test]
+    fn test_rollback_and_skip_restores_context() {
+        let (mut executor, mut ctx, _flaky) = setup(usize::MAX);
+        ctx.declare_variable("teeth".to_string(), crate::types::OasmType::U32, true).unwrap();
+        let before = ctx.clone();
+        let block = flaky_block(
+            vec![RepairStrategy::RetryWithBackoff, RepairStrategy::RollbackAndSkip],
+            3,
+        );
+        let runner = CommandBlockExecutor::new().with_backoff_base(Duration::from_millis(1));
+
+        let report = runner.execute(&block, &mut executor, &mut ctx);
+
+        assert_eq!(report.outcome, BlockOutcome::RolledBack);
+        assert_eq!(report.attempts.len(), 2);
+        assert_eq!(ctx.objects, before.objects);
+        assert_eq!(ctx.scope_stack, before.scope_stack);
+        assert_eq!(ctx.seq, before.seq);
+    }
+
+    #[test]
+    fn test_failed_check_triggers_repair() {
+        let (mut executor, mut ctx, _flaky) = setup(0);
+        let mut builder = BatchBuilder::new(BlockType::TestBlock);
+        builder
+            .add_instruction(instruction("FLAKY", vec![], 1))
+            .set_testing_config(TestingConfig {
+                run_tests: true,
+                test_types: vec![TestType::ValidationChecks],
+                failure_threshold: 0.0,
+            })
+            .set_repair_config(RepairConfig {
+                enabled: true,
+                repair_strategies: vec![RepairStrategy::RequestUserInput],
+                ..RepairConfig::default()
+            });
+        let block = builder.build().unwrap();
+
+        let mut runner = CommandBlockExecutor::new();
+        runner.register_check(TestType::ValidationChecks, Box::new(|_, _| Err("no geometry".to_string())));
+
+        let report = runner.execute(&block, &mut executor, &mut ctx);
+
+        assert_eq!(report.outcome, BlockOutcome::AwaitingUserInput);
+        assert_eq!(report.attempts.len(), 1);
+        assert_eq!(report.attempts[0].test_results.len(), 1);
+        assert!(!report.attempts[0].test_results[0].passed);
+    }
+
+    #[test]
+    fn test_without_repair_loop_reports_failure() {
+        let (mut executor, mut ctx, _flaky) = setup(1);
+        let mut builder = BatchBuilder::new(BlockType::CADBlock);
+        builder
+            .add_instruction(instruction("FLAKY", vec![], 7))
+            .add_instruction(instruction("VALIDATE", vec![], 8))
+            .set_execution_mode(ExecutionMode::Parallel);
+        let block = builder.build().unwrap();
+
+        let report = CommandBlockExecutor::new().execute(&block, &mut executor, &mut ctx);
+
+        assert_eq!(report.attempts.len(), 1);
+        // As in a sequential run, nothing after the failing instruction counts
+        assert_eq!(report.attempts[0].outcome, ExecutionOutcome::PartialSuccess { completed: 0, total: 2 });
+        match report.outcome {
+            BlockOutcome::Failed { reason } => assert!(reason.contains("line 7")),
+            other => panic!("expected failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_block_requiring_compilable_state_refuses_broken_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n").unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn broken( {}\n").unwrap();
+
+        let (mut executor, _, flaky) = setup(0);
+        let mut ctx = ExecutionContext::new(Actor::System, dir.path().to_path_buf());
+        let mut builder = BatchBuilder::new(BlockType::CADBlock);
+        builder.add_instruction(instruction("FLAKY", vec![], 1)).require_compilable_state();
+        let block = builder.build().unwrap();
+
+        let report = CommandBlockExecutor::new().execute(&block, &mut executor, &mut ctx);
+
+        let BlockOutcome::Failed { reason } = report.outcome else {
+            panic!("expected Failed, got {:?}", report.outcome);
+        };
+        assert!(reason.starts_with("Project does not compile: error"), "{}", reason);
+        assert!(report.attempts.is_empty());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 0);
+    }
+}
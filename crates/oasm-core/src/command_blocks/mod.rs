@@ -1,8 +1,12 @@
-/// OASM Command Block Builder
-/// Batches instructions together for atomic execution with testing/repair loops
+//! OASM Command Block Builder
+//! Batches instructions together for atomic execution with testing/repair loops
+
+pub mod executor;
+
+pub use executor::{BlockExecutionReport, BlockOutcome, CommandBlockExecutor};
 
 use crate::parser::Instruction;
-use crate::context::{RunId, Seq};
+use crate::context::{Confidence, RunId, Seq};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +46,22 @@ pub struct CommandBlock {
     pub run_id: RunId,
     pub seq: Seq,
     pub require_compilable_state: bool, // New flag for smart state awareness
+    #[serde(default)]
+    pub repair_config: RepairConfig,
+    /// Checks run after a successful batch when `test_after_execution` is set
+    #[serde(default)]
+    pub testing_config: TestingConfig,
+}
+
+impl CommandBlock {
+    /// Pick the repair strategy for a failure repaired with the given confidence.
+    /// Returns None when the block has no repair loop.
+    pub fn plan_repair(&self, confidence: Confidence) -> Option<RepairStrategy> {
+        if !self.repair_on_failure {
+            return None;
+        }
+        Some(self.repair_config.should_auto_apply(confidence))
+    }
 }
 
 /// Command block builder trait
@@ -55,6 +75,8 @@ pub trait CommandBlockBuilder {
     fn enable_testing(&mut self) -> &mut Self;
     fn enable_repair_loop(&mut self) -> &mut Self;
     fn require_compilable_state(&mut self) -> &mut Self; // New method
+    fn set_repair_config(&mut self, config: RepairConfig) -> &mut Self;
+    fn set_testing_config(&mut self, config: TestingConfig) -> &mut Self;
     fn build(self) -> Result<CommandBlock, BuildError>;
 }
 
@@ -78,6 +100,8 @@ pub struct BatchBuilder {
     test_after_execution: bool,
     repair_on_failure: bool,
     require_compilable_state: bool,
+    repair_config: RepairConfig,
+    testing_config: TestingConfig,
     run_id: RunId,
     seq: Seq,
 }
@@ -95,6 +119,8 @@ impl CommandBlockBuilder for BatchBuilder {
             test_after_execution: false,
             repair_on_failure: false,
             require_compilable_state: false,
+            repair_config: RepairConfig::default(),
+            testing_config: TestingConfig::default(),
             run_id: RunId::new(),
             seq: Seq::zero(),
         }
@@ -133,6 +159,7 @@ impl CommandBlockBuilder for BatchBuilder {
 
     fn enable_repair_loop(&mut self) -> &mut Self {
         self.repair_on_failure = true;
+        self.repair_config.enabled = true;
         self
     }
 
@@ -141,6 +168,18 @@ impl CommandBlockBuilder for BatchBuilder {
         self
     }
 
+    fn set_repair_config(&mut self, config: RepairConfig) -> &mut Self {
+        self.repair_on_failure = config.enabled;
+        self.repair_config = config;
+        self
+    }
+
+    fn set_testing_config(&mut self, config: TestingConfig) -> &mut Self {
+        self.test_after_execution = config.run_tests;
+        self.testing_config = config;
+        self
+    }
+
     fn build(self) -> Result<CommandBlock, BuildError> {
         if self.instructions.is_empty() {
             return Err(BuildError::NoInstructions);
@@ -160,6 +199,8 @@ impl CommandBlockBuilder for BatchBuilder {
             test_after_execution: self.test_after_execution,
             repair_on_failure: self.repair_on_failure,
             require_compilable_state: self.require_compilable_state,
+            repair_config: self.repair_config,
+            testing_config: self.testing_config,
             created: Utc::now(),
             run_id: self.run_id,
             seq: self.seq,
@@ -175,6 +216,17 @@ pub struct TestingConfig {
     pub failure_threshold: f64,  // 0.0 - 1.0
 }
 
+impl Default for TestingConfig {
+    /// Run the executor's registered checks; any failure fails the block
+    fn default() -> Self {
+        Self {
+            run_tests: true,
+            test_types: Vec::new(),
+            failure_threshold: 0.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TestType {
     UnitTests,
@@ -183,12 +235,54 @@ pub enum TestType {
     TopologyChecks,  // CAD-specific
 }
 
+/// Confidence at or above which repairs are applied without asking
+pub const DEFAULT_AUTO_REPAIR_THRESHOLD: f64 = 0.85;
+
+fn default_auto_repair_threshold() -> f64 {
+    DEFAULT_AUTO_REPAIR_THRESHOLD
+}
+
 /// Repair loop configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepairConfig {
     pub enabled: bool,
     pub max_attempts: usize,
     pub repair_strategies: Vec<RepairStrategy>,
+    #[serde(default = "default_auto_repair_threshold")]
+    pub auto_repair_threshold: f64,
+}
+
+impl Default for RepairConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: 3,
+            repair_strategies: vec![
+                RepairStrategy::RetryWithBackoff,
+                RepairStrategy::ApplyAlternativeMethod,
+                RepairStrategy::RequestUserInput,
+            ],
+            auto_repair_threshold: DEFAULT_AUTO_REPAIR_THRESHOLD,
+        }
+    }
+}
+
+impl RepairConfig {
+    /// Confident repairs take the first automatic strategy configured, else
+    /// the first strategy configured at all; anything below the threshold,
+    /// with no strategies, or with the repair loop disabled, asks the user.
+    pub fn should_auto_apply(&self, confidence: Confidence) -> RepairStrategy {
+        if !self.enabled || !confidence.exceeds_threshold(self.auto_repair_threshold) {
+            return RepairStrategy::RequestUserInput;
+        }
+
+        self.repair_strategies
+            .iter()
+            .find(|s| s.is_automatic())
+            .or_else(|| self.repair_strategies.first())
+            .cloned()
+            .unwrap_or(RepairStrategy::RequestUserInput)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -199,6 +293,13 @@ pub enum RepairStrategy {
     RequestUserInput,
 }
 
+impl RepairStrategy {
+    /// Strategies that repair without human involvement
+    pub fn is_automatic(&self) -> bool {
+        matches!(self, RepairStrategy::RetryWithBackoff | RepairStrategy::ApplyAlternativeMethod)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +314,7 @@ mod tests {
                 mnemonic: "CREATE".to_string(),
                 operands: vec![Operand::Identifier("gear".to_string())],
                 line_number: 1,
+                provenance: None,
             })
             .add_target("src/main.rs".to_string())
             .add_rule("fix_unsafe".to_string());
@@ -234,6 +336,7 @@ mod tests {
                 mnemonic: "VALIDATE".to_string(),
                 operands: vec![],
                 line_number: 1,
+                provenance: None,
             })
             .enable_testing()
             .enable_repair_loop()
@@ -245,6 +348,22 @@ mod tests {
         assert!(block.repair_on_failure);
         assert!(block.checkpoint_before);
         assert!(block.checkpoint_after);
+        assert!(block.testing_config.run_tests);
+
+        // The testing config travels with the block
+        let mut builder = BatchBuilder::new(BlockType::TestBlock);
+        builder
+            .add_instruction(block.instructions[0].clone())
+            .set_testing_config(TestingConfig {
+                run_tests: true,
+                test_types: vec![TestType::TopologyChecks],
+                failure_threshold: 0.25,
+            });
+        let block = builder.build().unwrap();
+        assert!(block.test_after_execution);
+        let decoded: CommandBlock = serde_json::from_str(&serde_json::to_string(&block).unwrap()).unwrap();
+        assert_eq!(decoded.testing_config.test_types, vec![TestType::TopologyChecks]);
+        assert_eq!(decoded.testing_config.failure_threshold, 0.25);
     }
 
     #[test]
@@ -252,4 +371,59 @@ mod tests {
         let builder = BatchBuilder::new(BlockType::RepairBlock);
         assert!(builder.build().is_err());
     }
+
+    #[test]
+    fn test_auto_repair_threshold_boundary() {
+        let config = RepairConfig {
+            enabled: true,
+            repair_strategies: vec![
+                RepairStrategy::ApplyAlternativeMethod,
+                RepairStrategy::RetryWithBackoff,
+            ],
+            ..RepairConfig::default()
+        };
+
+        assert_eq!(config.should_auto_apply(Confidence::new(0.85)), RepairStrategy::ApplyAlternativeMethod);
+        assert_eq!(config.should_auto_apply(Confidence::new(0.8499)), RepairStrategy::RequestUserInput);
+        assert_eq!(config.should_auto_apply(Confidence::new(0.99)), RepairStrategy::ApplyAlternativeMethod);
+
+        // Without an automatic strategy the configured one is kept
+        let manual = RepairConfig { repair_strategies: vec![RepairStrategy::RollbackAndSkip], ..config.clone() };
+        assert_eq!(manual.should_auto_apply(Confidence::new(0.99)), RepairStrategy::RollbackAndSkip);
+        let none = RepairConfig { repair_strategies: Vec::new(), ..config.clone() };
+        assert_eq!(none.should_auto_apply(Confidence::new(0.99)), RepairStrategy::RequestUserInput);
+
+        let disabled = RepairConfig { enabled: false, ..config };
+        assert_eq!(disabled.should_auto_apply(Confidence::new(0.99)), RepairStrategy::RequestUserInput);
+    }
+
+    #[test]
+    fn test_plan_repair() {
+        let mut builder = BatchBuilder::new(BlockType::RepairBlock);
+        builder.add_instruction(Instruction {
+            mnemonic: "VALIDATE".to_string(),
+            operands: vec![],
+            line_number: 1,
+            provenance: None,
+        });
+        let block = builder.build().unwrap();
+        assert_eq!(block.plan_repair(Confidence::new(0.99)), None);
+
+        let mut builder = BatchBuilder::new(BlockType::RepairBlock);
+        builder
+            .add_instruction(Instruction {
+                mnemonic: "VALIDATE".to_string(),
+                operands: vec![],
+                line_number: 1,
+                provenance: None,
+            })
+            .set_repair_config(RepairConfig {
+                enabled: true,
+                repair_strategies: vec![RepairStrategy::RollbackAndSkip],
+                ..RepairConfig::default()
+            });
+        let block = builder.build().unwrap();
+        assert_eq!(block.plan_repair(Confidence::new(0.9)), Some(RepairStrategy::RollbackAndSkip));
+        assert_eq!(block.plan_repair(Confidence::new(0.5)), Some(RepairStrategy::RequestUserInput));
+    }
 }
@@ -0,0 +1,172 @@
+//! Context diffs
+//! Structured record of what changed between two states of an
+//! ExecutionContext (objects and visible variables), for lineage and dry runs.
+
+use super::{ExecutionContext, Object, Seq, Variable};
+use crate::expression::value_diff;
+use crate::types::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One object or variable that differs. Objects are recorded as
+/// `Value::Object`; a variable without a value yet is `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl Change {
+    /// Field-level differences (`teeth: 20 != 24`) between before and after
+    pub fn details(&self) -> Vec<String> {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => value_diff(before, after),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Changes between two context states, each list sorted by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiffSnapshot {
+    pub seq_before: Seq,
+    pub seq_after: Seq,
+    pub objects: Vec<Change>,
+    pub variables: Vec<Change>,
+}
+
+impl DiffSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty() && self.variables.is_empty()
+    }
+
+    /// Names of the objects with a given kind of change
+    pub fn objects_with(&self, kind: ChangeKind) -> Vec<&str> {
+        names_with(&self.objects, kind)
+    }
+
+    /// Names of the variables with a given kind of change
+    pub fn variables_with(&self, kind: ChangeKind) -> Vec<&str> {
+        names_with(&self.variables, kind)
+    }
+}
+
+fn names_with(changes: &[Change], kind: ChangeKind) -> Vec<&str> {
+    changes.iter().filter(|c| c.kind == kind).map(|c| c.name.as_str()).collect()
+}
+
+/// Diff two states of a context. Variables are compared as visible at the
+/// end of each state (inner scopes shadowing outer ones); a variable counts
+/// as modified when its value, type or mutability changed.
+pub fn diff_contexts(before: &ExecutionContext, after: &ExecutionContext) -> DiffSnapshot {
+    let object_value = |object: &Object| Value::Object {
+        id: object.id.clone(),
+        object_type: object.object_type.clone(),
+        properties: object.properties.clone(),
+    };
+
+    DiffSnapshot {
+        seq_before: before.seq,
+        seq_after: after.seq,
+        objects: diff_maps(&object_map(before), &object_map(after), |o| Some(object_value(o))),
+        variables: diff_maps(&visible_variables(before), &visible_variables(after), |v| v.value.clone()),
+    }
+}
+
+fn object_map(ctx: &ExecutionContext) -> HashMap<&str, &Object> {
+    ctx.objects.iter().map(|(id, object)| (id.as_str(), object)).collect()
+}
+
+/// Variables by name, inner scopes shadowing outer ones
+fn visible_variables(ctx: &ExecutionContext) -> HashMap<&str, &Variable> {
+    ctx.scope_stack
+        .iter()
+        .flat_map(|scope| scope.variables.iter())
+        .map(|(name, variable)| (name.as_str(), variable))
+        .collect()
+}
+
+fn diff_maps<T: PartialEq>(
+    before: &HashMap<&str, &T>,
+    after: &HashMap<&str, &T>,
+    value: impl Fn(&T) -> Option<Value>,
+) -> Vec<Change> {
+    let mut changes: Vec<Change> = after
+        .iter()
+        .filter_map(|(name, item)| {
+            let kind = match before.get(name) {
+                None => ChangeKind::Added,
+                Some(previous) if previous != item => ChangeKind::Modified,
+                Some(_) => return None,
+            };
+            Some(Change {
+                name: name.to_string(),
+                kind,
+                before: before.get(name).and_then(|previous| value(previous)),
+                after: value(item),
+            })
+        })
+        .collect();
+
+    changes.extend(before.iter().filter(|(name, _)| !after.contains_key(*name)).map(|(name, item)| Change {
+        name: name.to_string(),
+        kind: ChangeKind::Removed,
+        before: value(item),
+        after: None,
+    }));
+
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{Actor, ContextManager};
+    use crate::executor::{InstructionExecutor, NativeExecutor};
+    use crate::parser::{InstructionParser, NativeParser};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_diff_contexts_after_create_and_set() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        ctx.declare_variable("scale".to_string(), crate::types::OasmType::F64, true).unwrap();
+        ctx.assign_variable("scale", Value::F64(1.0)).unwrap();
+        let before = ctx.clone();
+
+        let program = NativeParser
+            .parse_file("CREATE gear\nSET gear_0000.teeth = 24\nSET count = 3\nSET scale = 2.0")
+            .unwrap();
+        NativeExecutor::new().execute_batch(&program, &mut ctx).unwrap();
+
+        let diff = diff_contexts(&before, &ctx);
+        assert_eq!(diff.objects_with(ChangeKind::Added), vec!["gear_0000"]);
+        assert_eq!(diff.variables_with(ChangeKind::Added), vec!["count"]);
+        assert_eq!(diff.variables_with(ChangeKind::Modified), vec!["scale"]);
+        assert!(diff.seq_after > diff.seq_before);
+
+        let count = diff.variables.iter().find(|c| c.name == "count").unwrap();
+        assert_eq!((count.before.clone(), count.after.clone()), (None, Some(Value::U32(3))));
+        let scale = diff.variables.iter().find(|c| c.name == "scale").unwrap();
+        assert_eq!(scale.details(), vec!["value: 1 != 2"]);
+        let Some(Value::Object { properties, .. }) = &diff.objects[0].after else {
+            panic!("objects are recorded as Value::Object");
+        };
+        assert_eq!(properties["teeth"], Value::U32(24));
+
+        // And the other way round the same entries are removals
+        let reverse = diff_contexts(&ctx, &before);
+        assert_eq!(reverse.objects_with(ChangeKind::Removed), vec!["gear_0000"]);
+        assert_eq!(reverse.variables_with(ChangeKind::Removed), vec!["count"]);
+        assert!(diff_contexts(&ctx, &ctx).is_empty());
+    }
+}
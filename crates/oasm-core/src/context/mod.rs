@@ -1,26 +1,72 @@
-/// OASM Execution Context Manager
-/// Manages execution state: variables, objects, scopes, run tracking
+//! OASM Execution Context Manager
+//! Manages execution state: variables, objects, scopes, run tracking
 
 use crate::types::{OasmType, Value};
+use crate::validators::suppression::Suppression;
+use crate::validators::{IssueSeverity, ValidationIssue};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
 use crate::symbol_table::{SymbolTable, SymbolMetadata, SymbolType};
 
+pub mod diff;
+pub use diff::{diff_contexts, Change, ChangeKind, DiffSnapshot};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RunId(pub Uuid);
 
 impl RunId {
     pub fn new() -> Self { Self(Uuid::new_v4()) }
+
+    pub fn from_string(s: &str) -> Result<Self, uuid::Error> { Ok(Self(Uuid::parse_str(s)?)) }
+
+    /// A fixed id derived from `seed`; the same seed always gives the same id
+    pub fn from_seed(seed: u64) -> Self {
+        // splitmix64 stretches the seed into 16 bytes shaped as a v4 UUID
+        let mut state = seed;
+        let mut bytes = [0u8; 16];
+        for chunk in bytes.chunks_mut(8) {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            chunk.copy_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+        }
+        Self(uuid::Builder::from_random_bytes(bytes).into_uuid())
+    }
+
+    /// The id for a new run: seeded while deterministic mode is on for this
+    /// thread (each call takes the next seed), random otherwise
+    pub fn generate() -> Self {
+        DETERMINISTIC_SEED.with(|next| match next.get() {
+            Some(seed) => {
+                next.set(Some(seed.wrapping_add(1)));
+                Self::from_seed(seed)
+            }
+            None => Self::new(),
+        })
+    }
+}
+
+thread_local! {
+    /// Seed for the next `RunId::generate` on this thread; None means random
+    static DETERMINISTIC_SEED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Turn deterministic run ids on (starting from `seed`) or off for the
+/// current thread, returning the previous setting
+pub fn set_deterministic_seed(seed: Option<u64>) -> Option<u64> {
+    DETERMINISTIC_SEED.with(|next| next.replace(seed))
 }
 
 impl Default for RunId {
     fn default() -> Self { Self::new() }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Seq(pub u64);
 
 impl Seq {
@@ -36,7 +82,19 @@ pub enum Actor {
     System,
 }
 
-#[derive(Debug, Clone)]
+/// Confidence level (0.0 - 1.0) attached to automated decisions
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Confidence(pub f64);
+
+impl Confidence {
+    pub fn new(value: f64) -> Self { Self(value.clamp(0.0, 1.0)) }
+    pub fn high() -> Self { Self(0.9) }
+    pub fn medium() -> Self { Self(0.7) }
+    pub fn low() -> Self { Self(0.5) }
+    pub fn exceeds_threshold(&self, threshold: f64) -> bool { self.0 >= threshold }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Variable {
     pub name: String,
     pub var_type: OasmType,
@@ -44,7 +102,7 @@ pub struct Variable {
     pub mutable: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Object {
     pub id: String,
     pub object_type: String,
@@ -52,7 +110,7 @@ pub struct Object {
     pub created: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Scope {
     pub name: String,
     pub variables: HashMap<String, Variable>,
@@ -68,24 +126,51 @@ impl Scope {
 pub struct ExecutionContext {
     pub run_id: RunId,
     pub seq: Seq,
+    /// Number of the next generated object id (`<type>_<NNNN>`); advanced
+    /// only by `create_object`, independent of `seq`
+    pub object_counter: u64,
     pub actor: Actor,
     pub working_directory: PathBuf,
     pub scope_stack: Vec<Scope>,
     pub objects: HashMap<String, Object>,
     pub symbol_table: SymbolTable, // New: tracking all symbols for debugging
+    pub pending_tests: Vec<TestAnnotation>, // In-script assertions awaiting lineage
+    pub suppressions: Vec<ScopedSuppression>, // Active SUPPRESS instructions
     pub created: DateTime<Utc>,
 }
 
+/// A script-level suppression and how long it stays active
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopedSuppression {
+    pub suppression: Suppression,
+    /// Scope stack depth when it was issued; popping that scope ends it
+    pub scope_depth: usize,
+    /// Instructions left before it ends; None lasts for the whole scope
+    pub remaining: Option<usize>,
+}
+
+/// Result of an in-script check (e.g. ASSERT), later recorded in lineage
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestAnnotation {
+    pub name: String,
+    pub passed: bool,
+    pub message: Option<String>,
+    pub line_number: Option<usize>,
+}
+
 impl ExecutionContext {
     pub fn new(actor: Actor, working_directory: PathBuf) -> Self {
         Self {
-            run_id: RunId::new(),
+            run_id: RunId::generate(),
             seq: Seq::zero(),
+            object_counter: 0,
             actor,
             working_directory,
             scope_stack: vec![Scope::new("global".to_string())],
             objects: HashMap::new(),
             symbol_table: SymbolTable::new(),
+            pending_tests: Vec::new(),
+            suppressions: Vec::new(),
             created: Utc::now(),
         }
     }
@@ -99,6 +184,8 @@ pub trait ContextManager {
     fn get_variable(&self, name: &str) -> Result<&Variable, ContextError>;
     fn create_object(&mut self, object_type: String, id: Option<String>) -> Result<String, ContextError>;
     fn get_object(&self, id: &str) -> Result<&Object, ContextError>;
+    fn set_property(&mut self, object_id: &str, property: &str, value: Value) -> Result<(), ContextError>;
+    fn get_property(&self, object_id: &str, property: &str) -> Result<&Value, ContextError>;
 }
 
 #[derive(Debug, Clone)]
@@ -106,13 +193,164 @@ pub enum ContextError {
     ScopeStackEmpty,
     VariableAlreadyDefined(String),
     VariableNotFound(String),
+    /// The variable existed but its scope has been popped (needs symbol
+    /// table tombstones)
+    VariableOutOfScope { name: String, scope_depth: usize, dropped_at: DateTime<Utc> },
     ObjectNotFound(String),
+    /// `create_object` was asked for an id that is already taken
+    ObjectAlreadyExists(String),
+    PropertyNotFound { object: String, property: String },
 }
 
 impl ExecutionContext {
     pub fn next_seq(&mut self) {
         self.seq = self.seq.next();
     }
+
+    /// The id `create_object` will generate next for `object_type`
+    pub fn next_object_id(&self, object_type: &str) -> String {
+        Self::generated_object_id(object_type, self.object_counter)
+    }
+
+    /// Generated object id `{type}_{counter:04}`, where `counter` is the
+    /// object counter at creation
+    pub fn generated_object_id(object_type: &str, counter: u64) -> String {
+        format!("{}_{:04}", object_type, counter)
+    }
+
+    /// Hand over the accumulated test annotations (e.g. to a lineage hook)
+    pub fn take_pending_tests(&mut self) -> Vec<TestAnnotation> {
+        std::mem::take(&mut self.pending_tests)
+    }
+
+    /// Suppressions currently in effect, for a ValidationContext
+    pub fn active_suppressions(&self) -> Vec<Suppression> {
+        self.suppressions.iter().map(|s| s.suppression.clone()).collect()
+    }
+
+    /// Count one instruction against `next=N` suppressions, dropping spent ones
+    pub fn tick_suppressions(&mut self) {
+        for scoped in &mut self.suppressions {
+            if let Some(remaining) = scoped.remaining.as_mut() {
+                *remaining = remaining.saturating_sub(1);
+            }
+        }
+        self.suppressions.retain(|s| s.remaining != Some(0));
+    }
+
+    /// Estimated bytes held by variable values and object properties (see
+    /// Value::estimated_size)
+    pub fn estimated_memory(&self) -> usize {
+        let variables: usize = self
+            .scope_stack
+            .iter()
+            .flat_map(|scope| scope.variables.values())
+            .filter_map(|var| var.value.as_ref())
+            .map(Value::estimated_size)
+            .sum();
+        let objects: usize = self
+            .objects
+            .values()
+            .flat_map(|object| object.properties.values())
+            .map(Value::estimated_size)
+            .sum();
+        variables + objects
+    }
+
+    /// Change the declared type of the innermost variable called `name`.
+    /// Its current value is left as is; callers convert it first.
+    pub fn retype_variable(&mut self, name: &str, var_type: OasmType) -> Result<(), ContextError> {
+        let var = self
+            .scope_stack
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.variables.get_mut(name))
+            .ok_or_else(|| ContextError::VariableNotFound(name.to_string()))?;
+        var.var_type = var_type.clone();
+        self.symbol_table.update_type(name, var_type);
+        Ok(())
+    }
+
+    /// Like `declare_variable`, but also reports shadowing: redeclaring an
+    /// outer-scope variable with a different type yields a warning.
+    /// Shadowing with the same type is allowed silently.
+    pub fn declare_variable_checked(
+        &mut self,
+        name: String,
+        var_type: OasmType,
+        mutable: bool,
+    ) -> Result<Vec<ValidationIssue>, ContextError> {
+        let outer = self
+            .scope_stack
+            .iter()
+            .rev()
+            .skip(1)
+            .find_map(|scope| scope.variables.get(&name).map(|var| (scope.name.clone(), var.var_type.clone())));
+
+        let mut warnings = Vec::new();
+        if let Some((scope_name, outer_type)) = outer {
+            if outer_type != var_type {
+                warnings.push(ValidationIssue {
+                    severity: IssueSeverity::Warning,
+                    code: "core_scope.shadowing".to_string(),
+                    message: format!(
+                        "'{}: {:?}' shadows '{}: {:?}' from scope '{}'",
+                        name, var_type, name, outer_type, scope_name
+                    ),
+                    location: None,
+                    suggestion: Some(format!("Rename the inner '{}' or declare it as {:?}", name, outer_type)),
+                });
+            }
+        }
+
+        self.declare_variable(name, var_type, mutable)?;
+        Ok(warnings)
+    }
+
+    /// Snapshot the mutable execution state (scopes, objects, symbols,
+    /// pending test results, seq, object counter)
+    pub fn checkpoint(&self) -> ContextCheckpoint {
+        ContextCheckpoint {
+            scope_stack: self.scope_stack.clone(),
+            suppressions: self.suppressions.clone(),
+            objects: self.objects.clone(),
+            symbol_table: self.symbol_table.clone(),
+            pending_tests: self.pending_tests.clone(),
+            seq: self.seq,
+            object_counter: self.object_counter,
+        }
+    }
+
+    /// Roll the context back to a previously taken checkpoint
+    pub fn restore(&mut self, checkpoint: ContextCheckpoint) {
+        self.scope_stack = checkpoint.scope_stack;
+        self.suppressions = checkpoint.suppressions;
+        self.objects = checkpoint.objects;
+        self.symbol_table = checkpoint.symbol_table;
+        self.pending_tests = checkpoint.pending_tests;
+        self.seq = checkpoint.seq;
+        self.object_counter = checkpoint.object_counter;
+    }
+}
+
+/// Opaque snapshot of an ExecutionContext taken by `checkpoint()`.
+/// Currently a full clone; fields stay private so the representation can
+/// move to copy-on-write without touching callers.
+#[derive(Debug, Clone)]
+pub struct ContextCheckpoint {
+    scope_stack: Vec<Scope>,
+    suppressions: Vec<ScopedSuppression>,
+    objects: HashMap<String, Object>,
+    symbol_table: SymbolTable,
+    pending_tests: Vec<TestAnnotation>,
+    seq: Seq,
+    object_counter: u64,
+}
+
+impl ContextCheckpoint {
+    pub fn seq(&self) -> Seq {
+        self.seq
+    }
 }
 
 impl ContextManager for ExecutionContext {
@@ -124,7 +362,30 @@ impl ContextManager for ExecutionContext {
         if self.scope_stack.len() <= 1 {
             Err(ContextError::ScopeStackEmpty)
         } else {
-            Ok(self.scope_stack.pop().unwrap())
+            let popped_depth = self.scope_stack.len();
+            let scope = self.scope_stack.pop().unwrap();
+            let depth = self.scope_stack.len();
+            self.suppressions.retain(|s| s.scope_depth <= depth);
+            self.symbol_table.drop_scope(scope.variables.keys(), popped_depth);
+
+            // A popped variable that shadowed an outer one leaves the outer
+            // one visible again; re-record it
+            for name in scope.variables.keys() {
+                let outer = self.scope_stack.iter().enumerate().rev().find_map(|(i, s)| Some((i + 1, s.variables.get(name)?)));
+                if let Some((depth, var)) = outer {
+                    self.symbol_table.insert(SymbolMetadata {
+                        name: name.clone(),
+                        symbol_type: SymbolType::Variable,
+                        data_type: var.var_type.clone(),
+                        created_at: Utc::now(),
+                        last_modified: Utc::now(),
+                        source_line: 0,
+                        scope_depth: depth,
+                        dropped_at: None,
+                    });
+                }
+            }
+            Ok(scope)
         }
     }
 
@@ -148,6 +409,8 @@ impl ContextManager for ExecutionContext {
             created_at: Utc::now(),
             last_modified: Utc::now(),
             source_line: 0, // In real usage, pass from instruction
+            scope_depth: self.scope_stack.len(),
+            dropped_at: None,
         });
         Ok(())
     }
@@ -169,11 +432,33 @@ impl ContextManager for ExecutionContext {
                 return Ok(var);
             }
         }
-        Err(ContextError::VariableNotFound(name.to_string()))
+        match self.symbol_table.get(name) {
+            Some(symbol) if symbol.symbol_type == SymbolType::Variable => match symbol.dropped_at {
+                Some(dropped_at) => Err(ContextError::VariableOutOfScope {
+                    name: name.to_string(),
+                    scope_depth: symbol.scope_depth,
+                    dropped_at,
+                }),
+                None => Err(ContextError::VariableNotFound(name.to_string())),
+            },
+            _ => Err(ContextError::VariableNotFound(name.to_string())),
+        }
     }
 
     fn create_object(&mut self, object_type: String, id: Option<String>) -> Result<String, ContextError> {
-        let object_id = id.unwrap_or_else(|| format!("{}_{:04}", object_type, self.seq.0));
+        // Generated ids come from the object counter, so repeated creates
+        // never reuse one; an id that is taken is an error, not an overwrite
+        let object_id = match id {
+            Some(id) => id,
+            None => {
+                let id = self.next_object_id(&object_type);
+                self.object_counter += 1;
+                id
+            }
+        };
+        if self.objects.contains_key(&object_id) {
+            return Err(ContextError::ObjectAlreadyExists(object_id));
+        }
         let object = Object {
             id: object_id.clone(),
             object_type: object_type.clone(),
@@ -190,6 +475,8 @@ impl ContextManager for ExecutionContext {
             created_at: Utc::now(),
             last_modified: Utc::now(),
             source_line: 0,
+            scope_depth: 0,
+            dropped_at: None,
         });
 
         Ok(object_id)
@@ -198,6 +485,26 @@ impl ContextManager for ExecutionContext {
     fn get_object(&self, id: &str) -> Result<&Object, ContextError> {
         self.objects.get(id).ok_or_else(|| ContextError::ObjectNotFound(id.to_string()))
     }
+
+    fn set_property(&mut self, object_id: &str, property: &str, value: Value) -> Result<(), ContextError> {
+        let object = self
+            .objects
+            .get_mut(object_id)
+            .ok_or_else(|| ContextError::ObjectNotFound(object_id.to_string()))?;
+        object.properties.insert(property.to_string(), value);
+        self.symbol_table.update_timestamp(object_id);
+        Ok(())
+    }
+
+    fn get_property(&self, object_id: &str, property: &str) -> Result<&Value, ContextError> {
+        self.get_object(object_id)?
+            .properties
+            .get(property)
+            .ok_or_else(|| ContextError::PropertyNotFound {
+                object: object_id.to_string(),
+                property: property.to_string(),
+            })
+    }
 }
 
 impl std::fmt::Display for ContextError {
@@ -206,7 +513,18 @@ impl std::fmt::Display for ContextError {
             ContextError::ScopeStackEmpty => write!(f, "Scope stack is empty"),
             ContextError::VariableAlreadyDefined(name) => write!(f, "Variable '{}' already defined", name),
             ContextError::VariableNotFound(name) => write!(f, "Variable '{}' not found", name),
+            ContextError::VariableOutOfScope { name, scope_depth, dropped_at } => write!(
+                f,
+                "Variable '{}' existed but went out of scope at {} (declared at scope depth {})",
+                name,
+                dropped_at.to_rfc3339(),
+                scope_depth
+            ),
             ContextError::ObjectNotFound(id) => write!(f, "Object '{}' not found", id),
+            ContextError::ObjectAlreadyExists(id) => write!(f, "Object '{}' already exists", id),
+            ContextError::PropertyNotFound { object, property } => {
+                write!(f, "Object '{}' has no property '{}'", object, property)
+            }
         }
     }
 }
@@ -218,3 +536,22 @@ impl std::fmt::Display for RunId {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_id_from_seed_is_stable() {
+        assert_eq!(RunId::from_seed(42), RunId::from_seed(42));
+        assert_ne!(RunId::from_seed(42), RunId::from_seed(43));
+        assert_eq!(RunId::from_seed(42).0.get_version_num(), 4);
+
+        let previous = set_deterministic_seed(Some(42));
+        let first = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let second = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        assert_eq!(set_deterministic_seed(previous), Some(44));
+        assert_eq!((first.run_id, second.run_id), (RunId::from_seed(42), RunId::from_seed(43)));
+        assert_ne!(ExecutionContext::new(Actor::System, PathBuf::from(".")).run_id, first.run_id);
+    }
+}
@@ -0,0 +1,84 @@
+//! Dry-run execution
+//! Runs a program against a clone of the context and diffs the clone with the
+//! original, so scripts can be previewed without touching the real context.
+
+use super::{dispatch, BatchPolicy, ExecutionOutcome, InstructionRegistry};
+use crate::context::{diff_contexts, ChangeKind, ExecutionContext};
+use crate::parser::Instruction;
+
+/// Effects a program would have on a context, and where it would fail
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DryRunReport {
+    pub created_objects: Vec<String>,
+    pub modified_objects: Vec<String>,
+    pub created_variables: Vec<String>,
+    pub modified_variables: Vec<String>,
+    pub predicted_errors: Vec<PredictedError>,
+    /// Instructions that would run (a run stops where its BatchPolicy would)
+    pub instructions_run: usize,
+}
+
+impl DryRunReport {
+    /// True if the program would run without errors
+    pub fn is_clean(&self) -> bool {
+        self.predicted_errors.is_empty()
+    }
+
+    /// True if the program would change any object or variable
+    pub fn has_effects(&self) -> bool {
+        !(self.created_objects.is_empty()
+            && self.modified_objects.is_empty()
+            && self.created_variables.is_empty()
+            && self.modified_variables.is_empty())
+    }
+}
+
+/// An instruction that would fail
+#[derive(Debug, Clone, PartialEq)]
+pub struct PredictedError {
+    pub line_number: usize,
+    pub mnemonic: String,
+    pub reason: String,
+}
+
+pub(super) fn dry_run(
+    registry: &InstructionRegistry,
+    policy: BatchPolicy,
+    instructions: &[Instruction],
+    ctx: &ExecutionContext,
+) -> DryRunReport {
+    let mut scratch = ctx.clone();
+    let mut report = DryRunReport::default();
+
+    for instruction in instructions {
+        report.instructions_run += 1;
+        let result = dispatch(registry, instruction, &mut scratch);
+        let stop = policy.stops_after(&result);
+        let reason = match result {
+            Ok(result) => match result.outcome {
+                ExecutionOutcome::Failed { reason } => Some(reason),
+                _ => None,
+            },
+            Err(e) => Some(format!("{:?}", e)),
+        };
+
+        if let Some(reason) = reason {
+            report.predicted_errors.push(PredictedError {
+                line_number: instruction.line_number,
+                mnemonic: instruction.mnemonic.clone(),
+                reason,
+            });
+            if stop {
+                break;
+            }
+        }
+    }
+
+    let diff = diff_contexts(ctx, &scratch);
+    let names = |changes: Vec<&str>| changes.into_iter().map(str::to_string).collect::<Vec<_>>();
+    report.created_objects = names(diff.objects_with(ChangeKind::Added));
+    report.modified_objects = names(diff.objects_with(ChangeKind::Modified));
+    report.created_variables = names(diff.variables_with(ChangeKind::Added));
+    report.modified_variables = names(diff.variables_with(ChangeKind::Modified));
+    report
+}
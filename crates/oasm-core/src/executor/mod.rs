@@ -1,9 +1,23 @@
-/// OASM Native Executor
-/// Executes OASM instructions with command block batching support
+//! OASM Native Executor
+//! Executes OASM instructions with command block batching support
 
-use crate::context::{ContextManager, ExecutionContext, ContextError};
+mod dry_run;
+mod parallel;
+
+pub use dry_run::{DryRunReport, PredictedError};
+pub use parallel::Footprint;
+
+use crate::command_blocks::{CommandBlock, ExecutionMode};
+use crate::context::{ContextManager, ExecutionContext, ContextError, ScopedSuppression, TestAnnotation};
+use crate::expansion::{ExpansionError, ExpansionMechanism, ExpansionTracker};
+use crate::expression::{value_diff, CompareOp, Expression};
+use crate::geometry::mesh_stats;
 use crate::parser::{Instruction, Operand};
-use crate::types::{Value, NativeTypeChecker, TypeChecker};
+use crate::symbol_table::SymbolType;
+use crate::types::{evaluate_operation, OasmType, Value, NativeTypeChecker, TypeChecker, TypeError};
+use crate::validators::suppression::{self, Suppression};
+use crate::validators::{IssueSeverity, ValidationIssue};
+use std::time::{Duration, Instant};
 
 /// Execution result
 #[derive(Debug, Clone)]
@@ -22,10 +36,48 @@ pub enum ExecutionOutcome {
     PartialSuccess { completed: usize, total: usize },
 }
 
+/// What execute_batch does when an instruction fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchPolicy {
+    /// Stop at the first instruction that returns an error; instructions
+    /// that ran but did not succeed (e.g. a failed ASSERT) are recorded and
+    /// the batch goes on
+    #[default]
+    StopOnError,
+    /// Also stop at the first instruction whose outcome is not Success
+    StopOnFailure,
+    /// Record the failure, keep running, and mark the batch failed (the
+    /// stopping policies report a mixed batch as PartialSuccess)
+    ContinueOnError,
+}
+
+impl BatchPolicy {
+    /// Whether a batch under this policy stops after `result`
+    fn stops_after(&self, result: &Result<ExecutionResult, ExecutorError>) -> bool {
+        match (self, result) {
+            (BatchPolicy::ContinueOnError, _) => false,
+            (_, Err(_)) => true,
+            (BatchPolicy::StopOnFailure, Ok(result)) => result.outcome != ExecutionOutcome::Success,
+            (BatchPolicy::StopOnError, Ok(_)) => false,
+        }
+    }
+}
+
 /// Executor trait
 pub trait InstructionExecutor {
     fn execute(&mut self, instruction: &Instruction, ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError>;
     fn execute_batch(&mut self, instructions: &[Instruction], ctx: &mut ExecutionContext) -> Result<BatchResult, ExecutorError>;
+
+    /// Execute a batch honouring an execution mode; executors without
+    /// parallel support run everything sequentially
+    fn execute_batch_with_mode(
+        &mut self,
+        instructions: &[Instruction],
+        _mode: &ExecutionMode,
+        ctx: &mut ExecutionContext,
+    ) -> Result<BatchResult, ExecutorError> {
+        self.execute_batch(instructions, ctx)
+    }
 }
 
 /// Batch execution result
@@ -34,6 +86,198 @@ pub struct BatchResult {
     pub outcome: ExecutionOutcome,
     pub individual_results: Vec<ExecutionResult>,
     pub total_duration_ms: u64,
+    /// Instructions that ran concurrently with at least one other group
+    pub parallel_instructions: usize,
+    /// Rule warnings raised while running the batch (e.g. resource limits)
+    pub warnings: Vec<ValidationIssue>,
+}
+
+/// Budgets enforced across one batch (`core_resource_limits`); None is unlimited
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ResourceLimits {
+    /// Estimated bytes held by variables and object properties
+    /// (ExecutionContext::estimated_memory)
+    pub max_memory_bytes: Option<usize>,
+    /// Wall-clock time for the whole batch
+    pub max_total_duration: Option<Duration>,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_memory_bytes.is_none() && self.max_total_duration.is_none()
+    }
+
+    /// The first limit the batch has gone over, if any
+    fn check(&self, ctx: &ExecutionContext, started: Instant) -> Option<LimitExceeded> {
+        if let Some(max) = self.max_memory_bytes {
+            let used = ctx.estimated_memory();
+            if used > max {
+                return Some(LimitExceeded::Memory { used, max });
+            }
+        }
+        if let Some(max) = self.max_total_duration {
+            let elapsed = started.elapsed();
+            if elapsed > max {
+                return Some(LimitExceeded::Duration { elapsed, max });
+            }
+        }
+        None
+    }
+}
+
+/// A resource limit a batch went over
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LimitExceeded {
+    Memory { used: usize, max: usize },
+    Duration { elapsed: Duration, max: Duration },
+}
+
+impl LimitExceeded {
+    /// Check type of the matching `core_resource_limits` condition
+    fn check_type(&self) -> &'static str {
+        match self {
+            LimitExceeded::Memory { .. } => "max_memory",
+            LimitExceeded::Duration { .. } => "max_execution_time",
+        }
+    }
+
+    /// Warning for the `core_resource_limits` condition, using its message
+    fn warning(&self, line_number: usize) -> ValidationIssue {
+        let rule_message = crate::rules::hierarchy::get_core_rules()
+            .into_iter()
+            .filter(|r| r.rule.id == "core_resource_limits")
+            .flat_map(|r| r.rule.conditions)
+            .find(|c| c.check_type == self.check_type())
+            .map(|c| c.message);
+
+        ValidationIssue {
+            severity: IssueSeverity::Warning,
+            code: format!("core_resource_limits.{}", self.check_type()),
+            message: match rule_message {
+                Some(message) => format!("{}: {}", message, self),
+                None => self.to_string(),
+            },
+            location: Some(crate::validators::IssueLocation {
+                file: None,
+                line: Some(line_number),
+                column: None,
+                object_id: None,
+            }),
+            suggestion: Some("Raise the limit or split the batch".to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Memory { used, max } => {
+                write!(f, "estimated memory {} bytes exceeds the limit of {} bytes", used, max)
+            }
+            LimitExceeded::Duration { elapsed, max } => {
+                write!(f, "batch ran for {:?}, over the limit of {:?}", elapsed, max)
+            }
+        }
+    }
+}
+
+fn failed_result(instruction: &Instruction, error: &ExecutorError) -> ExecutionResult {
+    ExecutionResult {
+        outcome: ExecutionOutcome::Failed {
+            reason: format!("line {}: {} failed: {:?}", instruction.line_number, instruction.mnemonic, error),
+        },
+        output: None,
+        modified_objects: vec![],
+        duration_ms: 0,
+    }
+}
+
+/// Accumulates per-instruction results into a BatchResult
+struct BatchTally {
+    policy: BatchPolicy,
+    completed: usize,
+    failures: Vec<String>,
+    /// A resource limit stopped the batch; it fails whatever the policy
+    aborted: bool,
+    results: Vec<(usize, ExecutionResult)>,
+    warnings: Vec<ValidationIssue>,
+}
+
+impl BatchTally {
+    fn new(policy: BatchPolicy) -> Self {
+        Self { policy, completed: 0, failures: Vec::new(), aborted: false, results: Vec::new(), warnings: Vec::new() }
+    }
+
+    /// Stop the batch after `instruction` because a resource limit was hit
+    fn abort(&mut self, instruction: &Instruction, exceeded: LimitExceeded) {
+        self.failures.push(format!(
+            "line {}: resource limit hit after {}: {}",
+            instruction.line_number, instruction.mnemonic, exceeded
+        ));
+        self.warnings.push(exceeded.warning(instruction.line_number));
+        self.aborted = true;
+    }
+
+    /// Record the outcome of instruction `index`. Returns false when the
+    /// batch should stop (see `BatchPolicy`).
+    fn record(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        result: Result<ExecutionResult, ExecutorError>,
+    ) -> bool {
+        let keep_going = !self.policy.stops_after(&result);
+        let result = match result {
+            Ok(result) => result,
+            Err(e) if self.policy == BatchPolicy::ContinueOnError => failed_result(instruction, &e),
+            Err(_) => return false,
+        };
+
+        let succeeded = result.outcome == ExecutionOutcome::Success;
+        if succeeded {
+            self.completed += 1;
+        } else if let ExecutionOutcome::Failed { reason } = &result.outcome {
+            self.failures.push(reason.clone());
+        }
+        self.results.push((index, result));
+
+        keep_going
+    }
+
+    /// Record an instruction that already ran alongside others; errors are
+    /// kept as failed results even under StopOnError
+    fn record_ran(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        result: Result<ExecutionResult, ExecutorError>,
+    ) -> bool {
+        let keep_going = !self.policy.stops_after(&result);
+        let result = result.unwrap_or_else(|e| failed_result(instruction, &e));
+        self.record(index, instruction, Ok(result)) && keep_going
+    }
+
+    fn finish(mut self, total: usize, start: std::time::Instant, parallel_instructions: usize) -> BatchResult {
+        // Parallel groups report out of order; results follow the source order
+        self.results.sort_by_key(|(index, _)| *index);
+
+        let failed = self.aborted || (self.policy == BatchPolicy::ContinueOnError && !self.failures.is_empty());
+        let outcome = if failed {
+            ExecutionOutcome::Failed { reason: self.failures.join("; ") }
+        } else if self.completed == total {
+            ExecutionOutcome::Success
+        } else {
+            ExecutionOutcome::PartialSuccess { completed: self.completed, total }
+        };
+
+        BatchResult {
+            outcome,
+            individual_results: self.results.into_iter().map(|(_, result)| result).collect(),
+            total_duration_ms: start.elapsed().as_millis() as u64,
+            parallel_instructions,
+            warnings: self.warnings,
+        }
+    }
 }
 
 /// Executor errors
@@ -57,17 +301,184 @@ use std::sync::Arc;
 /// Instruction handler trait
 pub trait InstructionHandler: Send + Sync {
     fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError>;
+
+    /// Operand count this handler accepts, used for static arity checks
+    fn arity(&self) -> OperandArity {
+        OperandArity::any()
+    }
+
+    /// Names (variables and object ids) the instruction reads or writes, used
+    /// to find instructions that can run in parallel. `next_object` is the
+    /// object counter the instruction will see. None means the footprint is
+    /// unknown.
+    fn footprint(&self, _operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        None
+    }
+}
+
+/// Accepted operand count range (`max: None` means unbounded)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperandArity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl OperandArity {
+    pub fn exactly(n: usize) -> Self {
+        Self { min: n, max: Some(n) }
+    }
+
+    pub fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub fn range(min: usize, max: usize) -> Self {
+        Self { min, max: Some(max) }
+    }
+
+    pub fn any() -> Self {
+        Self::at_least(0)
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl std::fmt::Display for OperandArity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "exactly {}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+/// Operand count mismatch found before execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArityError {
+    pub mnemonic: String,
+    pub line_number: usize,
+    pub expected: OperandArity,
+    pub found: usize,
+}
+
+impl std::fmt::Display for ArityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: {} expects {} operand(s), found {}",
+            self.line_number, self.mnemonic, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ArityError {}
+
+/// Resolve a value operand: literals as-is, identifiers from variables,
+/// `object.property` from the object's property map
+pub fn resolve_operand(operand: &Operand, ctx: &ExecutionContext) -> Result<Value, ExecutorError> {
+    match operand {
+        Operand::Literal(value) => Ok(value.clone()),
+        Operand::Identifier(name) => ctx
+            .get_variable(name)?
+            .value
+            .clone()
+            .ok_or_else(|| ExecutorError::RuntimeError(format!("Variable '{}' has no value", name))),
+        Operand::Property { object, property } => Ok(ctx.get_property(object, property)?.clone()),
+        Operand::Array(items) => items
+            .iter()
+            .map(|item| resolve_operand(item, ctx))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Operand::Assignment { target, .. } => Err(ExecutorError::RuntimeError(format!(
+            "Assignment to '{}' is not a value",
+            target.render()
+        ))),
+        Operand::Expression { op, lhs, rhs } => {
+            let resolve = |side: &Operand| match side {
+                Operand::Identifier(name) if ctx.get_variable(name).is_err() => Err(ExecutorError::RuntimeError(format!(
+                    "Unknown identifier '{}' in expression {}",
+                    name,
+                    operand.render()
+                ))),
+                _ => resolve_operand(side, ctx),
+            };
+            let (left, right) = (resolve(lhs)?, resolve(rhs)?);
+            evaluate_operation(op, &[left, right]).map_err(|e| match e {
+                TypeError::DivisionByZero | TypeError::Overflow { .. } => {
+                    ExecutorError::RuntimeError(format!("{} in expression {}", e, operand.render()))
+                }
+                other => ExecutorError::TypeError { variable: operand.render(), error: other.to_string() },
+            })
+        }
+    }
+}
+
+/// Resolve the value operands that follow an instruction's object operand.
+/// Geometry handlers resolve them even before they compute anything, so an
+/// unknown variable or a missing `object.property` fails the instruction.
+fn resolve_values(operands: &[Operand], ctx: &ExecutionContext) -> Result<Vec<Value>, ExecutorError> {
+    operands.iter().skip(1).map(|operand| resolve_operand(operand, ctx)).collect()
+}
+
+/// Check every instruction's operand count against its handler's advertised arity.
+/// Mnemonics without a registered handler are skipped.
+pub fn validate_arity(instructions: &[Instruction], registry: &InstructionRegistry) -> Vec<ArityError> {
+    instructions
+        .iter()
+        .filter_map(|instruction| {
+            let expected = registry.get(&instruction.mnemonic)?.arity();
+            let found = instruction.operands.len();
+            if expected.accepts(found) {
+                None
+            } else {
+                Some(ArityError {
+                    mnemonic: instruction.mnemonic.clone(),
+                    line_number: instruction.line_number,
+                    expected,
+                    found,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Why `register_alias` refused an alias
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasError {
+    /// The canonical mnemonic has no registered handler
+    UnknownCanonical { alias: String, canonical: String },
+    /// The alias is already a registered mnemonic
+    ShadowsHandler(String),
+}
+
+impl std::fmt::Display for AliasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AliasError::UnknownCanonical { alias, canonical } => {
+                write!(f, "cannot alias {} to {}: no handler is registered for {}", alias, canonical, canonical)
+            }
+            AliasError::ShadowsHandler(alias) => write!(f, "{} is already a registered instruction", alias),
+        }
+    }
 }
 
+impl std::error::Error for AliasError {}
+
 /// Instruction registry
 pub struct InstructionRegistry {
     handlers: HashMap<String, Arc<dyn InstructionHandler>>,
+    /// Alias mnemonic -> canonical mnemonic, both uppercase
+    aliases: HashMap<String, String>,
 }
 
 impl InstructionRegistry {
     pub fn new() -> Self {
         Self {
             handlers: HashMap::new(),
+            aliases: HashMap::new(),
         }
     }
 
@@ -75,8 +486,47 @@ impl InstructionRegistry {
         self.handlers.insert(mnemonic.to_uppercase(), handler);
     }
 
+    /// Make `alias` another name for the handler registered as `canonical`.
+    /// An alias of an alias points at the same canonical mnemonic.
+    pub fn register_alias(&mut self, alias: &str, canonical: &str) -> Result<(), AliasError> {
+        let alias = alias.to_uppercase();
+        let canonical = canonical.to_uppercase();
+        let canonical = self.aliases.get(&canonical).cloned().unwrap_or(canonical);
+
+        if self.handlers.contains_key(&alias) {
+            return Err(AliasError::ShadowsHandler(alias));
+        }
+        if !self.handlers.contains_key(&canonical) {
+            return Err(AliasError::UnknownCanonical { alias, canonical });
+        }
+        self.aliases.insert(alias, canonical);
+        Ok(())
+    }
+
+    /// Canonical mnemonic for `mnemonic` (uppercased; aliases resolved)
+    pub fn canonical(&self, mnemonic: &str) -> String {
+        let mnemonic = mnemonic.to_uppercase();
+        self.aliases.get(&mnemonic).cloned().unwrap_or(mnemonic)
+    }
+
+    /// `canonical`, counting an alias hop as one level of expansion
+    pub fn canonical_within(&self, mnemonic: &str, tracker: &mut ExpansionTracker) -> Result<String, ExpansionError> {
+        let upper = mnemonic.to_uppercase();
+        let Some(canonical) = self.aliases.get(&upper) else { return Ok(upper) };
+        tracker.enter(ExpansionMechanism::Alias, &upper)?;
+        tracker.exit();
+        Ok(canonical.clone())
+    }
+
     pub fn get(&self, mnemonic: &str) -> Option<Arc<dyn InstructionHandler>> {
-        self.handlers.get(&mnemonic.to_uppercase()).cloned()
+        self.handlers.get(&self.canonical(mnemonic)).cloned()
+    }
+
+    /// Registered mnemonic closest to an unknown one, for "did you mean" hints
+    pub fn suggest(&self, mnemonic: &str) -> Option<String> {
+        let mut known: Vec<&str> = self.handlers.keys().chain(self.aliases.keys()).map(String::as_str).collect();
+        known.sort_unstable();
+        crate::text_util::closest_match(mnemonic, &known, 2)
     }
 }
 
@@ -85,6 +535,7 @@ impl Default for InstructionRegistry {
         let mut registry = Self::new();
         registry.register("CREATE", Arc::new(CreateHandler));
         registry.register("SET", Arc::new(SetHandler));
+        registry.register("CAST", Arc::new(CastHandler));
         registry.register("EXTRUDE", Arc::new(ExtrudeHandler));
         registry.register("FILLET", Arc::new(FilletHandler));
         registry.register("MOVE", Arc::new(MoveHandler));
@@ -93,12 +544,35 @@ impl Default for InstructionRegistry {
         registry.register("BOOLEAN", Arc::new(BooleanHandler));
         registry.register("VALIDATE", Arc::new(ValidateHandler));
         registry.register("EXPORT", Arc::new(ExportHandler));
+        registry.register("STATS", Arc::new(StatsHandler));
+        registry.register("BBOX", Arc::new(BboxHandler));
+        registry.register("ASSERT", Arc::new(AssertHandler));
+        registry.register("ASSERT_EQ", Arc::new(AssertEqHandler));
+        registry.register("SUPPRESS", Arc::new(SuppressHandler));
+        registry.register("INSPECT", Arc::new(InspectHandler));
         registry
     }
 }
 
 struct CreateHandler;
 impl InstructionHandler for CreateHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::at_least(1)
+    }
+
+    /// CREATE only touches the object it allocates, `{type}_{counter:04}`
+    /// from the object counter (not the seq)
+    fn footprint(&self, operands: &[Operand], next_object: u64) -> Option<Footprint> {
+        match operands.first() {
+            Some(Operand::Identifier(object_type)) => Some(Footprint {
+                names: [ExecutionContext::generated_object_id(object_type, next_object)].into(),
+                seq_bumps: 1,
+                objects_created: 1,
+            }),
+            _ => None,
+        }
+    }
+
     fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
         let start = std::time::Instant::now();
 
@@ -131,6 +605,14 @@ impl InstructionHandler for CreateHandler {
 
 struct SetHandler;
 impl InstructionHandler for SetHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(1)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands).with_seq_bumps(1))
+    }
+
     fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
         let start = std::time::Instant::now();
         let type_checker = NativeTypeChecker;
@@ -144,19 +626,67 @@ impl InstructionHandler for SetHandler {
 
         match &operands[0] {
             Operand::Assignment { target, value } => {
-                let val = match &**value {
-                    Operand::Literal(v) => v.clone(),
-                    _ => return Err(ExecutorError::RuntimeError("Cannot extract value".to_string())),
-                };
+                let target_name = target.render();
+                // Type errors inside an expression are reported against the target
+                let val = resolve_operand(value, ctx).map_err(|e| match e {
+                    ExecutorError::TypeError { error, .. } => ExecutorError::TypeError { variable: target_name.clone(), error },
+                    other => other,
+                })?;
+                let inferred_type = type_checker.infer_type(&val);
+
+                let target = match target.as_ref() {
+                    Operand::Identifier(name) => name,
+                    // `SET object.property = value` writes into the object's property map
+                    Operand::Property { object, property } => {
+                        if !ctx.objects.contains_key(object) {
+                            return Err(ExecutorError::RuntimeError(format!(
+                                "Cannot set '{}': no object '{}'",
+                                target_name, object
+                            )));
+                        }
+                        if let Ok(existing) = ctx.get_property(object, property) {
+                            let existing_type = type_checker.infer_type(existing);
+                            if let Err(type_err) = type_checker.check_assignment(&existing_type, &inferred_type) {
+                                return Err(ExecutorError::TypeError {
+                                    variable: target_name,
+                                    error: format!("{}", type_err),
+                                });
+                            }
+                        }
+                        // Also bumps the object's symbol table timestamp
+                        ctx.set_property(object, property, val)?;
+                        ctx.next_seq();
 
-                if let Ok(var) = ctx.get_variable(target) {
-                    let inferred_type = type_checker.infer_type(&val);
-                    if let Err(type_err) = type_checker.check_assignment(&var.var_type, &inferred_type) {
-                        return Err(ExecutorError::TypeError {
-                            variable: target.clone(),
-                            error: format!("{}", type_err),
+                        return Ok(ExecutionResult {
+                            outcome: ExecutionOutcome::Success,
+                            output: None,
+                            modified_objects: vec![object.clone()],
+                            duration_ms: start.elapsed().as_millis() as u64,
                         });
                     }
+                    _ => {
+                        return Err(ExecutorError::InvalidInstruction {
+                            instruction: "SET".to_string(),
+                            reason: format!("Cannot assign to '{}'", target_name),
+                        })
+                    }
+                };
+
+                // Declared variables must accept the value's type; first assignment
+                // to an undeclared name declares it with the inferred type.
+                match ctx.get_variable(target) {
+                    Ok(var) => {
+                        if let Err(type_err) = type_checker.check_assignment(&var.var_type, &inferred_type) {
+                            return Err(ExecutorError::TypeError {
+                                variable: target.clone(),
+                                error: format!("{}", type_err),
+                            });
+                        }
+                    }
+                    Err(ContextError::VariableNotFound(_) | ContextError::VariableOutOfScope { .. }) => {
+                        ctx.declare_variable(target.clone(), inferred_type, true)?;
+                    }
+                    Err(e) => return Err(e.into()),
                 }
 
                 ctx.assign_variable(target, val)?;
@@ -177,9 +707,115 @@ impl InstructionHandler for SetHandler {
     }
 }
 
+/// Explicit (possibly narrowing) conversion, range-checked by `explicit_cast`:
+/// - `CAST target = value TYPE` assigns the converted value to `target`
+/// - `CAST var TYPE` (or `CAST TYPE, var`) converts a variable in place; it
+///   takes the new type, which the symbol table records
+/// - `CAST literal TYPE` only produces the converted value
+struct CastHandler;
+impl InstructionHandler for CastHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(2)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands).with_seq_bumps(1))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        let start = std::time::Instant::now();
+        let type_checker = NativeTypeChecker;
+
+        let is_type = |operand: &Operand| matches!(operand, Operand::Identifier(name) if OasmType::from_name(name).is_some());
+        let (source, type_name) = match operands {
+            [source, Operand::Identifier(type_name)] if is_type(&operands[1]) || !is_type(source) => (source, type_name),
+            [Operand::Identifier(type_name), source] => (source, type_name),
+            _ => {
+                return Err(ExecutorError::InvalidInstruction {
+                    instruction: "CAST".to_string(),
+                    reason: "Expected CAST target = value TYPE, or CAST value TYPE".to_string(),
+                })
+            }
+        };
+        let to = OasmType::from_name(type_name).ok_or_else(|| ExecutorError::InvalidInstruction {
+            instruction: "CAST".to_string(),
+            reason: format!("Unknown type '{}'", type_name),
+        })?;
+
+        let (target, value) = match source {
+            Operand::Assignment { target, value } => match target.as_ref() {
+                Operand::Identifier(name) => (Some(name), value.as_ref()),
+                _ => {
+                    return Err(ExecutorError::InvalidInstruction {
+                        instruction: "CAST".to_string(),
+                        reason: format!("CAST can only assign to a variable, not '{}'", target.render()),
+                    })
+                }
+            },
+            Operand::Identifier(name) => (Some(name), source),
+            other => (None, other),
+        };
+
+        let val = resolve_operand(value, ctx)?;
+        let converted = match type_checker.explicit_cast(&val, &to) {
+            Ok(converted) => converted,
+            Err(e @ TypeError::CastOutOfRange { .. }) => return Err(ExecutorError::RuntimeError(e.to_string())),
+            Err(e) => {
+                return Err(ExecutorError::TypeError {
+                    variable: target.cloned().unwrap_or_default(),
+                    error: e.to_string(),
+                })
+            }
+        };
+
+        match (source, target) {
+            // In place: the variable now holds, and is declared as, the target type
+            (Operand::Identifier(_), Some(target)) => {
+                ctx.retype_variable(target, to)?;
+                ctx.assign_variable(target, converted.clone())?;
+            }
+            (_, Some(target)) => {
+                // The converted value must still suit a declared target
+                match ctx.get_variable(target) {
+                    Ok(var) => {
+                        if let Err(type_err) = type_checker.check_assignment(&var.var_type, &to) {
+                            return Err(ExecutorError::TypeError {
+                                variable: target.clone(),
+                                error: format!("{}", type_err),
+                            });
+                        }
+                    }
+                    Err(ContextError::VariableNotFound(_) | ContextError::VariableOutOfScope { .. }) => {
+                        ctx.declare_variable(target.clone(), to, true)?;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+                ctx.assign_variable(target, converted.clone())?;
+            }
+            (_, None) => {}
+        }
+        ctx.next_seq();
+
+        Ok(ExecutionResult {
+            outcome: ExecutionOutcome::Success,
+            output: Some(converted),
+            modified_objects: vec![],
+            duration_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+}
+
 struct ExtrudeHandler;
 impl InstructionHandler for ExtrudeHandler {
-    fn execute(&self, operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(2)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
         let start = std::time::Instant::now();
         // Extrude logic: EXTRUDE object, distance
         if operands.len() < 2 {
@@ -188,7 +824,8 @@ impl InstructionHandler for ExtrudeHandler {
                 reason: "Missing operands (expected: object, distance)".to_string(),
             });
         }
-        
+        resolve_values(operands, ctx)?;
+
         // Implementation details omitted for brevity, but this would update mesh data
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
@@ -201,7 +838,16 @@ impl InstructionHandler for ExtrudeHandler {
 
 struct FilletHandler;
 impl InstructionHandler for FilletHandler {
-    fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(2)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        resolve_values(operands, ctx)?;
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
             output: None,
@@ -213,7 +859,16 @@ impl InstructionHandler for FilletHandler {
 
 struct MoveHandler;
 impl InstructionHandler for MoveHandler {
-    fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(2, 4)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        resolve_values(operands, ctx)?;
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
             output: None,
@@ -225,7 +880,16 @@ impl InstructionHandler for MoveHandler {
 
 struct RotateHandler;
 impl InstructionHandler for RotateHandler {
-    fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(2, 4)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        resolve_values(operands, ctx)?;
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
             output: None,
@@ -237,7 +901,16 @@ impl InstructionHandler for RotateHandler {
 
 struct ScaleHandler;
 impl InstructionHandler for ScaleHandler {
-    fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(2, 4)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        resolve_values(operands, ctx)?;
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
             output: None,
@@ -249,6 +922,14 @@ impl InstructionHandler for ScaleHandler {
 
 struct BooleanHandler;
 impl InstructionHandler for BooleanHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(3)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
     fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
@@ -261,6 +942,14 @@ impl InstructionHandler for BooleanHandler {
 
 struct ValidateHandler;
 impl InstructionHandler for ValidateHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(0, 1)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
     fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
@@ -273,7 +962,16 @@ impl InstructionHandler for ValidateHandler {
 
 struct ExportHandler;
 impl InstructionHandler for ExportHandler {
-    fn execute(&self, _operands: &[Operand], _ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(1, 2)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        resolve_values(operands, ctx)?;
         Ok(ExecutionResult {
             outcome: ExecutionOutcome::Success,
             output: None,
@@ -283,69 +981,1206 @@ impl InstructionHandler for ExportHandler {
     }
 }
 
-/// Native executor
-pub struct NativeExecutor {
-    registry: InstructionRegistry,
-}
-
-impl NativeExecutor {
-    pub fn new() -> Self {
-        Self { registry: InstructionRegistry::default() }
-    }
-
-    pub fn with_registry(registry: InstructionRegistry) -> Self {
-        Self { registry }
+struct StatsHandler;
+impl InstructionHandler for StatsHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(1)
     }
-}
 
-impl InstructionExecutor for NativeExecutor {
-    fn execute(&mut self, instruction: &Instruction, ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
-        if let Some(handler) = self.registry.get(&instruction.mnemonic) {
-            handler.execute(&instruction.operands, ctx)
-        } else {
-            // Default behavior for unknown instructions (fallback to success for now, as in original)
-            Ok(ExecutionResult {
-                outcome: ExecutionOutcome::Success,
-                output: None,
-                modified_objects: vec![],
-                duration_ms: 0,
-            })
-        }
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
     }
 
-    fn execute_batch(&mut self, instructions: &[Instruction], ctx: &mut ExecutionContext) -> Result<BatchResult, ExecutorError> {
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
         let start = std::time::Instant::now();
-        let mut individual_results = Vec::new();
-        let mut completed = 0;
 
-        for instruction in instructions {
-            match self.execute(instruction, ctx) {
-                Ok(result) => {
-                    if result.outcome == ExecutionOutcome::Success {
-                        completed += 1;
-                    }
-                    individual_results.push(result);
-                }
-                Err(_) => break,
-            }
-        }
+        let operand = operands.first().ok_or_else(|| ExecutorError::InvalidInstruction {
+            instruction: "STATS".to_string(),
+            reason: "Missing object".to_string(),
+        })?;
 
-        let outcome = if completed == instructions.len() {
-            ExecutionOutcome::Success
-        } else {
-            ExecutionOutcome::PartialSuccess { completed, total: instructions.len() }
+        // An object is inspected through its `mesh` property; anything else
+        // (variable, property access) must resolve to a mesh value directly
+        let value = match operand {
+            Operand::Identifier(id) if ctx.objects.contains_key(id) => ctx.get_property(id, "mesh")?.clone(),
+            other => resolve_operand(other, ctx)?,
         };
 
-        Ok(BatchResult {
-            outcome,
-            individual_results,
-            total_duration_ms: start.elapsed().as_millis() as u64,
+        let stats = match &value {
+            Value::Mesh { vertices, faces } => mesh_stats(vertices, faces),
+            _ => return Err(ExecutorError::RuntimeError(format!(
+                "STATS expects a mesh, found {:?}",
+                NativeTypeChecker.infer_type(&value)
+            ))),
+        };
+
+        Ok(ExecutionResult {
+            outcome: ExecutionOutcome::Success,
+            output: Some(stats.to_value()),
+            modified_objects: vec![],
+            duration_ms: start.elapsed().as_millis() as u64,
         })
     }
 }
 
-impl Default for NativeExecutor {
+/// `BBOX object`: store the bounding box of the object's `mesh` property as
+/// its `bbox` property (also returned as the output)
+struct BboxHandler;
+impl InstructionHandler for BboxHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::exactly(1)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands).with_seq_bumps(1))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        let start = std::time::Instant::now();
+
+        let object = match operands.first() {
+            Some(Operand::Identifier(id)) if ctx.objects.contains_key(id) => id.clone(),
+            other => {
+                return Err(ExecutorError::InvalidInstruction {
+                    instruction: "BBOX".to_string(),
+                    reason: match other {
+                        Some(operand) => format!("'{}' is not an object", operand.render()),
+                        None => "Missing object".to_string(),
+                    },
+                })
+            }
+        };
+
+        let mesh = ctx.get_property(&object, "mesh")?;
+        let bbox = match mesh {
+            Value::Mesh { .. } => mesh.mesh_bounding_box().ok_or_else(|| {
+                ExecutorError::RuntimeError(format!("BBOX: the mesh of '{}' has no vertices", object))
+            })?,
+            _ => return Err(ExecutorError::RuntimeError(format!(
+                "BBOX expects a mesh, found {:?}",
+                NativeTypeChecker.infer_type(mesh)
+            ))),
+        };
+
+        ctx.set_property(&object, "bbox", bbox.clone())?;
+        ctx.next_seq();
+
+        Ok(ExecutionResult {
+            outcome: ExecutionOutcome::Success,
+            output: Some(bbox),
+            modified_objects: vec![object],
+            duration_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+/// Split an optional trailing "message" off assertion operands. A message is
+/// only recognised where the operand count would otherwise be invalid.
+fn split_assert_message(operands: &[Operand], expression_len: &[usize]) -> (Vec<Operand>, Option<String>) {
+    if let Some((Operand::Literal(Value::String(message)), rest)) = operands.split_last() {
+        if expression_len.contains(&rest.len()) {
+            return (rest.to_vec(), Some(message.clone()));
+        }
+    }
+    (operands.to_vec(), None)
+}
+
+/// Record an assertion outcome and build the instruction result
+fn assertion_result(
+    ctx: &mut ExecutionContext,
+    passed: bool,
+    rendered: String,
+    detail: Option<String>,
+    message: Option<String>,
+    start: std::time::Instant,
+) -> ExecutionResult {
+    let reason = (!passed).then(|| {
+        let mut reason = format!("assertion failed: {} is false", rendered);
+        if let Some(detail) = detail {
+            reason.push_str(&format!(" ({})", detail));
+        }
+        if let Some(message) = &message {
+            reason.push_str(&format!(": {}", message));
+        }
+        reason
+    });
+
+    ctx.pending_tests.push(TestAnnotation {
+        name: message.unwrap_or(rendered),
+        passed,
+        message: reason.clone(),
+        line_number: None,
+    });
+
+    ExecutionResult {
+        outcome: match reason {
+            None => ExecutionOutcome::Success,
+            Some(reason) => ExecutionOutcome::Failed { reason },
+        },
+        output: Some(Value::Bool(passed)),
+        modified_objects: vec![],
+        duration_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+/// ASSERT <expression> ["message"]
+struct AssertHandler;
+impl InstructionHandler for AssertHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(1, 4)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        let start = std::time::Instant::now();
+        let (operands, message) = split_assert_message(operands, &[1, 3]);
+
+        let expression = Expression::from_operands(&operands).map_err(|reason| ExecutorError::InvalidInstruction {
+            instruction: "ASSERT".to_string(),
+            reason,
+        })?;
+        let evaluated = expression.evaluate(ctx)?;
+
+        Ok(assertion_result(ctx, evaluated.result, evaluated.rendered, None, message, start))
+    }
+}
+
+/// ASSERT_EQ a b ["message"], reporting a value diff on failure
+struct AssertEqHandler;
+impl InstructionHandler for AssertEqHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(2, 3)
+    }
+
+    fn footprint(&self, operands: &[Operand], _next_object: u64) -> Option<Footprint> {
+        Some(Footprint::of_operands(operands))
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        let start = std::time::Instant::now();
+        let (operands, message) = split_assert_message(operands, &[2]);
+
+        let [lhs, rhs] = operands.as_slice() else {
+            return Err(ExecutorError::InvalidInstruction {
+                instruction: "ASSERT_EQ".to_string(),
+                reason: format!("expected 2 operands, found {}", operands.len()),
+            });
+        };
+
+        let evaluated = Expression::Compare { lhs: lhs.clone(), op: CompareOp::Eq, rhs: rhs.clone() }.evaluate(ctx)?;
+        let detail = if evaluated.result {
+            None
+        } else {
+            let diff = value_diff(&resolve_operand(lhs, ctx)?, &resolve_operand(rhs, ctx)?);
+            (!diff.is_empty()).then(|| diff.join(", "))
+        };
+
+        Ok(assertion_result(ctx, evaluated.result, evaluated.rendered, detail, message, start))
+    }
+}
+
+/// SUPPRESS <code> [until=YYYY-MM-DD] [next=N] "reason"
+/// Acknowledges a validation issue for the next N instructions, or for the
+/// rest of the enclosing scope (see validators::suppression)
+struct SuppressHandler;
+impl InstructionHandler for SuppressHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(2, 4)
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        let invalid = |reason: String| ExecutorError::InvalidInstruction { instruction: "SUPPRESS".to_string(), reason };
+
+        let mut code = None;
+        let mut reason = None;
+        let mut until = None;
+        let mut next = None;
+        for operand in operands {
+            let option = match operand {
+                Operand::Literal(Value::String(text)) => {
+                    reason = Some(text.clone());
+                    continue;
+                }
+                Operand::Identifier(name) if code.is_none() => {
+                    code = Some(name.clone());
+                    continue;
+                }
+                Operand::Property { object, property } if code.is_none() => {
+                    code = Some(format!("{}.{}", object, property));
+                    continue;
+                }
+                Operand::Identifier(option) => option.split_once('=').map(|(k, v)| (k.to_string(), v.to_string())),
+                Operand::Assignment { target, value } => match value.as_ref() {
+                    Operand::Identifier(v) => Some((target.render(), v.clone())),
+                    Operand::Literal(Value::U32(n)) => Some((target.render(), n.to_string())),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match option {
+                Some((key, value)) if key == "until" => until = Some(suppression::parse_date(&value).map_err(invalid)?),
+                Some((key, value)) if key == "next" => {
+                    let n = value.parse::<usize>().ok().filter(|n| *n > 0);
+                    next = Some(n.ok_or_else(|| invalid(format!("next={} is not a positive count", value)))?);
+                }
+                _ => return Err(invalid(format!("unexpected operand {:?}", operand))),
+            }
+        }
+
+        let mut suppression = Suppression::new(code.unwrap_or_default(), reason.unwrap_or_default());
+        suppression.until = until;
+        suppression.check().map_err(invalid)?;
+
+        ctx.suppressions.push(ScopedSuppression {
+            suppression,
+            scope_depth: ctx.scope_stack.len(),
+            remaining: next,
+        });
+
+        Ok(ExecutionResult {
+            outcome: ExecutionOutcome::Success,
+            output: None,
+            modified_objects: vec![],
+            duration_ms: 0,
+        })
+    }
+}
+
+/// INSPECT [prefix] [type=Object|Variable|Macro|Constant] [since="RFC 3339 time"]
+/// Lists the symbols that exist right now, in creation order, as an array of
+/// `Symbol` structs (see SymbolMetadata::to_value)
+struct InspectHandler;
+impl InstructionHandler for InspectHandler {
+    fn arity(&self) -> OperandArity {
+        OperandArity::range(0, 3)
+    }
+
+    fn execute(&self, operands: &[Operand], ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        let invalid = |reason: String| ExecutorError::InvalidInstruction { instruction: "INSPECT".to_string(), reason };
+
+        let mut prefix = None;
+        let mut symbol_type = None;
+        let mut since = None;
+        for operand in operands {
+            let (key, value) = match operand {
+                Operand::Identifier(option) if option.contains('=') => {
+                    let (k, v) = option.split_once('=').unwrap_or_default();
+                    (k.to_string(), v.trim_matches('"').to_string())
+                }
+                Operand::Assignment { target, value } => match value.as_ref() {
+                    Operand::Identifier(v) | Operand::Literal(Value::String(v)) => (target.render(), v.clone()),
+                    _ => return Err(invalid(format!("unexpected value for {}=", target.render()))),
+                },
+                Operand::Identifier(name) | Operand::Literal(Value::String(name)) if prefix.is_none() => {
+                    prefix = Some(name.clone());
+                    continue;
+                }
+                _ => return Err(invalid(format!("unexpected operand {:?}", operand))),
+            };
+
+            match key.as_str() {
+                "type" => {
+                    let parsed = SymbolType::from_name(&value);
+                    symbol_type = Some(parsed.ok_or_else(|| invalid(format!("unknown symbol type '{}'", value)))?);
+                }
+                "since" => {
+                    let parsed = chrono::DateTime::parse_from_rfc3339(&value)
+                        .map_err(|e| invalid(format!("since={} is not an RFC 3339 time: {}", value, e)))?;
+                    since = Some(parsed.with_timezone(&chrono::Utc));
+                }
+                _ => return Err(invalid(format!("unknown option '{}'", key))),
+            }
+        }
+
+        let symbols = ctx.symbol_table.query(|symbol| {
+            prefix.as_deref().is_none_or(|p| symbol.name.starts_with(p))
+                && symbol_type.is_none_or(|t| symbol.symbol_type == t)
+                && since.is_none_or(|t| symbol.last_modified > t)
+        });
+
+        Ok(ExecutionResult {
+            outcome: ExecutionOutcome::Success,
+            output: Some(Value::Array(symbols.into_iter().map(|s| s.to_value()).collect())),
+            modified_objects: vec![],
+            duration_ms: 0,
+        })
+    }
+}
+
+/// Native executor
+pub struct NativeExecutor {
+    registry: InstructionRegistry,
+    batch_policy: BatchPolicy,
+    limits: ResourceLimits,
+}
+
+impl NativeExecutor {
+    pub fn new() -> Self {
+        Self::with_registry(InstructionRegistry::default())
+    }
+
+    pub fn with_registry(registry: InstructionRegistry) -> Self {
+        Self { registry, batch_policy: BatchPolicy::default(), limits: ResourceLimits::default() }
+    }
+
+    pub fn with_batch_policy(mut self, policy: BatchPolicy) -> Self {
+        self.batch_policy = policy;
+        self
+    }
+
+    /// Enforce resource limits on every batch. A batch that goes over a
+    /// limit stops after the instruction that crossed it, fails, and carries
+    /// a `core_resource_limits` warning. Limited batches run sequentially so
+    /// the limits can be checked after each instruction.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Execute a command block, honouring its checkpoint_before flag: if the
+    /// batch does not fully succeed and no repair loop is configured, the
+    /// context is rolled back to its pre-block state.
+    pub fn execute_block(&mut self, block: &CommandBlock, ctx: &mut ExecutionContext) -> Result<BatchResult, ExecutorError> {
+        let checkpoint = block.checkpoint_before.then(|| ctx.checkpoint());
+
+        let result = self.execute_batch_with_mode(&block.instructions, &block.execution_mode, ctx);
+
+        let failed = !matches!(&result, Ok(batch) if batch.outcome == ExecutionOutcome::Success);
+        if failed && !block.repair_on_failure {
+            if let Some(checkpoint) = checkpoint {
+                ctx.restore(checkpoint);
+            }
+        }
+
+        result
+    }
+
+    /// Preview a program: run it against a clone of `ctx` and report the
+    /// objects and variables it would create or modify, and the errors it
+    /// would hit. `ctx` itself is left untouched.
+    pub fn dry_run(&mut self, instructions: &[Instruction], ctx: &ExecutionContext) -> DryRunReport {
+        dry_run::dry_run(&self.registry, self.batch_policy, instructions, ctx)
+    }
+}
+
+/// Run one instruction through the registry
+fn dispatch(
+    registry: &InstructionRegistry,
+    instruction: &Instruction,
+    ctx: &mut ExecutionContext,
+) -> Result<ExecutionResult, ExecutorError> {
+    let result = if let Some(handler) = registry.get(&instruction.mnemonic) {
+        let annotated = ctx.pending_tests.len();
+        let result = handler.execute(&instruction.operands, ctx);
+        for annotation in &mut ctx.pending_tests[annotated..] {
+            annotation.line_number.get_or_insert(instruction.line_number);
+        }
+        result
+    } else {
+        // Default behavior for unknown instructions (fallback to success for now, as in original)
+        Ok(ExecutionResult {
+            outcome: ExecutionOutcome::Success,
+            output: None,
+            modified_objects: vec![],
+            duration_ms: 0,
+        })
+    };
+
+    // A SUPPRESS does not count against the `next=N` window it opens
+    if registry.canonical(&instruction.mnemonic) != "SUPPRESS" {
+        ctx.tick_suppressions();
+    }
+    result
+}
+
+impl InstructionExecutor for NativeExecutor {
+    fn execute(&mut self, instruction: &Instruction, ctx: &mut ExecutionContext) -> Result<ExecutionResult, ExecutorError> {
+        dispatch(&self.registry, instruction, ctx)
+    }
+
+    fn execute_batch(&mut self, instructions: &[Instruction], ctx: &mut ExecutionContext) -> Result<BatchResult, ExecutorError> {
+        let start = Instant::now();
+        let mut tally = BatchTally::new(self.batch_policy);
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            if !tally.record(index, instruction, self.execute(instruction, ctx)) {
+                break;
+            }
+            if let Some(exceeded) = self.limits.check(ctx, start) {
+                tally.abort(instruction, exceeded);
+                break;
+            }
+        }
+
+        Ok(tally.finish(instructions.len(), start, 0))
+    }
+
+    fn execute_batch_with_mode(
+        &mut self,
+        instructions: &[Instruction],
+        mode: &ExecutionMode,
+        ctx: &mut ExecutionContext,
+    ) -> Result<BatchResult, ExecutorError> {
+        if *mode == ExecutionMode::Sequential || !self.limits.is_unlimited() {
+            return self.execute_batch(instructions, ctx);
+        }
+        Ok(parallel::execute(&self.registry, self.batch_policy, instructions, mode, ctx))
+    }
+}
+
+impl Default for NativeExecutor {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_blocks::{BatchBuilder, BlockType, CommandBlockBuilder};
+    use crate::context::Actor;
+    use crate::parser::{InstructionParser, NativeParser};
+    use std::path::PathBuf;
+
+    fn set(target: &str, value: Value) -> Instruction {
+        Instruction {
+            mnemonic: "SET".to_string(),
+            operands: vec![Operand::Assignment {
+                target: Box::new(match target.split_once('.') {
+                    Some((object, property)) => Operand::Property { object: object.to_string(), property: property.to_string() },
+                    None => Operand::Identifier(target.to_string()),
+                }),
+                value: Box::new(Operand::Literal(value)),
+            }],
+            line_number: 1,
+            provenance: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_arity_flags_missing_operand() {
+        let source = "CREATE gear\nEXTRUDE gear\nEXTRUDE gear 10\nFROBNICATE";
+        let instructions = NativeParser.parse_file(source).unwrap();
+        let registry = InstructionRegistry::default();
+
+        let errors = validate_arity(&instructions, &registry);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].mnemonic, "EXTRUDE");
+        assert_eq!(errors[0].line_number, 2);
+        assert_eq!(errors[0].found, 1);
+        assert_eq!(errors[0].expected, OperandArity::exactly(2));
+        assert_eq!(errors[0].to_string(), "line 2: EXTRUDE expects exactly 2 operand(s), found 1");
+
+        assert_eq!(registry.suggest("EXTRDUE").as_deref(), Some("EXTRUDE"));
+        assert_eq!(registry.suggest("FROBNICATE"), None);
+    }
+
+    #[test]
+    fn test_dry_run_reports_without_mutating() {
+        let ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let program = NativeParser
+            .parse_file("CREATE gear\nSET gear_0000.teeth = 20\nSET count = 3")
+            .unwrap();
+
+        let report = executor.dry_run(&program, &ctx);
+
+        assert!(report.is_clean());
+        assert_eq!(report.instructions_run, 3);
+        assert_eq!(report.created_objects, vec!["gear_0000"]);
+        assert_eq!(report.created_variables, vec!["count"]);
+        assert!(report.modified_objects.is_empty());
+        assert!(ctx.objects.is_empty());
+        assert!(ctx.scope_stack.iter().all(|scope| scope.variables.is_empty()));
+
+        let failing = NativeParser.parse_file("SET missing.teeth = 1\nSET count = 4").unwrap();
+        let report = executor.dry_run(&failing, &ctx);
+        assert_eq!(report.predicted_errors.len(), 1);
+        assert_eq!(report.predicted_errors[0].line_number, 1);
+        assert_eq!(report.instructions_run, 1);
+        assert!(!report.has_effects());
+    }
+
+    #[test]
+    fn test_property_set_then_read() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let program = NativeParser
+            .parse_file("CREATE gear\nSET gear_0000.teeth = 20\nSET count = gear_0000.teeth")
+            .unwrap();
+
+        for instruction in &program {
+            executor.execute(instruction, &mut ctx).unwrap();
+        }
+
+        assert_eq!(ctx.get_property("gear_0000", "teeth").unwrap(), &Value::U32(20));
+        assert_eq!(ctx.get_variable("count").unwrap().value, Some(Value::U32(20)));
+        assert!(matches!(
+            ctx.get_property("gear_0000", "module"),
+            Err(ContextError::PropertyNotFound { .. })
+        ));
+        assert!(resolve_operand(
+            &Operand::Property { object: "gear_0000".to_string(), property: "module".to_string() },
+            &ctx,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_geometry_handlers_resolve_value_operands() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let program = NativeParser
+            .parse_file("CREATE gear\nSET gear_0000.depth = 2.5\nSET dx = 1\nEXTRUDE gear_0000 gear_0000.depth\nMOVE gear_0000 dx 0 0")
+            .unwrap();
+        for instruction in &program {
+            executor.execute(instruction, &mut ctx).unwrap();
+        }
+
+        for line in ["EXTRUDE gear_0000 gear_0000.height", "FILLET gear_0000 radius", "SCALE gear_0000 dx factor"] {
+            let instruction = NativeParser.parse_line(line, 1).unwrap().unwrap();
+            assert!(executor.execute(&instruction, &mut ctx).is_err(), "{} should not resolve", line);
+        }
+    }
+
+    #[test]
+    fn test_property_access_on_missing_object() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+
+        assert!(matches!(ctx.get_property("ghost", "teeth"), Err(ContextError::ObjectNotFound(_))));
+
+        let set = NativeParser.parse_line("SET ghost.teeth = 20", 1).unwrap().unwrap();
+        match executor.execute(&set, &mut ctx) {
+            Err(ExecutorError::RuntimeError(msg)) => assert!(msg.contains("no object 'ghost'"), "{}", msg),
+            other => panic!("expected RuntimeError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_created_object_ids_are_unique() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let ids: Vec<String> = (0..3).map(|_| ctx.create_object("cube".to_string(), None).unwrap()).collect();
+        assert_eq!(ids, vec!["cube_0000", "cube_0001", "cube_0002"]);
+        assert_eq!(ctx.objects.len(), 3);
+        assert_eq!(ctx.seq, crate::context::Seq::zero());
+
+        // CREATE goes through the same counter; taken ids are errors, not overwrites
+        let mut executor = NativeExecutor::new();
+        let create = NativeParser.parse_line("CREATE cube", 1).unwrap().unwrap();
+        let output = executor.execute(&create, &mut ctx).unwrap().output;
+        assert_eq!(output, Some(Value::String("cube_0003".to_string())));
+        assert!(matches!(
+            ctx.create_object("cube".to_string(), Some("cube_0001".to_string())),
+            Err(ContextError::ObjectAlreadyExists(id)) if id == "cube_0001"
+        ));
+        assert_eq!(ctx.objects.len(), 4);
+    }
+
+    #[test]
+    fn test_set_object_property() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        ctx.create_object("gear".to_string(), Some("gear".to_string())).unwrap();
+        let created = ctx.symbol_table.get("gear").unwrap().last_modified;
+
+        let set = NativeParser.parse_line("SET gear.teeth = 20", 1).unwrap().unwrap();
+        let result = executor.execute(&set, &mut ctx).unwrap();
+        assert_eq!(result.modified_objects, vec!["gear".to_string()]);
+        assert_eq!(ctx.objects["gear"].properties["teeth"], Value::U32(20));
+        assert!(ctx.symbol_table.get("gear").unwrap().last_modified >= created);
+
+        // The property keeps its type
+        let set = NativeParser.parse_line("SET gear.teeth = \"many\"", 1).unwrap().unwrap();
+        assert!(matches!(executor.execute(&set, &mut ctx), Err(ExecutorError::TypeError { variable, .. }) if variable == "gear.teeth"));
+        assert_eq!(ctx.objects["gear"].properties["teeth"], Value::U32(20));
+    }
+
+    #[test]
+    fn test_set_rejects_mismatched_declared_type() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        ctx.declare_variable("count".to_string(), OasmType::U32, true).unwrap();
+
+        let mut executor = NativeExecutor::new();
+        let err = executor.execute(&set("count", Value::F64(1.5)), &mut ctx).unwrap_err();
+
+        match err {
+            ExecutorError::TypeError { variable, error } => {
+                assert_eq!(variable, "count");
+                assert!(error.contains("U32"));
+                assert!(error.contains("F64"));
+            }
+            other => panic!("expected TypeError, got {:?}", other),
+        }
+        assert!(ctx.get_variable("count").unwrap().value.is_none());
+    }
+
+    #[test]
+    fn test_cast_narrows_explicitly() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        ctx.declare_variable("teeth".to_string(), OasmType::U32, true).unwrap();
+        let mut executor = NativeExecutor::new();
+
+        // Plain SET refuses the truncating assignment and points at CAST
+        let err = executor.execute(&set("teeth", Value::F64(20.7)), &mut ctx).unwrap_err();
+        assert!(matches!(err, ExecutorError::TypeError { error, .. } if error.contains("use CAST")));
+
+        let cast = NativeParser.parse_line("CAST teeth = 20.7 U32", 1).unwrap().unwrap();
+        let result = executor.execute(&cast, &mut ctx).unwrap();
+        assert_eq!(result.output, Some(Value::U32(20)));
+        assert_eq!(ctx.get_variable("teeth").unwrap().value, Some(Value::U32(20)));
+
+        let overflow = NativeParser.parse_line("CAST small = 300 U8", 1).unwrap().unwrap();
+        assert!(matches!(executor.execute(&overflow, &mut ctx), Err(ExecutorError::RuntimeError(_))));
+
+        let forbidden = NativeParser.parse_line("CAST n = \"12\" F64", 1).unwrap().unwrap();
+        assert!(matches!(executor.execute(&forbidden, &mut ctx), Err(ExecutorError::TypeError { .. })));
+    }
+
+    #[test]
+    fn test_cast_variable_in_place() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        executor.execute(&set("x", Value::U32(7)), &mut ctx).unwrap();
+
+        let widen = NativeParser.parse_line("CAST x I64", 1).unwrap().unwrap();
+        let result = executor.execute(&widen, &mut ctx).unwrap();
+        assert_eq!(result.output, Some(Value::I64(7)));
+        let x = ctx.get_variable("x").unwrap();
+        assert_eq!((x.var_type.clone(), x.value.clone()), (OasmType::I64, Some(Value::I64(7))));
+
+        let to_string = NativeParser.parse_line("CAST x String", 1).unwrap().unwrap();
+        match executor.execute(&to_string, &mut ctx).unwrap_err() {
+            ExecutorError::TypeError { variable, .. } => assert_eq!(variable, "x"),
+            other => panic!("expected TypeError, got {:?}", other),
+        }
+        assert_eq!(ctx.get_variable("x").unwrap().value, Some(Value::I64(7)));
+
+        // Type-first form; floats truncate toward zero and out-of-range values fail
+        executor.execute(&set("ratio", Value::F64(-2.7)), &mut ctx).unwrap();
+        let narrow = NativeParser.parse_line("CAST I32, ratio", 1).unwrap().unwrap();
+        assert_eq!(executor.execute(&narrow, &mut ctx).unwrap().output, Some(Value::I32(-2)));
+        assert_eq!(ctx.symbol_table.get("ratio").unwrap().data_type, OasmType::I32);
+
+        ctx.declare_variable("big".to_string(), OasmType::U64, true).unwrap();
+        ctx.assign_variable("big", Value::U64(5_000_000_000)).unwrap();
+        let overflow = NativeParser.parse_line("CAST U32, big", 1).unwrap().unwrap();
+        assert!(matches!(executor.execute(&overflow, &mut ctx), Err(ExecutorError::RuntimeError(_))));
+        assert_eq!(ctx.get_variable("big").unwrap().var_type, OasmType::U64);
+
+        // A literal is converted without touching the context
+        let literal = NativeParser.parse_line("CAST 2.9 I32", 1).unwrap().unwrap();
+        assert_eq!(executor.execute(&literal, &mut ctx).unwrap().output, Some(Value::I32(2)));
+    }
+
+    #[test]
+    fn test_set_declares_undeclared_variable() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+
+        executor.execute(&set("radius", Value::F64(2.5)), &mut ctx).unwrap();
+
+        let var = ctx.get_variable("radius").unwrap();
+        assert_eq!(var.var_type, OasmType::F64);
+        assert_eq!(var.value, Some(Value::F64(2.5)));
+
+        let symbol = ctx.symbol_table.get("radius").unwrap();
+        assert_eq!(symbol.symbol_type, SymbolType::Variable);
+        assert_eq!(symbol.data_type, OasmType::F64);
+
+        // Subsequent assignments are checked against the inferred type
+        assert!(executor.execute(&set("radius", Value::Bool(true)), &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_set_evaluates_expression() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let script = "SET width = 4\nSET height = 2.5\nSET area = width * height\nSET bigger = area > width + 5";
+        for instruction in NativeParser.parse_file(script).unwrap() {
+            executor.execute(&instruction, &mut ctx).unwrap();
+        }
+        assert_eq!(ctx.get_variable("area").unwrap().value, Some(Value::F64(10.0)));
+        assert_eq!(ctx.get_variable("bigger").unwrap().value, Some(Value::Bool(true)));
+
+        let divide = NativeParser.parse_line("SET ratio = width / 0", 1).unwrap().unwrap();
+        assert!(matches!(executor.execute(&divide, &mut ctx), Err(ExecutorError::RuntimeError(_))));
+        let mismatch = NativeParser.parse_line("SET area = width + true", 1).unwrap().unwrap();
+        assert!(matches!(
+            executor.execute(&mismatch, &mut ctx),
+            Err(ExecutorError::TypeError { variable, .. }) if variable == "area"
+        ));
+    }
+
+    #[test]
+    fn test_set_expression_errors_name_the_line() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new().with_batch_policy(BatchPolicy::ContinueOnError);
+        let script = "SET w = 6\nSET total = 2 + 3 * 4\nSET grouped = (2 + 3) * w\nSET bad = w / (w - 6)\nSET ghost = w + depth";
+        let result = executor.execute_batch(&NativeParser.parse_file(script).unwrap(), &mut ctx).unwrap();
+
+        assert_eq!(ctx.get_variable("total").unwrap().value, Some(Value::U32(14)));
+        assert_eq!(ctx.get_variable("grouped").unwrap().value, Some(Value::U32(30)));
+        let ExecutionOutcome::Failed { reason } = result.outcome else {
+            panic!("Expected failures, got {:?}", result.outcome);
+        };
+        assert!(reason.contains("line 4") && reason.contains("Division by zero in expression (w / (w - 6))"), "{}", reason);
+        assert!(reason.contains("line 5") && reason.contains("Unknown identifier 'depth'"), "{}", reason);
+    }
+
+    fn block_with(instructions: Vec<Instruction>, configure: impl FnOnce(&mut BatchBuilder)) -> CommandBlock {
+        let mut builder = BatchBuilder::new(BlockType::CADBlock);
+        for instruction in instructions {
+            builder.add_instruction(instruction);
+        }
+        configure(&mut builder);
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_failing_block_rolls_back_to_checkpoint() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        executor.execute(&set("radius", Value::F64(2.5)), &mut ctx).unwrap();
+        let before = ctx.clone();
+
+        let mut instructions = NativeParser.parse_file("CREATE gear").unwrap();
+        instructions.push(set("teeth", Value::U32(20)));
+        instructions.extend(NativeParser.parse_file("ASSERT teeth == 20").unwrap());
+        instructions.push(set("radius", Value::Bool(true)));
+        let block = block_with(instructions, |b| {
+            b.enable_checkpoints();
+        });
+
+        let result = executor.execute_block(&block, &mut ctx).unwrap();
+
+        assert_ne!(result.outcome, ExecutionOutcome::Success);
+        assert_eq!(ctx.scope_stack, before.scope_stack);
+        assert_eq!(ctx.objects, before.objects);
+        assert_eq!(ctx.symbol_table, before.symbol_table);
+        // The block's assertion ran, but its result is rolled back with it
+        assert!(ctx.pending_tests.is_empty());
+        assert_eq!(ctx.seq, before.seq);
+    }
+
+    #[test]
+    fn test_block_keeps_state_on_success_or_repair() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+
+        let block = block_with(vec![set("teeth", Value::U32(20))], |b| {
+            b.enable_checkpoints();
+        });
+        let result = executor.execute_block(&block, &mut ctx).unwrap();
+        assert_eq!(result.outcome, ExecutionOutcome::Success);
+        assert!(ctx.get_variable("teeth").is_ok());
+
+        // With a repair loop configured, partial state is left for the repair pass
+        let block = block_with(
+            vec![set("module", Value::F64(1.5)), set("teeth", Value::Bool(true))],
+            |b| {
+                b.enable_checkpoints().enable_repair_loop();
+            },
+        );
+        let result = executor.execute_block(&block, &mut ctx).unwrap();
+        assert_ne!(result.outcome, ExecutionOutcome::Success);
+        assert!(ctx.get_variable("module").is_ok());
+    }
+
+    fn unit_cube() -> Value {
+        let vertices = (0..8)
+            .map(|i| [(i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64])
+            .collect();
+        let faces = vec![
+            vec![0, 2, 3, 1], vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4], vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2], vec![1, 3, 7, 5],
+        ];
+        Value::Mesh { vertices, faces }
+    }
+
+    #[test]
+    fn test_stats_for_unit_cube() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let cube = ctx.create_object("cube".to_string(), None).unwrap();
+        ctx.set_property(&cube, "mesh", unit_cube()).unwrap();
+
+        let instruction = Instruction {
+            mnemonic: "STATS".to_string(),
+            operands: vec![Operand::Identifier(cube)],
+            line_number: 1,
+            provenance: None,
+        };
+        let output = executor.execute(&instruction, &mut ctx).unwrap().output.unwrap();
+
+        let Value::Struct { name, fields } = output else { panic!("expected struct") };
+        assert_eq!(name, "MeshStats");
+        assert_eq!(fields["vertex_count"], Value::U64(8));
+        assert_eq!(fields["face_count"], Value::U64(6));
+        assert_eq!(fields["dimensions"], Value::Vector3([1.0, 1.0, 1.0]));
+        assert_eq!(fields["bounding_box"], Value::BoundingBox { min: [0.0; 3], max: [1.0; 3] });
+        assert_eq!(fields["surface_area"], Value::F64(6.0));
+    }
+
+    #[test]
+    fn test_bbox_of_unit_cube() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let cube = ctx.create_object("cube".to_string(), None).unwrap();
+        ctx.set_property(&cube, "mesh", unit_cube()).unwrap();
+        let bbox = |object: &str| Instruction {
+            mnemonic: "BBOX".to_string(),
+            operands: vec![Operand::Identifier(object.to_string())],
+            line_number: 1,
+            provenance: None,
+        };
+
+        let expected = Value::BoundingBox { min: [0.0, 0.0, 0.0], max: [1.0, 1.0, 1.0] };
+        assert_eq!(unit_cube().mesh_bounding_box(), Some(expected.clone()));
+        let result = executor.execute(&bbox(&cube), &mut ctx).unwrap();
+        assert_eq!(result.output, Some(expected.clone()));
+        assert_eq!(result.modified_objects, vec![cube.clone()]);
+        assert_eq!(ctx.get_property(&cube, "bbox").unwrap(), &expected);
+
+        // An empty mesh has no box
+        let empty = Value::Mesh { vertices: vec![], faces: vec![] };
+        assert_eq!(empty.mesh_bounding_box(), None);
+        assert_eq!(Value::F64(1.0).mesh_bounding_box(), None);
+        let hollow = ctx.create_object("cube".to_string(), None).unwrap();
+        ctx.set_property(&hollow, "mesh", empty).unwrap();
+        assert!(matches!(
+            executor.execute(&bbox(&hollow), &mut ctx),
+            Err(ExecutorError::RuntimeError(reason)) if reason.contains("no vertices")
+        ));
+        assert!(ctx.get_property(&hollow, "bbox").is_err());
+        assert!(executor.execute(&bbox("missing"), &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_stats_rejects_non_mesh() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        executor.execute(&set("radius", Value::F64(2.5)), &mut ctx).unwrap();
+
+        let instruction = Instruction {
+            mnemonic: "STATS".to_string(),
+            operands: vec![Operand::Identifier("radius".to_string())],
+            line_number: 1,
+            provenance: None,
+        };
+        assert!(matches!(
+            executor.execute(&instruction, &mut ctx),
+            Err(ExecutorError::RuntimeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_assert_pass_and_fail_with_values() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let script = "SET teeth = 3\nASSERT teeth >= 2\nASSERT teeth >= 4 \"need 4 teeth\"";
+        let instructions = NativeParser.parse_file(script).unwrap();
+
+        executor.execute(&instructions[0], &mut ctx).unwrap();
+        let pass = executor.execute(&instructions[1], &mut ctx).unwrap();
+        assert_eq!(pass.outcome, ExecutionOutcome::Success);
+
+        let fail = executor.execute(&instructions[2], &mut ctx).unwrap();
+        match fail.outcome {
+            ExecutionOutcome::Failed { reason } => {
+                assert!(reason.contains("teeth (3) >= 4 is false"), "{}", reason);
+                assert!(reason.ends_with("need 4 teeth"));
+            }
+            other => panic!("expected failure, got {:?}", other),
+        }
+
+        let tests = ctx.take_pending_tests();
+        assert_eq!(tests.len(), 2);
+        assert!(tests[0].passed);
+        assert!(!tests[1].passed);
+        assert_eq!(tests[1].name, "need 4 teeth");
+        assert_eq!(tests[1].line_number, Some(3));
+        assert!(ctx.pending_tests.is_empty());
+    }
+
+    #[test]
+    fn test_assert_eq_reports_value_diff() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let instructions = NativeParser.parse_file("SET module = 1.5\nASSERT_EQ module 2.0").unwrap();
+
+        let result = executor.execute_batch(&instructions, &mut ctx).unwrap();
+
+        assert_eq!(result.outcome, ExecutionOutcome::PartialSuccess { completed: 1, total: 2 });
+        match &result.individual_results[1].outcome {
+            ExecutionOutcome::Failed { reason } => {
+                assert!(reason.contains("module (1.5) == 2 is false"), "{}", reason);
+                assert!(reason.contains("value: 1.5 != 2"), "{}", reason);
+            }
+            other => panic!("expected failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_continue_on_error_accumulates_failures() {
+        let script = "SET teeth = 3\nASSERT teeth > 10\nASSERT missing\nSET module = 2.5\nASSERT_EQ teeth 3";
+        let instructions = NativeParser.parse_file(script).unwrap();
+
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new().with_batch_policy(BatchPolicy::ContinueOnError);
+        let result = executor.execute_batch(&instructions, &mut ctx).unwrap();
+
+        assert_eq!(result.individual_results.len(), 5);
+        assert!(ctx.get_variable("module").is_ok());
+        match result.outcome {
+            ExecutionOutcome::Failed { reason } => {
+                assert!(reason.contains("teeth (3) > 10 is false"));
+                assert!(reason.contains("line 3: ASSERT failed"));
+            }
+            other => panic!("expected failure, got {:?}", other),
+        }
+        let passed: Vec<bool> = ctx.pending_tests.iter().map(|t| t.passed).collect();
+        assert_eq!(passed, vec![false, true]);
+
+        // The default policy runs past a failed assertion and stops at the error
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let result = NativeExecutor::new().execute_batch(&instructions, &mut ctx).unwrap();
+        assert_eq!(result.individual_results.len(), 2);
+        assert_eq!(result.outcome, ExecutionOutcome::PartialSuccess { completed: 1, total: 5 });
+        assert!(ctx.get_variable("module").is_err());
+
+        // A mixed batch under the stopping policies is a partial success
+        let instructions = NativeParser.parse_file("SET teeth = 3\nASSERT teeth > 10\nSET module = 2.5").unwrap();
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let result = NativeExecutor::new().execute_batch(&instructions, &mut ctx).unwrap();
+        assert_eq!(result.individual_results.len(), 3);
+        assert_eq!(result.outcome, ExecutionOutcome::PartialSuccess { completed: 2, total: 3 });
+        assert!(ctx.get_variable("module").is_ok());
+
+        // StopOnFailure stops at the failed assertion itself
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new().with_batch_policy(BatchPolicy::StopOnFailure);
+        let result = executor.execute_batch(&instructions, &mut ctx).unwrap();
+        assert_eq!(result.individual_results.len(), 2);
+        assert_eq!(result.outcome, ExecutionOutcome::PartialSuccess { completed: 1, total: 3 });
+        assert!(ctx.get_variable("module").is_err());
+    }
+
+    #[test]
+    fn test_suppress_window_and_scope() {
+        let parser = crate::parser::NativeParser;
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let run = |executor: &mut NativeExecutor, ctx: &mut ExecutionContext, line: &str| {
+            let instruction = parser.parse_line(line, 1).unwrap().unwrap();
+            executor.execute(&instruction, ctx)
+        };
+
+        run(&mut executor, &mut ctx, r#"SUPPRESS NOT_WATERTIGHT until=2025-06-01 next=2 "lid added later""#).unwrap();
+        let active = ctx.active_suppressions();
+        assert_eq!(active[0].code, "NOT_WATERTIGHT");
+        assert_eq!(active[0].until, chrono::NaiveDate::from_ymd_opt(2025, 6, 1));
+        run(&mut executor, &mut ctx, "SET a = 1").unwrap();
+        assert_eq!(ctx.active_suppressions().len(), 1);
+        run(&mut executor, &mut ctx, "SET b = 2").unwrap();
+        assert!(ctx.active_suppressions().is_empty());
+
+        // Without next=N it lasts until its scope is popped
+        ctx.push_scope("block".to_string());
+        run(&mut executor, &mut ctx, r#"SUPPRESS DEGENERATE_GEOMETRY "sliver faces from import""#).unwrap();
+        run(&mut executor, &mut ctx, "SET c = 3").unwrap();
+        assert_eq!(ctx.active_suppressions().len(), 1);
+        ctx.pop_scope().unwrap();
+        assert!(ctx.active_suppressions().is_empty());
+
+        assert!(run(&mut executor, &mut ctx, "SUPPRESS DEGENERATE_GEOMETRY next=0 \"x\"").is_err());
+        assert!(run(&mut executor, &mut ctx, "SUPPRESS DEGENERATE_GEOMETRY until=soon").is_err());
+    }
+
+    #[test]
+    fn test_inspect_filters_symbols() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::new();
+        let script = "SET gear_teeth = 24\nCREATE gear\nSET gear_ratio = 1.5\nCREATE shaft\nSET tolerance = 0.01";
+        for instruction in NativeParser.parse_file(script).unwrap() {
+            executor.execute(&instruction, &mut ctx).unwrap();
+        }
+
+        let inspect = |source: &str, executor: &mut NativeExecutor, ctx: &mut ExecutionContext| {
+            let instruction = NativeParser.parse_line(source, 1).unwrap().unwrap();
+            let Some(Value::Array(symbols)) = executor.execute(&instruction, ctx).unwrap().output else {
+                panic!("INSPECT should return an array");
+            };
+            symbols
+                .into_iter()
+                .map(|symbol| match symbol {
+                    Value::Struct { fields, .. } => {
+                        assert!(fields.contains_key("created_at") && fields.contains_key("last_modified"));
+                        match (&fields["name"], &fields["symbol_type"]) {
+                            (Value::String(name), Value::String(kind)) => format!("{}:{}", name, kind),
+                            other => panic!("unexpected fields {:?}", other),
+                        }
+                    }
+                    other => panic!("expected a Symbol struct, found {:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            inspect("INSPECT gear", &mut executor, &mut ctx),
+            vec!["gear_teeth:Variable", "gear_0000:Object", "gear_ratio:Variable"]
+        );
+        assert_eq!(
+            inspect("INSPECT type=Object", &mut executor, &mut ctx),
+            vec!["gear_0000:Object", "shaft_0001:Object"]
+        );
+        assert_eq!(inspect("INSPECT gear type=object", &mut executor, &mut ctx), vec!["gear_0000:Object"]);
+        assert_eq!(inspect("INSPECT", &mut executor, &mut ctx).len(), 5);
+        assert!(inspect("INSPECT since=\"2999-01-01T00:00:00Z\"", &mut executor, &mut ctx).is_empty());
+
+        let bad = NativeParser.parse_line("INSPECT type=widget", 1).unwrap().unwrap();
+        assert!(executor.execute(&bad, &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_pop_scope_drops_symbols_and_reports_out_of_scope() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        ctx.symbol_table = ctx.symbol_table.clone().with_tombstones(true);
+        ctx.declare_variable("total".to_string(), OasmType::I32, true).unwrap();
+        ctx.declare_variable("shadowed".to_string(), OasmType::I32, true).unwrap();
+
+        ctx.push_scope("loop".to_string());
+        ctx.declare_variable("step".to_string(), OasmType::F64, true).unwrap();
+        ctx.declare_variable("shadowed".to_string(), OasmType::Bool, true).unwrap();
+        assert_eq!(ctx.symbol_table.get("step").unwrap().scope_depth, 2);
+        ctx.pop_scope().unwrap();
+
+        let step = ctx.symbol_table.get("step").unwrap();
+        assert!(step.dropped_at.is_some());
+        assert_eq!(ctx.symbol_table.dropped().len(), 1);
+        let live: Vec<&str> = ctx.symbol_table.all().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(live, vec!["total", "shadowed"]);
+        assert_eq!(ctx.symbol_table.get("shadowed").unwrap().data_type, OasmType::I32);
+
+        let err = ctx.get_variable("step").unwrap_err();
+        assert!(matches!(err, ContextError::VariableOutOfScope { scope_depth: 2, .. }));
+        assert!(err.to_string().contains("existed but went out of scope"), "{}", err);
+        assert!(matches!(ctx.get_variable("never"), Err(ContextError::VariableNotFound(_))));
+
+        // SET may declare the name again at the current scope
+        let mut executor = NativeExecutor::new();
+        executor.execute(&set("step", Value::F64(0.5)), &mut ctx).unwrap();
+        assert!(ctx.symbol_table.get("step").unwrap().dropped_at.is_none());
+
+        // Without tombstones the entry is removed outright
+        let mut plain = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        plain.push_scope("block".to_string());
+        plain.declare_variable("tmp".to_string(), OasmType::I32, true).unwrap();
+        plain.pop_scope().unwrap();
+        assert!(plain.symbol_table.get("tmp").is_none());
+        assert!(matches!(plain.get_variable("tmp"), Err(ContextError::VariableNotFound(_))));
+    }
+
+    #[test]
+    fn test_declare_variable_checked_warns_on_type_changing_shadow() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        assert!(ctx.declare_variable_checked("x".to_string(), OasmType::U32, true).unwrap().is_empty());
+        assert!(ctx.declare_variable_checked("n".to_string(), OasmType::U32, true).unwrap().is_empty());
+
+        ctx.push_scope("block".to_string());
+        let warnings = ctx.declare_variable_checked("x".to_string(), OasmType::F64, true).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, IssueSeverity::Warning);
+        assert_eq!(warnings[0].code, "core_scope.shadowing");
+        assert!(warnings[0].message.contains("U32") && warnings[0].message.contains("F64"), "{}", warnings[0].message);
+
+        // Same-type shadowing is fine, a duplicate in the same scope still fails
+        assert!(ctx.declare_variable_checked("n".to_string(), OasmType::U32, true).unwrap().is_empty());
+        assert!(matches!(
+            ctx.declare_variable_checked("x".to_string(), OasmType::F64, true),
+            Err(ContextError::VariableAlreadyDefined(_))
+        ));
+        assert_eq!(ctx.get_variable("x").unwrap().var_type, OasmType::F64);
+    }
+
+    #[test]
+    fn test_register_alias_resolves_to_canonical_handler() {
+        let mut registry = InstructionRegistry::default();
+        registry.register_alias("fillet_edge", "FILLET").unwrap();
+        registry.register_alias("BOX", "create").unwrap();
+        registry.register_alias("cube", "box").unwrap();
+
+        let canonical = registry.get("CREATE").unwrap();
+        assert!(Arc::ptr_eq(&registry.get("box").unwrap(), &canonical));
+        assert!(Arc::ptr_eq(&registry.get("Cube").unwrap(), &canonical));
+        assert!(Arc::ptr_eq(&registry.get("FILLET_EDGE").unwrap(), &registry.get("fillet").unwrap()));
+        assert_eq!(registry.canonical("cube"), "CREATE");
+
+        assert_eq!(
+            registry.register_alias("SPIN", "REVOLVE"),
+            Err(AliasError::UnknownCanonical { alias: "SPIN".to_string(), canonical: "REVOLVE".to_string() })
+        );
+        assert!(registry.get("spin").is_none());
+        assert_eq!(registry.register_alias("set", "CREATE"), Err(AliasError::ShadowsHandler("SET".to_string())));
+
+        // Aliased mnemonics execute through the canonical handler
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let mut executor = NativeExecutor::with_registry(registry);
+        let instruction = NativeParser.parse_line("BOX cube", 1).unwrap().unwrap();
+        let result = executor.execute(&instruction, &mut ctx).unwrap();
+        assert_eq!(result.modified_objects, vec!["cube_0000"]);
+    }
+
+    #[test]
+    fn test_memory_limit_aborts_batch_with_rule_warning() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let limits = ResourceLimits { max_memory_bytes: Some(200), max_total_duration: None };
+        let mut executor = NativeExecutor::new().with_limits(limits);
+        let program = NativeParser
+            .parse_file("SET a = 1\nSET label = \"a string long enough to go over the two hundred byte budget once stored in a variable alongside the first one, which it does\"\nSET b = 2")
+            .unwrap();
+
+        let batch = executor.execute_batch(&program, &mut ctx).unwrap();
+
+        let ExecutionOutcome::Failed { reason } = &batch.outcome else {
+            panic!("expected failure, got {:?}", batch.outcome);
+        };
+        assert!(reason.starts_with("line 2: resource limit hit after SET: estimated memory"), "{}", reason);
+        assert_eq!(batch.individual_results.len(), 2);
+        assert!(ctx.get_variable("b").is_err());
+        assert_eq!(batch.warnings.len(), 1);
+        assert_eq!(batch.warnings[0].code, "core_resource_limits.max_memory");
+        assert_eq!(batch.warnings[0].severity, IssueSeverity::Warning);
+        assert!(batch.warnings[0].message.starts_with("Approaching memory limit"));
+
+        // Under the budget nothing is reported
+        let mut small = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let ok = executor.execute_batch(&program[..1], &mut small).unwrap();
+        assert_eq!(ok.outcome, ExecutionOutcome::Success);
+        assert!(ok.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_time_limit_aborts_batch_with_rule_warning() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let limits = ResourceLimits { max_memory_bytes: None, max_total_duration: Some(Duration::ZERO) };
+        let mut executor = NativeExecutor::new().with_limits(limits);
+        let program = NativeParser.parse_file("SET a = 1\nSET b = 2\nSET c = 3").unwrap();
+
+        // Limits also apply when a parallel mode is requested
+        let batch = executor.execute_batch_with_mode(&program, &ExecutionMode::Parallel, &mut ctx).unwrap();
+
+        assert!(matches!(batch.outcome, ExecutionOutcome::Failed { .. }));
+        assert_eq!(batch.individual_results.len(), 1);
+        assert_eq!(batch.warnings.len(), 1);
+        assert_eq!(batch.warnings[0].code, "core_resource_limits.max_execution_time");
+        assert!(batch.warnings[0].message.starts_with("Execution time limit exceeded"));
+        assert_eq!(batch.warnings[0].location.as_ref().and_then(|l| l.line), Some(1));
+    }
+}
@@ -0,0 +1,469 @@
+//! Parallel batch execution
+//! Splits a batch into segments of instructions with known footprints, groups
+//! instructions that touch the same names, and runs independent groups on
+//! cloned contexts. Results merge back in instruction order, so the final
+//! context matches a sequential run.
+
+use super::{
+    dispatch, BatchPolicy, BatchResult, BatchTally, ExecutionResult, ExecutorError,
+    InstructionRegistry,
+};
+use crate::command_blocks::ExecutionMode;
+use crate::context::{ExecutionContext, Seq, TestAnnotation};
+use crate::expression::CompareOp;
+use crate::parser::{Instruction, Operand};
+use std::collections::{BTreeSet, HashMap};
+
+/// Variables and object ids an instruction touches, plus how many times it
+/// advances the context seq and the object counter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footprint {
+    pub names: BTreeSet<String>,
+    pub seq_bumps: u64,
+    pub objects_created: u64,
+}
+
+impl Footprint {
+    /// Every name referenced by the operands (identifiers, property owners,
+    /// assignment targets); comparison operator tokens are skipped
+    pub fn of_operands(operands: &[Operand]) -> Self {
+        let mut names = BTreeSet::new();
+        for operand in operands {
+            collect_names(operand, &mut names);
+        }
+        Self { names, seq_bumps: 0, objects_created: 0 }
+    }
+
+    pub fn with_seq_bumps(mut self, seq_bumps: u64) -> Self {
+        self.seq_bumps = seq_bumps;
+        self
+    }
+
+    pub fn with_objects_created(mut self, objects_created: u64) -> Self {
+        self.objects_created = objects_created;
+        self
+    }
+
+    pub fn is_disjoint(&self, other: &Footprint) -> bool {
+        self.names.is_disjoint(&other.names)
+    }
+}
+
+fn collect_names(operand: &Operand, names: &mut BTreeSet<String>) {
+    match operand {
+        Operand::Identifier(name) if CompareOp::from_token(name).is_none() => {
+            names.insert(name.clone());
+        }
+        Operand::Identifier(_) | Operand::Literal(_) => {}
+        Operand::Property { object, .. } => {
+            names.insert(object.clone());
+        }
+        Operand::Array(items) => {
+            for item in items {
+                collect_names(item, names);
+            }
+        }
+        Operand::Expression { lhs, rhs, .. } => {
+            collect_names(lhs, names);
+            collect_names(rhs, names);
+        }
+        Operand::Assignment { target, value } => {
+            collect_names(target, names);
+            collect_names(value, names);
+        }
+    }
+}
+
+/// One instruction of a segment and the seq and object counter offsets it
+/// runs at
+struct Member {
+    index: usize,
+    seq_offset: u64,
+    object_offset: u64,
+    footprint: Footprint,
+}
+
+/// Results of one group, with the sub-context it ran on
+struct GroupRun {
+    ctx: ExecutionContext,
+    results: Vec<(usize, Result<ExecutionResult, ExecutorError>, Vec<TestAnnotation>)>,
+    /// Sub-context after each result, kept when the policy can stop the
+    /// batch so the merge can leave out what ran past the stopping point
+    checkpoints: Vec<ExecutionContext>,
+}
+
+/// Run `instructions` under `mode`. ConditionalParallel runs an instruction
+/// without a known footprint on its own, between segments; Parallel falls
+/// back to the operand names for such instructions.
+pub(super) fn execute(
+    registry: &InstructionRegistry,
+    policy: BatchPolicy,
+    instructions: &[Instruction],
+    mode: &ExecutionMode,
+    ctx: &mut ExecutionContext,
+) -> BatchResult {
+    let start = std::time::Instant::now();
+    let mut tally = BatchTally::new(policy);
+    let mut parallel_instructions = 0;
+    let mut next = 0;
+
+    while next < instructions.len() {
+        let segment = plan_segment(registry, instructions, next, mode, ctx.object_counter);
+
+        if segment.is_empty() {
+            let instruction = &instructions[next];
+            let keep_going = tally.record(next, instruction, dispatch(registry, instruction, ctx));
+            next += 1;
+            if !keep_going {
+                break;
+            }
+            continue;
+        }
+
+        next += segment.len();
+        let groups = group_members(segment);
+        if groups.len() > 1 {
+            parallel_instructions += groups.iter().map(Vec::len).sum::<usize>();
+        }
+
+        let mut keep_going = true;
+        for (index, result) in run_segment(registry, policy, instructions, groups, ctx) {
+            keep_going &= tally.record_ran(index, &instructions[index], result);
+        }
+        if !keep_going {
+            break;
+        }
+    }
+
+    tally.finish(instructions.len(), start, parallel_instructions)
+}
+
+/// Instructions that change context-wide state (active suppressions) that
+/// sub-contexts cannot merge back, or that read all of it (INSPECT); they
+/// always run alone
+const CONTEXT_WIDE: &[&str] = &["SUPPRESS", "INSPECT"];
+
+/// Collect the instructions from `from` onwards whose footprints are known
+fn plan_segment(
+    registry: &InstructionRegistry,
+    instructions: &[Instruction],
+    from: usize,
+    mode: &ExecutionMode,
+    next_object: u64,
+) -> Vec<Member> {
+    let mut members = Vec::new();
+    let mut seq_offset = 0;
+    let mut object_offset = 0;
+
+    for (index, instruction) in instructions.iter().enumerate().skip(from) {
+        if CONTEXT_WIDE.contains(&registry.canonical(&instruction.mnemonic).as_str()) {
+            break;
+        }
+        let footprint = registry
+            .get(&instruction.mnemonic)
+            .and_then(|handler| handler.footprint(&instruction.operands, next_object + object_offset));
+        let footprint = match (footprint, mode) {
+            (Some(footprint), _) => footprint,
+            (None, ExecutionMode::Parallel) => Footprint::of_operands(&instruction.operands),
+            (None, _) => break,
+        };
+        let (bumps, created) = (footprint.seq_bumps, footprint.objects_created);
+        members.push(Member { index, seq_offset, object_offset, footprint });
+        seq_offset += bumps;
+        object_offset += created;
+    }
+
+    members
+}
+
+/// Partition members into groups of transitively overlapping footprints,
+/// ordered by their first instruction
+fn group_members(members: Vec<Member>) -> Vec<Vec<Member>> {
+    let mut groups: Vec<(BTreeSet<String>, Vec<Member>)> = Vec::new();
+
+    for member in members {
+        let (overlapping, mut rest): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .partition(|(names, _)| !names.is_disjoint(&member.footprint.names));
+
+        let mut names = member.footprint.names.clone();
+        let mut merged = Vec::new();
+        for (group_names, group) in overlapping {
+            names.extend(group_names);
+            merged.extend(group);
+        }
+        merged.push(member);
+        merged.sort_by_key(|m| m.index);
+
+        rest.push((names, merged));
+        rest.sort_by_key(|(_, group)| group[0].index);
+        groups = rest;
+    }
+
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+fn run_segment(
+    registry: &InstructionRegistry,
+    policy: BatchPolicy,
+    instructions: &[Instruction],
+    groups: Vec<Vec<Member>>,
+    ctx: &mut ExecutionContext,
+) -> Vec<(usize, Result<ExecutionResult, ExecutorError>)> {
+    let base = ctx.clone();
+    let base_seq = base.seq.0;
+    let base_objects = base.object_counter;
+
+    let runs: Vec<GroupRun> = std::thread::scope(|scope| {
+        let handles: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                let base = &base;
+                scope.spawn(move || {
+                    let mut sub = base.clone();
+                    sub.pending_tests.clear();
+                    let mut results = Vec::new();
+                    let mut checkpoints = Vec::new();
+                    for member in group {
+                        sub.seq = Seq(base_seq + member.seq_offset);
+                        sub.object_counter = base_objects + member.object_offset;
+                        let result = dispatch(registry, &instructions[member.index], &mut sub);
+                        let stop = policy.stops_after(&result);
+                        results.push((member.index, result, sub.take_pending_tests()));
+                        if policy != BatchPolicy::ContinueOnError {
+                            checkpoints.push(sub.clone());
+                        }
+                        if stop {
+                            break;
+                        }
+                    }
+                    GroupRun { ctx: sub, results, checkpoints }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("parallel instruction group panicked"))
+            .collect()
+    });
+
+    // A sequential run would have stopped at the first stopping result;
+    // nothing a group ran after it is merged or reported
+    let stop_at = runs
+        .iter()
+        .flat_map(|run| &run.results)
+        .filter(|(_, result, _)| policy.stops_after(result))
+        .map(|(index, _, _)| *index)
+        .min();
+
+    let mut results = Vec::new();
+    for mut run in runs {
+        let kept = match stop_at {
+            Some(stop_at) => run.results.iter().take_while(|(index, _, _)| *index <= stop_at).count(),
+            None => run.results.len(),
+        };
+        if kept < run.results.len() {
+            run.results.truncate(kept);
+            if let Some(last) = kept.checked_sub(1) {
+                merge_changes(ctx, &base, &run.checkpoints[last]);
+            }
+        } else {
+            merge_changes(ctx, &base, &run.ctx);
+        }
+        results.extend(run.results);
+    }
+    results.sort_by_key(|(index, _, _)| *index);
+
+    results
+        .into_iter()
+        .map(|(index, result, tests)| {
+            ctx.pending_tests.extend(tests);
+            // Sub-context ticks are discarded with the sub-context
+            ctx.tick_suppressions();
+            (index, result)
+        })
+        .collect()
+}
+
+/// Copy everything `sub` changed relative to `base` into `ctx`
+fn merge_changes(ctx: &mut ExecutionContext, base: &ExecutionContext, sub: &ExecutionContext) {
+    for (id, object) in &sub.objects {
+        if base.objects.get(id) != Some(object) {
+            ctx.objects.insert(id.clone(), object.clone());
+        }
+    }
+    for id in base.objects.keys().filter(|id| !sub.objects.contains_key(*id)) {
+        ctx.objects.remove(id);
+    }
+
+    for (depth, scope) in sub.scope_stack.iter().enumerate() {
+        let base_vars: HashMap<_, _> = base
+            .scope_stack
+            .get(depth)
+            .map(|s| s.variables.iter().collect())
+            .unwrap_or_default();
+        let Some(target) = ctx.scope_stack.get_mut(depth) else { break };
+        for (name, variable) in &scope.variables {
+            if base_vars.get(name) != Some(&variable) {
+                target.variables.insert(name.clone(), variable.clone());
+            }
+        }
+    }
+
+    for symbol in sub.symbol_table.snapshot() {
+        if base.symbol_table.get(&symbol.name) != Some(&symbol) {
+            ctx.symbol_table.insert(symbol);
+        }
+    }
+
+    ctx.seq = ctx.seq.max(sub.seq);
+    ctx.object_counter = ctx.object_counter.max(sub.object_counter);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{Actor, ContextManager};
+    use crate::executor::{ExecutionOutcome, InstructionExecutor, NativeExecutor};
+    use crate::parser::{InstructionParser, NativeParser};
+    use crate::types::Value;
+    use std::path::PathBuf;
+
+    const CHAINS: &str = "CREATE Cube\n\
+                          CREATE Sphere\n\
+                          SET Cube_0000.width = 2\n\
+                          SET Sphere_0001.radius = 5\n";
+
+    fn context() -> ExecutionContext {
+        ExecutionContext::new(Actor::System, PathBuf::from("."))
+    }
+
+    #[test]
+    fn test_independent_chains_match_sequential_run() {
+        let instructions = NativeParser.parse_file(CHAINS).unwrap();
+
+        let mut sequential_ctx = context();
+        let sequential = NativeExecutor::new().execute_batch(&instructions, &mut sequential_ctx).unwrap();
+
+        let mut parallel_ctx = context();
+        let parallel = NativeExecutor::new()
+            .execute_batch_with_mode(&instructions, &ExecutionMode::Parallel, &mut parallel_ctx)
+            .unwrap();
+
+        assert_eq!(parallel.outcome, ExecutionOutcome::Success);
+        assert_eq!(parallel.parallel_instructions, 4);
+        assert_eq!(sequential.parallel_instructions, 0);
+
+        let outputs = |batch: &BatchResult| batch.individual_results.iter().map(|r| r.output.clone()).collect::<Vec<_>>();
+        assert_eq!(outputs(&parallel), outputs(&sequential));
+        assert_eq!(parallel.individual_results[0].output, Some(Value::String("Cube_0000".to_string())));
+
+        assert_eq!(parallel_ctx.seq, sequential_ctx.seq);
+        let mut ids: Vec<_> = parallel_ctx.objects.keys().cloned().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["Cube_0000".to_string(), "Sphere_0001".to_string()]);
+        for id in &ids {
+            assert_eq!(parallel_ctx.objects[id].properties, sequential_ctx.objects[id].properties);
+        }
+        assert_eq!(parallel_ctx.get_property("Sphere_0001", "radius").unwrap(), &Value::U32(5));
+    }
+
+    #[test]
+    fn test_conditional_parallel_runs_unknown_instructions_alone() {
+        let source = "SET a = 1\nSET b = 2\nCUSTOM a b\nSET c = 3\nSET a = 4\n";
+        let instructions = NativeParser.parse_file(source).unwrap();
+
+        let mut ctx = context();
+        let result = NativeExecutor::new()
+            .execute_batch_with_mode(&instructions, &ExecutionMode::ConditionalParallel, &mut ctx)
+            .unwrap();
+
+        assert_eq!(result.outcome, ExecutionOutcome::Success);
+        assert_eq!(result.individual_results.len(), 5);
+        // `a`/`b` run side by side, CUSTOM is a barrier, then `c`/`a`
+        assert_eq!(result.parallel_instructions, 4);
+        assert_eq!(ctx.get_variable("a").unwrap().value, Some(Value::U32(4)));
+        assert_eq!(ctx.get_variable("c").unwrap().value, Some(Value::U32(3)));
+        assert_eq!(ctx.seq, Seq(4));
+    }
+
+    #[test]
+    fn test_assertions_merge_in_instruction_order() {
+        let source = "SET a = 1\nSET b = 2\nASSERT b == 2 \"second\"\nASSERT a == 1 \"first\"\n";
+        let instructions = NativeParser.parse_file(source).unwrap();
+
+        let mut ctx = context();
+        NativeExecutor::new()
+            .execute_batch_with_mode(&instructions, &ExecutionMode::Parallel, &mut ctx)
+            .unwrap();
+
+        let lines: Vec<_> = ctx.pending_tests.iter().map(|t| t.line_number).collect();
+        assert_eq!(lines, vec![Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn test_stop_on_error_drops_work_past_the_failure() {
+        let source = "SET a = 1\nSET missing.teeth = 1\nSET b = 2\nSET a = 3\n";
+        let instructions = NativeParser.parse_file(source).unwrap();
+
+        let mut ctx = context();
+        let result = NativeExecutor::new()
+            .execute_batch_with_mode(&instructions, &ExecutionMode::Parallel, &mut ctx)
+            .unwrap();
+
+        // Matches a sequential run: `b` and the second `a` never happened
+        assert_eq!(result.outcome, ExecutionOutcome::PartialSuccess { completed: 1, total: 4 });
+        assert_eq!(result.individual_results.len(), 2);
+        assert_eq!(ctx.get_variable("a").unwrap().value, Some(Value::U32(1)));
+        assert!(ctx.get_variable("b").is_err());
+    }
+
+    #[test]
+    fn test_independent_creates_are_deterministic() {
+        let source: String = (0..16).map(|i| format!("CREATE Part{}\n", i % 4)).collect();
+        let instructions = NativeParser.parse_file(&source).unwrap();
+
+        let object_set = |ctx: &ExecutionContext| {
+            let mut objects: Vec<_> = ctx
+                .objects
+                .values()
+                .map(|o| (o.id.clone(), o.object_type.clone(), format!("{:?}", o.properties)))
+                .collect();
+            objects.sort();
+            objects
+        };
+
+        let mut sequential_ctx = context();
+        NativeExecutor::new().execute_batch(&instructions, &mut sequential_ctx).unwrap();
+        assert_eq!(sequential_ctx.objects.len(), 16);
+
+        for mode in [ExecutionMode::Parallel, ExecutionMode::ConditionalParallel] {
+            for _ in 0..5 {
+                let mut parallel_ctx = context();
+                let result = NativeExecutor::new()
+                    .execute_batch_with_mode(&instructions, &mode, &mut parallel_ctx)
+                    .unwrap();
+                assert_eq!(result.outcome, ExecutionOutcome::Success);
+                assert_eq!(object_set(&parallel_ctx), object_set(&sequential_ctx));
+                assert_eq!(parallel_ctx.seq, sequential_ctx.seq);
+            }
+        }
+    }
+
+    #[test]
+    fn test_suppress_runs_alone_and_window_counts_parallel_work() {
+        let source = "SUPPRESS NOT_WATERTIGHT next=3 \"lid added later\"\nSET a = 1\nSET b = 2\nSET c = 3\n";
+        let instructions = NativeParser.parse_file(source).unwrap();
+
+        let mut ctx = context();
+        NativeExecutor::new()
+            .execute_batch_with_mode(&instructions[..3], &ExecutionMode::Parallel, &mut ctx)
+            .unwrap();
+        assert_eq!(ctx.active_suppressions().len(), 1);
+
+        NativeExecutor::new()
+            .execute_batch_with_mode(&instructions[3..], &ExecutionMode::Parallel, &mut ctx)
+            .unwrap();
+        assert!(ctx.active_suppressions().is_empty());
+    }
+}
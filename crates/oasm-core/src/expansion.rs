@@ -0,0 +1,262 @@
+//! OASM Expansion Budget
+//! Shared limits for macro, template and alias expansion so recursive or
+//! exponential definitions fail with a typed error instead of exhausting
+//! the stack or memory.
+
+use crate::parser::{Instruction, Operand};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Project configuration file whose `expansion` section sets the budget
+pub const CONFIG_FILE: &str = "oasm.config.yaml";
+
+/// Limits applied across one expansion run (all mechanisms combined)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpansionBudget {
+    /// Nested expansions (macro inside macro, template pass inside pass, ...)
+    pub max_depth: usize,
+    /// Instructions produced in total
+    pub max_instructions: usize,
+    /// Approximate source bytes produced in total
+    pub max_source_bytes: usize,
+}
+
+impl Default for ExpansionBudget {
+    fn default() -> Self {
+        Self {
+            max_depth: 64,
+            max_instructions: 100_000,
+            max_source_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl ExpansionBudget {
+    /// Budget from the `expansion` section of a configuration document
+    /// (`max_depth`, `max_instructions`, `max_source_bytes`); missing keys
+    /// keep their defaults
+    pub fn from_config_yaml(content: &str) -> Result<Self, serde_yaml::Error> {
+        #[derive(Deserialize, Default)]
+        struct Config {
+            #[serde(default)]
+            expansion: ExpansionBudget,
+        }
+        let config: Option<Config> = serde_yaml::from_str(content)?;
+        Ok(config.unwrap_or_default().expansion)
+    }
+
+    /// Budget configured in `oasm.config.yaml` under `root`; the defaults
+    /// when the project has no configuration file
+    pub fn from_project(root: &Path) -> Result<Self, BudgetConfigError> {
+        let path = root.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let error = |reason: String| BudgetConfigError { path: path.clone(), reason };
+        let content = std::fs::read_to_string(&path).map_err(|e| error(e.to_string()))?;
+        Self::from_config_yaml(&content).map_err(|e| error(e.to_string()))
+    }
+}
+
+/// The project configuration holds no valid expansion budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetConfigError {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl fmt::Display for BudgetConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid expansion budget in {}: {}", self.path.display(), self.reason)
+    }
+}
+
+impl std::error::Error for BudgetConfigError {}
+
+/// What was being expanded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionMechanism {
+    Macro,
+    Template,
+    Alias,
+    /// An `#include`d file (see `macro_processor::resolve_includes_with`)
+    Include,
+}
+
+impl fmt::Display for ExpansionMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpansionMechanism::Macro => write!(f, "macro"),
+            ExpansionMechanism::Template => write!(f, "template"),
+            ExpansionMechanism::Alias => write!(f, "alias"),
+            ExpansionMechanism::Include => write!(f, "include"),
+        }
+    }
+}
+
+/// Which budget limit was exceeded (with the configured maximum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetLimit {
+    Depth(usize),
+    Instructions(usize),
+    SourceBytes(usize),
+}
+
+impl fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetLimit::Depth(max) => write!(f, "depth limit of {}", max),
+            BudgetLimit::Instructions(max) => write!(f, "instruction limit of {}", max),
+            BudgetLimit::SourceBytes(max) => write!(f, "source size limit of {} bytes", max),
+        }
+    }
+}
+
+/// An expansion exceeded its budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionError {
+    pub mechanism: ExpansionMechanism,
+    /// Definition being expanded when the limit was hit
+    pub definition: String,
+    pub limit: BudgetLimit,
+    /// Expansions that led here, outermost first (`macro:NAME`)
+    pub chain: Vec<String>,
+}
+
+impl fmt::Display for ExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} '{}' exceeded the expansion {} (chain: {})",
+            self.mechanism,
+            self.definition,
+            self.limit,
+            self.chain.join(" -> ")
+        )
+    }
+}
+
+impl std::error::Error for ExpansionError {}
+
+/// Tracks usage against an ExpansionBudget while expanding
+#[derive(Debug, Clone)]
+pub struct ExpansionTracker {
+    budget: ExpansionBudget,
+    chain: Vec<(ExpansionMechanism, String)>,
+    instructions: usize,
+    source_bytes: usize,
+}
+
+impl ExpansionTracker {
+    pub fn new(budget: ExpansionBudget) -> Self {
+        Self { budget, chain: Vec::new(), instructions: 0, source_bytes: 0 }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.chain.len()
+    }
+
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    pub fn source_bytes(&self) -> usize {
+        self.source_bytes
+    }
+
+    /// Start expanding a definition; fails if that would exceed the depth limit
+    pub fn enter(&mut self, mechanism: ExpansionMechanism, definition: &str) -> Result<(), ExpansionError> {
+        self.chain.push((mechanism, definition.to_string()));
+        if self.chain.len() > self.budget.max_depth {
+            return Err(self.error(BudgetLimit::Depth(self.budget.max_depth)));
+        }
+        Ok(())
+    }
+
+    /// Finish the innermost expansion
+    pub fn exit(&mut self) {
+        self.chain.pop();
+    }
+
+    /// Account for produced output against the innermost expansion
+    pub fn charge(&mut self, instructions: usize, source_bytes: usize) -> Result<(), ExpansionError> {
+        self.instructions += instructions;
+        self.source_bytes += source_bytes;
+        if self.instructions > self.budget.max_instructions {
+            return Err(self.error(BudgetLimit::Instructions(self.budget.max_instructions)));
+        }
+        if self.source_bytes > self.budget.max_source_bytes {
+            return Err(self.error(BudgetLimit::SourceBytes(self.budget.max_source_bytes)));
+        }
+        Ok(())
+    }
+
+    fn error(&self, limit: BudgetLimit) -> ExpansionError {
+        let (mechanism, definition) = self
+            .chain
+            .last()
+            .cloned()
+            .unwrap_or((ExpansionMechanism::Macro, String::new()));
+        ExpansionError {
+            mechanism,
+            definition,
+            limit,
+            chain: self.chain.iter().map(|(m, name)| format!("{}:{}", m, name)).collect(),
+        }
+    }
+}
+
+/// Approximate source length of an instruction, for the byte budget
+pub fn instruction_source_len(instruction: &Instruction) -> usize {
+    instruction.mnemonic.len() + instruction.operands.iter().map(|o| operand_source_len(o) + 1).sum::<usize>()
+}
+
+fn operand_source_len(operand: &Operand) -> usize {
+    match operand {
+        Operand::Identifier(name) => name.len(),
+        Operand::Literal(value) => format!("{:?}", value).len(),
+        Operand::Property { object, property } => object.len() + 1 + property.len(),
+        Operand::Array(items) => 2 + items.iter().map(|i| operand_source_len(i) + 2).sum::<usize>(),
+        Operand::Assignment { target, value } => operand_source_len(target) + 3 + operand_source_len(value),
+        Operand::Expression { .. } => operand.render().len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_reports_depth_with_chain() {
+        let mut tracker = ExpansionTracker::new(ExpansionBudget { max_depth: 2, ..ExpansionBudget::default() });
+        tracker.enter(ExpansionMechanism::Template, "gear").unwrap();
+        tracker.enter(ExpansionMechanism::Macro, "TOOTH").unwrap();
+
+        let err = tracker.enter(ExpansionMechanism::Alias, "t").unwrap_err();
+        assert_eq!(err.mechanism, ExpansionMechanism::Alias);
+        assert_eq!(err.limit, BudgetLimit::Depth(2));
+        assert_eq!(err.chain, vec!["template:gear", "macro:TOOTH", "alias:t"]);
+        assert_eq!(
+            err.to_string(),
+            "alias 't' exceeded the expansion depth limit of 2 (chain: template:gear -> macro:TOOTH -> alias:t)"
+        );
+    }
+
+    #[test]
+    fn test_budget_from_project_config() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(ExpansionBudget::from_project(dir.path()).unwrap(), ExpansionBudget::default());
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "scanner: {depth: 3}\n").unwrap();
+        assert_eq!(ExpansionBudget::from_project(dir.path()).unwrap(), ExpansionBudget::default());
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "expansion:\n  max_depth: 8\n").unwrap();
+        let budget = ExpansionBudget::from_project(dir.path()).unwrap();
+        assert_eq!(budget, ExpansionBudget { max_depth: 8, ..ExpansionBudget::default() });
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "expansion:\n  max_depth: many\n").unwrap();
+        assert!(ExpansionBudget::from_project(dir.path()).unwrap_err().to_string().contains("Invalid expansion budget"));
+    }
+}
@@ -0,0 +1,258 @@
+//! OASM Expression Evaluation
+//! Comparison expressions over instruction operands (used by ASSERT)
+
+use crate::context::ExecutionContext;
+use crate::executor::{resolve_operand, ExecutorError};
+use crate::parser::Operand;
+use crate::types::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "==" => Some(CompareOp::Eq),
+            "!=" => Some(CompareOp::Ne),
+            "<" => Some(CompareOp::Lt),
+            "<=" => Some(CompareOp::Le),
+            ">" => Some(CompareOp::Gt),
+            ">=" => Some(CompareOp::Ge),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+/// An expression: a single operand (truthiness) or `lhs op rhs`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Value(Operand),
+    Compare { lhs: Operand, op: CompareOp, rhs: Operand },
+}
+
+/// Result of evaluating an expression, with its rendered form
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluated {
+    pub result: bool,
+    /// e.g. `teeth (3) >= 4`
+    pub rendered: String,
+}
+
+impl Expression {
+    /// Parse an expression from instruction operands (`a`, or `a OP b`)
+    pub fn from_operands(operands: &[Operand]) -> Result<Self, String> {
+        match operands {
+            [value] => Ok(Expression::Value(value.clone())),
+            [lhs, Operand::Identifier(op), rhs] => match CompareOp::from_token(op) {
+                Some(op) => Ok(Expression::Compare { lhs: lhs.clone(), op, rhs: rhs.clone() }),
+                None => Err(format!("unknown operator '{}'", op)),
+            },
+            _ => Err(format!("expected `value` or `lhs OP rhs`, found {} operand(s)", operands.len())),
+        }
+    }
+
+    pub fn evaluate(&self, ctx: &ExecutionContext) -> Result<Evaluated, ExecutorError> {
+        match self {
+            Expression::Value(operand) => {
+                let value = resolve_operand(operand, ctx)?;
+                Ok(Evaluated { result: is_truthy(&value), rendered: render_operand(operand, &value) })
+            }
+            Expression::Compare { lhs, op, rhs } => {
+                let left = resolve_operand(lhs, ctx)?;
+                let right = resolve_operand(rhs, ctx)?;
+                let result = compare(&left, *op, &right).map_err(ExecutorError::RuntimeError)?;
+                Ok(Evaluated {
+                    result,
+                    rendered: format!(
+                        "{} {} {}",
+                        render_operand(lhs, &left),
+                        op.symbol(),
+                        render_operand(rhs, &right)
+                    ),
+                })
+            }
+        }
+    }
+}
+
+/// Compare two values: numbers by value across widths, everything else by
+/// equality (strings and chars also support ordering)
+pub fn compare(left: &Value, op: CompareOp, right: &Value) -> Result<bool, String> {
+    let ordering = match (as_f64(left), as_f64(right)) {
+        (Some(l), Some(r)) => l.partial_cmp(&r),
+        _ => match (left, right) {
+            (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+            (Value::Char(l), Value::Char(r)) => Some(l.cmp(r)),
+            _ => None,
+        },
+    };
+
+    match (op, ordering) {
+        (CompareOp::Eq, Some(o)) => Ok(o == Ordering::Equal),
+        (CompareOp::Ne, Some(o)) => Ok(o != Ordering::Equal),
+        (CompareOp::Eq, None) => Ok(left == right),
+        (CompareOp::Ne, None) => Ok(left != right),
+        (CompareOp::Lt, Some(o)) => Ok(o == Ordering::Less),
+        (CompareOp::Le, Some(o)) => Ok(o != Ordering::Greater),
+        (CompareOp::Gt, Some(o)) => Ok(o == Ordering::Greater),
+        (CompareOp::Ge, Some(o)) => Ok(o != Ordering::Less),
+        (_, None) => Err(format!(
+            "cannot compare {} {} {}",
+            render_value(left),
+            op.symbol(),
+            render_value(right)
+        )),
+    }
+}
+
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Void => false,
+        other => as_f64(other).map(|n| n != 0.0).unwrap_or(true),
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::U8(n) => Some(*n as f64),
+        Value::U16(n) => Some(*n as f64),
+        Value::U32(n) => Some(*n as f64),
+        Value::U64(n) => Some(*n as f64),
+        Value::I8(n) => Some(*n as f64),
+        Value::I16(n) => Some(*n as f64),
+        Value::I32(n) => Some(*n as f64),
+        Value::I64(n) => Some(*n as f64),
+        Value::F32(n) => Some(*n as f64),
+        Value::F64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Human-readable rendering of a value for messages
+pub fn render_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{}\"", s),
+        Value::Char(c) => format!("'{}'", c),
+        Value::Bool(b) => b.to_string(),
+        Value::Void => "void".to_string(),
+        Value::U8(n) => n.to_string(),
+        Value::U16(n) => n.to_string(),
+        Value::U32(n) => n.to_string(),
+        Value::U64(n) => n.to_string(),
+        Value::I8(n) => n.to_string(),
+        Value::I16(n) => n.to_string(),
+        Value::I32(n) => n.to_string(),
+        Value::I64(n) => n.to_string(),
+        Value::F32(n) => n.to_string(),
+        Value::F64(n) => n.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Identifiers and properties show their resolved value; literals show as-is
+fn render_operand(operand: &Operand, value: &Value) -> String {
+    match operand {
+        Operand::Identifier(name) => format!("{} ({})", name, render_value(value)),
+        Operand::Property { object, property } => {
+            format!("{}.{} ({})", object, property, render_value(value))
+        }
+        _ => render_value(value),
+    }
+}
+
+/// Paths at which two values differ, e.g. `teeth: 3 != 4`
+pub fn value_diff(left: &Value, right: &Value) -> Vec<String> {
+    let mut diffs = Vec::new();
+    diff_into("", left, right, &mut diffs);
+    diffs
+}
+
+fn diff_into(path: &str, left: &Value, right: &Value, diffs: &mut Vec<String>) {
+    let label = |p: &str| if p.is_empty() { "value".to_string() } else { p.to_string() };
+
+    match (left, right) {
+        (Value::Array(l), Value::Array(r)) => {
+            for i in 0..l.len().max(r.len()) {
+                let item = format!("{}[{}]", path, i);
+                match (l.get(i), r.get(i)) {
+                    (Some(a), Some(b)) => diff_into(&item, a, b, diffs),
+                    (Some(a), None) => diffs.push(format!("{}: {} != <missing>", item, render_value(a))),
+                    (None, Some(b)) => diffs.push(format!("{}: <missing> != {}", item, render_value(b))),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Struct { fields: l, .. }, Value::Struct { fields: r, .. })
+        | (Value::Object { properties: l, .. }, Value::Object { properties: r, .. }) => {
+            let keys: BTreeSet<&String> = l.keys().chain(r.keys()).collect();
+            for key in keys {
+                let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match (l.get(key), r.get(key)) {
+                    (Some(a), Some(b)) => diff_into(&field, a, b, diffs),
+                    (Some(a), None) => diffs.push(format!("{}: {} != <missing>", field, render_value(a))),
+                    (None, Some(b)) => diffs.push(format!("{}: <missing> != {}", field, render_value(b))),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {
+            if !compare(left, CompareOp::Eq, right).unwrap_or(false) {
+                diffs.push(format!("{}: {} != {}", label(path), render_value(left), render_value(right)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_compare_across_numeric_widths() {
+        assert!(compare(&Value::U32(3), CompareOp::Lt, &Value::F64(3.5)).unwrap());
+        assert!(compare(&Value::I64(4), CompareOp::Eq, &Value::U8(4)).unwrap());
+        assert!(compare(&Value::String("a".into()), CompareOp::Lt, &Value::String("b".into())).unwrap());
+        assert!(compare(&Value::Bool(true), CompareOp::Gt, &Value::U32(1)).is_err());
+        assert_eq!(render_value(&Value::F64(2.5)), "2.5");
+    }
+
+    #[test]
+    fn test_value_diff_reports_field_paths() {
+        let gear = |teeth: u32| Value::Struct {
+            name: "Gear".to_string(),
+            fields: HashMap::from([
+                ("teeth".to_string(), Value::U32(teeth)),
+                ("module".to_string(), Value::F64(1.5)),
+            ]),
+        };
+
+        assert!(value_diff(&gear(20), &gear(20)).is_empty());
+        assert_eq!(value_diff(&gear(20), &gear(18)), vec!["teeth: 20 != 18".to_string()]);
+        assert_eq!(value_diff(&Value::U32(1), &Value::U32(2)), vec!["value: 1 != 2".to_string()]);
+    }
+}
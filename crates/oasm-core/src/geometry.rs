@@ -0,0 +1,223 @@
+//! Geometry helpers shared by instruction handlers and validators
+
+use crate::types::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Faces with less area than this count as degenerate
+pub const AREA_EPSILON: f64 = 1e-12;
+
+/// Undirected mesh edge as (lower vertex index, higher vertex index)
+pub type Edge = (usize, usize);
+
+/// Summary statistics for a polygon mesh
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshStats {
+    pub vertex_count: usize,
+    pub face_count: usize,
+    pub bbox_min: [f64; 3],
+    pub bbox_max: [f64; 3],
+    pub surface_area: f64,
+}
+
+impl MeshStats {
+    /// Bounding box extents along x, y and z
+    pub fn dimensions(&self) -> [f64; 3] {
+        [
+            self.bbox_max[0] - self.bbox_min[0],
+            self.bbox_max[1] - self.bbox_min[1],
+            self.bbox_max[2] - self.bbox_min[2],
+        ]
+    }
+
+    /// Convert to a `MeshStats` struct value usable by SET and validators
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("vertex_count".to_string(), Value::U64(self.vertex_count as u64));
+        fields.insert("face_count".to_string(), Value::U64(self.face_count as u64));
+        fields.insert(
+            "bounding_box".to_string(),
+            Value::BoundingBox { min: self.bbox_min, max: self.bbox_max },
+        );
+        fields.insert("dimensions".to_string(), Value::Vector3(self.dimensions()));
+        fields.insert("surface_area".to_string(), Value::F64(self.surface_area));
+
+        Value::Struct { name: "MeshStats".to_string(), fields }
+    }
+}
+
+/// Axis-aligned (min, max) corners around the vertices; None without any
+pub fn bounding_box(vertices: &[[f64; 3]]) -> Option<([f64; 3], [f64; 3])> {
+    if vertices.is_empty() {
+        return None;
+    }
+    Some(vertices.iter().fold(
+        ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]),
+        |(mut min, mut max), v| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(v[axis]);
+                max[axis] = max[axis].max(v[axis]);
+            }
+            (min, max)
+        },
+    ))
+}
+
+/// Compute stats for a mesh. Faces are fan-triangulated for the area, and
+/// face indices outside the vertex list are ignored.
+pub fn mesh_stats(vertices: &[[f64; 3]], faces: &[Vec<usize>]) -> MeshStats {
+    let (bbox_min, bbox_max) = bounding_box(vertices).unwrap_or(([0.0; 3], [0.0; 3]));
+
+    let surface_area = faces
+        .iter()
+        .map(|face| {
+            let points: Vec<[f64; 3]> = face.iter().filter_map(|&i| vertices.get(i).copied()).collect();
+            if points.len() < 3 {
+                return 0.0;
+            }
+            (1..points.len() - 1)
+                .map(|i| triangle_area(points[0], points[i], points[i + 1]))
+                .sum::<f64>()
+        })
+        .sum();
+
+    MeshStats {
+        vertex_count: vertices.len(),
+        face_count: faces.len(),
+        bbox_min,
+        bbox_max,
+        surface_area,
+    }
+}
+
+/// Faces using each edge. A closed manifold mesh has exactly two per edge;
+/// one means an open boundary, more than two a non-manifold edge.
+pub fn edge_faces(faces: &[Vec<usize>]) -> BTreeMap<Edge, Vec<usize>> {
+    let mut edges: BTreeMap<Edge, Vec<usize>> = BTreeMap::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        for (a, b) in face_edges(face) {
+            if a != b {
+                edges.entry((a.min(b), a.max(b))).or_default().push(face_index);
+            }
+        }
+    }
+    edges
+}
+
+/// Faces with fewer than three valid vertices or (near) zero area
+pub fn degenerate_faces(vertices: &[[f64; 3]], faces: &[Vec<usize>]) -> Vec<usize> {
+    faces
+        .iter()
+        .enumerate()
+        .filter(|(_, face)| {
+            let points: Option<Vec<[f64; 3]>> = face.iter().map(|&i| vertices.get(i).copied()).collect();
+            match points {
+                Some(points) if points.len() >= 3 => 0.5 * length(area_vector(&points)) < AREA_EPSILON,
+                _ => true,
+            }
+        })
+        .map(|(face_index, _)| face_index)
+        .collect()
+}
+
+/// Pairs of faces that traverse a shared edge in the same direction, i.e.
+/// whose windings (and so normals) disagree
+pub fn inconsistent_windings(faces: &[Vec<usize>]) -> Vec<(usize, usize)> {
+    let mut directed: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut pairs = Vec::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        for edge in face_edges(face).filter(|(a, b)| a != b) {
+            match directed.get(&edge) {
+                Some(&other) if other != face_index => pairs.push((other, face_index)),
+                Some(_) => {}
+                None => {
+                    directed.insert(edge, face_index);
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Pairs of faces sharing no vertex whose bounding boxes overlap with
+/// positive volume. A cheap stand-in for real self-intersection tests: it
+/// finds interpenetrating parts but can flag close, non-touching faces.
+pub fn overlapping_faces(vertices: &[[f64; 3]], faces: &[Vec<usize>]) -> Vec<(usize, usize)> {
+    let boxes: Vec<Option<([f64; 3], [f64; 3])>> = faces
+        .iter()
+        .map(|face| {
+            let points: Vec<[f64; 3]> = face.iter().filter_map(|&i| vertices.get(i).copied()).collect();
+            (!points.is_empty()).then(|| mesh_stats(&points, &[])).map(|s| (s.bbox_min, s.bbox_max))
+        })
+        .collect();
+
+    let mut pairs = Vec::new();
+    for i in 0..faces.len() {
+        for j in i + 1..faces.len() {
+            let (Some((min_a, max_a)), Some((min_b, max_b))) = (boxes[i], boxes[j]) else {
+                continue;
+            };
+            if faces[i].iter().any(|v| faces[j].contains(v)) {
+                continue;
+            }
+            let overlaps = (0..3).all(|axis| max_a[axis].min(max_b[axis]) - min_a[axis].max(min_b[axis]) > AREA_EPSILON);
+            if overlaps {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// Consecutive vertex pairs around a face, closing back to the first
+fn face_edges(face: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()]))
+}
+
+/// Sum of fan-triangle cross products; its length is twice the polygon's area
+fn area_vector(points: &[[f64; 3]]) -> [f64; 3] {
+    let origin = points[0];
+    let mut total = [0.0; 3];
+    for i in 1..points.len() - 1 {
+        let c = cross(sub(points[i], origin), sub(points[i + 1], origin));
+        for axis in 0..3 {
+            total[axis] += c[axis];
+        }
+    }
+    total
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(u: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn length(v: [f64; 3]) -> f64 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+fn triangle_area(a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> f64 {
+    0.5 * length(cross(sub(b, a), sub(c, a)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degenerate_faces_have_no_area() {
+        let vertices = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]];
+        let stats = mesh_stats(&vertices, &[vec![0, 1], vec![0, 1, 7]]);
+
+        assert_eq!(stats.face_count, 2);
+        assert_eq!(stats.surface_area, 0.0);
+        assert_eq!(stats.dimensions(), [1.0, 0.0, 0.0]);
+        assert_eq!(mesh_stats(&[], &[]).dimensions(), [0.0; 3]);
+    }
+}
@@ -1,4 +1,4 @@
-/// Instruction parser and executor for OASM assembly
+//! Instruction parser and executor for OASM assembly
 
 use std::collections::HashMap;
 
@@ -88,7 +88,7 @@ fn classify_token(s: &str) -> Token {
 
     // Check if it's a keyword
     let keywords = vec![
-        "CREATE", "DEFINE", "SET", "EXTRUDE", "MOVE", "ROTATE", "SCALE",
+        "CREATE", "DEFINE", "SET", "CAST", "EXTRUDE", "MOVE", "ROTATE", "SCALE",
         "VALIDATE", "SCAN", "EXPORT", "INSERT", "APPLY", "ATTACH",
     ];
 
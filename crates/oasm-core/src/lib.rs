@@ -1,15 +1,15 @@
-/// OASM Core - Internal modules, blocks, and scripts
-///
-/// This crate contains the internal implementation of OASM:
-/// - Module system
-/// - Block definitions
-/// - Rule engine
-/// - Instruction parser and executor
+//! OASM Core - Internal modules, blocks, and scripts
+//!
+//! This crate contains the internal implementation of OASM:
+//! - Module system
+//! - Block definitions
+//! - Rule engine
+//! - Instruction parser and executor
 
 pub mod modules;
 pub mod blocks;
 pub mod rules;          // Hierarchical rule engine (Core→Domain→Project→Session)
-pub mod rules_legacy;   // Legacy rule loading functions
+pub mod rules_legacy;   // Deprecated flat rule sets (see rules::hierarchy)
 pub mod instructions;
 
 // Native modular components
@@ -23,6 +23,12 @@ pub mod state_evaluator; // Smart state evaluation logic
 pub mod macro_processor; // Macro expansion logic
 pub mod symbol_table;   // Searchable symbol tracking for debugging
 pub mod templates;      // YAML-based template loading and expansion
+pub mod regex_cache;    // Shared compiled-regex cache
+pub mod geometry;       // Mesh statistics and geometry helpers
+pub mod expression;     // Comparison expressions (ASSERT)
+pub mod expansion;      // Shared budget for macro/template/alias expansion
+pub mod text_util;      // Shared "did you mean" suggestions
+pub mod bench;          // Parse + execute throughput measurement
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -152,35 +158,51 @@ impl Default for BlockRegistry {
     }
 }
 
-/// Rule engine
+/// Flat rule engine kept for older callers. Rules are registered as
+/// Core-level rules in a HierarchicalRuleEngine, which does the evaluation.
 pub struct RuleEngine {
-    rules: HashMap<String, Rule>,
+    engine: rules::HierarchicalRuleEngine,
 }
 
 impl RuleEngine {
     pub fn new() -> Self {
         Self {
-            rules: HashMap::new(),
+            engine: rules::HierarchicalRuleEngine::new(),
         }
     }
 
+    /// Register (or replace) a rule
     pub fn register_rule(&mut self, rule: Rule) {
-        self.rules.insert(rule.id.clone(), rule);
+        let hrule = rules::HierarchicalRule {
+            rule,
+            level: rules::RuleLevel::Core,
+            overrides: None,
+            source: rules::RuleSource::Builtin,
+            enabled: true,
+        };
+        self.engine.register_rule(hrule).expect("flat rules have no overrides");
     }
 
     pub fn get_rules_for_program(&self, program_type: &str) -> Vec<&Rule> {
-        self.rules
-            .values()
+        self.engine
+            .get_rules_by_level(rules::RuleLevel::Core)
+            .into_iter()
+            .map(|hrule| &hrule.rule)
             .filter(|r| r.program_type == program_type)
             .collect()
     }
 
-    pub fn validate(&self, program_type: &str, _data: &HashMap<String, String>) -> Vec<String> {
-        let errors = Vec::new();
-        for _rule in self.get_rules_for_program(program_type) {
-            // Rule validation logic would go here
-        }
-        errors
+    /// Error-severity violations as `rule_id: message (detail)` lines
+    pub fn validate(&self, program_type: &str, data: &HashMap<String, String>) -> Vec<String> {
+        self.engine
+            .validate(program_type, data)
+            .errors
+            .into_iter()
+            .map(|message| match message.detail {
+                Some(detail) => format!("{}: {} ({})", message.rule_id, message.message, detail),
+                None => format!("{}: {}", message.rule_id, message.message),
+            })
+            .collect()
     }
 }
 
@@ -207,4 +229,29 @@ mod tests {
         registry.register(module.clone());
         assert!(registry.get("test_module").is_some());
     }
+
+    #[test]
+    fn test_rule_engine_reports_errors() {
+        let mut engine = RuleEngine::new();
+        engine.register_rule(Rule {
+            id: "cad_depth".to_string(),
+            program_type: "cad".to_string(),
+            category: RuleCategory::Constraint,
+            conditions: vec![Condition {
+                check_type: "max_value:depth:100".to_string(),
+                severity: Severity::Error,
+                message: "Extrusion too deep".to_string(),
+            }],
+        });
+
+        let too_deep = HashMap::from([("depth".to_string(), "120".to_string())]);
+        assert_eq!(
+            engine.validate("cad", &too_deep),
+            vec!["cad_depth: Extrusion too deep (Field 'depth' is 120 (max 100))"]
+        );
+        assert!(engine.validate("engine", &too_deep).is_empty());
+
+        let shallow = HashMap::from([("depth".to_string(), "80".to_string())]);
+        assert!(engine.validate("cad", &shallow).is_empty());
+    }
 }
@@ -1,5 +1,10 @@
 use std::collections::HashMap;
-use crate::parser::Instruction;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::executor::InstructionRegistry;
+use crate::expansion::{instruction_source_len, ExpansionBudget, ExpansionError, ExpansionMechanism, ExpansionTracker};
+use crate::parser::{Instruction, InstructionParser, NativeParser, ParseError};
 
 /// Represents a defined macro in OASM
 #[derive(Debug, Clone)]
@@ -33,36 +38,436 @@ impl MacroRegistry {
 /// Processor responsible for expanding macros before execution
 pub struct MacroProcessor {
     registry: MacroRegistry,
+    budget: ExpansionBudget,
+    /// Resolves aliased mnemonics in the output, within the same budget
+    aliases: Option<Arc<InstructionRegistry>>,
 }
 
 impl MacroProcessor {
     pub fn new(registry: MacroRegistry) -> Self {
-        Self { registry }
+        Self { registry, budget: ExpansionBudget::default(), aliases: None }
     }
 
-    /// Expands a list of instructions, replacing macro calls with their definitions
-    pub fn expand(&self, instructions: Vec<Instruction>) -> Vec<Instruction> {
+    pub fn with_budget(mut self, budget: ExpansionBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn with_aliases(mut self, instructions: Arc<InstructionRegistry>) -> Self {
+        self.aliases = Some(instructions);
+        self
+    }
+
+    /// Expands a list of instructions, replacing macro calls with their
+    /// definitions (recursively, within the processor's budget)
+    pub fn expand(&self, instructions: Vec<Instruction>) -> Result<Vec<Instruction>, ExpansionError> {
+        let mut tracker = ExpansionTracker::new(self.budget);
         let mut expanded = Vec::new();
+        self.expand_into(&instructions, None, &mut tracker, &mut expanded)?;
+        Ok(expanded)
+    }
 
+    /// Expand with a tracker shared with other mechanisms (templates, aliases)
+    pub fn expand_with(
+        &self,
+        instructions: &[Instruction],
+        tracker: &mut ExpansionTracker,
+    ) -> Result<Vec<Instruction>, ExpansionError> {
+        let mut expanded = Vec::new();
+        self.expand_into(instructions, None, tracker, &mut expanded)?;
+        Ok(expanded)
+    }
+
+    fn expand_into(
+        &self,
+        instructions: &[Instruction],
+        call_line: Option<usize>,
+        tracker: &mut ExpansionTracker,
+        expanded: &mut Vec<Instruction>,
+    ) -> Result<(), ExpansionError> {
         for instr in instructions {
+            // Expanded instructions report the line of the outermost call
+            let line_number = call_line.unwrap_or(instr.line_number);
+
             if let Some(m) = self.registry.get(&instr.mnemonic) {
-                // If the mnemonic matches a macro, expand it
-                // For now, we do simple replacement (ignoring params for this basic version)
-                for mut macro_instr in m.instructions.clone() {
-                    macro_instr.line_number = instr.line_number; // Keep context
-                    expanded.push(macro_instr);
-                }
+                // Simple replacement (parameters are not substituted yet)
+                tracker.enter(ExpansionMechanism::Macro, &m.name)?;
+                self.expand_into(&m.instructions, Some(line_number), tracker, expanded)?;
+                tracker.exit();
             } else {
+                tracker.charge(1, instruction_source_len(instr))?;
+                let mut instr = instr.clone();
+                instr.line_number = line_number;
+                if let Some(aliases) = &self.aliases {
+                    instr.mnemonic = aliases.canonical_within(&instr.mnemonic, tracker)?;
+                }
                 expanded.push(instr);
             }
         }
 
-        expanded
+        Ok(())
+    }
+}
+
+/// Directive that inlines another script: `#include "parts/gear.oasm"`.
+/// The parser treats `#` lines as comments, so includes are resolved on the
+/// source text before parsing.
+pub const INCLUDE_DIRECTIVE: &str = "#include";
+
+/// File and line a line of the combined source came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceOrigin {
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// Maps lines of a combined source back to the files they came from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    origins: Vec<SourceOrigin>,
+}
+
+impl SourceMap {
+    /// Origin of a 1-based line of the combined source (e.g. an
+    /// `Instruction::line_number` or a `ParseError` line)
+    pub fn locate(&self, line: usize) -> Option<&SourceOrigin> {
+        line.checked_sub(1).and_then(|index| self.origins.get(index))
     }
 }
 
+/// A script with its includes inlined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludedSource {
+    pub source: String,
+    pub source_map: SourceMap,
+}
+
+/// An `#include` that could not be resolved; `file` and `line` locate the directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncludeError {
+    /// The root script could not be read
+    Unreadable { file: PathBuf, reason: String },
+    Missing { file: PathBuf, line: usize, target: PathBuf },
+    /// Including `target` would re-enter a file already being included;
+    /// `chain` is the include stack, outermost first
+    Circular { file: PathBuf, line: usize, target: PathBuf, chain: Vec<PathBuf> },
+    Malformed { file: PathBuf, line: usize, directive: String },
+    /// Includes nested too deeply or inlined too much source
+    Expansion(ExpansionError),
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncludeError::Unreadable { file, reason } => write!(f, "Cannot read {}: {}", file.display(), reason),
+            IncludeError::Missing { file, line, target } => {
+                write!(f, "{}:{}: included file {} not found", file.display(), line, target.display())
+            }
+            IncludeError::Circular { file, line, target, chain } => write!(
+                f,
+                "{}:{}: circular include of {} ({})",
+                file.display(),
+                line,
+                target.display(),
+                chain.iter().chain([target]).map(|p| p.display().to_string()).collect::<Vec<_>>().join(" -> ")
+            ),
+            IncludeError::Malformed { file, line, directive } => {
+                write!(f, "{}:{}: expected #include \"path\", found '{}'", file.display(), line, directive)
+            }
+            IncludeError::Expansion(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for IncludeError {}
+
+/// Read `path` and inline its `#include` directives recursively. Relative
+/// include paths resolve against the including file's directory. A file may
+/// be included more than once, but not from inside itself. Nesting and the
+/// inlined source size are bounded by the default expansion budget.
+pub fn resolve_includes(path: &Path) -> Result<IncludedSource, IncludeError> {
+    resolve_includes_with(path, &mut ExpansionTracker::new(ExpansionBudget::default()))
+}
+
+/// `resolve_includes`, charging each file against `tracker`: every file is
+/// one level of depth, and its bytes count towards the source size limit
+pub fn resolve_includes_with(path: &Path, tracker: &mut ExpansionTracker) -> Result<IncludedSource, IncludeError> {
+    let root = path.canonicalize().map_err(|e| IncludeError::Unreadable { file: path.to_path_buf(), reason: e.to_string() })?;
+    let mut included = IncludedSource { source: String::new(), source_map: SourceMap::default() };
+    let mut stack = Vec::new();
+    include_into(&root, &mut stack, tracker, &mut included)?;
+    Ok(included)
+}
+
+fn include_into(
+    file: &Path,
+    stack: &mut Vec<PathBuf>,
+    tracker: &mut ExpansionTracker,
+    out: &mut IncludedSource,
+) -> Result<(), IncludeError> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| IncludeError::Unreadable { file: file.to_path_buf(), reason: e.to_string() })?;
+    tracker.enter(ExpansionMechanism::Include, &file.display().to_string()).map_err(IncludeError::Expansion)?;
+    tracker.charge(0, content.len()).map_err(IncludeError::Expansion)?;
+    stack.push(file.to_path_buf());
+
+    for (index, text) in content.lines().enumerate() {
+        let line = index + 1;
+        // `#includes ...` is an ordinary comment
+        let directive = text.trim().strip_prefix(INCLUDE_DIRECTIVE).filter(|r| r.is_empty() || r.starts_with(char::is_whitespace));
+        let Some(rest) = directive else {
+            out.source.push_str(text);
+            out.source.push('\n');
+            out.source_map.origins.push(SourceOrigin { file: file.to_path_buf(), line });
+            continue;
+        };
+
+        let target = rest
+            .trim()
+            .strip_prefix('"')
+            .and_then(|r| r.strip_suffix('"'))
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| IncludeError::Malformed { file: file.to_path_buf(), line, directive: text.trim().to_string() })?;
+        let target = file.parent().unwrap_or(Path::new(".")).join(target);
+        let resolved = target.canonicalize().map_err(|_| IncludeError::Missing {
+            file: file.to_path_buf(),
+            line,
+            target: target.clone(),
+        })?;
+
+        if stack.contains(&resolved) {
+            return Err(IncludeError::Circular { file: file.to_path_buf(), line, target: resolved, chain: stack.clone() });
+        }
+        include_into(&resolved, stack, tracker, out)?;
+    }
+
+    stack.pop();
+    tracker.exit();
+    Ok(())
+}
+
+/// A script file parsed with its includes inlined. Instruction line
+/// numbers are lines of the combined source; `source_map` locates them.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub instructions: Vec<Instruction>,
+    pub source_map: SourceMap,
+}
+
+/// Why `load_script` could not produce instructions
+#[derive(Debug, Clone)]
+pub enum ScriptError {
+    Include(IncludeError),
+    /// `origin` is the file and line the error was found on
+    Parse { origin: Option<SourceOrigin>, error: ParseError },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Include(error) => write!(f, "{}", error),
+            ScriptError::Parse { origin: Some(origin), error } => {
+                write!(f, "{}:{}: {}", origin.file.display(), origin.line, error.message())
+            }
+            ScriptError::Parse { origin: None, error } => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Read a script, inline its includes within `budget` and parse it
+pub fn load_script(path: &Path, budget: ExpansionBudget) -> Result<Script, ScriptError> {
+    let included = resolve_includes_with(path, &mut ExpansionTracker::new(budget)).map_err(ScriptError::Include)?;
+    let instructions = NativeParser.parse_file(&included.source).map_err(|error| ScriptError::Parse {
+        origin: included.source_map.locate(error.line()).cloned(),
+        error,
+    })?;
+    Ok(Script { instructions, source_map: included.source_map })
+}
+
 impl Default for MacroRegistry {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expansion::BudgetLimit;
+    use crate::parser::{InstructionParser, NativeParser};
+
+    fn define(registry: &mut MacroRegistry, name: &str, body: &str) {
+        registry.register(Macro {
+            name: name.to_string(),
+            parameters: Vec::new(),
+            instructions: NativeParser.parse_file(body).unwrap(),
+        });
+    }
+
+    #[test]
+    fn test_nested_macros_expand_at_call_line() {
+        let mut registry = MacroRegistry::new();
+        define(&mut registry, "INNER", "SET x = 1\nSET y = 2\n");
+        define(&mut registry, "OUTER", "INNER\nVALIDATE\n");
+
+        let program = NativeParser.parse_file("CREATE Cube\nOUTER\n").unwrap();
+        let expanded = MacroProcessor::new(registry).expand(program).unwrap();
+
+        let mnemonics: Vec<_> = expanded.iter().map(|i| (i.mnemonic.as_str(), i.line_number)).collect();
+        assert_eq!(mnemonics, vec![("CREATE", 1), ("SET", 2), ("SET", 2), ("VALIDATE", 2)]);
+    }
+
+    #[test]
+    fn test_fork_bomb_hits_depth_budget() {
+        // Each expansion invokes two copies of itself
+        let mut registry = MacroRegistry::new();
+        define(&mut registry, "BOMB", "BOMB\nBOMB\n");
+
+        let program = NativeParser.parse_file("BOMB\n").unwrap();
+        // Default budget: the first branch recurses until the depth limit
+        let err = MacroProcessor::new(registry).expand(program).unwrap_err();
+
+        let max_depth = ExpansionBudget::default().max_depth;
+        assert_eq!(err.mechanism, ExpansionMechanism::Macro);
+        assert_eq!(err.definition, "BOMB");
+        assert_eq!(err.limit, BudgetLimit::Depth(max_depth));
+        assert_eq!(err.chain, vec!["macro:BOMB".to_string(); max_depth + 1]);
+    }
+
+    #[test]
+    fn test_exponential_macros_hit_instruction_budget() {
+        // L0 -> 2x L1 -> ... -> 2^12 leaf instructions
+        let mut registry = MacroRegistry::new();
+        for level in 0..12 {
+            define(&mut registry, &format!("L{}", level), &format!("L{0}\nL{0}\n", level + 1));
+        }
+        define(&mut registry, "L12", "SET x = 1\n");
+
+        let program = NativeParser.parse_file("L0\n").unwrap();
+        let budget = ExpansionBudget { max_instructions: 1000, ..ExpansionBudget::default() };
+        let err = MacroProcessor::new(registry).with_budget(budget).expand(program).unwrap_err();
+
+        assert_eq!(err.limit, BudgetLimit::Instructions(1000));
+        assert_eq!(err.definition, "L12");
+        let expected: Vec<String> = (0..=12).map(|level| format!("macro:L{}", level)).collect();
+        assert_eq!(err.chain, expected);
+    }
+
+    #[test]
+    fn test_aliases_resolve_within_budget() {
+        let macros = || {
+            let mut registry = MacroRegistry::new();
+            define(&mut registry, "PART", "BOX cube\n");
+            registry
+        };
+        let mut instructions = InstructionRegistry::default();
+        instructions.register_alias("BOX", "CREATE").unwrap();
+        let instructions = Arc::new(instructions);
+
+        let program = NativeParser.parse_file("PART\nSET x = 1\n").unwrap();
+        let expanded = MacroProcessor::new(macros()).with_aliases(instructions.clone()).expand(program.clone()).unwrap();
+        let mnemonics: Vec<_> = expanded.iter().map(|i| i.mnemonic.as_str()).collect();
+        assert_eq!(mnemonics, vec!["CREATE", "SET"]);
+
+        // The alias hop is one level below the macro that produced it
+        let budget = ExpansionBudget { max_depth: 1, ..ExpansionBudget::default() };
+        let err = MacroProcessor::new(macros()).with_budget(budget).with_aliases(instructions).expand(program).unwrap_err();
+        assert_eq!(err.mechanism, ExpansionMechanism::Alias);
+        assert_eq!(err.chain, vec!["macro:PART", "alias:BOX"]);
+    }
+
+    #[test]
+    fn test_include_inlines_files_with_source_map() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("parts")).unwrap();
+        std::fs::write(dir.path().join("main.oasm"), "CREATE gear\n#include \"parts/teeth.oasm\"\nVALIDATE gear\n#includes end here\n").unwrap();
+        // Relative to parts/, not to main.oasm
+        std::fs::write(dir.path().join("parts/teeth.oasm"), "SET teeth = 20\n  #include \"ratio.oasm\"\n").unwrap();
+        std::fs::write(dir.path().join("parts/ratio.oasm"), "SET ratio = teeth / 4\n").unwrap();
+
+        let included = resolve_includes(&dir.path().join("main.oasm")).unwrap();
+        assert_eq!(included.source, "CREATE gear\nSET teeth = 20\nSET ratio = teeth / 4\nVALIDATE gear\n#includes end here\n");
+
+        let instructions = NativeParser.parse_file(&included.source).unwrap();
+        let ratio = included.source_map.locate(instructions[2].line_number).unwrap();
+        assert!(ratio.file.ends_with("parts/ratio.oasm"));
+        assert_eq!(ratio.line, 1);
+        let validate = included.source_map.locate(instructions[3].line_number).unwrap();
+        assert!(validate.file.ends_with("main.oasm"));
+        assert_eq!(validate.line, 3);
+    }
+
+    #[test]
+    fn test_circular_include_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.oasm"), "CREATE gear\n#include \"b.oasm\"\n").unwrap();
+        std::fs::write(dir.path().join("b.oasm"), "SET x = 1\n\n#include \"a.oasm\"\n").unwrap();
+
+        match resolve_includes(&dir.path().join("a.oasm")).unwrap_err() {
+            IncludeError::Circular { file, line, target, chain } => {
+                assert!(file.ends_with("b.oasm"));
+                assert_eq!(line, 3);
+                assert!(target.ends_with("a.oasm"));
+                assert_eq!(chain.len(), 2);
+            }
+            other => panic!("Expected a circular include, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_include_names_the_directive_line() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.oasm"), "CREATE gear\nSET x = 1\n#include \"gone.oasm\"\n").unwrap();
+
+        let err = resolve_includes(&dir.path().join("main.oasm")).unwrap_err();
+        assert!(matches!(&err, IncludeError::Missing { line: 3, target, .. } if target.ends_with("gone.oasm")));
+        assert!(err.to_string().contains("main.oasm:3: included file"));
+
+        std::fs::write(dir.path().join("bad.oasm"), "#include gone.oasm\n").unwrap();
+        assert!(matches!(
+            resolve_includes(&dir.path().join("bad.oasm")),
+            Err(IncludeError::Malformed { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_include_depth_is_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        for level in 0..4 {
+            std::fs::write(dir.path().join(format!("l{}.oasm", level)), format!("SET x{0} = {0}\n#include \"l{1}.oasm\"\n", level, level + 1)).unwrap();
+        }
+        std::fs::write(dir.path().join("l4.oasm"), "SET x4 = 4\n").unwrap();
+
+        assert!(resolve_includes(&dir.path().join("l0.oasm")).is_ok());
+
+        let mut tracker = ExpansionTracker::new(ExpansionBudget { max_depth: 2, ..ExpansionBudget::default() });
+        let Err(IncludeError::Expansion(err)) = resolve_includes_with(&dir.path().join("l0.oasm"), &mut tracker) else {
+            panic!("expected the depth limit");
+        };
+        assert_eq!((err.mechanism, err.limit), (ExpansionMechanism::Include, BudgetLimit::Depth(2)));
+        assert!(err.definition.ends_with("l2.oasm"), "{}", err.definition);
+        assert_eq!(err.chain.len(), 3);
+    }
+
+    #[test]
+    fn test_load_script_locates_parse_errors_in_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.oasm"), "CREATE gear\n#include \"teeth.oasm\"\n").unwrap();
+        std::fs::write(dir.path().join("teeth.oasm"), "SET teeth = 20\nSET name = \"open\n").unwrap();
+
+        let err = load_script(&dir.path().join("main.oasm"), ExpansionBudget::default()).unwrap_err();
+        let ScriptError::Parse { origin: Some(origin), .. } = &err else { panic!("expected a parse error, got {:?}", err) };
+        assert!(origin.file.ends_with("teeth.oasm"));
+        assert_eq!(origin.line, 2);
+
+        std::fs::write(dir.path().join("teeth.oasm"), "SET teeth = 20\n").unwrap();
+        let script = load_script(&dir.path().join("main.oasm"), ExpansionBudget::default()).unwrap();
+        assert_eq!(script.instructions.len(), 2);
+        assert!(script.source_map.locate(script.instructions[1].line_number).unwrap().file.ends_with("teeth.oasm"));
+
+        let tiny = ExpansionBudget { max_source_bytes: 16, ..ExpansionBudget::default() };
+        assert!(matches!(load_script(&dir.path().join("main.oasm"), tiny), Err(ScriptError::Include(IncludeError::Expansion(_)))));
+    }
+}
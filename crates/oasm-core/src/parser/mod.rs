@@ -1,8 +1,10 @@
-/// OASM Native Parser
-/// Parses OASM's own instruction syntax (not assembly mnemonics)
+//! OASM Native Parser
+//! Parses OASM's own instruction syntax (not assembly mnemonics)
 
-use crate::types::Value;
+use crate::context::Actor;
+use crate::types::{Operation, Value};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Parsed instruction (native OASM)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -10,6 +12,27 @@ pub struct Instruction {
     pub mnemonic: String,
     pub operands: Vec<Operand>,
     pub line_number: usize,
+    /// Who authored the line, when the parser was told
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<InstructionProvenance>,
+}
+
+/// Author and source file of an instruction (per-line blame in
+/// collaborative scripts)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstructionProvenance {
+    pub actor: Actor,
+    pub source_file: Option<PathBuf>,
+}
+
+impl Instruction {
+    /// Source form of the instruction (`SET gear_0000.teeth = 24`)
+    pub fn render(&self) -> String {
+        std::iter::once(self.mnemonic.clone())
+            .chain(self.operands.iter().map(Operand::render))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
 /// Operand types
@@ -19,7 +42,26 @@ pub enum Operand {
     Literal(Value),
     Property { object: String, property: String },
     Array(Vec<Operand>),
-    Assignment { target: String, value: Box<Operand> },
+    /// `target = value`; the target is an `Identifier` (a variable) or a
+    /// `Property` (`gear.teeth`)
+    Assignment { target: Box<Operand>, value: Box<Operand> },
+    /// Binary expression on an assignment's right-hand side, e.g. `a + b * 2`
+    Expression { op: Operation, lhs: Box<Operand>, rhs: Box<Operand> },
+}
+
+impl Operand {
+    /// Source form of the operand; expressions are fully parenthesized
+    pub fn render(&self) -> String {
+        match self {
+            Operand::Identifier(name) => name.clone(),
+            Operand::Literal(Value::String(s)) => format!("\"{}\"", s),
+            Operand::Literal(value) => crate::expression::render_value(value),
+            Operand::Property { object, property } => format!("{}.{}", object, property),
+            Operand::Array(items) => format!("[{}]", items.iter().map(Operand::render).collect::<Vec<_>>().join(", ")),
+            Operand::Assignment { target, value } => format!("{} = {}", target.render(), value.render()),
+            Operand::Expression { op, lhs, rhs } => format!("({} {} {})", lhs.render(), op.symbol(), rhs.render()),
+        }
+    }
 }
 
 /// Parser trait
@@ -37,6 +79,36 @@ pub enum ParseError {
     InvalidNumber { line: usize, value: String },
 }
 
+impl ParseError {
+    /// 1-based line the error was found on
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::UnexpectedToken { line, .. }
+            | ParseError::InvalidSyntax { line, .. }
+            | ParseError::UnterminatedString { line }
+            | ParseError::InvalidNumber { line, .. } => *line,
+        }
+    }
+
+    /// What went wrong, without the line number
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::UnexpectedToken { token, .. } => format!("unexpected token '{}'", token),
+            ParseError::InvalidSyntax { message, .. } => message.clone(),
+            ParseError::UnterminatedString { .. } => "unterminated string".to_string(),
+            ParseError::InvalidNumber { value, .. } => format!("invalid number '{}'", value),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line(), self.message())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 /// Native OASM parser
 pub struct NativeParser;
 
@@ -50,7 +122,7 @@ impl InstructionParser for NativeParser {
         }
 
         // Split into tokens
-        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+        let tokens = tokenize(trimmed);
         if tokens.is_empty() {
             return Ok(None);
         }
@@ -65,6 +137,7 @@ impl InstructionParser for NativeParser {
             mnemonic,
             operands,
             line_number,
+            provenance: None,
         }))
     }
 
@@ -81,6 +154,58 @@ impl InstructionParser for NativeParser {
     }
 }
 
+impl NativeParser {
+    /// Parse a file, stamping every instruction with `provenance`
+    pub fn parse_with_provenance(
+        &self,
+        source: &str,
+        provenance: &InstructionProvenance,
+    ) -> Result<Vec<Instruction>, ParseError> {
+        let mut instructions = self.parse_file(source)?;
+        for instruction in &mut instructions {
+            instruction.provenance = Some(provenance.clone());
+        }
+        Ok(instructions)
+    }
+}
+
+/// Split on whitespace and commas, keeping double-quoted strings (with spaces
+/// or commas) as one token. Parentheses outside quotes are tokens of their
+/// own. An unterminated quote runs to the end of the line.
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                start.get_or_insert(i);
+            }
+            c if (c.is_whitespace() || c == ',') && !in_quotes => {
+                if let Some(s) = start.take() {
+                    tokens.push(&line[s..i]);
+                }
+            }
+            '(' | ')' if !in_quotes => {
+                if let Some(s) = start.take() {
+                    tokens.push(&line[s..i]);
+                }
+                tokens.push(&line[i..i + 1]);
+            }
+            _ => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&line[s..]);
+    }
+
+    tokens
+}
+
 impl NativeParser {
     fn parse_operands(&self, tokens: &[&str], line_number: usize) -> Result<Vec<Operand>, ParseError> {
         let mut operands = Vec::new();
@@ -95,29 +220,35 @@ impl NativeParser {
                 continue;
             }
 
-            // Assignment: name = value
+            // Assignment: name = value, or object.property = expression
             if i + 2 < tokens.len() && tokens[i + 1] == "=" {
-                let target = tokens[i].to_string();
-                let value = self.parse_value(tokens[i + 2], line_number)?;
+                let target = match self.parse_value(tokens[i], line_number)? {
+                    target @ (Operand::Identifier(_) | Operand::Property { .. }) => target,
+                    _ => {
+                        return Err(ParseError::InvalidSyntax {
+                            line: line_number,
+                            message: format!("cannot assign to '{}'", tokens[i]),
+                        })
+                    }
+                };
+                let (value, next) = self.parse_expression(tokens, i + 2, 0, line_number)?;
                 operands.push(Operand::Assignment {
-                    target,
+                    target: Box::new(target),
                     value: Box::new(value),
                 });
-                i += 3;
+                i = next;
                 continue;
             }
 
-            // Property access: object.property
-            if token.contains('.') {
-                let parts: Vec<&str> = token.split('.').collect();
-                if parts.len() == 2 {
-                    operands.push(Operand::Property {
-                        object: parts[0].to_string(),
-                        property: parts[1].to_string(),
-                    });
-                    i += 1;
-                    continue;
-                }
+            // Parenthesized expression
+            if token == "(" {
+                let (operand, next) = self.parse_expression(tokens, i, 0, line_number)?;
+                operands.push(operand);
+                i = next;
+                continue;
+            }
+            if token == ")" {
+                return Err(unbalanced(line_number, "')' without a matching '('"));
             }
 
             // Array: [1, 2, 3]
@@ -136,6 +267,50 @@ impl NativeParser {
         Ok(operands)
     }
 
+    /// Precedence climbing from `tokens[start]`: an operand followed by any
+    /// number of `OP operand` pairs binding at least `min_precedence`.
+    /// Returns the operand and the index of the first unused token.
+    fn parse_expression(
+        &self,
+        tokens: &[&str],
+        start: usize,
+        min_precedence: u8,
+        line_number: usize,
+    ) -> Result<(Operand, usize), ParseError> {
+        let (mut lhs, mut i) = match tokens.get(start) {
+            None => {
+                return Err(ParseError::InvalidSyntax { line: line_number, message: "expected a value".to_string() })
+            }
+            Some(&"(") => {
+                let (inner, next) = self.parse_expression(tokens, start + 1, 0, line_number)?;
+                if tokens.get(next) != Some(&")") {
+                    return Err(unbalanced(line_number, "'(' is never closed"));
+                }
+                (inner, next + 1)
+            }
+            Some(&")") => return Err(unbalanced(line_number, "')' without a matching '('")),
+            Some(token) => (self.parse_value(token, line_number)?, start + 1),
+        };
+
+        while let Some(op) = tokens.get(i).and_then(|token| Operation::from_symbol(token)) {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            if i + 1 >= tokens.len() {
+                return Err(ParseError::InvalidSyntax {
+                    line: line_number,
+                    message: format!("operator '{}' has no right-hand operand", tokens[i]),
+                });
+            }
+            let (rhs, next) = self.parse_expression(tokens, i + 1, precedence + 1, line_number)?;
+            lhs = Operand::Expression { op, lhs: Box::new(lhs), rhs: Box::new(rhs) };
+            i = next;
+        }
+
+        Ok((lhs, i))
+    }
+
     fn parse_value(&self, token: &str, line_number: usize) -> Result<Operand, ParseError> {
         // String literal
         if token.starts_with('"') {
@@ -162,15 +337,54 @@ impl NativeParser {
             return Ok(Operand::Literal(Value::F64(n)));
         }
 
+        // Property access: object.property
+        if let Some((object, property)) = token.split_once('.') {
+            if !object.is_empty() && !property.is_empty() && !property.contains('.') {
+                return Ok(Operand::Property {
+                    object: object.to_string(),
+                    property: property.to_string(),
+                });
+            }
+        }
+
         // Otherwise, it's an identifier
         Ok(Operand::Identifier(token.to_string()))
     }
 }
 
+fn unbalanced(line: usize, detail: &str) -> ParseError {
+    ParseError::InvalidSyntax { line, message: format!("unbalanced parentheses: {}", detail) }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_provenance_survives_round_trip() {
+        let provenance = InstructionProvenance {
+            actor: Actor::Human { username: "alice".to_string() },
+            source_file: Some(PathBuf::from("parts/gear.oasm")),
+        };
+        let program = NativeParser
+            .parse_with_provenance("CREATE gear\n# teeth\nSET gear_0000.teeth = 24", &provenance)
+            .unwrap();
+
+        assert_eq!(program.len(), 2);
+        assert!(program.iter().all(|i| i.provenance.as_ref() == Some(&provenance)));
+
+        let json = serde_json::to_string(&program).unwrap();
+        let decoded: Vec<Instruction> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, program);
+        assert_eq!(decoded[1].line_number, 3);
+
+        // Instructions without provenance serialize as before
+        let plain = NativeParser.parse_line("CREATE gear", 1).unwrap().unwrap();
+        assert!(!serde_json::to_string(&plain).unwrap().contains("provenance"));
+        let legacy: Instruction = serde_json::from_str(r#"{"mnemonic":"CREATE","operands":[],"line_number":1}"#).unwrap();
+        assert_eq!(legacy.provenance, None);
+    }
+
     #[test]
     fn test_parse_create() {
         let parser = NativeParser;
@@ -190,7 +404,7 @@ mod tests {
         assert_eq!(instr.operands.len(), 1);
         
         if let Operand::Assignment { target, value } = &instr.operands[0] {
-            assert_eq!(target, "teeth");
+            assert_eq!(**target, Operand::Identifier("teeth".to_string()));
             assert!(matches!(**value, Operand::Literal(Value::U32(20))));
         } else {
             panic!("Expected assignment operand");
@@ -213,6 +427,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_property_assignment() {
+        let instr = NativeParser.parse_line("SET gear.teeth = 20", 1).unwrap().unwrap();
+        assert_eq!(
+            instr.operands,
+            vec![Operand::Assignment {
+                target: Box::new(Operand::Property { object: "gear".to_string(), property: "teeth".to_string() }),
+                value: Box::new(Operand::Literal(Value::U32(20))),
+            }]
+        );
+        assert_eq!(instr.render(), "SET gear.teeth = 20");
+
+        assert!(matches!(
+            NativeParser.parse_line("SET 20 = teeth", 1),
+            Err(ParseError::InvalidSyntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_property_and_float_operands() {
+        let parser = NativeParser;
+        let instr = parser.parse_line("SET count = gear.teeth", 1).unwrap().unwrap();
+        if let Operand::Assignment { value, .. } = &instr.operands[0] {
+            assert!(matches!(&**value, Operand::Property { object, property } if object == "gear" && property == "teeth"));
+        } else {
+            panic!("Expected assignment operand");
+        }
+
+        let instr = parser.parse_line("EXTRUDE gear 2.5", 1).unwrap().unwrap();
+        assert_eq!(instr.operands[1], Operand::Literal(Value::F64(2.5)));
+    }
+
     #[test]
     fn test_parse_file() {
         let parser = NativeParser;
@@ -244,4 +490,59 @@ SET teeth = 20
         let instructions = parser.parse_file(source).unwrap();
         assert_eq!(instructions.len(), 2);
     }
+
+    #[test]
+    fn test_parse_quoted_string_with_spaces() {
+        let parser = NativeParser;
+        let instr = parser.parse_line("ASSERT teeth >= 4 \"need at least 4 teeth\"", 1).unwrap().unwrap();
+
+        assert_eq!(instr.operands.len(), 4);
+        assert_eq!(instr.operands[1], Operand::Identifier(">=".to_string()));
+        assert_eq!(instr.operands[3], Operand::Literal(Value::String("need at least 4 teeth".to_string())));
+
+        assert!(matches!(
+            parser.parse_line("ASSERT ok \"unterminated", 2),
+            Err(ParseError::UnterminatedString { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_commas_separate_operands() {
+        let instr = NativeParser.parse_line("CAST I32, ratio", 1).unwrap().unwrap();
+        assert_eq!(
+            instr.operands,
+            vec![Operand::Identifier("I32".to_string()), Operand::Identifier("ratio".to_string())]
+        );
+
+        let instr = NativeParser.parse_line("SET label = \"a, b\"", 1).unwrap().unwrap();
+        assert_eq!(instr.operands.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_expression_precedence_and_parentheses() {
+        let value = |line: &str| match NativeParser.parse_line(line, 1).unwrap().unwrap().operands.remove(0) {
+            Operand::Assignment { value, .. } => value.render(),
+            other => panic!("Expected assignment operand, got {:?}", other),
+        };
+
+        assert_eq!(value("SET x = 2 + 3 * 4"), "(2 + (3 * 4))");
+        assert_eq!(value("SET x = (2 + 3) * 4"), "((2 + 3) * 4)");
+        assert_eq!(value("SET x = 10 - 4 - 3"), "((10 - 4) - 3)");
+        assert_eq!(value("SET area = (gear.width) * height % 7"), "((gear.width * height) % 7)");
+
+        // CAST's type operand is not swallowed by the expression
+        let instr = NativeParser.parse_line("CAST ratio = teeth / 2 F32", 1).unwrap().unwrap();
+        assert_eq!(instr.operands[1], Operand::Identifier("F32".to_string()));
+
+        for (line, detail) in [
+            ("SET x = (2 + 3 * 4", "never closed"),
+            ("SET x = 2 + 3)", "without a matching"),
+            ("SET x = 2 *", "no right-hand operand"),
+        ] {
+            match NativeParser.parse_file(&format!("CREATE gear\n{}", line)) {
+                Err(ParseError::InvalidSyntax { line: 2, message }) => assert!(message.contains(detail), "{}", message),
+                other => panic!("Expected a syntax error on line 2 for '{}', got {:?}", line, other),
+            }
+        }
+    }
 }
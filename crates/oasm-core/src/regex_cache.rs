@@ -0,0 +1,211 @@
+//! Shared regex cache
+//! Compiles each pattern once (including failures) behind an LRU bound so
+//! validators, rule conditions and repair scans can match per file cheaply.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Default number of distinct patterns kept by the global cache
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// A pattern that failed to compile (cached so it is not retried)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexCacheError {
+    pub pattern: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for RegexCacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Invalid regex '{}': {}", self.pattern, self.message)
+    }
+}
+
+impl std::error::Error for RegexCacheError {}
+
+/// Counters exposed for profiling
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub compilations: u64,
+    pub compile_errors: u64,
+    pub evictions: u64,
+    pub entries: usize,
+}
+
+struct Entry {
+    compiled: Result<Arc<Regex>, RegexCacheError>,
+    last_used: u64,
+}
+
+struct Inner {
+    entries: HashMap<String, Entry>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+/// Thread-safe, LRU-bounded cache of compiled regexes keyed by pattern
+pub struct RegexCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+impl RegexCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                tick: 0,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Process-wide cache shared by validators, the rule engine and repair scans
+    pub fn global() -> &'static RegexCache {
+        static GLOBAL: OnceLock<RegexCache> = OnceLock::new();
+        GLOBAL.get_or_init(|| RegexCache::new(DEFAULT_CAPACITY))
+    }
+
+    /// Get the compiled regex for `pattern`, compiling it on first use.
+    /// Compilation happens under the lock so concurrent callers compile once.
+    pub fn get(&self, pattern: &str) -> Result<Arc<Regex>, RegexCacheError> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if let Some(entry) = inner.entries.get_mut(pattern) {
+            entry.last_used = tick;
+            let compiled = entry.compiled.clone();
+            inner.stats.hits += 1;
+            return compiled;
+        }
+
+        inner.stats.misses += 1;
+        inner.stats.compilations += 1;
+        let compiled = Regex::new(pattern).map(Arc::new).map_err(|e| RegexCacheError {
+            pattern: pattern.to_string(),
+            message: e.to_string(),
+        });
+        if compiled.is_err() {
+            inner.stats.compile_errors += 1;
+        }
+
+        if inner.entries.len() >= self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                inner.entries.remove(&key);
+                inner.stats.evictions += 1;
+            }
+        }
+
+        inner.entries.insert(
+            pattern.to_string(),
+            Entry {
+                compiled: compiled.clone(),
+                last_used: tick,
+            },
+        );
+        compiled
+    }
+
+    /// Compile-check a pattern (e.g. at rule load time), warming the cache
+    pub fn validate(&self, pattern: &str) -> Result<(), RegexCacheError> {
+        self.get(pattern).map(|_| ())
+    }
+
+    /// Match `text` against `pattern` using the cached regex
+    pub fn is_match(&self, pattern: &str, text: &str) -> Result<bool, RegexCacheError> {
+        Ok(self.get(pattern)?.is_match(text))
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            entries: inner.entries.len(),
+            ..inner.stats
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entries.clear();
+    }
+}
+
+impl Default for RegexCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_single_compilation_under_concurrency() {
+        let cache = Arc::new(RegexCache::new(8));
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        let text = format!("file_{}.rs", i);
+                        assert!(cache.is_match(r"^file_\d+\.rs$", &text).unwrap());
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.compilations, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 399);
+    }
+
+    #[test]
+    fn test_compile_errors_are_cached() {
+        let cache = RegexCache::new(8);
+
+        let first = cache.get("([unclosed").unwrap_err();
+        let second = cache.get("([unclosed").unwrap_err();
+
+        assert_eq!(first, second);
+        let stats = cache.stats();
+        assert_eq!(stats.compilations, 1);
+        assert_eq!(stats.compile_errors, 1);
+        assert_eq!(stats.hits, 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = RegexCache::new(2);
+        cache.validate("a").unwrap();
+        cache.validate("b").unwrap();
+        cache.validate("a").unwrap();
+        cache.validate("c").unwrap();
+
+        let stats = cache.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.entries, 2);
+
+        // "b" was evicted, so it compiles again; "a" is still cached
+        cache.validate("a").unwrap();
+        assert_eq!(cache.stats().compilations, 3);
+        cache.validate("b").unwrap();
+        assert_eq!(cache.stats().compilations, 4);
+    }
+}
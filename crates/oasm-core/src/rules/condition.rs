@@ -0,0 +1,169 @@
+//! Condition evaluation for rule checks
+//!
+//! `check_type` is either a named check (`type_mismatch`, `edges_connected`,
+//! `parameters_in_bounds`, ...) or a parameterised one:
+//! - `required:field`
+//! - `max_value:field:limit` / `min_value:field:limit`
+//! - `matches:field:pattern`
+//!
+//! Fields are looked up in the context's properties. A check whose field is
+//! absent passes (only `required` cares about presence); unknown named checks
+//! are skipped, while malformed parameterised ones are reported as violations.
+
+use crate::regex_cache::RegexCache;
+use crate::validators::ValidationContext;
+
+/// Interprets condition check types against a ValidationContext
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConditionEvaluator;
+
+impl ConditionEvaluator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Returns a description of the violation, or None if the check passes
+    pub fn evaluate(&self, check_type: &str, context: &ValidationContext) -> Option<String> {
+        let mut parts = check_type.splitn(3, ':');
+        let name = parts.next().unwrap_or_default();
+        let field = parts.next();
+        let argument = parts.next();
+
+        match (name, field, argument) {
+            ("required", Some(field), None) => Self::required(context, field),
+            ("max_value", Some(field), Some(limit)) => Self::bound(context, field, limit, true),
+            ("min_value", Some(field), Some(limit)) => Self::bound(context, field, limit, false),
+            ("matches", Some(field), Some(pattern)) => Self::matches(context, field, pattern),
+            (_, None, None) => Self::named(name, context),
+            _ => Some(format!("Malformed condition check '{}'", check_type)),
+        }
+    }
+
+    /// `evaluate`, plus the object the violation is on for checks that look
+    /// at individual objects (`edges_connected`)
+    pub fn evaluate_located(&self, check_type: &str, context: &ValidationContext) -> Option<(String, Option<String>)> {
+        if check_type == "edges_connected" {
+            return Self::disconnected_mesh(context)
+                .map(|obj_id| (format!("Object '{}' has disconnected edges", obj_id), Some(obj_id.to_string())));
+        }
+        self.evaluate(check_type, context).map(|detail| (detail, None))
+    }
+
+    /// First mesh (by id) with disconnected edges; CAD only
+    fn disconnected_mesh(context: &ValidationContext) -> Option<&str> {
+        if context.program_type != "cad" {
+            return None;
+        }
+        context
+            .objects
+            .iter()
+            .filter(|(_, obj)| obj.object_type == "mesh" && obj.properties.contains_key("disconnected_edges"))
+            .map(|(obj_id, _)| obj_id.as_str())
+            .min()
+    }
+
+    fn required(context: &ValidationContext, field: &str) -> Option<String> {
+        match context.properties.get(field) {
+            Some(value) if !value.trim().is_empty() => None,
+            _ => Some(format!("Required field '{}' is missing", field)),
+        }
+    }
+
+    fn bound(context: &ValidationContext, field: &str, limit: &str, is_max: bool) -> Option<String> {
+        let limit: f64 = match limit.parse() {
+            Ok(limit) => limit,
+            Err(_) => return Some(format!("Invalid limit '{}' for field '{}'", limit, field)),
+        };
+        let raw = context.properties.get(field)?;
+        let value: f64 = match raw.trim().parse() {
+            Ok(value) => value,
+            Err(_) => return Some(format!("Field '{}' is not numeric: '{}'", field, raw)),
+        };
+
+        if is_max && value > limit {
+            Some(format!("Field '{}' is {} (max {})", field, value, limit))
+        } else if !is_max && value < limit {
+            Some(format!("Field '{}' is {} (min {})", field, value, limit))
+        } else {
+            None
+        }
+    }
+
+    fn matches(context: &ValidationContext, field: &str, pattern: &str) -> Option<String> {
+        let value = context.properties.get(field)?;
+        match RegexCache::global().is_match(pattern, value) {
+            Ok(true) => None,
+            Ok(false) => Some(format!("Field '{}' ('{}') does not match /{}/", field, value, pattern)),
+            Err(e) => Some(e.to_string()),
+        }
+    }
+
+    fn named(name: &str, context: &ValidationContext) -> Option<String> {
+        match name {
+            "type_mismatch" => {
+                // Variables declared but never assigned
+                for (name, var) in &context.variables {
+                    if var.value.is_none() {
+                        return Some(format!("Variable '{}' declared but not initialized", name));
+                    }
+                }
+                // Plain data: expected_type / actual_type pair
+                match (context.properties.get("expected_type"), context.properties.get("actual_type")) {
+                    (Some(expected), Some(actual)) if expected != actual => {
+                        Some(format!("Expected type '{}', found '{}'", expected, actual))
+                    }
+                    _ => None,
+                }
+            }
+            "edges_connected" => {
+                // Mesh edges must be connected (CAD-specific)
+                Self::disconnected_mesh(context).map(|obj_id| format!("Object '{}' has disconnected edges", obj_id))
+            }
+            "parameters_in_bounds" => {
+                // `*_param` properties must be within 0..=1000
+                for (key, value) in &context.properties {
+                    if key.ends_with("_param") {
+                        if let Ok(num_val) = value.parse::<f64>() {
+                            if !(0.0..=1000.0).contains(&num_val) {
+                                return Some(format!("Parameter '{}' out of bounds: {}", key, num_val));
+                            }
+                        }
+                    }
+                }
+                None
+            }
+            // Unknown or not yet implemented (e.g. no_circular_refs) - skip
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(properties: &[(&str, &str)]) -> ValidationContext {
+        let mut context = ValidationContext::new("cad".to_string());
+        for (key, value) in properties {
+            context.properties.insert(key.to_string(), value.to_string());
+        }
+        context
+    }
+
+    #[test]
+    fn test_parameterised_checks() {
+        let evaluator = ConditionEvaluator::new();
+        let ctx = context(&[("depth", "120"), ("name", "gear_01")]);
+
+        assert!(evaluator.evaluate("max_value:depth:100", &ctx).is_some());
+        assert!(evaluator.evaluate("max_value:depth:200", &ctx).is_none());
+        assert!(evaluator.evaluate("min_value:depth:150", &ctx).is_some());
+        assert!(evaluator.evaluate("max_value:missing:1", &ctx).is_none());
+        assert!(evaluator.evaluate("required:name", &ctx).is_none());
+        assert!(evaluator.evaluate("required:owner", &ctx).is_some());
+        assert!(evaluator.evaluate("matches:name:^gear_[0-9]+$", &ctx).is_none());
+        assert!(evaluator.evaluate("matches:name:^shaft", &ctx).is_some());
+        assert!(evaluator.evaluate("parameters_in_bounds", &context(&[("radius_param", "5000")])).is_some());
+        assert!(evaluator.evaluate("type_mismatch", &context(&[("expected_type", "U32"), ("actual_type", "F64")])).is_some());
+    }
+}
@@ -1,6 +1,6 @@
-/// Rule hierarchy management and built-in rules for each level
+//! Rule hierarchy management and built-in rules for each level
 
-use super::{HierarchicalRule, RuleLevel, RuleSource};
+use super::{HierarchicalRule, HierarchicalRuleEngine, RuleEngineError, RuleLevel, RuleSource};
 use crate::{Condition, Rule, RuleCategory, Severity};
 
 /// Core-level rules (system-wide, cannot be overridden by default)
@@ -228,6 +228,16 @@ pub fn load_builtin_rules() -> Vec<HierarchicalRule> {
     rules
 }
 
+/// Engine holding the built-in rules, with overrides checked once all are in
+pub fn builtin_engine() -> Result<HierarchicalRuleEngine, RuleEngineError> {
+    let mut engine = HierarchicalRuleEngine::new();
+    for hrule in load_builtin_rules() {
+        engine.register_rule_deferred(hrule);
+    }
+    engine.finalize()?;
+    Ok(engine)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -250,5 +260,8 @@ mod tests {
     fn test_all_builtin_rules() {
         let all_rules = load_builtin_rules();
         assert!(all_rules.len() >= 9);
+
+        let engine = builtin_engine().unwrap();
+        assert_eq!(engine.get_rules_by_level(RuleLevel::Core).len(), 3);
     }
 }
@@ -1,10 +1,10 @@
-/// Rule loader - loads rules from YAML templates and project configs
+//! Rule loader - loads rules from YAML templates and project configs
 
 use super::{HierarchicalRule, RuleLevel, RuleSource};
+use crate::regex_cache::RegexCache;
 use crate::{Condition, Rule, RuleCategory, Severity};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Rule definition in YAML templates
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,38 +42,100 @@ impl RuleLoader {
         self.template_paths.push(path);
     }
 
-    /// Load rules from YAML file
-    pub fn load_from_yaml(&self, path: &PathBuf) -> Result<Vec<HierarchicalRule>, LoaderError> {
-        // TODO: Implement actual YAML loading when serde_yaml is available
-        // Check if file exists
+    /// Load rules from a YAML template file (a list of rule definitions)
+    pub fn load_from_yaml(&self, path: &Path) -> Result<Vec<HierarchicalRule>, LoaderError> {
         if !path.exists() {
-            return Err(LoaderError::FileNotFound(path.clone()));
+            return Err(LoaderError::FileNotFound(path.to_path_buf()));
         }
-        // For now, return empty vec until YAML parser is integrated
-        Ok(Vec::new())
+        let content = std::fs::read_to_string(path).map_err(|e| LoaderError::IoError(e.to_string()))?;
+
+        let values: Vec<serde_yaml::Value> = if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_yaml::from_str(&content)
+                .map_err(|e| LoaderError::ParseError(format!("{}: {}", path.display(), e)))?
+        };
+
+        let source = RuleSource::Template { path: path.display().to_string() };
+        self.create_rules(values, path, &source)
     }
 
-    /// Load project-level rules from project config
-    pub fn load_project_rules(&self, project_path: &PathBuf) -> Result<Vec<HierarchicalRule>, LoaderError> {
+    /// Load project-level rules from `oasm.project.yaml` (its `rules:` list)
+    pub fn load_project_rules(&self, project_path: &Path) -> Result<Vec<HierarchicalRule>, LoaderError> {
         let config_path = project_path.join("oasm.project.yaml");
 
         if !config_path.exists() {
             return Ok(Vec::new());
         }
+        let content = std::fs::read_to_string(&config_path).map_err(|e| LoaderError::IoError(e.to_string()))?;
+
+        let config: ProjectConfig = if content.trim().is_empty() {
+            ProjectConfig::default()
+        } else {
+            serde_yaml::from_str(&content)
+                .map_err(|e| LoaderError::ParseError(format!("{}: {}", config_path.display(), e)))?
+        };
+
+        let source = RuleSource::ProjectConfig { path: config_path.display().to_string() };
+        self.create_rules(config.rules, &config_path, &source)
+    }
+
+    /// Deserialize each entry, then build rules. Malformed entries are all
+    /// reported together in one ParseError; the first invalid value
+    /// (level, category, severity, pattern) fails with its own variant.
+    fn create_rules(
+        &self,
+        values: Vec<serde_yaml::Value>,
+        path: &Path,
+        source: &RuleSource,
+    ) -> Result<Vec<HierarchicalRule>, LoaderError> {
+        let mut definitions = Vec::new();
+        let mut failures = Vec::new();
+
+        for (index, value) in values.into_iter().enumerate() {
+            let id = value
+                .get("id")
+                .and_then(|id| id.as_str())
+                .map(|id| format!("'{}'", id))
+                .unwrap_or_else(|| format!("#{}", index));
+            match serde_yaml::from_value::<RuleDefinition>(value) {
+                Ok(def) => definitions.push(def),
+                Err(e) => failures.push(format!("rule {}: {}", id, e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(LoaderError::ParseError(format!("{}: {}", path.display(), failures.join("; "))));
+        }
 
-        // TODO: Implement actual project config loading
-        Ok(Vec::new())
+        definitions
+            .into_iter()
+            .map(|def| self.create_rule(def, source.clone()))
+            .collect()
     }
 
     /// Create a hierarchical rule from definition
     pub fn create_rule(&self, def: RuleDefinition, source: RuleSource) -> Result<HierarchicalRule, LoaderError> {
-        let level = self.parse_level(&def.level)?;
-        let category = self.parse_category(&def.category)?;
+        let level = self.parse_level(&def.level).map_err(|e| in_rule(&def.id, "level", e))?;
+        let category = self.parse_category(&def.category).map_err(|e| in_rule(&def.id, "category", e))?;
         let conditions = def.conditions
             .into_iter()
-            .map(|c| self.parse_condition(c))
+            .enumerate()
+            .map(|(i, c)| {
+                self.parse_condition(c)
+                    .map_err(|e| in_rule(&def.id, &format!("conditions[{}].severity", i), e))
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
+        // Reject bad `matches:<field>:<pattern>` regexes now rather than per evaluation
+        for condition in &conditions {
+            if let Some(pattern) = regex_pattern(&condition.check_type) {
+                RegexCache::global()
+                    .validate(pattern)
+                    .map_err(|e| LoaderError::InvalidPattern(format!("{}: {}", def.id, e)))?;
+            }
+        }
+
         Ok(HierarchicalRule {
             rule: Rule {
                 id: def.id,
@@ -126,6 +188,75 @@ impl RuleLoader {
     }
 }
 
+impl From<&HierarchicalRule> for RuleDefinition {
+    /// Inverse of `RuleLoader::create_rule`, for writing rules back to YAML
+    fn from(hrule: &HierarchicalRule) -> Self {
+        let level = match hrule.level {
+            RuleLevel::Core => "core",
+            RuleLevel::Domain => "domain",
+            RuleLevel::Project => "project",
+            RuleLevel::Session => "session",
+        };
+        let category = match hrule.rule.category {
+            RuleCategory::Validation => "validation",
+            RuleCategory::Behavior => "behavior",
+            RuleCategory::Constraint => "constraint",
+            RuleCategory::Output => "output",
+        };
+
+        Self {
+            id: hrule.rule.id.clone(),
+            program_type: hrule.rule.program_type.clone(),
+            category: category.to_string(),
+            level: level.to_string(),
+            overrides: hrule.overrides.clone(),
+            enabled: Some(hrule.enabled),
+            conditions: hrule
+                .rule
+                .conditions
+                .iter()
+                .map(|c| ConditionDefinition {
+                    check_type: c.check_type.clone(),
+                    severity: match c.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                        Severity::Info => "info",
+                    }
+                    .to_string(),
+                    message: c.message.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Project config file; only the rules are read here
+#[derive(Debug, Default, Deserialize)]
+struct ProjectConfig {
+    #[serde(default)]
+    rules: Vec<serde_yaml::Value>,
+}
+
+/// Prefix a value error with the rule id and field it came from
+fn in_rule(id: &str, field: &str, error: LoaderError) -> LoaderError {
+    let locate = |value: String| format!("{}: {} '{}'", id, field, value);
+    match error {
+        LoaderError::InvalidLevel(value) => LoaderError::InvalidLevel(locate(value)),
+        LoaderError::InvalidCategory(value) => LoaderError::InvalidCategory(locate(value)),
+        LoaderError::InvalidSeverity(value) => LoaderError::InvalidSeverity(locate(value)),
+        other => other,
+    }
+}
+
+/// Pattern part of a `matches:<field>:<pattern>` check type
+fn regex_pattern(check_type: &str) -> Option<&str> {
+    let mut parts = check_type.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("matches"), Some(_), Some(pattern)) => Some(pattern),
+        _ => None,
+    }
+}
+
 impl Default for RuleLoader {
     fn default() -> Self {
         Self::new()
@@ -140,6 +271,7 @@ pub enum LoaderError {
     InvalidLevel(String),
     InvalidCategory(String),
     InvalidSeverity(String),
+    InvalidPattern(String),
     IoError(String),
 }
 
@@ -151,6 +283,7 @@ impl std::fmt::Display for LoaderError {
             LoaderError::InvalidLevel(level) => write!(f, "Invalid rule level: {}", level),
             LoaderError::InvalidCategory(cat) => write!(f, "Invalid category: {}", cat),
             LoaderError::InvalidSeverity(sev) => write!(f, "Invalid severity: {}", sev),
+            LoaderError::InvalidPattern(msg) => write!(f, "Invalid pattern: {}", msg),
             LoaderError::IoError(msg) => write!(f, "IO error: {}", msg),
         }
     }
@@ -205,4 +338,76 @@ mod tests {
         assert_eq!(hrule.level, RuleLevel::Project);
         assert_eq!(hrule.rule.category, RuleCategory::Validation);
     }
+
+    #[test]
+    fn test_create_rule_rejects_invalid_regex() {
+        let loader = RuleLoader::new();
+        let def = RuleDefinition {
+            id: "bad_pattern".to_string(),
+            program_type: "all".to_string(),
+            category: "validation".to_string(),
+            level: "project".to_string(),
+            overrides: None,
+            enabled: None,
+            conditions: vec![ConditionDefinition {
+                check_type: "matches:name:([a-z".to_string(),
+                severity: "error".to_string(),
+                message: "Name must be lowercase".to_string(),
+            }],
+        };
+
+        let err = loader.create_rule(def, RuleSource::Builtin).unwrap_err();
+        assert!(matches!(err, LoaderError::InvalidPattern(msg) if msg.starts_with("bad_pattern")));
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rules").join(name)
+    }
+
+    #[test]
+    fn test_load_from_yaml_valid_rules() {
+        let loader = RuleLoader::new();
+        let path = fixture("valid_rules.yaml");
+        let rules = loader.load_from_yaml(&path).unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].rule.id, "cad_gear_teeth");
+        assert_eq!(rules[0].level, RuleLevel::Domain);
+        assert_eq!(rules[0].rule.conditions[0].severity, Severity::Error);
+        assert_eq!(rules[1].overrides.as_deref(), Some("cad_gear_teeth"));
+        assert!(!rules[1].enabled);
+        assert!(matches!(&rules[0].source, RuleSource::Template { path: p } if p.ends_with("valid_rules.yaml")));
+    }
+
+    #[test]
+    fn test_load_from_yaml_reports_invalid_values() {
+        let loader = RuleLoader::new();
+
+        let err = loader.load_from_yaml(&fixture("invalid_severity.yaml")).unwrap_err();
+        assert!(matches!(&err, LoaderError::InvalidSeverity(msg)
+            if msg == "cad_bad_severity: conditions[1].severity 'fatal'"), "{}", err);
+
+        let err = loader.load_from_yaml(&fixture("unknown_level.yaml")).unwrap_err();
+        assert!(matches!(&err, LoaderError::InvalidLevel(msg) if msg == "cad_bad_level: level 'galaxy'"), "{}", err);
+
+        let err = loader.load_from_yaml(&fixture("missing_field.yaml")).unwrap_err();
+        assert!(matches!(&err, LoaderError::ParseError(msg)
+            if msg.contains("rule 'cad_no_category'") && msg.contains("category")), "{}", err);
+
+        let err = loader.load_from_yaml(&fixture("does_not_exist.yaml")).unwrap_err();
+        assert!(matches!(err, LoaderError::FileNotFound(_)));
+    }
+
+    #[test]
+    fn test_load_project_rules() {
+        let loader = RuleLoader::new();
+        let rules = loader.load_project_rules(&fixture("project")).unwrap();
+
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].level, RuleLevel::Project);
+        assert!(matches!(&rules[0].source, RuleSource::ProjectConfig { path } if path.ends_with("oasm.project.yaml")));
+
+        // No config file is not an error
+        assert!(loader.load_project_rules(&fixture("")).unwrap().is_empty());
+    }
 }
@@ -1,13 +1,20 @@
-/// Hierarchical Rule Engine for OASM
-/// Implements Core → Domain → Project → Session hierarchy (most specific wins)
+//! Hierarchical Rule Engine for OASM
+//! Implements Core → Domain → Project → Session hierarchy (most specific wins)
 
+pub mod condition;
 pub mod hierarchy;
 pub mod loader;
 pub mod resolver;
 
-use crate::{Rule, RuleCategory, Severity};
+pub use condition::ConditionEvaluator;
+pub use loader::RuleDefinition;
+
+use crate::validators::suppression::{context_suppressions, SuppressedIssue, SUPPRESSED_TAG};
+use crate::validators::ValidationContext;
+use crate::{Rule, Severity};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
 /// Rule hierarchy levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -48,6 +55,8 @@ pub struct HierarchicalRuleEngine {
     rules: HashMap<String, HierarchicalRule>,
     level_index: HashMap<RuleLevel, Vec<String>>,  // Level -> Rule IDs
     program_index: HashMap<String, Vec<String>>,   // Program type -> Rule IDs
+    pending_overrides: Vec<String>,                // Deferred rule IDs awaiting finalize()
+    level_escalation: bool,                        // See apply_level_escalation
 }
 
 impl HierarchicalRuleEngine {
@@ -56,33 +65,103 @@ impl HierarchicalRuleEngine {
             rules: HashMap::new(),
             level_index: HashMap::new(),
             program_index: HashMap::new(),
+            pending_overrides: Vec::new(),
+            level_escalation: false,
+        }
+    }
+
+    /// Let core error conditions escalate domain/project warnings of the
+    /// same check_type to errors during resolution (off by default)
+    pub fn set_level_escalation(&mut self, enabled: bool) {
+        self.level_escalation = enabled;
+    }
+
+    /// Register a hierarchical rule. Its `overrides` target must already be
+    /// registered; use `register_rule_deferred` + `finalize` for bulk loads
+    /// where rules may arrive before the rules they override.
+    pub fn register_rule(&mut self, hrule: HierarchicalRule) -> Result<(), RuleEngineError> {
+        if let Some(target) = &hrule.overrides {
+            self.check_override_target(&hrule.rule.id, target)?;
         }
+        self.insert_rule(hrule);
+        Ok(())
+    }
+
+    /// Register a rule without checking its override; call `finalize` once
+    /// the batch is in
+    pub fn register_rule_deferred(&mut self, hrule: HierarchicalRule) {
+        if hrule.overrides.is_some() {
+            self.pending_overrides.push(hrule.rule.id.clone());
+        }
+        self.insert_rule(hrule);
+    }
+
+    /// Check the overrides of every deferred rule (targets exist, no cycles).
+    /// On error the rules stay registered and still pending, so the batch can
+    /// be fixed and finalized again; the error names the first problem.
+    pub fn finalize(&mut self) -> Result<(), RuleEngineError> {
+        for rule_id in &self.pending_overrides {
+            let Some(target) = self.rules.get(rule_id).and_then(|r| r.overrides.as_deref()) else {
+                continue;
+            };
+            self.check_override_target(rule_id, target)?;
+        }
+
+        let cycles = resolver::RuleResolver::default().detect_circular_overrides(&self.rules);
+        if let Some(cycle) = cycles.into_iter().next() {
+            return Err(RuleEngineError::CircularOverride { cycle });
+        }
+        self.pending_overrides.clear();
+        Ok(())
+    }
+
+    fn check_override_target(&self, rule_id: &str, target: &str) -> Result<(), RuleEngineError> {
+        if self.rules.contains_key(target) {
+            return Ok(());
+        }
+        let reason = match self.rule_not_found(target) {
+            RuleEngineError::RuleNotFound { suggestion: Some(suggestion), .. } => {
+                format!("no such rule (did you mean '{}'?)", suggestion)
+            }
+            _ => "no such rule".to_string(),
+        };
+        Err(RuleEngineError::InvalidOverride {
+            rule_id: rule_id.to_string(),
+            overrides: target.to_string(),
+            reason,
+        })
     }
 
-    /// Register a hierarchical rule
-    pub fn register_rule(&mut self, hrule: HierarchicalRule) {
+    fn insert_rule(&mut self, hrule: HierarchicalRule) {
         let rule_id = hrule.rule.id.clone();
         let level = hrule.level;
         let program_type = hrule.rule.program_type.clone();
 
+        // Re-registering an id replaces the rule; drop its old index entries
+        if self.rules.contains_key(&rule_id) {
+            for ids in self.level_index.values_mut().chain(self.program_index.values_mut()) {
+                ids.retain(|id| *id != rule_id);
+            }
+        }
+
         // Add to main registry
         self.rules.insert(rule_id.clone(), hrule);
 
         // Add to level index
         self.level_index
             .entry(level)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(rule_id.clone());
 
         // Add to program index
         self.program_index
             .entry(program_type)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(rule_id);
     }
 
     /// Get rules for a program type with hierarchy resolution
-    pub fn get_resolved_rules(&self, program_type: &str) -> Vec<&HierarchicalRule> {
+    pub fn get_resolved_rules(&self, program_type: &str) -> Vec<Cow<'_, HierarchicalRule>> {
         let mut rules = Vec::new();
         let mut overridden = HashMap::new();
 
@@ -107,12 +186,15 @@ impl HierarchicalRuleEngine {
                 .collect();
 
             // Sort by level (Session > Project > Domain > Core)
-            hrules.sort_by(|a, b| b.level.cmp(&a.level));
+            hrules.sort_by_key(|r| std::cmp::Reverse(r.level));
 
-            // Process overrides
+            // Process overrides; one pointing at a disabled (or missing)
+            // rule has nothing to replace
             for hrule in &hrules {
                 if let Some(overrides_id) = &hrule.overrides {
-                    overridden.insert(overrides_id.clone(), true);
+                    if self.rules.get(overrides_id).is_some_and(|target| target.enabled) {
+                        overridden.insert(overrides_id.clone(), true);
+                    }
                 }
             }
 
@@ -124,7 +206,47 @@ impl HierarchicalRuleEngine {
             }
         }
 
+        if self.level_escalation {
+            self.apply_level_escalation(rules)
+        } else {
+            rules.into_iter().map(Cow::Borrowed).collect()
+        }
+    }
+
+    /// Escalate Domain/Project warning conditions to errors when a resolved
+    /// Core rule has an error condition with the same check_type. Rules that
+    /// change are cloned; the registered rules are left untouched.
+    pub fn apply_level_escalation<'a>(&self, rules: Vec<&'a HierarchicalRule>) -> Vec<Cow<'a, HierarchicalRule>> {
+        let enforced: HashSet<&str> = rules
+            .iter()
+            .filter(|hr| hr.level == RuleLevel::Core)
+            .flat_map(|hr| &hr.rule.conditions)
+            .filter(|c| c.severity == Severity::Error)
+            .map(|c| c.check_type.as_str())
+            .collect();
+
+        let escalates = |hrule: &HierarchicalRule| {
+            matches!(hrule.level, RuleLevel::Domain | RuleLevel::Project)
+                && hrule.rule.conditions.iter().any(|c| {
+                    c.severity == Severity::Warning && enforced.contains(c.check_type.as_str())
+                })
+        };
+
         rules
+            .iter()
+            .map(|&hrule| {
+                if !escalates(hrule) {
+                    return Cow::Borrowed(hrule);
+                }
+                let mut escalated = hrule.clone();
+                for condition in &mut escalated.rule.conditions {
+                    if condition.severity == Severity::Warning && enforced.contains(condition.check_type.as_str()) {
+                        condition.severity = Severity::Error;
+                    }
+                }
+                Cow::Owned(escalated)
+            })
+            .collect()
     }
 
     /// Get rules by level
@@ -145,7 +267,16 @@ impl HierarchicalRuleEngine {
             hrule.enabled = false;
             Ok(())
         } else {
-            Err(RuleEngineError::RuleNotFound(rule_id.to_string()))
+            Err(self.rule_not_found(rule_id))
+        }
+    }
+
+    fn rule_not_found(&self, rule_id: &str) -> RuleEngineError {
+        let mut known: Vec<&str> = self.rules.keys().map(String::as_str).collect();
+        known.sort_unstable();
+        RuleEngineError::RuleNotFound {
+            rule_id: rule_id.to_string(),
+            suggestion: crate::text_util::closest_match(rule_id, &known, 3),
         }
     }
 
@@ -155,61 +286,196 @@ impl HierarchicalRuleEngine {
             hrule.enabled = true;
             Ok(())
         } else {
-            Err(RuleEngineError::RuleNotFound(rule_id.to_string()))
+            Err(self.rule_not_found(rule_id))
         }
     }
 
+    /// Register a temporary rule for a running session. The rule is stored
+    /// at Session level; `overrides` must name an existing non-Session rule
+    /// and must not close an override cycle.
+    pub fn add_session_rule(
+        &mut self,
+        rule: Rule,
+        session_id: &str,
+        overrides: Option<String>,
+    ) -> Result<(), RuleEngineError> {
+        if let Some(existing) = self.rules.get(&rule.id) {
+            return Err(RuleEngineError::ConflictingRules {
+                rule1: rule.id.clone(),
+                rule2: existing.rule.id.clone(),
+            });
+        }
+
+        if let Some(target_id) = &overrides {
+            let target = self.rules.get(target_id).ok_or_else(|| self.rule_not_found(target_id))?;
+            if target.level == RuleLevel::Session {
+                return Err(RuleEngineError::InvalidOverride {
+                    rule_id: rule.id.clone(),
+                    overrides: target_id.clone(),
+                    reason: "it is a Session rule".to_string(),
+                });
+            }
+        }
+
+        let hrule = HierarchicalRule {
+            rule,
+            level: RuleLevel::Session,
+            overrides,
+            source: RuleSource::UserDefined { session_id: session_id.to_string() },
+            enabled: true,
+        };
+
+        let mut candidate = self.rules.clone();
+        candidate.insert(hrule.rule.id.clone(), hrule.clone());
+        let cycles = resolver::RuleResolver::default().detect_circular_overrides(&candidate);
+        if let Some(cycle) = cycles.into_iter().find(|cycle| cycle.contains(&hrule.rule.id)) {
+            return Err(RuleEngineError::CircularOverride { cycle });
+        }
+
+        self.insert_rule(hrule);
+        Ok(())
+    }
+
+    /// Remove every rule registered by `session_id`; returns how many were removed
+    pub fn remove_session_rules(&mut self, session_id: &str) -> usize {
+        let removed: Vec<String> = self
+            .rules
+            .values()
+            .filter(|hrule| is_from_session(hrule, session_id))
+            .map(|hrule| hrule.rule.id.clone())
+            .collect();
+
+        for rule_id in &removed {
+            self.rules.remove(rule_id);
+        }
+        for ids in self.level_index.values_mut().chain(self.program_index.values_mut()) {
+            ids.retain(|id| !removed.contains(id));
+        }
+
+        removed.len()
+    }
+
+    /// Rules registered by `session_id` as definitions (sorted by id), ready
+    /// to be written to YAML and loaded back with `RuleLoader`
+    pub fn export_session_rules(&self, session_id: &str) -> Vec<RuleDefinition> {
+        let mut definitions: Vec<RuleDefinition> = self
+            .rules
+            .values()
+            .filter(|hrule| is_from_session(hrule, session_id))
+            .map(RuleDefinition::from)
+            .collect();
+        definitions.sort_by(|a, b| a.id.cmp(&b.id));
+        definitions
+    }
+
     /// Validate data against rules
     pub fn validate(
         &self,
         program_type: &str,
         data: &HashMap<String, String>,
     ) -> ValidationResult {
-        let mut errors = Vec::new();
-        let mut warnings = Vec::new();
-        let mut info = Vec::new();
+        let mut context = ValidationContext::new(program_type.to_string());
+        context.properties = data.clone();
+        self.validate_context(&context)
+    }
 
-        let rules = self.get_resolved_rules(program_type);
+    /// Validate a full context (objects, variables, properties) against the
+    /// rules resolved for its program type. Script-level suppressions and
+    /// the objects' `__suppress` entries downgrade matching messages (by rule
+    /// id or check type) to info, object-level ones only for violations on
+    /// that object; Core error rules cannot be suppressed. Malformed
+    /// `__suppress` entries are skipped here (`CombinedValidator` reports them).
+    pub fn validate_context(&self, context: &ValidationContext) -> ValidationResult {
+        let evaluator = ConditionEvaluator::new();
+        let today = chrono::Utc::now().date_naive();
+        let suppressions: Vec<_> = context_suppressions(context).into_iter().filter_map(Result::ok).collect();
+        let mut result = ValidationResult {
+            passed: true,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            info: Vec::new(),
+            suppressed: Vec::new(),
+        };
+
+        let rules = self.get_resolved_rules(&context.program_type);
 
         for hrule in rules {
             for condition in &hrule.rule.conditions {
-                // TODO: Implement actual condition checking
-                // For now, placeholder logic based on severity
-                let message = ValidationMessage {
+                let Some((detail, object_id)) = evaluator.evaluate_located(&condition.check_type, context) else {
+                    continue;
+                };
+
+                let mut message = ValidationMessage {
                     rule_id: hrule.rule.id.clone(),
                     level: hrule.level,
                     severity: condition.severity.clone(),
                     message: condition.message.clone(),
                     check_type: condition.check_type.clone(),
+                    detail: Some(detail),
                 };
 
-                match condition.severity {
-                    Severity::Error => {
-                        if data.is_empty() {
-                            errors.push(message);
-                        }
-                    }
-                    Severity::Warning => {
-                        if data.is_empty() {
-                            warnings.push(message);
-                        }
-                    }
-                    Severity::Info => {
-                        if data.is_empty() {
-                            info.push(message);
-                        }
+                let object_id = object_id.as_deref();
+                let suppression = suppressions.iter().find(|s| {
+                    s.matches(&message.rule_id, object_id) || s.matches(&message.check_type, object_id)
+                });
+                if let Some(suppression) = suppression {
+                    let note = if suppression.is_expired(today) {
+                        Some(format!(
+                            "Suppression of '{}' expired on {}",
+                            suppression.code,
+                            suppression.until.map(|d| d.to_string()).unwrap_or_default()
+                        ))
+                    } else if hrule.level == RuleLevel::Core && message.severity == Severity::Error {
+                        Some(format!("'{}' is an error-severity Core rule and cannot be suppressed", message.rule_id))
+                    } else {
+                        result.suppressed.push(SuppressedIssue {
+                            code: suppression.code.clone(),
+                            original_severity: message.severity.clone().into(),
+                            message: message.message.clone(),
+                            object_id: object_id.map(str::to_string),
+                            reason: suppression.reason.clone(),
+                            until: suppression.until,
+                        });
+                        message.severity = Severity::Info;
+                        message.message = format!("{} {}", SUPPRESSED_TAG, message.message);
+                        None
+                    };
+                    if let Some(note) = note {
+                        result.warnings.push(ValidationMessage {
+                            severity: Severity::Warning,
+                            message: note,
+                            detail: Some(suppression.reason.clone()),
+                            ..message.clone()
+                        });
                     }
                 }
+
+                match message.severity {
+                    Severity::Error => result.errors.push(message),
+                    Severity::Warning => result.warnings.push(message),
+                    Severity::Info => result.info.push(message),
+                }
             }
         }
 
-        ValidationResult {
-            passed: errors.is_empty(),
-            errors,
-            warnings,
-            info,
-        }
+        result.passed = result.errors.is_empty();
+        result
     }
+
+    /// True if `code` (a rule id or check type) belongs to an error-severity
+    /// Core rule, which suppressions may not downgrade
+    pub fn is_unsuppressible(&self, code: &str) -> bool {
+        self.get_rules_by_level(RuleLevel::Core).iter().filter(|hr| hr.enabled).any(|hr| {
+            hr.rule
+                .conditions
+                .iter()
+                .any(|c| c.severity == Severity::Error && (hr.rule.id == code || c.check_type == code))
+        })
+    }
+}
+
+fn is_from_session(hrule: &HierarchicalRule, session_id: &str) -> bool {
+    matches!(&hrule.source, RuleSource::UserDefined { session_id: id } if id == session_id)
 }
 
 impl Default for HierarchicalRuleEngine {
@@ -225,6 +491,9 @@ pub struct ValidationResult {
     pub errors: Vec<ValidationMessage>,
     pub warnings: Vec<ValidationMessage>,
     pub info: Vec<ValidationMessage>,
+    /// Messages downgraded by a suppression
+    #[serde(default)]
+    pub suppressed: Vec<SuppressedIssue>,
 }
 
 /// Validation message
@@ -235,26 +504,37 @@ pub struct ValidationMessage {
     pub severity: Severity,
     pub message: String,
     pub check_type: String,
+    /// What the condition found (e.g. "Field 'depth' is 120 (max 100)")
+    #[serde(default)]
+    pub detail: Option<String>,
 }
 
 /// Rule engine errors
 #[derive(Debug, Clone)]
 pub enum RuleEngineError {
-    RuleNotFound(String),
-    InvalidOverride { rule_id: String, overrides: String },
+    RuleNotFound { rule_id: String, suggestion: Option<String> },
+    InvalidOverride { rule_id: String, overrides: String, reason: String },
     ConflictingRules { rule1: String, rule2: String },
+    /// Override chain that loops back on itself, in override order
+    CircularOverride { cycle: Vec<String> },
 }
 
 impl std::fmt::Display for RuleEngineError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            RuleEngineError::RuleNotFound(id) => write!(f, "Rule not found: {}", id),
-            RuleEngineError::InvalidOverride { rule_id, overrides } => {
-                write!(f, "Rule {} cannot override {}", rule_id, overrides)
+            RuleEngineError::RuleNotFound { rule_id, suggestion: Some(suggestion) } => {
+                write!(f, "Rule not found: {} (did you mean '{}'?)", rule_id, suggestion)
+            }
+            RuleEngineError::RuleNotFound { rule_id, suggestion: None } => write!(f, "Rule not found: {}", rule_id),
+            RuleEngineError::InvalidOverride { rule_id, overrides, reason } => {
+                write!(f, "Rule {} cannot override {}: {}", rule_id, overrides, reason)
             }
             RuleEngineError::ConflictingRules { rule1, rule2 } => {
                 write!(f, "Conflicting rules: {} and {}", rule1, rule2)
             }
+            RuleEngineError::CircularOverride { cycle } => {
+                write!(f, "Circular override: {}", cycle.join(" -> "))
+            }
         }
     }
 }
@@ -264,6 +544,7 @@ impl std::error::Error for RuleEngineError {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::RuleCategory;
 
     #[test]
     fn test_register_and_retrieve() {
@@ -282,10 +563,14 @@ mod tests {
             enabled: true,
         };
 
-        engine.register_rule(hrule);
+        engine.register_rule(hrule).unwrap();
 
         let core_rules = engine.get_rules_by_level(RuleLevel::Core);
         assert_eq!(core_rules.len(), 1);
+
+        let err = engine.disable_rule("core_type_safty").unwrap_err();
+        assert_eq!(err.to_string(), "Rule not found: core_type_safty (did you mean 'core_type_safety'?)");
+        assert_eq!(engine.enable_rule("cad_gear").unwrap_err().to_string(), "Rule not found: cad_gear");
     }
 
     #[test]
@@ -322,11 +607,333 @@ mod tests {
             enabled: true,
         };
 
-        engine.register_rule(core_rule);
-        engine.register_rule(session_rule);
+        engine.register_rule(core_rule).unwrap();
+        engine.register_rule(session_rule).unwrap();
 
         let resolved = engine.get_resolved_rules("cad");
         assert_eq!(resolved.len(), 1);
         assert_eq!(resolved[0].rule.id, "session_max_depth");
     }
+
+    fn max_depth_rule(severity: Severity) -> HierarchicalRule {
+        HierarchicalRule {
+            rule: Rule {
+                id: "project_max_depth".to_string(),
+                program_type: "cad".to_string(),
+                category: RuleCategory::Constraint,
+                conditions: vec![crate::Condition {
+                    check_type: "max_value:depth:100".to_string(),
+                    severity,
+                    message: "Extrusion too deep".to_string(),
+                }],
+            },
+            level: RuleLevel::Project,
+            overrides: None,
+            source: RuleSource::Builtin,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_max_value_condition_follows_data() {
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule(max_depth_rule(Severity::Error)).unwrap();
+
+        let too_deep = HashMap::from([("depth".to_string(), "120".to_string())]);
+        let result = engine.validate("cad", &too_deep);
+        assert!(!result.passed);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].detail.as_deref(), Some("Field 'depth' is 120 (max 100)"));
+
+        let shallow = HashMap::from([("depth".to_string(), "80".to_string())]);
+        let result = engine.validate("cad", &shallow);
+        assert!(result.passed);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn test_warning_conditions_are_reported_as_warnings() {
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule(max_depth_rule(Severity::Warning)).unwrap();
+
+        let too_deep = HashMap::from([("depth".to_string(), "120".to_string())]);
+        let result = engine.validate("cad", &too_deep);
+        assert!(result.passed);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].rule_id, "project_max_depth");
+    }
+
+    fn session_rule(id: &str) -> Rule {
+        Rule {
+            id: id.to_string(),
+            program_type: "cad".to_string(),
+            category: RuleCategory::Constraint,
+            conditions: vec![crate::Condition {
+                check_type: "max_value:depth:50".to_string(),
+                severity: Severity::Warning,
+                message: "Session depth limit".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_session_rule_override_validation() {
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule(max_depth_rule(Severity::Error)).unwrap();
+
+        let err = engine
+            .add_session_rule(session_rule("session_depth"), "s1", Some("project_max_dept".to_string()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Rule not found: project_max_dept (did you mean 'project_max_depth'?)");
+
+        engine
+            .add_session_rule(session_rule("session_depth"), "s1", Some("project_max_depth".to_string()))
+            .unwrap();
+        let resolved = engine.get_resolved_rules("cad");
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].rule.id, "session_depth");
+
+        // Session rules cannot be override targets
+        let err = engine
+            .add_session_rule(session_rule("session_tighter"), "s1", Some("session_depth".to_string()))
+            .unwrap_err();
+        assert!(matches!(err, RuleEngineError::InvalidOverride { .. }));
+
+        // A project rule already overriding the new id would close a cycle
+        let mut pending = max_depth_rule(Severity::Error);
+        pending.rule.id = "project_pending".to_string();
+        pending.overrides = Some("session_loop".to_string());
+        engine.register_rule_deferred(pending);
+        let err = engine
+            .add_session_rule(session_rule("session_loop"), "s1", Some("project_pending".to_string()))
+            .unwrap_err();
+        assert!(matches!(err, RuleEngineError::CircularOverride { .. }));
+        assert!(err.to_string().starts_with("Circular override: "));
+        assert!(engine.get_rules_by_level(RuleLevel::Session).iter().all(|r| r.rule.id != "session_loop"));
+    }
+
+    #[test]
+    fn test_remove_session_rules() {
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule(max_depth_rule(Severity::Error)).unwrap();
+        engine
+            .add_session_rule(session_rule("session_depth"), "s1", Some("project_max_depth".to_string()))
+            .unwrap();
+        engine.add_session_rule(session_rule("session_other"), "s2", None).unwrap();
+
+        assert_eq!(engine.remove_session_rules("s1"), 1);
+        assert_eq!(engine.remove_session_rules("s1"), 0);
+
+        let ids: Vec<_> = engine.get_resolved_rules("cad").iter().map(|r| r.rule.id.clone()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"project_max_depth".to_string()));
+        assert!(ids.contains(&"session_other".to_string()));
+    }
+
+    #[test]
+    fn test_export_session_rules_round_trip() {
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule(max_depth_rule(Severity::Error)).unwrap();
+        engine
+            .add_session_rule(session_rule("session_depth"), "s1", Some("project_max_depth".to_string()))
+            .unwrap();
+
+        let exported = engine.export_session_rules("s1");
+        assert_eq!(exported.len(), 1);
+        assert!(engine.export_session_rules("s2").is_empty());
+
+        let yaml = serde_yaml::to_string(&exported).unwrap();
+        let definitions: Vec<RuleDefinition> = serde_yaml::from_str(&yaml).unwrap();
+        let source = RuleSource::UserDefined { session_id: "s1".to_string() };
+        let loaded = loader::RuleLoader::new().create_rule(definitions[0].clone(), source.clone()).unwrap();
+
+        assert_eq!(loaded.rule.id, "session_depth");
+        assert_eq!(loaded.level, RuleLevel::Session);
+        assert_eq!(loaded.overrides.as_deref(), Some("project_max_depth"));
+        assert_eq!(loaded.source, source);
+        assert_eq!(loaded.rule.category, RuleCategory::Constraint);
+        assert_eq!(loaded.rule.conditions[0].severity, Severity::Warning);
+        assert_eq!(loaded.rule.conditions[0].check_type, "max_value:depth:50");
+    }
+
+    fn overriding(id: &str, level: RuleLevel, target: &str) -> HierarchicalRule {
+        let mut hrule = max_depth_rule(Severity::Error);
+        hrule.rule.id = id.to_string();
+        hrule.level = level;
+        hrule.overrides = Some(target.to_string());
+        hrule
+    }
+
+    #[test]
+    fn test_override_typo_is_rejected() {
+        let mut engine = HierarchicalRuleEngine::new();
+        let mut core = max_depth_rule(Severity::Error);
+        core.rule.id = "core_max_depth".to_string();
+        core.level = RuleLevel::Core;
+        engine.register_rule(core).unwrap();
+
+        let err = engine
+            .register_rule(overriding("project_depth", RuleLevel::Project, "core_max_dept"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Rule project_depth cannot override core_max_dept: no such rule (did you mean 'core_max_depth'?)"
+        );
+
+        // The typo'd rule was not registered and the Core rule stays active
+        let resolved = engine.get_resolved_rules("cad");
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].rule.id, "core_max_depth");
+    }
+
+    #[test]
+    fn test_deferred_registration_finalize() {
+        // Overrider arrives before its target
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule_deferred(overriding("project_depth", RuleLevel::Project, "core_depth"));
+        let mut core = max_depth_rule(Severity::Error);
+        core.rule.id = "core_depth".to_string();
+        core.level = RuleLevel::Core;
+        engine.register_rule_deferred(core);
+        engine.finalize().unwrap();
+        assert_eq!(engine.get_resolved_rules("cad")[0].rule.id, "project_depth");
+
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule_deferred(overriding("project_depth", RuleLevel::Project, "core_missing"));
+        engine.register_rule_deferred(overriding("domain_depth", RuleLevel::Domain, "core_other"));
+        assert!(matches!(engine.finalize(), Err(RuleEngineError::InvalidOverride { .. })));
+
+        // A failed finalize keeps every pending id, including the unchecked ones
+        let mut core = max_depth_rule(Severity::Error);
+        core.rule.id = "core_missing".to_string();
+        core.level = RuleLevel::Core;
+        engine.register_rule_deferred(core);
+        match engine.finalize() {
+            Err(RuleEngineError::InvalidOverride { rule_id, .. }) => assert_eq!(rule_id, "domain_depth"),
+            other => panic!("expected InvalidOverride, got {:?}", other),
+        }
+
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule_deferred(overriding("rule_a", RuleLevel::Project, "rule_b"));
+        engine.register_rule_deferred(overriding("rule_b", RuleLevel::Domain, "rule_a"));
+        assert!(matches!(engine.finalize(), Err(RuleEngineError::CircularOverride { .. })));
+    }
+
+    #[test]
+    fn test_override_of_disabled_rule_is_skipped() {
+        let mut engine = HierarchicalRuleEngine::new();
+        let mut core = max_depth_rule(Severity::Error);
+        core.rule.id = "core_depth".to_string();
+        core.level = RuleLevel::Core;
+        engine.register_rule(core).unwrap();
+        engine.register_rule(overriding("project_depth", RuleLevel::Project, "core_depth")).unwrap();
+
+        engine.disable_rule("core_depth").unwrap();
+        let ids: Vec<_> = engine.get_resolved_rules("cad").iter().map(|r| r.rule.id.clone()).collect();
+        assert_eq!(ids, vec!["project_depth"]);
+
+        // Disabling the overrider brings the target back
+        engine.enable_rule("core_depth").unwrap();
+        engine.disable_rule("project_depth").unwrap();
+        let ids: Vec<_> = engine.get_resolved_rules("cad").iter().map(|r| r.rule.id.clone()).collect();
+        assert_eq!(ids, vec!["core_depth"]);
+    }
+
+    #[test]
+    fn test_core_error_escalates_domain_warning() {
+        let mut engine = HierarchicalRuleEngine::new();
+        let mut core = max_depth_rule(Severity::Error);
+        core.rule.id = "core_depth".to_string();
+        core.level = RuleLevel::Core;
+        let mut domain = max_depth_rule(Severity::Warning);
+        domain.rule.id = "domain_depth".to_string();
+        domain.level = RuleLevel::Domain;
+        engine.register_rule(core).unwrap();
+        engine.register_rule(domain).unwrap();
+
+        let too_deep = HashMap::from([("depth".to_string(), "120".to_string())]);
+        let result = engine.validate("cad", &too_deep);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.warnings[0].rule_id, "domain_depth");
+
+        engine.set_level_escalation(true);
+        let result = engine.validate("cad", &too_deep);
+        assert!(result.warnings.is_empty());
+        let ids: Vec<_> = result.errors.iter().map(|e| e.rule_id.as_str()).collect();
+        assert_eq!(ids, vec!["domain_depth", "core_depth"]);
+
+        // The registered rule keeps its own severity
+        assert_eq!(engine.rules["domain_depth"].rule.conditions[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_suppressions_in_rule_engine() {
+        use crate::validators::suppression::Suppression;
+
+        let mut engine = HierarchicalRuleEngine::new();
+        engine.register_rule(max_depth_rule(Severity::Warning)).unwrap();
+        let mut core = max_depth_rule(Severity::Error);
+        core.rule.id = "core_depth".to_string();
+        core.rule.conditions[0].check_type = "max_value:depth:500".to_string();
+        core.level = RuleLevel::Core;
+        engine.register_rule(core).unwrap();
+
+        let mut context = ValidationContext::new("cad".to_string());
+        context.properties.insert("depth".to_string(), "600".to_string());
+        context.suppressions = vec![
+            Suppression::new("project_max_depth", "deep pocket is intended"),
+            Suppression::new("core_depth", "also intended"),
+        ];
+        let result = engine.validate_context(&context);
+
+        assert_eq!(result.info.len(), 1);
+        assert!(result.info[0].message.starts_with("[suppressed]"));
+        assert_eq!(result.suppressed[0].code, "project_max_depth");
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.warnings[0].message.contains("cannot be suppressed"));
+
+        context.suppressions =
+            vec![Suppression::new("project_max_depth", "deep pocket").with_until(chrono::NaiveDate::MIN)];
+        let result = engine.validate_context(&context);
+        assert!(result.suppressed.is_empty());
+        assert!(result.warnings.iter().any(|w| w.message.contains("expired on")));
+    }
+
+    #[test]
+    fn test_object_suppressions_in_rule_engine() {
+        use crate::context::Object;
+        use crate::types::Value;
+        use crate::validators::suppression::SUPPRESS_PROPERTY;
+
+        let mut engine = HierarchicalRuleEngine::new();
+        let mut edges = max_depth_rule(Severity::Warning);
+        edges.rule.id = "project_edges".to_string();
+        edges.rule.conditions[0].check_type = "edges_connected".to_string();
+        engine.register_rule(edges).unwrap();
+
+        let mesh = |id: &str, suppress: Option<&str>| {
+            let mut object = Object {
+                id: id.to_string(),
+                object_type: "mesh".to_string(),
+                properties: HashMap::new(),
+                created: chrono::Utc::now(),
+            };
+            object.properties.insert("disconnected_edges".to_string(), Value::I64(2));
+            if let Some(spec) = suppress {
+                object.properties.insert(SUPPRESS_PROPERTY.to_string(), Value::String(spec.to_string()));
+            }
+            object
+        };
+        let mut context = ValidationContext::new("cad".to_string());
+        context.objects.insert("lid".to_string(), mesh("lid", Some(r#"edges_connected "open lid is intentional""#)));
+        let result = engine.validate_context(&context);
+        assert!(result.warnings.is_empty());
+        assert_eq!(result.suppressed[0].object_id.as_deref(), Some("lid"));
+
+        // Another object's suppression does not cover this one
+        context.objects.insert("base".to_string(), mesh("base", None));
+        let result = engine.validate_context(&context);
+        assert!(result.suppressed.is_empty());
+        assert_eq!(result.warnings[0].detail.as_deref(), Some("Object 'base' has disconnected edges"));
+    }
 }
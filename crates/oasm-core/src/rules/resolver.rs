@@ -1,14 +1,19 @@
-/// Rule resolver - resolves rule conflicts and applies hierarchy
+//! Rule resolver - resolves rule conflicts and applies hierarchy
 
-use super::{HierarchicalRule, RuleLevel, ValidationMessage, ValidationResult};
+use super::{HierarchicalRule, RuleSource, ValidationMessage, ValidationResult};
+use crate::context::Confidence;
 use crate::Severity;
 use std::collections::HashMap;
 
 /// Rule resolver
 pub struct RuleResolver {
     conflict_strategy: ConflictStrategy,
+    confidence_threshold: f64,
 }
 
+/// Confidence an automated override needs by default (see `resolve_with_confidence`)
+pub const DEFAULT_OVERRIDE_CONFIDENCE: f64 = 0.8;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ConflictStrategy {
     MostSpecificWins,  // Default: Session > Project > Domain > Core
@@ -20,13 +25,47 @@ impl RuleResolver {
     pub fn new(strategy: ConflictStrategy) -> Self {
         Self {
             conflict_strategy: strategy,
+            confidence_threshold: DEFAULT_OVERRIDE_CONFIDENCE,
         }
     }
 
+    /// Confidence below which user-defined overrides are held back
+    pub fn with_confidence_threshold(mut self, threshold: f64) -> Self {
+        self.confidence_threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Resolve conflicts for rules proposed by an actor with the given
+    /// confidence. A user-defined (session) rule that would override a
+    /// lower-level rule only takes part if the confidence reaches the
+    /// threshold; otherwise it is dropped and the rule it would have
+    /// relaxed stays in force. Rules that override nothing are unaffected.
+    pub fn resolve_with_confidence<'a>(
+        &self,
+        rules: &[&'a HierarchicalRule],
+        actor_confidence: Confidence,
+    ) -> Vec<&'a HierarchicalRule> {
+        let trusted = actor_confidence.exceeds_threshold(self.confidence_threshold);
+        let admitted: Vec<&'a HierarchicalRule> = rules
+            .iter()
+            .copied()
+            .filter(|&rule| {
+                let gated = matches!(rule.source, RuleSource::UserDefined { .. });
+                let base_id = self.get_base_id(&rule.rule.id);
+                let overrides_lower = rules
+                    .iter()
+                    .any(|other| other.level < rule.level && self.get_base_id(&other.rule.id) == base_id);
+                !(gated && overrides_lower && !trusted)
+            })
+            .collect();
+
+        self.resolve_conflicts(&admitted)
+    }
+
     /// Resolve conflicts between rules at different levels
     pub fn resolve_conflicts<'a>(
         &self,
-        rules: &'a [&'a HierarchicalRule],
+        rules: &[&'a HierarchicalRule],
     ) -> Vec<&'a HierarchicalRule> {
         match self.conflict_strategy {
             ConflictStrategy::MostSpecificWins => self.resolve_most_specific(rules),
@@ -37,14 +76,14 @@ impl RuleResolver {
 
     fn resolve_most_specific<'a>(
         &self,
-        rules: &'a [&'a HierarchicalRule],
+        rules: &[&'a HierarchicalRule],
     ) -> Vec<&'a HierarchicalRule> {
         let mut by_id: HashMap<String, Vec<&'a HierarchicalRule>> = HashMap::new();
 
         // Group rules by ID (base ID, ignoring level prefix)
         for &rule in rules {
             let base_id = self.get_base_id(&rule.rule.id);
-            by_id.entry(base_id).or_insert_with(Vec::new).push(rule);
+            by_id.entry(base_id).or_default().push(rule);
         }
 
         // For each group, select the most specific (highest level)
@@ -54,7 +93,7 @@ impl RuleResolver {
                 resolved.push(group[0]);
             } else {
                 // Sort by level (Session > Project > Domain > Core)
-                group.sort_by(|a, b| b.level.cmp(&a.level));
+                group.sort_by_key(|r| std::cmp::Reverse(r.level));
                 resolved.push(group[0]);
             }
         }
@@ -64,13 +103,13 @@ impl RuleResolver {
 
     fn resolve_most_restrictive<'a>(
         &self,
-        rules: &'a [&'a HierarchicalRule],
+        rules: &[&'a HierarchicalRule],
     ) -> Vec<&'a HierarchicalRule> {
         let mut by_id: HashMap<String, Vec<&'a HierarchicalRule>> = HashMap::new();
 
         for &rule in rules {
             let base_id = self.get_base_id(&rule.rule.id);
-            by_id.entry(base_id).or_insert_with(Vec::new).push(rule);
+            by_id.entry(base_id).or_default().push(rule);
         }
 
         let mut resolved = Vec::new();
@@ -98,7 +137,7 @@ impl RuleResolver {
 
     fn resolve_merge<'a>(
         &self,
-        rules: &'a [&'a HierarchicalRule],
+        rules: &[&'a HierarchicalRule],
     ) -> Vec<&'a HierarchicalRule> {
         // For merge strategy, return all rules
         // Actual merging happens at validation time
@@ -191,6 +230,7 @@ impl RuleResolver {
             errors: Vec::new(),
             warnings: Vec::new(),
             info: Vec::new(),
+            suppressed: Vec::new(),
         };
 
         for result in results {
@@ -200,6 +240,7 @@ impl RuleResolver {
             merged.errors.extend(result.errors);
             merged.warnings.extend(result.warnings);
             merged.info.extend(result.info);
+            merged.suppressed.extend(result.suppressed);
         }
 
         // Deduplicate messages
@@ -216,8 +257,8 @@ impl RuleResolver {
 
         for msg in messages {
             let key = format!("{}:{}", msg.rule_id, msg.check_type);
-            if !seen.contains_key(&key) {
-                seen.insert(key, true);
+            if let std::collections::hash_map::Entry::Vacant(e) = seen.entry(key) {
+                e.insert(true);
                 deduped.push(msg);
             }
         }
@@ -235,7 +276,7 @@ impl Default for RuleResolver {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::rules::{RuleSource};
+    use crate::rules::{RuleLevel, RuleSource};
     use crate::{Rule, RuleCategory};
 
     #[test]
@@ -309,6 +350,44 @@ mod tests {
         assert!(resolved.iter().any(|r| r.rule.id == "core_type_safety"));
     }
 
+    #[test]
+    fn test_low_confidence_override_is_held_back() {
+        let rule = |id: &str, level, source| HierarchicalRule {
+            rule: Rule {
+                id: id.to_string(),
+                program_type: "cad".to_string(),
+                category: RuleCategory::Constraint,
+                conditions: vec![],
+            },
+            level,
+            overrides: (level == RuleLevel::Session).then(|| "core_max_depth".to_string()),
+            source,
+            enabled: true,
+        };
+        let core_rule = rule("core_max_depth", RuleLevel::Core, RuleSource::Builtin);
+        let session_rule = rule(
+            "session_max_depth",
+            RuleLevel::Session,
+            RuleSource::UserDefined { session_id: "ai".to_string() },
+        );
+        let rules = vec![&core_rule, &session_rule];
+        let resolver = RuleResolver::default();
+
+        let resolved = resolver.resolve_with_confidence(&rules, Confidence::new(0.4));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].rule.id, "core_max_depth");
+
+        let resolved = resolver.resolve_with_confidence(&rules, Confidence::new(0.95));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].rule.id, "session_max_depth");
+
+        // The threshold is configurable, and a rule overriding nothing is never held back
+        let lenient = RuleResolver::default().with_confidence_threshold(0.3);
+        assert_eq!(lenient.resolve_with_confidence(&rules, Confidence::new(0.4))[0].rule.id, "session_max_depth");
+        let alone = resolver.resolve_with_confidence(&[&session_rule], Confidence::new(0.1));
+        assert_eq!(alone.len(), 1);
+    }
+
     #[test]
     fn test_circular_override_detection() {
         let resolver = RuleResolver::default();
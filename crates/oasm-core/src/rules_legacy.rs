@@ -1,8 +1,14 @@
-/// Rule system for validation and behavior
+//! Rule system for validation and behavior
+//!
+//! DEPRECATED: flat per-program rule sets from before the hierarchical
+//! engine. `RuleEngine` already evaluates through `HierarchicalRuleEngine`;
+//! new code should use `rules::hierarchy::builtin_engine` directly. This
+//! module will be removed once nothing calls it.
 
 use crate::{Rule, RuleCategory, Condition, Severity, RuleEngine};
 
 /// Load rules for a specific program type
+#[deprecated(note = "use rules::hierarchy::get_domain_rules / builtin_engine")]
 pub fn load_rules_for_program(program_type: &str) -> Vec<Rule> {
     match program_type {
         "cad" => vec![
@@ -74,6 +80,8 @@ pub fn load_rules_for_program(program_type: &str) -> Vec<Rule> {
 }
 
 /// Initialize rule engine with all rules
+#[deprecated(note = "use rules::hierarchy::builtin_engine")]
+#[allow(deprecated)]
 pub fn init_rule_engine(engine: &mut RuleEngine) {
     for program_type in &["cad", "engine", "document", "compression", "debug"] {
         for rule in load_rules_for_program(program_type) {
@@ -83,6 +91,7 @@ pub fn init_rule_engine(engine: &mut RuleEngine) {
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
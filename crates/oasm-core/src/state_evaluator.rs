@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
 
 /// Represents the overall 'compilable' state of the OASM environment
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,14 +43,186 @@ impl CompilableState {
     }
 }
 
-/// Aggregates state from various OASM components
+impl Default for CompilableState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Language of the project whose state is checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    CPlusPlus,
+    Python,
+    Rust,
+    Go,
+    JavaScript,
+}
+
+/// How a check command reports problems
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticFormat {
+    /// `cargo --message-format=json`: one JSON message per stdout line
+    CargoJson,
+    /// Exit status decides; stderr lines are the errors
+    ExitStatus,
+}
+
+/// Command run in the working directory to decide whether it compiles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub format: DiagnosticFormat,
+}
+
+impl CheckCommand {
+    pub fn new(program: &str, args: &[&str], format: DiagnosticFormat) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            format,
+        }
+    }
+
+    /// Default check for a language, if there is a standard one
+    pub fn default_for(language: Language) -> Option<Self> {
+        match language {
+            Language::Rust => Some(Self::new("cargo", &["check", "--message-format=json"], DiagnosticFormat::CargoJson)),
+            Language::Python => Some(Self::new("python3", &["-m", "compileall", "-q", "."], DiagnosticFormat::ExitStatus)),
+            Language::Go => Some(Self::new("go", &["build", "./..."], DiagnosticFormat::ExitStatus)),
+            Language::CPlusPlus | Language::JavaScript => None,
+        }
+    }
+
+    fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Whether a working directory compiles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateReport {
+    pub compilable: bool,
+    /// Compiler errors in the order reported
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Hash of the tracked files the report was computed for
+    pub content_hash: String,
+    /// True when served from the cache instead of running the check
+    pub cached: bool,
+}
+
+impl StateReport {
+    pub fn first_error(&self) -> Option<&str> {
+        self.errors.first().map(String::as_str)
+    }
+}
+
+/// Why the state of a working directory could not be determined
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateError {
+    Io { path: PathBuf, reason: String },
+    /// No check command configured (or known) for the language
+    NoCheckCommand(Language),
+    /// The check command could not be started
+    CommandFailed { command: String, reason: String },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Io { path, reason } => write!(f, "Cannot read {}: {}", path.display(), reason),
+            StateError::NoCheckCommand(language) => write!(f, "No check command configured for {:?}", language),
+            StateError::CommandFailed { command, reason } => write!(f, "Cannot run '{}': {}", command, reason),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Directories never included in the content hash (build output, VCS data)
+const UNTRACKED_DIRS: &[&str] = &["target", ".git", "node_modules", "__pycache__", "build", "dist"];
+
+/// Aggregates state from various OASM components. `is_compilable` runs the
+/// language's check command and caches the report per directory until a
+/// tracked file changes.
 pub struct StateEvaluator {
-    // In a real scenario, this might hold references to registries or databases
+    language: Language,
+    commands: HashMap<Language, CheckCommand>,
+    /// Last report per directory, keyed by canonical path
+    cache: Mutex<HashMap<PathBuf, StateReport>>,
 }
 
 impl StateEvaluator {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            language: Language::Rust,
+            commands: HashMap::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Override the check command for a language
+    pub fn with_command(mut self, language: Language, command: CheckCommand) -> Self {
+        self.commands.insert(language, command);
+        self
+    }
+
+    /// Check whether `dir` compiles. The check is skipped when the tracked
+    /// files hash the same as for the last report on `dir`.
+    pub fn is_compilable(&self, dir: &Path) -> Result<StateReport, StateError> {
+        let io_error = |path: &Path, e: std::io::Error| StateError::Io { path: path.to_path_buf(), reason: e.to_string() };
+
+        let dir = dir.canonicalize().map_err(|e| io_error(dir, e))?;
+        let hash = content_hash(&dir).map_err(|e| io_error(&dir, e))?;
+        if let Some(report) = self.cache.lock().unwrap().get(&dir).filter(|r| r.content_hash == hash) {
+            return Ok(StateReport { cached: true, ..report.clone() });
+        }
+
+        let command = self
+            .commands
+            .get(&self.language)
+            .cloned()
+            .or_else(|| CheckCommand::default_for(self.language))
+            .ok_or(StateError::NoCheckCommand(self.language))?;
+        let output = Command::new(&command.program)
+            .args(&command.args)
+            .current_dir(&dir)
+            .output()
+            .map_err(|e| StateError::CommandFailed { command: command.display(), reason: e.to_string() })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (mut errors, warnings) = match command.format {
+            DiagnosticFormat::CargoJson => cargo_diagnostics(&stdout),
+            DiagnosticFormat::ExitStatus => (Vec::new(), Vec::new()),
+        };
+        if !output.status.success() && errors.is_empty() {
+            errors = stderr.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect();
+            if errors.is_empty() {
+                errors.push(format!("'{}' exited with {}", command.display(), output.status));
+            }
+        }
+
+        // Hash again: the check may generate tracked files (Cargo.lock)
+        let report = StateReport {
+            compilable: output.status.success() && errors.is_empty(),
+            errors,
+            warnings,
+            content_hash: content_hash(&dir).map_err(|e| io_error(&dir, e))?,
+            cached: false,
+        };
+        self.cache.lock().unwrap().insert(dir, report.clone());
+        Ok(report)
     }
 
     /// Evaluates the current project state
@@ -79,3 +257,96 @@ impl Default for StateEvaluator {
         Self::new()
     }
 }
+
+/// Errors and warnings from `cargo --message-format=json` output
+fn cargo_diagnostics(stdout: &str) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for line in stdout.lines() {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if message["reason"] != "compiler-message" {
+            continue;
+        }
+        let diagnostic = &message["message"];
+        let text = diagnostic["rendered"]
+            .as_str()
+            .or_else(|| diagnostic["message"].as_str())
+            .unwrap_or_default()
+            .trim_end()
+            .to_string();
+        match diagnostic["level"].as_str() {
+            Some("error") | Some("error: internal compiler error") => errors.push(text),
+            Some("warning") => warnings.push(text),
+            _ => {}
+        }
+    }
+    (errors, warnings)
+}
+
+/// Hash of every tracked file's relative path and contents, in path order
+fn content_hash(dir: &Path) -> std::io::Result<String> {
+    let mut files = Vec::new();
+    collect_tracked(dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        file.strip_prefix(dir).unwrap_or(&file).hash(&mut hasher);
+        std::fs::read(&file)?.hash(&mut hasher);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+fn collect_tracked(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if !UNTRACKED_DIRS.contains(&name) {
+                collect_tracked(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cargo_project(dir: &Path, lib: &str) {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n").unwrap();
+        std::fs::write(dir.join("src/lib.rs"), lib).unwrap();
+    }
+
+    #[test]
+    fn test_is_compilable_runs_cargo_check_and_caches() {
+        let dir = tempfile::tempdir().unwrap();
+        cargo_project(dir.path(), "pub fn teeth() -> u32 { 24 }\n");
+        let evaluator = StateEvaluator::new();
+
+        let report = evaluator.is_compilable(dir.path()).unwrap();
+        assert!(report.compilable, "{:?}", report.errors);
+        assert!(!report.cached);
+        // Build output under target/ does not change the hash
+        assert!(evaluator.is_compilable(dir.path()).unwrap().cached);
+
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn teeth() -> u32 { 24 \n").unwrap();
+        let broken = evaluator.is_compilable(dir.path()).unwrap();
+        assert!(!broken.compilable);
+        assert!(!broken.cached);
+        assert!(broken.first_error().unwrap().contains("unclosed delimiter"), "{:?}", broken.errors);
+    }
+
+    #[test]
+    fn test_missing_check_command_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let evaluator = StateEvaluator::new().with_language(Language::JavaScript);
+        assert_eq!(evaluator.is_compilable(dir.path()).unwrap_err(), StateError::NoCheckCommand(Language::JavaScript));
+    }
+}
@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use crate::types::OasmType;
+use crate::types::{OasmType, Value};
 
 /// Metadata for a single symbol (object or variable)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SymbolMetadata {
     pub name: String,
     pub symbol_type: SymbolType,
@@ -12,9 +12,32 @@ pub struct SymbolMetadata {
     pub created_at: DateTime<Utc>,
     pub last_modified: DateTime<Utc>,
     pub source_line: usize,
+    /// Scope stack depth at declaration (1 = global scope; 0 for objects,
+    /// which are not scoped)
+    #[serde(default)]
+    pub scope_depth: usize,
+    /// When the declaring scope was popped; only set on tombstones
+    #[serde(default)]
+    pub dropped_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl SymbolMetadata {
+    /// `Symbol` struct value as reported by INSPECT
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("name".to_string(), Value::String(self.name.clone()));
+        fields.insert("symbol_type".to_string(), Value::String(format!("{:?}", self.symbol_type)));
+        fields.insert("data_type".to_string(), Value::String(format!("{:?}", self.data_type)));
+        fields.insert("created_at".to_string(), Value::String(self.created_at.to_rfc3339()));
+        fields.insert("last_modified".to_string(), Value::String(self.last_modified.to_rfc3339()));
+        fields.insert("source_line".to_string(), Value::U64(self.source_line as u64));
+        fields.insert("scope_depth".to_string(), Value::U64(self.scope_depth as u64));
+
+        Value::Struct { name: "Symbol".to_string(), fields }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SymbolType {
     Object,
     Variable,
@@ -22,20 +45,51 @@ pub enum SymbolType {
     Constant,
 }
 
+impl SymbolType {
+    /// Parse a type name as written in scripts (`Object`, `variable`, ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "object" => Some(SymbolType::Object),
+            "variable" => Some(SymbolType::Variable),
+            "macro" => Some(SymbolType::Macro),
+            "constant" => Some(SymbolType::Constant),
+            _ => None,
+        }
+    }
+}
+
 /// A centralized table for tracking project-wide symbols
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct SymbolTable {
     symbols: HashMap<String, SymbolMetadata>,
+    /// Symbol names in the order they were first inserted
+    #[serde(default)]
+    order: Vec<String>,
+    /// Keep symbols of popped scopes as tombstones instead of removing them
+    #[serde(default)]
+    keep_tombstones: bool,
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         Self {
             symbols: HashMap::new(),
+            order: Vec::new(),
+            keep_tombstones: false,
         }
     }
 
+    /// Keep symbols whose scope was popped, marked with `dropped_at`, so
+    /// debug views and out-of-scope errors can still see them
+    pub fn with_tombstones(mut self, keep: bool) -> Self {
+        self.keep_tombstones = keep;
+        self
+    }
+
     pub fn insert(&mut self, metadata: SymbolMetadata) {
+        if !self.symbols.contains_key(&metadata.name) {
+            self.order.push(metadata.name.clone());
+        }
         self.symbols.insert(metadata.name.clone(), metadata);
     }
 
@@ -49,14 +103,70 @@ impl SymbolTable {
         }
     }
 
+    /// Record a new data type for a symbol (after an in-place CAST)
+    pub fn update_type(&mut self, name: &str, data_type: OasmType) {
+        if let Some(symbol) = self.symbols.get_mut(name) {
+            symbol.data_type = data_type;
+            symbol.last_modified = Utc::now();
+        }
+    }
+
+    /// Drop the symbols a popped scope declared: `names` declared at `depth`.
+    /// Symbols re-declared elsewhere since (other depth) are left alone.
+    pub fn drop_scope<'a>(&mut self, names: impl IntoIterator<Item = &'a String>, depth: usize) {
+        let now = Utc::now();
+        for name in names {
+            if !self.symbols.get(name).is_some_and(|s| s.scope_depth == depth && s.dropped_at.is_none()) {
+                continue;
+            }
+            if self.keep_tombstones {
+                if let Some(symbol) = self.symbols.get_mut(name) {
+                    symbol.dropped_at = Some(now);
+                }
+            } else {
+                self.symbols.remove(name);
+                self.order.retain(|n| n != name);
+            }
+        }
+    }
+
+    /// Every live symbol, in creation order
+    pub fn all(&self) -> Vec<&SymbolMetadata> {
+        self.entries().filter(|s| s.dropped_at.is_none()).collect()
+    }
+
+    /// Tombstones of symbols whose scope was popped, in creation order
+    pub fn dropped(&self) -> Vec<&SymbolMetadata> {
+        self.entries().filter(|s| s.dropped_at.is_some()).collect()
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &SymbolMetadata> {
+        self.order.iter().filter_map(|name| self.symbols.get(name))
+    }
+
+    /// Symbols whose name starts with `prefix`, in creation order
+    pub fn find_by_prefix(&self, prefix: &str) -> Vec<&SymbolMetadata> {
+        self.query(|s| s.name.starts_with(prefix))
+    }
+
+    /// Symbols of one kind, in creation order
     pub fn list_by_type(&self, symbol_type: SymbolType) -> Vec<&SymbolMetadata> {
-        self.symbols.values()
-            .filter(|s| s.symbol_type == symbol_type)
-            .collect()
+        self.query(|s| s.symbol_type == symbol_type)
+    }
+
+    /// Symbols modified strictly after `since`, in creation order
+    pub fn modified_after(&self, since: DateTime<Utc>) -> Vec<&SymbolMetadata> {
+        self.query(|s| s.last_modified > since)
+    }
+
+    /// Symbols matching an arbitrary predicate, in creation order
+    pub fn query(&self, predicate: impl Fn(&SymbolMetadata) -> bool) -> Vec<&SymbolMetadata> {
+        self.all().into_iter().filter(|s| predicate(s)).collect()
     }
 
-    /// Captures a snapshot for the debugger
+    /// Captures a snapshot for the debugger (tombstones included), in
+    /// creation order
     pub fn snapshot(&self) -> Vec<SymbolMetadata> {
-        self.symbols.values().cloned().collect()
+        self.entries().cloned().collect()
     }
 }
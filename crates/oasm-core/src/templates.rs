@@ -3,6 +3,11 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use anyhow::{Result, Context};
 use std::fs;
+use crate::expansion::{ExpansionBudget, ExpansionError, ExpansionMechanism, ExpansionTracker};
+use crate::parser::{Instruction, InstructionParser, NativeParser, ParseError};
+use crate::regex_cache::RegexCache;
+use crate::types::{NativeTypeChecker, OasmType, TypeChecker, TypeError, Value};
+use std::fmt;
 
 /// A template loaded from a YAML file
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,7 +64,7 @@ impl TemplateManager {
         for entry in fs::read_dir(category_path)? {
             let entry = entry?;
             let path = entry.path();
-            if path.extension().map_or(false, |ext| ext == "yaml" || ext == "yml") {
+            if path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml") {
                 templates.push(path.strip_prefix(&self.template_dir)?.to_path_buf());
             }
         }
@@ -84,6 +89,38 @@ impl TemplateInstantiator {
         instantiated
     }
 
+    /// Replaces placeholders repeatedly so values may refer to other
+    /// placeholders (derived values). Each pass counts as one level of
+    /// expansion and its output is charged against the tracker's byte budget.
+    pub fn instantiate_string_budgeted(
+        name: &str,
+        content: &str,
+        placeholders: &HashMap<String, String>,
+        tracker: &mut ExpansionTracker,
+    ) -> Result<String, ExpansionError> {
+        let start_depth = tracker.depth();
+        let mut current = content.to_string();
+
+        let result = loop {
+            if let Err(e) = tracker.enter(ExpansionMechanism::Template, name) {
+                break Err(e);
+            }
+            let next = Self::instantiate_string(&current, placeholders);
+            if let Err(e) = tracker.charge(0, next.len()) {
+                break Err(e);
+            }
+            if next == current {
+                break Ok(next);
+            }
+            current = next;
+        };
+
+        while tracker.depth() > start_depth {
+            tracker.exit();
+        }
+        result
+    }
+
     /// Instantiates a template into a directory
     pub fn instantiate_to_file(
         template: &Template,
@@ -102,3 +139,467 @@ impl TemplateInstantiator {
         }
     }
 }
+
+/// `{{param}}` placeholder in an instruction template body
+const PARAM_PLACEHOLDER: &str = r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}";
+
+/// Parameter declared by an instruction template. Types are primitive
+/// type names as written in CAST (`u32`, `f64`, `string`, ...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateParam {
+    pub name: String,
+    pub param_type: OasmType,
+    /// Used when the parameter is not given; required when None
+    pub default: Option<Value>,
+}
+
+/// A parametric block of OASM instructions loaded from YAML:
+///
+/// ```yaml
+/// name: gear
+/// params:
+///   - { name: teeth, type: u32 }
+///   - { name: module, type: f64, default: 1.5 }
+/// body: |
+///   CREATE gear
+///   SET gear_0000.teeth = {{teeth}}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub params: Vec<TemplateParam>,
+    pub body: String,
+    /// File the template was loaded from
+    pub source: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct TemplateFile {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    params: Vec<ParamFile>,
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct ParamFile {
+    name: String,
+    #[serde(rename = "type")]
+    param_type: String,
+    default: Option<serde_yaml::Value>,
+}
+
+/// Why loading or expanding an instruction template failed
+#[derive(Debug, Clone)]
+pub enum TemplateError {
+    Io { path: PathBuf, reason: String },
+    /// The file is not a valid template (bad YAML, type name or default)
+    Invalid { path: PathBuf, reason: String },
+    DuplicateTemplate { name: String, paths: (PathBuf, PathBuf) },
+    UnknownTemplate(String),
+    MissingParameter { template: String, param: String },
+    UnknownParameter { template: String, param: String },
+    TypeMismatch { template: String, param: String, error: Box<TypeError> },
+    /// A text value holds a character that would escape its place in the body
+    UnsafeValue { template: String, param: String, found: char },
+    /// The body uses `{{placeholder}}` but declares no such parameter
+    UnknownPlaceholder { template: String, placeholder: String },
+    /// The expanded body is not valid OASM
+    Parse { template: String, error: ParseError },
+    /// Expanding went over the expansion budget
+    Expansion(ExpansionError),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Io { path, reason } => write!(f, "Cannot read template {}: {}", path.display(), reason),
+            TemplateError::Invalid { path, reason } => write!(f, "Invalid template {}: {}", path.display(), reason),
+            TemplateError::DuplicateTemplate { name, paths } => write!(
+                f,
+                "Template '{}' is defined in both {} and {}",
+                name,
+                paths.0.display(),
+                paths.1.display()
+            ),
+            TemplateError::UnknownTemplate(name) => write!(f, "Unknown template '{}'", name),
+            TemplateError::MissingParameter { template, param } => {
+                write!(f, "Template '{}' requires parameter '{}'", template, param)
+            }
+            TemplateError::UnknownParameter { template, param } => {
+                write!(f, "Template '{}' has no parameter '{}'", template, param)
+            }
+            TemplateError::TypeMismatch { template, param, error } => {
+                write!(f, "Template '{}' parameter '{}': {}", template, param, error)
+            }
+            TemplateError::UnsafeValue { template, param, found } => write!(
+                f,
+                "Template '{}' parameter '{}' contains {:?}, which cannot be substituted into OASM source",
+                template, param, found
+            ),
+            TemplateError::UnknownPlaceholder { template, placeholder } => write!(
+                f,
+                "Template '{}' uses {{{{{}}}}} but declares no such parameter",
+                template, placeholder
+            ),
+            TemplateError::Parse { template, error } => {
+                write!(f, "Template '{}' expands to invalid OASM: {:?}", template, error)
+            }
+            TemplateError::Expansion(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Instruction templates by name
+#[derive(Debug, Clone, Default)]
+pub struct TemplateLibrary {
+    templates: HashMap<String, InstructionTemplate>,
+    /// Applied to each `expand` (see `ExpansionBudget::from_project`)
+    budget: ExpansionBudget,
+}
+
+impl TemplateLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_budget(mut self, budget: ExpansionBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Load every `.yaml`/`.yml` file in `dir` (not recursive)
+    pub fn load_dir(dir: &Path) -> Result<Self, TemplateError> {
+        let io_error = |path: &Path, e: std::io::Error| TemplateError::Io { path: path.to_path_buf(), reason: e.to_string() };
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+            let path = entry.map_err(|e| io_error(dir, e))?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut library = Self::new();
+        for path in paths {
+            let content = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+            library.insert(InstructionTemplate::from_yaml(&content, &path)?)?;
+        }
+        Ok(library)
+    }
+
+    /// Add a template; names must be unique
+    pub fn insert(&mut self, template: InstructionTemplate) -> Result<(), TemplateError> {
+        if let Some(existing) = self.templates.get(&template.name) {
+            return Err(TemplateError::DuplicateTemplate {
+                name: template.name.clone(),
+                paths: (existing.source.clone(), template.source),
+            });
+        }
+        self.templates.insert(template.name.clone(), template);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&InstructionTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Template names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// Expand a template into instructions. Each parameter is checked
+    /// against its declared type (widened implicitly where the type checker
+    /// allows it); defaults fill in what is not given. The output is
+    /// charged against the library's budget.
+    pub fn expand(&self, name: &str, params: HashMap<String, Value>) -> Result<Vec<Instruction>, TemplateError> {
+        self.expand_with(name, params, &mut ExpansionTracker::new(self.budget))
+    }
+
+    /// Expand with a tracker shared with other mechanisms (macros, aliases)
+    pub fn expand_with(
+        &self,
+        name: &str,
+        params: HashMap<String, Value>,
+        tracker: &mut ExpansionTracker,
+    ) -> Result<Vec<Instruction>, TemplateError> {
+        let template = self.get(name).ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))?;
+        template.expand_with(params, tracker)
+    }
+}
+
+impl InstructionTemplate {
+    /// Parse a template file's contents; `path` is recorded for errors
+    pub fn from_yaml(content: &str, path: &Path) -> Result<Self, TemplateError> {
+        let invalid = |reason: String| TemplateError::Invalid { path: path.to_path_buf(), reason };
+
+        let file: TemplateFile = serde_yaml::from_str(content).map_err(|e| invalid(e.to_string()))?;
+        let mut params = Vec::new();
+        for param in file.params {
+            let param_type = OasmType::from_name(&param.param_type)
+                .ok_or_else(|| invalid(format!("parameter '{}' has unknown type '{}'", param.name, param.param_type)))?;
+            let default = match &param.default {
+                Some(yaml) => Some(default_value(yaml, &param_type).ok_or_else(|| {
+                    invalid(format!("default of parameter '{}' is not a valid {:?}", param.name, param_type))
+                })?),
+                None => None,
+            };
+            params.push(TemplateParam { name: param.name, param_type, default });
+        }
+
+        Ok(Self {
+            name: file.name,
+            description: file.description,
+            params,
+            body: file.body,
+            source: path.to_path_buf(),
+        })
+    }
+
+    /// See `TemplateLibrary::expand`; charged against the default budget
+    pub fn expand(&self, params: HashMap<String, Value>) -> Result<Vec<Instruction>, TemplateError> {
+        self.expand_with(params, &mut ExpansionTracker::new(ExpansionBudget::default()))
+    }
+
+    /// See `TemplateLibrary::expand_with`
+    pub fn expand_with(
+        &self,
+        mut params: HashMap<String, Value>,
+        tracker: &mut ExpansionTracker,
+    ) -> Result<Vec<Instruction>, TemplateError> {
+        let checker = NativeTypeChecker;
+
+        if let Some(extra) = params.keys().filter(|k| !self.params.iter().any(|p| &p.name == *k)).min() {
+            return Err(TemplateError::UnknownParameter { template: self.name.clone(), param: extra.clone() });
+        }
+
+        let mut values = HashMap::new();
+        for param in &self.params {
+            let value = match params.remove(&param.name).or_else(|| param.default.clone()) {
+                Some(value) => value,
+                None => {
+                    return Err(TemplateError::MissingParameter { template: self.name.clone(), param: param.name.clone() })
+                }
+            };
+            let mismatch = |error| TemplateError::TypeMismatch {
+                template: self.name.clone(),
+                param: param.name.clone(),
+                error: Box::new(error),
+            };
+            let found = checker.infer_type(&value);
+            checker.check_assignment(&param.param_type, &found).map_err(mismatch)?;
+            let value = if found == param.param_type {
+                value
+            } else {
+                value.cast_to(&param.param_type).ok_or_else(|| {
+                    mismatch(TypeError::TypeMismatch { expected: param.param_type.clone(), found })
+                })?
+            };
+            let literal = source_literal(&value).map_err(|found| TemplateError::UnsafeValue {
+                template: self.name.clone(),
+                param: param.name.clone(),
+                found,
+            })?;
+            values.insert(param.name.as_str(), literal);
+        }
+
+        let placeholder = RegexCache::global().get(PARAM_PLACEHOLDER).expect("placeholder pattern is valid");
+        if let Some(unknown) = placeholder.captures_iter(&self.body).map(|c| c[1].to_string()).find(|n| !values.contains_key(n.as_str())) {
+            return Err(TemplateError::UnknownPlaceholder { template: self.name.clone(), placeholder: unknown });
+        }
+        let source = placeholder.replace_all(&self.body, |c: &regex::Captures| values[&c[1]].clone());
+
+        tracker.enter(ExpansionMechanism::Template, &self.name).map_err(TemplateError::Expansion)?;
+        let expanded = NativeParser
+            .parse_file(&source)
+            .map_err(|error| TemplateError::Parse { template: self.name.clone(), error })
+            .and_then(|instructions| {
+                tracker.charge(instructions.len(), source.len()).map_err(TemplateError::Expansion)?;
+                Ok(instructions)
+            });
+        tracker.exit();
+        expanded
+    }
+}
+
+/// A YAML default as a value of the declared type
+fn default_value(yaml: &serde_yaml::Value, ty: &OasmType) -> Option<Value> {
+    match (yaml, ty) {
+        (serde_yaml::Value::Bool(b), OasmType::Bool) => Some(Value::Bool(*b)),
+        (serde_yaml::Value::String(s), OasmType::String) => Some(Value::String(s.clone())),
+        (serde_yaml::Value::String(s), OasmType::Char) => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Value::Char(c)),
+                _ => None,
+            }
+        }
+        (serde_yaml::Value::Number(n), _) => match n.as_i64() {
+            Some(i) => Value::I64(i).cast_to(ty),
+            None => n.as_f64().and_then(|f| Value::F64(f).cast_to(ty)),
+        },
+        _ => None,
+    }
+}
+
+/// How a parameter value is written into the body. Strings go in verbatim
+/// so they can name objects (`SET {{id}}.teeth`); quote the placeholder to
+/// get a string literal. Floats keep their decimal point so they parse
+/// back as floats.
+///
+/// OASM string literals have no escapes, so text holding a quote, a
+/// backslash or a control character (newlines included) could close the
+/// literal or start a new instruction; it is rejected with the offending
+/// character instead.
+fn source_literal(value: &Value) -> Result<String, char> {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Char(c) => c.to_string(),
+        Value::F32(n) => return Ok(format!("{:?}", n)),
+        Value::F64(n) => return Ok(format!("{:?}", n)),
+        other => return Ok(crate::expression::render_value(other)),
+    };
+    match text.chars().find(|&c| c == '"' || c == '\\' || c.is_control()) {
+        Some(found) => Err(found),
+        None => Ok(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expansion::{BudgetLimit, ExpansionBudget};
+    use crate::context::{Actor, ExecutionContext};
+    use crate::executor::{InstructionExecutor, NativeExecutor};
+
+    fn fixture_library() -> TemplateLibrary {
+        TemplateLibrary::load_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/templates")).unwrap()
+    }
+
+    #[test]
+    fn test_gear_template_expands_to_create_and_set() {
+        let library = fixture_library();
+        assert_eq!(library.names(), vec!["gear", "gear_with_bore"]);
+
+        let params = HashMap::from([
+            ("teeth".to_string(), Value::U32(24)),
+            ("module".to_string(), Value::U32(2)),
+        ]);
+        let program = library.expand("gear", params).unwrap();
+        assert_eq!(program.iter().map(|i| i.mnemonic.as_str()).collect::<Vec<_>>(), vec!["CREATE", "SET", "SET", "SET"]);
+
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        NativeExecutor::new().execute_batch(&program, &mut ctx).unwrap();
+        let gear = &ctx.objects["gear_0000"].properties;
+        assert_eq!(gear["teeth"], Value::U32(24));
+        // U32 widened to the declared f64; `label` fell back to its default
+        assert_eq!(gear["module"], Value::F64(2.0));
+        assert_eq!(gear["label"], Value::String("spur".to_string()));
+    }
+
+    #[test]
+    fn test_template_errors_are_distinct() {
+        let library = fixture_library();
+
+        let missing = library.expand("gear", HashMap::new());
+        assert!(matches!(missing, Err(TemplateError::MissingParameter { param, .. }) if param == "teeth"));
+
+        let wrong_type = library.expand("gear", HashMap::from([("teeth".to_string(), Value::String("many".to_string()))]));
+        assert!(matches!(wrong_type, Err(TemplateError::TypeMismatch { param, .. }) if param == "teeth"));
+
+        let unknown = library.expand("gear_with_bore", HashMap::from([("teeth".to_string(), Value::U32(12))]));
+        assert_eq!(unknown.unwrap_err().to_string(), "Template 'gear_with_bore' uses {{bore}} but declares no such parameter");
+
+        assert!(matches!(library.expand("pulley", HashMap::new()), Err(TemplateError::UnknownTemplate(_))));
+    }
+
+    #[test]
+    fn test_hostile_string_params_are_rejected() {
+        let library = fixture_library();
+        let expand = |label: &str| {
+            library.expand(
+                "gear",
+                HashMap::from([
+                    ("teeth".to_string(), Value::U32(12)),
+                    ("label".to_string(), Value::String(label.to_string())),
+                ]),
+            )
+        };
+
+        // Would close the quoted label and inject a second SET
+        let hostile = expand("spur\"\nSET gear_0000.teeth = 999\nSET gear_0000.note = \"");
+        assert!(matches!(hostile, Err(TemplateError::UnsafeValue { ref param, found: '"', .. }) if param == "label"));
+        assert!(matches!(expand("a\\b"), Err(TemplateError::UnsafeValue { found: '\\', .. })));
+        assert!(matches!(expand("two\nlines"), Err(TemplateError::UnsafeValue { found: '\n', .. })));
+
+        let id = library.expand(
+            "gear",
+            HashMap::from([
+                ("teeth".to_string(), Value::U32(12)),
+                ("id".to_string(), Value::String("gear_0000.teeth = 1\nDELETE gear_0000".to_string())),
+            ]),
+        );
+        assert!(matches!(id, Err(TemplateError::UnsafeValue { found: '\n', .. })));
+
+        // Spaces and punctuation inside the quotes are fine
+        let program = expand("spur, 20° pressure angle").unwrap();
+        assert_eq!(program.len(), 4);
+    }
+
+    #[test]
+    fn test_expansion_is_charged_against_the_budget() {
+        let params = || HashMap::from([("teeth".to_string(), Value::U32(24))]);
+        let library = fixture_library().with_budget(ExpansionBudget { max_instructions: 3, ..ExpansionBudget::default() });
+
+        let err = library.expand("gear", params()).unwrap_err();
+        let TemplateError::Expansion(err) = err else { panic!("expected a budget error, got {:?}", err) };
+        assert_eq!(err.limit, BudgetLimit::Instructions(3));
+        assert_eq!(err.chain, vec!["template:gear"]);
+
+        // A shared tracker carries the outer chain and the running totals
+        let mut tracker = ExpansionTracker::new(ExpansionBudget { max_depth: 1, ..ExpansionBudget::default() });
+        tracker.enter(ExpansionMechanism::Macro, "GEARBOX").unwrap();
+        let err = fixture_library().expand_with("gear", params(), &mut tracker).unwrap_err();
+        assert_eq!(err.to_string(), "template 'gear' exceeded the expansion depth limit of 1 (chain: macro:GEARBOX -> template:gear)");
+        tracker.exit();
+
+        let mut tracker = ExpansionTracker::new(ExpansionBudget::default());
+        fixture_library().expand_with("gear", params(), &mut tracker).unwrap();
+        assert_eq!((tracker.instructions(), tracker.depth()), (4, 0));
+    }
+
+    #[test]
+    fn test_derived_placeholders_resolve_within_budget() {
+        let placeholders = HashMap::from([
+            ("size".to_string(), "{width}x{height}".to_string()),
+            ("width".to_string(), "4".to_string()),
+            ("height".to_string(), "2".to_string()),
+        ]);
+        let mut tracker = ExpansionTracker::new(ExpansionBudget::default());
+
+        let out = TemplateInstantiator::instantiate_string_budgeted("box", "size={size}", &placeholders, &mut tracker).unwrap();
+        assert_eq!(out, "size=4x2");
+        assert_eq!(tracker.depth(), 0);
+    }
+
+    #[test]
+    fn test_self_doubling_placeholder_hits_byte_budget() {
+        let placeholders = HashMap::from([("a".to_string(), "{a}{a}".to_string())]);
+        let budget = ExpansionBudget { max_source_bytes: 4096, ..ExpansionBudget::default() };
+        let mut tracker = ExpansionTracker::new(budget);
+
+        let err = TemplateInstantiator::instantiate_string_budgeted("bomb", "{a}", &placeholders, &mut tracker).unwrap_err();
+        assert_eq!(err.mechanism, ExpansionMechanism::Template);
+        assert_eq!(err.definition, "bomb");
+        assert_eq!(err.limit, BudgetLimit::SourceBytes(4096));
+        assert_eq!(tracker.depth(), 0);
+    }
+}
@@ -0,0 +1,56 @@
+//! Text helpers shared across the crate
+//! Typo suggestions ("did you mean ...?") for mnemonics, rule IDs,
+//! capability names and config keys all go through `closest_match`.
+
+/// Levenshtein edit distance between two strings (by char)
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Candidate closest to `input` within `max_distance` edits, ignoring case.
+/// Ties go to the earliest candidate; None if nothing is close enough.
+pub fn closest_match(input: &str, candidates: &[&str], max_distance: usize) -> Option<String> {
+    let input = input.to_lowercase();
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(&input, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("EXTRUDE", "EXTRUDE"), 0);
+    }
+
+    #[test]
+    fn test_closest_match_within_and_beyond_cutoff() {
+        let mnemonics = ["CREATE", "SET", "EXTRUDE", "SCALE"];
+
+        assert_eq!(closest_match("CRAETE", &mnemonics, 2).as_deref(), Some("CREATE"));
+        assert_eq!(closest_match("extrud", &mnemonics, 2).as_deref(), Some("EXTRUDE"));
+        assert_eq!(closest_match("TRANSFORM", &mnemonics, 2), None);
+        assert_eq!(closest_match("CRAETE", &mnemonics, 1), None);
+        assert_eq!(closest_match("anything", &[], 5), None);
+    }
+}
@@ -0,0 +1,349 @@
+//! Runtime evaluation of operations on values
+//!
+//! `validate_operation` decides what an operation's result type would be;
+//! `evaluate_operation` computes it.
+//!
+//! ARITHMETIC: operands of one type keep it. Mixed operands use the type the
+//! other implicitly widens to, F64 when a float is involved, and I64 for
+//! integers of mixed signedness. Integer results are range-checked
+//! (`Overflow`), division truncates toward zero, and a zero divisor is a
+//! `DivisionByZero` error for integers and floats alike. Otherwise floats
+//! follow IEEE 754: a NaN operand yields NaN.
+//!
+//! COMPARISONS return Bool. A NaN compares unequal to everything, itself
+//! included, and every ordering against it is false.
+//!
+//! GEOMETRY: Dot and Cross on Vector3; MatrixMultiply (or Multiply) of a
+//! Matrix4x4 with a Vector4 or another Matrix4x4, row-major.
+
+use super::{NativeTypeChecker, OasmType, Operation, TypeChecker, TypeError, Value};
+use std::cmp::Ordering;
+
+impl Operation {
+    /// Operation written as an infix operator in scripts (`*`, `<=`, `dot`, ...)
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        let op = match symbol.to_lowercase().as_str() {
+            "+" => Operation::Add,
+            "-" => Operation::Subtract,
+            "*" => Operation::Multiply,
+            "/" => Operation::Divide,
+            "%" => Operation::Modulo,
+            "==" => Operation::Equal,
+            "!=" => Operation::NotEqual,
+            "<" => Operation::LessThan,
+            "<=" => Operation::LessOrEqual,
+            ">" => Operation::GreaterThan,
+            ">=" => Operation::GreaterOrEqual,
+            "&&" | "and" => Operation::And,
+            "||" | "or" => Operation::Or,
+            "dot" => Operation::Dot,
+            "cross" => Operation::Cross,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Infix spelling, the inverse of `from_symbol`
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operation::Add => "+",
+            Operation::Subtract => "-",
+            Operation::Multiply => "*",
+            Operation::Divide => "/",
+            Operation::Modulo => "%",
+            Operation::Equal => "==",
+            Operation::NotEqual => "!=",
+            Operation::LessThan => "<",
+            Operation::LessOrEqual => "<=",
+            Operation::GreaterThan => ">",
+            Operation::GreaterOrEqual => ">=",
+            Operation::And => "&&",
+            Operation::Or => "||",
+            Operation::Not => "!",
+            Operation::Dot => "dot",
+            Operation::Cross => "cross",
+            Operation::MatrixMultiply => "@",
+            Operation::PropertyAccess => ".",
+            Operation::MethodCall => "()",
+        }
+    }
+
+    /// Binding strength as an infix operator; higher binds tighter
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operation::Or => 1,
+            Operation::And => 2,
+            Operation::Equal
+            | Operation::NotEqual
+            | Operation::LessThan
+            | Operation::LessOrEqual
+            | Operation::GreaterThan
+            | Operation::GreaterOrEqual => 3,
+            Operation::Add | Operation::Subtract => 4,
+            _ => 5,
+        }
+    }
+}
+
+/// Compute `op` over `operands`
+pub fn evaluate_operation(op: &Operation, operands: &[Value]) -> Result<Value, TypeError> {
+    let invalid = || TypeError::InvalidOperation {
+        op: op.clone(),
+        operands: operands.iter().map(|v| NativeTypeChecker.infer_type(v)).collect(),
+    };
+
+    match (op, operands) {
+        (Operation::Multiply, [Value::Matrix4x4(_), Value::Vector4(_) | Value::Matrix4x4(_)]) => {
+            evaluate_operation(&Operation::MatrixMultiply, operands)
+        }
+        (Operation::Add | Operation::Subtract | Operation::Multiply | Operation::Divide | Operation::Modulo, [lhs, rhs]) => {
+            arithmetic(op, lhs, rhs).ok_or_else(invalid)?
+        }
+        (
+            Operation::Equal
+            | Operation::NotEqual
+            | Operation::LessThan
+            | Operation::LessOrEqual
+            | Operation::GreaterThan
+            | Operation::GreaterOrEqual,
+            [lhs, rhs],
+        ) => {
+            let ordering = match (lhs, rhs) {
+                (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+                (Value::Char(l), Value::Char(r)) => Some(l.cmp(r)),
+                (Value::Bool(l), Value::Bool(r)) if matches!(op, Operation::Equal | Operation::NotEqual) => {
+                    Some(l.cmp(r))
+                }
+                _ => match (Number::of(lhs), Number::of(rhs)) {
+                    (Some(Number::Int(l)), Some(Number::Int(r))) => Some(l.cmp(&r)),
+                    (Some(l), Some(r)) => l.as_f64().partial_cmp(&r.as_f64()),
+                    _ => return Err(invalid()),
+                },
+            };
+            let result = match op {
+                Operation::Equal => ordering == Some(Ordering::Equal),
+                Operation::NotEqual => ordering != Some(Ordering::Equal),
+                Operation::LessThan => ordering == Some(Ordering::Less),
+                Operation::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                Operation::GreaterThan => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            };
+            Ok(Value::Bool(result))
+        }
+        (Operation::And, [Value::Bool(l), Value::Bool(r)]) => Ok(Value::Bool(*l && *r)),
+        (Operation::Or, [Value::Bool(l), Value::Bool(r)]) => Ok(Value::Bool(*l || *r)),
+        (Operation::Not, [Value::Bool(b)]) => Ok(Value::Bool(!b)),
+        (Operation::Dot, [Value::Vector3(a), Value::Vector3(b)]) => {
+            Ok(Value::F64(a.iter().zip(b).map(|(x, y)| x * y).sum()))
+        }
+        (Operation::Cross, [Value::Vector3(a), Value::Vector3(b)]) => Ok(Value::Vector3([
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ])),
+        (Operation::MatrixMultiply, [Value::Matrix4x4(m), Value::Vector4(v)]) => {
+            Ok(Value::Vector4(std::array::from_fn(|i| (0..4).map(|k| m[i][k] * v[k]).sum())))
+        }
+        (Operation::MatrixMultiply, [Value::Matrix4x4(a), Value::Matrix4x4(b)]) => Ok(Value::Matrix4x4(
+            std::array::from_fn(|i| std::array::from_fn(|j| (0..4).map(|k| a[i][k] * b[k][j]).sum())),
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+/// A numeric operand, integers widened losslessly
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn of(value: &Value) -> Option<Self> {
+        let number = match value {
+            Value::U8(n) => Number::Int((*n).into()),
+            Value::U16(n) => Number::Int((*n).into()),
+            Value::U32(n) => Number::Int((*n).into()),
+            Value::U64(n) => Number::Int((*n).into()),
+            Value::I8(n) => Number::Int((*n).into()),
+            Value::I16(n) => Number::Int((*n).into()),
+            Value::I32(n) => Number::Int((*n).into()),
+            Value::I64(n) => Number::Int((*n).into()),
+            Value::F32(n) => Number::Float((*n).into()),
+            Value::F64(n) => Number::Float(*n),
+            _ => return None,
+        };
+        Some(number)
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(n) => n as f64,
+            Number::Float(n) => n,
+        }
+    }
+}
+
+/// Type both arithmetic operands are brought to
+fn common_type(lhs: &OasmType, rhs: &OasmType) -> OasmType {
+    let checker = NativeTypeChecker;
+    if checker.can_cast(lhs, rhs) {
+        rhs.clone()
+    } else if checker.can_cast(rhs, lhs) {
+        lhs.clone()
+    } else if matches!(lhs, OasmType::F32 | OasmType::F64) || matches!(rhs, OasmType::F32 | OasmType::F64) {
+        OasmType::F64
+    } else {
+        OasmType::I64
+    }
+}
+
+/// None if an operand is not numeric
+fn arithmetic(op: &Operation, lhs: &Value, rhs: &Value) -> Option<Result<Value, TypeError>> {
+    let (l, r) = (Number::of(lhs)?, Number::of(rhs)?);
+    let checker = NativeTypeChecker;
+    let ty = common_type(&checker.infer_type(lhs), &checker.infer_type(rhs));
+
+    let result = match (&ty, l, r) {
+        (OasmType::F32 | OasmType::F64, l, r) => {
+            let (l, r) = (l.as_f64(), r.as_f64());
+            if matches!(op, Operation::Divide | Operation::Modulo) && r == 0.0 {
+                return Some(Err(TypeError::DivisionByZero));
+            }
+            let n = match op {
+                Operation::Add => l + r,
+                Operation::Subtract => l - r,
+                Operation::Multiply => l * r,
+                Operation::Divide => l / r,
+                _ => l % r,
+            };
+            Ok(Value::F64(n).cast_to(&ty).unwrap_or(Value::F64(n)))
+        }
+        (_, Number::Int(l), Number::Int(r)) => {
+            if matches!(op, Operation::Divide | Operation::Modulo) && r == 0 {
+                return Some(Err(TypeError::DivisionByZero));
+            }
+            let n = match op {
+                Operation::Add => l.checked_add(r),
+                Operation::Subtract => l.checked_sub(r),
+                Operation::Multiply => l.checked_mul(r),
+                Operation::Divide => l.checked_div(r),
+                _ => l.checked_rem(r),
+            };
+            // i128 holds any I64 or U64 value, so the cast is the range check
+            n.and_then(|n| i64::try_from(n).map(Value::I64).or_else(|_| u64::try_from(n).map(Value::U64)).ok())
+                .and_then(|v| v.cast_to(&ty))
+                .ok_or_else(|| TypeError::Overflow { op: op.clone(), result_type: ty.clone() })
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_arithmetic() {
+        assert_eq!(evaluate_operation(&Operation::Multiply, &[Value::U32(6), Value::U32(7)]).unwrap(), Value::U32(42));
+        assert_eq!(evaluate_operation(&Operation::Divide, &[Value::I32(-7), Value::I32(2)]).unwrap(), Value::I32(-3));
+        assert_eq!(evaluate_operation(&Operation::Modulo, &[Value::U8(7), Value::U8(3)]).unwrap(), Value::U8(1));
+        assert_eq!(evaluate_operation(&Operation::Add, &[Value::F32(1.5), Value::F32(2.0)]).unwrap(), Value::F32(3.5));
+
+        // Mixed operands widen
+        assert_eq!(evaluate_operation(&Operation::Add, &[Value::U8(200), Value::U32(100)]).unwrap(), Value::U32(300));
+        assert_eq!(evaluate_operation(&Operation::Multiply, &[Value::U32(3), Value::F64(1.5)]).unwrap(), Value::F64(4.5));
+        assert_eq!(evaluate_operation(&Operation::Subtract, &[Value::U32(2), Value::I32(5)]).unwrap(), Value::I64(-3));
+
+        assert!(matches!(
+            evaluate_operation(&Operation::Add, &[Value::U8(200), Value::U8(100)]),
+            Err(TypeError::Overflow { result_type: OasmType::U8, .. })
+        ));
+        assert!(matches!(
+            evaluate_operation(&Operation::Subtract, &[Value::U32(1), Value::U32(2)]),
+            Err(TypeError::Overflow { .. })
+        ));
+        assert!(matches!(
+            evaluate_operation(&Operation::Add, &[Value::String("a".into()), Value::U32(1)]),
+            Err(TypeError::InvalidOperation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_division_by_zero_and_nan() {
+        for divisor in [Value::U32(0), Value::F64(0.0), Value::F64(-0.0)] {
+            assert!(matches!(
+                evaluate_operation(&Operation::Divide, &[Value::U32(1), divisor.clone()]),
+                Err(TypeError::DivisionByZero)
+            ));
+            assert!(matches!(
+                evaluate_operation(&Operation::Modulo, &[Value::U32(1), divisor]),
+                Err(TypeError::DivisionByZero)
+            ));
+        }
+
+        let nan = Value::F64(f64::NAN);
+        match evaluate_operation(&Operation::Add, &[nan.clone(), Value::F64(1.0)]).unwrap() {
+            Value::F64(n) => assert!(n.is_nan()),
+            other => panic!("expected F64, got {:?}", other),
+        }
+        assert_eq!(evaluate_operation(&Operation::Equal, &[nan.clone(), nan.clone()]).unwrap(), Value::Bool(false));
+        assert_eq!(evaluate_operation(&Operation::NotEqual, &[nan.clone(), nan.clone()]).unwrap(), Value::Bool(true));
+        assert_eq!(evaluate_operation(&Operation::LessOrEqual, &[nan.clone(), Value::F64(1.0)]).unwrap(), Value::Bool(false));
+        assert_eq!(evaluate_operation(&Operation::GreaterThan, &[nan, Value::F64(1.0)]).unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert_eq!(evaluate_operation(&Operation::LessThan, &[Value::U32(3), Value::F64(3.5)]).unwrap(), Value::Bool(true));
+        assert_eq!(
+            evaluate_operation(&Operation::Equal, &[Value::U64(u64::MAX), Value::I64(-1)]).unwrap(),
+            Value::Bool(false)
+        );
+        assert_eq!(
+            evaluate_operation(&Operation::GreaterOrEqual, &[Value::String("b".into()), Value::String("a".into())]).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(evaluate_operation(&Operation::Equal, &[Value::Bool(true), Value::Bool(true)]).unwrap(), Value::Bool(true));
+        assert!(evaluate_operation(&Operation::LessThan, &[Value::Bool(false), Value::Bool(true)]).is_err());
+
+        assert_eq!(evaluate_operation(&Operation::And, &[Value::Bool(true), Value::Bool(false)]).unwrap(), Value::Bool(false));
+        assert_eq!(evaluate_operation(&Operation::Not, &[Value::Bool(false)]).unwrap(), Value::Bool(true));
+        assert!(evaluate_operation(&Operation::Or, &[Value::Bool(true), Value::U32(1)]).is_err());
+    }
+
+    #[test]
+    fn test_geometric_operations() {
+        let x = Value::Vector3([1.0, 0.0, 0.0]);
+        let y = Value::Vector3([0.0, 1.0, 0.0]);
+        assert_eq!(evaluate_operation(&Operation::Dot, &[x.clone(), y.clone()]).unwrap(), Value::F64(0.0));
+        assert_eq!(
+            evaluate_operation(&Operation::Dot, &[Value::Vector3([1.0, 2.0, 3.0]), Value::Vector3([4.0, 5.0, 6.0])]).unwrap(),
+            Value::F64(32.0)
+        );
+        assert_eq!(evaluate_operation(&Operation::Cross, &[x, y]).unwrap(), Value::Vector3([0.0, 0.0, 1.0]));
+
+        // Translation by (1, 2, 3) applied to a point and composed with itself
+        let mut translate = [[0.0; 4]; 4];
+        for (i, row) in translate.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        translate[0][3] = 1.0;
+        translate[1][3] = 2.0;
+        translate[2][3] = 3.0;
+        let m = Value::Matrix4x4(translate);
+        assert_eq!(
+            evaluate_operation(&Operation::MatrixMultiply, &[m.clone(), Value::Vector4([1.0, 1.0, 1.0, 1.0])]).unwrap(),
+            Value::Vector4([2.0, 3.0, 4.0, 1.0])
+        );
+        match evaluate_operation(&Operation::Multiply, &[m.clone(), m]).unwrap() {
+            Value::Matrix4x4(t) => assert_eq!([t[0][3], t[1][3], t[2][3], t[3][3]], [2.0, 4.0, 6.0, 1.0]),
+            other => panic!("expected Matrix4x4, got {:?}", other),
+        }
+
+        assert!(evaluate_operation(&Operation::Cross, &[Value::Vector2([1.0, 0.0]), Value::Vector2([0.0, 1.0])]).is_err());
+        assert_eq!(Operation::from_symbol("DOT"), Some(Operation::Dot));
+        assert!(Operation::Multiply.precedence() > Operation::Add.precedence());
+    }
+}
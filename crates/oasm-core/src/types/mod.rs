@@ -1,10 +1,13 @@
-/// OASM Native Type System
-///
-/// Defines OASM's native types: primitives, composites, geometric, objects
+//! OASM Native Type System
+//!
+//! Defines OASM's native types: primitives, composites, geometric, objects
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod eval;
+pub use eval::evaluate_operation;
+
 /// OASM native type system
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OasmType {
@@ -56,6 +59,29 @@ pub enum OasmType {
     Unknown,
 }
 
+impl OasmType {
+    /// Primitive type by name (`U32`, `f64`, `Bool`, ...), as written in CAST
+    pub fn from_name(name: &str) -> Option<Self> {
+        let ty = match name.to_uppercase().as_str() {
+            "U8" => OasmType::U8,
+            "U16" => OasmType::U16,
+            "U32" => OasmType::U32,
+            "U64" => OasmType::U64,
+            "I8" => OasmType::I8,
+            "I16" => OasmType::I16,
+            "I32" => OasmType::I32,
+            "I64" => OasmType::I64,
+            "F32" => OasmType::F32,
+            "F64" => OasmType::F64,
+            "BOOL" => OasmType::Bool,
+            "CHAR" => OasmType::Char,
+            "STRING" => OasmType::String,
+            _ => return None,
+        };
+        Some(ty)
+    }
+}
+
 /// Field in a struct
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Field {
@@ -117,6 +143,91 @@ pub enum Value {
     Void,
 }
 
+impl Value {
+    /// Convert a numeric value to another numeric type. Floats truncate toward
+    /// zero when cast to an integer; None if the value is not numeric, the
+    /// target is not numeric, or the result does not fit the target type.
+    pub fn cast_to(&self, to: &OasmType) -> Option<Value> {
+        let integer: Option<i128> = match self {
+            Value::U8(n) => Some((*n).into()),
+            Value::U16(n) => Some((*n).into()),
+            Value::U32(n) => Some((*n).into()),
+            Value::U64(n) => Some((*n).into()),
+            Value::I8(n) => Some((*n).into()),
+            Value::I16(n) => Some((*n).into()),
+            Value::I32(n) => Some((*n).into()),
+            Value::I64(n) => Some((*n).into()),
+            Value::F32(n) if n.is_finite() => Some(n.trunc() as i128),
+            Value::F64(n) if n.is_finite() => Some(n.trunc() as i128),
+            Value::F32(_) | Value::F64(_) => None,
+            _ => return None,
+        };
+        let float = match self {
+            Value::F32(n) => f64::from(*n),
+            Value::F64(n) => *n,
+            _ => integer? as f64,
+        };
+
+        let value = match to {
+            OasmType::U8 => Value::U8(integer?.try_into().ok()?),
+            OasmType::U16 => Value::U16(integer?.try_into().ok()?),
+            OasmType::U32 => Value::U32(integer?.try_into().ok()?),
+            OasmType::U64 => Value::U64(integer?.try_into().ok()?),
+            OasmType::I8 => Value::I8(integer?.try_into().ok()?),
+            OasmType::I16 => Value::I16(integer?.try_into().ok()?),
+            OasmType::I32 => Value::I32(integer?.try_into().ok()?),
+            OasmType::I64 => Value::I64(integer?.try_into().ok()?),
+            OasmType::F32 => Value::F32(float as f32),
+            OasmType::F64 => Value::F64(float),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Axis-aligned bounding box of a mesh's vertices; None for anything
+    /// but a mesh with at least one vertex
+    pub fn mesh_bounding_box(&self) -> Option<Value> {
+        match self {
+            Value::Mesh { vertices, .. } => {
+                let (min, max) = crate::geometry::bounding_box(vertices)?;
+                Some(Value::BoundingBox { min, max })
+            }
+            _ => None,
+        }
+    }
+
+    /// Rough number of bytes this value occupies: the enum itself plus the
+    /// heap data it owns (string bytes, elements, map entries)
+    pub fn estimated_size(&self) -> usize {
+        let map_size = |map: &HashMap<String, Value>| -> usize {
+            map.iter().map(|(key, value)| std::mem::size_of::<String>() + key.len() + value.estimated_size()).sum()
+        };
+        let heap = match self {
+            Value::String(text) => text.len(),
+            Value::Array(items) => items.iter().map(Value::estimated_size).sum(),
+            Value::Struct { name, fields } => name.len() + map_size(fields),
+            Value::Enum { name, variant, fields } => name.len() + variant.len() + fields.as_ref().map_or(0, map_size),
+            Value::Mesh { vertices, faces } => {
+                vertices.len() * std::mem::size_of::<[f64; 3]>()
+                    + faces.iter().map(|f| std::mem::size_of::<Vec<usize>>() + f.len() * std::mem::size_of::<usize>()).sum::<usize>()
+            }
+            Value::Object { id, object_type, properties } => id.len() + object_type.len() + map_size(properties),
+            _ => 0,
+        };
+        std::mem::size_of::<Value>() + heap
+    }
+}
+
+/// How a value of one type may become another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastKind {
+    /// Lossless; allowed in plain assignment
+    Implicit,
+    /// Narrowing or float→int truncation; needs a CAST instruction
+    Explicit,
+    Forbidden,
+}
+
 /// Type checker trait
 pub trait TypeChecker {
     /// Infer the type of a value
@@ -134,10 +245,39 @@ pub trait TypeChecker {
 
     /// Check if a type can be cast to another
     fn can_cast(&self, from: &OasmType, to: &OasmType) -> bool;
+
+    /// Check if a type can be cast to another with an explicit CAST
+    /// (everything `can_cast` allows, plus narrowing and float→int)
+    fn can_cast_explicit(&self, from: &OasmType, to: &OasmType) -> bool;
+
+    /// Convert `value` to `to` as an explicit CAST would. Integer results
+    /// are range-checked; floats truncate toward zero, and NaN or infinity
+    /// never converts to an integer.
+    fn explicit_cast(&self, value: &Value, to: &OasmType) -> Result<Value, TypeError> {
+        let from = self.infer_type(value);
+        if !self.can_cast_explicit(&from, to) {
+            return Err(TypeError::InvalidCast { from, to: to.clone() });
+        }
+        value.cast_to(to).ok_or_else(|| TypeError::CastOutOfRange {
+            value: format!("{:?}", value),
+            to: to.clone(),
+        })
+    }
+
+    /// Classify a cast from one type to another
+    fn cast_kind(&self, from: &OasmType, to: &OasmType) -> CastKind {
+        if self.can_cast(from, to) {
+            CastKind::Implicit
+        } else if self.can_cast_explicit(from, to) {
+            CastKind::Explicit
+        } else {
+            CastKind::Forbidden
+        }
+    }
 }
 
 /// Operation types
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Operation {
     // Arithmetic
     Add,
@@ -189,6 +329,23 @@ pub enum TypeError {
         from: OasmType,
         to: OasmType,
     },
+    /// Assignment would narrow or truncate; allowed only through CAST
+    ExplicitCastRequired {
+        from: OasmType,
+        to: OasmType,
+    },
+    /// An allowed cast whose value does not fit the target (or is NaN/infinite)
+    CastOutOfRange {
+        value: String,
+        to: OasmType,
+    },
+    /// Division or remainder with a zero divisor
+    DivisionByZero,
+    /// Integer result does not fit the operands' common type
+    Overflow {
+        op: Operation,
+        result_type: OasmType,
+    },
 }
 
 impl std::fmt::Display for TypeError {
@@ -209,6 +366,16 @@ impl std::fmt::Display for TypeError {
             TypeError::InvalidCast { from, to } => {
                 write!(f, "Invalid cast from {:?} to {:?}", from, to)
             }
+            TypeError::ExplicitCastRequired { from, to } => {
+                write!(f, "Cannot assign {:?} to {:?} implicitly; use CAST", from, to)
+            }
+            TypeError::CastOutOfRange { value, to } => {
+                write!(f, "{} does not fit in {:?}", value, to)
+            }
+            TypeError::DivisionByZero => write!(f, "Division by zero"),
+            TypeError::Overflow { op, result_type } => {
+                write!(f, "{:?} overflows {:?}", op, result_type)
+            }
         }
     }
 }
@@ -218,6 +385,50 @@ impl std::error::Error for TypeError {}
 /// Native type checker implementation
 pub struct NativeTypeChecker;
 
+fn is_numeric(ty: &OasmType) -> bool {
+    matches!(
+        ty,
+        OasmType::U8 | OasmType::U16 | OasmType::U32 | OasmType::U64
+            | OasmType::I8 | OasmType::I16 | OasmType::I32 | OasmType::I64
+            | OasmType::F32 | OasmType::F64
+    )
+}
+
+/// Range of an integer type
+fn integer_range(ty: &OasmType) -> Option<(i128, i128)> {
+    Some(match ty {
+        OasmType::U8 => (0, u8::MAX.into()),
+        OasmType::U16 => (0, u16::MAX.into()),
+        OasmType::U32 => (0, u32::MAX.into()),
+        OasmType::U64 => (0, u64::MAX.into()),
+        OasmType::I8 => (i8::MIN.into(), i8::MAX.into()),
+        OasmType::I16 => (i16::MIN.into(), i16::MAX.into()),
+        OasmType::I32 => (i32::MIN.into(), i32::MAX.into()),
+        OasmType::I64 => (i64::MIN.into(), i64::MAX.into()),
+        _ => return None,
+    })
+}
+
+/// True if every value of `from` converts to `to` exactly: integers into
+/// wider ranges (U32 -> I64 included), and into floats whose mantissa holds
+/// them; F32 into F64
+pub fn is_widening(from: &OasmType, to: &OasmType) -> bool {
+    let mantissa_bits = match to {
+        OasmType::F32 => 24,
+        OasmType::F64 => 53,
+        _ => {
+            return match (integer_range(from), integer_range(to)) {
+                (Some((from_min, from_max)), Some((to_min, to_max))) => to_min <= from_min && from_max <= to_max,
+                _ => false,
+            };
+        }
+    };
+    match integer_range(from) {
+        Some((min, max)) => min.unsigned_abs().max(max.unsigned_abs()) <= 1 << mantissa_bits,
+        None => from == to || (*from == OasmType::F32 && *to == OasmType::F64),
+    }
+}
+
 impl TypeChecker for NativeTypeChecker {
     fn infer_type(&self, value: &Value) -> OasmType {
         match value {
@@ -270,15 +481,16 @@ impl TypeChecker for NativeTypeChecker {
     }
 
     fn check_assignment(&self, target: &OasmType, value: &OasmType) -> Result<(), TypeError> {
-        if target == value {
-            Ok(())
-        } else if self.can_cast(value, target) {
-            Ok(())
-        } else {
-            Err(TypeError::TypeMismatch {
+        match self.cast_kind(value, target) {
+            CastKind::Implicit => Ok(()),
+            CastKind::Explicit => Err(TypeError::ExplicitCastRequired {
+                from: value.clone(),
+                to: target.clone(),
+            }),
+            CastKind::Forbidden => Err(TypeError::TypeMismatch {
                 expected: target.clone(),
                 found: value.clone(),
-            })
+            }),
         }
     }
 
@@ -316,14 +528,25 @@ impl TypeChecker for NativeTypeChecker {
             | Operation::LessOrEqual
             | Operation::GreaterThan
             | Operation::GreaterOrEqual => {
-                // Comparison operations return bool
-                if operands.len() != 2 {
-                    return Err(TypeError::InvalidOperation {
+                // Comparison operations return bool. Numbers compare only at
+                // the same width, as arithmetic does (mixed widths need an
+                // explicit CAST); String and Char only with themselves; Bool
+                // supports equality but not ordering.
+                let ordering = !matches!(op, Operation::Equal | Operation::NotEqual);
+                let comparable = match operands {
+                    [lhs, rhs] if is_numeric(lhs) => lhs == rhs,
+                    [OasmType::String, OasmType::String] | [OasmType::Char, OasmType::Char] => true,
+                    [OasmType::Bool, OasmType::Bool] => !ordering,
+                    _ => false,
+                };
+                if comparable {
+                    Ok(OasmType::Bool)
+                } else {
+                    Err(TypeError::InvalidOperation {
                         op: op.clone(),
                         operands: operands.to_vec(),
-                    });
+                    })
                 }
-                Ok(OasmType::Bool)
             }
             Operation::And | Operation::Or => {
                 // Logical operations on bools
@@ -439,6 +662,11 @@ impl TypeChecker for NativeTypeChecker {
             _ => false,
         }
     }
+
+    fn can_cast_explicit(&self, from: &OasmType, to: &OasmType) -> bool {
+        // Any numeric type converts to any other (narrowing checked at runtime)
+        self.can_cast(from, to) || (is_numeric(from) && is_numeric(to))
+    }
 }
 
 #[cfg(test)]
@@ -450,7 +678,7 @@ mod tests {
         let checker = NativeTypeChecker;
 
         assert_eq!(checker.infer_type(&Value::U32(42)), OasmType::U32);
-        assert_eq!(checker.infer_type(&Value::F64(3.14)), OasmType::F64);
+        assert_eq!(checker.infer_type(&Value::F64(2.5)), OasmType::F64);
         assert_eq!(checker.infer_type(&Value::Bool(true)), OasmType::Bool);
         assert_eq!(
             checker.infer_type(&Value::String("hello".to_string())),
@@ -491,6 +719,36 @@ mod tests {
         assert_eq!(result.unwrap(), OasmType::Bool);
     }
 
+    #[test]
+    fn test_validate_comparison_operand_compatibility() {
+        let checker = NativeTypeChecker;
+
+        assert_eq!(
+            checker.validate_operation(&Operation::Equal, &[OasmType::String, OasmType::String]).unwrap(),
+            OasmType::Bool
+        );
+        assert!(checker.validate_operation(&Operation::NotEqual, &[OasmType::Bool, OasmType::Bool]).is_ok());
+        assert!(checker.validate_operation(&Operation::LessThan, &[OasmType::F64, OasmType::F64]).is_ok());
+
+        // Mixed widths need an explicit cast, as for arithmetic
+        for operands in [[OasmType::U8, OasmType::F64], [OasmType::I32, OasmType::I64], [OasmType::U64, OasmType::I64]] {
+            assert!(matches!(
+                checker.validate_operation(&Operation::Equal, &operands),
+                Err(TypeError::InvalidOperation { .. })
+            ));
+        }
+
+        assert!(matches!(
+            checker.validate_operation(&Operation::LessThan, &[OasmType::Bool, OasmType::Bool]),
+            Err(TypeError::InvalidOperation { .. })
+        ));
+        assert!(matches!(
+            checker.validate_operation(&Operation::Equal, &[OasmType::Vector3, OasmType::I32]),
+            Err(TypeError::InvalidOperation { .. })
+        ));
+        assert!(checker.validate_operation(&Operation::Equal, &[OasmType::String, OasmType::Char]).is_err());
+    }
+
     #[test]
     fn test_validate_vector_operations() {
         let checker = NativeTypeChecker;
@@ -524,4 +782,71 @@ mod tests {
         assert!(!checker.can_cast(&OasmType::Bool, &OasmType::U32));
         assert!(!checker.can_cast(&OasmType::String, &OasmType::F64));
     }
+
+    #[test]
+    fn test_cast_kind() {
+        let checker = NativeTypeChecker;
+
+        assert_eq!(checker.cast_kind(&OasmType::U64, &OasmType::U32), CastKind::Explicit);
+        assert_eq!(checker.cast_kind(&OasmType::F64, &OasmType::I32), CastKind::Explicit);
+        assert_eq!(checker.cast_kind(&OasmType::U8, &OasmType::U32), CastKind::Implicit);
+        assert_eq!(checker.cast_kind(&OasmType::String, &OasmType::F64), CastKind::Forbidden);
+        assert!(checker.can_cast_explicit(&OasmType::U64, &OasmType::U32));
+        assert!(!checker.can_cast_explicit(&OasmType::Bool, &OasmType::U8));
+
+        // Explicit casts are rejected in plain assignment
+        assert!(matches!(
+            checker.check_assignment(&OasmType::U32, &OasmType::U64),
+            Err(TypeError::ExplicitCastRequired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_is_widening() {
+        assert!(is_widening(&OasmType::U32, &OasmType::I64));
+        assert!(is_widening(&OasmType::I32, &OasmType::F64));
+        assert!(is_widening(&OasmType::F32, &OasmType::F64));
+        assert!(!is_widening(&OasmType::I8, &OasmType::U64));
+        assert!(!is_widening(&OasmType::U64, &OasmType::I64));
+        assert!(!is_widening(&OasmType::I64, &OasmType::F64));
+        assert!(!is_widening(&OasmType::U32, &OasmType::F32));
+        assert!(!is_widening(&OasmType::F64, &OasmType::F32));
+    }
+
+    #[test]
+    fn test_value_cast_to() {
+        assert_eq!(Value::U64(300).cast_to(&OasmType::U16), Some(Value::U16(300)));
+        assert_eq!(Value::U64(300).cast_to(&OasmType::U8), None);
+        assert_eq!(Value::F64(-2.9).cast_to(&OasmType::I32), Some(Value::I32(-2)));
+        assert_eq!(Value::F64(-2.9).cast_to(&OasmType::U32), None);
+        assert_eq!(Value::U32(7).cast_to(&OasmType::F32), Some(Value::F32(7.0)));
+        assert_eq!(Value::Bool(true).cast_to(&OasmType::U8), None);
+    }
+
+    #[test]
+    fn test_explicit_cast() {
+        let checker = NativeTypeChecker;
+
+        // Narrowing succeeds while the value fits
+        assert_eq!(checker.explicit_cast(&Value::U64(4_000), &OasmType::U32).unwrap(), Value::U32(4_000));
+        assert!(matches!(
+            checker.explicit_cast(&Value::U64(5_000_000_000), &OasmType::U32),
+            Err(TypeError::CastOutOfRange { .. })
+        ));
+
+        // Float to int truncates toward zero; NaN, infinity and overflow fail
+        assert_eq!(checker.explicit_cast(&Value::F64(2.9), &OasmType::I32).unwrap(), Value::I32(2));
+        assert_eq!(checker.explicit_cast(&Value::F64(-2.9), &OasmType::I32).unwrap(), Value::I32(-2));
+        for bad in [f64::NAN, f64::INFINITY, 3e9] {
+            assert!(matches!(
+                checker.explicit_cast(&Value::F64(bad), &OasmType::I32),
+                Err(TypeError::CastOutOfRange { .. })
+            ));
+        }
+
+        assert!(matches!(
+            checker.explicit_cast(&Value::U32(1), &OasmType::String),
+            Err(TypeError::InvalidCast { .. })
+        ));
+    }
 }
@@ -1,11 +1,13 @@
-/// OASM Validators - Type, Topology, and Rules validation
+//! OASM Validators - Type, Topology, and Rules validation
 
 pub mod type_validator;
 pub mod topology_validator;
 pub mod rules_validator;
+pub mod suppression;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use suppression::{SuppressedIssue, Suppression};
 
 /// Validation result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +16,9 @@ pub struct ValidationReport {
     pub validator: String,
     pub issues: Vec<ValidationIssue>,
     pub metadata: HashMap<String, String>,
+    /// Issues downgraded by a suppression, kept so they stay visible
+    #[serde(default)]
+    pub suppressed: Vec<SuppressedIssue>,
 }
 
 /// Validation issue
@@ -33,6 +38,16 @@ pub enum IssueSeverity {
     Info,
 }
 
+impl From<crate::Severity> for IssueSeverity {
+    fn from(severity: crate::Severity) -> Self {
+        match severity {
+            crate::Severity::Error => IssueSeverity::Error,
+            crate::Severity::Warning => IssueSeverity::Warning,
+            crate::Severity::Info => IssueSeverity::Info,
+        }
+    }
+}
+
 /// Location of a validation issue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IssueLocation {
@@ -49,6 +64,7 @@ impl ValidationReport {
             validator,
             issues: Vec::new(),
             metadata: HashMap::new(),
+            suppressed: Vec::new(),
         }
     }
 
@@ -93,12 +109,43 @@ impl ValidationReport {
             .count()
     }
 
+    /// Metadata key holding how many objects and variables were checked
+    pub const CHECKED_KEY: &'static str = "checked";
+    /// Penalty weights per issue, by severity
+    pub const ERROR_WEIGHT: f64 = 10.0;
+    pub const WARNING_WEIGHT: f64 = 3.0;
+    pub const INFO_WEIGHT: f64 = 0.5;
+
+    /// Headline health number from 100 (no issues) towards 0. The weighted
+    /// issue penalty is spread over the checked items (`CHECKED_KEY`
+    /// metadata, at least one), so a large project with a few warnings
+    /// scores higher than a small one with the same warnings.
+    pub fn health_score(&self) -> f64 {
+        let penalty: f64 = self
+            .issues
+            .iter()
+            .map(|issue| match issue.severity {
+                IssueSeverity::Error => Self::ERROR_WEIGHT,
+                IssueSeverity::Warning => Self::WARNING_WEIGHT,
+                IssueSeverity::Info => Self::INFO_WEIGHT,
+            })
+            .sum();
+        let checked = self
+            .metadata
+            .get(Self::CHECKED_KEY)
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(0)
+            .max(1);
+        100.0 / (1.0 + penalty / checked as f64)
+    }
+
     pub fn merge(&mut self, other: ValidationReport) {
         if !other.passed {
             self.passed = false;
         }
         self.issues.extend(other.issues);
         self.metadata.extend(other.metadata);
+        self.suppressed.extend(other.suppressed);
     }
 }
 
@@ -118,8 +165,14 @@ impl CombinedValidator {
         }
     }
 
-    /// Run all validators and combine results
+    /// Run all validators and combine results, applying the context's
+    /// suppressions as of today
     pub fn validate_all(&self, context: &ValidationContext) -> ValidationReport {
+        self.validate_all_on(context, chrono::Utc::now().date_naive())
+    }
+
+    /// `validate_all` with suppression expiry judged on `today`
+    pub fn validate_all_on(&self, context: &ValidationContext, today: chrono::NaiveDate) -> ValidationReport {
         let mut combined = ValidationReport::new("combined".to_string());
 
         // Run type validation
@@ -136,6 +189,14 @@ impl CombinedValidator {
         let rules_report = self.rules_validator.validate(context);
         combined.merge(rules_report);
 
+        let engine = self.rules_validator.engine();
+        suppression::apply_suppressions(&mut combined, context, today, |code| engine.is_unsuppressible(code));
+
+        let checked = context.objects.len() + context.variables.len();
+        combined.metadata.insert(ValidationReport::CHECKED_KEY.to_string(), checked.to_string());
+        combined
+            .metadata
+            .insert("health_score".to_string(), format!("{:.1}", combined.health_score()));
         combined
     }
 }
@@ -153,6 +214,8 @@ pub struct ValidationContext {
     pub objects: HashMap<String, crate::context::Object>,
     pub variables: HashMap<String, crate::context::Variable>,
     pub properties: HashMap<String, String>,
+    /// Script-level suppressions in effect (object-level ones live on the objects)
+    pub suppressions: Vec<Suppression>,
 }
 
 impl ValidationContext {
@@ -162,6 +225,7 @@ impl ValidationContext {
             objects: HashMap::new(),
             variables: HashMap::new(),
             properties: HashMap::new(),
+            suppressions: Vec::new(),
         }
     }
 }
@@ -196,4 +260,98 @@ mod tests {
         assert_eq!(report1.error_count(), 1);
         assert_eq!(report1.warning_count(), 1);
     }
+
+    #[test]
+    fn test_health_score() {
+        let clean = ValidationReport::new("clean".to_string());
+        assert_eq!(clean.health_score(), 100.0);
+
+        let mut errors = ValidationReport::new("errors".to_string());
+        errors.add_error("E001".to_string(), "Error".to_string());
+        let mut warnings = ValidationReport::new("warnings".to_string());
+        warnings.add_warning("W001".to_string(), "Warning".to_string());
+        assert!(errors.health_score() < warnings.health_score());
+        assert!(warnings.health_score() < 100.0);
+
+        // The same issue weighs less over more checked items
+        warnings.metadata.insert(ValidationReport::CHECKED_KEY.to_string(), "30".to_string());
+        assert!(warnings.health_score() > 90.0);
+
+        let report = CombinedValidator::new()
+            .validate_all_on(&lidless_box(r#"NOT_WATERTIGHT until=2025-01-31 "lid""#), today());
+        assert_eq!(report.metadata[ValidationReport::CHECKED_KEY], "1");
+        assert_eq!(report.metadata["health_score"], format!("{:.1}", report.health_score()));
+        assert!(report.health_score() < 100.0);
+    }
+
+    fn lidless_box(suppress: &str) -> ValidationContext {
+        let mut object = crate::context::Object {
+            id: "box".to_string(),
+            object_type: "mesh".to_string(),
+            properties: HashMap::new(),
+            created: chrono::Utc::now(),
+        };
+        object.properties.insert("open_edges".to_string(), crate::types::Value::Bool(true));
+        object.properties.insert(
+            suppression::SUPPRESS_PROPERTY.to_string(),
+            crate::types::Value::Array(vec![crate::types::Value::String(suppress.to_string())]),
+        );
+        let mut context = ValidationContext::new("cad".to_string());
+        context.objects.insert("box".to_string(), object);
+        context
+    }
+
+    fn today() -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()
+    }
+
+    #[test]
+    fn test_active_suppression_downgrades_issue() {
+        let context = lidless_box(r#"NOT_WATERTIGHT until=2025-06-01 "lid is added in assembly""#);
+        let report = CombinedValidator::new().validate_all_on(&context, today());
+
+        assert!(report.passed);
+        let issue = report.issues.iter().find(|i| i.code == "NOT_WATERTIGHT").unwrap();
+        assert_eq!(issue.severity, IssueSeverity::Info);
+        assert!(issue.message.starts_with(suppression::SUPPRESSED_TAG));
+
+        assert_eq!(report.suppressed.len(), 1);
+        let suppressed = &report.suppressed[0];
+        assert_eq!(suppressed.original_severity, IssueSeverity::Error);
+        assert_eq!(suppressed.object_id.as_deref(), Some("box"));
+        assert_eq!(suppressed.reason, "lid is added in assembly");
+    }
+
+    #[test]
+    fn test_expired_suppression_is_ignored_with_warning() {
+        let context = lidless_box(r#"NOT_WATERTIGHT until=2025-01-31 "lid is added in assembly""#);
+        let report = CombinedValidator::new().validate_all_on(&context, today());
+
+        assert!(!report.passed);
+        assert!(report.suppressed.is_empty());
+        let expired = report.issues.iter().find(|i| i.code == "SUPPRESSION_EXPIRED").unwrap();
+        assert_eq!(expired.severity, IssueSeverity::Warning);
+        assert!(expired.message.contains("expired on 2025-01-31"));
+    }
+
+    #[test]
+    fn test_core_error_cannot_be_suppressed() {
+        let mut context = ValidationContext::new("cad".to_string());
+        context.variables.insert(
+            "teeth".to_string(),
+            crate::context::Variable {
+                name: "teeth".to_string(),
+                var_type: crate::types::OasmType::U32,
+                value: None,
+                mutable: true,
+            },
+        );
+        context.suppressions.push(Suppression::new("type_mismatch", "set later by the template"));
+        let report = CombinedValidator::new().validate_all_on(&context, today());
+
+        assert!(!report.passed);
+        assert!(report.suppressed.is_empty());
+        assert!(report.issues.iter().any(|i| i.code == "type_mismatch" && i.severity == IssueSeverity::Error));
+        assert!(report.issues.iter().any(|i| i.code == "SUPPRESSION_FORBIDDEN"));
+    }
 }
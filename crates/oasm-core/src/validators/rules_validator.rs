@@ -1,7 +1,7 @@
-/// Rules validator - validates using hierarchical rule engine
+//! Rules validator - validates using hierarchical rule engine
 
 use super::{IssueSeverity, ValidationContext, ValidationIssue, ValidationReport};
-use crate::rules::{hierarchy, HierarchicalRuleEngine};
+use crate::rules::{hierarchy, ConditionEvaluator, HierarchicalRuleEngine};
 use crate::Severity;
 use std::collections::HashMap;
 
@@ -11,12 +11,8 @@ pub struct RulesValidator {
 
 impl RulesValidator {
     pub fn new() -> Self {
-        let mut engine = HierarchicalRuleEngine::new();
-
         // Load built-in rules (Core + Domain)
-        for hrule in hierarchy::load_builtin_rules() {
-            engine.register_rule(hrule);
-        }
+        let engine = hierarchy::builtin_engine().expect("built-in rule overrides are consistent");
 
         Self { engine }
     }
@@ -63,66 +59,7 @@ impl RulesValidator {
         context: &ValidationContext,
         condition: &crate::Condition,
     ) -> Option<String> {
-        // TODO: Implement actual condition checking logic
-        // For now, placeholder checks based on check_type
-
-        match condition.check_type.as_str() {
-            "type_mismatch" => {
-                // Check if any variables have type mismatches
-                for (name, var) in &context.variables {
-                    if var.value.is_none() {
-                        return Some(format!(
-                            "Variable '{}' declared but not initialized",
-                            name
-                        ));
-                    }
-                }
-                None
-            }
-            "edges_connected" => {
-                // Check if mesh edges are connected (CAD-specific)
-                if context.program_type == "cad" {
-                    for (obj_id, obj) in &context.objects {
-                        if obj.object_type == "mesh"
-                            && obj.properties.contains_key("disconnected_edges")
-                        {
-                            return Some(format!(
-                                "Object '{}' has disconnected edges",
-                                obj_id
-                            ));
-                        }
-                    }
-                }
-                None
-            }
-            "no_circular_refs" => {
-                // Check for circular references (engine-specific)
-                if context.program_type == "engine" {
-                    // TODO: Implement circular reference detection
-                }
-                None
-            }
-            "parameters_in_bounds" => {
-                // Check if parameters are within bounds
-                for (key, value) in &context.properties {
-                    if key.ends_with("_param") {
-                        if let Ok(num_val) = value.parse::<f64>() {
-                            if num_val < 0.0 || num_val > 1000.0 {
-                                return Some(format!(
-                                    "Parameter '{}' out of bounds: {}",
-                                    key, num_val
-                                ));
-                            }
-                        }
-                    }
-                }
-                None
-            }
-            _ => {
-                // Unknown check type - skip
-                None
-            }
-        }
+        ConditionEvaluator::new().evaluate(&condition.check_type, context)
     }
 
     pub fn engine(&self) -> &HierarchicalRuleEngine {
@@ -179,7 +116,7 @@ mod tests {
         );
         context.objects.insert("test_mesh".to_string(), object);
 
-        let report = validator.validate(&context);
+        let _report = validator.validate(&context);
 
         // Should detect the topology issue via rules
         // Note: This depends on the rules being properly configured
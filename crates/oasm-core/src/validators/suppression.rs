@@ -0,0 +1,250 @@
+//! Validation suppressions
+//!
+//! A suppression acknowledges a known issue without disabling the rule that
+//! raises it. Matching issues are downgraded to Info, tagged `[suppressed]`,
+//! and listed in the report's `suppressed` section so they stay visible.
+//!
+//! SOURCES:
+//! - Object level: the reserved `__suppress` property, a string or an array
+//!   of strings `CODE [until=YYYY-MM-DD] "reason"`. Applies to that object's
+//!   issues only.
+//! - Script level: `SUPPRESS CODE [until=YYYY-MM-DD] [next=N] "reason"`,
+//!   active for the next N instructions or until its enclosing scope is
+//!   popped (see `ExecutionContext::active_suppressions`).
+//!
+//! An expired suppression is ignored and reported as a warning naming the
+//! expiry. Error-severity Core rules cannot be suppressed.
+
+use super::{IssueLocation, IssueSeverity, ValidationContext, ValidationIssue, ValidationReport};
+use crate::context::Object;
+use crate::types::Value;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Reserved object property holding object-level suppressions
+pub const SUPPRESS_PROPERTY: &str = "__suppress";
+
+/// Prefix added to the message of a suppressed issue
+pub const SUPPRESSED_TAG: &str = "[suppressed]";
+
+/// Acknowledgement of an issue code, with a justification and optional expiry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suppression {
+    pub code: String,
+    /// Last day the suppression applies; None never expires
+    pub until: Option<NaiveDate>,
+    pub reason: String,
+    /// Object it is attached to; None matches issues anywhere
+    pub object_id: Option<String>,
+}
+
+impl Suppression {
+    pub fn new(code: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self { code: code.into(), until: None, reason: reason.into(), object_id: None }
+    }
+
+    pub fn with_until(mut self, until: NaiveDate) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Parse `CODE [until=YYYY-MM-DD] "reason"`; the reason is required
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (code, mut rest) = spec.split_once(char::is_whitespace).unwrap_or((spec, ""));
+        rest = rest.trim();
+
+        let mut until = None;
+        if let Some(tail) = rest.strip_prefix("until=") {
+            let (date, tail) = tail.split_once(char::is_whitespace).unwrap_or((tail, ""));
+            until = Some(parse_date(date)?);
+            rest = tail.trim();
+        }
+
+        let reason = rest
+            .strip_prefix('"')
+            .and_then(|r| r.strip_suffix('"'))
+            .ok_or_else(|| format!("Suppression '{}' needs a quoted reason", spec))?;
+
+        let suppression = Self { code: code.to_string(), until, reason: reason.to_string(), object_id: None };
+        suppression.check()?;
+        Ok(suppression)
+    }
+
+    /// Reject suppressions without a code or justification
+    pub fn check(&self) -> Result<(), String> {
+        if self.code.is_empty() {
+            return Err("Suppression has no code".to_string());
+        }
+        if self.reason.trim().is_empty() {
+            return Err(format!("Suppression of '{}' needs a reason", self.code));
+        }
+        Ok(())
+    }
+
+    /// True once `today` is past the `until` date
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.until.is_some_and(|until| today > until)
+    }
+
+    /// True if this suppression covers an issue with `code` on `object_id`
+    pub fn matches(&self, code: &str, object_id: Option<&str>) -> bool {
+        self.code == code && (self.object_id.is_none() || self.object_id.as_deref() == object_id)
+    }
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date '{}' (expected YYYY-MM-DD)", date))
+}
+
+/// Suppressions in an object's `__suppress` property, attached to the object
+pub fn object_suppressions(object: &Object) -> Vec<Result<Suppression, String>> {
+    let specs = match object.properties.get(SUPPRESS_PROPERTY) {
+        None => return Vec::new(),
+        Some(Value::String(spec)) => vec![Some(spec.as_str())],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(spec) => Some(spec.as_str()),
+                _ => None,
+            })
+            .collect(),
+        Some(_) => vec![None],
+    };
+
+    specs
+        .into_iter()
+        .map(|spec| {
+            let spec = spec.ok_or_else(|| format!("'{}' entries must be strings", SUPPRESS_PROPERTY))?;
+            let mut suppression = Suppression::parse(spec)?;
+            suppression.object_id = Some(object.id.clone());
+            Ok(suppression)
+        })
+        .collect()
+}
+
+/// Script-level and object-level suppressions of a context, objects in id order
+pub fn context_suppressions(context: &ValidationContext) -> Vec<Result<Suppression, String>> {
+    let mut objects: Vec<&Object> = context.objects.values().collect();
+    objects.sort_by(|a, b| a.id.cmp(&b.id));
+
+    context
+        .suppressions
+        .iter()
+        .cloned()
+        .map(Ok)
+        .chain(objects.into_iter().flat_map(object_suppressions))
+        .collect()
+}
+
+/// An issue downgraded by a suppression (the report's `suppressed` section)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuppressedIssue {
+    pub code: String,
+    pub original_severity: IssueSeverity,
+    pub message: String,
+    pub object_id: Option<String>,
+    pub reason: String,
+    pub until: Option<NaiveDate>,
+}
+
+/// Apply the context's suppressions to a report. `forbidden(code)` names
+/// codes that must not be suppressed at Error severity (Core error rules);
+/// their issues stay errors and a SUPPRESSION_FORBIDDEN warning is added.
+pub fn apply_suppressions(
+    report: &mut ValidationReport,
+    context: &ValidationContext,
+    today: NaiveDate,
+    forbidden: impl Fn(&str) -> bool,
+) {
+    let mut notes = Vec::new();
+    let mut active = Vec::new();
+
+    for suppression in context_suppressions(context) {
+        match suppression {
+            Err(e) => notes.push(note("SUPPRESSION_INVALID", e, None)),
+            Ok(s) if s.is_expired(today) => notes.push(note(
+                "SUPPRESSION_EXPIRED",
+                format!(
+                    "Suppression of '{}' expired on {} ({}); its issues are reported normally",
+                    s.code,
+                    s.until.map(|d| d.to_string()).unwrap_or_default(),
+                    s.reason
+                ),
+                s.object_id.clone(),
+            )),
+            Ok(s) => active.push(s),
+        }
+    }
+
+    let mut refused = HashSet::new();
+    for issue in &mut report.issues {
+        let object_id = issue.location.as_ref().and_then(|l| l.object_id.clone());
+        let Some(suppression) = active.iter().find(|s| s.matches(&issue.code, object_id.as_deref())) else {
+            continue;
+        };
+
+        if issue.severity == IssueSeverity::Error && forbidden(&issue.code) {
+            if refused.insert((issue.code.clone(), suppression.object_id.clone())) {
+                notes.push(note(
+                    "SUPPRESSION_FORBIDDEN",
+                    format!("'{}' is an error-severity Core rule and cannot be suppressed", issue.code),
+                    suppression.object_id.clone(),
+                ));
+            }
+            continue;
+        }
+
+        report.suppressed.push(SuppressedIssue {
+            code: issue.code.clone(),
+            original_severity: issue.severity,
+            message: issue.message.clone(),
+            object_id,
+            reason: suppression.reason.clone(),
+            until: suppression.until,
+        });
+        issue.severity = IssueSeverity::Info;
+        issue.message = format!("{} {}", SUPPRESSED_TAG, issue.message);
+    }
+
+    for issue in notes {
+        report.issues.push(issue);
+    }
+    report.passed = report.error_count() == 0;
+}
+
+fn note(code: &str, message: String, object_id: Option<String>) -> ValidationIssue {
+    ValidationIssue {
+        severity: IssueSeverity::Warning,
+        code: code.to_string(),
+        message,
+        location: object_id.map(|object_id| IssueLocation {
+            file: None,
+            line: None,
+            column: None,
+            object_id: Some(object_id),
+        }),
+        suggestion: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suppression() {
+        let s = Suppression::parse(r#"NOT_WATERTIGHT until=2025-06-01 "open lid is intentional""#).unwrap();
+        assert_eq!(s.code, "NOT_WATERTIGHT");
+        assert_eq!(s.until, Some(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()));
+        assert_eq!(s.reason, "open lid is intentional");
+        assert!(s.is_expired(NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()));
+        assert!(!s.is_expired(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()));
+
+        assert!(Suppression::parse(r#"DEGENERATE_GEOMETRY "known""#).unwrap().until.is_none());
+        assert!(Suppression::parse("NOT_WATERTIGHT until=2025-06-01").is_err());
+        assert!(Suppression::parse(r#"NOT_WATERTIGHT until=June "x""#).is_err());
+        assert!(Suppression::parse(r#"NOT_WATERTIGHT """#).is_err());
+    }
+}
@@ -1,7 +1,13 @@
-/// Topology validator - validates CAD geometry (manifold, watertight, etc.)
+//! Topology validator - validates CAD geometry (manifold, watertight, etc.)
+//!
+//! Objects with a `mesh` property (Value::Mesh) are analyzed directly;
+//! objects without one fall back to marker properties such as
+//! `non_manifold_edges`. Issue messages name the offending edges and faces.
 
 use super::{IssueSeverity, ValidationContext, ValidationIssue, ValidationReport};
+use crate::geometry;
 use crate::types::Value;
+use std::collections::HashMap;
 
 pub struct TopologyValidator {
     strict_mode: bool,
@@ -119,56 +125,101 @@ impl TopologyValidator {
         report
     }
 
-    fn has_geometry(&self, _properties: &std::collections::HashMap<String, Value>) -> bool {
+    fn has_geometry(&self, _properties: &HashMap<String, Value>) -> bool {
         // TODO: Check if properties contain mesh/geometry data
         // For now, assume all objects have geometry
         true
     }
 
-    fn check_manifold(&self, properties: &std::collections::HashMap<String, Value>) -> Result<(), String> {
-        // TODO: Implement actual manifold checking
-        // A mesh is manifold if every edge is connected to exactly 2 faces
-        // For now, placeholder check
-        if properties.contains_key("non_manifold_edges") {
-            return Err("Mesh has non-manifold edges".to_string());
-        }
-        Ok(())
+    fn check_manifold(&self, properties: &HashMap<String, Value>) -> Result<(), String> {
+        // A mesh is manifold if no edge is shared by more than 2 faces
+        let Some((_, faces)) = mesh_of(properties) else {
+            return marker(properties, "non_manifold_edges", "Mesh has non-manifold edges");
+        };
+        let bad: Vec<String> = geometry::edge_faces(faces)
+            .into_iter()
+            .filter(|(_, users)| users.len() > 2)
+            .map(|(edge, users)| format!("edge {:?} shared by faces {:?}", edge, users))
+            .collect();
+        offenders(bad)
     }
 
-    fn check_watertight(&self, properties: &std::collections::HashMap<String, Value>) -> Result<(), String> {
-        // TODO: Implement actual watertight checking
-        // A mesh is watertight if all edges form closed loops
-        if properties.contains_key("open_edges") {
-            return Err("Mesh has open edges".to_string());
-        }
-        Ok(())
+    fn check_watertight(&self, properties: &HashMap<String, Value>) -> Result<(), String> {
+        // A mesh is watertight if every edge has a face on both sides
+        let Some((_, faces)) = mesh_of(properties) else {
+            return marker(properties, "open_edges", "Mesh has open edges");
+        };
+        let bad: Vec<String> = geometry::edge_faces(faces)
+            .into_iter()
+            .filter(|(_, users)| users.len() < 2)
+            .map(|(edge, users)| format!("open edge {:?} of face {}", edge, users[0]))
+            .collect();
+        offenders(bad)
     }
 
-    fn check_no_self_intersections(&self, properties: &std::collections::HashMap<String, Value>) -> Result<(), String> {
-        // TODO: Implement actual self-intersection detection
-        // Check if any faces intersect each other
-        if properties.contains_key("self_intersecting") {
-            return Err("Mesh has self-intersecting faces".to_string());
-        }
-        Ok(())
+    fn check_no_self_intersections(&self, properties: &HashMap<String, Value>) -> Result<(), String> {
+        // Bounding-box overlap of faces that share no vertex (heuristic)
+        let Some((vertices, faces)) = mesh_of(properties) else {
+            return marker(properties, "self_intersecting", "Mesh has self-intersecting faces");
+        };
+        let bad: Vec<String> = geometry::overlapping_faces(vertices, faces)
+            .into_iter()
+            .map(|(a, b)| format!("faces {} and {} overlap", a, b))
+            .collect();
+        offenders(bad)
     }
 
-    fn check_face_normals(&self, properties: &std::collections::HashMap<String, Value>) -> Result<(), String> {
-        // TODO: Implement normal consistency checking
-        // Check if all face normals point outward consistently
-        if properties.contains_key("flipped_normals") {
-            return Err("Mesh has inconsistent face normals".to_string());
-        }
-        Ok(())
+    fn check_face_normals(&self, properties: &HashMap<String, Value>) -> Result<(), String> {
+        // Neighbouring faces must wind the same way, so normals all point out (or all in)
+        let Some((_, faces)) = mesh_of(properties) else {
+            return marker(properties, "flipped_normals", "Mesh has inconsistent face normals");
+        };
+        let bad: Vec<String> = geometry::inconsistent_windings(faces)
+            .into_iter()
+            .map(|(a, b)| format!("faces {} and {} wind in opposite directions", a, b))
+            .collect();
+        offenders(bad)
     }
 
-    fn check_no_degenerate_faces(&self, properties: &std::collections::HashMap<String, Value>) -> Result<(), String> {
-        // TODO: Implement degenerate geometry detection
-        // Check for zero-area faces, duplicate vertices, etc.
-        if properties.contains_key("degenerate_faces") {
-            return Err("Mesh has degenerate faces".to_string());
-        }
-        Ok(())
+    fn check_no_degenerate_faces(&self, properties: &HashMap<String, Value>) -> Result<(), String> {
+        let Some((vertices, faces)) = mesh_of(properties) else {
+            return marker(properties, "degenerate_faces", "Mesh has degenerate faces");
+        };
+        let bad: Vec<String> = geometry::degenerate_faces(vertices, faces)
+            .into_iter()
+            .map(|face| format!("face {} has zero area", face))
+            .collect();
+        offenders(bad)
+    }
+}
+
+/// Vertices and faces of a mesh value
+type MeshRef<'a> = (&'a [[f64; 3]], &'a [Vec<usize>]);
+
+/// Mesh in an object's `mesh` property (where STATS reads it from)
+fn mesh_of(properties: &HashMap<String, Value>) -> Option<MeshRef<'_>> {
+    match properties.get("mesh") {
+        Some(Value::Mesh { vertices, faces }) => Some((vertices, faces)),
+        _ => None,
+    }
+}
+
+/// Objects without mesh data can flag problems with marker properties
+fn marker(properties: &HashMap<String, Value>, key: &str, message: &str) -> Result<(), String> {
+    if properties.contains_key(key) {
+        return Err(message.to_string());
+    }
+    Ok(())
+}
+
+/// Most offenders listed in an issue message
+const MAX_LISTED: usize = 5;
+
+fn offenders(bad: Vec<String>) -> Result<(), String> {
+    match bad.len() {
+        0 => Ok(()),
+        n if n <= MAX_LISTED => Err(bad.join("; ")),
+        n => Err(format!("{}; and {} more", bad[..MAX_LISTED].join("; "), n - MAX_LISTED)),
     }
 }
 
@@ -217,4 +268,73 @@ mod tests {
         assert!(!report.passed);
         assert!(report.error_count() > 0);
     }
+
+    fn cube_faces() -> Vec<Vec<usize>> {
+        vec![
+            vec![0, 2, 3, 1], vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4], vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2], vec![1, 3, 7, 5],
+        ]
+    }
+
+    fn mesh_context(faces: Vec<Vec<usize>>) -> ValidationContext {
+        let vertices = (0..8)
+            .map(|i| [(i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64])
+            .collect();
+        let object = Object {
+            id: "box".to_string(),
+            object_type: "mesh".to_string(),
+            properties: HashMap::from([("mesh".to_string(), Value::Mesh { vertices, faces })]),
+            created: Utc::now(),
+        };
+        let mut context = ValidationContext::new("cad".to_string());
+        context.objects.insert("box".to_string(), object);
+        context
+    }
+
+    fn codes(report: &ValidationReport) -> Vec<&str> {
+        report.issues.iter().map(|issue| issue.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_closed_cube_passes() {
+        let report = TopologyValidator::new().with_strict_mode(true).validate(&mesh_context(cube_faces()));
+        assert!(report.passed);
+        assert!(report.issues.is_empty());
+    }
+
+    #[test]
+    fn test_open_box_is_not_watertight() {
+        let mut faces = cube_faces();
+        faces.remove(1); // no lid
+        let report = TopologyValidator::new().validate(&mesh_context(faces));
+
+        assert!(!report.passed);
+        assert_eq!(codes(&report), vec!["NOT_WATERTIGHT"]);
+        let issue = &report.issues[0];
+        assert_eq!(issue.location.as_ref().and_then(|l| l.object_id.as_deref()), Some("box"));
+        assert!(issue.message.contains("open edge (4, 5) of face 1"), "{}", issue.message);
+    }
+
+    #[test]
+    fn test_flipped_face_has_inconsistent_normals() {
+        let mut faces = cube_faces();
+        faces[1].reverse();
+        let context = mesh_context(faces);
+
+        assert!(TopologyValidator::new().validate(&context).passed);
+        let report = TopologyValidator::new().with_strict_mode(true).validate(&context);
+        assert_eq!(codes(&report), vec!["INCONSISTENT_NORMALS"]);
+        assert!(report.issues[0].message.contains("faces 1 and"));
+    }
+
+    #[test]
+    fn test_degenerate_face_is_reported() {
+        let mut faces = cube_faces();
+        faces.push(vec![0, 1, 0]);
+        let report = TopologyValidator::new().validate(&mesh_context(faces));
+
+        let degenerate = report.issues.iter().find(|i| i.code == "DEGENERATE_GEOMETRY").unwrap();
+        assert!(degenerate.message.contains("face 6 has zero area"));
+    }
 }
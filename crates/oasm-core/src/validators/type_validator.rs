@@ -1,7 +1,19 @@
-/// Type validator - validates type safety and correctness
+//! Type validator - validates type safety and correctness
+//!
+//! ISSUE CODES (stable; dashboards aggregate on them):
+//! - `TYPE_MISMATCH` (error): a variable's value cannot be assigned to its declared type
+//! - `LOSSY_VALUE` (error): the value only fits its declared type through a
+//!   narrowing CAST that can lose precision or range, e.g. an F64 stored where
+//!   F32 was declared
+//! - `CAST_REQUIRED` (warning): the value needs an explicit CAST to its declared
+//!   type, but the cast is widening and loses nothing (a U32 in an I64)
+//! - `UNINITIALIZED` (error): an immutable variable has no value and never can
+//! - `UNKNOWN_PROPERTY_TYPE` (warning): a property's type cannot be inferred
+//!   (e.g. an empty array)
+//! - `INVALID_PROPERTY_TYPE` (warning): a property type the object type does not expect
 
-use super::{IssueSeverity, ValidationContext, ValidationIssue, ValidationReport};
-use crate::types::{NativeTypeChecker, OasmType, TypeChecker, TypeError};
+use super::{IssueLocation, IssueSeverity, ValidationContext, ValidationIssue, ValidationReport};
+use crate::types::{is_widening, NativeTypeChecker, OasmType, TypeChecker, TypeError};
 
 pub struct TypeValidator {
     type_checker: NativeTypeChecker,
@@ -17,40 +29,92 @@ impl TypeValidator {
     pub fn validate(&self, context: &ValidationContext) -> ValidationReport {
         let mut report = ValidationReport::new("type_validator".to_string());
 
-        // Validate all variables
-        for (name, variable) in &context.variables {
-            if let Some(value) = &variable.value {
-                // Infer the actual type from the value
-                let inferred_type = self.type_checker.infer_type(value);
-
-                // Check if it matches the declared type
-                if let Err(type_error) = self
-                    .type_checker
-                    .check_assignment(&variable.var_type, &inferred_type)
-                {
+        // Sorted so reports are stable across runs
+        let mut variables: Vec<_> = context.variables.iter().collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (name, variable) in variables {
+            let Some(value) = &variable.value else {
+                if !variable.mutable {
                     report.add_issue(ValidationIssue {
                         severity: IssueSeverity::Error,
-                        code: "TYPE_MISMATCH".to_string(),
-                        message: format!(
-                            "Variable '{}' has type mismatch: {}",
-                            name, type_error
-                        ),
+                        code: "UNINITIALIZED".to_string(),
+                        message: format!("Immutable variable '{}' has no value", name),
                         location: None,
-                        suggestion: Some(format!(
-                            "Ensure the value matches the declared type '{:?}'",
-                            variable.var_type
-                        )),
+                        suggestion: Some("Give it a value where it is declared, or make it mutable".to_string()),
                     });
                 }
+                continue;
+            };
+
+            let inferred_type = self.type_checker.infer_type(value);
+            match self.type_checker.check_assignment(&variable.var_type, &inferred_type) {
+                Ok(()) => {}
+                Err(TypeError::ExplicitCastRequired { from, to }) if is_widening(&from, &to) => {
+                    report.add_issue(ValidationIssue {
+                        severity: IssueSeverity::Warning,
+                        code: "CAST_REQUIRED".to_string(),
+                        message: format!("Variable '{}' is declared {:?} but holds a {:?} value", name, to, from),
+                        location: None,
+                        suggestion: Some(format!("CAST the value to {:?}", to)),
+                    })
+                }
+                Err(TypeError::ExplicitCastRequired { from, to }) => report.add_issue(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    code: "LOSSY_VALUE".to_string(),
+                    message: format!(
+                        "Variable '{}' is declared {:?} but holds a {:?} value; it may lose precision or range",
+                        name, to, from
+                    ),
+                    location: None,
+                    suggestion: Some(format!("CAST the value to {:?}, or declare '{}' as {:?}", to, name, from)),
+                }),
+                Err(type_error) => report.add_issue(ValidationIssue {
+                    severity: IssueSeverity::Error,
+                    code: "TYPE_MISMATCH".to_string(),
+                    message: format!("Variable '{}' has type mismatch: {}", name, type_error),
+                    location: None,
+                    suggestion: Some(format!(
+                        "Ensure the value matches the declared type '{:?}'",
+                        variable.var_type
+                    )),
+                }),
             }
         }
 
-        // Validate object properties
-        for (obj_id, object) in &context.objects {
-            for (prop_name, prop_value) in &object.properties {
-                // Infer type from value
+        let mut objects: Vec<_> = context.objects.iter().collect();
+        objects.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (obj_id, object) in objects {
+            let location = || {
+                Some(IssueLocation {
+                    file: None,
+                    line: None,
+                    column: None,
+                    object_id: Some(obj_id.clone()),
+                })
+            };
+
+            let mut properties: Vec<_> = object.properties.iter().collect();
+            properties.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (prop_name, prop_value) in properties {
                 let inferred_type = self.type_checker.infer_type(prop_value);
 
+                if contains_unknown(&inferred_type) {
+                    report.add_issue(ValidationIssue {
+                        severity: IssueSeverity::Warning,
+                        code: "UNKNOWN_PROPERTY_TYPE".to_string(),
+                        message: format!(
+                            "Object '{}' property '{}' has a type that cannot be inferred ({:?})",
+                            obj_id, prop_name, inferred_type
+                        ),
+                        location: location(),
+                        suggestion: Some("Set a value with a concrete type".to_string()),
+                    });
+                    continue;
+                }
+
                 // Check if the type is valid for this object type
                 if let Err(e) = self.validate_property_type(&object.object_type, prop_name, &inferred_type) {
                     report.add_issue(ValidationIssue {
@@ -60,12 +124,7 @@ impl TypeValidator {
                             "Object '{}' property '{}' has unexpected type: {}",
                             obj_id, prop_name, e
                         ),
-                        location: Some(super::IssueLocation {
-                            file: None,
-                            line: None,
-                            column: None,
-                            object_id: Some(obj_id.clone()),
-                        }),
+                        location: location(),
                         suggestion: None,
                     });
                 }
@@ -87,6 +146,15 @@ impl TypeValidator {
     }
 }
 
+/// True if `ty` is Unknown or built from an Unknown (an empty array's elements)
+fn contains_unknown(ty: &OasmType) -> bool {
+    match ty {
+        OasmType::Unknown => true,
+        OasmType::Array { element_type, .. } => contains_unknown(element_type),
+        _ => false,
+    }
+}
+
 impl Default for TypeValidator {
     fn default() -> Self {
         Self::new()
@@ -96,7 +164,7 @@ impl Default for TypeValidator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::context::Variable;
+    use crate::context::{Object, Variable};
     use crate::types::{OasmType, Value};
 
     #[test]
@@ -118,6 +186,74 @@ mod tests {
         assert_eq!(report.error_count(), 1);
     }
 
+    fn codes(report: &ValidationReport) -> Vec<(&str, IssueSeverity)> {
+        report.issues.iter().map(|i| (i.code.as_str(), i.severity)).collect()
+    }
+
+    fn variable(name: &str, var_type: OasmType, value: Option<Value>, mutable: bool) -> (String, Variable) {
+        (name.to_string(), Variable { name: name.to_string(), var_type, value, mutable })
+    }
+
+    #[test]
+    fn test_mismatch_codes_are_stable() {
+        let validator = TypeValidator::new();
+        let mut context = ValidationContext::new("test".to_string());
+        context.variables.extend([
+            variable("flag", OasmType::Bool, Some(Value::U8(1)), false),
+            variable("ratio", OasmType::F32, Some(Value::F64(1.5)), true),
+            variable("signed", OasmType::I64, Some(Value::U32(7)), true),
+            variable("wide", OasmType::F64, Some(Value::F32(1.5)), true),
+        ]);
+
+        let report = validator.validate(&context);
+        assert_eq!(
+            codes(&report),
+            vec![
+                ("TYPE_MISMATCH", IssueSeverity::Error),
+                ("LOSSY_VALUE", IssueSeverity::Error),
+                ("CAST_REQUIRED", IssueSeverity::Warning),
+            ]
+        );
+        assert!(report.issues[0].message.contains("'flag'"));
+        assert!(report.issues[1].message.contains("declared F32 but holds a F64"));
+        assert!(report.issues[2].message.contains("'signed' is declared I64 but holds a U32"));
+    }
+
+    #[test]
+    fn test_uninitialized_immutable_variable() {
+        let validator = TypeValidator::new();
+        let mut context = ValidationContext::new("test".to_string());
+        context.variables.extend([
+            variable("limit", OasmType::U32, None, false),
+            variable("counter", OasmType::U32, None, true),
+        ]);
+
+        let report = validator.validate(&context);
+        assert!(!report.passed);
+        assert_eq!(codes(&report), vec![("UNINITIALIZED", IssueSeverity::Error)]);
+        assert!(report.issues[0].message.contains("'limit'"));
+    }
+
+    #[test]
+    fn test_unknown_property_type() {
+        let validator = TypeValidator::new();
+        let mut context = ValidationContext::new("test".to_string());
+        let mut object = Object {
+            id: "gear".to_string(),
+            object_type: "cad".to_string(),
+            properties: std::collections::HashMap::new(),
+            created: chrono::Utc::now(),
+        };
+        object.properties.insert("holes".to_string(), Value::Array(Vec::new()));
+        object.properties.insert("teeth".to_string(), Value::U32(20));
+        context.objects.insert("gear".to_string(), object);
+
+        let report = validator.validate(&context);
+        assert!(report.passed);
+        assert_eq!(codes(&report), vec![("UNKNOWN_PROPERTY_TYPE", IssueSeverity::Warning)]);
+        assert_eq!(report.issues[0].location.as_ref().unwrap().object_id.as_deref(), Some("gear"));
+    }
+
     #[test]
     fn test_valid_types() {
         let validator = TypeValidator::new();
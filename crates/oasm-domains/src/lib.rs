@@ -1,3 +1,4 @@
 pub mod cad;
+pub mod lineage;
 
 pub use cad::{CADOperations, NativeCADEngine, OpenCascadeEngine};
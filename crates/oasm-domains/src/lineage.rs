@@ -0,0 +1,63 @@
+//! Bridges in-script test annotations from the execution context into
+//! JSON lineage test records
+use asm_formats::lineage::LineageHook;
+use asm_formats::schemas::TestRecord;
+use asm_formats::TestStatus;
+use oasm_core::context::{ExecutionContext, TestAnnotation};
+
+/// Convert one annotation to a lineage test record
+pub fn to_test_record(index: usize, annotation: TestAnnotation) -> TestRecord {
+    let test_id = match annotation.line_number {
+        Some(line) => format!("assert_line_{}", line),
+        None => format!("assert_{}", index),
+    };
+    let status = if annotation.passed {
+        TestStatus::Passed
+    } else {
+        TestStatus::Failed {
+            reason: annotation.message.clone().unwrap_or_else(|| "assertion failed".to_string()),
+        }
+    };
+
+    TestRecord {
+        test_id,
+        test_name: annotation.name,
+        status,
+        duration_ms: None,
+        logs: annotation.message.into_iter().collect(),
+    }
+}
+
+/// Move the context's pending test annotations into the lineage hook
+pub fn drain_assertions(ctx: &mut ExecutionContext, hook: &mut LineageHook) {
+    for (index, annotation) in ctx.take_pending_tests().into_iter().enumerate() {
+        hook.push_test(to_test_record(index, annotation));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oasm_core::context::Actor;
+    use oasm_core::executor::{InstructionExecutor, NativeExecutor};
+    use oasm_core::parser::{InstructionParser, NativeParser};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_assertions_flow_into_lineage_tests() {
+        let mut ctx = ExecutionContext::new(Actor::System, PathBuf::from("."));
+        let instructions = NativeParser
+            .parse_file("SET teeth = 3\nASSERT teeth == 3\nASSERT teeth >= 4")
+            .unwrap();
+        NativeExecutor::new().execute_batch(&instructions, &mut ctx).unwrap();
+
+        let mut hook = LineageHook::new();
+        drain_assertions(&mut ctx, &mut hook);
+
+        let records = hook.pending();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].test_id, "assert_line_2");
+        assert!(matches!(records[0].status, TestStatus::Passed));
+        assert!(matches!(&records[1].status, TestStatus::Failed { reason } if reason.contains("teeth (3) >= 4")));
+    }
+}
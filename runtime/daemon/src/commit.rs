@@ -1,19 +1,161 @@
 use anyhow::Result;
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 
+/// Options for `commit_text_with`
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// Keep the previous contents as `<path>.bak`
+    pub backup: bool,
+}
+
+/// Why a commit failed (wrapped in anyhow; downcast to inspect)
+#[derive(Debug)]
+pub enum CommitError {
+    DirectoryMissing(PathBuf),
+    ReadOnly(PathBuf),
+    Io { path: PathBuf, source: std::io::Error },
+}
+
+impl std::fmt::Display for CommitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CommitError::DirectoryMissing(dir) => write!(f, "Directory does not exist: {}", dir.display()),
+            CommitError::ReadOnly(dir) => write!(f, "Directory is read-only: {}", dir.display()),
+            CommitError::Io { path, source } => write!(f, "I/O error committing {}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for CommitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CommitError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl CommitError {
+    fn from_io(dir: &Path, path: &Path, e: std::io::Error) -> Self {
+        match e.kind() {
+            ErrorKind::NotFound if !dir.exists() => CommitError::DirectoryMissing(dir.to_path_buf()),
+            ErrorKind::PermissionDenied => CommitError::ReadOnly(dir.to_path_buf()),
+            _ => CommitError::Io { path: path.to_path_buf(), source: e },
+        }
+    }
+}
+
 pub fn commit_text(path: &str, contents: &str) -> Result<()> {
-    let mut tmp = NamedTempFile::new()?;
-    tmp.write_all(contents.as_bytes())?;
-    let _ = tmp.persist(path)?;
+    commit_text_with(path, contents, &CommitOptions::default())
+}
+
+/// Atomically replace `path` with `contents`: write and fsync a temp file in
+/// the same directory, optionally back up the old file, then rename over the
+/// target. An interrupted commit leaves the original untouched.
+pub fn commit_text_with(path: &str, contents: &str, options: &CommitOptions) -> Result<()> {
+    let target = Path::new(path);
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let io_err = |e: std::io::Error| CommitError::from_io(&dir, target, e);
+
+    let mut tmp = NamedTempFile::new_in(&dir).map_err(io_err)?;
+    tmp.write_all(contents.as_bytes()).map_err(io_err)?;
+    tmp.as_file().sync_all().map_err(io_err)?;
+
+    if options.backup && target.exists() {
+        let backup = PathBuf::from(format!("{}.bak", path));
+        std::fs::copy(target, &backup).map_err(io_err)?;
+    }
+
+    replace(tmp, target).map_err(io_err)?;
+    sync_dir(&dir);
+
     log::info!("Committed file: {}", path);
     Ok(())
 }
 
+/// Append `line` to `path`, creating it if needed. Not atomic: an
+/// interrupted append can leave a partial line.
+#[deprecated(note = "appends are not atomic; use commit_text_with")]
+#[allow(dead_code)] // kept for external callers; the daemon binary no longer appends
 pub fn append_line(path: &str, line: &str) -> Result<()> {
     let mut f = OpenOptions::new().create(true).append(true).open(path)?;
     writeln!(f, "{}", line)?;
     log::info!("Appended line to {}: {}", path, line);
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn replace(tmp: NamedTempFile, target: &Path) -> std::io::Result<()> {
+    tmp.persist(target).map(|_| ()).map_err(|e| e.error)
+}
+
+/// MoveFileEx with REPLACE_EXISTING refuses read-only targets, so clear the
+/// attribute and retry once before giving up.
+#[cfg(windows)]
+fn replace(tmp: NamedTempFile, target: &Path) -> std::io::Result<()> {
+    match tmp.persist(target) {
+        Ok(_) => Ok(()),
+        Err(e) if e.error.kind() == ErrorKind::PermissionDenied && target.exists() => {
+            let mut permissions = std::fs::metadata(target)?.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            std::fs::set_permissions(target, permissions)?;
+            e.file.persist(target).map(|_| ()).map_err(|e| e.error)
+        }
+        Err(e) => Err(e.error),
+    }
+}
+
+/// Make the rename durable (best effort; not supported on Windows)
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(handle) = std::fs::File::open(dir) {
+        let _ = handle.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_over_existing_keeps_backup() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("manifest.yaml");
+        std::fs::write(&path, "version: 1\n")?;
+        let path_str = path.to_str().unwrap();
+
+        commit_text_with(path_str, "version: 2\n", &CommitOptions { backup: true })?;
+
+        assert_eq!(std::fs::read_to_string(&path)?, "version: 2\n");
+        assert_eq!(std::fs::read_to_string(format!("{}.bak", path_str))?, "version: 1\n");
+        // No temp files left behind
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_commit_leaves_original_untouched() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let original = dir.path().join("manifest.yaml");
+        std::fs::write(&original, "version: 1\n")?;
+
+        let missing = dir.path().join("missing").join("manifest.yaml");
+        let err = commit_text(missing.to_str().unwrap(), "version: 2\n").unwrap_err();
+
+        assert!(matches!(err.downcast_ref::<CommitError>(), Some(CommitError::DirectoryMissing(_))));
+        assert_eq!(std::fs::read_to_string(&original)?, "version: 1\n");
+        assert!(!Path::new(&format!("{}.bak", original.display())).exists());
+
+        Ok(())
+    }
+}
@@ -1,16 +1,28 @@
 use anyhow::Result;
+use crate::overlay::OverlayWatchConfig;
 
 pub struct Daemon {
     pub watch_paths: Vec<String>,
+    pub overlays: OverlayWatchConfig,
 }
 
 impl Daemon {
     pub fn new(watch_paths: Vec<String>) -> Self {
-        Self { watch_paths }
+        Self { watch_paths, overlays: OverlayWatchConfig::default() }
+    }
+
+    /// Also validate `*.overlay.yaml` files on save
+    pub fn with_overlays(mut self, overlays: OverlayWatchConfig) -> Self {
+        self.overlays = overlays;
+        self
     }
 
     pub fn start(&self) -> Result<()> {
-        log::info!("Daemon starting with {} path(s)", self.watch_paths.len());
-        crate::supervisor::run(&self.watch_paths)
+        log::info!(
+            "Daemon starting with {} path(s), {} overlay dir(s)",
+            self.watch_paths.len(),
+            self.overlays.dirs.len()
+        );
+        crate::supervisor::run(&self.watch_paths, self.overlays.clone())
     }
 }
@@ -9,6 +9,8 @@ pub mod lineage;
 pub mod converter;
 pub mod handler;
 pub mod manifest_loader;
+pub mod overlay;
+pub mod watch;
 
 // Re-export commonly used types and functions
 pub use parser::{parse_manifest, to_yaml};
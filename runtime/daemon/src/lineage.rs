@@ -3,17 +3,23 @@ use chrono::Utc;
 use serde::Serialize;
 use std::fs::OpenOptions;
 use std::io::Write;   // <-- Added import
+use std::path::Path;
 
 const LINEAGE_LOG: &str = "runtime/daemon/lineage/lineage.log";
 const LINEAGE_CBOR: &str = "runtime/daemon/lineage/lineage.cbor";
 
 pub fn record_event(line: &str) -> Result<()> {
+    record_event_to(Path::new(LINEAGE_LOG), line)
+}
+
+/// Append a timestamped `line` to the lineage log at `log`
+pub fn record_event_to(log: &Path, line: &str) -> Result<()> {
     let ts = Utc::now().to_rfc3339();
     let entry = format!("{} {}", ts, line);
     OpenOptions::new()
         .create(true)
         .append(true)
-        .open(LINEAGE_LOG)?
+        .open(log)?
         .write_all(format!("{}\n", entry).as_bytes())?;
     Ok(())
 }
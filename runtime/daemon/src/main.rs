@@ -3,10 +3,11 @@ mod commit;
 mod converter;
 mod handler;
 mod lineage;
+pub mod overlay;
 mod parser;
 mod supervisor;
 mod types;
-mod validator;
+pub mod validator;
 mod watch;
 pub mod manifest_loader;
 
@@ -29,7 +30,15 @@ fn main() {
         "ui/rust_ui/rust_ui.yaml".to_string(),
     ];
 
-    match crate::daemon::Daemon::new(watch_paths).start() {
+    // Overlay dirs that exist in this checkout; validation is on-save only
+    let overlays = crate::overlay::OverlayWatchConfig {
+        dirs: ["overlays"].iter().filter(|d| std::path::Path::new(d).is_dir()).map(|d| d.to_string()).collect(),
+        spool_dir: Some("runtime/daemon/spool".into()),
+        dashboard_log: Some("runtime/daemon/lineage/overlay_dashboard.jsonl".into()),
+        ..Default::default()
+    };
+
+    match crate::daemon::Daemon::new(watch_paths).with_overlays(overlays).start() {
         Ok(_) => {
             log::info!("Supervisor loop exited cleanly");
             std::process::exit(0);
@@ -1,11 +1,12 @@
-/// Manifest Loader
-/// Provides easy location and loading of modules, files, schemas, and settings
-/// based on the master manifest
+//! Manifest Loader
+//! Provides easy location and loading of modules, files, schemas, and settings
+//! based on the master manifest
 
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
-use anyhow::{Result, Context};
+use anyhow::{bail, Context, Result};
+use oasm_core::text_util::closest_match;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MasterManifest {
@@ -25,13 +26,13 @@ pub struct MasterManifest {
     pub health: HealthMonitoring,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct SerializationFormats {
     pub oasm: OasmFormats,
     pub objex: ObjexFormats,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct OasmFormats {
     pub primary: String,
     pub mirror: String,
@@ -39,7 +40,7 @@ pub struct OasmFormats {
     pub schemas: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ObjexFormats {
     pub archive: String,
     pub runtime: String,
@@ -47,7 +48,7 @@ pub struct ObjexFormats {
     pub metadata: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ModuleInfo {
     pub id: String,
     pub name: String,
@@ -64,7 +65,7 @@ pub struct ModuleInfo {
     pub dependencies: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ConfigLocations {
     pub runtime: ConfigFile,
     pub ui: ConfigFile,
@@ -73,14 +74,14 @@ pub struct ConfigLocations {
     pub compiler: ConfigFile,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ConfigFile {
     pub primary: String,
     pub schema: Option<String>,
     pub fallback: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct SchemaInfo {
     pub id: String,
     pub format: String,
@@ -88,7 +89,7 @@ pub struct SchemaInfo {
     pub validates: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct TemplateLibrary {
     pub schemas: TemplateCategory,
     pub scripts: TemplateCategory,
@@ -97,38 +98,38 @@ pub struct TemplateLibrary {
     pub scans: TemplateCategory,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct TemplateCategory {
     pub location: String,
     pub index: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct OutputLocations {
     pub logs: LogLocations,
     pub exports: ExportLocations,
     pub cache: CacheLocations,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct LogLocations {
     pub structure_debug: String,
     pub daemon_logs: String,
     pub lineage: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ExportLocations {
     pub cad: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct CacheLocations {
     pub build: String,
     pub temp: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct Integrations {
     pub powershell: PowerShellIntegration,
     pub python: PythonIntegration,
@@ -136,14 +137,14 @@ pub struct Integrations {
     pub objex: ObjexIntegration,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct PowerShellIntegration {
     pub module: String,
     pub scripts: String,
     pub entry: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct PythonIntegration {
     pub plugins: String,
     pub venv: String,
@@ -155,20 +156,29 @@ pub struct WpShellIntegration {
     pub profile: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl Default for WpShellIntegration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            profile: "default".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ObjexIntegration {
     pub enabled: bool,
     pub hdf5_archives: Option<String>,
     pub primitives: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct Capabilities {
     pub available: Vec<String>,
     pub default_enabled: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct LoadOrder {
     pub bootstrap: Vec<String>,
     pub startup: Vec<String>,
@@ -184,12 +194,337 @@ pub struct HealthMonitoring {
     pub alerts: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl Default for HealthMonitoring {
+    fn default() -> Self {
+        Self {
+            heartbeat_file: "logs/heartbeat.json".to_string(),
+            daemon_status: "logs/daemon_status.json".to_string(),
+            context_status: "logs/context_status.json".to_string(),
+            checks: Vec::new(),
+            alerts: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct HealthCheck {
     pub module: String,
     pub interval: String,
 }
 
+/// Manifest version written by this build; older majors are migrated on load
+pub const CURRENT_MANIFEST_VERSION: &str = "2.0";
+
+/// Config types accepted by `ManifestLoader::config_path`
+const CONFIG_TYPES: [&str; 5] = ["runtime", "ui", "daemon", "shell", "compiler"];
+
+/// Version 1 manifest layout (predates `health` and `integrations.wpshell`).
+/// Every top-level section is optional so partially written manifests still load.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
+pub struct MasterManifestV1 {
+    pub oasm_version: String,
+    pub last_updated: String,
+    pub serialization: SerializationFormats,
+    pub modules: Vec<ModuleInfo>,
+    pub configs: ConfigLocations,
+    pub schemas: Vec<SchemaInfo>,
+    pub templates: TemplateLibrary,
+    pub outputs: OutputLocations,
+    pub integrations: IntegrationsV1,
+    pub capabilities: Capabilities,
+    pub load_order: LoadOrder,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
+pub struct IntegrationsV1 {
+    pub powershell: PowerShellIntegration,
+    pub python: PythonIntegration,
+    pub objex: ObjexIntegration,
+}
+
+impl MasterManifestV1 {
+    /// Upgrade to the current layout, filling sections v1 did not have
+    pub fn migrate(self) -> MasterManifest {
+        MasterManifest {
+            manifest_version: CURRENT_MANIFEST_VERSION.to_string(),
+            oasm_version: self.oasm_version,
+            last_updated: self.last_updated,
+            serialization: self.serialization,
+            modules: self.modules,
+            configs: self.configs,
+            schemas: self.schemas,
+            templates: self.templates,
+            outputs: self.outputs,
+            integrations: Integrations {
+                powershell: self.integrations.powershell,
+                python: self.integrations.python,
+                wpshell: WpShellIntegration::default(),
+                objex: self.integrations.objex,
+            },
+            capabilities: self.capabilities,
+            load_order: self.load_order,
+            health: HealthMonitoring::default(),
+        }
+    }
+}
+
+/// A parsed manifest tagged with the layout it was written in
+#[derive(Debug, Clone)]
+pub enum VersionedManifest {
+    V1(MasterManifestV1),
+    V2(MasterManifest),
+}
+
+impl VersionedManifest {
+    /// Parse YAML (or JSON, which is valid YAML) and dispatch on the major
+    /// of `manifest_version`; any 1.x or 2.x minor is accepted
+    pub fn parse(content: &str) -> Result<Self> {
+        let value: serde_yaml::Value = serde_yaml::from_str(content)
+            .context("Failed to parse manifest YAML")?;
+        let serde_yaml::Value::Mapping(mut mapping) = value else {
+            bail!("Manifest root must be a mapping");
+        };
+        let version = manifest_version(&mut mapping)?;
+        let value = serde_yaml::Value::Mapping(mapping);
+
+        match split_version(&version).map(|(major, _)| major) {
+            Some(1) => Ok(Self::V1(
+                serde_yaml::from_value(value).context("Failed to parse v1 manifest")?,
+            )),
+            Some(2) => Ok(Self::V2(
+                serde_yaml::from_value(value).context("Failed to parse manifest")?,
+            )),
+            _ => bail!(
+                "Unsupported manifest_version '{}' (this build reads 1.x and up to {})",
+                version,
+                CURRENT_MANIFEST_VERSION
+            ),
+        }
+    }
+
+    /// Migrate to the current manifest layout
+    pub fn into_current(self) -> MasterManifest {
+        match self {
+            Self::V1(v1) => v1.migrate(),
+            Self::V2(manifest) => manifest,
+        }
+    }
+}
+
+/// A single upgrade step between two manifest versions, applied to the raw
+/// YAML mapping before it is deserialized
+#[derive(Clone)]
+pub struct Migration {
+    pub from: String,
+    pub to: String,
+    pub apply: fn(&mut serde_yaml::Mapping) -> Result<()>,
+}
+
+impl Migration {
+    pub fn new(from: &str, to: &str, apply: fn(&mut serde_yaml::Mapping) -> Result<()>) -> Self {
+        Self { from: from.to_string(), to: to.to_string(), apply }
+    }
+}
+
+impl std::fmt::Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Migration({} -> {})", self.from, self.to)
+    }
+}
+
+/// Registered migrations, chained from a manifest's version up to `target`
+#[derive(Debug, Clone)]
+pub struct MigrationRegistry {
+    target: String,
+    migrations: Vec<Migration>,
+}
+
+impl MigrationRegistry {
+    /// Empty registry migrating towards `target`
+    pub fn to_version(target: &str) -> Self {
+        Self { target: target.to_string(), migrations: Vec::new() }
+    }
+
+    /// Migrations shipped with this build, targeting CURRENT_MANIFEST_VERSION
+    pub fn builtin() -> Self {
+        let mut registry = Self::to_version(CURRENT_MANIFEST_VERSION);
+        registry.register(Migration::new("1.0", "2.0", migrate_v1_to_v2));
+        registry
+    }
+
+    pub fn register(&mut self, migration: Migration) {
+        self.migrations.push(migration);
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Apply migrations in sequence until the mapping is at the target
+    /// version. Returns the version the manifest started at.
+    ///
+    /// A minor version with no migration registered for it is a no-op
+    /// step: in the target's major version the manifest is taken as it is,
+    /// otherwise it continues from the closest lower registered version of
+    /// its major (1.2 migrates like 1.0 when only 1.0 -> 2.0 is registered).
+    pub fn migrate(&self, mapping: &mut serde_yaml::Mapping) -> Result<String> {
+        let original = manifest_version(mapping)?;
+        let mut version = original.clone();
+
+        // Each migration can apply at most once, which also rules out cycles
+        for _ in 0..=self.migrations.len() {
+            if version == self.target {
+                return Ok(original);
+            }
+            let exact = self.migrations.iter().find(|m| m.from == version);
+            if exact.is_none() && same_major(&version, &self.target) {
+                return Ok(original);
+            }
+            let Some(migration) = exact.or_else(|| self.closest_migration(&version)) else {
+                break;
+            };
+            (migration.apply)(mapping)
+                .with_context(|| format!("Migration {} -> {} failed", migration.from, migration.to))?;
+            version = migration.to.clone();
+            mapping.insert("manifest_version".into(), version.clone().into());
+        }
+
+        bail!(
+            "Unsupported manifest_version '{}' (this build migrates to {} from: {})",
+            version,
+            self.target,
+            self.migrations.iter().map(|m| m.from.as_str()).collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+impl MigrationRegistry {
+    /// The migration from the highest registered version at or below
+    /// `version` within its major
+    fn closest_migration(&self, version: &str) -> Option<&Migration> {
+        let (major, minor) = split_version(version)?;
+        self.migrations
+            .iter()
+            .filter_map(|m| Some((split_version(&m.from)?, m)))
+            .filter(|((from_major, from_minor), _)| *from_major == major && *from_minor <= minor)
+            .max_by_key(|(from, _)| *from)
+            .map(|(_, migration)| migration)
+    }
+}
+
+/// `major.minor` of a normalised version, if both parts are numbers
+fn split_version(version: &str) -> Option<(u64, u64)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+fn same_major(a: &str, b: &str) -> bool {
+    matches!((split_version(a), split_version(b)), (Some((a, _)), Some((b, _))) if a == b)
+}
+
+/// Read and normalise `manifest_version` (`1`, `1.0` and `"1.0"` are all "1.0")
+fn manifest_version(mapping: &mut serde_yaml::Mapping) -> Result<String> {
+    let key = serde_yaml::Value::from("manifest_version");
+    let mut version = match mapping.get(&key) {
+        Some(serde_yaml::Value::String(s)) => s.trim().to_string(),
+        // `manifest_version: 1.0` arrives as a number
+        Some(serde_yaml::Value::Number(n)) => n.to_string(),
+        Some(other) => bail!("manifest_version must be a string, found {:?}", other),
+        None => bail!("Manifest is missing manifest_version"),
+    };
+    if !version.contains('.') {
+        version.push_str(".0");
+    }
+    mapping.insert(key, serde_yaml::Value::from(version.clone()));
+    Ok(version)
+}
+
+fn migrate_v1_to_v2(mapping: &mut serde_yaml::Mapping) -> Result<()> {
+    let v1: MasterManifestV1 = serde_yaml::from_value(serde_yaml::Value::Mapping(mapping.clone()))
+        .context("Failed to parse v1 manifest")?;
+    match serde_yaml::to_value(VersionedManifest::V1(v1).into_current())? {
+        serde_yaml::Value::Mapping(migrated) => *mapping = migrated,
+        _ => bail!("Migrated manifest is not a mapping"),
+    }
+    Ok(())
+}
+
+impl MasterManifest {
+    /// Current-version manifest listing `modules`, everything else at its
+    /// default. Auto-start modules load at startup, the rest on demand.
+    pub fn new(modules: Vec<ModuleInfo>) -> Self {
+        let (startup, on_demand): (Vec<&ModuleInfo>, Vec<&ModuleInfo>) = modules.iter().partition(|m| m.auto_start);
+        let load_order = LoadOrder {
+            bootstrap: Vec::new(),
+            startup: startup.into_iter().map(|m| m.id.clone()).collect(),
+            on_demand: on_demand.into_iter().map(|m| m.id.clone()).collect(),
+        };
+
+        Self {
+            manifest_version: CURRENT_MANIFEST_VERSION.to_string(),
+            oasm_version: env!("CARGO_PKG_VERSION").to_string(),
+            last_updated: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            serialization: SerializationFormats::default(),
+            modules,
+            configs: ConfigLocations::default(),
+            schemas: Vec::new(),
+            templates: TemplateLibrary::default(),
+            outputs: OutputLocations::default(),
+            integrations: Integrations::default(),
+            capabilities: Capabilities::default(),
+            load_order,
+            health: HealthMonitoring::default(),
+        }
+    }
+
+    /// Parse a manifest of any supported version, upgrading it to the current layout
+    pub fn parse(content: &str) -> Result<Self> {
+        Self::parse_with(content, &MigrationRegistry::builtin())
+    }
+
+    /// Parse YAML (or JSON, which is valid YAML) and migrate it with `registry`
+    pub fn parse_with(content: &str, registry: &MigrationRegistry) -> Result<Self> {
+        let mapping = migrate_content(content, registry)?;
+        serde_yaml::from_value(serde_yaml::Value::Mapping(mapping)).context("Failed to parse manifest")
+    }
+}
+
+/// Parse manifest text into a mapping and migrate it to the registry's target
+pub fn migrate_content(content: &str, registry: &MigrationRegistry) -> Result<serde_yaml::Mapping> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content)
+        .context("Failed to parse manifest YAML")?;
+    let serde_yaml::Value::Mapping(mut mapping) = value else {
+        bail!("Manifest root must be a mapping");
+    };
+    registry.migrate(&mut mapping)?;
+    Ok(mapping)
+}
+
+/// Why the module startup order could not be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestError {
+    /// `module` depends on a module the manifest does not define
+    UnknownDependency { module: String, dependency: String },
+    /// Modules that depend on each other, in dependency order; the first
+    /// module is repeated at the end (`a → b → a`)
+    DependencyCycle(Vec<String>),
+}
+
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ManifestError::UnknownDependency { module, dependency } => {
+                write!(f, "Module '{}' depends on unknown module '{}'", module, dependency)
+            }
+            ManifestError::DependencyCycle(cycle) => write!(f, "Dependency cycle: {}", cycle.join(" → ")),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
 /// Manifest Loader - Easy access to all OASM components
 pub struct ManifestLoader {
     manifest: MasterManifest,
@@ -199,11 +534,15 @@ pub struct ManifestLoader {
 impl ManifestLoader {
     /// Load the master manifest from a file
     pub fn load(manifest_path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_with(manifest_path, &MigrationRegistry::builtin())
+    }
+
+    /// Load the master manifest, upgrading older versions with `registry`
+    pub fn load_with(manifest_path: impl AsRef<Path>, registry: &MigrationRegistry) -> Result<Self> {
         let content = std::fs::read_to_string(&manifest_path)
             .context("Failed to read manifest file")?;
 
-        let manifest: MasterManifest = serde_yaml::from_str(&content)
-            .context("Failed to parse manifest YAML")?;
+        let manifest = MasterManifest::parse_with(&content, registry)?;
 
         let root = manifest_path.as_ref()
             .parent()
@@ -285,11 +624,134 @@ impl ManifestLoader {
         )
     }
 
+    /// Modules to start, each after its dependencies: everything in the
+    /// bootstrap and startup phases, auto-start modules, and whatever they
+    /// depend on. Among modules that are ready at the same time, bootstrap
+    /// comes before startup (in list order), then the other auto-start
+    /// modules in manifest order; a dependency ranks with its earliest
+    /// dependent.
+    pub fn resolve_startup_order(&self) -> std::result::Result<Vec<&ModuleInfo>, ManifestError> {
+        let modules = &self.manifest.modules;
+        let index_of = |id: &str| modules.iter().position(|m| m.id == id);
+        let (bootstrap, startup, _) = self.load_order();
+
+        // Tie-break rank of each module that has to start
+        let mut rank: HashMap<usize, (usize, usize)> = HashMap::new();
+        for (phase, ids) in [bootstrap, startup].into_iter().enumerate() {
+            for (position, id) in ids.iter().enumerate() {
+                if let Some(index) = index_of(id) {
+                    rank.entry(index).or_insert((phase, position));
+                }
+            }
+        }
+        for (index, _) in modules.iter().enumerate().filter(|(_, m)| m.auto_start) {
+            rank.entry(index).or_insert((2, 0));
+        }
+
+        // Pull in dependencies and resolve them to indices
+        let mut dependencies: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut pending: Vec<usize> = rank.keys().copied().collect();
+        pending.sort_unstable();
+        while let Some(index) = pending.pop() {
+            if dependencies.contains_key(&index) {
+                continue;
+            }
+            let module = &modules[index];
+            let mut resolved = Vec::new();
+            for dependency in &module.dependencies {
+                let dep_index = index_of(dependency).ok_or_else(|| ManifestError::UnknownDependency {
+                    module: module.id.clone(),
+                    dependency: dependency.clone(),
+                })?;
+                rank.entry(dep_index).or_insert((usize::MAX, 0));
+                pending.push(dep_index);
+                resolved.push(dep_index);
+            }
+            dependencies.insert(index, resolved);
+        }
+
+        // Dependencies inherit their dependents' rank; a chain is at most
+        // one pass per module (cycles are reported below)
+        for _ in 0..dependencies.len() {
+            let mut changed = false;
+            for (index, deps) in &dependencies {
+                for dep in deps {
+                    if rank[index] < rank[dep] {
+                        rank.insert(*dep, rank[index]);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut order = Vec::with_capacity(dependencies.len());
+        let mut started = std::collections::HashSet::new();
+        while order.len() < dependencies.len() {
+            let next = dependencies
+                .iter()
+                .filter(|(index, deps)| !started.contains(*index) && deps.iter().all(|d| started.contains(d)))
+                .map(|(index, _)| *index)
+                .min_by_key(|index| (rank[index], *index));
+            let Some(next) = next else {
+                return Err(ManifestError::DependencyCycle(self.find_cycle(&dependencies, &started)));
+            };
+            started.insert(next);
+            order.push(&modules[next]);
+        }
+        Ok(order)
+    }
+
+    /// A cycle among the modules that could not start. Each of them waits on
+    /// another unstarted module, so following those edges must loop.
+    fn find_cycle(
+        &self,
+        dependencies: &HashMap<usize, Vec<usize>>,
+        started: &std::collections::HashSet<usize>,
+    ) -> Vec<String> {
+        let blocked = |index: &usize| dependencies[index].iter().copied().find(|d| !started.contains(d));
+        let mut path: Vec<usize> = vec![*dependencies
+            .keys()
+            .filter(|index| !started.contains(*index))
+            .min()
+            .expect("an unstarted module")];
+        loop {
+            let current = *path.last().expect("non-empty path");
+            let next = blocked(&current).expect("unstarted module waits on another");
+            if let Some(start) = path.iter().position(|&index| index == next) {
+                let mut cycle: Vec<String> =
+                    path[start..].iter().map(|&index| self.manifest.modules[index].id.clone()).collect();
+                cycle.push(self.manifest.modules[next].id.clone());
+                return cycle;
+            }
+            path.push(next);
+        }
+    }
+
     /// Check if a capability is available
     pub fn has_capability(&self, cap: &str) -> bool {
         self.manifest.capabilities.available.contains(&cap.to_string())
     }
 
+    /// Known module closest to an unknown ID, for "did you mean" hints
+    pub fn suggest_module(&self, id: &str) -> Option<String> {
+        let ids: Vec<&str> = self.manifest.modules.iter().map(|m| m.id.as_str()).collect();
+        closest_match(id, &ids, 2)
+    }
+
+    /// Available capability closest to an unknown name
+    pub fn suggest_capability(&self, cap: &str) -> Option<String> {
+        let caps: Vec<&str> = self.manifest.capabilities.available.iter().map(String::as_str).collect();
+        closest_match(cap, &caps, 2)
+    }
+
+    /// Config type closest to an unknown one (see `config_path`)
+    pub fn suggest_config_type(&self, config_type: &str) -> Option<String> {
+        closest_match(config_type, &CONFIG_TYPES, 2)
+    }
+
     /// Get default enabled capabilities
     pub fn default_capabilities(&self) -> &Vec<String> {
         &self.manifest.capabilities.default_enabled
@@ -317,6 +779,48 @@ impl ManifestLoader {
         }
     }
 
+    /// Consistency problems, one line each: duplicate module IDs, module
+    /// locations or entries missing under the root, and dependencies or
+    /// load-order entries naming unknown modules. Empty when healthy.
+    pub fn doctor(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for module in &self.manifest.modules {
+            if !seen.insert(module.id.as_str()) {
+                problems.push(format!("Duplicate module id '{}'", module.id));
+            }
+            if !self.root.join(&module.location).exists() {
+                problems.push(format!("Module '{}': location '{}' does not exist", module.id, module.location));
+            }
+            if let Some(entry) = &module.entry {
+                if !self.root.join(entry).exists() {
+                    problems.push(format!("Module '{}': entry '{}' does not exist", module.id, entry));
+                }
+            }
+            for dependency in &module.dependencies {
+                if self.get_module(dependency).is_none() {
+                    problems.push(format!("Module '{}': {}", module.id, self.unknown_module(dependency)));
+                }
+            }
+        }
+
+        let (bootstrap, startup, on_demand) = self.load_order();
+        for (stage, ids) in [("bootstrap", bootstrap), ("startup", startup), ("on_demand", on_demand)] {
+            for id in ids.iter().filter(|id| self.get_module(id).is_none()) {
+                problems.push(format!("load_order.{}: {}", stage, self.unknown_module(id)));
+            }
+        }
+        problems
+    }
+
+    fn unknown_module(&self, id: &str) -> String {
+        match self.suggest_module(id) {
+            Some(suggestion) => format!("unknown module '{}' (did you mean '{}'?)", id, suggestion),
+            None => format!("unknown module '{}'", id),
+        }
+    }
+
     /// Get the full manifest
     pub fn manifest(&self) -> &MasterManifest {
         &self.manifest
@@ -332,9 +836,213 @@ impl ManifestLoader {
 mod tests {
     use super::*;
 
+    const V1_MANIFEST: &str = r#"
+manifest_version: 1.0
+oasm_version: "0.1.0"
+last_updated: "2025-01-01"
+modules:
+  - id: daemon
+    name: Runtime Daemon
+    type: service
+    location: runtime/daemon
+    capabilities: [file_access]
+    auto_start: true
+    dependencies: []
+integrations:
+  objex:
+    enabled: true
+"#;
+
     #[test]
     fn test_manifest_loading() {
-        // This would load the actual manifest in a real test
-        // For now, just a placeholder
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_dir = dir.path().join("manifests");
+        std::fs::create_dir_all(&manifest_dir).unwrap();
+        let path = manifest_dir.join("oasm_manifest.yaml");
+        std::fs::write(&path, V1_MANIFEST).unwrap();
+
+        let loader = ManifestLoader::load(&path).unwrap();
+        assert_eq!(loader.root(), dir.path());
+        assert_eq!(loader.module_path("daemon"), Some(dir.path().join("runtime/daemon")));
+        assert!(!loader.integration_enabled("wpshell"));
+
+        assert_eq!(loader.suggest_module("deamon").as_deref(), Some("daemon"));
+        assert_eq!(loader.suggest_config_type("compilr").as_deref(), Some("compiler"));
+        assert_eq!(loader.suggest_config_type("network"), None);
+    }
+
+    #[test]
+    fn test_doctor_reports_inconsistencies() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("runtime/daemon")).unwrap();
+        std::fs::create_dir_all(dir.path().join("manifests")).unwrap();
+        let path = dir.path().join("manifests/oasm_manifest.yaml");
+
+        let module = |id: &str, location: &str| ModuleInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            location: location.to_string(),
+            ..ModuleInfo::default()
+        };
+        let mut manifest = MasterManifest::new(vec![module("daemon", "runtime/daemon")]);
+        std::fs::write(&path, serde_yaml::to_string(&manifest).unwrap()).unwrap();
+        assert!(ManifestLoader::load(&path).unwrap().doctor().is_empty());
+
+        manifest.modules[0].dependencies.push("deamon".to_string());
+        manifest.modules.push(module("shell", "shells/oasm-shell"));
+        manifest.load_order.startup.push("ui".to_string());
+        std::fs::write(&path, serde_yaml::to_string(&manifest).unwrap()).unwrap();
+
+        let problems = ManifestLoader::load(&path).unwrap().doctor();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].contains("did you mean 'daemon'"));
+        assert!(problems[1].contains("'shells/oasm-shell' does not exist"));
+        assert!(problems[2].starts_with("load_order.startup: unknown module 'ui'"));
+    }
+
+    fn loader_for(modules: Vec<ModuleInfo>) -> (tempfile::TempDir, ManifestLoader) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("manifests")).unwrap();
+        let path = dir.path().join("manifests/oasm_manifest.yaml");
+        std::fs::write(&path, serde_yaml::to_string(&MasterManifest::new(modules)).unwrap()).unwrap();
+        let loader = ManifestLoader::load(&path).unwrap();
+        (dir, loader)
+    }
+
+    fn depends(id: &str, dependencies: &[&str]) -> ModuleInfo {
+        ModuleInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            auto_start: true,
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            ..ModuleInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_startup_order_follows_dependencies() {
+        let (_dir, loader) = loader_for(vec![depends("a", &["b"]), depends("b", &["c"]), depends("c", &[])]);
+        let order: Vec<&str> = loader.resolve_startup_order().unwrap().iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(order, ["c", "b", "a"]);
+
+        // Independent modules keep their phase order; an on-demand
+        // dependency is started first anyway
+        let mut shell = depends("shell", &["ui"]);
+        shell.auto_start = false;
+        let mut ui = depends("ui", &[]);
+        ui.auto_start = false;
+        let (_dir, mut loader) = loader_for(vec![depends("daemon", &[]), depends("watcher", &["ui"]), ui, shell]);
+        loader.manifest.load_order.bootstrap.push("watcher".to_string());
+        let order: Vec<&str> = loader.resolve_startup_order().unwrap().iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(order, ["ui", "watcher", "daemon"]);
+    }
+
+    #[test]
+    fn test_startup_order_reports_cycles() {
+        let (_dir, loader) =
+            loader_for(vec![depends("a", &["b"]), depends("b", &["c"]), depends("c", &["a"]), depends("d", &[])]);
+        let err = loader.resolve_startup_order().unwrap_err();
+        assert_eq!(err, ManifestError::DependencyCycle(vec!["a".into(), "b".into(), "c".into(), "a".into()]));
+        assert_eq!(err.to_string(), "Dependency cycle: a → b → c → a");
+
+        let (_dir, loader) = loader_for(vec![depends("a", &["deamon"])]);
+        assert_eq!(
+            loader.resolve_startup_order().unwrap_err(),
+            ManifestError::UnknownDependency { module: "a".into(), dependency: "deamon".into() }
+        );
+    }
+
+    #[test]
+    fn test_v1_manifest_migrates_with_defaults() {
+        let manifest = MasterManifest::parse(V1_MANIFEST).unwrap();
+
+        assert_eq!(manifest.manifest_version, CURRENT_MANIFEST_VERSION);
+        assert_eq!(manifest.modules.len(), 1);
+        assert!(manifest.integrations.objex.enabled);
+        assert!(!manifest.integrations.wpshell.enabled);
+        assert_eq!(manifest.integrations.wpshell.profile, "default");
+        assert_eq!(manifest.health.heartbeat_file, "logs/heartbeat.json");
+        assert!(manifest.health.checks.is_empty());
+    }
+
+    #[test]
+    fn test_migrated_manifest_round_trips() {
+        let manifest = MasterManifest::parse(V1_MANIFEST).unwrap();
+
+        let yaml = serde_yaml::to_string(&manifest).unwrap();
+        let from_yaml = MasterManifest::parse(&yaml).unwrap();
+        assert_eq!(from_yaml.manifest_version, CURRENT_MANIFEST_VERSION);
+        assert_eq!(from_yaml.modules[0].id, "daemon");
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let from_json = MasterManifest::parse(&json).unwrap();
+        assert_eq!(from_json.health.daemon_status, manifest.health.daemon_status);
+    }
+
+    #[test]
+    fn test_minor_versions_load() {
+        for version in ["1.1", "1.2"] {
+            let manifest = MasterManifest::parse(&V1_MANIFEST.replace("manifest_version: 1.0", &format!("manifest_version: {}", version))).unwrap();
+            assert_eq!(manifest.manifest_version, CURRENT_MANIFEST_VERSION, "from {}", version);
+            assert_eq!(manifest.health.heartbeat_file, "logs/heartbeat.json");
+        }
+
+        let current = serde_yaml::to_string(&MasterManifest::parse(V1_MANIFEST).unwrap()).unwrap();
+        let manifest = MasterManifest::parse(&current.replace("manifest_version: '2.0'", "manifest_version: '2.1'")).unwrap();
+        assert_eq!(manifest.manifest_version, "2.1");
+        assert_eq!(manifest.modules[0].id, "daemon");
+    }
+
+    #[test]
+    fn test_versioned_manifest_dispatches_on_major() {
+        let v1 = VersionedManifest::parse(&V1_MANIFEST.replace("manifest_version: 1.0", "manifest_version: 1.2")).unwrap();
+        assert!(matches!(v1, VersionedManifest::V1(_)));
+        let current = v1.into_current();
+        assert_eq!(current.manifest_version, CURRENT_MANIFEST_VERSION);
+        assert_eq!(current.modules[0].id, "daemon");
+
+        let yaml = serde_yaml::to_string(&current).unwrap().replace("manifest_version: '2.0'", "manifest_version: '2.1'");
+        match VersionedManifest::parse(&yaml).unwrap() {
+            VersionedManifest::V2(manifest) => assert_eq!(manifest.manifest_version, "2.1"),
+            other => panic!("expected V2, got {:?}", other),
+        }
+        assert!(VersionedManifest::parse("manifest_version: \"3.0\"\n").unwrap_err().to_string().contains("'3.0'"));
+    }
+
+    #[test]
+    fn test_unsupported_version_is_named() {
+        let err = MasterManifest::parse("manifest_version: \"3.1\"\n").unwrap_err();
+        assert!(err.to_string().contains("'3.1'"));
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        assert!(MasterManifest::parse("- not\n- a mapping\n").is_err());
+        assert!(MasterManifest::parse("oasm_version: \"0.1.0\"\n").is_err());
+        assert!(MasterManifest::parse("manifest_version: [1]\n").is_err());
+    }
+
+    #[test]
+    fn test_registered_migration_fills_new_field() {
+        let mut registry = MigrationRegistry::to_version("1.1");
+        registry.register(Migration::new("1.0", "1.1", |mapping| {
+            mapping
+                .entry("last_updated".into())
+                .or_insert_with(|| "unknown".into());
+            Ok(())
+        }));
+
+        let mapping = migrate_content("manifest_version: 1.0\noasm_version: \"0.1.0\"\n", &registry).unwrap();
+
+        assert_eq!(mapping.get("manifest_version").and_then(|v| v.as_str()), Some("1.1"));
+        assert_eq!(mapping.get("last_updated").and_then(|v| v.as_str()), Some("unknown"));
+
+        // Migrations chain, and versions with no path to the target are rejected
+        registry.register(Migration::new("1.1", "2.0", migrate_v1_to_v2));
+        let registry = MigrationRegistry { target: "2.0".to_string(), ..registry };
+        let manifest = MasterManifest::parse_with(V1_MANIFEST, &registry).unwrap();
+        assert_eq!(manifest.last_updated, "2025-01-01");
+        assert_eq!(manifest.manifest_version, "2.0");
+        assert!(migrate_content("manifest_version: \"0.9\"\n", &registry).is_err());
     }
 }
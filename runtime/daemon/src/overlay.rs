@@ -0,0 +1,245 @@
+//! YAML overlay validate-on-save
+//!
+//! `*.overlay.yaml` files under the watched overlay directories are checked
+//! once their saves settle (see `watch::Debouncer`): the YAML must match the
+//! YAMLOverlay schema and pass `validate_yaml_overlay`. Each check writes a
+//! sibling `*.overlay.report.json`, records a lineage event and appends a
+//! dashboard row (`Section::Overlay`). A passing overlay can be pre-converted
+//! to CBOR in the spool so executing it later needs no conversion.
+//!
+//! A failing overlay is feedback for the person editing it, not a build
+//! failure: nothing is quarantined and the next save is checked again.
+
+use anyhow::{Context, Result};
+use asm_formats::converters::overlay_to_cbor;
+use asm_formats::schemas::{Section, YAMLOverlay};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::types::WatchEvent;
+use crate::watch::Debouncer;
+
+pub const OVERLAY_SUFFIX: &str = ".overlay.yaml";
+pub const REPORT_SUFFIX: &str = ".overlay.report.json";
+pub const DASHBOARD_SECTION: Section = Section::Overlay;
+
+/// Overlay part of the daemon's watch configuration
+#[derive(Debug, Clone)]
+pub struct OverlayWatchConfig {
+    /// Directories watched recursively for `*.overlay.yaml`
+    pub dirs: Vec<String>,
+    /// Where passing overlays are pre-converted to `<object_id>.cbor`; None disables it
+    pub spool_dir: Option<PathBuf>,
+    /// JSONL file receiving one dashboard row per validation; None disables it
+    pub dashboard_log: Option<PathBuf>,
+    /// Quiet period before a saved overlay is validated
+    pub debounce: Duration,
+}
+
+impl Default for OverlayWatchConfig {
+    fn default() -> Self {
+        Self {
+            dirs: Vec::new(),
+            spool_dir: None,
+            dashboard_log: None,
+            debounce: Duration::from_millis(300),
+        }
+    }
+}
+
+pub fn is_overlay(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(OVERLAY_SUFFIX)
+}
+
+/// `gear.overlay.yaml` -> `gear.overlay.report.json` in the same directory
+pub fn report_path(overlay: &Path) -> PathBuf {
+    let name = overlay.to_string_lossy();
+    PathBuf::from(format!("{}{}", name.trim_end_matches(OVERLAY_SUFFIX), REPORT_SUFFIX))
+}
+
+/// Result of validating one overlay file (the `.overlay.report.json` contents)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverlayReport {
+    pub path: String,
+    pub passed: bool,
+    pub errors: Vec<String>,
+    pub validated_at: String,
+    /// Pre-converted CBOR object in the spool, if one was written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spool_object: Option<String>,
+}
+
+impl OverlayReport {
+    /// Dashboard row in the compiler's JSONL layout; errors count as warnings
+    pub fn dashboard_row(&self, id: usize) -> serde_json::Value {
+        let alias = Path::new(&self.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.clone());
+
+        serde_json::json!({
+            "id": id,
+            "n": id,
+            "alias": alias,
+            "relPath": self.path,
+            "link": self.path,
+            "progress": format!("{}/?", id),
+            "visual": if self.passed { "ok" } else { "invalid" },
+            "totals": { "crit": 0, "block": 0, "warn": self.errors.len() },
+            "diagnostics": self.errors,
+            "timestamp": self.validated_at,
+            "section": DASHBOARD_SECTION,
+        })
+    }
+}
+
+/// Validate one overlay file, write its report and, if it passes and a spool
+/// is configured, its CBOR object. Errors only for I/O problems.
+pub fn validate_overlay_file(path: &Path, config: &OverlayWatchConfig) -> Result<OverlayReport> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read overlay {}", path.display()))?;
+
+    let mut report = OverlayReport {
+        path: path.to_string_lossy().to_string(),
+        passed: false,
+        errors: Vec::new(),
+        validated_at: chrono::Utc::now().to_rfc3339(),
+        spool_object: None,
+    };
+
+    match serde_yaml::from_str::<YAMLOverlay>(&content) {
+        Err(e) => report.errors.push(format!("schema: {}", e)),
+        Ok(overlay) => match overlay_to_cbor(&overlay) {
+            Err(e) => report.errors.push(e.to_string()),
+            Ok(object) => {
+                report.passed = true;
+                if let Some(spool_dir) = &config.spool_dir {
+                    std::fs::create_dir_all(spool_dir)?;
+                    let spool_path = spool_dir.join(format!("{}.cbor", object.object_id));
+                    std::fs::write(&spool_path, serde_cbor::to_vec(&object)?)?;
+                    report.spool_object = Some(spool_path.to_string_lossy().to_string());
+                }
+            }
+        },
+    }
+
+    std::fs::write(report_path(path), serde_json::to_string_pretty(&report)?)?;
+    Ok(report)
+}
+
+/// Overlay side of the supervisor loop: claims overlay events, debounces
+/// them, and validates overlays once their saves settle
+pub struct OverlayWatcher {
+    config: OverlayWatchConfig,
+    debouncer: Debouncer,
+    rows: usize,
+}
+
+impl OverlayWatcher {
+    pub fn new(config: OverlayWatchConfig) -> Self {
+        let debouncer = Debouncer::new(config.debounce);
+        Self { config, debouncer, rows: 0 }
+    }
+
+    pub fn config(&self) -> &OverlayWatchConfig {
+        &self.config
+    }
+
+    /// True if `path` is an overlay or lives under an overlay directory
+    fn claims(&self, path: &Path) -> bool {
+        is_overlay(path) || self.config.dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// Take an event if it belongs to the overlay watch. Saves are queued
+    /// for validation; other files under overlay directories (reports,
+    /// editor swap files) are ignored rather than treated as manifests.
+    pub fn observe(&mut self, event: &WatchEvent, now: Instant) -> bool {
+        let (path, removed) = match event {
+            WatchEvent::Created { path } | WatchEvent::Changed { path } => (path, false),
+            WatchEvent::Removed { path } => (path, true),
+            WatchEvent::Error { .. } => return false,
+        };
+        let path = Path::new(path);
+        if !self.claims(path) {
+            return false;
+        }
+
+        if is_overlay(path) {
+            if removed {
+                self.debouncer.cancel(&path.to_string_lossy());
+            } else {
+                self.debouncer.push(&path.to_string_lossy(), now);
+            }
+        }
+        true
+    }
+
+    /// When the next queued overlay becomes ready
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.debouncer.next_deadline()
+    }
+
+    /// Validate every overlay whose saves have settled
+    pub fn flush(&mut self, now: Instant) -> Vec<OverlayReport> {
+        let mut reports = Vec::new();
+        for path in self.debouncer.take_ready(now) {
+            match validate_overlay_file(Path::new(&path), &self.config) {
+                Ok(report) => {
+                    self.emit(&report);
+                    reports.push(report);
+                }
+                Err(e) => log::warn!("Overlay check skipped for {}: {:#}", path, e),
+            }
+        }
+        reports
+    }
+
+    /// Structured event plus dashboard row for one validation
+    fn emit(&mut self, report: &OverlayReport) {
+        let msg = format!(
+            "overlay_validated path={} passed={} errors={}",
+            report.path,
+            report.passed,
+            report.errors.len()
+        );
+        crate::lineage::record_event(&msg).ok();
+        crate::lineage::record_event_cbor("overlay", &msg).ok();
+
+        self.rows += 1;
+        if let Some(log_path) = &self.config.dashboard_log {
+            let row = report.dashboard_row(self.rows);
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path)
+                .and_then(|mut file| writeln!(file, "{}", row));
+            if let Err(e) = written {
+                log::warn!("Failed to write overlay dashboard row to {}: {}", log_path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_path_and_claims() {
+        assert_eq!(report_path(Path::new("ov/gear.overlay.yaml")), PathBuf::from("ov/gear.overlay.report.json"));
+
+        let mut watcher = OverlayWatcher::new(OverlayWatchConfig {
+            dirs: vec!["ov".to_string()],
+            ..OverlayWatchConfig::default()
+        });
+        let now = Instant::now();
+        assert!(watcher.observe(&WatchEvent::Changed { path: "ov/gear.overlay.report.json".to_string() }, now));
+        assert!(watcher.next_deadline().is_none());
+        assert!(watcher.observe(&WatchEvent::Changed { path: "ov/gear.overlay.yaml".to_string() }, now));
+        assert!(!watcher.observe(&WatchEvent::Changed { path: "crate_manifest.yaml".to_string() }, now));
+        assert_eq!(watcher.next_deadline(), Some(now + watcher.config().debounce));
+    }
+}
@@ -1,10 +1,13 @@
 use anyhow::Result;
 use tokio::runtime::Builder;
 use tokio::sync::mpsc;
+use std::time::Instant;
+use crate::overlay::{OverlayWatchConfig, OverlayWatcher};
 use crate::types::WatchEvent;
 
 /// Runs the supervisor loop: watches paths, processes events, records lineage.
-pub fn run(paths: &[String]) -> Result<()> {
+/// Overlay directories are watched too; their events go to the OverlayWatcher.
+pub fn run(paths: &[String], overlays: OverlayWatchConfig) -> Result<()> {
     let rt = Builder::new_multi_thread()
         .enable_all()
         .build()?;
@@ -12,14 +15,36 @@ pub fn run(paths: &[String]) -> Result<()> {
     rt.block_on(async move {
         let (tx, mut rx) = mpsc::channel::<WatchEvent>(128);
 
+        let mut watch_paths = paths.to_vec();
+        watch_paths.extend(overlays.dirs.iter().cloned());
+        let mut overlay_watcher = OverlayWatcher::new(overlays);
+
         // Start watcher task
-        crate::watch::start_watch(paths.to_vec(), tx).await?;
+        crate::watch::start_watch(watch_paths, tx).await?;
 
         // Initial scan: process manifests once
         initialize(paths).await;
 
-        // Event loop
-        while let Some(ev) = rx.recv().await {
+        // Event loop; waits no longer than the next debounced overlay
+        loop {
+            let next = match overlay_watcher.next_deadline() {
+                Some(deadline) => {
+                    let deadline = tokio::time::Instant::from_std(deadline);
+                    match tokio::time::timeout_at(deadline, rx.recv()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            overlay_watcher.flush(Instant::now());
+                            continue;
+                        }
+                    }
+                }
+                None => rx.recv().await,
+            };
+            let Some(ev) = next else { break };
+            if overlay_watcher.observe(&ev, Instant::now()) {
+                continue;
+            }
+
             match &ev {
                 WatchEvent::Created { path } | WatchEvent::Changed { path } => {
                     process_manifest(path).await;
@@ -1,6 +1,152 @@
 use anyhow::{bail, Result};
 use crate::types::CrateManifest;
 
+/// Keys a crate manifest may have at the top level and under `package`
+const TOP_LEVEL_KEYS: [&str; 3] = ["package", "dependencies", "dev-dependencies"];
+const PACKAGE_KEYS: [&str; 3] = ["name", "version", "edition"];
+
+/// What is wrong with a manifest field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestIssueKind {
+    MissingField,
+    InvalidValue,
+    UnknownField,
+}
+
+/// One problem found by `check_manifest`, located by line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestIssue {
+    pub kind: ManifestIssueKind,
+    /// Dotted path of the field, e.g. `package.name`
+    pub field: String,
+    pub message: String,
+    /// 1-based line of the offending key; a missing field points at its
+    /// parent's key (line 1 at the top level)
+    pub line: usize,
+}
+
+/// Check manifest YAML field by field, before it is parsed into a
+/// CrateManifest. Text that is not a YAML mapping yields no issues; parsing
+/// reports it. Issues come in document order of the fields checked.
+pub fn check_manifest(content: &str) -> Vec<ManifestIssue> {
+    let Ok(serde_yaml::Value::Mapping(root)) = serde_yaml::from_str::<serde_yaml::Value>(content) else {
+        return Vec::new();
+    };
+    let mut issues = Vec::new();
+    let mut issue = |kind, path: &[&str], message: String| {
+        let line = key_line(content, path)
+            .or_else(|| key_line(content, &path[..path.len() - 1]))
+            .unwrap_or(1);
+        issues.push(ManifestIssue { kind, field: path.join("."), message, line });
+    };
+
+    for key in root.keys() {
+        let name = key.as_str().unwrap_or_default();
+        if !TOP_LEVEL_KEYS.contains(&name) {
+            issue(ManifestIssueKind::UnknownField, &[name], format!("Unknown field '{}'", name));
+        }
+    }
+
+    match root.get("package") {
+        None => issue(ManifestIssueKind::MissingField, &["package"], "Missing required field 'package'".to_string()),
+        Some(serde_yaml::Value::Mapping(package)) => {
+            for field in PACKAGE_KEYS {
+                let path = ["package", field];
+                match package.get(field) {
+                    None => issue(
+                        ManifestIssueKind::MissingField,
+                        &path,
+                        format!("Missing required field 'package.{}'", field),
+                    ),
+                    Some(serde_yaml::Value::String(value)) if !value.trim().is_empty() => {}
+                    Some(serde_yaml::Value::String(_)) => issue(
+                        ManifestIssueKind::InvalidValue,
+                        &path,
+                        format!("package.{} must not be empty", field),
+                    ),
+                    Some(other) => issue(
+                        ManifestIssueKind::InvalidValue,
+                        &path,
+                        format!("package.{} must be a string, found {}", field, yaml_type(other)),
+                    ),
+                }
+            }
+            for key in package.keys() {
+                let name = key.as_str().unwrap_or_default();
+                if !PACKAGE_KEYS.contains(&name) {
+                    issue(
+                        ManifestIssueKind::UnknownField,
+                        &["package", name],
+                        format!("Unknown field 'package.{}'", name),
+                    );
+                }
+            }
+        }
+        Some(other) => issue(
+            ManifestIssueKind::InvalidValue,
+            &["package"],
+            format!("package must be a mapping, found {}", yaml_type(other)),
+        ),
+    }
+
+    for table in ["dependencies", "dev-dependencies"] {
+        match root.get(table) {
+            None | Some(serde_yaml::Value::Mapping(_)) | Some(serde_yaml::Value::Null) => {}
+            Some(other) => issue(
+                ManifestIssueKind::InvalidValue,
+                &[table],
+                format!("{} must be a mapping, found {}", table, yaml_type(other)),
+            ),
+        }
+    }
+
+    issues
+}
+
+fn yaml_type(value: &serde_yaml::Value) -> &'static str {
+    match value {
+        serde_yaml::Value::Null => "null",
+        serde_yaml::Value::Bool(_) => "a boolean",
+        serde_yaml::Value::Number(_) => "a number",
+        serde_yaml::Value::String(_) => "a string",
+        serde_yaml::Value::Sequence(_) => "a list",
+        serde_yaml::Value::Mapping(_) => "a mapping",
+        serde_yaml::Value::Tagged(_) => "a tagged value",
+    }
+}
+
+/// 1-based line of a block-style key path such as `package` / `name`.
+/// Each key is searched below its parent, at a deeper indent.
+fn key_line(content: &str, path: &[&str]) -> Option<usize> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut start = 0;
+    let mut end = lines.len();
+    let mut parent_indent: Option<usize> = None;
+    let mut found = None;
+
+    for key in path {
+        let (index, indent) = (start..end).find_map(|i| {
+            let line = lines[i];
+            let indent = line.len() - line.trim_start().len();
+            let deeper = parent_indent.map_or(indent == 0, |p| indent > p);
+            let is_key = line.trim_start().strip_prefix(key).is_some_and(|rest| rest.starts_with(':'));
+            (deeper && is_key).then_some((i, indent))
+        })?;
+
+        // The key's block ends at the next line indented no deeper than it
+        start = index + 1;
+        end = (start..end)
+            .find(|&i| {
+                let line = lines[i];
+                !line.trim().is_empty() && !line.trim_start().starts_with('#') && line.len() - line.trim_start().len() <= indent
+            })
+            .unwrap_or(end);
+        parent_indent = Some(indent);
+        found = Some(index + 1);
+    }
+    found
+}
+
 pub fn validate_manifest(m: &CrateManifest) -> Result<CrateManifest> {
     let mut manifest = m.clone();
 
@@ -29,3 +175,37 @@ pub fn validate_manifest(m: &CrateManifest) -> Result<CrateManifest> {
 
     Ok(manifest)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_manifest_locates_issues() {
+        let content = "\
+# crate manifest
+package:
+  name: gear
+  version: [1, 0]
+  homepage: example.org
+dependencies:
+  log: \"0.4\"
+";
+        let issues = check_manifest(content);
+        let summary: Vec<(ManifestIssueKind, &str, usize)> =
+            issues.iter().map(|i| (i.kind, i.field.as_str(), i.line)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ManifestIssueKind::InvalidValue, "package.version", 4),
+                (ManifestIssueKind::MissingField, "package.edition", 2),
+                (ManifestIssueKind::UnknownField, "package.homepage", 5),
+            ]
+        );
+        assert!(issues[0].message.contains("found a list"));
+
+        assert!(check_manifest("package:\n  name: a\n  version: \"1\"\n  edition: \"2021\"\n").is_empty());
+        assert_eq!(check_manifest("dependencies: {}\n")[0].field, "package");
+        assert!(check_manifest("- not a mapping\n").is_empty());
+    }
+}
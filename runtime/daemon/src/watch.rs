@@ -1,10 +1,58 @@
 use anyhow::Result;
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::sync::mpsc::Sender;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use crate::types::WatchEvent;
 
+/// Coalesces rapid events per path: a path becomes ready once no event for
+/// it has arrived for `window`, so a burst of saves is handled once.
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    window: Duration,
+    pending: HashMap<String, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: HashMap::new() }
+    }
+
+    /// Record an event for `path`, restarting its quiet period
+    pub fn push(&mut self, path: &str, now: Instant) {
+        self.pending.insert(path.to_string(), now);
+    }
+
+    /// Forget a queued path (e.g. the file was removed)
+    pub fn cancel(&mut self, path: &str) {
+        self.pending.remove(path);
+    }
+
+    /// Earliest time a queued path becomes ready
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().map(|last| *last + self.window)
+    }
+
+    /// Remove and return the paths that have been quiet for the window, sorted
+    pub fn take_ready(&mut self, now: Instant) -> Vec<String> {
+        let window = self.window;
+        let mut ready: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, last)| now.duration_since(**last) >= window)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready.sort();
+        ready
+    }
+}
+
 /// Starts a file watcher for provided paths and emits WatchEvent into tx.
+/// Directories are watched recursively, files on their own.
 pub async fn start_watch(paths: Vec<String>, tx: Sender<WatchEvent>) -> Result<()> {
     let path_count = paths.len();
     let tx_clone = tx.clone();
@@ -14,7 +62,7 @@ pub async fn start_watch(paths: Vec<String>, tx: Sender<WatchEvent>) -> Result<(
             move |res: Result<notify::Event, notify::Error>| {
                 match res {
                     Ok(event) => {
-                        let path = event.paths.get(0).cloned().unwrap_or(PathBuf::from(""));
+                        let path = event.paths.first().cloned().unwrap_or(PathBuf::from(""));
                         let path_str = path.to_string_lossy().to_string();
                         let ev = match event.kind {
                             EventKind::Create(_) => WatchEvent::Created { path: path_str },
@@ -35,16 +83,17 @@ pub async fn start_watch(paths: Vec<String>, tx: Sender<WatchEvent>) -> Result<(
         ).expect("Failed to create watcher");
 
         for p in &paths {
-            if let Err(e) = watcher.watch(&PathBuf::from(p), RecursiveMode::NonRecursive) {
+            let mode = if Path::new(p).is_dir() { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+            if let Err(e) = watcher.watch(&PathBuf::from(p), mode) {
                 let _ = tx_clone.blocking_send(WatchEvent::Error {
                     message: format!("watch_add_error path={} err={}", p, e),
                 });
             }
         }
 
-        // Keep watcher alive - it needs to run indefinitely
-        loop {
-            std::thread::sleep(std::time::Duration::from_secs(3600));
+        // Keep watcher alive until the receiving side goes away
+        while !tx_clone.is_closed() {
+            std::thread::sleep(std::time::Duration::from_secs(1));
         }
     });
 
@@ -1,13 +1,120 @@
 #[test]
 fn test_manifest_pipeline() {
     env_logger::try_init().ok();
-    let path = "../../manifests/oasm_manifest.yaml";
-    let result = compiler::compile_manifest(path);
+
+    // The pipeline commits the manifest back in place, so work on a copy
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/crate_manifest.yaml");
+    let content = std::fs::read_to_string(fixture).unwrap();
+    assert_eq!(runtime_daemon::validator::check_manifest(&content), vec![]);
+    let path = dir.path().join("crate_manifest.yaml");
+    std::fs::write(&path, &content).unwrap();
+
+    let lineage_log = dir.path().join("lineage.log");
+    let options = compiler::CompileOptions { lineage_log: Some(lineage_log.clone()), ..Default::default() };
+    let result = compiler::compile_manifest_with(path.to_str().unwrap(), &options);
     assert!(result.is_ok(), "Pipeline failed: {:?}", result);
 
     // Verify lineage log was updated
-    let log_contents = std::fs::read_to_string("../lineage/lineage.log")
+    let log_contents = std::fs::read_to_string(&lineage_log)
         .expect("Failed to read lineage.log");
     assert!(log_contents.contains("Manifest compiled successfully"),
             "Lineage log missing success entry");
 }
+
+mod overlay_watch {
+    use asm_formats::runtime::CommandBlockBuilder;
+    use asm_formats::schemas::{AutoPopulatedFields, BlockType, ParameterValue, YAMLOverlay};
+    use asm_formats::{Actor, ExecutionMetadata, RunId, Seq};
+    use runtime_daemon::overlay::{report_path, OverlayReport, OverlayWatchConfig, OverlayWatcher};
+    use runtime_daemon::types::WatchEvent;
+    use std::time::{Duration, Instant};
+    use tokio::sync::mpsc;
+
+    fn valid_overlay() -> String {
+        let overlay = YAMLOverlay {
+            comment: None,
+            metadata: ExecutionMetadata::new(Actor::System),
+            command: CommandBlockBuilder::new(BlockType::LintCheck)
+                .parameter("level", ParameterValue::String("strict".to_string()))
+                .build(),
+            auto_populated: AutoPopulatedFields {
+                run_id: RunId::new(),
+                seq: Seq::zero(),
+                timestamp: chrono::Utc::now(),
+                actor: Actor::System,
+                file_path: None,
+                rule_group: None,
+                confidence: None,
+                tests_planned: vec![],
+            },
+            annotations: vec![],
+            source_template_id: None,
+        };
+        serde_yaml::to_string(&overlay).unwrap()
+    }
+
+    /// Drive the overlay watcher like the supervisor loop until it reports
+    async fn next_report(rx: &mut mpsc::Receiver<WatchEvent>, watcher: &mut OverlayWatcher) -> OverlayReport {
+        let give_up = Instant::now() + Duration::from_secs(10);
+        loop {
+            assert!(Instant::now() < give_up, "no overlay report within 10s");
+            let wait_until = watcher.next_deadline().unwrap_or_else(|| Instant::now() + Duration::from_millis(100));
+            if let Ok(Some(ev)) = tokio::time::timeout_at(tokio::time::Instant::from_std(wait_until), rx.recv()).await {
+                watcher.observe(&ev, Instant::now());
+                continue;
+            }
+            if let Some(report) = watcher.flush(Instant::now()).pop() {
+                return report;
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_overlay_report_flips_after_fix() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = tempfile::tempdir().unwrap();
+        let dashboard = spool.path().join("dashboard.jsonl");
+        let watched = dir.path().to_string_lossy().to_string();
+
+        let (tx, mut rx) = mpsc::channel(128);
+        runtime_daemon::watch::start_watch(vec![watched.clone()], tx).await.unwrap();
+        let mut watcher = OverlayWatcher::new(OverlayWatchConfig {
+            dirs: vec![watched],
+            spool_dir: Some(spool.path().join("cbor")),
+            dashboard_log: Some(dashboard.clone()),
+            debounce: Duration::from_millis(50),
+        });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let overlay_path = dir.path().join("gear.overlay.yaml");
+        std::fs::write(&overlay_path, "command: [unterminated\n").unwrap();
+        let report = next_report(&mut rx, &mut watcher).await;
+        assert!(!report.passed);
+        assert!(report.errors[0].starts_with("schema: "), "{:?}", report.errors);
+
+        let saved: OverlayReport =
+            serde_json::from_str(&std::fs::read_to_string(report_path(&overlay_path)).unwrap()).unwrap();
+        assert_eq!(saved, report);
+
+        std::fs::write(&overlay_path, valid_overlay()).unwrap();
+        let report = next_report(&mut rx, &mut watcher).await;
+        assert!(report.passed, "{:?}", report.errors);
+        let spooled = report.spool_object.clone().expect("passing overlay is spooled");
+        assert!(std::path::Path::new(&spooled).is_file());
+
+        let saved: OverlayReport =
+            serde_json::from_str(&std::fs::read_to_string(report_path(&overlay_path)).unwrap()).unwrap();
+        assert!(saved.passed);
+
+        let rows: Vec<compiler::cli_dashboard::DashboardRow> = std::fs::read_to_string(&dashboard)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.section == Some(compiler::cli_dashboard::Section::Overlay)));
+        assert_eq!(rows[0].totals.warn, 1);
+        assert_eq!(rows[1].totals.warn, 0);
+    }
+}
@@ -0,0 +1,689 @@
+//! First-run bootstrap
+//! Detects the project layout (Cargo workspace members, Python directories)
+//! and writes the files the rest of OASM expects:
+//! - `oasm.config.yaml` for the scanner (exclusions, arms, popup decisions)
+//! - `manifests/oasm_manifest.yaml`, a master manifest listing detected modules
+//! - `oasm.project.yaml`, an initial project rules file
+//!
+//! The result is then checked with the manifest doctor, the rule loader and a
+//! first scan. Files that already exist are merged or skipped, as decided per
+//! file; they are never silently overwritten.
+
+use asm_formats::PopupDecision;
+use oasm_core::rules::loader::RuleLoader;
+use runtime_daemon::manifest_loader::{ManifestLoader, MasterManifest, ModuleInfo};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = "oasm.config.yaml";
+pub const MANIFEST_FILE: &str = "manifests/oasm_manifest.yaml";
+pub const PROJECT_RULES_FILE: &str = "oasm.project.yaml";
+
+/// Directories never scanned or proposed as modules (as in the phase 1 defaults)
+const EXCLUDED_DIRS: [&str; 8] = [".git", "node_modules", "target", "build", "dist", "logs", ".venv", "venv"];
+
+/// Python directories are searched this many levels below the root
+const PYTHON_DEPTH: usize = 3;
+
+const ENCODINGS: [&str; 3] = ["utf-8", "utf-16", "latin-1"];
+const DUPLICATE_STRATEGIES: [&str; 3] = ["keep-first", "keep-latest", "fail"];
+const LANGUAGES: [&str; 5] = ["rust", "python", "cpp", "go", "javascript"];
+const EXISTING_ACTIONS: [&str; 2] = ["skip", "merge"];
+
+/// Source of answers to the bootstrap's popup decisions
+pub trait DecisionProvider {
+    /// Pick one of `options` (the first is the default). `key` names the
+    /// decision, and the `bootstrap` flag that answers it.
+    fn choose(&mut self, key: &str, prompt: &str, options: &[&str]) -> String;
+}
+
+/// Answers from `bootstrap` flags. Unanswered decisions are asked on stdin,
+/// or take their default when non-interactive (`--yes`).
+#[derive(Debug, Default)]
+pub struct FlagDecisions {
+    answers: HashMap<String, String>,
+    interactive: bool,
+}
+
+impl FlagDecisions {
+    /// Non-interactive providers use defaults for everything not answered with `answer`
+    pub fn new(interactive: bool) -> Self {
+        Self { interactive, ..Self::default() }
+    }
+
+    pub fn answer(mut self, key: &str, value: &str) -> Self {
+        self.answers.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl DecisionProvider for FlagDecisions {
+    fn choose(&mut self, key: &str, prompt: &str, options: &[&str]) -> String {
+        if let Some(answer) = self.answers.get(key) {
+            return answer.clone();
+        }
+        if !self.interactive {
+            return options[0].to_string();
+        }
+
+        loop {
+            print!("{} [{}] (default {}): ", prompt, options.join("/"), options[0]);
+            io::stdout().flush().ok();
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+                return options[0].to_string();
+            }
+            match line.trim() {
+                "" => return options[0].to_string(),
+                answer if options.contains(&answer) => return answer.to_string(),
+                answer => println!("  '{}' is not one of: {}", answer, options.join(", ")),
+            }
+        }
+    }
+}
+
+/// What a bootstrap run needs besides the decision provider
+#[derive(Debug, Clone)]
+pub struct BootstrapOptions {
+    pub root: PathBuf,
+}
+
+/// Parse `bootstrap` arguments:
+/// `[--root DIR] [--yes] [--encoding E] [--duplicates D] [--language L] [--existing merge|skip]`
+pub fn parse_args(args: &[&str]) -> Result<(BootstrapOptions, FlagDecisions), String> {
+    let mut root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let mut decisions = FlagDecisions::new(true);
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().copied().ok_or_else(|| format!("{} needs a value", flag));
+        let (key, options): (&str, &[&str]) = match *flag {
+            "--yes" | "-y" => {
+                decisions.interactive = false;
+                continue;
+            }
+            "--root" => {
+                root = PathBuf::from(value()?);
+                continue;
+            }
+            "--encoding" => ("encoding", &ENCODINGS),
+            "--duplicates" => ("duplicates", &DUPLICATE_STRATEGIES),
+            "--language" => ("language", &LANGUAGES),
+            "--existing" => ("existing", &EXISTING_ACTIONS),
+            other => return Err(format!("Unknown bootstrap option '{}'", other)),
+        };
+        let value = value()?;
+        if !options.contains(&value) {
+            return Err(format!("{} must be one of: {}", flag, options.join(", ")));
+        }
+        decisions = decisions.answer(key, value);
+    }
+
+    Ok((BootstrapOptions { root }, decisions))
+}
+
+/// Project layout found under the root
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layout {
+    /// Cargo packages: (package name, path relative to the root)
+    pub cargo_members: Vec<(String, String)>,
+    /// Top-most directories holding Python sources or a pyproject.toml
+    pub python_dirs: Vec<String>,
+}
+
+impl Layout {
+    /// Proposed manifest modules, one per package or Python directory
+    pub fn modules(&self) -> Vec<ModuleInfo> {
+        let rust = self.cargo_members.iter().map(|(name, location)| module(name, location, "rust_crate"));
+        let python = self.python_dirs.iter().map(|location| {
+            let name = location.rsplit('/').next().unwrap_or(location);
+            module(name, location, "python_package")
+        });
+        rust.chain(python).collect()
+    }
+
+    /// Proposed arms: the locations of the proposed modules
+    pub fn arms(&self) -> Vec<String> {
+        let mut arms: Vec<String> = self.cargo_members.iter().map(|(_, location)| location.clone()).collect();
+        arms.extend(self.python_dirs.iter().cloned());
+        arms.retain(|arm| arm != ".");
+        arms
+    }
+
+    /// Languages present, most prominent first, for the default-language decision
+    fn languages(&self) -> Vec<&'static str> {
+        let mut languages = LANGUAGES.to_vec();
+        if self.cargo_members.is_empty() && !self.python_dirs.is_empty() {
+            languages.swap(0, 1);
+        }
+        languages
+    }
+}
+
+fn module(name: &str, location: &str, module_type: &str) -> ModuleInfo {
+    ModuleInfo {
+        id: name.replace('-', "_"),
+        name: name.to_string(),
+        module_type: module_type.to_string(),
+        location: location.to_string(),
+        ..ModuleInfo::default()
+    }
+}
+
+/// Detect Cargo packages (workspace members, or the root package) and Python directories
+pub fn detect_layout(root: &Path) -> Layout {
+    let mut layout = Layout::default();
+
+    if let Ok(manifest) = std::fs::read_to_string(root.join("Cargo.toml")) {
+        for member in workspace_members(&manifest) {
+            for location in expand_member(root, &member) {
+                if let Some(name) = package_name(&root.join(&location)) {
+                    layout.cargo_members.push((name, location));
+                }
+            }
+        }
+        if layout.cargo_members.is_empty() {
+            if let Some(name) = package_name(root) {
+                layout.cargo_members.push((name, ".".to_string()));
+            }
+        }
+    }
+
+    find_python_dirs(root, root, 0, &mut layout.python_dirs);
+    layout.python_dirs.sort();
+    layout
+}
+
+/// The parts of a Cargo.toml that layout detection reads
+#[derive(Debug, Default, Deserialize)]
+struct CargoManifest {
+    workspace: Option<CargoWorkspace>,
+    package: Option<CargoPackage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CargoWorkspace {
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoPackage {
+    name: String,
+}
+
+/// `[workspace] members`; none when the manifest is not valid TOML
+fn workspace_members(cargo_toml: &str) -> Vec<String> {
+    toml::from_str::<CargoManifest>(cargo_toml)
+        .ok()
+        .and_then(|manifest| manifest.workspace)
+        .map(|workspace| workspace.members)
+        .unwrap_or_default()
+}
+
+/// `crates/*` expands to the crate directories under `crates/`
+fn expand_member(root: &Path, member: &str) -> Vec<String> {
+    let Some(parent) = member.strip_suffix("/*") else {
+        return vec![member.to_string()];
+    };
+    let mut locations: Vec<String> = std::fs::read_dir(root.join(parent))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("Cargo.toml").is_file())
+        .map(|entry| format!("{}/{}", parent, entry.file_name().to_string_lossy()))
+        .collect();
+    locations.sort();
+    locations
+}
+
+/// `name` from a crate's `[package]` section
+fn package_name(dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    toml::from_str::<CargoManifest>(&content).ok()?.package.map(|package| package.name)
+}
+
+fn find_python_dirs(root: &Path, dir: &Path, depth: usize, found: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut subdirs = Vec::new();
+    let mut is_python = false;
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !EXCLUDED_DIRS.contains(&name.as_str()) && !name.starts_with('.') {
+                subdirs.push(path);
+            }
+        } else if name.ends_with(".py") || name == "pyproject.toml" {
+            is_python = true;
+        }
+    }
+
+    // The root itself is never a Python module; nested package dirs belong to the top-most one
+    if is_python && depth > 0 {
+        found.push(relative(root, dir));
+    } else if depth < PYTHON_DEPTH {
+        for subdir in subdirs {
+            find_python_dirs(root, &subdir, depth + 1, found);
+        }
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// What happened to one generated file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    Created,
+    Merged,
+    Skipped,
+}
+
+/// Outcome of a bootstrap run
+#[derive(Debug, Clone)]
+pub struct BootstrapReport {
+    pub layout: Layout,
+    pub decisions: Vec<PopupDecision>,
+    pub files: Vec<(String, FileAction)>,
+    /// Doctor, rule loader and config problems; empty when consistent
+    pub problems: Vec<String>,
+    /// Files seen by the first scan, outside excluded directories
+    pub scanned_files: usize,
+}
+
+impl BootstrapReport {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Decisions made so far, recorded as popup decisions
+struct Recorder<'a> {
+    provider: &'a mut dyn DecisionProvider,
+    decisions: Vec<PopupDecision>,
+}
+
+impl Recorder<'_> {
+    fn choose(&mut self, key: &str, prompt: &str, options: &[&str]) -> String {
+        let decision = self.provider.choose(key, prompt, options);
+        self.decisions.push(PopupDecision {
+            timestamp: chrono::Utc::now(),
+            prompt: prompt.to_string(),
+            decision: decision.clone(),
+            options_presented: options.iter().map(|o| o.to_string()).collect(),
+        });
+        decision
+    }
+}
+
+/// Run the bootstrap: detect, decide, write, then check the result
+pub fn run(options: &BootstrapOptions, provider: &mut dyn DecisionProvider) -> anyhow::Result<BootstrapReport> {
+    let root = &options.root;
+    let layout = detect_layout(root);
+    let mut recorder = Recorder { provider, decisions: Vec::new() };
+
+    let encoding = recorder.choose("encoding", "File encoding", &ENCODINGS);
+    let duplicates = recorder.choose("duplicates", "Duplicate file strategy", &DUPLICATE_STRATEGIES);
+    let language = recorder.choose("language", "Default language", &layout.languages());
+
+    let project = root
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "project".to_string());
+
+    let generated = [
+        (CONFIG_FILE, config_yaml(&layout, &encoding, &duplicates, &language)),
+        (MANIFEST_FILE, serde_yaml::to_string(&MasterManifest::new(layout.modules()))?),
+        (PROJECT_RULES_FILE, project_rules_yaml(&project)),
+    ];
+
+    let mut files = Vec::new();
+    for (name, content) in generated {
+        let path = root.join(name);
+        let action = if path.exists() {
+            let prompt = format!("{} exists; merge or skip", name);
+            match recorder.choose("existing", &prompt, &EXISTING_ACTIONS).as_str() {
+                "merge" => {
+                    let existing = std::fs::read_to_string(&path)?;
+                    let merged = merge(name, &existing, &content)
+                        .map_err(|e| anyhow::anyhow!("Cannot merge {}: {}", name, e))?;
+                    std::fs::write(&path, merged)?;
+                    FileAction::Merged
+                }
+                _ => FileAction::Skipped,
+            }
+        } else {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, content)?;
+            FileAction::Created
+        };
+        files.push((name.to_string(), action));
+    }
+
+    Ok(BootstrapReport {
+        problems: check(root),
+        scanned_files: count_files(root),
+        layout,
+        decisions: recorder.decisions,
+        files,
+    })
+}
+
+/// Scanner config. Arms use flow style so line-based readers of
+/// `exclusions` (oasm-phase1) do not mistake them for exclusions.
+fn config_yaml(layout: &Layout, encoding: &str, duplicates: &str, language: &str) -> String {
+    format!(
+        r#"exclusions:
+  - .git/
+  - node_modules/
+  - target/
+  - build/
+  - dist/
+  - logs/
+  - '**/*.lock'
+  - '**/*.tmp'
+  - '**/*.bak'
+  - .DS_Store
+autoRepairThreshold: 85
+arms: [{}]
+logRetention: 10
+concurrency: 2
+encoding: {}
+duplicateStrategy: {}
+defaultLanguage: {}
+"#,
+        layout.arms().join(", "),
+        encoding,
+        duplicates,
+        language
+    )
+}
+
+fn project_rules_yaml(project: &str) -> String {
+    format!(
+        r#"name: {project}
+rules:
+  - id: {project}_named
+    program_type: general
+    category: validation
+    level: project
+    conditions:
+      - check_type: "required:name"
+        severity: info
+        message: Objects should have a name
+"#
+    )
+}
+
+/// Merge generated content into an existing file, keeping what is there:
+/// the config gains missing top-level keys, the manifest missing modules,
+/// the rules file missing rule ids
+fn merge(name: &str, existing: &str, generated: &str) -> Result<String, String> {
+    match name {
+        CONFIG_FILE => merge_config(existing, generated),
+        MANIFEST_FILE => merge_manifest(existing, generated),
+        _ => merge_rules(existing, generated),
+    }
+}
+
+fn merge_config(existing: &str, generated: &str) -> Result<String, String> {
+    let current: serde_yaml::Mapping = parse_mapping(existing)?;
+
+    // Append the generated text of each missing key, so existing text stays untouched
+    let mut merged = existing.to_string();
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    let mut include = false;
+    for line in generated.lines() {
+        if !line.starts_with(' ') {
+            let key = line.split(':').next().unwrap_or_default();
+            include = !current.contains_key(key);
+        }
+        if include {
+            merged.push_str(line);
+            merged.push('\n');
+        }
+    }
+    Ok(merged)
+}
+
+fn merge_manifest(existing: &str, generated: &str) -> Result<String, String> {
+    let mut manifest = MasterManifest::parse(existing).map_err(|e| format!("{:#}", e))?;
+    let detected = MasterManifest::parse(generated).map_err(|e| format!("{:#}", e))?;
+
+    for module in detected.modules {
+        if manifest.modules.iter().all(|m| m.id != module.id) {
+            manifest.load_order.on_demand.push(module.id.clone());
+            manifest.modules.push(module);
+        }
+    }
+    serde_yaml::to_string(&manifest).map_err(|e| e.to_string())
+}
+
+fn merge_rules(existing: &str, generated: &str) -> Result<String, String> {
+    let mut current = parse_mapping(existing)?;
+    let detected = parse_mapping(generated)?;
+
+    let rule_id = |rule: &serde_yaml::Value| rule.get("id").and_then(|id| id.as_str()).map(str::to_string);
+    let rules = current
+        .entry("rules".into())
+        .or_insert_with(|| serde_yaml::Value::Sequence(Vec::new()));
+    let serde_yaml::Value::Sequence(rules) = rules else {
+        return Err("'rules' is not a list".to_string());
+    };
+
+    if let Some(serde_yaml::Value::Sequence(new_rules)) = detected.get("rules") {
+        for rule in new_rules {
+            if rules.iter().all(|r| rule_id(r) != rule_id(rule)) {
+                rules.push(rule.clone());
+            }
+        }
+    }
+    if !current.contains_key("name") {
+        if let Some(name) = detected.get("name") {
+            current.insert("name".into(), name.clone());
+        }
+    }
+    serde_yaml::to_string(&current).map_err(|e| e.to_string())
+}
+
+fn parse_mapping(content: &str) -> Result<serde_yaml::Mapping, String> {
+    if content.trim().is_empty() {
+        return Ok(serde_yaml::Mapping::new());
+    }
+    serde_yaml::from_str(content).map_err(|e| e.to_string())
+}
+
+/// Manifest doctor plus config and project rules checks
+pub fn check(root: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    match std::fs::read_to_string(root.join(CONFIG_FILE)) {
+        Err(e) => problems.push(format!("{}: {}", CONFIG_FILE, e)),
+        Ok(content) => {
+            if let Err(e) = parse_mapping(&content) {
+                problems.push(format!("{}: {}", CONFIG_FILE, e));
+            }
+        }
+    }
+
+    match ManifestLoader::load(root.join(MANIFEST_FILE)) {
+        Err(e) => problems.push(format!("{}: {:#}", MANIFEST_FILE, e)),
+        Ok(loader) => problems.extend(loader.doctor().into_iter().map(|p| format!("{}: {}", MANIFEST_FILE, p))),
+    }
+
+    if let Err(e) = RuleLoader::new().load_project_rules(root) {
+        problems.push(format!("{}: {}", PROJECT_RULES_FILE, e));
+    }
+    problems
+}
+
+/// First scan: files under the root outside excluded directories
+fn count_files(dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() && !EXCLUDED_DIRS.contains(&name.as_str()) => count_files(&entry.path()),
+                Ok(t) if t.is_file() => 1,
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+/// Print the outcome and what to do next
+pub fn print_report(report: &BootstrapReport) {
+    println!("\nDetected {} Cargo package(s), {} Python dir(s)", report.layout.cargo_members.len(), report.layout.python_dirs.len());
+    for module in report.layout.modules() {
+        println!("  module {:<24} {} ({})", module.id, module.location, module.module_type);
+    }
+    for decision in &report.decisions {
+        println!("  {}: {}", decision.prompt, decision.decision);
+    }
+    for (file, action) in &report.files {
+        println!("  {:<32} {:?}", file, action);
+    }
+    if report.files.iter().any(|(_, action)| *action == FileAction::Skipped) {
+        println!("  (kept existing files; re-run with --existing merge to add what was detected)");
+    }
+    println!("First scan: {} file(s)", report.scanned_files);
+
+    if report.passed() {
+        println!("[OK] Manifest doctor and rules check passed");
+    } else {
+        println!("[FAIL] {} problem(s):", report.problems.len());
+        for problem in &report.problems {
+            println!("  - {}", problem);
+        }
+    }
+
+    println!("\nNext steps:");
+    println!("  - Review {} and set capabilities/auto_start per module", MANIFEST_FILE);
+    println!("  - Add project rules to {}", PROJECT_RULES_FILE);
+    println!("  - Run oasm-phase1 for a full structure scan");
+    println!("  - Start the daemon with runtime_daemon");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(report: &BootstrapReport, file: &str) -> Option<FileAction> {
+        report.files.iter().find(|(name, _)| name == file).map(|(_, action)| *action)
+    }
+
+    fn fixture_workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+
+        // `default-members` comes first and must not be mistaken for `members`
+        write("Cargo.toml", "[workspace]\ndefault-members = [\"app\"]\nmembers = [\n    \"app\", # binary\n    \"crates/*\",\n]\n");
+        write("app/Cargo.toml", "[package]\nname = \"app\"\nversion = \"0.1.0\"\n");
+        write("crates/gear-core/Cargo.toml", "[package]\nname = \"gear-core\"\n\n[dependencies]\nname = \"x\"\n");
+        write("crates/gear-core/src/lib.rs", "");
+        write("tools/py/build.py", "print('hi')\n");
+        write("tools/py/pkg/__init__.py", "");
+        write("target/debug/junk.py", "");
+        dir
+    }
+
+    #[test]
+    fn test_detect_layout() {
+        let dir = fixture_workspace();
+        let layout = detect_layout(dir.path());
+
+        assert_eq!(
+            layout.cargo_members,
+            vec![("app".to_string(), "app".to_string()), ("gear-core".to_string(), "crates/gear-core".to_string())]
+        );
+        assert_eq!(layout.python_dirs, vec!["tools/py"]);
+        assert_eq!(layout.modules()[1].id, "gear_core");
+        assert_eq!(layout.arms(), vec!["app", "crates/gear-core", "tools/py"]);
+    }
+
+    #[test]
+    fn test_non_interactive_bootstrap_passes_doctor() {
+        let dir = fixture_workspace();
+        let options = BootstrapOptions { root: dir.path().to_path_buf() };
+        let mut decisions = FlagDecisions::new(false).answer("language", "python");
+
+        let report = run(&options, &mut decisions).unwrap();
+        assert!(report.passed(), "{:?}", report.problems);
+        assert_eq!(action(&report, MANIFEST_FILE), Some(FileAction::Created));
+        assert_eq!(report.decisions.len(), 3);
+        assert!(report.scanned_files >= 8);
+
+        let config = std::fs::read_to_string(dir.path().join(CONFIG_FILE)).unwrap();
+        assert!(config.contains("arms: [app, crates/gear-core, tools/py]"));
+        assert!(config.contains("encoding: utf-8\nduplicateStrategy: keep-first\ndefaultLanguage: python"));
+        let rules = RuleLoader::new().load_project_rules(dir.path()).unwrap();
+        assert_eq!(rules.len(), 1);
+    }
+
+    #[test]
+    fn test_rerun_skips_or_merges_existing_files() {
+        let dir = fixture_workspace();
+        let options = BootstrapOptions { root: dir.path().to_path_buf() };
+        run(&options, &mut FlagDecisions::new(false)).unwrap();
+
+        // Hand edits survive a default (skip) re-run
+        let config_path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&config_path, "exclusions:\n  - vendor/\nconcurrency: 8\n").unwrap();
+        let report = run(&options, &mut FlagDecisions::new(false)).unwrap();
+        assert_eq!(action(&report, CONFIG_FILE), Some(FileAction::Skipped));
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), "exclusions:\n  - vendor/\nconcurrency: 8\n");
+
+        // Merging keeps existing values and adds what is missing
+        std::fs::create_dir_all(dir.path().join("crates/gear-io")).unwrap();
+        std::fs::write(dir.path().join("crates/gear-io/Cargo.toml"), "[package]\nname = \"gear-io\"\n").unwrap();
+        let mut merge = FlagDecisions::new(false).answer("existing", "merge");
+        let report = run(&options, &mut merge).unwrap();
+        assert!(report.passed(), "{:?}", report.problems);
+        assert!(report.files.iter().all(|(_, action)| *action == FileAction::Merged));
+
+        let config = std::fs::read_to_string(&config_path).unwrap();
+        assert!(config.starts_with("exclusions:\n  - vendor/\nconcurrency: 8\n"));
+        assert_eq!(config.matches("concurrency").count(), 1);
+        assert!(config.contains("autoRepairThreshold: 85"));
+
+        let loader = ManifestLoader::load(dir.path().join(MANIFEST_FILE)).unwrap();
+        assert_eq!(loader.manifest().modules.len(), 4);
+        assert!(loader.get_module("gear_io").is_some());
+        assert_eq!(RuleLoader::new().load_project_rules(dir.path()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let (options, mut decisions) =
+            parse_args(&["--root", "/tmp/x", "--yes", "--existing", "merge"]).unwrap();
+        assert_eq!(options.root, PathBuf::from("/tmp/x"));
+        assert_eq!(decisions.choose("existing", "?", &EXISTING_ACTIONS), "merge");
+        assert_eq!(decisions.choose("encoding", "?", &ENCODINGS), "utf-8");
+
+        assert!(parse_args(&["--language", "cobol"]).is_err());
+        assert!(parse_args(&["--root"]).is_err());
+        assert!(parse_args(&["--force"]).is_err());
+    }
+}
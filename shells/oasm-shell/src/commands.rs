@@ -0,0 +1,140 @@
+use clap::{CommandFactory, Parser, Subcommand};
+
+/// Built-ins whose rest of line is passed through verbatim (OASM source
+/// keeps its quoting and spacing)
+const RAW_TAIL: &[&str] = &["run"];
+
+/// One line of shell input, parsed as a built-in command
+#[derive(Parser, Debug, PartialEq)]
+#[command(name = "oasm", no_binary_name = true, disable_help_subcommand = true)]
+struct ShellLine {
+    #[command(subcommand)]
+    command: ShellCommand,
+}
+
+/// Shell built-in commands
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ShellCommand {
+    /// Exit the shell (prints the run summary when recording)
+    #[command(alias = "quit")]
+    Exit,
+    /// Show command history
+    History {
+        /// Only show the last N commands
+        count: Option<usize>,
+    },
+    /// Clear screen
+    Clear,
+    /// Show shell help
+    Help,
+    /// Show task count, capabilities and OASM session
+    Status,
+    /// Start a multi-line OASM block ('end' runs it, 'cancel' discards)
+    Begin,
+    /// Run the open OASM block
+    End,
+    /// Discard the open OASM block
+    Cancel,
+    /// Execute OASM instructions (e.g. run CREATE gear)
+    Run {
+        /// OASM source, as typed
+        source: String,
+    },
+    /// Execute an OASM script file, inlining its #include directives
+    Load {
+        path: std::path::PathBuf,
+    },
+    /// List active capabilities
+    Caps {
+        #[command(subcommand)]
+        action: Option<CapsAction>,
+    },
+    /// Set up oasm.config.yaml, the master manifest and project rules
+    Bootstrap {
+        /// Options for `bootstrap::parse_args`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Print the OASM source a recorded run executed (reads OASM_LINEAGE_DIR)
+    Reconstruct {
+        run_id: String,
+    },
+    /// Show a recorded run and the runs it was spawned from as a tree (reads OASM_LINEAGE_DIR)
+    Ancestry {
+        run_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum CapsAction {
+    /// Export capabilities as JSON with their source
+    Export,
+}
+
+impl ShellCommand {
+    /// Parse a line as a built-in. `None` when its first word is not a
+    /// built-in (the line goes to the router); `Some(Err)` carries clap's
+    /// usage error or `--help` text for a built-in used wrongly.
+    pub fn parse_line(line: &str) -> Option<Result<ShellCommand, clap::Error>> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        ShellLine::command().find_subcommand(name)?;
+
+        let mut args = vec![name.to_string()];
+        if RAW_TAIL.contains(&name) {
+            if !rest.trim().is_empty() {
+                args.push(rest.trim().to_string());
+            }
+        } else {
+            args.extend(rest.split_whitespace().map(str::to_string));
+        }
+        Some(ShellLine::try_parse_from(args).map(|parsed| parsed.command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    #[test]
+    fn test_parse_builtins() {
+        assert_eq!(ShellCommand::parse_line("history 3").unwrap().unwrap(), ShellCommand::History { count: Some(3) });
+        assert_eq!(ShellCommand::parse_line("history").unwrap().unwrap(), ShellCommand::History { count: None });
+        assert_eq!(ShellCommand::parse_line("quit").unwrap().unwrap(), ShellCommand::Exit);
+        assert_eq!(
+            ShellCommand::parse_line(r#"run SET name = "two  spaces""#).unwrap().unwrap(),
+            ShellCommand::Run { source: r#"SET name = "two  spaces""#.to_string() }
+        );
+        assert_eq!(
+            ShellCommand::parse_line("bootstrap --yes --root /tmp/p").unwrap().unwrap(),
+            ShellCommand::Bootstrap { args: vec!["--yes".to_string(), "--root".to_string(), "/tmp/p".to_string()] }
+        );
+        assert_eq!(
+            ShellCommand::parse_line("caps export").unwrap().unwrap(),
+            ShellCommand::Caps { action: Some(CapsAction::Export) }
+        );
+
+        // Anything else is left to the router
+        assert!(ShellCommand::parse_line("exec ls -la").is_none());
+        assert!(ShellCommand::parse_line("CREATE gear").is_none());
+    }
+
+    #[test]
+    fn test_invalid_usage_is_a_clap_error() {
+        let err = ShellCommand::parse_line("caps frobnicate").unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidSubcommand);
+        assert!(err.to_string().contains("Usage:"), "{}", err);
+
+        let err = ShellCommand::parse_line("history three").unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        assert_eq!(
+            ShellCommand::parse_line("run").unwrap().unwrap_err().kind(),
+            ErrorKind::MissingRequiredArgument
+        );
+        assert_eq!(
+            ShellCommand::parse_line("history --help").unwrap().unwrap_err().kind(),
+            ErrorKind::DisplayHelp
+        );
+    }
+}
@@ -1,15 +1,16 @@
-/// ConPTY (Console Pseudoterminal) integration for Windows terminal emulation.
-/// Provides a consistent terminal interface for executive function shell.
-///
-/// ConPTY allows the OASM shell to:
-/// - Provide consistent ANSI escape code handling
-/// - Support terminal-based UI elements (progress bars, etc.)
-/// - Integrate with Windows Terminal and other modern terminals
+//! ConPTY (Console Pseudoterminal) integration for Windows terminal emulation.
+//! Provides a consistent terminal interface for executive function shell.
+//!
+//! ConPTY allows the OASM shell to:
+//! - Provide consistent ANSI escape code handling
+//! - Support terminal-based UI elements (progress bars, etc.)
+//! - Integrate with Windows Terminal and other modern terminals
 
 use std::process::{Command, Stdio};
-use std::io::{self, Write};
+use std::io;
 
 /// Start a ConPTY session with a specified command
+#[allow(dead_code)] // exec does not spawn processes yet
 pub fn start_conpty(cmd: &str, args: &[&str]) -> io::Result<()> {
     println!("[CONPTY] Starting pseudoterminal session");
     println!("[EXEC] Command: {} {}", cmd, args.join(" "));
@@ -37,6 +38,7 @@ pub fn start_conpty(cmd: &str, args: &[&str]) -> io::Result<()> {
 }
 
 /// Initialize ConPTY with default settings
+#[allow(dead_code)] // not called at startup yet
 pub fn init_conpty() {
     println!("[CONPTY] Initializing pseudoterminal support");
     println!("[INFO] ANSI escape codes enabled for structured output");
@@ -1,8 +1,14 @@
+mod bootstrap;
+mod commands;
 mod conpty;
 mod router;
 mod security;
 mod python_bridge;
+mod repl;
 
+use asm_formats::lineage::{render_ancestry, LineageManager};
+use commands::{CapsAction, ShellCommand};
+use asm_formats::module_map::ModuleMapper;
 use std::io::{self, Write};
 
 fn main() {
@@ -16,9 +22,16 @@ fn main() {
     let mut history: Vec<String> = Vec::new();
     let mut task_count = 0u32;
 
+    // OASM session; context persists across commands
+    let mut session = repl::Repl::new();
+
     loop {
         // Clear, structured prompt (reduces cognitive load)
-        print!("oasm[{}]> ", task_count);
+        if session.in_block() {
+            print!("oasm[{}]...{}> ", task_count, session.block_len() + 1);
+        } else {
+            print!("oasm[{}]> ", task_count);
+        }
         io::stdout().flush().unwrap();
 
         let mut input = String::new();
@@ -26,6 +39,23 @@ fn main() {
             Ok(_) => {
                 let cmd = input.trim();
 
+                // Collect block lines until 'end' or 'cancel'
+                if session.in_block() {
+                    match cmd {
+                        "end" => {
+                            history.push(cmd.to_string());
+                            task_count += 1;
+                            session.end();
+                        }
+                        "cancel" => session.cancel(),
+                        _ => {
+                            history.push(cmd.to_string());
+                            session.push_line(cmd);
+                        }
+                    }
+                    continue;
+                }
+
                 // Empty command handling
                 if cmd.is_empty() {
                     continue;
@@ -36,34 +66,23 @@ fn main() {
                 task_count += 1;
 
                 // Built-in commands
-                match cmd {
-                    "exit" | "quit" => {
+                match ShellCommand::parse_line(cmd) {
+                    Some(Ok(ShellCommand::Exit)) => {
                         println!("Tasks completed: {}", task_count - 1);
+                        print_run_summary();
                         println!("Goodbye!");
                         break;
                     }
-                    "history" => {
-                        println!("\nCommand History:");
-                        for (i, h) in history.iter().enumerate() {
-                            println!("  {}: {}", i + 1, h);
-                        }
-                        println!();
-                        continue;
-                    }
-                    "clear" => {
-                        print!("\x1B[2J\x1B[1;1H"); // Clear screen
-                        continue;
-                    }
-                    "help" => {
-                        print_help();
+                    Some(Ok(command)) => {
+                        dispatch(command, &history, task_count, &mut session);
                         continue;
                     }
-                    "status" => {
-                        println!("Tasks executed: {}", task_count - 1);
-                        println!("Capabilities active: {}", security::get_active_caps());
+                    Some(Err(e)) => {
+                        // Usage errors and `--help` both come back from clap
+                        print!("{}", e);
                         continue;
                     }
-                    _ => {}
+                    None => {}
                 }
 
                 // Route command through security and execution
@@ -77,12 +96,64 @@ fn main() {
     }
 }
 
+/// Run a parsed built-in (everything but `exit`, which ends the loop)
+fn dispatch(command: ShellCommand, history: &[String], task_count: u32, session: &mut repl::Repl) {
+    match command {
+        ShellCommand::Exit => {}
+        ShellCommand::History { count } => {
+            let skip = count.map_or(0, |count| history.len().saturating_sub(count));
+            println!("\nCommand History:");
+            for (i, h) in history.iter().enumerate().skip(skip) {
+                println!("  {}: {}", i + 1, h);
+            }
+            println!();
+        }
+        ShellCommand::Clear => print!("\x1B[2J\x1B[1;1H"), // Clear screen
+        ShellCommand::Help => print_help(),
+        ShellCommand::Status => {
+            println!("Tasks executed: {}", task_count - 1);
+            println!("Capabilities active: {}", security::get_active_caps());
+            println!("OASM session: {}", session.summary());
+        }
+        ShellCommand::Begin => session.begin(),
+        ShellCommand::End => session.end(),
+        ShellCommand::Cancel => session.cancel(),
+        // Evaluate OASM instructions in the session context
+        ShellCommand::Run { source } => session.run_source(&source),
+        ShellCommand::Load { path } => session.run_file(&path),
+        ShellCommand::Caps { action: None } => security::list_capabilities(),
+        ShellCommand::Caps { action: Some(CapsAction::Export) } => {
+            match serde_json::to_string_pretty(&security::export_capabilities()) {
+                Ok(json) => println!("{}", json),
+                Err(e) => println!("ERROR: Could not export capabilities: {}", e),
+            }
+        }
+        ShellCommand::Bootstrap { args } => {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            run_bootstrap(&args);
+        }
+        ShellCommand::Reconstruct { run_id } => print_reconstructed_source(&run_id),
+        ShellCommand::Ancestry { run_id } => print_ancestry(&run_id),
+    }
+}
+
 fn print_help() {
     println!("\nOASM Shell Commands:");
     println!("  help      - Show this help");
-    println!("  history   - Show command history");
-    println!("  status    - Show task count and capabilities");
+    println!("  history [N] - Show command history (the last N commands)");
+    println!("  status    - Show task count, capabilities and OASM session");
+    println!("  run <src> - Execute OASM instructions (e.g. run CREATE gear)");
+    println!("            INSPECT [prefix] [type=Object] lists the variables and objects that exist now");
+    println!("  load <file> - Execute an OASM script, inlining its #include directives");
+    println!("  begin     - Start a multi-line OASM block ('end' runs it, 'cancel' discards)");
+    println!("  exec <program> [args...] - Execute a program");
+    println!("  caps [export] - List active capabilities, or export them as JSON with their source");
+    println!("  bootstrap [--yes] [--root DIR] [--encoding E] [--duplicates D] [--language L] [--existing merge|skip]");
+    println!("            - Set up oasm.config.yaml, the master manifest and project rules");
+    println!("  reconstruct <run-id> - Print the OASM source a recorded run executed (reads OASM_LINEAGE_DIR)");
+    println!("  ancestry <run-id> - Show a recorded run and the runs it was spawned from (reads OASM_LINEAGE_DIR)");
     println!("  clear     - Clear screen");
+    println!("  <command> --help - Usage of a built-in command");
     println!("  exit/quit - Exit shell");
     println!("\nExecutive Function Features:");
     println!("  - Numbered prompts track task progression");
@@ -91,3 +162,108 @@ fn print_help() {
     println!("  - Clear error messages with recovery suggestions");
     println!();
 }
+
+/// First-run setup; see `bootstrap` for what is written and checked
+fn run_bootstrap(args: &[&str]) {
+    let (options, mut decisions) = match bootstrap::parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return;
+        }
+    };
+    match bootstrap::run(&options, &mut decisions) {
+        Ok(report) => bootstrap::print_report(&report),
+        Err(e) => println!("ERROR: Bootstrap failed: {:#}", e),
+    }
+}
+
+/// Lineage in `lineage_dir`, stamped with the hash of the configuration of
+/// the project the shell runs in
+fn lineage_manager(lineage_dir: &str) -> LineageManager {
+    LineageManager::new(lineage_dir).with_project_config(".")
+}
+
+/// Regenerate a recorded run's source from the lineage in OASM_LINEAGE_DIR
+fn print_reconstructed_source(run: &str) {
+    let Ok(lineage_dir) = std::env::var("OASM_LINEAGE_DIR") else {
+        println!("ERROR: OASM_LINEAGE_DIR is not set");
+        return;
+    };
+
+    let manager = lineage_manager(&lineage_dir);
+    let run_id = match manager.list_runs() {
+        Ok(runs) => runs.into_iter().map(|(id, ..)| id).find(|id| id.to_string() == run),
+        Err(e) => {
+            println!("ERROR: Could not read lineage in {}: {}", lineage_dir, e);
+            return;
+        }
+    };
+    let Some(run_id) = run_id else {
+        println!("ERROR: No lineage for run {} in {}", run, lineage_dir);
+        return;
+    };
+
+    match manager.reconstruct_source(run_id) {
+        Ok(source) => print!("{}", source),
+        Err(e) => println!("ERROR: {}", e),
+    }
+}
+
+/// Print a recorded run's ancestry (parent runs first) as an indented tree
+fn print_ancestry(run: &str) {
+    let Ok(lineage_dir) = std::env::var("OASM_LINEAGE_DIR") else {
+        println!("ERROR: OASM_LINEAGE_DIR is not set");
+        return;
+    };
+
+    let manager = lineage_manager(&lineage_dir);
+    let run_id = match manager.list_runs() {
+        Ok(runs) => runs.into_iter().map(|(id, ..)| id).find(|id| id.to_string() == run),
+        Err(e) => {
+            println!("ERROR: Could not read lineage in {}: {}", lineage_dir, e);
+            return;
+        }
+    };
+    let Some(run_id) = run_id else {
+        println!("ERROR: No lineage for run {} in {}", run, lineage_dir);
+        return;
+    };
+
+    match manager.build_full_ancestry(run_id) {
+        Ok(ancestry) => print!("{}", render_ancestry(&ancestry)),
+        Err(e) => println!("ERROR: {}", e),
+    }
+}
+
+/// When the shell runs inside a recorded run (OASM_LINEAGE_DIR and
+/// OASM_RUN_ID set), print the run's summary and save it as summary.md
+fn print_run_summary() {
+    let (Ok(lineage_dir), Ok(run)) = (std::env::var("OASM_LINEAGE_DIR"), std::env::var("OASM_RUN_ID")) else {
+        return;
+    };
+
+    let manager = lineage_manager(&lineage_dir);
+    let run_id = match manager.list_runs() {
+        Ok(runs) => runs.into_iter().map(|(id, ..)| id).find(|id| id.to_string() == run),
+        Err(e) => {
+            println!("[WARN] Could not read lineage in {}: {}", lineage_dir, e);
+            return;
+        }
+    };
+    let Some(run_id) = run_id else {
+        println!("[WARN] No lineage for run {} in {}", run, lineage_dir);
+        return;
+    };
+
+    let mapper = ModuleMapper::new().with_crate("runtime/daemon", "runtime_daemon");
+    match manager.summarize_run(run_id, &mapper) {
+        Ok(summary) => {
+            println!("\n{}", summary.to_markdown());
+            if let Err(e) = manager.save_summary(&summary) {
+                println!("[WARN] Could not save run summary: {}", e);
+            }
+        }
+        Err(e) => println!("[WARN] Could not summarize run {}: {}", run, e),
+    }
+}
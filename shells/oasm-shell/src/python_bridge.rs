@@ -1,11 +1,11 @@
-/// Python bridge for extending shell with Python plugins (PyO3).
-/// Enables automation scripts and custom executive function workflows.
-///
-/// Example use cases:
-/// - Task planning and breakdown scripts
-/// - Reminder/notification automation
-/// - Data processing pipelines
-/// - Custom UI extensions
+//! Python bridge for extending shell with Python plugins (PyO3).
+//! Enables automation scripts and custom executive function workflows.
+//!
+//! Example use cases:
+//! - Task planning and breakdown scripts
+//! - Reminder/notification automation
+//! - Data processing pipelines
+//! - Custom UI extensions
 
 use std::collections::HashMap;
 use std::sync::Mutex;
@@ -15,13 +15,16 @@ static PLUGINS: Mutex<Option<HashMap<String, PluginMetadata>>> = Mutex::new(None
 
 #[derive(Clone)]
 pub struct PluginMetadata {
+    #[allow(dead_code)] // the registry is keyed by name
     pub name: String,
     pub version: String,
     pub capabilities: Vec<String>,
+    #[allow(dead_code)] // always true until plugins really load
     pub loaded: bool,
 }
 
 /// Initialize the Python bridge
+#[allow(dead_code)] // no shell command loads plugins yet
 pub fn init_python_bridge() {
     let mut plugins = PLUGINS.lock().unwrap();
     *plugins = Some(HashMap::new());
@@ -29,6 +32,7 @@ pub fn init_python_bridge() {
 }
 
 /// Load a Python plugin by name
+#[allow(dead_code)] // no shell command loads plugins yet
 pub fn load_plugin(name: &str) -> Result<(), String> {
     println!("[PYTHON] Loading plugin: {}", name);
 
@@ -55,6 +59,7 @@ pub fn load_plugin(name: &str) -> Result<(), String> {
 }
 
 /// Unload a Python plugin
+#[allow(dead_code)] // no shell command loads plugins yet
 pub fn unload_plugin(name: &str) -> Result<(), String> {
     let mut plugins = PLUGINS.lock().unwrap();
     if let Some(ref mut map) = *plugins {
@@ -70,6 +75,7 @@ pub fn unload_plugin(name: &str) -> Result<(), String> {
 }
 
 /// List all loaded plugins
+#[allow(dead_code)] // no shell command loads plugins yet
 pub fn list_plugins() {
     let plugins = PLUGINS.lock().unwrap();
     if let Some(ref map) = *plugins {
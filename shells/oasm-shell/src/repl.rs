@@ -0,0 +1,283 @@
+use oasm_core::context::{Actor, ExecutionContext};
+use oasm_core::executor::{ExecutionOutcome, ExecutorError, InstructionExecutor, NativeExecutor};
+use oasm_core::expansion::ExpansionBudget;
+use oasm_core::macro_processor::load_script;
+use oasm_core::parser::{Instruction, InstructionParser, NativeParser};
+use oasm_core::types::Value;
+use std::path::{Path, PathBuf};
+
+/// Interactive OASM session: parses and executes instructions against one
+/// ExecutionContext, so variables and objects persist between commands.
+pub struct Repl {
+    parser: NativeParser,
+    executor: NativeExecutor,
+    ctx: ExecutionContext,
+    /// Lines collected between `begin` and `end`
+    block: Option<Vec<String>>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let working_directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self {
+            parser: NativeParser,
+            executor: NativeExecutor::new(),
+            ctx: ExecutionContext::new(Actor::System, working_directory),
+            block: None,
+        }
+    }
+
+    /// True while a `begin` block is being collected
+    pub fn in_block(&self) -> bool {
+        self.block.is_some()
+    }
+
+    /// Number of lines collected so far in the open block
+    pub fn block_len(&self) -> usize {
+        self.block.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Start collecting a multi-line block
+    pub fn begin(&mut self) {
+        if self.block.is_some() {
+            println!("ERROR: A block is already open");
+            println!("SUGGESTION: Type 'end' to run it or 'cancel' to discard it");
+            return;
+        }
+        self.block = Some(Vec::new());
+        println!("[BLOCK] Enter OASM instructions, 'end' to run, 'cancel' to discard");
+    }
+
+    /// Add one line to the open block
+    pub fn push_line(&mut self, line: &str) {
+        if let Some(block) = self.block.as_mut() {
+            block.push(line.to_string());
+        }
+    }
+
+    /// Close the open block and run it
+    pub fn end(&mut self) {
+        match self.block.take() {
+            Some(lines) => self.run_source(&lines.join("\n")),
+            None => {
+                println!("ERROR: No block is open");
+                println!("SUGGESTION: Type 'begin' to start a multi-line block");
+            }
+        }
+    }
+
+    /// Discard the open block without running it
+    pub fn cancel(&mut self) {
+        match self.block.take() {
+            Some(lines) => println!("[BLOCK] Discarded {} line(s)", lines.len()),
+            None => println!("[BLOCK] No block is open"),
+        }
+    }
+
+    /// Parse and execute OASM source, printing each instruction's result.
+    /// Execution stops at the first failing instruction; everything before
+    /// it stays applied to the session context.
+    pub fn run_source(&mut self, source: &str) {
+        let instructions = match self.parser.parse_file(source) {
+            Ok(instructions) => instructions,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                println!("SUGGESTION: Fix the syntax on line {} and run it again", e.line());
+                return;
+            }
+        };
+
+        self.run_instructions(&instructions, |line| format!("line {}", line));
+    }
+
+    /// Run a script file with its `#include`s inlined, within the expansion
+    /// budget configured in the working directory's oasm.config.yaml.
+    /// Errors name the file and line they come from.
+    pub fn run_file(&mut self, path: &Path) {
+        let budget = match ExpansionBudget::from_project(&self.ctx.working_directory) {
+            Ok(budget) => budget,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                println!("SUGGESTION: Fix the expansion section of oasm.config.yaml");
+                return;
+            }
+        };
+        let script = match load_script(&self.ctx.working_directory.join(path), budget) {
+            Ok(script) => script,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                println!("SUGGESTION: Fix the script or its #include directives and load it again");
+                return;
+            }
+        };
+        self.run_instructions(&script.instructions, |line| match script.source_map.locate(line) {
+            Some(origin) => format!("{}:{}", origin.file.display(), origin.line),
+            None => format!("line {}", line),
+        });
+    }
+
+    /// Execute instructions in order, naming each line through `locate`
+    fn run_instructions(&mut self, instructions: &[Instruction], locate: impl Fn(usize) -> String) {
+        if instructions.is_empty() {
+            println!("[OASM] Nothing to run");
+            return;
+        }
+
+        for instruction in instructions {
+            match self.executor.execute(instruction, &mut self.ctx) {
+                Ok(result) => {
+                    match &result.outcome {
+                        ExecutionOutcome::Success => println!("  {}: {} -> OK", instruction.line_number, instruction.mnemonic),
+                        ExecutionOutcome::PartialSuccess { completed, total } => println!(
+                            "  {}: {} -> PARTIAL ({}/{})",
+                            instruction.line_number, instruction.mnemonic, completed, total
+                        ),
+                        ExecutionOutcome::Failed { reason } => {
+                            println!("ERROR: {}: {} failed: {}", locate(instruction.line_number), instruction.mnemonic, reason);
+                            println!("SUGGESTION: Check the operands of '{}' and run it again", instruction.mnemonic);
+                            return;
+                        }
+                    }
+                    match &result.output {
+                        Some(Value::Array(symbols)) if instruction.mnemonic.eq_ignore_ascii_case("INSPECT") => {
+                            print_symbols(symbols)
+                        }
+                        Some(output) => println!("      = {:?}", output),
+                        None => {}
+                    }
+                }
+                Err(e) => {
+                    println!("ERROR: {}: {} failed: {}", locate(instruction.line_number), instruction.mnemonic, describe_executor_error(&e));
+                    println!("SUGGESTION: {}", suggestion_for(&e));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Summary of the session context for `status`
+    pub fn summary(&self) -> String {
+        let variables: usize = self.ctx.scope_stack.iter().map(|s| s.variables.len()).sum();
+        format!("{} variable(s), {} object(s)", variables, self.ctx.objects.len())
+    }
+}
+
+/// One row per symbol from INSPECT: name, kind, data type and timestamps
+fn print_symbols(symbols: &[Value]) {
+    if symbols.is_empty() {
+        println!("      (no matching symbols)");
+        return;
+    }
+    for symbol in symbols {
+        let Value::Struct { fields, .. } = symbol else {
+            continue;
+        };
+        let field = |name: &str| match fields.get(name) {
+            Some(Value::String(text)) => text.clone(),
+            Some(other) => format!("{:?}", other),
+            None => "-".to_string(),
+        };
+        println!(
+            "      {:<20} {:<9} {:<24} created {}  modified {}",
+            field("name"),
+            field("symbol_type"),
+            field("data_type"),
+            field("created_at"),
+            field("last_modified")
+        );
+    }
+}
+
+fn describe_executor_error(error: &ExecutorError) -> String {
+    match error {
+        ExecutorError::ContextError(message) | ExecutorError::RuntimeError(message) => message.clone(),
+        ExecutorError::InvalidInstruction { instruction, reason } => format!("{}: {}", instruction, reason),
+        ExecutorError::TypeError { variable, error } => format!("'{}': {}", variable, error),
+    }
+}
+
+/// Recovery hint for an executor error (executive function support)
+fn suggestion_for(error: &ExecutorError) -> &'static str {
+    match error {
+        ExecutorError::ContextError(_) => "Declare the variable or CREATE the object before using it",
+        ExecutorError::InvalidInstruction { .. } => "Check the instruction name and operand count",
+        ExecutorError::TypeError { .. } => "Assign a value matching the variable's declared type",
+        ExecutorError::RuntimeError(_) => "Review the instruction's inputs; earlier lines stay applied",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oasm_core::context::ContextManager;
+    use oasm_core::parser::ParseError;
+
+    #[test]
+    fn test_session_keeps_state_between_commands() {
+        let mut repl = Repl::new();
+        repl.run_source("CREATE gear");
+        repl.run_source("SET teeth = 20");
+        assert_eq!(repl.summary(), "1 variable(s), 1 object(s)");
+
+        // Everything before a failing line stays applied, nothing after it runs
+        repl.run_source("SET module = 2.5\nASSERT teeth > 30\nSET pitch = 3");
+        assert!(repl.ctx.get_variable("module").is_ok());
+        assert!(repl.ctx.get_variable("pitch").is_err());
+
+        // A syntax error runs nothing
+        repl.run_source("SET ratio = 2\nSET name = \"unterminated");
+        assert!(repl.ctx.get_variable("ratio").is_err());
+    }
+
+    #[test]
+    fn test_run_file_inlines_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.oasm"), "CREATE gear\n#include \"teeth.oasm\"\n").unwrap();
+        std::fs::write(dir.path().join("teeth.oasm"), "SET teeth = 20\n").unwrap();
+
+        let mut repl = Repl::new();
+        repl.ctx.working_directory = dir.path().to_path_buf();
+        repl.run_file(Path::new("main.oasm"));
+        assert_eq!(repl.summary(), "1 variable(s), 1 object(s)");
+
+        // An over-deep include chain runs nothing
+        std::fs::write(dir.path().join("oasm.config.yaml"), "expansion:\n  max_depth: 1\n").unwrap();
+        std::fs::write(dir.path().join("teeth.oasm"), "SET module = 2\n").unwrap();
+        repl.run_file(Path::new("main.oasm"));
+        assert!(repl.ctx.get_variable("module").is_err());
+    }
+
+    #[test]
+    fn test_block_runs_on_end_and_cancel_discards() {
+        let mut repl = Repl::new();
+        repl.begin();
+        repl.push_line("SET teeth = 20");
+        repl.push_line("SET module = 2.5");
+        assert!(repl.in_block());
+        assert_eq!(repl.block_len(), 2);
+        assert!(repl.ctx.get_variable("teeth").is_err());
+
+        repl.end();
+        assert!(!repl.in_block());
+        assert!(repl.ctx.get_variable("module").is_ok());
+
+        repl.begin();
+        repl.push_line("SET pitch = 3");
+        repl.cancel();
+        assert!(!repl.in_block());
+        assert!(repl.ctx.get_variable("pitch").is_err());
+
+        // Lines outside a block are ignored
+        repl.push_line("SET ratio = 2");
+        assert_eq!(repl.block_len(), 0);
+    }
+
+    #[test]
+    fn test_errors_are_described_with_suggestions() {
+        assert_eq!(ParseError::UnterminatedString { line: 3 }.to_string(), "line 3: unterminated string");
+
+        let error = ExecutorError::InvalidInstruction { instruction: "EXTRUDE".to_string(), reason: "missing depth".to_string() };
+        assert_eq!(describe_executor_error(&error), "EXTRUDE: missing depth");
+        assert_eq!(suggestion_for(&error), "Check the instruction name and operand count");
+    }
+}
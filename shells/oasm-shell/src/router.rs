@@ -13,7 +13,7 @@ pub fn route(cmd: &str) {
     let args = &parts[1..];
 
     match command {
-        "run" | "exec" => {
+        "exec" => {
             if !security::check_capability("process_control") {
                 println!("ERROR: Process execution requires 'process_control' capability");
                 println!("SUGGESTION: Enable capability with: enable process_control");
@@ -21,7 +21,7 @@ pub fn route(cmd: &str) {
             }
             if args.is_empty() {
                 println!("ERROR: Missing program name");
-                println!("USAGE: run <program> [args...]");
+                println!("USAGE: exec <program> [args...]");
                 return;
             }
             execute_program(args);
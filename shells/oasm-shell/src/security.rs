@@ -1,16 +1,27 @@
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::sync::Mutex;
 
-/// Global capability registry (thread-safe)
-static CAPABILITIES: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+/// How an active capability came to be enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilitySource {
+    /// Part of the initial set from `init_capabilities`
+    DefaultEnabled,
+    /// Enabled during the session with `enable_capability`
+    SessionGranted,
+}
+
+/// Global capability registry (thread-safe), active capabilities by name
+static CAPABILITIES: Mutex<Option<BTreeMap<String, CapabilitySource>>> = Mutex::new(None);
 
 /// Initialize the capability system with safe defaults
 pub fn init_capabilities() {
     let mut caps = CAPABILITIES.lock().unwrap();
-    let mut set = HashSet::new();
+    let mut set = BTreeMap::new();
 
     // Start with minimal capabilities (principle of least privilege)
-    set.insert("file_access".to_string());  // Read-only by default
+    set.insert("file_access".to_string(), CapabilitySource::DefaultEnabled);  // Read-only by default
 
     *caps = Some(set);
     println!("[SECURITY] Capabilities initialized (minimal set)");
@@ -20,7 +31,7 @@ pub fn init_capabilities() {
 pub fn check_capability(cap: &str) -> bool {
     let caps = CAPABILITIES.lock().unwrap();
     if let Some(ref set) = *caps {
-        set.contains(cap)
+        set.contains_key(cap)
     } else {
         false
     }
@@ -30,7 +41,8 @@ pub fn check_capability(cap: &str) -> bool {
 pub fn enable_capability(cap: &str) {
     let mut caps = CAPABILITIES.lock().unwrap();
     if let Some(ref mut set) = *caps {
-        if set.insert(cap.to_string()) {
+        if !set.contains_key(cap) {
+            set.insert(cap.to_string(), CapabilitySource::SessionGranted);
             println!("[SECURITY] Enabled capability: {}", cap);
             println!("[WARNING] This grants elevated permissions");
         } else {
@@ -43,7 +55,7 @@ pub fn enable_capability(cap: &str) {
 pub fn disable_capability(cap: &str) {
     let mut caps = CAPABILITIES.lock().unwrap();
     if let Some(ref mut set) = *caps {
-        if set.remove(cap) {
+        if set.remove(cap).is_some() {
             println!("[SECURITY] Disabled capability: {}", cap);
         } else {
             println!("[INFO] Capability '{}' was not enabled", cap);
@@ -66,10 +78,51 @@ pub fn list_capabilities() {
     let caps = CAPABILITIES.lock().unwrap();
     if let Some(ref set) = *caps {
         println!("\nActive Capabilities:");
-        for cap in set.iter() {
+        for cap in set.keys() {
             println!("  - {}", cap);
         }
     } else {
         println!("Capability system not initialized");
     }
 }
+
+/// Snapshot of the active capabilities and where each came from, for auditing
+pub fn export_capabilities() -> serde_json::Value {
+    let caps = CAPABILITIES.lock().unwrap();
+    let capabilities: Vec<serde_json::Value> = caps
+        .iter()
+        .flatten()
+        .map(|(name, source)| serde_json::json!({ "name": name, "source": source }))
+        .collect();
+
+    serde_json::json!({
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "initialized": caps.is_some(),
+        "capabilities": capabilities,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_reflects_sources() {
+        init_capabilities();
+        enable_capability("exec");
+
+        let export = export_capabilities();
+        assert_eq!(export["initialized"], true);
+        assert_eq!(
+            export["capabilities"],
+            serde_json::json!([
+                { "name": "exec", "source": "session_granted" },
+                { "name": "file_access", "source": "default_enabled" },
+            ])
+        );
+
+        // Re-enabling a default keeps its source
+        enable_capability("file_access");
+        assert_eq!(export_capabilities()["capabilities"][1]["source"], "default_enabled");
+    }
+}
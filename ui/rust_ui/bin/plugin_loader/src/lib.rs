@@ -1,6 +1,26 @@
 use libloading::{Library, Symbol};
+use std::ffi::{c_char, CStr};
+use std::sync::Mutex;
 use tracing::{info, error};
 
+/// ABI version plugins must report from `plugin_abi_version`; bump it on any
+/// change to the symbols or calling conventions the loader relies on
+pub const OASM_PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type EntryFn = unsafe extern "C" fn();
+
+/// Libraries of started plugins. They stay loaded for the life of the
+/// process: a plugin may have left threads, callbacks or statics behind
+/// that point into its code.
+static LOADED: Mutex<Vec<Library>> = Mutex::new(Vec::new());
+
+/// Number of plugins started by `load_plug_fn`
+pub fn loaded_plugin_count() -> usize {
+    LOADED.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
 #[no_mangle]
 /// # Safety
 /// Caller must ensure inputs are valid and safe to use.
@@ -8,18 +28,86 @@ pub unsafe extern "C" fn load_plug_fn(path_ptr: *const u8, len: usize) -> bool {
     let slice = std::slice::from_raw_parts(path_ptr, len);
     let path = String::from_utf8_lossy(slice).to_string();
 
-    match Library::new(&path) {
-        Ok(lib) => {
-            info!("load_plug_fn: loaded {}", path);
-            match lib.get::<Symbol<unsafe extern "C" fn()>>(b"plugin_entry") {
-                Ok(_) => info!("plugin_entry symbol present"),
-                Err(_) => info!("plugin_entry symbol not found"),
-            }
-            true
-        }
+    let lib = match Library::new(&path) {
+        Ok(lib) => lib,
         Err(e) => {
             error!("load_plug_fn: failed to load {}: {}", path, e);
-            false
+            return false;
+        }
+    };
+
+    // Optional; a NUL-terminated name reads better in logs than the path
+    let name = match lib.get::<NameFn>(b"plugin_name") {
+        Ok(plugin_name) => {
+            let ptr = plugin_name();
+            if ptr.is_null() { path.clone() } else { CStr::from_ptr(ptr).to_string_lossy().into_owned() }
+        }
+        Err(_) => path.clone(),
+    };
+    info!("load_plug_fn: loaded {}", name);
+
+    let found = match lib.get::<Symbol<AbiVersionFn>>(b"plugin_abi_version") {
+        Ok(abi_version) => abi_version(),
+        Err(_) => {
+            error!("load_plug_fn: {} has no plugin_abi_version symbol; refusing to start it", name);
+            return false;
+        }
+    };
+    if found != OASM_PLUGIN_ABI_VERSION {
+        error!(
+            "load_plug_fn: {} has plugin ABI version {}, expected {}; refusing to start it",
+            name, found, OASM_PLUGIN_ABI_VERSION
+        );
+        return false;
+    }
+
+    match lib.get::<Symbol<EntryFn>>(b"plugin_entry") {
+        Ok(plugin_entry) => {
+            info!("plugin_entry symbol present");
+            plugin_entry();
         }
+        Err(_) => info!("plugin_entry symbol not found"),
+    }
+    LOADED.lock().unwrap_or_else(|e| e.into_inner()).push(lib);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// Compile `tests/fixtures/abi_plugin.rs` as a cdylib reporting `abi_version`
+    fn build_fixture(abi_version: u32) -> PathBuf {
+        let out_dir = std::env::temp_dir().join(format!("oasm_plugin_fixture_{}_{}", std::process::id(), abi_version));
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/abi_plugin.rs");
+        let status = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+            .env("FIXTURE_ABI_VERSION", abi_version.to_string())
+            .args(["--crate-type", "cdylib", "--crate-name", "abi_plugin", "--edition", "2021", "--out-dir"])
+            .arg(&out_dir)
+            .arg(&source)
+            .status()
+            .expect("rustc should be on PATH");
+        assert!(status.success(), "failed to build {}", source.display());
+        out_dir.join(format!("{}abi_plugin{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX))
+    }
+
+    fn load(path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        unsafe { load_plug_fn(path.as_ptr(), path.len()) }
+    }
+
+    #[test]
+    fn test_rejects_mismatched_abi_version() {
+        let mismatched = build_fixture(OASM_PLUGIN_ABI_VERSION + 1);
+        assert!(!load(&mismatched));
+        assert_eq!(loaded_plugin_count(), 0);
+
+        let matching = build_fixture(OASM_PLUGIN_ABI_VERSION);
+        assert!(load(&matching));
+        // Started plugins are never unloaded
+        assert_eq!(loaded_plugin_count(), 1);
     }
 }
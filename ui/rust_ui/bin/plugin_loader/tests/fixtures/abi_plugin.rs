@@ -0,0 +1,17 @@
+//! Plugin fixture for the loader's ABI check. The reported ABI version is
+//! baked in from FIXTURE_ABI_VERSION when the fixture is compiled.
+
+use std::ffi::c_char;
+
+#[no_mangle]
+pub extern "C" fn plugin_abi_version() -> u32 {
+    option_env!("FIXTURE_ABI_VERSION").and_then(|v| v.parse().ok()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn plugin_name() -> *const c_char {
+    c"abi fixture".as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn plugin_entry() {}
@@ -1,14 +1,251 @@
-use regex::Regex;
+use oasm_core::regex_cache::RegexCache;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Component, Path};
 use tracing::info;
 
+/// Characters allowed in an unquoted local part besides letters and digits
+const LOCAL_SPECIALS: &str = "!#$%&'*+/=?^_`{|}~-.";
+const URL_SCHEME_PATTERN: &str = r"^[A-Za-z][A-Za-z0-9+.\-]*$";
+
+const MAX_EMAIL_LEN: usize = 254;
+const MAX_LOCAL_LEN: usize = 64;
+const MAX_DOMAIN_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+const MAX_URL_LEN: usize = 2048;
+const MAX_PATH_LEN: usize = 4096;
+const MAX_PATH_COMPONENT_LEN: usize = 255;
+
+/// Borrow the caller's bytes as UTF-8; null pointers and invalid UTF-8 are rejected
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    std::str::from_utf8(std::slice::from_raw_parts(ptr, len)).ok()
+}
+
+/// `example.co`, `localhost`: dot-separated labels of letters, digits and
+/// inner hyphens, each at most 63 bytes
+fn is_valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= MAX_DOMAIN_LEN
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= MAX_LABEL_LEN
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Unquoted addresses only: `local@domain` where the domain has at least two
+/// labels and a non-numeric top-level label
+pub fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+
+    let local_ok = !local.is_empty()
+        && local.len() <= MAX_LOCAL_LEN
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local.chars().all(|c| c.is_ascii_alphanumeric() || LOCAL_SPECIALS.contains(c));
+
+    let domain_ok = is_valid_hostname(domain)
+        && domain
+            .rsplit_once('.')
+            .is_some_and(|(_, tld)| tld.len() >= 2 && !tld.chars().all(|c| c.is_ascii_digit()));
+
+    email.len() <= MAX_EMAIL_LEN && local_ok && domain_ok
+}
+
+/// `scheme://[user@]host[:port][/path][?query][#fragment]` where host is a
+/// hostname, an IPv4 address or a bracketed IPv6 address
+pub fn is_valid_url(url: &str) -> bool {
+    if url.len() > MAX_URL_LEN || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return false;
+    };
+    if !RegexCache::global().is_match(URL_SCHEME_PATTERN, scheme).unwrap_or(false) {
+        return false;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+
+    let (host, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+        let Some((v6, after)) = bracketed.split_once(']') else {
+            return false;
+        };
+        if v6.parse::<Ipv6Addr>().is_err() {
+            return false;
+        }
+        match after {
+            "" => (v6, None),
+            _ => match after.strip_prefix(':') {
+                Some(port) => (v6, Some(port)),
+                None => return false,
+            },
+        }
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        }
+    };
+
+    let port_ok = match port {
+        None => true,
+        Some(port) => port.parse::<u16>().is_ok_and(|p| p != 0),
+    };
+    let host_ok = host.contains(':') // IPv6, checked above
+        || host.parse::<Ipv4Addr>().is_ok()
+        || (is_valid_hostname(host) && !host.chars().all(|c| c.is_ascii_digit() || c == '.'));
+
+    port_ok && host_ok
+}
+
+/// Non-empty, no NUL bytes, at most 4096 bytes with components of at most 255
+pub fn is_valid_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.len() <= MAX_PATH_LEN
+        && !path.contains('\0')
+        && Path::new(path).components().all(|component| match component {
+            Component::Normal(name) => name.len() <= MAX_PATH_COMPONENT_LEN,
+            _ => true,
+        })
+}
+
 #[no_mangle]
 /// # Safety
 /// Caller must ensure inputs are valid and safe to use.
 pub unsafe extern "C" fn val_email_fn(ptr: *const u8, len: usize) -> bool {
-    let slice = std::slice::from_raw_parts(ptr, len);
-    let email = String::from_utf8_lossy(slice);
-    let re = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
-    let ok = re.is_match(&email);
+    let Some(email) = input(ptr, len) else {
+        return false;
+    };
+    let ok = is_valid_email(email);
     info!("val_email_fn: {} => {}", email, ok);
     ok
 }
+
+#[no_mangle]
+/// # Safety
+/// Caller must ensure inputs are valid and safe to use.
+pub unsafe extern "C" fn val_url_fn(ptr: *const u8, len: usize) -> bool {
+    let Some(url) = input(ptr, len) else {
+        return false;
+    };
+    let ok = is_valid_url(url);
+    info!("val_url_fn: {} => {}", url, ok);
+    ok
+}
+
+#[no_mangle]
+/// # Safety
+/// Caller must ensure inputs are valid and safe to use.
+pub unsafe extern "C" fn val_path_fn(ptr: *const u8, len: usize) -> bool {
+    let Some(path) = input(ptr, len) else {
+        return false;
+    };
+    let ok = is_valid_path(path);
+    info!("val_path_fn: {} => {}", path, ok);
+    ok
+}
+
+#[no_mangle]
+/// Like `val_path_fn`, and the path must also exist
+///
+/// # Safety
+/// Caller must ensure inputs are valid and safe to use.
+pub unsafe extern "C" fn val_existing_path_fn(ptr: *const u8, len: usize) -> bool {
+    let Some(path) = input(ptr, len) else {
+        return false;
+    };
+    let ok = is_valid_path(path) && Path::new(path).exists();
+    info!("val_existing_path_fn: {} => {}", path, ok);
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(f: unsafe extern "C" fn(*const u8, usize) -> bool, text: &str) -> bool {
+        unsafe { f(text.as_ptr(), text.len()) }
+    }
+
+    #[test]
+    fn test_email_edge_cases() {
+        for good in ["user+tag@sub.example.co", "a@x.com", "first.last@example.org", "o'brien@host-name.io"] {
+            assert!(call(val_email_fn, good), "{}", good);
+        }
+
+        let long_label = format!("a@{}.com", "x".repeat(64));
+        let long_local = format!("{}@x.com", "a".repeat(65));
+        for bad in [
+            "a..b@x.com",
+            ".a@x.com",
+            "a.@x.com",
+            "a@b@x.com",
+            "a@x",
+            "a@-x.com",
+            "a@x..com",
+            "a@x.1",
+            "a b@x.com",
+            "@x.com",
+            long_label.as_str(),
+            long_local.as_str(),
+        ] {
+            assert!(!call(val_email_fn, bad), "{}", bad);
+        }
+
+        assert!(!unsafe { val_email_fn(std::ptr::null(), 0) });
+        let invalid_utf8 = [0xff, b'@', b'x', b'.', b'c', b'o'];
+        assert!(!unsafe { val_email_fn(invalid_utf8.as_ptr(), invalid_utf8.len()) });
+    }
+
+    #[test]
+    fn test_url_requires_scheme_and_host() {
+        for good in [
+            "https://example.com",
+            "http://localhost:8080/api?x=1",
+            "ftp://user@files.example.org/pub",
+            "http://127.0.0.1/",
+            "http://[::1]:3000/",
+            "git+ssh://git.example.com/repo.git",
+        ] {
+            assert!(call(val_url_fn, good), "{}", good);
+        }
+        for bad in [
+            "example.com",
+            "https://",
+            "https:///path",
+            "1http://example.com",
+            "http://exa mple.com",
+            "http://example.com:99999",
+            "http://example.com:0",
+            "http://-bad.com",
+            "http://999.1.1.1",
+            "http://[::zz]/",
+            "http://[::1]junk/",
+        ] {
+            assert!(!call(val_url_fn, bad), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_path_limits_and_existence() {
+        assert!(call(val_path_fn, "models/gear.oasm"));
+        assert!(call(val_path_fn, "/tmp/../etc"));
+        assert!(!call(val_path_fn, ""));
+        assert!(!call(val_path_fn, "models/\0gear"));
+        assert!(!call(val_path_fn, &format!("models/{}", "g".repeat(256))));
+        assert!(!call(val_path_fn, &"a/".repeat(2049)));
+
+        let manifest = env!("CARGO_MANIFEST_DIR");
+        assert!(call(val_existing_path_fn, manifest));
+        assert!(!call(val_existing_path_fn, &format!("{}/no-such-file", manifest)));
+    }
+}
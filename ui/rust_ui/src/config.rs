@@ -18,6 +18,7 @@ pub enum Lifecycle {
 pub struct ModuleState {
     pub name: String,
     pub enabled: bool,
+    #[allow(dead_code)] // read by validate_state
     pub validated: bool,
 }
 
@@ -25,8 +26,10 @@ pub struct ModuleState {
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppState {
     pub profile_name: String,
+    #[allow(dead_code)] // part of the config schema, not used at startup yet
     pub version: Option<String>,
     pub environment: Option<String>,
+    #[allow(dead_code)] // written by set_lifecycle
     pub lifecycle: Lifecycle,
     pub modules: Vec<ModuleState>,
 }
@@ -59,6 +62,7 @@ pub fn load_state() -> Result<AppState> {
 }
 
 /// Validate the loaded state against schema rules
+#[allow(dead_code)] // not wired into startup yet
 pub fn validate_state(state: &AppState) -> Result<()> {
     if state.profile_name.trim().is_empty() {
         error!("Profile name is missing in configuration");
@@ -89,6 +93,7 @@ pub fn validate_state(state: &AppState) -> Result<()> {
 }
 
 /// Update lifecycle with logging
+#[allow(dead_code)] // not wired into startup yet
 pub fn set_lifecycle(state: &mut AppState, phase: Lifecycle) {
     state.lifecycle = phase.clone();
     info!(phase = ?phase, "Lifecycle updated");
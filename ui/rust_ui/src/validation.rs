@@ -14,6 +14,7 @@ use crate::config::AppState;
 /// - Proper error handling for all unsafe operations
 /// - Type-safe symbol resolution with explicit signatures
 /// - Controlled test invocation with known-safe dummy data
+#[allow(dead_code)] // validate_dlls is still a stub
 pub fn validate_library(path: &str) -> Result<()> {
     // SAFETY: Library::new loads a DLL from the filesystem.
     // This is inherently unsafe but necessary for dynamic loading.